@@ -1,20 +1,35 @@
 extern crate proc_macro;
 use crate::proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
-use quote::{quote, ToTokens};
-use syn;
+use proc_macro2::Ident;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
 
 /// A struct that contains the name of a struct field and the corresponding type
 #[derive(Debug)]
 struct FieldNameType {
-    field_name: String,
+    field_name: Ident,
     field_type: proc_macro2::TokenStream,
+    /// Marked with `#[ffi_drop(secret)]`, the memory is zeroed before it's freed
+    secret: bool,
+    /// A `*const *const libc::c_char` array of C strings, `field_type` is the type of the strings
+    string_array: bool,
+    /// The struct is marked with `#[ffi_drop(tombstone)]`, the field is set to null once freed
+    tombstone: bool,
+    /// The length and capacity fields given with `#[ffi_drop(vec(...))]`
+    vec: Option<VecFields>,
+    /// Marked with `#[ffi_drop(nested)]`, a single struct from `ffi_toolkit::raw_ptr()`
+    boxed: bool,
+    /// A `*mut` pointer, which has to be marked with `nested` or `vec`
+    mutable: bool,
+    /// The function given with `#[ffi_drop(with = "...")]`, which frees the field instead
+    with: Option<proc_macro2::TokenStream>,
+    /// A `secret` field that isn't a pointer, e.g. an array of key bytes, which is zeroed in
+    /// place
+    wipe: bool,
 }
 
-/// The actual code to free the *const pointers
-impl quote::ToTokens for FieldNameType {
-    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let field_type = &self.field_type;
+impl FieldNameType {
+    fn is_c_str(&self) -> bool {
         // Convert field type to string for easy pattern matching
         let field_type_string = self
             .field_type
@@ -22,86 +37,1552 @@ impl quote::ToTokens for FieldNameType {
             .into_iter()
             .map(|token| token.to_string())
             .collect::<String>();
-        match &field_type_string[..] {
-            // Free string with `free_c_str`
-            "libc::c_char" => {
-                let field_name = Ident::new(&self.field_name, Span::call_site());
-                let gen = quote! {
+        !self.string_array
+            && !self.boxed
+            && !self.wipe
+            && self.with.is_none()
+            && self.vec.is_none()
+            && is_c_char(&field_type_string)
+    }
+
+    /// The name of the field holding the length of the vector, for fields that aren't C strings
+    fn len_field_name(&self) -> Ident {
+        if let Some(vec) = &self.vec {
+            return vec.len.clone();
+        }
+        let field_name = self.field_name.to_string();
+        Ident::new(
+            &format!("{}{}", &field_name[..field_name.len() - 4], "_len"),
+            self.field_name.span(),
+        )
+    }
+
+    /// The name of the field holding the capacity of the vector, the length unless given
+    fn cap_field_name(&self) -> Ident {
+        match self.vec {
+            Some(VecFields {
+                cap: Some(ref cap), ..
+            }) => cap.clone(),
+            _ => self.len_field_name(),
+        }
+    }
+}
+
+/// The fields of a `#[ffi_drop(vec(len = "...", cap = "..."))]` vector
+#[derive(Debug)]
+struct VecFields {
+    len: Ident,
+    cap: Option<Ident>,
+}
+
+/// The address freed pointer fields are set to with the `poison` feature, odd so that it's never
+/// a valid pointer to anything but bytes, the same as `ffi_toolkit::POISON_PTR`
+const POISON_PTR: usize = 0xDEAD_BEEF;
+
+/// The byte freed vectors are overwritten with, the same as `ffi_toolkit::POISON_BYTE`
+const POISON_BYTE: u8 = 0xDE;
+
+/// The actual code to free the *const pointers
+impl quote::ToTokens for FieldNameType {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let field_type = &self.field_type;
+        let field_name = &self.field_name;
+        // The struct's own memory is freed after the `Drop`, so the field is zeroed here, the
+        // assertion keeps it from being a type with a destructor of its own
+        if self.wipe {
+            let gen = quote! {
+                {
+                    fn assert_copy<T: ::core::marker::Copy>(_: &T) {}
+                    assert_copy(&self.#field_name);
+                    zero_memory(
+                        &mut self.#field_name as *mut #field_type as *mut u8,
+                        ::core::mem::size_of::<#field_type>(),
+                    );
+                }
+            };
+            gen.to_tokens(tokens);
+            return;
+        }
+        // The handler owns the field from here on
+        if let Some(with) = &self.with {
+            let gen = quote! {
+                #with(self.#field_name);
+            };
+            gen.to_tokens(tokens);
+            return;
+        }
+        // Dropping the struct frees what it owns in turn
+        if self.boxed {
+            let gen = quote! {
+                ::ffi_toolkit::free_raw_ptr(self.#field_name as *mut #field_type);
+            };
+            gen.to_tokens(tokens);
+        }
+        // Free the strings of an array, then the array itself
+        else if self.string_array && cfg!(feature = "poison") {
+            let field_name_len = self.len_field_name();
+            let field_name_cap = self.cap_field_name();
+            let free = if self.secret {
+                quote! { free_secret_c_str }
+            } else {
+                quote! { free_c_str }
+            };
+            let gen = quote! {
+                if !self.#field_name.is_null() {
+                    let mut c_strs = ::ffi_toolkit::vec_from_raw_parts_checked(
+                        self.#field_name as *mut *const #field_type,
+                        self.#field_name_len,
+                        self.#field_name_cap,
+                    );
+                    for c_str in c_strs.drain(..) {
+                        #free(c_str as *mut #field_type);
+                    }
+                    poison_memory(
+                        c_strs.as_mut_ptr() as *mut u8,
+                        c_strs.capacity() * ::core::mem::size_of::<*const #field_type>(),
+                    );
+                }
+            };
+            gen.to_tokens(tokens);
+        } else if self.string_array {
+            let field_name_len = self.len_field_name();
+            let field_name_cap = self.cap_field_name();
+            let free = if self.secret {
+                quote! { free_secret_c_str }
+            } else {
+                quote! { free_c_str }
+            };
+            let gen = quote! {
+                if !self.#field_name.is_null() {
+                    for c_str in ::ffi_toolkit::vec_from_raw_parts_checked(
+                        self.#field_name as *mut *const #field_type,
+                        self.#field_name_len,
+                        self.#field_name_cap,
+                    ) {
+                        #free(c_str as *mut #field_type);
+                    }
+                }
+            };
+            gen.to_tokens(tokens);
+        }
+        // Free string with `free_c_str`
+        else if self.is_c_str() {
+            // Secrets are zeroed by `free_secret_c_str`, zeroing them here would change the length
+            // `free_c_str` determines for the deallocation
+            let gen = if self.secret {
+                quote! {
+                    free_secret_c_str(self.#field_name as *mut #field_type);
+                }
+            } else {
+                quote! {
                     free_c_str(self.#field_name as *mut #field_type);
-                };
-                gen.to_tokens(tokens);
-            }
-            // Expect all others to be vectors
-            _ => {
-                if !self.field_name.ends_with("_ptr") {
-                    panic!(
-                        "Pointer needs to have field with `_ptr` as suffix. \
-                         This field is named `{}`.",
-                        self.field_name
-                    )
-                }
-                // Field ends with `_ptr` so we can re-construct the corresponding length field
-                let field_name_len = Ident::new(
-                    &format!(
-                        "{}{}",
-                        &self.field_name[..&self.field_name.len() - 4],
-                        "_len"
-                    ),
-                    Span::call_site(),
-                );
-                let field_name_ptr = Ident::new(&self.field_name, Span::call_site());
+                }
+            };
+            gen.to_tokens(tokens);
+        }
+        // Expect all others to be vectors
+        else {
+            // Field ends with `_ptr` so we can re-construct the corresponding length field
+            let field_name_len = self.len_field_name();
+            let field_name_cap = self.cap_field_name();
+            if self.secret {
                 let gen = quote! {
-                    drop(Vec::from_raw_parts(
-                            self.#field_name_ptr as *mut #field_type,
-                            self.#field_name_len,
-                            self.#field_name_len,
-                    ));
+                    if !self.#field_name.is_null() {
+                        zero_memory(
+                            self.#field_name as *mut u8,
+                            self.#field_name_len * ::core::mem::size_of::<#field_type>(),
+                        );
+                    }
                 };
                 gen.to_tokens(tokens);
             }
+            // Null for a default response, which has no vector to free
+            // Secrets stay zeroed
+            let gen = if cfg!(feature = "poison") && !self.secret {
+                // The elements are dropped before their memory is poisoned
+                quote! {
+                    if !self.#field_name.is_null() {
+                        let mut elements = ::ffi_toolkit::vec_from_raw_parts_checked(
+                                self.#field_name as *mut #field_type,
+                                self.#field_name_len,
+                                self.#field_name_cap,
+                        );
+                        elements.clear();
+                        poison_memory(
+                            elements.as_mut_ptr() as *mut u8,
+                            elements.capacity() * ::core::mem::size_of::<#field_type>(),
+                        );
+                    }
+                }
+            } else {
+                quote! {
+                    if !self.#field_name.is_null() {
+                        drop(::ffi_toolkit::vec_from_raw_parts_checked(
+                                self.#field_name as *mut #field_type,
+                                self.#field_name_len,
+                                self.#field_name_cap,
+                        ));
+                    }
+                }
+            };
+            gen.to_tokens(tokens);
+        }
+        // Freeing the field again is a no-op
+        if self.tombstone {
+            let null = if self.mutable {
+                quote! { ::core::ptr::null_mut() }
+            } else {
+                quote! { ::core::ptr::null() }
+            };
+            let gen = quote! {
+                self.#field_name = #null;
+            };
+            gen.to_tokens(tokens);
+        }
+        // A stale copy of the struct crashes instead of reading freed memory, and freeing the
+        // field again crashes instead of corrupting the heap
+        else if cfg!(feature = "poison") {
+            let gen = quote! {
+                self.#field_name = ::core::ptr::without_provenance_mut::<u8>(#POISON_PTR) as _;
+            };
+            gen.to_tokens(tokens);
         }
     }
 }
 
-#[proc_macro_derive(DropStructMacro)]
-pub fn drop_struct_macro_derive(input: TokenStream) -> TokenStream {
-    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+/// The options given with `#[ffi_drop(...)]` on a field
+#[derive(Default)]
+struct FieldOptions {
+    /// `secret`, the memory is zeroed before it's freed
+    secret: bool,
+    /// `vec(len = "...", cap = "...")`, the field is a vector with these length and capacity
+    /// fields
+    vec: Option<VecFields>,
+    /// `nested`, the field points at a single struct from `ffi_toolkit::raw_ptr()`, or with
+    /// `vec` at an array of structs, which is also freed if the pointer is `*mut`
+    nested: bool,
+    /// `skip`, the field points at memory the struct doesn't own and isn't freed
+    skip: bool,
+    /// `with = "path::to::fn"`, the field is passed to the function to free it
+    with: Option<syn::Path>,
+    /// `error_value = "..."`, the field's value in the error response of `FFIResponse` with
+    /// `#[ffi_drop(error_response)]`
+    error_value: Option<syn::Expr>,
+    /// `offset = 16`, the field's offset asserted with the struct's `layout`
+    offset: Option<syn::Expr>,
+}
 
-    // A list of fields that should get dropped
-    let mut to_be_dropped = Vec::new();
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ffi_drop"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("secret") {
+                options.secret = true;
+                Ok(())
+            } else if meta.path.is_ident("vec") {
+                options.vec = Some(vec_fields(field, &meta)?);
+                Ok(())
+            } else if meta.path.is_ident("nested") {
+                options.nested = true;
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                let path: syn::LitStr = meta.value()?.parse()?;
+                options.with = Some(path.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("arena") {
+                // The arena frees everything in it at once
+                options.with = Some(syn::parse_quote!(::ffi_toolkit::free_arena));
+                Ok(())
+            } else if meta.path.is_ident("error_value") {
+                let value: syn::LitStr = meta.value()?.parse()?;
+                options.error_value = Some(value.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("offset") {
+                options.offset = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unknown `ffi_drop` option, expected `secret`, `vec`, `nested`, `skip`, `with`, \
+                     `arena`, `error_value` or `offset`",
+                ))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+/// Parses `vec(ptr = "...", len = "...", cap = "...")`, `ptr` is optional and `cap` defaults to
+/// `len`
+fn vec_fields(field: &syn::Field, meta: &syn::meta::ParseNestedMeta) -> syn::Result<VecFields> {
+    let mut len = None;
+    let mut cap = None;
+    meta.parse_nested_meta(|vec_meta| {
+        let name: syn::LitStr = vec_meta.value()?.parse()?;
+        let name = name.parse::<Ident>()?;
+        if vec_meta.path.is_ident("ptr") {
+            // Only for readability, the pointer is the field the attribute is on
+            if field.ident.as_ref() != Some(&name) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "`ptr` has to be the field the attribute is on",
+                ));
+            }
+        } else if vec_meta.path.is_ident("len") {
+            len = Some(name);
+        } else if vec_meta.path.is_ident("cap") {
+            cap = Some(name);
+        } else {
+            return Err(vec_meta.error("unknown `vec` field, expected `ptr`, `len` or `cap`"));
+        }
+        Ok(())
+    })?;
+    let len = len.ok_or_else(|| meta.error("`vec` needs the `len` field"))?;
+    Ok(VecFields { len, cap })
+}
+
+/// The options given with `#[ffi_drop(...)]` on the struct
+#[derive(Default)]
+struct DropOptions {
+    /// The name of the exported destructor requested with `destroy`
+    ///
+    /// Without a value it's `destroy_` followed by the struct name in snake case.
+    destroy: Option<Ident>,
+    /// Requested with `tombstone`, destroying the struct twice is a no-op
+    tombstone: bool,
+    /// The `ffi_toolkit::TaggedResponse::TYPE_TAG` given with `tag = 7`
+    tag: Option<syn::LitInt>,
+    /// Requested with `error_response`, `FFIResponse` generates `new_error_response()` instead
+    /// of `Default`
+    error_response: bool,
+    /// The size and alignment given with `layout(size = 32, align = 8)`
+    layout: Option<(syn::Expr, syn::Expr)>,
+}
+
+fn drop_options(ast: &syn::DeriveInput) -> syn::Result<DropOptions> {
+    let mut options = DropOptions::default();
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ffi_drop"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("destroy") {
+                options.destroy = Some(if meta.input.peek(syn::Token![=]) {
+                    let name: syn::LitStr = meta.value()?.parse()?;
+                    name.parse::<Ident>()?
+                } else {
+                    default_destroy_fn_name(ast)
+                });
+                Ok(())
+            } else if meta.path.is_ident("tombstone") {
+                options.tombstone = true;
+                Ok(())
+            } else if meta.path.is_ident("tag") {
+                options.tag = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("error_response") {
+                options.error_response = true;
+                Ok(())
+            } else if meta.path.is_ident("layout") {
+                let mut size = None;
+                let mut align = None;
+                meta.parse_nested_meta(|layout_meta| {
+                    if layout_meta.path.is_ident("size") {
+                        size = Some(layout_meta.value()?.parse()?);
+                    } else if layout_meta.path.is_ident("align") {
+                        align = Some(layout_meta.value()?.parse()?);
+                    } else {
+                        return Err(
+                            layout_meta.error("unknown `layout` field, expected `size` or `align`")
+                        );
+                    }
+                    Ok(())
+                })?;
+                match (size, align) {
+                    (Some(size), Some(align)) => options.layout = Some((size, align)),
+                    _ => return Err(meta.error("`layout` needs both `size` and `align`")),
+                }
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unknown `ffi_drop` option, expected `destroy`, `tombstone`, `tag`, \
+                     `error_response` or `layout`",
+                ))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+/// Whether the type is `c_char`, of libc (`libc::c_char`) or any other path, e.g.
+/// `ffi_toolkit::c_types::c_char` for targets without libc
+fn is_c_char(type_string: &str) -> bool {
+    type_string == "c_char" || type_string.ends_with("::c_char")
+}
+
+fn default_destroy_fn_name(ast: &syn::DeriveInput) -> Ident {
+    Ident::new(
+        &format!("destroy_{}", snake_case(&ast.ident.to_string())),
+        ast.ident.span(),
+    )
+}
+
+/// `SealResponse` becomes `seal_response`, `FFISectorInfo` becomes `ffi_sector_info`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// The code zeroing `len` bytes at `ptr`, the writes are volatile so they aren't optimized away
+///
+/// Miri doesn't optimize them away either, but interprets a volatile write per byte very slowly,
+/// so under `cfg(miri)` the memory is overwritten with a single `write_bytes()`.
+fn zero_memory_fn() -> proc_macro2::TokenStream {
+    fill_memory_fn(quote! { zero_memory }, 0)
+}
+
+/// The code overwriting `len` bytes at `ptr` with `POISON_BYTE`, see `zero_memory_fn()`
+fn poison_memory_fn() -> proc_macro2::TokenStream {
+    fill_memory_fn(quote! { poison_memory }, POISON_BYTE)
+}
+
+fn fill_memory_fn(name: proc_macro2::TokenStream, byte: u8) -> proc_macro2::TokenStream {
+    quote! {
+        #[cfg(not(miri))]
+        unsafe fn #name(ptr: *mut u8, len: usize) {
+            for offset in 0..len {
+                ::core::ptr::write_volatile(ptr.add(offset), #byte);
+            }
+            ::core::sync::atomic::compiler_fence(::core::sync::atomic::Ordering::SeqCst);
+        }
+        #[cfg(miri)]
+        unsafe fn #name(ptr: *mut u8, len: usize) {
+            ::core::ptr::write_bytes(ptr, #byte, len);
+        }
+    }
+}
+
+/// The name of the type a path type ends with, e.g. `SecretBytes` for
+/// `ffi_toolkit::SecretBytes`
+fn last_segment(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Why a field that wasn't recognized as something to free is an error, if it is a pointer
+fn unsupported_pointer(ty: &syn::Type) -> Option<&'static str> {
+    match ty {
+        syn::Type::Ptr(type_ptr) if type_ptr.mutability.is_some() => Some(
+            "`DropStructMacro` only frees `*mut` pointers it's told about, mark the field with \
+             `#[ffi_drop(skip)]` if the struct doesn't own what it points at, or with \
+             `#[ffi_drop(nested)]`, `#[ffi_drop(vec(...))]` or `#[ffi_drop(with = \"...\")]` if \
+             it does",
+        ),
+        syn::Type::Ptr(_) => Some(
+            "`DropStructMacro` doesn't know how to free a pointer of this type, mark the field \
+             with `#[ffi_drop(skip)]` if the struct doesn't own what it points at, or with \
+             `#[ffi_drop(with = \"...\")]` to free it with a function",
+        ),
+        syn::Type::Path(type_path)
+            if type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "NonNull") =>
+        {
+            Some(
+                "`DropStructMacro` doesn't free `NonNull` pointers, mark the field with \
+                 `#[ffi_drop(skip)]` if the struct doesn't own what it points at, or with \
+             `#[ffi_drop(with = \"...\")]` to free it with a function",
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Collects the fields that should get dropped, or an error pointing at the offending field
+fn fields_to_drop(ast: &syn::DeriveInput) -> syn::Result<Vec<FieldNameType>> {
+    let data_struct = match ast.data {
+        syn::Data::Struct(ref data_struct) => data_struct,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`DropStructMacro` works only with structs",
+            ))
+        }
+    };
+    let fields_named = match data_struct.fields {
+        syn::Fields::Named(ref fields_named) => fields_named,
+        // Without named fields there are no pointers we know how to free
+        _ => return Ok(Vec::new()),
+    };
 
-    // Only take *const pointers into account (also not *mut)
-    match ast.data {
-        syn::Data::Struct(ref data_struct) => {
-            if let syn::Fields::Named(ref fields_named) = data_struct.fields {
-                for field in fields_named.named.iter() {
-                    if let syn::Type::Ptr(ref type_ptr) = field.ty {
-                        if type_ptr.const_token.is_some() {
-                            if let syn::Type::Path(ref type_path) = *type_ptr.elem {
-                                let field_name = field.ident.clone().unwrap().to_string();
-                                let field_type = type_path.path.clone().into_token_stream();
+    let mut to_be_dropped = Vec::new();
+    // *const pointers are freed by default, *mut pointers only if they are marked
+    for field in fields_named.named.iter() {
+        let FieldOptions {
+            secret,
+            mut vec,
+            nested,
+            skip,
+            with,
+            // Only used by `FFIResponse`
+            error_value: _,
+            // Only used by the layout assertion
+            offset: _,
+        } = field_options(field)?;
+        if let Some(with) = with {
+            if skip || nested || secret || vec.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`#[ffi_drop(with = \"...\")]` and `#[ffi_drop(arena)]` can't be combined with \
+                     other `ffi_drop` options",
+                ));
+            }
+            to_be_dropped.push(FieldNameType {
+                field_name: field.ident.clone().unwrap(),
+                field_type: field.ty.to_token_stream(),
+                secret: false,
+                string_array: false,
+                tombstone: false,
+                vec: None,
+                boxed: false,
+                mutable: false,
+                with: Some(with.into_token_stream()),
+                wipe: false,
+            });
+            continue;
+        }
+        if skip {
+            if nested || vec.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`#[ffi_drop(skip)]` can't be combined with `vec(...)` or `nested`",
+                ));
+            }
+            // `secret` still redacts it in `FfiDebug`
+            continue;
+        }
+        let mut dropped = false;
+        if nested && secret {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "`#[ffi_drop(secret)]` is not supported on `nested` fields",
+            ));
+        }
+        if let syn::Type::Ptr(ref type_ptr) = field.ty {
+            let mutable = type_ptr.mutability.is_some();
+            if !mutable || nested || vec.is_some() {
+                match *type_ptr.elem {
+                    syn::Type::Path(ref type_path) => {
+                        to_be_dropped.push(FieldNameType {
+                            field_name: field.ident.clone().unwrap(),
+                            field_type: type_path.path.clone().into_token_stream(),
+                            secret,
+                            string_array: false,
+                            tombstone: false,
+                            boxed: nested && vec.is_none(),
+                            vec: vec.take(),
+                            mutable,
+                            with: None,
+                            wipe: false,
+                        });
+                        dropped = true;
+                    }
+                    syn::Type::Ptr(ref inner) if inner.const_token.is_some() => {
+                        if let syn::Type::Path(ref type_path) = *inner.elem {
+                            let field_type = type_path.path.clone().into_token_stream();
+                            if is_c_char(&field_type.to_string().replace(' ', "")) {
                                 to_be_dropped.push(FieldNameType {
-                                    field_name,
+                                    field_name: field.ident.clone().unwrap(),
                                     field_type,
-                                })
+                                    secret,
+                                    string_array: true,
+                                    tombstone: false,
+                                    vec: vec.take(),
+                                    boxed: false,
+                                    mutable,
+                                    with: None,
+                                    wipe: false,
+                                });
+                                dropped = true;
                             }
                         }
                     }
+                    _ => {}
                 }
             }
         }
-        _ => panic!("Works only with structs"),
+        // Ignoring a pointer would leak what it points at
+        if !dropped {
+            if let Some(message) = unsupported_pointer(&field.ty) {
+                return Err(syn::Error::new_spanned(&field.ty, message));
+            }
+        }
+        if secret && !dropped {
+            match last_segment(&field.ty).as_deref() {
+                // It zeroes its memory on its own
+                Some("SecretBytes") => {}
+                // Zeroing it in place would leave the bytes it owns
+                Some("FfiBytes") => {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "`FfiBytes` isn't zeroed when it's dropped, use \
+                         `ffi_toolkit::SecretBytes` for secret bytes",
+                    ))
+                }
+                _ => to_be_dropped.push(FieldNameType {
+                    field_name: field.ident.clone().unwrap(),
+                    field_type: field.ty.to_token_stream(),
+                    secret,
+                    string_array: false,
+                    tombstone: false,
+                    vec: None,
+                    boxed: false,
+                    mutable: false,
+                    with: None,
+                    wipe: true,
+                }),
+            }
+            continue;
+        }
+        if vec.is_some() || (nested && !dropped) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "`#[ffi_drop(vec(...))]` and `#[ffi_drop(nested)]` are only supported on pointer \
+                 fields",
+            ));
+        }
+    }
+
+    for field in to_be_dropped
+        .iter()
+        .filter(|field| !field.is_c_str() && !field.boxed && !field.wipe && field.with.is_none())
+    {
+        // With explicit length and capacity fields the name doesn't matter
+        if field.vec.is_none() && !field.field_name.to_string().ends_with("_ptr") {
+            return Err(syn::Error::new(
+                field.field_name.span(),
+                format!(
+                    "Pointer needs to have field with `_ptr` as suffix. \
+                     This field is named `{}`.",
+                    field.field_name
+                ),
+            ));
+        }
+        // Freeing with the length as capacity would be wrong
+        if field.vec.is_none() {
+            let field_name = field.field_name.to_string();
+            let cap_field_name = format!("{}_cap", &field_name[..field_name.len() - 4]);
+            if fields_named.named.iter().any(|other| {
+                other
+                    .ident
+                    .as_ref()
+                    .is_some_and(|ident| ident == &cap_field_name)
+            }) {
+                return Err(syn::Error::new(
+                    field.field_name.span(),
+                    format!(
+                        "Pointer field `{}` has a capacity field `{}`, declare it with \
+                         `#[ffi_drop(vec(len = \"{}\", cap = \"{}\"))]`.",
+                        field.field_name,
+                        cap_field_name,
+                        field.len_field_name(),
+                        cap_field_name
+                    ),
+                ));
+            }
+        }
+        let has_field = |name: &Ident| {
+            fields_named
+                .named
+                .iter()
+                .any(|other| other.ident.as_ref() == Some(name))
+        };
+        let len_field_name = field.len_field_name();
+        if !has_field(&len_field_name) {
+            return Err(syn::Error::new(
+                field.field_name.span(),
+                format!(
+                    "Pointer field `{}` needs a field `{}` with the number of elements.",
+                    field.field_name, len_field_name
+                ),
+            ));
+        }
+        let cap_field_name = field.cap_field_name();
+        if !has_field(&cap_field_name) {
+            return Err(syn::Error::new(
+                cap_field_name.span(),
+                format!(
+                    "Pointer field `{}` needs a field `{}` with the capacity.",
+                    field.field_name, cap_field_name
+                ),
+            ));
+        }
+    }
+    Ok(to_be_dropped)
+}
+
+/// Implements `Drop`, freeing all `*const` pointer fields
+///
+/// `*const libc::c_char` fields are freed with `free_c_str()`, which needs to be in scope (any
+/// other `c_char` path works as well, e.g. `ffi_toolkit::c_types::c_char` for targets without
+/// libc). All other pointer fields need to be named `<name>_ptr` and are freed as a `Vec` with
+/// the length in the field `<name>_len` (rebuilt with `ffi_toolkit::vec_from_raw_parts_checked()`,
+/// so create them with `ffi_toolkit::vec_into_raw_parts_exact()`), `*const *const libc::c_char`
+/// fields are such a vector of C strings, which are freed with `free_c_str()` as well. A vector
+/// with other length and capacity fields is marked with
+/// `#[ffi_drop(vec(len = "proofs_count", cap = "proofs_cap"))]` (`ptr` may be given as well,
+/// `cap` defaults to `len`), its field may have any name, also if it is a `*const libc::c_char`
+/// vector of bytes. A pointer to a single nested struct created with
+/// `ffi_toolkit::raw_ptr()` is marked with `#[ffi_drop(nested)]` and freed with
+/// `ffi_toolkit::free_raw_ptr()`, which drops the struct and so frees what it owns in turn.
+/// Arrays of structs are dropped element by element anyway. Fields marked with
+/// `#[ffi_drop(skip)]` point at memory the struct doesn't own, e.g. caller-owned inputs, and
+/// aren't freed at all. Fields marked with `#[ffi_drop(secret)]` are zeroed before they are
+/// freed, C strings with `free_secret_c_str()`, which then needs to be in scope as well.
+/// `secret` fields that aren't pointers, e.g. a `Fil32` of ticket randomness or an array of key
+/// bytes, are zeroed in place, so that they don't linger in the freed struct; they need to be
+/// `Copy`. Secret bytes the struct owns are an `ffi_toolkit::SecretBytes`, which zeroes itself.
+///
+/// Fields with other resources, e.g. file descriptors or handles, are freed by a function of
+/// their own with `#[ffi_drop(with = "close_sector_fd")]`. It's called with the field's value,
+/// as `unsafe { close_sector_fd(self.sector_fd) }`, so the field type needs to be `Copy`.
+///
+/// A `*mut ffi_toolkit::FfiArena` field marked with `#[ffi_drop(arena)]` frees the arena and so
+/// all strings and arrays in it at once, the fields pointing into it are marked with `skip`.
+///
+/// Pointers the macro can't free are a compile error rather than a leak: `*mut` pointers that
+/// aren't marked with `nested`, `vec` or `skip`, pointers to other types than structs and C
+/// strings, `NonNull` fields and default-named vectors with a `<name>_cap` field that isn't
+/// declared with `vec(...)`.
+///
+/// With the `poison` feature the freed vectors (except secrets, which stay zeroed) are
+/// overwritten with `0xDE` bytes and all freed pointer fields are set to the odd address
+/// `0xDEAD_BEEF`, so that use-after-free bugs crash.
+///
+/// A panic while the fields are freed (e.g. of a `with` function) doesn't unwind out of the
+/// `Drop`: it is printed and recorded as the thread's last error, and the remaining fields are
+/// leaked.
+///
+/// With `#[ffi_drop(destroy)]` on the struct, an exported destructor taking a `*mut` pointer to
+/// the struct is generated as well, it frees the boxed struct with `ffi_toolkit::free_raw_ptr()`
+/// and ignores null pointers. A panic in it aborts the process, it never unwinds into the
+/// caller. It's named `destroy_<struct name in snake case>`, a different name
+/// can be given with `#[ffi_drop(destroy = "fil_destroy_seal_response")]`.
+///
+/// With `#[ffi_drop(destroy, tombstone)]` the freed pointer fields are set to null and the
+/// destructor uses `ffi_toolkit::destroy_tombstoned()`, which keeps the struct allocated as a
+/// tombstone, so that destroying it again is a no-op (counted by
+/// `ffi_toolkit::detected_double_frees()`) instead of undefined behavior.
+///
+/// A `debug_id: ffi_toolkit::FfiDebugId` field in front of all others (in a `#[repr(C)]` struct)
+/// makes the struct `ffi_toolkit::DebugIdentified`: `Drop` marks the header as destroyed, and the
+/// destructor refuses to free a pointer whose header isn't live, recording why as the thread's
+/// last error.
+///
+/// With `#[ffi_drop(tag = 7)]` the struct implements `ffi_toolkit::TaggedResponse`, so that it
+/// can be registered with `ffi_toolkit::register_destructor()` and destroyed with
+/// `fil_destroy(7, ptr)`.
+///
+/// With `#[ffi_drop(layout(size = 32, align = 8))]` the struct's layout is asserted at compile
+/// time with `ffi_toolkit::assert_ffi_layout!`, together with the offsets of the fields marked
+/// with e.g. `#[ffi_drop(offset = 16)]`. The struct needs `#[repr(C)]` then.
+#[proc_macro_derive(DropStructMacro, attributes(ffi_drop))]
+pub fn drop_struct_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    let gen = drop_options(&ast).and_then(|options| {
+        if options.error_response {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`error_response` is an option of `FFIResponse`, not of `DropStructMacro`",
+            ));
+        }
+        drop_impl(&ast, quote! {}, options)
+    });
+    match gen {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The `Drop` impl and, if `destroy` is given, the exported destructor
+///
+/// `prelude` is put in front of the code freeing the fields, e.g. to bring the free functions into
+/// scope.
+fn drop_impl(
+    ast: &syn::DeriveInput,
+    prelude: proc_macro2::TokenStream,
+    options: DropOptions,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // A list of fields that should get dropped
+    let mut to_be_dropped = fields_to_drop(ast)?;
+    for field in to_be_dropped.iter_mut() {
+        field.tombstone = options.tombstone;
     }
 
     let name = &ast.ident;
-    let gen = quote! {
+    let type_name = name.to_string();
+    let free = if options.tombstone {
+        quote! { ::ffi_toolkit::destroy_tombstoned(ptr); }
+    } else {
+        quote! { ::ffi_toolkit::free_raw_ptr(ptr); }
+    };
+    // A response with a debug id isn't freed if the pointer doesn't lead to a live one
+    let free = if has_debug_id(ast)? {
+        quote! {
+            if ::ffi_toolkit::__private::destroy_checked(#type_name, ptr) {
+                #free
+            }
+        }
+    } else {
+        free
+    };
+    let response_layout_impl =
+        response_layout_impl(ast, options.destroy.as_ref().map(ToString::to_string))?;
+    let destroy_fn = match options.destroy {
+        Some(destroy) => {
+            let doc = format!("Frees a `{}` that was handed out to the caller", name);
+            quote! {
+                #[doc = #doc]
+                #[no_mangle]
+                pub unsafe extern "C" fn #destroy(ptr: *mut #name) {
+                    ::ffi_toolkit::__private::destroy_or_abort(#type_name, || unsafe { #free });
+                }
+            }
+        }
+        None if options.tombstone => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`#[ffi_drop(tombstone)]` needs `destroy` as well",
+            ))
+        }
+        None => quote! {},
+    };
+    let layout_assertion = layout_assertion(ast, options.layout)?;
+    let (invalidate_debug_id, debug_id_impl) = if has_debug_id(ast)? {
+        (
+            quote! { self.debug_id.invalidate(); },
+            quote! { unsafe impl ::ffi_toolkit::DebugIdentified for #name {} },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let tagged_impl = match options.tag {
+        Some(tag) => quote! {
+            impl ::ffi_toolkit::TaggedResponse for #name {
+                const TYPE_TAG: ::ffi_toolkit::TypeTag = #tag;
+            }
+        },
+        None => quote! {},
+    };
+    let zero_memory = if to_be_dropped
+        .iter()
+        .any(|field| field.wipe || (field.secret && !field.is_c_str() && !field.string_array))
+    {
+        zero_memory_fn()
+    } else {
+        quote! {}
+    };
+    let poison_memory = if cfg!(feature = "poison")
+        && to_be_dropped.iter().any(|field| {
+            field.string_array
+                || (!field.is_c_str() && !field.boxed && field.with.is_none() && !field.secret)
+        }) {
+        poison_memory_fn()
+    } else {
+        quote! {}
+    };
+    Ok(quote! {
         impl Drop for #name {
             fn drop(&mut self) {
-                unsafe {
+                #prelude
+                #zero_memory
+                #poison_memory
+                ::ffi_toolkit::__private::drop_without_unwinding(#type_name, || unsafe {
                     #(#to_be_dropped)*
-                };
+                });
+                #invalidate_debug_id
+            }
+        }
+
+        #destroy_fn
+        #debug_id_impl
+        #tagged_impl
+        #layout_assertion
+        #response_layout_impl
+    })
+}
+
+/// The `ffi_toolkit::assert_ffi_layout!` for `#[ffi_drop(layout(...))]` and the field offsets
+fn layout_assertion(
+    ast: &syn::DeriveInput,
+    layout: Option<(syn::Expr, syn::Expr)>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut offsets = Vec::new();
+    if let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(ref fields_named),
+        ..
+    }) = ast.data
+    {
+        for field in fields_named.named.iter() {
+            if let Some(offset) = field_options(field)?.offset {
+                if layout.is_none() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "`offset` needs `#[ffi_drop(layout(size = ..., align = ...))]` on the \
+                         struct",
+                    ));
+                }
+                let field_name = &field.ident;
+                offsets.push(quote! { #field_name = #offset, });
+            }
+        }
+    }
+    let (size, align) = match layout {
+        Some(layout) => layout,
+        None => return Ok(quote! {}),
+    };
+    // Without `#[repr(C)]` the layout isn't stable, even if the assertion happens to hold
+    if !is_repr_c(ast)? {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "`layout` needs `#[repr(C)]` on the struct",
+        ));
+    }
+    let name = &ast.ident;
+    Ok(quote! {
+        ::ffi_toolkit::assert_ffi_layout!(#name, size = #size, align = #align, offsets {
+            #(#offsets)*
+        });
+    })
+}
+
+/// Whether the struct opts in to `ffi_toolkit::FfiDebugId` headers with a `debug_id` field,
+/// which must come first in a `#[repr(C)]` struct
+fn has_debug_id(ast: &syn::DeriveInput) -> syn::Result<bool> {
+    let fields_named = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields_named),
+            ..
+        }) => fields_named,
+        _ => return Ok(false),
+    };
+    let position = fields_named.named.iter().position(|field| {
+        field
+            .ident
+            .as_ref()
+            .is_some_and(|ident| ident == "debug_id")
+    });
+    match position {
+        None => Ok(false),
+        Some(0) if is_repr_c(ast)? => Ok(true),
+        Some(position) => Err(syn::Error::new_spanned(
+            &fields_named.named[position],
+            "`debug_id` must be the first field of a `#[repr(C)]` struct",
+        )),
+    }
+}
+
+/// Whether the struct is `#[repr(C)]`, possibly next to e.g. `align(8)`
+fn is_repr_c(ast: &syn::DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+            // Skips the arguments of e.g. `align(8)`
+            if meta.input.peek(syn::token::Paren) {
+                meta.input.parse::<proc_macro2::TokenTree>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+/// The `ffi_toolkit::ResponseLayout` impl of a `#[repr(C)]` struct, for `ffi_toolkit::c_harness`
+///
+/// Generic structs have no single layout and don't get one.
+fn response_layout_impl(
+    ast: &syn::DeriveInput,
+    destroy: Option<String>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let fields_named = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields_named),
+            ..
+        }) if ast.generics.params.is_empty() && is_repr_c(ast)? => fields_named,
+        _ => return Ok(quote! {}),
+    };
+    let name = &ast.ident;
+    let name_string = name.to_string();
+    let fields = fields_named.named.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_string = field_name.to_string();
+        quote! { (#field_string, ::core::mem::offset_of!(#name, #field_name)), }
+    });
+    let destroy = match destroy {
+        Some(destroy) => quote! { ::core::option::Option::Some(#destroy) },
+        None => quote! { ::core::option::Option::None },
+    };
+    Ok(quote! {
+        impl ::ffi_toolkit::ResponseLayout for #name {
+            const NAME: &'static str = #name_string;
+            const FIELDS: &'static [(&'static str, usize)] = &[#(#fields)*];
+            const DESTROY: ::core::option::Option<&'static str> = #destroy;
+        }
+    })
+}
+
+/// Generates the scaffolding of a response struct
+///
+/// The struct needs a `status_code: FCPResponseStatus` (or another `ffi_toolkit::StatusCode`)
+/// and an `error_msg: *const libc::c_char` field, next to its payload fields. Generated are:
+///
+///  - a `Default` impl, with `StatusCode::NO_ERROR` as status, null pointers and the `Default` of
+///    all other fields, or with `#[ffi_drop(error_response)]` on the struct a
+///    `new_error_response(code, message)` constructor for `catch_panic_response_with()` instead
+///    (fields without a `Default` give their value with `#[ffi_drop(error_value = "...")]`)
+///  - the `CodeAndMessage` impl for the type of the status code, a `FfiErrorChain` field gets the
+///    causes of errors reported by `catch_panic_result()`, an `error_payload: FfiBytes` field
+///    the details given with `set_error_with_payload()`
+///  - the `ResponseStatus` impl reading the status code and error message back
+///  - the `Drop` impl of `DropStructMacro` (which must not be derived as well), the free
+///    functions don't need to be in scope, and a leading `debug_id: FfiDebugId` field gets a new
+///    header in `Default`
+///  - the exported destructor, named as with `#[ffi_drop(destroy)]` of `DropStructMacro`
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(FFIResponse)]
+/// #[ffi_drop(destroy = "fil_destroy_seal_response")]
+/// pub struct SealResponse {
+///     pub status_code: FCPResponseStatus,
+///     pub error_msg: *const libc::c_char,
+///     pub proof_ptr: *const u8,
+///     pub proof_len: libc::size_t,
+/// }
+/// ```
+#[proc_macro_derive(FFIResponse, attributes(ffi_drop))]
+pub fn ffi_response_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    match ffi_response_impl(&ast) {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn ffi_response_impl(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields_named = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields_named),
+            ..
+        }) => fields_named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`FFIResponse` works only with structs with named fields",
+            ))
+        }
+    };
+    let field = |name: &str| {
+        fields_named
+            .named
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == name))
+    };
+    for (required, ty) in [
+        ("status_code", "FCPResponseStatus"),
+        ("error_msg", "*const libc::c_char"),
+    ] {
+        if field(required).is_none() {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                format!("`FFIResponse` needs a field `{}: {}`", required, ty),
+            ));
+        }
+    }
+    let code = &field("status_code").unwrap().ty;
+
+    // A `FfiErrorChain` field gets the causes of the error
+    let error_chain = fields_named.named.iter().find(|field| match field.ty {
+        syn::Type::Path(ref type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "FfiErrorChain"),
+        _ => false,
+    });
+    let set_error_chain = match error_chain {
+        Some(field) => {
+            let field_name = &field.ident;
+            quote! {
+                fn set_error_chain(&mut self, chain: ::ffi_toolkit::FfiErrorChain) {
+                    self.#field_name = chain;
+                }
+            }
+        }
+        None => quote! {},
+    };
+    // An `error_payload: FfiBytes` field gets the details of the error, it frees itself
+    let set_error_payload = match field("error_payload") {
+        Some(_) => quote! {
+            fn set_error_payload(&mut self, payload: ::ffi_toolkit::FfiBytes) {
+                self.error_payload = payload;
+            }
+        },
+        None => quote! {},
+    };
+
+    let name = &ast.ident;
+    let default_value = |field: &syn::Field| match field.ty {
+        syn::Type::Ptr(ref type_ptr) if type_ptr.mutability.is_some() => {
+            quote! { ::core::ptr::null_mut() }
+        }
+        syn::Type::Ptr(_) => quote! { ::core::ptr::null() },
+        _ => quote! { ::core::default::Default::default() },
+    };
+
+    let mut options = drop_options(ast)?;
+    let constructor = if options.error_response {
+        // The status code and message are arguments, the other fields get their `error_value`
+        let mut values = Vec::new();
+        for field in fields_named.named.iter() {
+            let field_name = field.ident.as_ref().unwrap();
+            let value = match field_options(field)?.error_value {
+                _ if field_name == "status_code" => quote! { code },
+                _ if field_name == "error_msg" => quote! { message },
+                Some(value) => quote! { #value },
+                None => default_value(field),
+            };
+            values.push(quote! { #field_name: #value, });
+        }
+        quote! {
+            impl #name {
+                /// The error response with `code` and `message`, which it owns
+                pub fn new_error_response(
+                    code: #code,
+                    message: *const ::core::ffi::c_char,
+                ) -> Self {
+                    #name {
+                        #(#values)*
+                    }
+                }
+            }
+        }
+    } else {
+        let mut defaults = Vec::new();
+        for field in fields_named.named.iter() {
+            if field_options(field)?.error_value.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`error_value` needs `#[ffi_drop(error_response)]` on the struct",
+                ));
+            }
+            let field_name = field.ident.as_ref().unwrap();
+            let value = if field_name == "status_code" {
+                quote! { <#code as ::ffi_toolkit::StatusCode>::NO_ERROR }
+            } else {
+                default_value(field)
+            };
+            defaults.push(quote! { #field_name: #value, });
+        }
+        quote! {
+            impl ::core::default::Default for #name {
+                fn default() -> Self {
+                    #name {
+                        #(#defaults)*
+                    }
+                }
+            }
+        }
+    };
+
+    if options.destroy.is_none() {
+        options.destroy = Some(default_destroy_fn_name(ast));
+    }
+    let drop = drop_impl(
+        ast,
+        quote! {
+            #[allow(unused_imports)]
+            use ::ffi_toolkit::{free_c_str, free_secret_c_str};
+        },
+        options,
+    )?;
+
+    Ok(quote! {
+        #constructor
+
+        impl ::ffi_toolkit::CodeAndMessage<#code> for #name {
+            fn set_error(&mut self, (code, message): (#code, *const ::core::ffi::c_char)) {
+                self.status_code = code;
+                self.error_msg = message;
+            }
+
+            #set_error_chain
+
+            #set_error_payload
+        }
+
+        impl ::ffi_toolkit::ResponseStatus<#code> for #name {
+            fn status_code(&self) -> #code {
+                self.status_code
+            }
+
+            fn error_msg(&self) -> *const ::core::ffi::c_char {
+                self.error_msg
+            }
+        }
+
+        #drop
+    })
+}
+
+/// Implements `Debug` like `#[derive(Debug)]`, but fields marked with `#[ffi_drop(secret)]` are
+/// printed as `<redacted>`
+#[proc_macro_derive(FfiDebug, attributes(ffi_drop))]
+pub fn ffi_debug_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    match ffi_debug_impl(&ast) {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn ffi_debug_impl(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields_named = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields_named),
+            ..
+        }) => fields_named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`FfiDebug` works only with structs with named fields",
+            ))
+        }
+    };
+    let mut fields = Vec::new();
+    for field in fields_named.named.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+        let label = field_name.to_string();
+        fields.push(if field_options(field)?.secret {
+            quote! { .field(#label, &format_args!("<redacted>")) }
+        } else {
+            quote! { .field(#label, &self.#field_name) }
+        });
+    }
+
+    let name = &ast.ident;
+    let label = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Debug for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(#label)
+                    #(#fields)*
+                    .finish()
             }
         }
+    })
+}
+
+/// Implements `serde::Serialize` for a response struct, following its pointers
+///
+/// Needs the `serde` feature of the toolkit. C strings are serialized as strings, arrays (with
+/// their `_len` fields, or the fields given with `vec(...)`) as sequences of their elements,
+/// arrays of C strings as sequences of strings and `nested` structs as the struct, null
+/// pointers as none. Pointers that can't be valid (unaligned, or with a length that overflows)
+/// fail the serialization instead of being read. Fields marked with `#[ffi_drop(skip)]` point at
+/// memory the struct doesn't own and are left out, `secret` fields are serialized as
+/// `"<redacted>"`. All other fields need to implement `Serialize`, the toolkit types do.
+///
+/// The pointers are followed like by the `Drop` of `DropStructMacro`, so they must be valid.
+#[proc_macro_derive(FfiSerialize, attributes(ffi_drop))]
+pub fn ffi_serialize_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    match ffi_serialize_impl(&ast) {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn ffi_serialize_impl(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields_named = match ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields_named),
+            ..
+        }) => fields_named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`FfiSerialize` works only with structs with named fields",
+            ))
+        }
+    };
+    let pointers = fields_to_drop(ast)?;
+    let mut fields = Vec::new();
+    for field in fields_named.named.iter() {
+        let options = field_options(field)?;
+        if options.skip {
+            continue;
+        }
+        let field_name = field.ident.as_ref().unwrap();
+        let label = field_name.to_string();
+        let value = match pointers
+            .iter()
+            .find(|pointer| pointer.field_name == *field_name)
+        {
+            _ if options.secret => quote! { &"<redacted>" },
+            Some(pointer) if pointer.with.is_some() => quote! { &self.#field_name },
+            Some(pointer) if pointer.is_c_str() => quote! {
+                &unsafe { ::ffi_toolkit::__private::SerializeCStr::new(self.#field_name as _) }
+            },
+            Some(pointer) if pointer.boxed => quote! {
+                &unsafe { ::ffi_toolkit::__private::SerializeNested::new(self.#field_name as _) }
+            },
+            Some(pointer) if pointer.string_array => {
+                let len = pointer.len_field_name();
+                quote! {
+                    &unsafe {
+                        ::ffi_toolkit::__private::SerializeStringArray::new(
+                            self.#field_name as _,
+                            self.#len as usize,
+                        )
+                    }
+                }
+            }
+            Some(pointer) => {
+                let len = pointer.len_field_name();
+                let element = &pointer.field_type;
+                quote! {
+                    &unsafe {
+                        ::ffi_toolkit::__private::SerializeSlice::<#element>::new(
+                            self.#field_name as *const #element,
+                            self.#len as usize,
+                        )
+                    }
+                }
+            }
+            None => quote! { &self.#field_name },
+        };
+        fields.push(quote! {
+            ::ffi_toolkit::__private::serde::ser::SerializeStruct::serialize_field(
+                &mut state,
+                #label,
+                #value,
+            )?;
+        });
+    }
+
+    let name = &ast.ident;
+    let label = name.to_string();
+    let len = fields.len();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ffi_toolkit::__private::serde::Serialize for #name #ty_generics
+            #where_clause
+        {
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: ::ffi_toolkit::__private::serde::Serializer,
+            {
+                let mut state = serializer.serialize_struct(#label, #len)?;
+                #(#fields)*
+                ::ffi_toolkit::__private::serde::ser::SerializeStruct::end(state)
+            }
+        }
+    })
+}
+
+/// Implements `ffi_toolkit::IntoFFIError` for an error enum
+///
+/// Every variant is mapped to the status code given with `#[ffi_error(code = "FCPCallerError")]`,
+/// variants without one use the code given on the enum, or `FCPUnclassifiedError`. The message is
+/// the error's `Display` output.
+///
+/// ```ignore
+/// #[derive(Debug, FFIErrorCode)]
+/// #[ffi_error(code = "FCPReceiverError")]
+/// enum SealError {
+///     #[ffi_error(code = "FCPCallerError")]
+///     InvalidSectorSize(u64),
+///     OutOfSpace,
+/// }
+/// ```
+#[proc_macro_derive(FFIErrorCode, attributes(ffi_error))]
+pub fn ffi_error_code_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    match ffi_error_code_impl(&ast) {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The status code given with `#[ffi_error(code = "...")]`, as path to the variant
+fn ffi_error_code(attrs: &[syn::Attribute]) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    let mut code = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ffi_error"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("code") {
+                let name: syn::LitStr = meta.value()?.parse()?;
+                let variant: Ident = name.parse()?;
+                // Spanned, so that an unknown status is reported at the string
+                code = Some(quote_spanned! {name.span()=>
+                    ::ffi_toolkit::FCPResponseStatus::#variant
+                });
+                Ok(())
+            } else {
+                Err(meta.error("unknown `ffi_error` option, expected `code`"))
+            }
+        })?;
+    }
+    Ok(code)
+}
+
+fn ffi_error_code_impl(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let data_enum = match ast.data {
+        syn::Data::Enum(ref data_enum) => data_enum,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "`FFIErrorCode` works only with enums",
+            ))
+        }
+    };
+    let default_code = ffi_error_code(&ast.attrs)?
+        .unwrap_or_else(|| quote! { ::ffi_toolkit::FCPResponseStatus::FCPUnclassifiedError });
+    let mut arms = Vec::new();
+    for variant in data_enum.variants.iter() {
+        let code = ffi_error_code(&variant.attrs)?.unwrap_or_else(|| default_code.clone());
+        let variant_name = &variant.ident;
+        let fields = match variant.fields {
+            syn::Fields::Named(_) => quote! { { .. } },
+            syn::Fields::Unnamed(_) => quote! { (..) },
+            syn::Fields::Unit => quote! {},
+        };
+        arms.push(quote! { Self::#variant_name #fields => #code, });
+    }
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ffi_toolkit::IntoFFIError for #name #ty_generics #where_clause {
+            fn code(&self) -> ::ffi_toolkit::FCPResponseStatus {
+                match *self {
+                    #(#arms)*
+                }
+            }
+
+            fn message(&self) -> ::ffi_toolkit::__private::String {
+                ::ffi_toolkit::__private::ToString::to_string(self)
+            }
+        }
+    })
+}
+
+/// Wraps the body of an exported function in `ffi_toolkit::catch_panic_response_named()`
+///
+/// The calls are measured as the function's name once `ffi_toolkit::enable_call_metrics()` is
+/// called. The signature stays as it is, so cbindgen still sees the right prototype. The function needs
+/// to return `*mut Response`, where `Response` implements `Default` and `CodeAndMessage`.
+///
+/// ```ignore
+/// #[no_mangle]
+/// #[ffi_catch_panic]
+/// pub unsafe extern "C" fn fil_seal(sector_id: u64) -> *mut SealResponse {
+///     ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn ffi_catch_panic(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            proc_macro2::TokenStream::from(attr).span(),
+            "`#[ffi_catch_panic]` takes no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let mut function: syn::ItemFn = match syn::parse(item) {
+        Ok(function) => function,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    match ffi_catch_panic_impl(&mut function) {
+        Ok(()) => function.into_token_stream().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn ffi_catch_panic_impl(function: &mut syn::ItemFn) -> syn::Result<()> {
+    if let Some(asyncness) = function.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`#[ffi_catch_panic]` can't be used on async functions",
+        ));
+    }
+    let response = match function.sig.output {
+        syn::ReturnType::Type(_, ref ty) => match **ty {
+            syn::Type::Ptr(ref type_ptr) if type_ptr.mutability.is_some() => &type_ptr.elem,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`#[ffi_catch_panic]` functions need to return `*mut Response`",
+                ))
+            }
+        },
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new_spanned(
+                &function.sig,
+                "`#[ffi_catch_panic]` functions need to return `*mut Response`",
+            ))
+        }
+    };
+    // Spanned, so that a response missing the traits is reported at the return type
+    let assert_response = quote_spanned! {response.span()=>
+        fn assert_response<T, C>()
+        where
+            T: ::core::default::Default + ::ffi_toolkit::CodeAndMessage<C>,
+            C: ::ffi_toolkit::StatusCode,
+        {
+        }
+        assert_response::<#response, _>();
     };
-    gen.into()
+    let name = function.sig.ident.to_string();
+    let block = &function.block;
+    *function.block = syn::parse_quote!({
+        #assert_response
+        ::ffi_toolkit::catch_panic_response_named(#name, move || #block)
+    });
+    Ok(())
 }
@@ -0,0 +1,54 @@
+//! Captures what `build_info()` reports about the build: the git commit, the compiler, the
+//! target and the profile.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+// the trimmed output of a command, `None` if it can't be run or fails
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    Some(output.trim().to_string())
+}
+
+fn main() {
+    // Builds from a published crate have no repository
+    let commit = output("git", &["rev-parse", "--short=12", "HEAD"]);
+    if let Some(git_dir) = output("git", &["rev-parse", "--absolute-git-dir"]) {
+        println!(
+            "cargo:rerun-if-changed={}",
+            Path::new(&git_dir).join("HEAD").display()
+        );
+        if let Some(reference) = output("git", &["symbolic-ref", "-q", "HEAD"]) {
+            println!(
+                "cargo:rerun-if-changed={}",
+                Path::new(&git_dir).join(reference).display()
+            );
+        }
+    }
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = output(&rustc, &["--version"]);
+
+    let unknown = || "unknown".to_string();
+    println!(
+        "cargo:rustc-env=FFI_TOOLKIT_GIT_COMMIT={}",
+        commit.unwrap_or_else(unknown)
+    );
+    println!(
+        "cargo:rustc-env=FFI_TOOLKIT_RUSTC_VERSION={}",
+        rustc_version.unwrap_or_else(unknown)
+    );
+    println!(
+        "cargo:rustc-env=FFI_TOOLKIT_TARGET={}",
+        env::var("TARGET").unwrap_or_else(|_| unknown())
+    );
+    println!(
+        "cargo:rustc-env=FFI_TOOLKIT_PROFILE={}",
+        env::var("PROFILE").unwrap_or_else(|_| unknown())
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
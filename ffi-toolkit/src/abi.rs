@@ -0,0 +1,148 @@
+//! A hash of the ABI of a library, so that bindings built against another version fail fast.
+//!
+//! `ffi_abi_version!` hashes a manual version number together with the layouts of the response
+//! structs (their names, sizes, alignments and, if listed, field offsets) at compile time, and
+//! exports `fil_abi_version()` and `fil_abi_check()`. Bindings embed the hash they were
+//! generated against and check it when they load the library, instead of corrupting memory
+//! when a struct changed underneath them.
+//!
+//! The layouts are the ones of the target, so the hash differs between e.g. 32 and 64 bit
+//! builds, as the ABI does.
+
+use crate::{set_last_error, FCPResponseStatus};
+
+// FNV-1a, simple enough to be a `const fn`
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A hash of a version number and struct layouts, built at compile time
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct AbiHash(u64);
+
+impl AbiHash {
+    pub const fn new(version: u32) -> Self {
+        AbiHash(FNV_OFFSET_BASIS).with_u64(version as u64)
+    }
+
+    /// Adds a struct with its size and alignment
+    pub const fn with_type(self, name: &str, size: usize, align: usize) -> Self {
+        self.with_str(name)
+            .with_u64(size as u64)
+            .with_u64(align as u64)
+    }
+
+    /// Adds a field of the last struct with its offset
+    pub const fn with_field(self, name: &str, offset: usize) -> Self {
+        self.with_str(name).with_u64(offset as u64)
+    }
+
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    const fn with_bytes(self, bytes: &[u8]) -> Self {
+        let mut hash = self.0;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+            i += 1;
+        }
+        AbiHash(hash)
+    }
+
+    const fn with_u64(self, value: u64) -> Self {
+        self.with_bytes(&value.to_le_bytes())
+    }
+
+    // Length-prefixed, so that `"ab", "c"` and `"a", "bc"` hash differently
+    const fn with_str(self, string: &str) -> Self {
+        self.with_u64(string.len() as u64)
+            .with_bytes(string.as_bytes())
+    }
+}
+
+/// Embeds the ABI hash of a library and exports `fil_abi_version()` and `fil_abi_check()`
+///
+/// The hash covers the `version` (bumped by hand for changes the layouts don't show, e.g. of a
+/// function signature) and the listed structs. Fields given in braces add their offsets, so
+/// that reordering them changes the hash as well. The hash is also available as the constant
+/// `FIL_ABI_VERSION`. Use it once per library, the exported names are fixed.
+///
+/// ```
+/// use ffi_toolkit::{ffi_abi_version, FCPResponseStatus};
+///
+/// #[repr(C)]
+/// pub struct SealResponse {
+///     pub status_code: FCPResponseStatus,
+///     pub error_msg: *const libc::c_char,
+///     pub proof_ptr: *const u8,
+///     pub proof_len: libc::size_t,
+/// }
+///
+/// #[repr(C)]
+/// pub struct VerifyResponse {
+///     pub status_code: FCPResponseStatus,
+///     pub error_msg: *const libc::c_char,
+///     pub is_valid: bool,
+/// }
+///
+/// ffi_abi_version! {
+///     version = 3;
+///     SealResponse { status_code, error_msg, proof_ptr, proof_len },
+///     VerifyResponse,
+/// }
+///
+/// assert_eq!(fil_abi_version(), FIL_ABI_VERSION);
+/// assert!(fil_abi_check(FIL_ABI_VERSION));
+/// assert!(!fil_abi_check(FIL_ABI_VERSION ^ 1));
+/// ```
+#[macro_export]
+macro_rules! ffi_abi_version {
+    {
+        version = $version:expr;
+        $( $type:ident $({ $($field:ident),* $(,)? })? ),* $(,)?
+    } => {
+        /// The ABI hash of the library, see `fil_abi_version()`
+        pub const FIL_ABI_VERSION: u64 = $crate::AbiHash::new($version)
+            $(
+                .with_type(
+                    stringify!($type),
+                    ::std::mem::size_of::<$type>(),
+                    ::std::mem::align_of::<$type>(),
+                )
+                $($(
+                    .with_field(stringify!($field), ::std::mem::offset_of!($type, $field))
+                )*)?
+            )*
+            .value();
+
+        /// The hash of the ABI of the library, for bindings to compare with the one they were
+        /// generated against
+        #[no_mangle]
+        pub extern "C" fn fil_abi_version() -> u64 {
+            FIL_ABI_VERSION
+        }
+
+        /// Whether the bindings' ABI hash `expected` is the one of the library
+        ///
+        /// On a mismatch the last error (see `fil_last_error_message()`) says so, with both hashes.
+        #[no_mangle]
+        pub extern "C" fn fil_abi_check(expected: u64) -> bool {
+            $crate::abi_check(FIL_ABI_VERSION, expected)
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn abi_check(actual: u64, expected: u64) -> bool {
+    if actual == expected {
+        return true;
+    }
+    let message = format!(
+        "the library's ABI version is {:016x}, the bindings expect {:016x}",
+        actual, expected
+    );
+    set_last_error(FCPResponseStatus::FCPCallerError, message);
+    false
+}
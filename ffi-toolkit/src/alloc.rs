@@ -0,0 +1,460 @@
+//! The layer all allocations that are handed out by the toolkit go through.
+//!
+//! By default they are made with the Rust global allocator. A host that wants to account for
+//! them installs its own allocator with `set_allocator()`, before the toolkit allocated
+//! anything. Responses, C strings and UTF-16 strings are allocated with it, arrays that are
+//! handed out as a pointer and a length (including `FfiBytes`) still come from `Vec`s.
+//!
+//! Every allocation is freed the way it was made, and the toolkit keeps pointers rather than
+//! addresses for memory it hands out again (interned strings, pooled boxes), so that all of it
+//! can be checked by Miri.
+
+use std::alloc::{dealloc, handle_alloc_error, Layout};
+use std::any;
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::{Mutex, OnceLock};
+
+use crate::{alloc_stats, destroy, pool};
+
+/// Allocates `size` bytes aligned to `align` (a power of two), null if that fails
+pub type FfiMallocFn = extern "C" fn(size: libc::size_t, align: libc::size_t) -> *mut libc::c_void;
+
+/// Frees memory allocated by the matching `FfiMallocFn`
+pub type FfiFreeFn = extern "C" fn(ptr: *mut libc::c_void);
+
+#[derive(Debug, Copy, Clone)]
+struct HostAllocator {
+    malloc: FfiMallocFn,
+    free: FfiFreeFn,
+}
+
+// Decided once, by `set_allocator()` or by the first allocation, so that everything is freed by
+// the allocator it came from
+static HOST_ALLOCATOR: OnceLock<Option<HostAllocator>> = OnceLock::new();
+
+fn host_allocator() -> Option<HostAllocator> {
+    *HOST_ALLOCATOR.get_or_init(|| None)
+}
+
+// whether the host's allocator is installed, without deciding on the default allocator
+pub(crate) fn has_host_allocator() -> bool {
+    HOST_ALLOCATOR.get().is_some_and(Option::is_some)
+}
+
+/// Makes the toolkit allocate everything it hands out to C with `malloc` and free it with `free`
+///
+/// Only possible before the toolkit's first allocation, returns whether the allocator was set.
+pub fn set_allocator(malloc: FfiMallocFn, free: FfiFreeFn) -> bool {
+    HOST_ALLOCATOR
+        .set(Some(HostAllocator { malloc, free }))
+        .is_ok()
+}
+
+/// See `set_allocator()`
+#[no_mangle]
+pub extern "C" fn fil_set_allocator(malloc: FfiMallocFn, free: FfiFreeFn) -> bool {
+    set_allocator(malloc, free)
+}
+
+// record an allocation with the test tracking, provenance, statistics and tagged destructors
+fn record_alloc(ptr: *const u8, type_name: &'static str) {
+    tracking::record_alloc(ptr, type_name);
+    provenance::record_alloc(ptr, type_name);
+    alloc_stats::record_alloc(ptr, type_name);
+    destroy::record_alloc(ptr, type_name);
+}
+
+/// The address `DropStructMacro` sets freed pointer fields to with the `poison` feature
+#[cfg(feature = "poison")]
+pub const POISON_PTR: usize = 0xDEAD_BEEF;
+
+/// The byte freed memory is overwritten with with the `poison` feature
+#[cfg(feature = "poison")]
+pub const POISON_BYTE: u8 = 0xDE;
+
+// returns whether the memory should actually be freed, see `tracking::record_free()` and
+// `provenance::record_free()`
+fn record_free(ptr: *const u8, type_name: &'static str) -> bool {
+    #[cfg(feature = "poison")]
+    if ptr as usize == POISON_PTR {
+        panic!("`{}` freed twice, the pointer was poisoned", type_name);
+    }
+    let free = tracking::record_free(ptr, type_name) && provenance::record_free(ptr, type_name);
+    if free {
+        alloc_stats::record_free(ptr);
+        destroy::record_free(ptr);
+    }
+    free
+}
+
+// allocate with the host's allocator, zero-sized allocations get a byte
+fn host_alloc(host: HostAllocator, layout: Layout) -> *mut u8 {
+    let ptr = (host.malloc)(layout.size().max(1), layout.align()) as *mut u8;
+    if ptr.is_null() {
+        handle_alloc_error(layout);
+    }
+    ptr
+}
+
+// copy `units` into memory of the host's allocator
+fn host_alloc_copy<T: Copy>(host: HostAllocator, units: &[T]) -> *mut T {
+    let ptr = host_alloc(host, Layout::for_value(units)) as *mut T;
+    unsafe { ptr::copy_nonoverlapping(units.as_ptr(), ptr, units.len()) };
+    ptr
+}
+
+// hand ownership of a C string over to C
+pub(crate) fn c_str_into_raw(c_string: CString) -> *mut libc::c_char {
+    let ptr = match host_allocator() {
+        Some(host) => host_alloc_copy(host, c_string.as_bytes_with_nul()) as *mut libc::c_char,
+        None => c_string.into_raw(),
+    };
+    record_alloc(ptr as *const u8, "c_char");
+    ptr
+}
+
+// free a C string that was created by `c_str_into_raw()`
+pub(crate) unsafe fn free_c_str(ptr: *mut libc::c_char) {
+    if record_free(ptr as *const u8, "c_char") {
+        match host_allocator() {
+            Some(host) => {
+                let len = CStr::from_ptr(ptr).to_bytes_with_nul().len();
+                poison(slice::from_raw_parts_mut(ptr as *mut u8, len));
+                (host.free)(ptr as *mut libc::c_void);
+            }
+            None => poison(&mut CString::from_raw(ptr).into_bytes_with_nul()),
+        }
+    }
+}
+
+// free a C string that was created by `c_str_into_raw()`, after zeroing its contents
+pub(crate) unsafe fn free_secret_c_str(ptr: *mut libc::c_char) {
+    if record_free(ptr as *const u8, "c_char") {
+        match host_allocator() {
+            Some(host) => {
+                let len = CStr::from_ptr(ptr).to_bytes().len();
+                zero(slice::from_raw_parts_mut(ptr as *mut u8, len));
+                (host.free)(ptr as *mut libc::c_void);
+            }
+            None => zero(&mut CString::from_raw(ptr).into_bytes_with_nul()),
+        }
+    }
+}
+
+// overwrite `bytes` with zeroes, in a way the compiler doesn't optimize away
+pub(crate) fn zero(bytes: &mut [u8]) {
+    fill(bytes, 0);
+}
+
+// overwrite freed `bytes` with `POISON_BYTE`, with the `poison` feature, secrets stay zeroed
+#[cfg(feature = "poison")]
+fn poison(bytes: &mut [u8]) {
+    fill(bytes, POISON_BYTE);
+}
+
+#[cfg(not(feature = "poison"))]
+fn poison(_bytes: &mut [u8]) {}
+
+#[cfg(not(miri))]
+fn fill(bytes: &mut [u8], value: u8) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, value) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+// Miri doesn't optimize the writes away, but interprets the volatile ones byte by byte slowly
+#[cfg(miri)]
+fn fill(bytes: &mut [u8], value: u8) {
+    bytes.fill(value);
+}
+
+// hand ownership of a nul-terminated UTF-16 string over to C
+pub(crate) fn wide_str_into_raw(wide: Vec<u16>) -> *mut u16 {
+    let ptr = match host_allocator() {
+        Some(host) => host_alloc_copy(host, &wide),
+        None => Box::into_raw(wide.into_boxed_slice()) as *mut u16,
+    };
+    record_alloc(ptr as *const u8, "u16");
+    ptr
+}
+
+// free a UTF-16 string that was created by `wide_str_into_raw()`, `len` counts the nul
+pub(crate) unsafe fn free_wide_str(ptr: *mut u16, len: usize) {
+    if record_free(ptr as *const u8, "u16") {
+        poison(slice::from_raw_parts_mut(ptr as *mut u8, len * 2));
+        match host_allocator() {
+            Some(host) => (host.free)(ptr as *mut libc::c_void),
+            None => drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len))),
+        }
+    }
+}
+
+// poison the memory of a value that was dropped already
+unsafe fn poison_value<T>(ptr: *mut T) {
+    poison(slice::from_raw_parts_mut(
+        ptr as *mut u8,
+        mem::size_of::<T>(),
+    ));
+}
+
+// hand ownership of a boxed value over to C
+pub(crate) fn box_into_raw<T>(value: T) -> *mut T {
+    let ptr = match host_allocator() {
+        Some(host) => {
+            let ptr = host_alloc(host, Layout::new::<T>()) as *mut T;
+            unsafe { ptr::write(ptr, value) };
+            ptr
+        }
+        None => Box::into_raw(Box::new(value)),
+    };
+    record_alloc(ptr as *const u8, any::type_name::<T>());
+    ptr
+}
+
+// like `box_into_raw()`, but with a box of the pool of `T` if it has one
+pub(crate) fn pooled_box_into_raw<T>(value: T) -> *mut T {
+    match pool::take(any::type_name::<T>(), Layout::new::<T>()) {
+        Some(ptr) => {
+            let ptr = ptr as *mut T;
+            unsafe { ptr::write(ptr, value) };
+            record_alloc(ptr as *const u8, any::type_name::<T>());
+            ptr
+        }
+        None => box_into_raw(value),
+    }
+}
+
+// free the memory of a box created by `box_into_raw()`, after its value was dropped
+pub(crate) unsafe fn dealloc_raw(ptr: *mut u8, layout: Layout) {
+    match host_allocator() {
+        Some(host) => (host.free)(ptr as *mut libc::c_void),
+        None if layout.size() != 0 => dealloc(ptr, layout),
+        None => {}
+    }
+}
+
+// free a value that was created by `box_into_raw()`, its memory goes back to the pool of `T` if
+// it has room
+pub(crate) unsafe fn free_box<T>(ptr: *mut T) {
+    if record_free(ptr as *const u8, any::type_name::<T>()) {
+        ptr::drop_in_place(ptr);
+        poison_value(ptr);
+        let layout = Layout::new::<T>();
+        if layout.size() == 0 || !pool::recycle(ptr as *mut u8, any::type_name::<T>(), layout) {
+            dealloc_raw(ptr as *mut u8, layout);
+        }
+    }
+}
+
+// The values dropped by `tombstone_box()`, their memory is never freed, so the addresses aren't
+// reused
+static TOMBSTONES: Mutex<Option<HashSet<usize>>> = Mutex::new(None);
+static DOUBLE_FREES: AtomicU64 = AtomicU64::new(0);
+
+// drop a value that was created by `box_into_raw()`, but keep its memory, so that dropping it
+// again is detected instead of freeing the memory twice
+pub(crate) unsafe fn tombstone_box<T>(ptr: *mut T) {
+    // All zero-sized values have the same address, and there's no memory to free twice
+    if mem::size_of::<T>() == 0 {
+        return free_box(ptr);
+    }
+    let first = TOMBSTONES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(ptr as usize);
+    if !first {
+        DOUBLE_FREES.fetch_add(1, atomic::Ordering::SeqCst);
+        return;
+    }
+    if record_free(ptr as *const u8, any::type_name::<T>()) {
+        ptr::drop_in_place(ptr);
+    }
+}
+
+// the number of allocations handed out and not freed yet, if they are counted
+pub(crate) fn live_allocations() -> Option<usize> {
+    if alloc_stats::alloc_stats_enabled() {
+        return Some(alloc_stats::live_ffi_allocations());
+    }
+    #[cfg(feature = "testing")]
+    return Some(tracking::live().max(0) as usize);
+    #[cfg(not(feature = "testing"))]
+    None
+}
+
+/// The number of times a tombstoned value was destroyed again, see `destroy_tombstoned()`
+pub fn detected_double_frees() -> u64 {
+    DOUBLE_FREES.load(atomic::Ordering::SeqCst)
+}
+
+/// See `detected_double_frees()`
+#[no_mangle]
+pub extern "C" fn fil_detected_double_frees() -> u64 {
+    detected_double_frees()
+}
+
+#[cfg(feature = "testing")]
+pub(crate) mod tracking {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicIsize, Ordering};
+
+    // The allocations of all threads that weren't freed yet
+    static LIVE: AtomicIsize = AtomicIsize::new(0);
+
+    /// The allocations and frees of a thread, while it's being tracked
+    #[derive(Debug, Default)]
+    pub(crate) struct Tracker {
+        pub live: HashMap<usize, &'static str>,
+        pub freed: HashMap<usize, &'static str>,
+        pub double_frees: Vec<&'static str>,
+    }
+
+    thread_local! {
+        static TRACKER: RefCell<Option<Tracker>> = const { RefCell::new(None) };
+    }
+
+    pub(crate) fn start() {
+        TRACKER.with(|tracker| *tracker.borrow_mut() = Some(Tracker::default()));
+    }
+
+    pub(crate) fn stop() -> Option<Tracker> {
+        TRACKER.with(|tracker| tracker.borrow_mut().take())
+    }
+
+    pub(crate) fn live() -> isize {
+        LIVE.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn record_alloc(ptr: *const u8, type_name: &'static str) {
+        LIVE.fetch_add(1, Ordering::SeqCst);
+        TRACKER.with(|tracker| {
+            if let Some(tracker) = tracker.borrow_mut().as_mut() {
+                tracker.freed.remove(&(ptr as usize));
+                tracker.live.insert(ptr as usize, type_name);
+            }
+        })
+    }
+
+    // returns whether the memory should actually be freed, a detected double free is skipped
+    pub(crate) fn record_free(ptr: *const u8, type_name: &'static str) -> bool {
+        let free = TRACKER.with(|tracker| match tracker.borrow_mut().as_mut() {
+            Some(tracker) => {
+                let address = ptr as usize;
+                if tracker.live.remove(&address).is_some() {
+                    tracker.freed.insert(address, type_name);
+                    true
+                } else if let Some(type_name) = tracker.freed.get(&address) {
+                    tracker.double_frees.push(type_name);
+                    false
+                } else {
+                    // Allocated before tracking started
+                    true
+                }
+            }
+            None => true,
+        });
+        if free {
+            LIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+        free
+    }
+}
+
+#[cfg(not(feature = "testing"))]
+pub(crate) mod tracking {
+    pub(crate) fn record_alloc(_ptr: *const u8, _type_name: &'static str) {}
+
+    pub(crate) fn record_free(_ptr: *const u8, _type_name: &'static str) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "provenance")]
+pub(crate) mod provenance {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use crate::{set_last_error, FCPResponseStatus};
+
+    // The pointers handed out and not freed yet, with their type names
+    static LIVE: Mutex<Option<HashMap<usize, &'static str>>> = Mutex::new(None);
+    static VIOLATIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    pub(crate) fn record_alloc(ptr: *const u8, type_name: &'static str) {
+        LIVE.lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(ptr as usize, type_name);
+    }
+
+    // returns whether the memory should actually be freed, it is leaked instead if the toolkit
+    // didn't hand it out, it was freed already or it is freed as a different type
+    pub(crate) fn record_free(ptr: *const u8, type_name: &'static str) -> bool {
+        let mut live = LIVE.lock().unwrap();
+        let live = live.get_or_insert_with(HashMap::new);
+        let violation = match live.get(&(ptr as usize)) {
+            Some(&allocated) if allocated == type_name => {
+                live.remove(&(ptr as usize));
+                return true;
+            }
+            Some(allocated) => format!(
+                "refusing to free {:p} as `{}`, it was handed out as `{}`",
+                ptr, type_name, allocated
+            ),
+            None => format!(
+                "refusing to free {:p} as `{}`, the toolkit didn't hand it out or it was freed \
+                 already",
+                ptr, type_name
+            ),
+        };
+        eprintln!("ffi-toolkit: {}", violation);
+        set_last_error(FCPResponseStatus::FCPCallerError, violation.clone());
+        VIOLATIONS.lock().unwrap().push(violation);
+        false
+    }
+
+    pub(crate) fn is_live(ptr: *const u8) -> bool {
+        LIVE.lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|live| live.contains_key(&(ptr as usize)))
+    }
+
+    pub(crate) fn take_violations() -> Vec<String> {
+        std::mem::take(&mut *VIOLATIONS.lock().unwrap())
+    }
+}
+
+#[cfg(not(feature = "provenance"))]
+pub(crate) mod provenance {
+    pub(crate) fn record_alloc(_ptr: *const u8, _type_name: &'static str) {}
+
+    pub(crate) fn record_free(_ptr: *const u8, _type_name: &'static str) -> bool {
+        true
+    }
+}
+
+/// Whether `ptr` was handed out by the toolkit and wasn't freed yet
+#[cfg(feature = "provenance")]
+pub fn is_toolkit_pointer<T>(ptr: *const T) -> bool {
+    provenance::is_live(ptr as *const u8)
+}
+
+/// The frees that were refused since the last call, see the `provenance` feature
+///
+/// Frees of responses, C strings and UTF-16 strings are checked against the pointers the toolkit
+/// handed out, a pointer it didn't, that was freed already or that is freed as another type is
+/// leaked instead of corrupting the heap. Each refused free is also printed to stderr and
+/// recorded as the thread's last error.
+#[cfg(feature = "provenance")]
+pub fn take_provenance_violations() -> Vec<String> {
+    provenance::take_violations()
+}
@@ -0,0 +1,159 @@
+//! Statistics of the allocations the toolkit handed out, to find leaked responses.
+//!
+//! Once enabled with `enable_alloc_stats()` (exported as `fil_enable_alloc_stats()`), every
+//! response, C string and UTF-16 string the toolkit allocates is recorded by its type name until
+//! it is freed. A count that keeps growing points to a response the host never destroys. The
+//! statistics cost a hash map update per allocation, so they are off by default. Allocations
+//! from before they were enabled aren't counted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str, FCPResponseStatus};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct Stats {
+    live: HashMap<usize, &'static str>,
+    allocated: HashMap<&'static str, u64>,
+}
+
+static STATS: Mutex<Option<Stats>> = Mutex::new(None);
+
+/// The allocations of one type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocTypeStats {
+    pub type_name: &'static str,
+    /// Allocated and not freed yet
+    pub live: usize,
+    /// Allocated since the statistics were enabled
+    pub allocated: u64,
+}
+
+pub(crate) fn record_alloc(ptr: *const u8, type_name: &'static str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(stats) = STATS.lock().unwrap().as_mut() {
+        stats.live.insert(ptr as usize, type_name);
+        *stats.allocated.entry(type_name).or_insert(0) += 1;
+    }
+}
+
+pub(crate) fn record_free(ptr: *const u8) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(stats) = STATS.lock().unwrap().as_mut() {
+        stats.live.remove(&(ptr as usize));
+    }
+}
+
+/// Starts or stops recording allocations, stopping discards the statistics
+pub fn enable_alloc_stats(enabled: bool) {
+    let mut stats = STATS.lock().unwrap();
+    *stats = if enabled {
+        Some(stats.take().unwrap_or_default())
+    } else {
+        None
+    };
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn alloc_stats_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// The statistics by type name, sorted by it, empty unless enabled
+pub fn alloc_stats() -> Vec<AllocTypeStats> {
+    let stats = STATS.lock().unwrap();
+    let stats = match stats.as_ref() {
+        Some(stats) => stats,
+        None => return Vec::new(),
+    };
+    let mut live = BTreeMap::new();
+    for type_name in stats.live.values() {
+        *live.entry(*type_name).or_insert(0) += 1;
+    }
+    let mut by_type: Vec<_> = stats
+        .allocated
+        .iter()
+        .map(|(&type_name, &allocated)| AllocTypeStats {
+            type_name,
+            live: live.get(type_name).copied().unwrap_or(0),
+            allocated,
+        })
+        .collect();
+    by_type.sort_by_key(|stats| stats.type_name);
+    by_type
+}
+
+/// The number of recorded allocations that weren't freed yet
+pub fn live_ffi_allocations() -> usize {
+    STATS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or(0, |stats| stats.live.len())
+}
+
+/// See `enable_alloc_stats()`
+#[no_mangle]
+pub extern "C" fn fil_enable_alloc_stats(enabled: bool) {
+    enable_alloc_stats(enabled);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct FfiAllocTypeStats {
+    pub type_name: *const libc::c_char,
+    pub live: u64,
+    pub allocated: u64,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct AllocStatsResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub enabled: bool,
+    /// The number of live allocations of all types, not counting this response
+    pub live_total: u64,
+    pub types_ptr: *const FfiAllocTypeStats,
+    pub types_len: libc::size_t,
+}
+
+/// The statistics, free them with `fil_destroy_alloc_stats_response()`
+#[no_mangle]
+pub extern "C" fn fil_alloc_stats() -> *mut AllocStatsResponse {
+    // Taken before the response is allocated, so that it doesn't count itself
+    let by_type = alloc_stats();
+    let live_total = live_ffi_allocations() as u64;
+    let types: Box<[FfiAllocTypeStats]> = by_type
+        .into_iter()
+        .map(|stats| FfiAllocTypeStats {
+            type_name: rust_str_to_c_str(stats.type_name),
+            live: stats.live as u64,
+            allocated: stats.allocated,
+        })
+        .collect();
+    let types_len = types.len();
+    raw_ptr(AllocStatsResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: ptr::null(),
+        enabled: alloc_stats_enabled(),
+        live_total,
+        types_ptr: Box::into_raw(types) as *const FfiAllocTypeStats,
+        types_len,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_alloc_stats_response(ptr: *mut AllocStatsResponse) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,161 @@
+//! An arena for responses owning many strings and arrays, freed with a single call.
+//!
+//! Instead of allocating every C string and array of a response separately (and freeing each of
+//! them again), they are copied into a `FfiArena` with `arena_str()` and `arena_slice()`. The
+//! response keeps the arena in a `*mut FfiArena` field marked with `#[ffi_drop(arena)]`, which
+//! frees all of it at once, and the fields pointing into it are marked with `#[ffi_drop(skip)]`.
+//!
+//! ```
+//! use drop_struct_macro_derive::FFIResponse;
+//! use ffi_toolkit::{arena_slice, arena_str, FCPResponseStatus, FfiArena};
+//!
+//! #[repr(C)]
+//! #[derive(FFIResponse)]
+//! pub struct ListSectorsResponse {
+//!     pub status_code: FCPResponseStatus,
+//!     pub error_msg: *const libc::c_char,
+//!     #[ffi_drop(skip)]
+//!     pub paths_ptr: *const *const libc::c_char,
+//!     pub paths_len: libc::size_t,
+//!     #[ffi_drop(arena)]
+//!     pub arena: *mut FfiArena,
+//! }
+//!
+//! let mut arena = FfiArena::new();
+//! let paths: Vec<_> = ["/sealed/s-1", "/sealed/s-2"]
+//!     .iter()
+//!     .map(|path| arena_str(&mut arena, path))
+//!     .collect();
+//! let response = ListSectorsResponse {
+//!     paths_ptr: arena_slice(&mut arena, &paths),
+//!     paths_len: paths.len(),
+//!     arena: arena.into_raw(),
+//!     ..Default::default()
+//! };
+//! drop(response);
+//! ```
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+use crate::{free_raw_ptr, raw_ptr};
+
+// The size of the chunks, larger allocations get a chunk of their own
+const CHUNK_SIZE: usize = 4096;
+// The alignment of the chunks, types with a larger one get a chunk of their own
+const CHUNK_ALIGN: usize = 16;
+
+/// Memory for the strings and arrays of one response
+///
+/// The memory is allocated in chunks, which never move, so pointers into the arena stay valid
+/// until the arena is freed, also if it is moved.
+#[derive(Default)]
+pub struct FfiArena {
+    chunks: Vec<(NonNull<u8>, Layout)>,
+    // The bytes used of the last regular chunk
+    used: usize,
+    // The index of the last regular chunk, `None` before the first one
+    current: Option<usize>,
+}
+
+// The arena only hands out pointers, the memory has no owner on another thread
+unsafe impl Send for FfiArena {}
+
+impl fmt::Debug for FfiArena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FfiArena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+impl FfiArena {
+    pub fn new() -> Self {
+        FfiArena::default()
+    }
+
+    /// The number of bytes of all chunks
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.iter().map(|(_, layout)| layout.size()).sum()
+    }
+
+    /// Hands the arena over to C, as the field of a response, see `free_arena()`
+    pub fn into_raw(self) -> *mut FfiArena {
+        raw_ptr(self)
+    }
+
+    fn new_chunk(&mut self, layout: Layout) -> NonNull<u8> {
+        let ptr = match NonNull::new(unsafe { alloc::alloc(layout) }) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(layout),
+        };
+        self.chunks.push((ptr, layout));
+        ptr
+    }
+
+    // `layout.size()` bytes aligned to `layout.align()`, which stay valid as long as the arena
+    fn alloc(&mut self, layout: Layout) -> NonNull<u8> {
+        if layout.size() == 0 {
+            // Aligned like the type, as for an empty `Vec`
+            return unsafe { NonNull::new_unchecked(layout.align() as *mut u8) };
+        }
+        if layout.size() > CHUNK_SIZE / 4 || layout.align() > CHUNK_ALIGN {
+            return self.new_chunk(layout.align_to(CHUNK_ALIGN).unwrap());
+        }
+        if let Some(current) = self.current {
+            let (chunk, _) = self.chunks[current];
+            let start = (self.used + layout.align() - 1) & !(layout.align() - 1);
+            if start + layout.size() <= CHUNK_SIZE {
+                self.used = start + layout.size();
+                return unsafe { NonNull::new_unchecked(chunk.as_ptr().add(start)) };
+            }
+        }
+        let chunk = self.new_chunk(Layout::from_size_align(CHUNK_SIZE, CHUNK_ALIGN).unwrap());
+        self.current = Some(self.chunks.len() - 1);
+        self.used = layout.size();
+        chunk
+    }
+}
+
+impl Drop for FfiArena {
+    fn drop(&mut self) {
+        for (ptr, layout) in self.chunks.drain(..) {
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+}
+
+/// Copies `string` into the arena as a C string, truncated at the first nul byte
+pub fn arena_str(arena: &mut FfiArena, string: &str) -> *const libc::c_char {
+    let bytes = string.as_bytes();
+    let len = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    let ptr = arena.alloc(Layout::array::<u8>(len + 1).unwrap()).as_ptr();
+    unsafe {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, len);
+        *ptr.add(len) = 0;
+    }
+    ptr as *const libc::c_char
+}
+
+/// Copies `items` into the arena, e.g. an array of C strings from `arena_str()`
+///
+/// The items are never dropped, so they can't own anything outside of the arena.
+pub fn arena_slice<T: Copy>(arena: &mut FfiArena, items: &[T]) -> *const T {
+    let ptr = arena
+        .alloc(Layout::array::<T>(items.len()).unwrap())
+        .as_ptr() as *mut T;
+    if mem::size_of::<T>() != 0 {
+        unsafe { ptr::copy_nonoverlapping(items.as_ptr(), ptr, items.len()) };
+    }
+    ptr
+}
+
+/// Frees an arena from `FfiArena::into_raw()` and everything in it, null pointers are ignored
+pub unsafe fn free_arena(arena: *mut FfiArena) {
+    free_raw_ptr(arena);
+}
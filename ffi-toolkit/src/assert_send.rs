@@ -0,0 +1,129 @@
+//! `Send` and `Sync` for values from the host that Rust can't prove thread-safe, such as
+//! callbacks and their `user_data`.
+//!
+//! Instead of an `unsafe impl Send` for every type holding such a value, the value is wrapped in
+//! `AssertSend` or `AssertSync`, whose unsafe constructors are where the host's promise is
+//! asserted. Values that only need to be moved, but must be used on the thread that created
+//! them (e.g. `user_data` of a host that isn't thread-safe), are wrapped with `bound()`: debug
+//! builds then panic when they are used on another thread.
+
+use std::fmt;
+#[cfg(debug_assertions)]
+use std::thread::{self, ThreadId};
+
+// The thread a value is bound to, nothing is checked in release builds
+#[derive(Clone, Copy)]
+struct Affinity {
+    #[cfg(debug_assertions)]
+    owner: Option<ThreadId>,
+}
+
+impl Affinity {
+    fn any() -> Self {
+        Affinity {
+            #[cfg(debug_assertions)]
+            owner: None,
+        }
+    }
+
+    fn current() -> Self {
+        Affinity {
+            #[cfg(debug_assertions)]
+            owner: Some(thread::current().id()),
+        }
+    }
+
+    fn check(&self, wrapper: &str) {
+        #[cfg(debug_assertions)]
+        if let Some(owner) = self.owner {
+            assert!(
+                thread::current().id() == owner,
+                "a value of `{}` bound to {:?} was used on {:?}",
+                wrapper,
+                owner,
+                thread::current().id()
+            );
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = wrapper;
+    }
+}
+
+macro_rules! assert_wrapper {
+    ($wrapper:ident) => {
+        impl<T> $wrapper<T> {
+            /// The caller must guarantee that `value` can be used from other threads
+            pub unsafe fn new(value: T) -> Self {
+                $wrapper {
+                    value,
+                    affinity: Affinity::any(),
+                }
+            }
+
+            /// Like `new()`, but the value may only be used on the current thread, which debug
+            /// builds check
+            pub unsafe fn bound(value: T) -> Self {
+                $wrapper {
+                    value,
+                    affinity: Affinity::current(),
+                }
+            }
+
+            pub fn get(&self) -> &T {
+                self.affinity.check(stringify!($wrapper));
+                &self.value
+            }
+
+            pub fn get_mut(&mut self) -> &mut T {
+                self.affinity.check(stringify!($wrapper));
+                &mut self.value
+            }
+
+            pub fn into_inner(self) -> T {
+                self.affinity.check(stringify!($wrapper));
+                self.value
+            }
+        }
+
+        impl<T: Clone> Clone for $wrapper<T> {
+            fn clone(&self) -> Self {
+                $wrapper {
+                    value: self.get().clone(),
+                    affinity: self.affinity,
+                }
+            }
+        }
+
+        impl<T: Copy> Copy for $wrapper<T> {}
+
+        impl<T: fmt::Debug> fmt::Debug for $wrapper<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_tuple(stringify!($wrapper))
+                    .field(&self.value)
+                    .finish()
+            }
+        }
+    };
+}
+
+/// A value that can be sent to other threads, as asserted by the caller of `new()`
+pub struct AssertSend<T> {
+    value: T,
+    affinity: Affinity,
+}
+
+unsafe impl<T> Send for AssertSend<T> {}
+
+assert_wrapper!(AssertSend);
+
+/// A value that can be shared between threads, as asserted by the caller of `new()`
+///
+/// It's `Send` if `T` is, wrap it in an `AssertSend` as well otherwise.
+pub struct AssertSync<T> {
+    value: T,
+    affinity: Affinity,
+}
+
+unsafe impl<T> Sync for AssertSync<T> {}
+
+assert_wrapper!(AssertSync);
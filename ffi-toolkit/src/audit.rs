@@ -0,0 +1,84 @@
+//! A ring buffer of the most recent FFI calls, for postmortems.
+//!
+//! Recording and reading are lock-free, so the crash handler can dump the ring from a signal
+//! handler. Entries written concurrently with a read may be skipped, which is fine for a trail
+//! that is only looked at after a crash.
+
+use std::slice;
+use std::str;
+use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+
+use crate::monotonic_now_ns;
+
+/// The number of calls the ring remembers
+pub const AUDIT_RING_LEN: usize = 32;
+
+struct Entry {
+    // Odd while the entry is being written, a seqlock
+    seq: AtomicUsize,
+    name_ptr: AtomicPtr<u8>,
+    name_len: AtomicUsize,
+    // `monotonic_now_ns()` when the call was recorded
+    at_ns: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY: Entry = Entry {
+    seq: AtomicUsize::new(0),
+    name_ptr: AtomicPtr::new(std::ptr::null_mut()),
+    name_len: AtomicUsize::new(0),
+    at_ns: AtomicU64::new(0),
+};
+
+static RING: [Entry; AUDIT_RING_LEN] = [EMPTY; AUDIT_RING_LEN];
+
+// The number of calls recorded so far, the next entry is at `NEXT % AUDIT_RING_LEN`
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// Records a call of the exported function `name`
+pub fn record_ffi_call(name: &'static str) {
+    let entry = &RING[NEXT.fetch_add(1, Ordering::SeqCst) % AUDIT_RING_LEN];
+    let seq = entry.seq.load(Ordering::SeqCst);
+    // Someone else writes the entry right now, the ring wrapped around meanwhile. Losing one
+    // of the two calls is fine.
+    if seq % 2 == 1
+        || entry
+            .seq
+            .compare_exchange(seq, seq + 1, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+    {
+        return;
+    }
+    entry
+        .name_ptr
+        .store(name.as_ptr() as *mut u8, Ordering::SeqCst);
+    entry.name_len.store(name.len(), Ordering::SeqCst);
+    entry.at_ns.store(monotonic_now_ns(), Ordering::SeqCst);
+    entry.seq.store(seq + 2, Ordering::SeqCst);
+}
+
+// calls `f` with the name and time of the recorded calls, oldest first, without allocating
+pub(crate) fn for_each_recent_call<F: FnMut(&'static str, u64)>(mut f: F) {
+    let next = NEXT.load(Ordering::SeqCst);
+    for index in next.saturating_sub(AUDIT_RING_LEN)..next {
+        let entry = &RING[index % AUDIT_RING_LEN];
+        let seq = entry.seq.load(Ordering::SeqCst);
+        let name_ptr = entry.name_ptr.load(Ordering::SeqCst);
+        let name_len = entry.name_len.load(Ordering::SeqCst);
+        let at_ns = entry.at_ns.load(Ordering::SeqCst);
+        // Not written yet, being written or it was overwritten while we read it
+        if seq == 0 || seq % 2 == 1 || entry.seq.load(Ordering::SeqCst) != seq {
+            continue;
+        }
+        // Only `&'static str`s are recorded
+        let name = unsafe { str::from_utf8_unchecked(slice::from_raw_parts(name_ptr, name_len)) };
+        f(name, at_ns);
+    }
+}
+
+/// The names of the most recently recorded calls, oldest first
+pub fn recent_ffi_calls() -> Vec<&'static str> {
+    let mut names = Vec::with_capacity(AUDIT_RING_LEN);
+    for_each_recent_call(|name, _| names.push(name));
+    names
+}
@@ -0,0 +1,203 @@
+//! Results of batch operations, with a status per item.
+//!
+//! A batch operation (verifying many proofs, unsealing many sectors) returns a `FfiBatch<T>`
+//! instead of a single aggregate error, so that the host sees which items failed and why. Item
+//! `i` has the status `items_ptr[i]` and, if it succeeded, the payload `payloads_ptr[i]`.
+//! Failed items have the `Default` payload. The batch is a field of the response:
+//!
+//! ```
+//! use drop_struct_macro_derive::FFIResponse;
+//! use ffi_toolkit::{FCPResponseStatus, FfiBatch};
+//!
+//! #[repr(C)]
+//! #[derive(FFIResponse)]
+//! pub struct VerifyBatchResponse {
+//!     pub status_code: FCPResponseStatus,
+//!     pub error_msg: *const libc::c_char,
+//!     pub proofs: FfiBatch<bool>,
+//! }
+//!
+//! let proofs: FfiBatch<bool> = vec![
+//!     Ok(true),
+//!     Err((FCPResponseStatus::FCPCallerError, "truncated proof".to_string())),
+//! ]
+//! .into_iter()
+//! .collect();
+//! assert_eq!(proofs.failed(), 1);
+//! ```
+
+use std::iter::FromIterator;
+use std::ptr;
+use std::slice;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{free_c_str, rust_str_to_c_str, FCPResponseStatus, IntoFFIError};
+
+/// The status of one item of a batch
+#[repr(C)]
+#[derive(Debug, DropStructMacro)]
+pub struct FfiBatchItem {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl FfiBatchItem {
+    pub fn ok() -> Self {
+        FfiBatchItem {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+        }
+    }
+
+    pub fn error<E: IntoFFIError + ?Sized>(err: &E) -> Self {
+        FfiBatchItem {
+            status_code: err.code(),
+            error_msg: rust_str_to_c_str(err.message()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status_code == FCPResponseStatus::FCPNoError
+    }
+}
+
+/// The items of a batch, which own their memory
+///
+/// Dropping it frees the items and payloads, so it can be a field of a `DropStructMacro` or
+/// `FFIResponse` response, the derived `Drop` leaves it to its own.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiBatch<T> {
+    pub items_ptr: *const FfiBatchItem,
+    pub payloads_ptr: *const T,
+    /// The number of items, and of payloads
+    pub len: libc::size_t,
+}
+
+impl<T> FfiBatch<T> {
+    pub fn builder() -> BatchBuilder<T> {
+        BatchBuilder::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn items(&self) -> &[FfiBatchItem] {
+        if self.len == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.items_ptr, self.len) }
+        }
+    }
+
+    pub fn payloads(&self) -> &[T] {
+        if self.len == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.payloads_ptr, self.len) }
+        }
+    }
+
+    /// The number of items that failed
+    pub fn failed(&self) -> usize {
+        self.items().iter().filter(|item| !item.is_ok()).count()
+    }
+}
+
+impl<T> Default for FfiBatch<T> {
+    fn default() -> Self {
+        FfiBatch {
+            items_ptr: ptr::null(),
+            payloads_ptr: ptr::null(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Drop for FfiBatch<T> {
+    fn drop(&mut self) {
+        // Null for a default batch
+        if !self.items_ptr.is_null() {
+            unsafe {
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    self.items_ptr as *mut FfiBatchItem,
+                    self.len,
+                )));
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    self.payloads_ptr as *mut T,
+                    self.len,
+                )));
+            }
+        }
+    }
+}
+
+impl<T: Default, E: IntoFFIError> FromIterator<Result<T, E>> for FfiBatch<T> {
+    fn from_iter<I: IntoIterator<Item = Result<T, E>>>(results: I) -> Self {
+        let mut builder = BatchBuilder::default();
+        for result in results {
+            builder.push_result(result);
+        }
+        builder.build()
+    }
+}
+
+/// Collects the items of a `FfiBatch`
+#[derive(Debug)]
+pub struct BatchBuilder<T> {
+    items: Vec<FfiBatchItem>,
+    payloads: Vec<T>,
+}
+
+impl<T> Default for BatchBuilder<T> {
+    fn default() -> Self {
+        BatchBuilder {
+            items: Vec::new(),
+            payloads: Vec::new(),
+        }
+    }
+}
+
+impl<T> BatchBuilder<T> {
+    pub fn push_ok(&mut self, payload: T) -> &mut Self {
+        self.items.push(FfiBatchItem::ok());
+        self.payloads.push(payload);
+        self
+    }
+
+    pub fn push_error<E: IntoFFIError + ?Sized>(&mut self, err: &E) -> &mut Self
+    where
+        T: Default,
+    {
+        self.items.push(FfiBatchItem::error(err));
+        self.payloads.push(T::default());
+        self
+    }
+
+    pub fn push_result<E: IntoFFIError>(&mut self, result: Result<T, E>) -> &mut Self
+    where
+        T: Default,
+    {
+        match result {
+            Ok(payload) => self.push_ok(payload),
+            Err(err) => self.push_error(&err),
+        }
+    }
+
+    pub fn build(&mut self) -> FfiBatch<T> {
+        let items = std::mem::take(&mut self.items).into_boxed_slice();
+        let payloads = std::mem::take(&mut self.payloads).into_boxed_slice();
+        let len = items.len();
+        FfiBatch {
+            items_ptr: Box::into_raw(items) as *const FfiBatchItem,
+            payloads_ptr: Box::into_raw(payloads) as *const T,
+            len,
+        }
+    }
+}
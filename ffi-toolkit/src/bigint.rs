@@ -0,0 +1,65 @@
+use std::slice;
+
+use num_bigint::BigUint;
+
+/// The little-endian bytes of `value` in canonical form
+///
+/// The canonical form has no trailing (most significant) zero bytes, zero is the empty buffer.
+pub fn biguint_to_le_bytes(value: &BigUint) -> Vec<u8> {
+    if value.bits() == 0 {
+        Vec::new()
+    } else {
+        value.to_bytes_le()
+    }
+}
+
+/// The little-endian bytes of `value` padded with zeros to `width` bytes, e.g. for field
+/// elements
+///
+/// Returns `None` if the value doesn't fit.
+pub fn biguint_to_le_bytes_padded(value: &BigUint, width: usize) -> Option<Vec<u8>> {
+    let mut bytes = biguint_to_le_bytes(value);
+    if bytes.len() > width {
+        return None;
+    }
+    bytes.resize(width, 0);
+    Some(bytes)
+}
+
+/// Parses canonical little-endian bytes, see `biguint_to_le_bytes()`
+pub fn biguint_from_le_bytes(bytes: &[u8]) -> Result<BigUint, String> {
+    if bytes.last() == Some(&0) {
+        return Err(format!(
+            "{} byte integer isn't canonical, it has trailing zero bytes",
+            bytes.len()
+        ));
+    }
+    Ok(BigUint::from_bytes_le(bytes))
+}
+
+/// Parses little-endian bytes that are padded to exactly `width` bytes, see
+/// `biguint_to_le_bytes_padded()`
+pub fn biguint_from_le_bytes_padded(bytes: &[u8], width: usize) -> Result<BigUint, String> {
+    if bytes.len() != width {
+        return Err(format!(
+            "integer has {} bytes, expected {}",
+            bytes.len(),
+            width
+        ));
+    }
+    Ok(BigUint::from_bytes_le(bytes))
+}
+
+/// Parses canonical little-endian bytes passed by the host
+///
+/// `ptr` must point to `len` bytes, it may only be null when `len` is 0.
+pub unsafe fn biguint_from_raw_le(ptr: *const u8, len: usize) -> Result<BigUint, String> {
+    if ptr.is_null() {
+        return if len == 0 {
+            Ok(BigUint::default())
+        } else {
+            Err("integer bytes must not be null".to_string())
+        };
+    }
+    biguint_from_le_bytes(slice::from_raw_parts(ptr, len))
+}
@@ -0,0 +1,133 @@
+//! Results written into buffers allocated by the caller.
+//!
+//! Instead of returning a response that owns the bytes (a copy on the host side and a free call),
+//! a function can write them into the caller's buffer. The convention is two-phase: the caller
+//! first passes a null buffer of length 0 and gets `FIL_BUFFER_TOO_SMALL` and the required
+//! length, then calls again with a buffer of that length.
+//!
+//! ```
+//! use ffi_toolkit::{write_to_caller_buffer, FfiBufferStatus};
+//!
+//! #[no_mangle]
+//! pub unsafe extern "C" fn fil_proof_bytes(
+//!     buffer: *mut u8,
+//!     buffer_len: usize,
+//!     required_len: *mut usize,
+//! ) -> FfiBufferStatus {
+//!     let proof = [7u8; 192];
+//!     write_to_caller_buffer(&proof, buffer, buffer_len, required_len)
+//! }
+//!
+//! let mut len = 0;
+//! let status = unsafe { fil_proof_bytes(std::ptr::null_mut(), 0, &mut len) };
+//! assert_eq!((status, len), (FfiBufferStatus::TooSmall, 192));
+//!
+//! let mut buffer = vec![0; len];
+//! let status = unsafe { fil_proof_bytes(buffer.as_mut_ptr(), buffer.len(), &mut len) };
+//! assert_eq!((status, len), (FfiBufferStatus::Written, 192));
+//! assert_eq!(buffer, [7u8; 192]);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::ptr;
+
+use crate::{FCPResponseStatus, IntoFFIError};
+
+status_code_enum! {
+    #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+    pub enum FfiBufferStatus {
+        Written = 0 => FIL_BUFFER_WRITTEN,
+        // The required length was reported, nothing was written
+        TooSmall = 1 => FIL_BUFFER_TOO_SMALL,
+        // The buffer is null but its length isn't 0
+        NullBuffer = 2 => FIL_BUFFER_NULL,
+    }
+}
+
+/// Why nothing was written into the caller's buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferError {
+    TooSmall { required: usize },
+    NullBuffer,
+}
+
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BufferError::TooSmall { required } => {
+                write!(
+                    f,
+                    "the buffer is too small, {} bytes are required",
+                    required
+                )
+            }
+            BufferError::NullBuffer => write!(f, "the buffer is null but its length isn't 0"),
+        }
+    }
+}
+
+impl Error for BufferError {}
+
+impl IntoFFIError for BufferError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl BufferError {
+    pub fn status(&self) -> FfiBufferStatus {
+        match self {
+            BufferError::TooSmall { .. } => FfiBufferStatus::TooSmall,
+            BufferError::NullBuffer => FfiBufferStatus::NullBuffer,
+        }
+    }
+}
+
+/// Copies `src` into the caller's buffer, returns the number of bytes written
+///
+/// Nothing is written if `src` doesn't fit, the error tells the required length then. A null
+/// `dst_ptr` is only accepted with a `dst_len` of 0, i.e. for querying the length. A non-null
+/// `dst_ptr` must be valid for writes of `dst_len` bytes and not overlap `src`.
+pub unsafe fn write_to_buffer(
+    src: &[u8],
+    dst_ptr: *mut u8,
+    dst_len: usize,
+) -> Result<usize, BufferError> {
+    if dst_ptr.is_null() && dst_len != 0 {
+        return Err(BufferError::NullBuffer);
+    }
+    if src.len() > dst_len {
+        return Err(BufferError::TooSmall {
+            required: src.len(),
+        });
+    }
+    if !src.is_empty() {
+        ptr::copy_nonoverlapping(src.as_ptr(), dst_ptr, src.len());
+    }
+    Ok(src.len())
+}
+
+/// `write_to_buffer()` for exported functions, following the two-phase convention
+///
+/// The number of bytes written, or the required length if the buffer is too small, is stored
+/// in `*required_len` unless it is null.
+pub unsafe fn write_to_caller_buffer(
+    src: &[u8],
+    dst_ptr: *mut u8,
+    dst_len: usize,
+    required_len: *mut usize,
+) -> FfiBufferStatus {
+    let result = write_to_buffer(src, dst_ptr, dst_len);
+    if !required_len.is_null() {
+        *required_len = src.len();
+    }
+    match result {
+        Ok(_) => FfiBufferStatus::Written,
+        Err(err) => err.status(),
+    }
+}
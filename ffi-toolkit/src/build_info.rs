@@ -0,0 +1,183 @@
+//! What the linked library was built with, so that a host can tell which toolkit features and
+//! versions it got, e.g. before relying on poisoning in a debugging session.
+
+use std::ffi::CStr;
+use std::fmt;
+
+use crate::alloc;
+
+/// Allocation tracking for tests (the `testing` feature)
+pub const FIL_FEATURE_TRACKING: u64 = 1 << 0;
+/// Freed memory is poisoned (`poison`)
+pub const FIL_FEATURE_POISON: u64 = 1 << 1;
+/// Frees of pointers the toolkit didn't hand out are refused (`provenance`)
+pub const FIL_FEATURE_PROVENANCE: u64 = 1 << 2;
+pub const FIL_FEATURE_FAILPOINTS: u64 = 1 << 3;
+pub const FIL_FEATURE_SANITIZER: u64 = 1 << 4;
+/// `init()` and `shutdown()` run when the library is loaded and unloaded (`ctor`)
+pub const FIL_FEATURE_CTOR: u64 = 1 << 5;
+pub const FIL_FEATURE_LOG: u64 = 1 << 6;
+pub const FIL_FEATURE_TRACING: u64 = 1 << 7;
+pub const FIL_FEATURE_CBOR: u64 = 1 << 8;
+pub const FIL_FEATURE_JSON: u64 = 1 << 9;
+pub const FIL_FEATURE_ZSTD: u64 = 1 << 10;
+pub const FIL_FEATURE_FUZZ_SUPPORT: u64 = 1 << 11;
+
+// The bits with the names of their Cargo features
+const FEATURES: &[(u64, &str, bool)] = &[
+    (FIL_FEATURE_TRACKING, "testing", cfg!(feature = "testing")),
+    (FIL_FEATURE_POISON, "poison", cfg!(feature = "poison")),
+    (
+        FIL_FEATURE_PROVENANCE,
+        "provenance",
+        cfg!(feature = "provenance"),
+    ),
+    (
+        FIL_FEATURE_FAILPOINTS,
+        "failpoints",
+        cfg!(feature = "failpoints"),
+    ),
+    (
+        FIL_FEATURE_SANITIZER,
+        "sanitizer",
+        cfg!(feature = "sanitizer"),
+    ),
+    (FIL_FEATURE_CTOR, "ctor", cfg!(feature = "ctor")),
+    (FIL_FEATURE_LOG, "log", cfg!(feature = "log")),
+    (FIL_FEATURE_TRACING, "tracing", cfg!(feature = "tracing")),
+    (FIL_FEATURE_CBOR, "cbor", cfg!(feature = "cbor")),
+    (FIL_FEATURE_JSON, "json", cfg!(feature = "json")),
+    (FIL_FEATURE_ZSTD, "zstd", cfg!(feature = "zstd")),
+    (
+        FIL_FEATURE_FUZZ_SUPPORT,
+        "fuzz-support",
+        cfg!(feature = "fuzz-support"),
+    ),
+];
+
+const CRATE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+// Captured by the build script, "unknown" if it couldn't find out
+const GIT_COMMIT: &str = concat!(env!("FFI_TOOLKIT_GIT_COMMIT"), "\0");
+const RUSTC_VERSION: &str = concat!(env!("FFI_TOOLKIT_RUSTC_VERSION"), "\0");
+const TARGET: &str = concat!(env!("FFI_TOOLKIT_TARGET"), "\0");
+const PROFILE: &str = concat!(env!("FFI_TOOLKIT_PROFILE"), "\0");
+
+/// The build of the toolkit, see `build_info()`
+///
+/// The strings are static, the host must not free them. The task pool and the allocator hooks
+/// are part of every build, `host_allocator` tells whether one was installed. The fields are
+/// read-only, Rust code uses the accessors.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BuildInfo {
+    /// The version of the `ffi-toolkit` crate
+    crate_version: *const libc::c_char,
+    /// The abbreviated commit the toolkit was built from
+    git_commit: *const libc::c_char,
+    rustc_version: *const libc::c_char,
+    /// The target triple
+    target: *const libc::c_char,
+    /// `debug` or `release`
+    profile: *const libc::c_char,
+    /// The `FIL_FEATURE_*` bits of the features the toolkit was built with
+    features: u64,
+    debug_assertions: bool,
+    /// Whether `set_allocator()` installed the host's allocator
+    host_allocator: bool,
+}
+
+fn c_str(s: &'static str) -> *const libc::c_char {
+    s.as_ptr() as *const libc::c_char
+}
+
+// the `&str` of a string of `BuildInfo`, which are all static
+fn to_str(ptr: *const libc::c_char) -> &'static str {
+    unsafe { CStr::from_ptr(ptr) }.to_str().unwrap_or("unknown")
+}
+
+impl BuildInfo {
+    pub fn crate_version(&self) -> &'static str {
+        to_str(self.crate_version)
+    }
+
+    pub fn git_commit(&self) -> &'static str {
+        to_str(self.git_commit)
+    }
+
+    pub fn rustc_version(&self) -> &'static str {
+        to_str(self.rustc_version)
+    }
+
+    pub fn target(&self) -> &'static str {
+        to_str(self.target)
+    }
+
+    pub fn profile(&self) -> &'static str {
+        to_str(self.profile)
+    }
+
+    /// The `FIL_FEATURE_*` bits
+    pub fn features(&self) -> u64 {
+        self.features
+    }
+
+    pub fn debug_assertions(&self) -> bool {
+        self.debug_assertions
+    }
+
+    pub fn host_allocator(&self) -> bool {
+        self.host_allocator
+    }
+
+    /// Whether the toolkit was built with the `FIL_FEATURE_*` bit `feature`
+    pub fn has_feature(&self, feature: u64) -> bool {
+        self.features & feature == feature
+    }
+
+    /// The Cargo features among the `FIL_FEATURE_*` ones the toolkit was built with
+    pub fn feature_names(&self) -> Vec<&'static str> {
+        FEATURES
+            .iter()
+            .filter(|&&(bit, _, _)| self.has_feature(bit))
+            .map(|&(_, name, _)| name)
+            .collect()
+    }
+}
+
+impl fmt::Debug for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BuildInfo")
+            .field("crate_version", &self.crate_version())
+            .field("git_commit", &self.git_commit())
+            .field("rustc_version", &self.rustc_version())
+            .field("target", &self.target())
+            .field("profile", &self.profile())
+            .field("features", &self.feature_names())
+            .field("debug_assertions", &self.debug_assertions)
+            .field("host_allocator", &self.host_allocator)
+            .finish()
+    }
+}
+
+/// The build of the linked toolkit
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        crate_version: c_str(CRATE_VERSION),
+        git_commit: c_str(GIT_COMMIT),
+        rustc_version: c_str(RUSTC_VERSION),
+        target: c_str(TARGET),
+        profile: c_str(PROFILE),
+        features: FEATURES
+            .iter()
+            .filter(|&&(_, _, enabled)| enabled)
+            .fold(0, |features, &(bit, _, _)| features | bit),
+        debug_assertions: cfg!(debug_assertions),
+        host_allocator: alloc::has_host_allocator(),
+    }
+}
+
+/// See `build_info()`
+#[no_mangle]
+pub extern "C" fn fil_toolkit_build_info() -> BuildInfo {
+    build_info()
+}
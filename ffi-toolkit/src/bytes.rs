@@ -0,0 +1,86 @@
+use alloc_crate::vec::Vec;
+use core::mem::ManuallyDrop;
+use core::slice;
+
+use crate::c_types::size_t;
+
+/// A byte array, which owns its memory
+///
+/// Dropping it frees the bytes, so it can be a field of a `DropStructMacro` response, the derived
+/// `Drop` leaves it to its own. C frees a `FfiBytes` it owns with `fil_free_bytes()`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiBytes {
+    pub ptr: *const u8,
+    pub len: size_t,
+    // The capacity of the `Vec` the bytes came from, C must not change it
+    pub cap: size_t,
+}
+
+impl FfiBytes {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self::from_vec(bytes)
+    }
+
+    /// Takes over the memory of the vector, without copying or reallocating it
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = ManuallyDrop::new(bytes);
+        FfiBytes {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            cap: bytes.capacity(),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+
+    /// Hands the memory back as the vector it came from
+    pub fn into_vec(self) -> Vec<u8> {
+        let bytes = ManuallyDrop::new(self);
+        unsafe { Vec::from_raw_parts(bytes.ptr as *mut u8, bytes.len, bytes.cap) }
+    }
+}
+
+impl Default for FfiBytes {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl From<Vec<u8>> for FfiBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from_vec(bytes)
+    }
+}
+
+impl Drop for FfiBytes {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Vec::from_raw_parts(self.ptr as *mut u8, self.len, self.cap));
+        }
+    }
+}
+
+/// Frees a `FfiBytes` that was handed out to the caller
+#[no_mangle]
+pub extern "C" fn fil_free_bytes(bytes: FfiBytes) {
+    drop(bytes);
+}
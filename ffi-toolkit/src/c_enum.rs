@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{FCPResponseStatus, IntoFFIError};
+
+/// An integer passed by C that is no variant of the enum it stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDiscriminant {
+    pub type_name: &'static str,
+    pub value: u64,
+}
+
+impl fmt::Display for InvalidDiscriminant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is not a valid `{}`", self.value, self.type_name)
+    }
+}
+
+impl Error for InvalidDiscriminant {}
+
+impl IntoFFIError for InvalidDiscriminant {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Declares a `#[repr(C)]` enum with a checked `TryFrom<u64>` for integers passed by C
+///
+/// Exported functions take the integer rather than the enum itself, as an out-of-range value in
+/// an enum is undefined behavior before any Rust code gets to look at it. The conversion fails
+/// with an `InvalidDiscriminant`, which is reported as a caller error by
+/// `catch_panic_result()`.
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use ffi_toolkit::try_from_c_enum;
+///
+/// try_from_c_enum! {
+///     #[derive(PartialEq, Debug, Copy, Clone)]
+///     pub enum RegisteredSealProof {
+///         StackedDrg2KiBV1 = 0,
+///         StackedDrg32GiBV1 = 3,
+///     }
+/// }
+///
+/// assert_eq!(
+///     RegisteredSealProof::try_from(3),
+///     Ok(RegisteredSealProof::StackedDrg32GiBV1)
+/// );
+/// let err = RegisteredSealProof::try_from(1).unwrap_err();
+/// assert_eq!(err.to_string(), "1 is not a valid `RegisteredSealProof`");
+/// ```
+#[macro_export]
+macro_rules! try_from_c_enum {
+    {
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident $(= $value:expr)?,
+            )*
+        }
+    } => {
+        #[repr(C)]
+        $(#[$meta])*
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant $(= $value)?,
+            )*
+        }
+
+        impl ::std::convert::TryFrom<u64> for $name {
+            type Error = $crate::InvalidDiscriminant;
+
+            fn try_from(value: u64) -> ::std::result::Result<Self, Self::Error> {
+                $(
+                    if value == $name::$variant as u64 {
+                        return Ok($name::$variant);
+                    }
+                )*
+                Err($crate::InvalidDiscriminant {
+                    type_name: stringify!($name),
+                    value,
+                })
+            }
+        }
+    };
+}
@@ -0,0 +1,154 @@
+//! C sources checking the layouts of response structs from the C side.
+//!
+//! `assert_ffi_layout!` checks the layouts the Rust side expects, the generated source checks
+//! the ones the C compiler sees in the header the bindings are generated from. It asserts the
+//! size, alignment and field offsets of every registered response with `_Static_assert`, so
+//! that merely compiling it fails on a mismatch, and `fil_harness_run()` destroys a null
+//! pointer and, for responses with a call registered, a response returned by the library:
+//!
+//! ```ignore
+//! let source = CHarness::new("filcrypto.h")
+//!     .response::<SealResponse>()
+//!     .round_trip::<VerifyResponse>("fil_verify_seal(NULL, NULL, 0)")
+//!     .with_main()
+//!     .source();
+//! ```
+//!
+//! The layouts are the ones of the target the harness is generated on, so it's meant for
+//! integration tests (or build scripts of crates that don't cross-compile), which then compile
+//! it in C11 mode and link it against the library.
+
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use crate::{write_file_atomic, ResponseLayout};
+
+struct Response {
+    name: &'static str,
+    size: usize,
+    align: usize,
+    fields: &'static [(&'static str, usize)],
+    destroy: Option<&'static str>,
+    // A C expression returning a response, for the round trip
+    call: Option<String>,
+}
+
+/// A C source with layout checks and destroy calls of response structs
+pub struct CHarness {
+    header: String,
+    responses: Vec<Response>,
+    main: bool,
+}
+
+impl CHarness {
+    /// A harness including `header`, the one declaring the responses
+    pub fn new<S: Into<String>>(header: S) -> Self {
+        CHarness {
+            header: header.into(),
+            responses: Vec::new(),
+            main: false,
+        }
+    }
+
+    /// Checks the layout of `T`, and that its destructor ignores null
+    pub fn response<T: ResponseLayout>(mut self) -> Self {
+        self.responses.push(Response {
+            name: T::NAME,
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+            fields: T::FIELDS,
+            destroy: T::DESTROY,
+            call: None,
+        });
+        self
+    }
+
+    /// Like `response()`, and destroys the response the C expression `call` returns
+    ///
+    /// The call is usually an exported function with invalid arguments, which returns an error
+    /// response without doing any work. `T` needs a generated destructor.
+    pub fn round_trip<T: ResponseLayout>(mut self, call: &str) -> Self {
+        assert!(
+            T::DESTROY.is_some(),
+            "`{}` has no generated destructor to destroy the response with",
+            T::NAME
+        );
+        self = self.response::<T>();
+        self.responses.last_mut().unwrap().call = Some(call.to_string());
+        self
+    }
+
+    /// Adds a `main()` that returns whether `fil_harness_run()` failed
+    pub fn with_main(mut self) -> Self {
+        self.main = true;
+        self
+    }
+
+    /// The C source
+    pub fn source(&self) -> String {
+        let mut source = format!(
+            "/* Generated by ffi-toolkit {}, do not edit. */\n\
+             \n\
+             #include <stddef.h>\n\
+             #include <stdio.h>\n\
+             #include \"{}\"\n\
+             \n",
+            env!("CARGO_PKG_VERSION"),
+            self.header
+        );
+        for response in &self.responses {
+            let name = response.name;
+            source += &format!(
+                "_Static_assert(sizeof({0}) == {1}, \"the size of {0} isn't {1}\");\n\
+                 _Static_assert(_Alignof({0}) == {2}, \"the alignment of {0} isn't {2}\");\n",
+                name, response.size, response.align
+            );
+            for (field, offset) in response.fields {
+                source += &format!(
+                    "_Static_assert(offsetof({0}, {1}) == {2}, \
+                     \"the offset of {0}.{1} isn't {2}\");\n",
+                    name, field, offset
+                );
+            }
+            source += "\n";
+        }
+
+        source += "/* Returns the number of failed checks */\nint fil_harness_run(void) {\n  \
+                   int failures = 0;\n";
+        for response in &self.responses {
+            let destroy = match response.destroy {
+                Some(destroy) => destroy,
+                None => continue,
+            };
+            source += &format!("  {}(NULL);\n", destroy);
+            if let Some(call) = &response.call {
+                source += &format!(
+                    "  {{\n    \
+                     {name} *response = {call};\n    \
+                     if (response == NULL) {{\n      \
+                     fprintf(stderr, \"{escaped} returned NULL\\n\");\n      \
+                     failures++;\n    \
+                     }} else {{\n      \
+                     {destroy}(response);\n    \
+                     }}\n  \
+                     }}\n",
+                    name = response.name,
+                    call = call,
+                    escaped = call.replace('\\', "\\\\").replace('"', "\\\""),
+                    destroy = destroy
+                );
+            }
+        }
+        source += "  return failures;\n}\n";
+        if self.main {
+            source += "\nint main(void) {\n  return fil_harness_run() != 0;\n}\n";
+        }
+        source
+    }
+
+    /// Writes `source()` to `path`
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_file_atomic(path.as_ref(), self.source().as_bytes())
+    }
+}
@@ -0,0 +1,11 @@
+//! The C types of the toolkit's signatures, which don't need libc.
+//!
+//! libc has no types for targets without a C library, e.g. `wasm32-unknown-unknown`, so the
+//! core of the toolkit (and responses that should build there) use these. Where libc has them
+//! they are the same types, e.g. `c_types::c_char` is `libc::c_char`, so both can be mixed.
+
+pub use core::ffi::{c_char, c_int, c_void};
+
+/// `size_t`, which Rust takes to be `usize` on all targets
+#[allow(non_camel_case_types)]
+pub type size_t = usize;
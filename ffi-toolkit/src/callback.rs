@@ -0,0 +1,125 @@
+//! Host callbacks: a C function pointer together with its `user_data`.
+//!
+//! `CCallback<Args, Ret>` bundles an `extern "C" fn(args..., user_data) -> Ret` with the
+//! `user_data` pointer, `Args` being a tuple of the arguments without `user_data` (as with
+//! `testing::MockCallback`). The host's promises are asserted once, by the unsafe constructors,
+//! afterwards the callback is called from safe Rust. The function pointer may be null, calls are
+//! skipped then. Functions defined in Rust, e.g. in tests, are passed as `Some(function as _)`.
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{AssertSend, AssertSync};
+
+/// The argument tuples of callbacks, implemented for up to six arguments
+pub trait CallbackArgs<Ret>: Sized {
+    /// `extern "C" fn(args..., user_data: *mut c_void) -> Ret`
+    type Fn: Copy + fmt::Debug;
+
+    fn invoke(function: Self::Fn, args: Self, user_data: *mut libc::c_void) -> Ret;
+}
+
+macro_rules! callback_args {
+    ($($arg:ident),*) => {
+        impl<$($arg,)* Ret> CallbackArgs<Ret> for ($($arg,)*) {
+            type Fn = extern "C" fn($($arg,)* *mut libc::c_void) -> Ret;
+
+            #[allow(non_snake_case)]
+            fn invoke(function: Self::Fn, ($($arg,)*): Self, user_data: *mut libc::c_void) -> Ret {
+                function($($arg,)* user_data)
+            }
+        }
+    };
+}
+
+callback_args!();
+callback_args!(A);
+callback_args!(A, B);
+callback_args!(A, B, C);
+callback_args!(A, B, C, D);
+callback_args!(A, B, C, D, E);
+callback_args!(A, B, C, D, E, F);
+
+/// A host callback, only usable on the thread it was created on, see `assume_send()`
+pub struct CCallback<Args: CallbackArgs<Ret>, Ret> {
+    function: Option<Args::Fn>,
+    user_data: *mut libc::c_void,
+}
+
+impl<Args: CallbackArgs<Ret>, Ret> CCallback<Args, Ret> {
+    /// The host must guarantee that `function` can be called with `user_data` for as long as the
+    /// callback is used
+    pub unsafe fn new(function: Option<Args::Fn>, user_data: *mut libc::c_void) -> Self {
+        CCallback {
+            function,
+            user_data,
+        }
+    }
+
+    /// Whether the function pointer is null
+    pub fn is_null(&self) -> bool {
+        self.function.is_none()
+    }
+
+    pub fn user_data(&self) -> *mut libc::c_void {
+        self.user_data
+    }
+
+    /// Calls the host, `None` if the function pointer is null
+    pub fn call(&self, args: Args) -> Option<Ret> {
+        self.function
+            .map(|function| Args::invoke(function, args, self.user_data))
+    }
+
+    /// Makes the callback usable from any thread
+    ///
+    /// The host must guarantee that `function` can be called with `user_data` from any thread,
+    /// also from several at once.
+    pub unsafe fn assume_send(self) -> SendCCallback<Args, Ret> {
+        SendCCallback(AssertSync::new(AssertSend::new(self)))
+    }
+}
+
+impl<Args: CallbackArgs<Ret>, Ret> Clone for CCallback<Args, Ret> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Args: CallbackArgs<Ret>, Ret> Copy for CCallback<Args, Ret> {}
+
+impl<Args: CallbackArgs<Ret>, Ret> fmt::Debug for CCallback<Args, Ret> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CCallback")
+            .field("function", &self.function)
+            .field("user_data", &self.user_data)
+            .finish()
+    }
+}
+
+/// A `CCallback` that can be used from any thread, see `CCallback::assume_send()`
+pub struct SendCCallback<Args: CallbackArgs<Ret>, Ret>(
+    AssertSync<AssertSend<CCallback<Args, Ret>>>,
+);
+
+impl<Args: CallbackArgs<Ret>, Ret> Clone for SendCCallback<Args, Ret> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Args: CallbackArgs<Ret>, Ret> Copy for SendCCallback<Args, Ret> {}
+
+impl<Args: CallbackArgs<Ret>, Ret> fmt::Debug for SendCCallback<Args, Ret> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SendCCallback").field(&**self).finish()
+    }
+}
+
+impl<Args: CallbackArgs<Ret>, Ret> Deref for SendCCallback<Args, Ret> {
+    type Target = CCallback<Args, Ret>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.get().get()
+    }
+}
@@ -0,0 +1,114 @@
+//! Cancellation of long-running operations by the host.
+//!
+//! The host creates a token with `fil_cancel_token_new()`, passes it to the operation and calls
+//! `fil_cancel_token_cancel()` to abort it, e.g. when a sealing job is no longer needed. The
+//! operation checks the token between its steps with `CancellationToken::is_cancelled()` or
+//! `check()` and returns early. Tokens are shared, both sides hold a reference until they free
+//! theirs.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::{arc_from_shared, release_shared, shared_raw_ptr, FCPResponseStatus, IntoFFIError};
+
+/// A cancellation token as seen by C
+#[repr(C)]
+pub struct FfiCancelToken {
+    _private: [u8; 0],
+}
+
+/// The Rust side of a cancellation token, clones refer to the same token
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The error of an operation that was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the operation was cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+// The caller asked for it
+impl IntoFFIError for Cancelled {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// `Err(Cancelled)` once the token is cancelled, for use with `?`
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Hands a reference to the token over to C, which frees it with `fil_cancel_token_free()`
+    pub fn into_ffi(self) -> *const FfiCancelToken {
+        shared_raw_ptr(self.cancelled) as *const FfiCancelToken
+    }
+
+    /// The token behind `token`, which C keeps its reference to
+    ///
+    /// A null `token` stands for an operation that can't be cancelled, a token that never is
+    /// cancelled is returned for it.
+    pub unsafe fn from_ffi(token: *const FfiCancelToken) -> Self {
+        match arc_from_shared(token as *const AtomicBool) {
+            Some(cancelled) => CancellationToken { cancelled },
+            None => Self::new(),
+        }
+    }
+}
+
+/// A new token that isn't cancelled, free it with `fil_cancel_token_free()`
+#[no_mangle]
+pub extern "C" fn fil_cancel_token_new() -> *const FfiCancelToken {
+    CancellationToken::new().into_ffi()
+}
+
+/// Cancels the operations using `token`, null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn fil_cancel_token_cancel(token: *const FfiCancelToken) {
+    if !token.is_null() {
+        CancellationToken::from_ffi(token).cancel();
+    }
+}
+
+/// Whether `token` was cancelled, false for null
+#[no_mangle]
+pub unsafe extern "C" fn fil_cancel_token_is_cancelled(token: *const FfiCancelToken) -> bool {
+    CancellationToken::from_ffi(token).is_cancelled()
+}
+
+/// Frees C's reference to `token`, operations using it can still check it, null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn fil_cancel_token_free(token: *const FfiCancelToken) {
+    release_shared(token as *const AtomicBool);
+}
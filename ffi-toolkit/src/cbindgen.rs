@@ -0,0 +1,177 @@
+//! The C declarations of the types the toolkit provides, for headers generated with cbindgen.
+//!
+//! cbindgen only sees a consumer's own crate, so it either skips toolkit types used in its
+//! responses or each consumer declares them on its own. Instead, build scripts write the
+//! canonical declarations with `emit_toolkit_header()`, and the consumer's `cbindgen.toml`
+//! includes that header and excludes the types declared in it:
+//!
+//! ```toml
+//! includes = ["ffi_toolkit.h"]
+//!
+//! [export]
+//! exclude = ["FCPResponseStatus", "FfiBytes", "FfiString", "StringRef", "FfiStringArray",
+//!            "FfiErrorChain", "FfiDuration", "FfiTimestamp", "FfiInstant", "FfiU128",
+//!            "FfiI128", "FfiVTableHeader", "FfiDebugId", "SecretBytes"]
+//! ```
+//!
+//! The types are re-exported here with the C names they are declared with, `TOOLKIT_TYPES`
+//! lists them. Their layouts are checked against the declarations at compile time.
+
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use crate::write_file_atomic;
+
+pub use crate::{
+    FCPResponseStatus, FfiBytes, FfiDebugId, FfiDuration, FfiErrorChain, FfiI128, FfiInstant,
+    FfiString, FfiStringArray, FfiTimestamp, FfiU128, FfiVTableHeader, SecretBytes, StringRef,
+};
+
+/// The file name `emit_toolkit_header()` writes
+pub const TOOLKIT_HEADER_NAME: &str = "ffi_toolkit.h";
+
+/// The C names of the types declared in the toolkit header, for `[export] exclude`
+pub const TOOLKIT_TYPES: &[&str] = &[
+    "FCPResponseStatus",
+    "FfiBytes",
+    "FfiString",
+    "StringRef",
+    "FfiStringArray",
+    "FfiErrorChain",
+    "FfiDuration",
+    "FfiTimestamp",
+    "FfiInstant",
+    "FfiU128",
+    "FfiI128",
+    "FfiVTableHeader",
+    "FfiDebugId",
+    "SecretBytes",
+];
+
+// The declarations below assume these layouts, e.g. `size_t` for `usize`
+const _: () = {
+    let word = mem::size_of::<usize>();
+    assert!(mem::size_of::<FCPResponseStatus>() == mem::size_of::<libc::c_int>());
+    assert!(mem::size_of::<FfiBytes>() == 3 * word);
+    assert!(mem::size_of::<FfiString>() == 3 * word);
+    assert!(mem::size_of::<StringRef>() == 2 * word);
+    assert!(mem::size_of::<FfiStringArray>() == 2 * word);
+    assert!(mem::size_of::<FfiErrorChain>() == 4 * word);
+    assert!(mem::size_of::<FfiDuration>() == 16);
+    assert!(mem::size_of::<FfiTimestamp>() == 16);
+    assert!(mem::size_of::<FfiInstant>() == 8);
+    assert!(mem::size_of::<FfiU128>() == 16);
+    assert!(mem::size_of::<FfiI128>() == 16);
+    assert!(mem::size_of::<FfiVTableHeader>() == 2 * word);
+    assert!(mem::size_of::<FfiDebugId>() == 8);
+    assert!(mem::size_of::<SecretBytes>() == 3 * word);
+};
+
+const STRUCTS: &str = "\
+typedef struct FfiBytes {
+  const uint8_t *ptr;
+  size_t len;
+  size_t cap;
+} FfiBytes;
+
+typedef struct FfiString {
+  const uint8_t *ptr;
+  size_t len;
+  size_t cap;
+} FfiString;
+
+typedef struct StringRef {
+  const uint8_t *ptr;
+  size_t len;
+} StringRef;
+
+typedef struct FfiStringArray {
+  const char *const *ptr;
+  size_t len;
+} FfiStringArray;
+
+typedef struct FfiErrorChain {
+  FfiStringArray messages;
+  const FCPResponseStatus *codes_ptr;
+  size_t codes_len;
+} FfiErrorChain;
+
+typedef struct FfiDuration {
+  uint64_t secs;
+  uint32_t nanos;
+} FfiDuration;
+
+typedef struct FfiTimestamp {
+  int64_t secs;
+  uint32_t nanos;
+} FfiTimestamp;
+
+typedef struct FfiInstant {
+  uint64_t monotonic_ns;
+} FfiInstant;
+
+typedef struct FfiU128 {
+  uint64_t hi;
+  uint64_t lo;
+} FfiU128;
+
+/* The two's complement of the value */
+typedef struct FfiI128 {
+  uint64_t hi;
+  uint64_t lo;
+} FfiI128;
+
+typedef struct FfiVTableHeader {
+  uint32_t version;
+  size_t size;
+} FfiVTableHeader;
+
+/* The header of responses with a `debug_id` */
+typedef struct FfiDebugId {
+  uint32_t magic;
+  uint32_t sequence;
+} FfiDebugId;
+
+/* Zeroed before it is freed, with fil_free_secret_bytes() */
+typedef struct SecretBytes {
+  const uint8_t *ptr;
+  size_t len;
+  size_t cap;
+} SecretBytes;
+";
+
+/// The canonical C declarations of the toolkit types, as a complete header
+pub fn toolkit_header() -> String {
+    format!(
+        "/* Generated by ffi-toolkit {}, do not edit. */\n\
+         \n\
+         #ifndef FFI_TOOLKIT_H\n\
+         #define FFI_TOOLKIT_H\n\
+         \n\
+         #include <stdbool.h>\n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         \n\
+         {}\n\
+         {}\n\
+         #endif /* FFI_TOOLKIT_H */\n",
+        env!("CARGO_PKG_VERSION"),
+        FCPResponseStatus::c_header(),
+        STRUCTS
+    )
+}
+
+/// Writes `toolkit_header()` to `ffi_toolkit.h` in `out_dir` and returns its path
+///
+/// Meant for build scripts, usually with `OUT_DIR` or the directory of the generated header.
+/// An unchanged header isn't written again, so it doesn't trigger rebuilds of C code.
+pub fn emit_toolkit_header<P: AsRef<Path>>(out_dir: P) -> io::Result<PathBuf> {
+    let path = out_dir.as_ref().join(TOOLKIT_HEADER_NAME);
+    let header = toolkit_header();
+    match std::fs::read(&path) {
+        Ok(existing) if existing == header.as_bytes() => {}
+        _ => write_file_atomic(&path, header.as_bytes())?,
+    }
+    Ok(path)
+}
@@ -0,0 +1,95 @@
+//! Complex nested structures passed across the boundary as CBOR, instead of a new `repr(C)`
+//! type for each of them.
+
+use std::fmt;
+use std::slice;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    error_response, error_response_with_payload, CodeAndMessage, FCPResponseStatus, FfiBytes,
+    StatusCode,
+};
+
+/// A payload that couldn't be encoded or decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CborError {
+    message: String,
+}
+
+impl CborError {
+    fn new<S: Into<String>>(message: S) -> Self {
+        CborError {
+            message: message.into(),
+        }
+    }
+
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<T: Default + CodeAndMessage>(&self, name: &str) -> *mut T {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid CBOR payload: {}", self.message)
+    }
+}
+
+/// Encodes `value` as CBOR
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<FfiBytes, CborError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|err| CborError::new(err.to_string()))?;
+    Ok(FfiBytes::new(bytes))
+}
+
+/// An error response with `payload` encoded as CBOR as the details of the error
+///
+/// The payload is left out if it can't be encoded, the message says why.
+pub fn error_response_with_cbor<T, S, C, P>(code: C, message: S, payload: &P) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    S: Into<String>,
+    C: StatusCode,
+    P: Serialize,
+{
+    match to_cbor(payload) {
+        Ok(payload) => error_response_with_payload(code, message, payload),
+        Err(err) => error_response(code, format!("{} ({})", message.into(), err)),
+    }
+}
+
+/// Decodes a `T` from CBOR, trailing bytes are an error
+pub fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CborError> {
+    let mut reader = bytes;
+    let value = ciborium::from_reader(&mut reader).map_err(|err| match err {
+        ciborium::de::Error::Io(_) => CborError::new("unexpected end of input"),
+        ciborium::de::Error::Syntax(offset) => {
+            CborError::new(format!("syntax error at offset {}", offset))
+        }
+        ciborium::de::Error::Semantic(offset, message) => match offset {
+            Some(offset) => CborError::new(format!("{} at offset {}", message, offset)),
+            None => CborError::new(message),
+        },
+        ciborium::de::Error::RecursionLimitExceeded => CborError::new("nested too deeply"),
+    })?;
+    if !reader.is_empty() {
+        return Err(CborError::new(format!("{} trailing bytes", reader.len())));
+    }
+    Ok(value)
+}
+
+/// Decodes a `T` from the `len` bytes of CBOR at `ptr`
+pub unsafe fn from_cbor_raw<T: DeserializeOwned>(
+    ptr: *const u8,
+    len: usize,
+) -> Result<T, CborError> {
+    if ptr.is_null() {
+        return Err(CborError::new("null pointer"));
+    }
+    from_cbor(slice::from_raw_parts(ptr, len))
+}
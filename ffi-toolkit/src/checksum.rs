@@ -0,0 +1,143 @@
+//! Byte buffers framed with their length and a CRC-32C checksum, so truncation or corruption
+//! on the host side is detected when the buffer is received instead of as a failing proof.
+//!
+//! A frame is the payload length as a little-endian `u64`, the CRC-32C of the payload as a
+//! little-endian `u32` and then the payload.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::slice;
+
+use crate::{error_response, CodeAndMessage, FCPResponseStatus, FfiBytes};
+
+/// The length of the header in front of the payload
+pub const FRAME_HEADER_LEN: usize = 12;
+
+// The reflected Castagnoli polynomial
+const CRC32C_POLYNOMIAL: u32 = 0x82f6_3b78;
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32C_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// The CRC-32C (Castagnoli) checksum of `bytes`
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32C_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumError {
+    Null,
+    /// The frame is shorter or longer than its header says
+    LengthMismatch {
+        expected: u64,
+        actual: usize,
+    },
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl ChecksumError {
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<T: Default + CodeAndMessage>(&self, name: &str) -> *mut T {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChecksumError::Null => write!(f, "null frame"),
+            ChecksumError::LengthMismatch { expected, actual } => write!(
+                f,
+                "frame has a payload of {} bytes, the header says {}",
+                actual, expected
+            ),
+            ChecksumError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "frame has checksum {:08x}, the header says {:08x}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+/// Frames `payload` with its length and checksum
+pub fn frame_with_checksum(payload: &[u8]) -> FfiBytes {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    frame.extend_from_slice(&crc32c(payload).to_le_bytes());
+    frame.extend_from_slice(payload);
+    FfiBytes::new(frame)
+}
+
+/// The payload of a frame whose length and checksum were verified
+#[derive(Debug, Copy, Clone)]
+pub struct ChecksummedFrame<'a> {
+    payload: &'a [u8],
+}
+
+impl<'a> ChecksummedFrame<'a> {
+    pub fn new(frame: &'a [u8]) -> Result<Self, ChecksumError> {
+        if frame.len() < FRAME_HEADER_LEN {
+            return Err(ChecksumError::LengthMismatch {
+                expected: FRAME_HEADER_LEN as u64,
+                actual: frame.len(),
+            });
+        }
+        let expected_len = u64::from_le_bytes(frame[..8].try_into().unwrap());
+        let expected_crc = u32::from_le_bytes(frame[8..12].try_into().unwrap());
+        let payload = &frame[FRAME_HEADER_LEN..];
+        if payload.len() as u64 != expected_len {
+            return Err(ChecksumError::LengthMismatch {
+                expected: expected_len,
+                actual: payload.len(),
+            });
+        }
+        let crc = crc32c(payload);
+        if crc != expected_crc {
+            return Err(ChecksumError::ChecksumMismatch {
+                expected: expected_crc,
+                actual: crc,
+            });
+        }
+        Ok(ChecksummedFrame { payload })
+    }
+
+    /// Verifies the frame of `len` bytes at `ptr`, which needs to stay valid for `'a`
+    pub unsafe fn from_raw(ptr: *const u8, len: usize) -> Result<Self, ChecksumError> {
+        if ptr.is_null() {
+            return Err(ChecksumError::Null);
+        }
+        Self::new(slice::from_raw_parts(ptr, len))
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+}
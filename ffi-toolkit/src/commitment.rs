@@ -0,0 +1,85 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::slice;
+
+use crate::ct::ct_eq;
+use crate::encoding;
+
+/// A 32-byte digest, e.g. `comm_r`, `comm_d` or randomness
+///
+/// Comparisons are constant-time.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct FfiCommitment(pub [u8; 32]);
+
+impl FfiCommitment {
+    pub const LEN: usize = 32;
+
+    /// Copies the digest `ptr` points to, which must be 32 readable bytes unless it's null
+    pub unsafe fn from_raw(ptr: *const u8) -> Result<Self, String> {
+        if ptr.is_null() {
+            return Err("commitment must not be null".to_string());
+        }
+        Self::from_slice(slice::from_raw_parts(ptr, Self::LEN))
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::LEN {
+            return Err(format!(
+                "commitment has {} bytes, expected {}",
+                bytes.len(),
+                Self::LEN
+            ));
+        }
+        let mut commitment = [0; 32];
+        commitment.copy_from_slice(bytes);
+        Ok(FfiCommitment(commitment))
+    }
+
+    /// Parses 64 hex digits
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let bytes = encoding::parse_hex(hex).map_err(|err| format!("invalid hex: {}", err))?;
+        Self::from_slice(&bytes)
+    }
+
+    /// Lowercase hex digits
+    pub fn to_hex(&self) -> String {
+        encoding::to_hex(&self.0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl PartialEq for FfiCommitment {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for FfiCommitment {}
+
+impl Hash for FfiCommitment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl From<[u8; 32]> for FfiCommitment {
+    fn from(bytes: [u8; 32]) -> Self {
+        FfiCommitment(bytes)
+    }
+}
+
+impl fmt::Debug for FfiCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FfiCommitment({})", self.to_hex())
+    }
+}
+
+impl fmt::Display for FfiCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
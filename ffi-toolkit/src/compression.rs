@@ -0,0 +1,89 @@
+//! zstd compression of large payloads, e.g. aggregated proofs, before they cross the boundary.
+//!
+//! A compressed payload starts with a header of the magic bytes `FILZ` and the uncompressed
+//! length as a little-endian `u64`, followed by a zstd frame. The receiver can allocate the
+//! output up front and reject payloads that would decompress to more than it's willing to hold.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+
+use crate::FfiBytes;
+
+const MAGIC: &[u8; 4] = b"FILZ";
+
+/// The length of the header in front of the zstd frame
+pub const COMPRESSION_HEADER_LEN: usize = 12;
+
+/// The compression level zstd uses by default
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug)]
+pub enum CompressionError {
+    /// The payload doesn't start with a valid header
+    InvalidHeader,
+    TooLarge {
+        len: u64,
+        max: usize,
+    },
+    /// The decompressed length differs from the one in the header
+    LengthMismatch {
+        expected: u64,
+        actual: usize,
+    },
+    Zstd(io::Error),
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompressionError::InvalidHeader => write!(f, "not a compressed payload"),
+            CompressionError::TooLarge { len, max } => write!(
+                f,
+                "payload of {} bytes exceeds the limit of {} bytes",
+                len, max
+            ),
+            CompressionError::LengthMismatch { expected, actual } => write!(
+                f,
+                "payload decompressed to {} bytes instead of {}",
+                actual, expected
+            ),
+            CompressionError::Zstd(err) => write!(f, "zstd: {}", err),
+        }
+    }
+}
+
+/// Compresses `bytes` with the zstd `level`, see `DEFAULT_COMPRESSION_LEVEL`
+pub fn compress_ffi(bytes: &[u8], level: i32) -> Result<FfiBytes, CompressionError> {
+    let compressed = zstd::bulk::compress(bytes, level).map_err(CompressionError::Zstd)?;
+    let mut payload = Vec::with_capacity(COMPRESSION_HEADER_LEN + compressed.len());
+    payload.extend_from_slice(MAGIC);
+    payload.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    payload.extend_from_slice(&compressed);
+    Ok(FfiBytes::new(payload))
+}
+
+/// The uncompressed length of the payload, from its header
+pub fn uncompressed_len(payload: &[u8]) -> Result<u64, CompressionError> {
+    if payload.len() < COMPRESSION_HEADER_LEN || &payload[..4] != MAGIC {
+        return Err(CompressionError::InvalidHeader);
+    }
+    Ok(u64::from_le_bytes(payload[4..12].try_into().unwrap()))
+}
+
+/// Decompresses a payload created by `compress_ffi()`, which must not exceed `max_len` bytes
+pub fn decompress_ffi(payload: &[u8], max_len: usize) -> Result<FfiBytes, CompressionError> {
+    let len = uncompressed_len(payload)?;
+    if len > max_len as u64 {
+        return Err(CompressionError::TooLarge { len, max: max_len });
+    }
+    let bytes = zstd::bulk::decompress(&payload[COMPRESSION_HEADER_LEN..], len as usize)
+        .map_err(CompressionError::Zstd)?;
+    if bytes.len() as u64 != len {
+        return Err(CompressionError::LengthMismatch {
+            expected: len,
+            actual: bytes.len(),
+        });
+    }
+    Ok(FfiBytes::new(bytes))
+}
@@ -0,0 +1,163 @@
+//! Limits on the number of concurrent calls of classes of exported functions, so that e.g. a
+//! host proving many sectors in parallel doesn't run out of memory.
+//!
+//! A class without a configured limit is never limited. Calls beyond the limit wait for one of
+//! the running calls to finish, `concurrency_stats()` tells how many are waiting, so that the
+//! host can apply backpressure. Unlike a rate limit, a call is never rejected.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::{c_str_to_rust_str, catch_panic_response, CodeAndMessage};
+
+#[derive(Default)]
+struct State {
+    // `None` once the limit is removed
+    limit: Option<usize>,
+    running: usize,
+    waiting: usize,
+}
+
+#[derive(Default)]
+struct Class {
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+// Classes are never removed, there may be permits and waiters holding on to them
+static CLASSES: Mutex<Option<HashMap<String, Arc<Class>>>> = Mutex::new(None);
+
+fn class(name: &str) -> Option<Arc<Class>> {
+    CLASSES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|classes| classes.get(name))
+        .cloned()
+}
+
+/// Sets the number of calls of `class` that may run at once, `None` removes the limit
+///
+/// Lowering the limit doesn't affect calls that are running already, raising it lets waiting
+/// calls proceed right away.
+pub fn configure_concurrency_limit(class: &str, limit: Option<usize>) {
+    let class = Arc::clone(
+        CLASSES
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .entry(class.to_string())
+            .or_default(),
+    );
+    class.state.lock().unwrap().limit = limit.map(|limit| limit.max(1));
+    class.released.notify_all();
+}
+
+/// A running call of a class, which lets the next one proceed when it is dropped
+#[must_use = "the call counts as running only until the permit is dropped"]
+pub struct ConcurrencyPermit {
+    // `None` for classes without a limit
+    class: Option<Arc<Class>>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if let Some(class) = self.class.take() {
+            class.state.lock().unwrap().running -= 1;
+            class.released.notify_one();
+        }
+    }
+}
+
+/// Waits until a call of `class` may run
+pub fn acquire_concurrency_permit(class: &str) -> ConcurrencyPermit {
+    let class = match self::class(class) {
+        Some(class) => class,
+        None => return ConcurrencyPermit { class: None },
+    };
+    let mut state = class.state.lock().unwrap();
+    state.waiting += 1;
+    let mut state = class
+        .released
+        .wait_while(state, |state| {
+            state.limit.is_some_and(|limit| state.running >= limit)
+        })
+        .unwrap();
+    state.waiting -= 1;
+    state.running += 1;
+    drop(state);
+    ConcurrencyPermit { class: Some(class) }
+}
+
+/// Like `acquire_concurrency_permit()`, `None` instead of waiting if the limit is reached
+pub fn try_acquire_concurrency_permit(class: &str) -> Option<ConcurrencyPermit> {
+    let class = match self::class(class) {
+        Some(class) => class,
+        None => return Some(ConcurrencyPermit { class: None }),
+    };
+    let mut state = class.state.lock().unwrap();
+    if state.limit.is_some_and(|limit| state.running >= limit) {
+        return None;
+    }
+    state.running += 1;
+    drop(state);
+    Some(ConcurrencyPermit { class: Some(class) })
+}
+
+/// Like `catch_panic_response()`, but first waits until a call of `class` may run
+pub fn catch_panic_response_bounded<F, T>(class: &str, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage,
+    F: FnOnce() -> *mut T,
+{
+    let _permit = acquire_concurrency_permit(class);
+    catch_panic_response(callback)
+}
+
+/// The calls of a class, see `concurrency_stats()`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FfiConcurrencyStats {
+    /// 0 if the class isn't limited
+    pub limit: libc::size_t,
+    pub running: libc::size_t,
+    /// Calls waiting for a running one to finish
+    pub waiting: libc::size_t,
+}
+
+/// The calls of `class`, only the ones of limited classes are counted
+pub fn concurrency_stats(class: &str) -> FfiConcurrencyStats {
+    match self::class(class) {
+        Some(class) => {
+            let state = class.state.lock().unwrap();
+            FfiConcurrencyStats {
+                limit: state.limit.unwrap_or(0),
+                running: state.running,
+                waiting: state.waiting,
+            }
+        }
+        None => FfiConcurrencyStats::default(),
+    }
+}
+
+/// Sets the limit of `class`, a `limit` of 0 removes it
+#[no_mangle]
+pub unsafe extern "C" fn fil_configure_concurrency_limit(
+    class: *const libc::c_char,
+    limit: libc::size_t,
+) {
+    let limit = if limit == 0 { None } else { Some(limit) };
+    configure_concurrency_limit(&c_str_to_rust_str(class), limit);
+}
+
+/// See `concurrency_stats()`
+#[no_mangle]
+pub unsafe extern "C" fn fil_concurrency_stats(class: *const libc::c_char) -> FfiConcurrencyStats {
+    concurrency_stats(&c_str_to_rust_str(class))
+}
+
+/// The number of calls of `class` waiting for a running one to finish
+#[no_mangle]
+pub unsafe extern "C" fn fil_concurrency_queue_length(class: *const libc::c_char) -> libc::size_t {
+    concurrency_stats(&c_str_to_rust_str(class)).waiting
+}
@@ -0,0 +1,168 @@
+//! A configuration type registered by the consumer, parsed from TOML by `fil_parse_config_toml()`.
+//!
+//! This replaces exporting a setter function per field: the consumer registers its config type
+//! once with `register_config_type()`, the host passes the whole configuration as TOML and the
+//! consumer reads the current configuration with `config()`. Fields missing from the TOML take
+//! their defaults if the type is marked `#[serde(default)]`, a null or empty string yields the
+//! `Default` of the type.
+
+use std::any::Any;
+use std::ffi::CStr;
+use std::fmt;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use drop_struct_macro_derive::DropStructMacro;
+use serde::de::DeserializeOwned;
+
+use crate::{error_response, free_c_str, free_raw_ptr, raw_ptr, CodeAndMessage, FCPResponseStatus};
+
+type AnyConfig = Arc<dyn Any + Send + Sync>;
+
+struct Registration {
+    parse: fn(&str) -> Result<AnyConfig, ConfigError>,
+    current: AnyConfig,
+}
+
+static CONFIG: Mutex<Option<Registration>> = Mutex::new(None);
+
+/// A configuration that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub message: String,
+    /// The 1-based position of the error, 0 if it's not known
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "invalid config: {}", self.message)
+        } else {
+            write!(
+                f,
+                "invalid config at line {}, column {}: {}",
+                self.line, self.column, self.message
+            )
+        }
+    }
+}
+
+// the 1-based line and column of the byte `offset` of `input`
+fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Parses a `T` from `input`, an empty input yields the default
+pub fn parse_config_toml<T: DeserializeOwned + Default>(input: &str) -> Result<T, ConfigError> {
+    if input.trim().is_empty() {
+        return Ok(T::default());
+    }
+    toml::from_str(input).map_err(|err| {
+        let (line, column) = err
+            .span()
+            .map_or((0, 0), |span| line_and_column(input, span.start));
+        ConfigError {
+            message: err.message().to_string(),
+            line,
+            column,
+        }
+    })
+}
+
+fn parse_any<T>(input: &str) -> Result<AnyConfig, ConfigError>
+where
+    T: DeserializeOwned + Default + Send + Sync + 'static,
+{
+    Ok(Arc::new(parse_config_toml::<T>(input)?))
+}
+
+/// Registers `T` as the configuration type, the current configuration is reset to its default
+pub fn register_config_type<T>()
+where
+    T: DeserializeOwned + Default + Send + Sync + 'static,
+{
+    *CONFIG.lock().unwrap() = Some(Registration {
+        parse: parse_any::<T>,
+        current: Arc::new(T::default()),
+    });
+}
+
+/// The current configuration, `None` if `T` isn't the registered configuration type
+pub fn config<T: Send + Sync + 'static>() -> Option<Arc<T>> {
+    let current = CONFIG.lock().unwrap().as_ref()?.current.clone();
+    current.downcast().ok()
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ParseConfigResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for ParseConfigResponse {
+    fn default() -> Self {
+        ParseConfigResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+        }
+    }
+}
+
+crate::code_and_message_impl!(ParseConfigResponse);
+
+/// Parses the TOML `config` into the registered configuration type and makes it the current
+/// configuration
+///
+/// An invalid configuration is an `FCPCallerError` and leaves the current configuration as is.
+#[no_mangle]
+pub unsafe extern "C" fn fil_parse_config_toml(
+    config: *const libc::c_char,
+) -> *mut ParseConfigResponse {
+    let input = if config.is_null() {
+        ""
+    } else {
+        match CStr::from_ptr(config).to_str() {
+            Ok(input) => input,
+            Err(_) => {
+                return error_response(
+                    FCPResponseStatus::FCPCallerError,
+                    "invalid argument `config`: must be valid UTF-8",
+                )
+            }
+        }
+    };
+    let parse = match CONFIG.lock().unwrap().as_ref() {
+        Some(registration) => registration.parse,
+        None => {
+            return error_response(
+                FCPResponseStatus::FCPReceiverError,
+                "no config type is registered",
+            )
+        }
+    };
+    // Parsed without holding the lock, a registration meanwhile wins
+    match parse(input) {
+        Ok(parsed) => {
+            if let Some(registration) = CONFIG.lock().unwrap().as_mut() {
+                if Any::type_id(&*registration.current) == Any::type_id(&*parsed) {
+                    registration.current = parsed;
+                }
+            }
+            raw_ptr(ParseConfigResponse::default())
+        }
+        Err(err) => error_response(FCPResponseStatus::FCPCallerError, err.to_string()),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_parse_config_response(ptr: *mut ParseConfigResponse) {
+    free_raw_ptr(ptr);
+}
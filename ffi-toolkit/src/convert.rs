@@ -0,0 +1,58 @@
+use crate::FfiU128;
+
+/// Converts a Rust value into its C representation
+///
+/// The C representation owns whatever it points to and is expected to free it on drop (e.g.
+/// through `DropStructMacro`).
+pub trait FfiInto<C> {
+    fn ffi_into(self) -> C;
+}
+
+/// Reconstructs a Rust value from its C representation
+///
+/// The C representation stays untouched, all data that is needed is copied.
+pub trait FfiFrom<C>: Sized {
+    /// The C representation must be valid, i.e. all its pointers need to point to memory of the
+    /// length they claim.
+    unsafe fn ffi_from(c_repr: &C) -> Self;
+}
+
+impl FfiInto<FfiU128> for u128 {
+    fn ffi_into(self) -> FfiU128 {
+        FfiU128::from(self)
+    }
+}
+
+impl FfiFrom<FfiU128> for u128 {
+    unsafe fn ffi_from(c_repr: &FfiU128) -> Self {
+        u128::from(*c_repr)
+    }
+}
+
+/// Generates a proptest round-trip test for a `FfiInto`/`FfiFrom` pair
+///
+/// Every generated value is converted into its C representation, back into a Rust value, which
+/// must be equal to the original one, and finally the C representation is dropped. Running the
+/// tests under Miri or a sanitizer hence also catches broken drop code.
+///
+/// The crate using the macro needs `proptest` as a dev-dependency.
+///
+/// ```ignore
+/// ffi_round_trip_test!(u128_round_trip, u128 => FfiU128, proptest::num::u128::ANY);
+/// ```
+#[macro_export]
+macro_rules! ffi_round_trip_test {
+    ($name:ident, $rust:ty => $c:ty, $strategy:expr) => {
+        #[test]
+        fn $name() {
+            ::proptest::proptest!(|(value in $strategy)| {
+                let value: $rust = value;
+                let c_repr: $c =
+                    $crate::FfiInto::<$c>::ffi_into(::std::clone::Clone::clone(&value));
+                let round_tripped: $rust = unsafe { $crate::FfiFrom::<$c>::ffi_from(&c_repr) };
+                ::std::mem::drop(c_repr);
+                ::proptest::prop_assert_eq!(round_tripped, value);
+            });
+        }
+    };
+}
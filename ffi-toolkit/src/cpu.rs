@@ -0,0 +1,90 @@
+/// The flags `fil_cpu_features()` returns, with their names
+pub const CPU_FEATURES: &[(u64, &str)] = &[
+    (FIL_CPU_SSE4_1, "sse4.1"),
+    (FIL_CPU_SSE4_2, "sse4.2"),
+    (FIL_CPU_AVX, "avx"),
+    (FIL_CPU_AVX2, "avx2"),
+    (FIL_CPU_AVX512F, "avx512f"),
+    (FIL_CPU_SHA, "sha"),
+    (FIL_CPU_AES, "aes"),
+    (FIL_CPU_PCLMULQDQ, "pclmulqdq"),
+    (FIL_CPU_BMI2, "bmi2"),
+    (FIL_CPU_ADX, "adx"),
+    (FIL_CPU_NEON, "neon"),
+    (FIL_CPU_ARM_SHA2, "sha2"),
+    (FIL_CPU_ARM_AES, "arm-aes"),
+];
+
+pub const FIL_CPU_SSE4_1: u64 = 1;
+pub const FIL_CPU_SSE4_2: u64 = 1 << 1;
+pub const FIL_CPU_AVX: u64 = 1 << 2;
+pub const FIL_CPU_AVX2: u64 = 1 << 3;
+pub const FIL_CPU_AVX512F: u64 = 1 << 4;
+/// The x86 SHA extensions
+pub const FIL_CPU_SHA: u64 = 1 << 5;
+/// AES-NI
+pub const FIL_CPU_AES: u64 = 1 << 6;
+pub const FIL_CPU_PCLMULQDQ: u64 = 1 << 7;
+pub const FIL_CPU_BMI2: u64 = 1 << 8;
+pub const FIL_CPU_ADX: u64 = 1 << 9;
+pub const FIL_CPU_NEON: u64 = 1 << 32;
+/// The Armv8 SHA-256 instructions
+pub const FIL_CPU_ARM_SHA2: u64 = 1 << 33;
+pub const FIL_CPU_ARM_AES: u64 = 1 << 34;
+
+/// The SIMD and crypto extensions of the CPU the library runs on, as `FIL_CPU_*` flags
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn cpu_features() -> u64 {
+    let detected = [
+        (FIL_CPU_SSE4_1, is_x86_feature_detected!("sse4.1")),
+        (FIL_CPU_SSE4_2, is_x86_feature_detected!("sse4.2")),
+        (FIL_CPU_AVX, is_x86_feature_detected!("avx")),
+        (FIL_CPU_AVX2, is_x86_feature_detected!("avx2")),
+        (FIL_CPU_AVX512F, is_x86_feature_detected!("avx512f")),
+        (FIL_CPU_SHA, is_x86_feature_detected!("sha")),
+        (FIL_CPU_AES, is_x86_feature_detected!("aes")),
+        (FIL_CPU_PCLMULQDQ, is_x86_feature_detected!("pclmulqdq")),
+        (FIL_CPU_BMI2, is_x86_feature_detected!("bmi2")),
+        (FIL_CPU_ADX, is_x86_feature_detected!("adx")),
+    ];
+    to_flags(&detected)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub fn cpu_features() -> u64 {
+    use std::arch::is_aarch64_feature_detected;
+
+    let detected = [
+        (FIL_CPU_NEON, is_aarch64_feature_detected!("neon")),
+        (FIL_CPU_ARM_SHA2, is_aarch64_feature_detected!("sha2")),
+        (FIL_CPU_ARM_AES, is_aarch64_feature_detected!("aes")),
+    ];
+    to_flags(&detected)
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn cpu_features() -> u64 {
+    0
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+fn to_flags(detected: &[(u64, bool)]) -> u64 {
+    detected
+        .iter()
+        .filter(|&&(_, detected)| detected)
+        .fold(0, |flags, &(flag, _)| flags | flag)
+}
+
+/// The names of the features in `flags`, e.g. for a warning that optimized paths aren't taken
+pub fn cpu_feature_names(flags: u64) -> Vec<&'static str> {
+    CPU_FEATURES
+        .iter()
+        .filter(|&&(flag, _)| flags & flag != 0)
+        .map(|&(_, name)| name)
+        .collect()
+}
+
+#[no_mangle]
+pub extern "C" fn fil_cpu_features() -> u64 {
+    cpu_features()
+}
@@ -0,0 +1,152 @@
+//! An opt-in handler for crashes that originate in native code.
+//!
+//! On `SIGSEGV` and `SIGABRT` it writes the signal, a backtrace (with glibc) and the recent FFI
+//! calls (see `record_ffi_call()`) to the crash log (see `open_crash_log()`), then re-raises the
+//! signal with the previous handler in place, so the process still dies (and dumps core) the
+//! way it would have without the toolkit.
+
+use std::cell::UnsafeCell;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{mem, ptr};
+
+use crate::audit::for_each_recent_call;
+use crate::crash_log::crash_log_fd;
+use crate::{c_str_to_path, monotonic_now_ns, open_crash_log, CrashLine};
+
+const SIGNALS: [libc::c_int; 2] = [libc::SIGSEGV, libc::SIGABRT];
+
+// The maximum number of frames in the backtrace
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const MAX_FRAMES: usize = 64;
+
+// The actions that were installed before the crash handler, by index into `SIGNALS`
+struct PreviousActions(UnsafeCell<[mem::MaybeUninit<libc::sigaction>; 2]>);
+
+// Only written while the handler isn't installed
+unsafe impl Sync for PreviousActions {}
+
+static PREVIOUS_ACTIONS: PreviousActions =
+    PreviousActions(UnsafeCell::new([mem::MaybeUninit::uninit(); 2]));
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the crash handler, writing to the crash log at `path`
+///
+/// Installing it again only replaces the crash log.
+pub fn install_crash_handler(path: &Path) -> io::Result<()> {
+    open_crash_log(path)?;
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    // The first call of `backtrace()` may load libgcc, which allocates, better not in the handler
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe {
+        let mut frames = [ptr::null_mut(); 1];
+        libc::backtrace(frames.as_mut_ptr(), 1);
+    }
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handle_crash as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+        let previous = &mut *PREVIOUS_ACTIONS.0.get();
+        for (signal, previous) in SIGNALS.iter().zip(previous.iter_mut()) {
+            if libc::sigaction(*signal, &action, previous.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Restores the handlers that were installed before `install_crash_handler()`
+pub fn uninstall_crash_handler() {
+    if INSTALLED.swap(false, Ordering::SeqCst) {
+        restore_previous_actions();
+    }
+}
+
+fn restore_previous_actions() {
+    unsafe {
+        let previous = &*PREVIOUS_ACTIONS.0.get();
+        for (signal, previous) in SIGNALS.iter().zip(previous.iter()) {
+            libc::sigaction(*signal, previous.as_ptr(), ptr::null_mut());
+        }
+    }
+}
+
+fn signal_name(signal: libc::c_int) -> &'static str {
+    match signal {
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGABRT => "SIGABRT",
+        _ => "unknown signal",
+    }
+}
+
+// Everything in here needs to be async-signal-safe
+extern "C" fn handle_crash(signal: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    let mut line = CrashLine::new();
+    line.push_str("fatal signal ")
+        .push_u64(signal as u64)
+        .push_str(" (")
+        .push_str(signal_name(signal))
+        .push_str(")");
+    #[cfg(target_os = "linux")]
+    if signal == libc::SIGSEGV && !info.is_null() {
+        line.push_str(" accessing ")
+            .push_hex(unsafe { (*info).si_addr() } as u64);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = info;
+    line.write();
+
+    write_backtrace();
+
+    CrashLine::new().push_str("recent FFI calls:").write();
+    let now_ns = monotonic_now_ns();
+    for_each_recent_call(|name, at_ns| {
+        CrashLine::new()
+            .push_str("  ")
+            .push_str(name)
+            .push_str(" (")
+            .push_u64(now_ns.saturating_sub(at_ns) / 1_000_000)
+            .push_str(" ms ago)")
+            .write();
+    });
+
+    // The signal is blocked until we return, then the previous handler gets it
+    restore_previous_actions();
+    unsafe { libc::raise(signal) };
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn write_backtrace() {
+    if let Some(fd) = crash_log_fd() {
+        CrashLine::new().push_str("backtrace:").write();
+        let mut frames = [ptr::null_mut(); MAX_FRAMES];
+        unsafe {
+            let len = libc::backtrace(frames.as_mut_ptr(), MAX_FRAMES as libc::c_int);
+            libc::backtrace_symbols_fd(frames.as_ptr(), len, fd);
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn write_backtrace() {
+    if crash_log_fd().is_some() {
+        CrashLine::new().push_str("backtrace: unavailable").write();
+    }
+}
+
+/// Installs the crash handler writing to the file at `path`, returns whether that worked
+#[no_mangle]
+pub unsafe extern "C" fn fil_install_crash_handler(path: *const libc::c_char) -> bool {
+    !path.is_null() && install_crash_handler(&c_str_to_path(path)).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn fil_uninstall_crash_handler() {
+    uninstall_crash_handler();
+}
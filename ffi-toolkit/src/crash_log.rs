@@ -0,0 +1,189 @@
+//! A minimal logging path that is async-signal-safe, for the last moments before a crash.
+//!
+//! The log file is opened up front, writing to it doesn't allocate, take locks or format through
+//! the normal logger: a line is assembled in a fixed buffer (`CrashLine`) and written with a
+//! single `write(2)`. This makes it usable from signal handlers and from panics that happen
+//! while the allocator or the logger are broken.
+
+use std::any::Any;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::io::IntoRawFd;
+use std::panic::Location;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use crate::{BorrowedFfiFd, OwnedFfiFd};
+
+/// The maximum length of a line, longer ones are truncated
+pub const CRASH_LINE_LEN: usize = 512;
+
+// The descriptor of the crash log, -1 if there is none
+static CRASH_LOG_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Makes `fd` the crash log, replacing (and closing) the previous one
+pub fn set_crash_log(fd: OwnedFfiFd) {
+    let previous = CRASH_LOG_FD.swap(fd.into_raw_fd(), Ordering::SeqCst);
+    if previous != -1 {
+        unsafe { libc::close(previous) };
+    }
+}
+
+/// Opens the file at `path` for appending and makes it the crash log
+pub fn open_crash_log(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().append(true).create(true).open(path)?;
+    set_crash_log(OwnedFfiFd::from(file));
+    Ok(())
+}
+
+/// Stops logging crashes and closes the crash log
+pub fn close_crash_log() {
+    let previous = CRASH_LOG_FD.swap(-1, Ordering::SeqCst);
+    if previous != -1 {
+        unsafe { libc::close(previous) };
+    }
+}
+
+// the descriptor of the crash log, if there is one
+pub(crate) fn crash_log_fd() -> Option<libc::c_int> {
+    match CRASH_LOG_FD.load(Ordering::SeqCst) {
+        -1 => None,
+        fd => Some(fd),
+    }
+}
+
+/// Writes `bytes` to the crash log, if there is one
+///
+/// Async-signal-safe. Errors are ignored, there is nowhere left to report them.
+pub fn crash_log_write(bytes: &[u8]) {
+    let fd = match crash_log_fd() {
+        Some(fd) => fd,
+        None => return,
+    };
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let written = unsafe { libc::write(fd, rest.as_ptr() as *const libc::c_void, rest.len()) };
+        if written > 0 {
+            rest = &rest[written as usize..];
+        } else if written == -1 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue;
+        } else {
+            return;
+        }
+    }
+}
+
+/// A line of the crash log, assembled in a fixed buffer
+///
+/// Content beyond `CRASH_LINE_LEN` bytes is dropped. The `push_*` functions are
+/// async-signal-safe, formatting through `fmt::Write` is fine from panics, but not from signal
+/// handlers.
+pub struct CrashLine {
+    buf: [u8; CRASH_LINE_LEN],
+    len: usize,
+}
+
+impl CrashLine {
+    pub const fn new() -> Self {
+        CrashLine {
+            buf: [0; CRASH_LINE_LEN],
+            len: 0,
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) -> &mut Self {
+        self.push_bytes(s.as_bytes())
+    }
+
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        let len = bytes.len().min(CRASH_LINE_LEN - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+        self.len += len;
+        self
+    }
+
+    pub fn push_u64(&mut self, mut value: u64) -> &mut Self {
+        let mut digits = [0; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        self.push_bytes(&digits[start..])
+    }
+
+    /// Pushes `value` as `0x` followed by 16 hex digits, e.g. for addresses
+    pub fn push_hex(&mut self, value: u64) -> &mut Self {
+        let mut digits = *b"0x0000000000000000";
+        for (i, digit) in digits[2..].iter_mut().enumerate() {
+            *digit = b"0123456789abcdef"[(value >> (60 - 4 * i)) as usize & 0xf];
+        }
+        self.push_bytes(&digits)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Writes the line to the crash log, with a trailing newline
+    ///
+    /// The newline is written even if the line is full.
+    pub fn write(&mut self) {
+        if self.len == CRASH_LINE_LEN {
+            self.buf[CRASH_LINE_LEN - 1] = b'\n';
+        } else {
+            self.push_bytes(b"\n");
+        }
+        crash_log_write(self.as_bytes());
+    }
+}
+
+impl Default for CrashLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Write for CrashLine {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+// logs a panic from the panic hook, without allocating
+pub(crate) fn log_panic(location: Option<&Location>, payload: &dyn Any) {
+    let mut line = CrashLine::new();
+    line.push_str("panic");
+    if let Some(location) = location {
+        line.push_str(" at ")
+            .push_str(location.file())
+            .push_str(":")
+            .push_u64(location.line().into());
+    }
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+    if let Some(message) = message {
+        line.push_str(": ").push_str(message);
+    }
+    line.write();
+}
+
+/// Makes a duplicate of `fd` the crash log, returns whether `fd` was valid
+#[no_mangle]
+pub extern "C" fn fil_set_crash_log_fd(fd: BorrowedFfiFd) -> bool {
+    match fd.import() {
+        Ok(owned) => {
+            set_crash_log(owned);
+            true
+        }
+        Err(_) => false,
+    }
+}
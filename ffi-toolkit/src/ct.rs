@@ -0,0 +1,36 @@
+use std::hint;
+
+use crate::try_slice_from_raw;
+
+/// Compares in time that only depends on the lengths, not on the contents
+///
+/// For secrets like tickets or keys, where an early return on the first differing byte would
+/// tell an attacker how much of a guess was right.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut difference = 0u8;
+    for (x, y) in a.iter().zip(b) {
+        difference |= x ^ y;
+    }
+    // Keep the compiler from short-circuiting the loop
+    hint::black_box(difference) == 0
+}
+
+/// `ct_eq()` for the host, a null pointer is only equal to an empty buffer if its length is 0
+#[no_mangle]
+pub unsafe extern "C" fn fil_ct_eq(
+    a_ptr: *const u8,
+    a_len: libc::size_t,
+    b_ptr: *const u8,
+    b_len: libc::size_t,
+) -> bool {
+    match (
+        try_slice_from_raw(a_ptr, a_len),
+        try_slice_from_raw(b_ptr, b_len),
+    ) {
+        (Some(a), Some(b)) => ct_eq(a, b),
+        _ => false,
+    }
+}
@@ -0,0 +1,174 @@
+//! Cursors streaming the items of a Rust iterator to the host, one at a time.
+//!
+//! A result set too large for one allocation (e.g. millions of challenges) is handed out as a
+//! cursor instead: the host calls `next` until it reports `FIL_CURSOR_DONE` and then frees the
+//! cursor, so only the item at hand is in memory. `declare_cursor!` declares the handle type and
+//! the two exported functions for one item type.
+//!
+//! ```
+//! use ffi_toolkit::{declare_cursor, FfiCursorStatus};
+//!
+//! declare_cursor! {
+//!     /// The challenges of a sector, created by `fil_challenges()`
+//!     pub struct FfiChallenges(u64);
+//!     next = fil_challenges_next;
+//!     destroy = fil_destroy_challenges;
+//! }
+//!
+//! let challenges = FfiChallenges::wrap((0..3u64).map(|i| i * 7));
+//! let mut challenge = 0;
+//! let mut all = Vec::new();
+//! unsafe {
+//!     while fil_challenges_next(challenges, &mut challenge) == FfiCursorStatus::Item {
+//!         all.push(challenge);
+//!     }
+//!     fil_destroy_challenges(challenges);
+//! }
+//! assert_eq!(all, [0, 7, 14]);
+//! ```
+
+use std::ptr;
+
+use crate::{
+    catch_panic_value_with_last_error, free_raw_ptr, raw_ptr, set_last_error, FCPResponseStatus,
+};
+
+status_code_enum! {
+    #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+    pub enum FfiCursorStatus {
+        // An item was written to `out`
+        Item = 0 => FIL_CURSOR_ITEM,
+        // The cursor is exhausted, nothing was written
+        Done = 1 => FIL_CURSOR_DONE,
+        // The cursor or `out` is null
+        NullPointer = 2 => FIL_CURSOR_NULL,
+        // The iterator panicked, the message is the thread's last error
+        Panicked = 3 => FIL_CURSOR_PANICKED,
+    }
+}
+
+/// An iterator owned by C, see `declare_cursor!`
+pub struct Cursor<T> {
+    iter: Box<dyn Iterator<Item = T> + Send>,
+    // Set once the iterator is exhausted or panicked, it isn't polled again
+    done: bool,
+}
+
+impl<T> Cursor<T> {
+    pub fn new<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        Cursor {
+            iter: Box::new(iter.into_iter()),
+            done: false,
+        }
+    }
+
+    /// The next item, a panic of the iterator ends the cursor and is recorded as the last error
+    pub fn next_item(&mut self) -> Result<Option<T>, FfiCursorStatus> {
+        if self.done {
+            return Ok(None);
+        }
+        let iter = &mut self.iter;
+        match catch_panic_value_with_last_error(|| Some(iter.next())) {
+            Some(Some(item)) => Ok(Some(item)),
+            Some(None) => {
+                self.done = true;
+                Ok(None)
+            }
+            None => {
+                self.done = true;
+                Err(FfiCursorStatus::Panicked)
+            }
+        }
+    }
+}
+
+/// Hands ownership of a cursor over `iter` to C, which frees it with `cursor_free()`
+pub fn cursor_new<I>(iter: I) -> *mut Cursor<I::Item>
+where
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+{
+    raw_ptr(Cursor::new(iter))
+}
+
+/// Moves the next item into `*out`, which is written without dropping its old value
+///
+/// `cursor` must be null or come from `cursor_new()` and not be freed yet, and C must not use
+/// it concurrently. `out` must be null or valid for writes. Items that own memory are owned by
+/// the caller afterwards.
+pub unsafe fn cursor_next<T>(cursor: *mut Cursor<T>, out: *mut T) -> FfiCursorStatus {
+    let cursor = match cursor.as_mut() {
+        Some(cursor) if !out.is_null() => cursor,
+        _ => {
+            set_last_error(
+                FCPResponseStatus::FCPCallerError,
+                "the cursor or its output pointer is null",
+            );
+            return FfiCursorStatus::NullPointer;
+        }
+    };
+    match cursor.next_item() {
+        Ok(Some(item)) => {
+            ptr::write(out, item);
+            FfiCursorStatus::Item
+        }
+        Ok(None) => FfiCursorStatus::Done,
+        Err(status) => status,
+    }
+}
+
+/// Frees a cursor and the items it didn't hand out, null pointers are ignored
+pub unsafe fn cursor_free<T>(cursor: *mut Cursor<T>) {
+    free_raw_ptr(cursor);
+}
+
+/// Declares an opaque cursor type over items of one type and its exported functions
+///
+/// Generated are `wrap()`, handing a cursor over an iterator to C, the exported `next`
+/// function, see `cursor_next()`, and the exported destructor, see `cursor_free()`. See the
+/// `cursor` module for an example.
+#[macro_export]
+macro_rules! declare_cursor {
+    {
+        $(#[$meta:meta])*
+        pub struct $handle:ident($item:ty);
+        next = $next:ident;
+        destroy = $destroy:ident;
+    } => {
+        $(#[$meta])*
+        #[repr(C)]
+        pub struct $handle {
+            _private: [u8; 0],
+        }
+
+        impl $handle {
+            /// Hands a cursor over `iter` over to C, which frees it with the destructor
+            pub fn wrap<I>(iter: I) -> *mut $handle
+            where
+                I: IntoIterator<Item = $item>,
+                I::IntoIter: Send + 'static,
+            {
+                $crate::cursor_new(iter) as *mut $handle
+            }
+        }
+
+        #[doc = concat!("Moves the next item of a `", stringify!($handle), "` into `*out`")]
+        #[no_mangle]
+        pub unsafe extern "C" fn $next(
+            cursor: *mut $handle,
+            out: *mut $item,
+        ) -> $crate::FfiCursorStatus {
+            $crate::cursor_next(cursor as *mut $crate::Cursor<$item>, out)
+        }
+
+        #[doc = concat!("Frees a `", stringify!($handle), "`, null pointers are ignored")]
+        #[no_mangle]
+        pub unsafe extern "C" fn $destroy(cursor: *mut $handle) {
+            $crate::cursor_free(cursor as *mut $crate::Cursor<$item>);
+        }
+    };
+}
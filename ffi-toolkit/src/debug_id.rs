@@ -0,0 +1,196 @@
+//! Headers identifying responses, so that a pointer the host hands back can be told apart from
+//! a stale, foreign or corrupted one.
+//!
+//! A response opts in with a `debug_id: FfiDebugId` field in front of all others. Its `Default`
+//! stamps a magic number and a sequence number, the derived `Drop` overwrites the magic number,
+//! and the derived destructor checks the header before freeing anything. The check reads the
+//! memory at the pointer, so it's a best effort: memory that was reused since is only caught if
+//! its contents don't happen to look like a live header. With the `poison` feature destroyed
+//! responses are overwritten, and show up as foreign rather than stale.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{set_last_error, FCPResponseStatus, IntoFFIError};
+
+/// The magic number of a live response
+pub const LIVE_MAGIC: u32 = 0xF1F1_D0D0;
+
+/// The magic number of a destroyed response
+pub const FREED_MAGIC: u32 = 0xF1F1_DEAD;
+
+// 0 is never handed out, so that zeroed memory doesn't look like a response
+static SEQUENCE: AtomicU32 = AtomicU32::new(1);
+
+/// The header of a response, `magic` and the response's `sequence` number
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FfiDebugId {
+    pub magic: u32,
+    pub sequence: u32,
+}
+
+impl FfiDebugId {
+    /// A live header with the next sequence number
+    pub fn next() -> Self {
+        let mut sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        // After 2^32 responses the numbers start over
+        if sequence == 0 {
+            sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        }
+        FfiDebugId {
+            magic: LIVE_MAGIC,
+            sequence,
+        }
+    }
+
+    /// Marks the response as destroyed, the sequence number tells which one it was
+    pub fn invalidate(&mut self) {
+        self.magic = FREED_MAGIC;
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.magic == LIVE_MAGIC
+    }
+}
+
+impl Default for FfiDebugId {
+    fn default() -> Self {
+        FfiDebugId::next()
+    }
+}
+
+/// A response whose first field is an `FfiDebugId`, implemented by the derives for a
+/// `debug_id` field
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]` with the `FfiDebugId` at offset 0.
+pub unsafe trait DebugIdentified {}
+
+/// Why a pointer isn't a live response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugIdError {
+    Null,
+    /// The pointer isn't aligned for an `FfiDebugId`, so it can't be one the toolkit handed out
+    Misaligned(usize),
+    /// The response with the sequence number was destroyed already
+    Stale(u32),
+    /// The magic number differs from a valid one in a few bits only, or the sequence number is 0
+    Corrupted(FfiDebugId),
+    /// The memory doesn't start with a header
+    Foreign(FfiDebugId),
+}
+
+impl fmt::Display for DebugIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DebugIdError::Null => write!(f, "null response pointer"),
+            DebugIdError::Misaligned(address) => {
+                write!(f, "misaligned response pointer {:#x}", address)
+            }
+            DebugIdError::Stale(sequence) => {
+                write!(f, "response #{} was destroyed already", sequence)
+            }
+            DebugIdError::Corrupted(id) => write!(
+                f,
+                "corrupted response header, magic number {:#010x}, sequence number {}",
+                id.magic, id.sequence
+            ),
+            DebugIdError::Foreign(id) => write!(
+                f,
+                "not a response of the toolkit, magic number {:#010x}",
+                id.magic
+            ),
+        }
+    }
+}
+
+impl Error for DebugIdError {}
+
+// The pointer comes from the caller
+impl IntoFFIError for DebugIdError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Reads the header `ptr` points to, and returns it if it is the one of a live response
+///
+/// `ptr` must still be readable for the size of an `FfiDebugId`.
+pub unsafe fn check_debug_id(ptr: *const libc::c_void) -> Result<FfiDebugId, DebugIdError> {
+    if ptr.is_null() {
+        return Err(DebugIdError::Null);
+    }
+    if !(ptr as *const FfiDebugId).is_aligned() {
+        return Err(DebugIdError::Misaligned(ptr as usize));
+    }
+    let id = *(ptr as *const FfiDebugId);
+    match id.magic {
+        LIVE_MAGIC if id.sequence == 0 => Err(DebugIdError::Corrupted(id)),
+        LIVE_MAGIC => Ok(id),
+        FREED_MAGIC => Err(DebugIdError::Stale(id.sequence)),
+        magic if is_damaged(magic, LIVE_MAGIC) || is_damaged(magic, FREED_MAGIC) => {
+            Err(DebugIdError::Corrupted(id))
+        }
+        _ => Err(DebugIdError::Foreign(id)),
+    }
+}
+
+// whether `magic` is `valid` with a few bits flipped, rather than something else entirely
+fn is_damaged(magic: u32, valid: u32) -> bool {
+    (magic ^ valid).count_ones() <= 4
+}
+
+/// The response `ptr` points to, if its header says it is live, see `check_debug_id()`
+pub unsafe fn checked_response<'a, T: DebugIdentified>(
+    ptr: *const T,
+) -> Result<&'a T, DebugIdError> {
+    check_debug_id(ptr as *const libc::c_void)?;
+    Ok(&*ptr)
+}
+
+/// Like `checked_response()`, for a response the caller may modify
+pub unsafe fn checked_response_mut<'a, T: DebugIdentified>(
+    ptr: *mut T,
+) -> Result<&'a mut T, DebugIdError> {
+    check_debug_id(ptr as *const libc::c_void)?;
+    Ok(&mut *ptr)
+}
+
+// Checks the response a derived destructor is given, an invalid one is recorded as the thread's
+// last error and must not be freed; null is left to the destructor
+#[doc(hidden)]
+pub unsafe fn destroy_checked<T: DebugIdentified>(type_name: &str, ptr: *const T) -> bool {
+    if ptr.is_null() {
+        return true;
+    }
+    match check_debug_id(ptr as *const libc::c_void) {
+        Ok(_) => true,
+        Err(err) => {
+            set_last_error(
+                err.code(),
+                format!("refusing to destroy a `{}`: {}", type_name, err),
+            );
+            false
+        }
+    }
+}
+
+/// Checks the header `ptr` points to, an invalid one is a `FCPCallerError` whose message is
+/// recorded as the thread's last error
+#[no_mangle]
+pub unsafe extern "C" fn fil_check_debug_id(ptr: *const libc::c_void) -> FCPResponseStatus {
+    match check_debug_id(ptr) {
+        Ok(_) => FCPResponseStatus::FCPNoError,
+        Err(err) => {
+            set_last_error(err.code(), err.message());
+            err.code()
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! A single exported destructor for all registered response types.
+//!
+//! Instead of a destroy function per response type, the host can free every response with
+//! `fil_destroy(tag, ptr)`. Each response type gets a tag with `#[ffi_drop(tag = 7)]` (which
+//! implements `TaggedResponse`) and is registered with `register_destructor()` when the library
+//! starts. From then on the toolkit remembers the tag of every response of a registered type it
+//! hands out, so that a pointer destroyed with the wrong tag, or destroyed twice, is rejected
+//! with a caller error instead of freeing it as the wrong type.
+
+use std::any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::{free_raw_ptr, set_last_error, FCPResponseStatus, IntoFFIError};
+
+/// Identifies a response type across the boundary
+pub type TypeTag = u32;
+
+/// A response type that can be destroyed with `fil_destroy()`
+pub trait TaggedResponse: Sized {
+    const TYPE_TAG: TypeTag;
+}
+
+#[derive(Clone, Copy)]
+struct Destructor {
+    type_name: &'static str,
+    destroy: unsafe fn(*mut libc::c_void),
+}
+
+#[derive(Default)]
+struct Registry {
+    destructors: HashMap<TypeTag, Destructor>,
+    // The tags of the types, by type name, as the allocations are recorded by it
+    tags: HashMap<&'static str, TypeTag>,
+    // The live responses of registered types
+    live: HashMap<usize, TypeTag>,
+}
+
+// Whether any type was registered, so that allocations aren't looked up before
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+/// Why a response can't be destroyed with `fil_destroy()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestroyError {
+    /// The tag was registered for another type already
+    TagInUse {
+        tag: TypeTag,
+        type_name: &'static str,
+    },
+    /// No type was registered with the tag
+    UnknownTag(TypeTag),
+    /// The pointer isn't a live response of a registered type
+    UnknownPointer,
+    /// The pointer is a response of another type
+    WrongTag {
+        tag: TypeTag,
+        type_name: &'static str,
+    },
+}
+
+impl fmt::Display for DestroyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DestroyError::TagInUse { tag, type_name } => {
+                write!(f, "the type tag {} is used by `{}` already", tag, type_name)
+            }
+            DestroyError::UnknownTag(tag) => write!(f, "no type is registered for tag {}", tag),
+            DestroyError::UnknownPointer => write!(
+                f,
+                "the pointer isn't a live response of a registered type, was it destroyed already?"
+            ),
+            DestroyError::WrongTag { tag, type_name } => {
+                write!(f, "the pointer is a `{}` (tag {})", type_name, tag)
+            }
+        }
+    }
+}
+
+impl Error for DestroyError {}
+
+impl IntoFFIError for DestroyError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+unsafe fn destroy<T>(ptr: *mut libc::c_void) {
+    free_raw_ptr(ptr as *mut T);
+}
+
+/// Makes `T` destroyable with `fil_destroy()`, responses handed out before aren't known to it
+///
+/// Registering a type again is a no-op, registering another type with the same tag an error.
+pub fn register_destructor<T: TaggedResponse>() -> Result<(), DestroyError> {
+    let type_name = any::type_name::<T>();
+    let mut registry = REGISTRY.lock().unwrap();
+    let registry = registry.get_or_insert_with(Registry::default);
+    if let Some(existing) = registry.destructors.get(&T::TYPE_TAG) {
+        if existing.type_name == type_name {
+            return Ok(());
+        }
+        return Err(DestroyError::TagInUse {
+            tag: T::TYPE_TAG,
+            type_name: existing.type_name,
+        });
+    }
+    registry.destructors.insert(
+        T::TYPE_TAG,
+        Destructor {
+            type_name,
+            destroy: destroy::<T>,
+        },
+    );
+    registry.tags.insert(type_name, T::TYPE_TAG);
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+pub(crate) fn record_alloc(ptr: *const u8, type_name: &'static str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(registry) = REGISTRY.lock().unwrap().as_mut() {
+        if let Some(&tag) = registry.tags.get(type_name) {
+            registry.live.insert(ptr as usize, tag);
+        }
+    }
+}
+
+pub(crate) fn record_free(ptr: *const u8) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(registry) = REGISTRY.lock().unwrap().as_mut() {
+        registry.live.remove(&(ptr as usize));
+    }
+}
+
+/// Frees the response `ptr` of the type registered with `tag`, null pointers are ignored
+pub unsafe fn destroy_tagged(tag: TypeTag, ptr: *mut libc::c_void) -> Result<(), DestroyError> {
+    if ptr.is_null() {
+        return Ok(());
+    }
+    let destructor = {
+        let registry = REGISTRY.lock().unwrap();
+        let registry = registry.as_ref();
+        let destructor = registry
+            .and_then(|registry| registry.destructors.get(&tag))
+            .copied()
+            .ok_or(DestroyError::UnknownTag(tag))?;
+        match registry.and_then(|registry| registry.live.get(&(ptr as usize))) {
+            Some(&live_tag) if live_tag == tag => destructor,
+            Some(&live_tag) => {
+                return Err(DestroyError::WrongTag {
+                    tag: live_tag,
+                    type_name: registry.unwrap().destructors[&live_tag].type_name,
+                })
+            }
+            None => return Err(DestroyError::UnknownPointer),
+        }
+    };
+    // Without the lock, freeing records the free
+    (destructor.destroy)(ptr);
+    Ok(())
+}
+
+/// Frees any response of a registered type, see `register_destructor()`
+///
+/// A pointer that isn't a live response with the tag `tag` is left alone and reported as a
+/// caller error, the message is available with `fil_last_error_message()`.
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy(tag: TypeTag, ptr: *mut libc::c_void) -> FCPResponseStatus {
+    match destroy_tagged(tag, ptr) {
+        Ok(()) => FCPResponseStatus::FCPNoError,
+        Err(err) => {
+            set_last_error(err.code(), err.message());
+            err.code()
+        }
+    }
+}
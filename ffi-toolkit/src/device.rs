@@ -0,0 +1,116 @@
+//! A uniform way of listing the devices (e.g. GPUs) consumers can use.
+//!
+//! Consumers register a probe per kind of device, `fil_list_devices()` runs all of them.
+
+use std::panic;
+use std::ptr;
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{
+    free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str, FCPResponseStatus, FfiStringArray,
+};
+
+/// A device found by a probe
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeviceInfo {
+    /// The kind of the device, e.g. `gpu`
+    pub kind: String,
+    pub name: String,
+    /// Identifies the device within its kind, e.g. a PCI bus id
+    pub id: String,
+    /// The memory of the device, 0 if unknown
+    pub memory_bytes: u64,
+}
+
+/// A function enumerating the devices of one kind
+pub type DeviceProbe = fn() -> Result<Vec<DeviceInfo>, String>;
+
+static PROBES: Mutex<Vec<(&'static str, DeviceProbe)>> = Mutex::new(Vec::new());
+
+/// Registers a probe under `name`, registering the same name again replaces the probe
+pub fn register_device_probe(name: &'static str, probe: DeviceProbe) {
+    let mut probes = PROBES.lock().unwrap();
+    match probes.iter_mut().find(|(existing, _)| *existing == name) {
+        Some(entry) => entry.1 = probe,
+        None => probes.push((name, probe)),
+    }
+}
+
+pub fn unregister_device_probe(name: &str) {
+    PROBES
+        .lock()
+        .unwrap()
+        .retain(|(existing, _)| *existing != name);
+}
+
+/// Runs all probes, in the order of their registration
+///
+/// Returns the devices and the errors of the probes that failed (or panicked), prefixed with the
+/// name of the probe.
+pub fn list_devices() -> (Vec<DeviceInfo>, Vec<String>) {
+    // The lock isn't held while probing, so probes may register other probes
+    let probes = PROBES.lock().unwrap().clone();
+    let mut devices = Vec::new();
+    let mut errors = Vec::new();
+    for (name, probe) in probes {
+        match panic::catch_unwind(probe) {
+            Ok(Ok(found)) => devices.extend(found),
+            Ok(Err(err)) => errors.push(format!("{}: {}", name, err)),
+            Err(_) => errors.push(format!("{}: probe panicked", name)),
+        }
+    }
+    (devices, errors)
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct FfiDevice {
+    pub kind: *const libc::c_char,
+    pub name: *const libc::c_char,
+    pub id: *const libc::c_char,
+    pub memory_bytes: u64,
+}
+
+impl From<DeviceInfo> for FfiDevice {
+    fn from(device: DeviceInfo) -> Self {
+        FfiDevice {
+            kind: rust_str_to_c_str(device.kind),
+            name: rust_str_to_c_str(device.name),
+            id: rust_str_to_c_str(device.id),
+            memory_bytes: device.memory_bytes,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ListDevicesResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub devices_ptr: *const FfiDevice,
+    pub devices_len: libc::size_t,
+    /// The errors of the probes that failed, the devices of the other probes are still listed
+    pub probe_errors: FfiStringArray,
+}
+
+/// Lists the devices of all registered probes
+#[no_mangle]
+pub extern "C" fn fil_list_devices() -> *mut ListDevicesResponse {
+    let (devices, errors) = list_devices();
+    let devices: Box<[FfiDevice]> = devices.into_iter().map(FfiDevice::from).collect();
+    let devices_len = devices.len();
+    raw_ptr(ListDevicesResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: ptr::null(),
+        devices_ptr: Box::into_raw(devices) as *const FfiDevice,
+        devices_len,
+        probe_errors: FfiStringArray::new(errors),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_list_devices_response(ptr: *mut ListDevicesResponse) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{
+    c_str_to_pbuf, c_str_to_rust_str, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str,
+    CodeAndMessage, FCPResponseStatus, FfiStringArray,
+};
+
+/// The kind of a failed file system operation, so that the host can react without parsing the
+/// error message
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum FfiIoErrorKind {
+    NoError,
+    NotFound,
+    PermissionDenied,
+    NotADirectory,
+    Other,
+}
+
+impl From<&io::Error> for FfiIoErrorKind {
+    fn from(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => FfiIoErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => FfiIoErrorKind::PermissionDenied,
+            io::ErrorKind::NotADirectory => FfiIoErrorKind::NotADirectory,
+            _ => FfiIoErrorKind::Other,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ListDirResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub error_kind: FfiIoErrorKind,
+    /// The names of the matching entries, sorted
+    pub entries: FfiStringArray,
+}
+
+impl Default for ListDirResponse {
+    fn default() -> Self {
+        ListDirResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            error_kind: FfiIoErrorKind::NoError,
+            entries: FfiStringArray::empty(),
+        }
+    }
+}
+
+crate::code_and_message_impl!(ListDirResponse);
+
+/// Whether `name` matches `pattern`, in which `*` matches any sequence of characters and `?` a
+/// single character
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // The positions to continue at when a `*` needs to match more characters
+    let (mut star, mut star_name) = (None, 0);
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_name = n;
+            p += 1;
+        } else if let Some(star) = star {
+            p = star + 1;
+            star_name += 1;
+            n = star_name;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The sorted names of the entries of `path` that match `pattern` (see `matches_pattern()`)
+pub fn list_dir(path: &Path, pattern: Option<&str>) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if pattern.is_none_or(|pattern| matches_pattern(pattern, &name)) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Lists the entries of the directory `path` whose names match `pattern`, all of them if it's
+/// null
+#[no_mangle]
+pub unsafe extern "C" fn fil_list_dir(
+    path: *const libc::c_char,
+    pattern: *const libc::c_char,
+) -> *mut ListDirResponse {
+    let pattern = if pattern.is_null() {
+        None
+    } else {
+        Some(c_str_to_rust_str(pattern))
+    };
+    let path = c_str_to_pbuf(path);
+    let mut response = ListDirResponse::default();
+    match list_dir(&path, pattern.as_deref()) {
+        Ok(names) => response.entries = FfiStringArray::new(names),
+        Err(err) => {
+            response.status_code = FCPResponseStatus::FCPCallerError;
+            response.error_msg =
+                rust_str_to_c_str(format!("failed to list {}: {}", path.display(), err));
+            response.error_kind = FfiIoErrorKind::from(&err);
+        }
+    }
+    raw_ptr(response)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_list_dir_response(ptr: *mut ListDirResponse) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,409 @@
+//! Parsing of hex- and multibase-encoded identifiers (e.g. CIDs) arriving from the host, and
+//! hex and base64 C strings for binary data in responses to hosts that only take strings.
+//!
+//! Errors carry the offset at which parsing failed, so the message that ends up in an
+//! `FCPCallerError` response tells the host what exactly is wrong with its input.
+
+use std::ffi::CStr;
+use std::fmt;
+
+use crate::{error_response, rust_str_to_c_str, CodeAndMessage, FCPResponseStatus, IntoFFIError};
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Why parsing failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The C string pointer was null
+    Null,
+    /// The C string isn't valid UTF-8
+    InvalidUtf8,
+    Empty,
+    /// A character that isn't part of the encoding's alphabet
+    InvalidCharacter(char),
+    /// The number of characters can't encode a whole number of bytes
+    InvalidLength,
+    /// The unused bits of the last character aren't zero
+    NonCanonical,
+    /// The multibase prefix isn't one of the supported bases
+    UnsupportedBase(char),
+    /// The decoded bytes ended early
+    Truncated,
+    /// The decoded value has the wrong number of bytes
+    WrongLength {
+        expected: usize,
+        actual: usize,
+    },
+    UnsupportedCidVersion(u64),
+}
+
+/// A parse failure at `offset`
+///
+/// For encoding errors the offset is the one of the character in the input, for errors in the
+/// structure of a CID it is the one of the byte in the decoded bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(offset: usize, kind: ParseErrorKind) -> Self {
+        ParseError { offset, kind }
+    }
+
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<T: Default + CodeAndMessage>(&self, name: &str) -> *mut T {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Null => write!(f, "null string"),
+            ParseErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ParseErrorKind::Empty => write!(f, "empty string"),
+            ParseErrorKind::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+            ParseErrorKind::InvalidLength => write!(f, "invalid length"),
+            ParseErrorKind::NonCanonical => write!(f, "non-canonical encoding"),
+            ParseErrorKind::UnsupportedBase(c) => write!(f, "unsupported multibase prefix {:?}", c),
+            ParseErrorKind::Truncated => write!(f, "truncated"),
+            ParseErrorKind::WrongLength { expected, actual } => {
+                write!(f, "{} bytes, expected {}", actual, expected)
+            }
+            ParseErrorKind::UnsupportedCidVersion(version) => {
+                write!(f, "unsupported CID version {}", version)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// The input is up to the caller
+impl IntoFFIError for ParseError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Lowercase hex digits
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for &byte in bytes {
+        hex.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        hex.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+    }
+    hex
+}
+
+/// Parses hex digits, upper- as well as lowercase
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>, ParseError> {
+    parse_hex_at(hex, 0)
+}
+
+// `offset` is the one of `hex` within the whole input
+fn parse_hex_at(hex: &str, offset: usize) -> Result<Vec<u8>, ParseError> {
+    let digits = hex.as_bytes();
+    if let Some(index) = digits.iter().position(|&digit| hex_value(digit).is_none()) {
+        return Err(invalid_character(hex, offset, index));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(ParseError::new(
+            offset + digits.len(),
+            ParseErrorKind::InvalidLength,
+        ));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| hex_value(pair[0]).unwrap() << 4 | hex_value(pair[1]).unwrap())
+        .collect())
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+// the error for the (possibly multi-byte) character at byte `index` of `input`
+fn invalid_character(input: &str, offset: usize, index: usize) -> ParseError {
+    let c = input[index..].chars().next().unwrap_or('\u{fffd}');
+    ParseError::new(offset + index, ParseErrorKind::InvalidCharacter(c))
+}
+
+/// Decodes a multibase string
+///
+/// Supported are base16 (`f`/`F`), base32 without padding (`b`/`B`), base58btc (`z`) and base64
+/// without padding (`m`, and `u` for the URL alphabet).
+pub fn parse_multibase(input: &str) -> Result<Vec<u8>, ParseError> {
+    let prefix = match input.chars().next() {
+        Some(prefix) => prefix,
+        None => return Err(ParseError::new(0, ParseErrorKind::Empty)),
+    };
+    let data = &input[prefix.len_utf8()..];
+    let offset = prefix.len_utf8();
+    match prefix {
+        'f' | 'F' => parse_hex_at(data, offset),
+        'b' => decode_bits(data, offset, 5, |c| alphabet_value(BASE32_ALPHABET, c)),
+        'B' => decode_bits(data, offset, 5, |c| {
+            alphabet_value(BASE32_ALPHABET, c.to_ascii_lowercase())
+        }),
+        'z' => decode_base58(data, offset),
+        'm' => decode_bits(data, offset, 6, |c| alphabet_value(BASE64_ALPHABET, c)),
+        'u' => decode_bits(data, offset, 6, |c| alphabet_value(BASE64URL_ALPHABET, c)),
+        _ => Err(ParseError::new(0, ParseErrorKind::UnsupportedBase(prefix))),
+    }
+}
+
+/// Standard base64 with padding
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut base64 = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buffer, (index, &byte)| {
+                buffer | (byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let value = (buffer >> (18 - 6 * index)) & 0x3f;
+                base64.push(BASE64_ALPHABET[value as usize] as char);
+            } else {
+                base64.push('=');
+            }
+        }
+    }
+    base64
+}
+
+/// Parses standard base64, with or without padding
+pub fn parse_base64(base64: &str) -> Result<Vec<u8>, ParseError> {
+    let data = base64.trim_end_matches('=');
+    let padding = base64.len() - data.len();
+    if padding > 0 && (padding > 2 || !base64.len().is_multiple_of(4)) {
+        return Err(ParseError::new(base64.len(), ParseErrorKind::InvalidLength));
+    }
+    decode_bits(data, 0, 6, |c| alphabet_value(BASE64_ALPHABET, c))
+}
+
+fn alphabet_value(alphabet: &[u8], c: u8) -> Option<u32> {
+    alphabet
+        .iter()
+        .position(|&digit| digit == c)
+        .map(|value| value as u32)
+}
+
+// decodes an encoding where every character holds `bits` bits, like base32 and base64
+fn decode_bits<F>(data: &str, offset: usize, bits: u32, value: F) -> Result<Vec<u8>, ParseError>
+where
+    F: Fn(u8) -> Option<u32>,
+{
+    let mut bytes = Vec::with_capacity(data.len() * bits as usize / 8);
+    let (mut buffer, mut buffered) = (0u32, 0);
+    for (index, &c) in data.as_bytes().iter().enumerate() {
+        let value = value(c).ok_or_else(|| invalid_character(data, offset, index))?;
+        buffer = (buffer << bits) | value;
+        buffered += bits;
+        if buffered >= 8 {
+            buffered -= 8;
+            bytes.push((buffer >> buffered) as u8);
+            buffer &= (1 << buffered) - 1;
+        }
+    }
+    // A whole character left over means the input was cut off
+    if buffered >= bits {
+        return Err(ParseError::new(
+            offset + data.len(),
+            ParseErrorKind::InvalidLength,
+        ));
+    }
+    if buffer != 0 {
+        return Err(ParseError::new(
+            offset + data.len() - 1,
+            ParseErrorKind::NonCanonical,
+        ));
+    }
+    Ok(bytes)
+}
+
+fn decode_base58(data: &str, offset: usize) -> Result<Vec<u8>, ParseError> {
+    // Little-endian base 256 digits of the value
+    let mut value: Vec<u8> = Vec::new();
+    for (index, &c) in data.as_bytes().iter().enumerate() {
+        let mut carry = alphabet_value(BASE58_ALPHABET, c)
+            .ok_or_else(|| invalid_character(data, offset, index))?;
+        for byte in value.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            value.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // Leading ones encode leading zero bytes
+    let zeros = data.bytes().take_while(|&c| c == b'1').count();
+    let mut bytes = vec![0; zeros];
+    bytes.extend(value.iter().rev());
+    Ok(bytes)
+}
+
+/// A parsed content identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cid {
+    pub version: u64,
+    /// The multicodec of the content, `0x70` (dag-pb) for version 0
+    pub codec: u64,
+    /// The multihash function code, e.g. `0x12` for sha2-256
+    pub hash_code: u64,
+    pub digest: Vec<u8>,
+}
+
+const DAG_PB: u64 = 0x70;
+const SHA2_256: u64 = 0x12;
+
+/// Parses a CID, version 0 (base58btc without prefix, `Qm...`) or 1 (multibase)
+pub fn parse_cid(input: &str) -> Result<Cid, ParseError> {
+    if input.len() == 46 && input.starts_with("Qm") {
+        let multihash = decode_base58(input, 0)?;
+        let (hash_code, digest) = parse_multihash(&multihash, 0)?;
+        if hash_code != SHA2_256 || digest.len() != 32 {
+            return Err(ParseError::new(0, ParseErrorKind::UnsupportedCidVersion(0)));
+        }
+        return Ok(Cid {
+            version: 0,
+            codec: DAG_PB,
+            hash_code,
+            digest,
+        });
+    }
+
+    let bytes = parse_multibase(input)?;
+    let (version, offset) = read_varint(&bytes, 0)?;
+    if version != 1 {
+        return Err(ParseError::new(
+            0,
+            ParseErrorKind::UnsupportedCidVersion(version),
+        ));
+    }
+    let (codec, offset) = read_varint(&bytes, offset)?;
+    let (hash_code, digest) = parse_multihash(&bytes[offset..], offset)?;
+    Ok(Cid {
+        version,
+        codec,
+        hash_code,
+        digest,
+    })
+}
+
+// `offset` is the one of `multihash` in the decoded bytes
+fn parse_multihash(multihash: &[u8], offset: usize) -> Result<(u64, Vec<u8>), ParseError> {
+    let (hash_code, position) =
+        read_varint(multihash, 0).map_err(|err| ParseError::new(offset + err.offset, err.kind))?;
+    let (len, position) = read_varint(multihash, position)
+        .map_err(|err| ParseError::new(offset + err.offset, err.kind))?;
+    let digest = &multihash[position..];
+    if digest.len() as u64 != len {
+        return Err(ParseError::new(
+            offset + position,
+            ParseErrorKind::WrongLength {
+                expected: len as usize,
+                actual: digest.len(),
+            },
+        ));
+    }
+    Ok((hash_code, digest.to_vec()))
+}
+
+// an unsigned LEB128 varint starting at `offset`, returns the value and the following offset
+fn read_varint(bytes: &[u8], offset: usize) -> Result<(u64, usize), ParseError> {
+    let mut value = 0u64;
+    // At most 9 bytes, as for multiformats
+    for (index, &byte) in bytes[offset..].iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            if byte == 0 && index > 0 {
+                return Err(ParseError::new(
+                    offset + index,
+                    ParseErrorKind::NonCanonical,
+                ));
+            }
+            return Ok((value, offset + index + 1));
+        }
+    }
+    Err(ParseError::new(bytes.len(), ParseErrorKind::Truncated))
+}
+
+// the string `ptr` points to, which needs to be valid UTF-8
+unsafe fn c_str_to_str<'a>(ptr: *const libc::c_char) -> Result<&'a str, ParseError> {
+    if ptr.is_null() {
+        return Err(ParseError::new(0, ParseErrorKind::Null));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|err| ParseError::new(err.valid_up_to(), ParseErrorKind::InvalidUtf8))
+}
+
+/// Parses hex digits from a C string, see `parse_hex()`
+pub unsafe fn parse_hex_c_str(ptr: *const libc::c_char) -> Result<Vec<u8>, ParseError> {
+    parse_hex(c_str_to_str(ptr)?)
+}
+
+/// Decodes the multibase string `ptr` points to, see `parse_multibase()`
+pub unsafe fn parse_multibase_c_str(ptr: *const libc::c_char) -> Result<Vec<u8>, ParseError> {
+    parse_multibase(c_str_to_str(ptr)?)
+}
+
+/// Parses the CID `ptr` points to, see `parse_cid()`
+pub unsafe fn parse_cid_c_str(ptr: *const libc::c_char) -> Result<Cid, ParseError> {
+    parse_cid(c_str_to_str(ptr)?)
+}
+
+/// `bytes` as lowercase hex digits in a C string, to be freed with `free_c_str()`
+pub fn bytes_to_c_hex(bytes: &[u8]) -> *mut libc::c_char {
+    rust_str_to_c_str(to_hex(bytes))
+}
+
+/// The bytes of the hex digits `ptr` points to, the counterpart of `bytes_to_c_hex()`
+///
+/// The error is an `FCPCallerError`, for use with `?` in functions returning a `Result` to
+/// `catch_panic_result()`.
+pub unsafe fn c_hex_to_bytes(ptr: *const libc::c_char) -> Result<Vec<u8>, ParseError> {
+    parse_hex_c_str(ptr)
+}
+
+/// `bytes` as standard base64 in a C string, to be freed with `free_c_str()`
+pub fn bytes_to_c_base64(bytes: &[u8]) -> *mut libc::c_char {
+    rust_str_to_c_str(to_base64(bytes))
+}
+
+/// The bytes of the base64 `ptr` points to, see `parse_base64()` and `c_hex_to_bytes()`
+pub unsafe fn c_base64_to_bytes(ptr: *const libc::c_char) -> Result<Vec<u8>, ParseError> {
+    parse_base64(c_str_to_str(ptr)?)
+}
@@ -0,0 +1,132 @@
+//! Explicit byte order for values that cross both the FFI boundary and the network.
+//!
+//! The bytes of a value are only ever produced and read by these helpers, so a value can't be
+//! swapped twice by hand: network order values go through `to_be_bytes_ffi()` and
+//! `from_be_ffi()`, buffers whose order depends on the peer carry it in `FfiOrderedBytes`.
+
+use std::convert::TryInto;
+use std::slice;
+
+use crate::FfiBytes;
+
+/// An integer that can be converted to and from bytes in either order
+pub trait FfiEndian: Copy {
+    /// The number of bytes of the value
+    const SIZE: usize;
+
+    fn to_be_vec(self) -> Vec<u8>;
+    fn to_le_vec(self) -> Vec<u8>;
+    /// `bytes` has exactly `SIZE` bytes
+    fn from_be_slice(bytes: &[u8]) -> Self;
+    /// `bytes` has exactly `SIZE` bytes
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+macro_rules! ffi_endian_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl FfiEndian for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn to_be_vec(self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+
+                fn to_le_vec(self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+
+                fn from_be_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_be_bytes(bytes.try_into().unwrap())
+                }
+
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+ffi_endian_impl!(u16, u32, u64, u128, i16, i32, i64, i128);
+
+/// The byte order of an `FfiOrderedBytes`
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum FfiByteOrder {
+    LittleEndian,
+    /// Network order
+    BigEndian,
+}
+
+impl FfiByteOrder {
+    /// The byte order of the machine we run on
+    pub const NATIVE: FfiByteOrder = if cfg!(target_endian = "big") {
+        FfiByteOrder::BigEndian
+    } else {
+        FfiByteOrder::LittleEndian
+    };
+}
+
+/// The bytes of `value` in network order
+pub fn to_be_bytes_ffi<T: FfiEndian>(value: T) -> FfiBytes {
+    FfiBytes::new(value.to_be_vec())
+}
+
+/// Reads a value in network order from the `len` bytes at `ptr`, which need to be exactly the
+/// size of the value
+pub unsafe fn from_be_ffi<T: FfiEndian>(ptr: *const u8, len: usize) -> Result<T, String> {
+    Ok(T::from_be_slice(checked_bytes::<T>(ptr, len)?))
+}
+
+unsafe fn checked_bytes<'a, T: FfiEndian>(ptr: *const u8, len: usize) -> Result<&'a [u8], String> {
+    if ptr.is_null() {
+        return Err("bytes must not be null".to_string());
+    }
+    if len != T::SIZE {
+        return Err(format!("expected {} bytes, got {}", T::SIZE, len));
+    }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+/// The bytes of a single value, tagged with their byte order
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiOrderedBytes {
+    pub order: FfiByteOrder,
+    pub bytes: FfiBytes,
+}
+
+impl FfiOrderedBytes {
+    pub fn new<T: FfiEndian>(value: T, order: FfiByteOrder) -> Self {
+        let bytes = match order {
+            FfiByteOrder::LittleEndian => value.to_le_vec(),
+            FfiByteOrder::BigEndian => value.to_be_vec(),
+        };
+        FfiOrderedBytes {
+            order,
+            bytes: FfiBytes::new(bytes),
+        }
+    }
+
+    /// Reads the value in the order of the tag
+    pub fn get<T: FfiEndian>(&self) -> Result<T, String> {
+        let bytes = unsafe { checked_bytes::<T>(self.bytes.ptr, self.bytes.len)? };
+        Ok(match self.order {
+            FfiByteOrder::LittleEndian => T::from_le_slice(bytes),
+            FfiByteOrder::BigEndian => T::from_be_slice(bytes),
+        })
+    }
+
+    /// The same value in `order`
+    pub fn to_order(&self, order: FfiByteOrder) -> Self {
+        let mut bytes = self.bytes.to_vec();
+        if order != self.order {
+            bytes.reverse();
+        }
+        FfiOrderedBytes {
+            order,
+            bytes: FfiBytes::new(bytes),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::env;
+
+use crate::{free_raw_ptr, raw_ptr, FfiMap};
+
+// Variables with these prefixes (or exactly these names) are included in the snapshot
+const ALLOWED_PREFIXES: &[&str] = &[
+    "FIL_",
+    "BELLMAN_",
+    "RUST_LOG",
+    "RUST_BACKTRACE",
+    "RUST_MIN_STACK",
+    "RAYON_",
+    "CUDA_",
+    "GPU_",
+    "OPENCL_",
+    "NEPTUNE_",
+    "TMPDIR",
+];
+
+// Values of variables whose names contain one of these are redacted
+const SECRET_MARKERS: &[&str] = &[
+    "KEY",
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+const REDACTED: &str = "<redacted>";
+
+/// The environment variables relevant for diagnosing the toolkit and its consumers, sorted by
+/// name
+///
+/// Only allow-listed variables are included (e.g. `FIL_*`, `RUST_LOG`, `CUDA_*`). The values of
+/// variables whose names look like they hold secrets are replaced by `<redacted>`.
+pub fn env_snapshot() -> Vec<(String, String)> {
+    let mut snapshot: Vec<(String, String)> = env::vars_os()
+        .filter_map(|(name, value)| {
+            let name = name.into_string().ok()?;
+            if !ALLOWED_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return None;
+            }
+            let upper = name.to_ascii_uppercase();
+            let value = if SECRET_MARKERS.iter().any(|marker| upper.contains(marker)) {
+                REDACTED.to_string()
+            } else {
+                value.to_string_lossy().into_owned()
+            };
+            Some((name, value))
+        })
+        .collect();
+    snapshot.sort();
+    snapshot
+}
+
+/// `env_snapshot()` for the host, the map needs to be freed with `fil_destroy_map()`
+#[no_mangle]
+pub extern "C" fn fil_env_snapshot() -> *mut FfiMap {
+    raw_ptr(FfiMap::new(env_snapshot()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_map(ptr: *mut FfiMap) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,82 @@
+use alloc_crate::boxed::Box;
+use alloc_crate::string::String;
+use alloc_crate::vec::Vec;
+use core::slice;
+
+use crate::c_types::size_t;
+use crate::{FCPResponseStatus, FfiStringArray};
+
+/// The causes of an error, from the error itself to its root cause
+///
+/// Every message has the code at the same index. It owns its memory and dropping it frees
+/// everything, so it can be a field of a response. Filled in by `catch_panic_result()` if the
+/// response implements `CodeAndMessage::set_error_chain()`, which `#[derive(FFIResponse)]` does
+/// for a field of this type.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiErrorChain {
+    pub messages: FfiStringArray,
+    pub codes_ptr: *const FCPResponseStatus,
+    pub codes_len: size_t,
+}
+
+impl FfiErrorChain {
+    pub fn new<I, S>(causes: I) -> Self
+    where
+        I: IntoIterator<Item = (FCPResponseStatus, S)>,
+        S: Into<String>,
+    {
+        let (codes, messages): (Vec<_>, Vec<_>) = causes
+            .into_iter()
+            .map(|(code, message)| (code, message.into()))
+            .unzip();
+        let codes = codes.into_boxed_slice();
+        let codes_len = codes.len();
+        FfiErrorChain {
+            messages: FfiStringArray::new(messages),
+            codes_ptr: Box::into_raw(codes) as *const FCPResponseStatus,
+            codes_len,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(Vec::<(FCPResponseStatus, String)>::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes_len == 0
+    }
+
+    pub fn codes(&self) -> &[FCPResponseStatus] {
+        unsafe { slice::from_raw_parts(self.codes_ptr, self.codes_len) }
+    }
+
+    /// Copies the causes, invalid UTF-8 in the messages is replaced
+    pub fn to_vec(&self) -> Vec<(FCPResponseStatus, String)> {
+        self.codes()
+            .iter()
+            .copied()
+            .zip(self.messages.to_vec())
+            .collect()
+    }
+}
+
+impl Default for FfiErrorChain {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Drop for FfiErrorChain {
+    fn drop(&mut self) {
+        unsafe {
+            let codes =
+                slice::from_raw_parts_mut(self.codes_ptr as *mut FCPResponseStatus, self.codes_len);
+            drop(Box::from_raw(codes as *mut [FCPResponseStatus]));
+        }
+    }
+}
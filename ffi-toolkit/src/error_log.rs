@@ -0,0 +1,181 @@
+//! Logging of the errors the guarded runners (`catch_panic_result()`, `catch_panic_response()`)
+//! return, through the toolkit's logger.
+//!
+//! Off by default. A host hammering a broken function would get the same message logged over and
+//! over, with a deduplication window only the first occurrence of a `(code, message)` pair is
+//! logged, and once the window is over a `repeated N times` summary of the suppressed ones. The
+//! responses get their errors either way.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{FCPResponseStatus, FfiDuration, FfiInstant};
+
+/// The target of the logged errors
+pub const ERROR_LOG_TARGET: &str = "ffi_toolkit::errors";
+
+// More distinct errors than this within a window are logged without deduplication
+const MAX_TRACKED_ERRORS: usize = 1024;
+
+/// Which errors are logged
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorLogging {
+    Off,
+    Every,
+    /// Repetitions within the window are summarized
+    Deduplicated(FfiDuration),
+}
+
+struct Repeats {
+    code: FCPResponseStatus,
+    since: FfiInstant,
+    repeated: u64,
+}
+
+struct State {
+    logging: ErrorLogging,
+    // By the raw code and the message
+    seen: Option<HashMap<(i32, String), Repeats>>,
+    swept_at: FfiInstant,
+}
+
+// Checked before locking, so that the runners don't contend while logging is off
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<State> = Mutex::new(State {
+    logging: ErrorLogging::Off,
+    seen: None,
+    swept_at: FfiInstant { monotonic_ns: 0 },
+});
+
+fn line(code: FCPResponseStatus, message: &str) -> String {
+    format!("{:?}: {}", code, message)
+}
+
+fn summary(code: FCPResponseStatus, message: &str, repeats: &Repeats, now: FfiInstant) -> String {
+    format!(
+        "{:?}: {} (repeated {} times in {:?})",
+        code,
+        message,
+        repeats.repeated,
+        now.saturating_duration_since(repeats.since)
+            .to_duration_saturating()
+    )
+}
+
+/// Sets which errors are logged, the summaries of a previous window are logged right away
+///
+/// `shutdown()` logs the summaries and turns the logging off.
+pub fn configure_error_logging(logging: ErrorLogging) {
+    flush_error_log();
+    STATE.lock().unwrap().logging = logging;
+    let was_enabled = ENABLED.swap(logging != ErrorLogging::Off, Ordering::Relaxed);
+    if logging != ErrorLogging::Off && !was_enabled {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Registries, stop_error_logging);
+    }
+}
+
+fn stop_error_logging() {
+    configure_error_logging(ErrorLogging::Off);
+}
+
+/// Logs the summaries of the suppressed errors, e.g. before the host reads the log
+pub fn flush_error_log() {
+    let now = FfiInstant::now();
+    let summaries: Vec<_> = match STATE.lock().unwrap().seen.take() {
+        Some(seen) => seen
+            .iter()
+            .filter(|(_, repeats)| repeats.repeated > 0)
+            .map(|((_, message), repeats)| summary(repeats.code, message, repeats, now))
+            .collect(),
+        None => Vec::new(),
+    };
+    for summary in summaries {
+        log::error!(target: ERROR_LOG_TARGET, "{}", summary);
+    }
+}
+
+// Logs an error a runner returns, as configured with `configure_error_logging()`
+pub(crate) fn report_error(code: FCPResponseStatus, message: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    // Logged after unlocking, the host's log callback may well fail another call
+    let mut lines = Vec::new();
+    {
+        let mut state = STATE.lock().unwrap();
+        match state.logging {
+            ErrorLogging::Off => {}
+            ErrorLogging::Every => lines.push(line(code, message)),
+            ErrorLogging::Deduplicated(window) => {
+                deduplicate(&mut state, window, code, message, &mut lines)
+            }
+        }
+    }
+    for line in lines {
+        log::error!(target: ERROR_LOG_TARGET, "{}", line);
+    }
+}
+
+// adds the lines to log for an error to `lines`: the error unless it was seen within the
+// window, and the summaries of the windows that are over
+fn deduplicate(
+    state: &mut State,
+    window: FfiDuration,
+    code: FCPResponseStatus,
+    message: &str,
+    lines: &mut Vec<String>,
+) {
+    let now = FfiInstant::now();
+    let expired = |repeats: &Repeats| now.saturating_duration_since(repeats.since) >= window;
+    let seen = state.seen.get_or_insert_with(HashMap::new);
+    // The other errors' windows are checked once per window
+    if now.saturating_duration_since(state.swept_at) >= window {
+        seen.retain(|(_, message), repeats| {
+            if !expired(repeats) {
+                return true;
+            }
+            if repeats.repeated > 0 {
+                lines.push(summary(repeats.code, message, repeats, now));
+            }
+            false
+        });
+        state.swept_at = now;
+    }
+    let new_window = Repeats {
+        code,
+        since: now,
+        repeated: 0,
+    };
+    let key = (code as i32, message.to_string());
+    let tracked = seen.len();
+    match seen.get_mut(&key) {
+        Some(repeats) if !expired(repeats) => {
+            repeats.repeated += 1;
+            return;
+        }
+        Some(repeats) => {
+            if repeats.repeated > 0 {
+                lines.push(summary(code, message, repeats, now));
+            }
+            *repeats = new_window;
+        }
+        None if tracked < MAX_TRACKED_ERRORS => {
+            seen.insert(key, new_window);
+        }
+        None => {}
+    }
+    lines.push(line(code, message));
+}
+
+/// Logs the errors of the guarded runners if `enabled`, identical ones within `dedup_window`
+/// only once, a zero window logs all of them
+#[no_mangle]
+pub extern "C" fn fil_configure_error_logging(enabled: bool, dedup_window: FfiDuration) {
+    configure_error_logging(match enabled {
+        false => ErrorLogging::Off,
+        true if dedup_window == FfiDuration::default() => ErrorLogging::Every,
+        true => ErrorLogging::Deduplicated(dedup_window),
+    });
+}
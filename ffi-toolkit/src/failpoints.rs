@@ -0,0 +1,108 @@
+//! Named points at which tests can inject panics or errors.
+//!
+//! The toolkit evaluates the point `"catch_panic_response"` right before running the guarded
+//! closure, consumers can add their own points with `fail_point!`. Points only ever fire with
+//! the `failpoints` feature, which is meant for tests; without it `fail_point!` compiles to
+//! nothing.
+//!
+//! The configuration is global, tests sharing a point must not run in parallel.
+
+use crate::FCPResponseStatus;
+
+/// What happens when the execution reaches a configured point
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailAction {
+    /// Panic with the given message
+    Panic(String),
+    /// Return an error response with the given status code and message
+    Error(FCPResponseStatus, String),
+}
+
+#[cfg(feature = "failpoints")]
+mod registry {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::FailAction;
+
+    pub(super) static POINTS: Mutex<Option<HashMap<String, FailAction>>> = Mutex::new(None);
+}
+
+/// Makes the point with the given name fail with `action`, until it's removed again
+pub fn configure<S: Into<String>>(name: S, action: FailAction) {
+    #[cfg(feature = "failpoints")]
+    registry::POINTS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(Default::default)
+        .insert(name.into(), action);
+    #[cfg(not(feature = "failpoints"))]
+    let _ = (name, action);
+}
+
+pub fn remove(name: &str) {
+    #[cfg(feature = "failpoints")]
+    if let Some(points) = registry::POINTS.lock().unwrap().as_mut() {
+        points.remove(name);
+    }
+    #[cfg(not(feature = "failpoints"))]
+    let _ = name;
+}
+
+pub fn clear() {
+    #[cfg(feature = "failpoints")]
+    registry::POINTS.lock().unwrap().take();
+}
+
+/// Evaluates a point: panics if it's configured to, returns the error it's configured to return
+#[inline(always)]
+pub fn eval(name: &str) -> Option<(FCPResponseStatus, String)> {
+    #[cfg(feature = "failpoints")]
+    {
+        let action = registry::POINTS
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|points| points.get(name).cloned());
+        match action {
+            Some(FailAction::Panic(message)) => panic!("{}", message),
+            Some(FailAction::Error(code, message)) => Some((code, message)),
+            None => None,
+        }
+    }
+    #[cfg(not(feature = "failpoints"))]
+    {
+        let _ = name;
+        None
+    }
+}
+
+/// Declares a named fail point in a function returning `*mut T` (where `T` is a response)
+///
+/// If the point is configured to return an error, the function returns an error response.
+///
+/// ```
+/// use ffi_toolkit::{fail_point, raw_ptr};
+/// # use ffi_toolkit::{code_and_message_impl, CodeAndMessage, FCPResponseStatus};
+/// # #[derive(Default)]
+/// # pub struct Response { status_code: i32, error_msg: *const libc::c_char }
+/// # impl CodeAndMessage for Response {
+/// #     fn set_error(&mut self, (_, message): (FCPResponseStatus, *const libc::c_char)) {
+/// #         self.error_msg = message;
+/// #     }
+/// # }
+///
+/// fn seal() -> *mut Response {
+///     fail_point!("seal::before_replication");
+///     raw_ptr(Response::default())
+/// }
+/// # seal();
+/// ```
+#[macro_export]
+macro_rules! fail_point {
+    ($name:expr) => {
+        if let Some((code, message)) = $crate::failpoints::eval($name) {
+            return $crate::error_response(code, message);
+        }
+    };
+}
@@ -0,0 +1,119 @@
+//! File descriptors handed across the boundary.
+//!
+//! Hosts can pass already-open files (e.g. unsealed sector files) instead of paths that would
+//! need to be reopened. An exported function takes a `BorrowedFfiFd`, which stays owned by the
+//! host, and imports it into an `OwnedFfiFd` that is a duplicate the Rust side closes on drop:
+//!
+//! ```ignore
+//! #[no_mangle]
+//! pub unsafe extern "C" fn fil_read_sector(fd: BorrowedFfiFd) -> *mut ReadResponse {
+//!     let file = match fd.import() {
+//!         Ok(owned) => owned.into_file(),
+//!         Err(err) => return error_response(FCPResponseStatus::FCPCallerError, err.to_string()),
+//!     };
+//!     ...
+//! }
+//! ```
+//!
+//! `borrow_fd()` and `take_fd()` are the same as plain functions of a raw descriptor, for both
+//! ownership conventions. Building a `File` with `File::from_raw_fd()` directly takes ownership
+//! and closes the host's descriptor, which is rarely what the host expects.
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+/// A file descriptor owned by the host, which must keep it open for the duration of the call
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BorrowedFfiFd(pub libc::c_int);
+
+impl BorrowedFfiFd {
+    /// Whether the descriptor refers to an open file
+    pub fn is_valid(self) -> bool {
+        self.0 >= 0 && unsafe { libc::fcntl(self.0, libc::F_GETFD) } != -1
+    }
+
+    /// Duplicates the descriptor, the host can close its own one independently
+    ///
+    /// The duplicate has `FD_CLOEXEC` set. Fails with `EBADF` for invalid descriptors.
+    pub fn import(self) -> io::Result<OwnedFfiFd> {
+        if self.0 < 0 {
+            return Err(io::Error::from_raw_os_error(libc::EBADF));
+        }
+        match unsafe { libc::fcntl(self.0, libc::F_DUPFD_CLOEXEC, 0) } {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(OwnedFfiFd(fd)),
+        }
+    }
+}
+
+/// Duplicates the host's descriptor into a `File`, the host keeps (and closes) its own one
+///
+/// See `BorrowedFfiFd::import()`.
+pub fn borrow_fd(fd: libc::c_int) -> io::Result<File> {
+    BorrowedFfiFd(fd).import().map(OwnedFfiFd::into_file)
+}
+
+/// Takes ownership of the host's descriptor, it is closed when the `File` is dropped
+///
+/// The host must neither use nor close `fd` afterwards. Fails with `EBADF`, without taking
+/// anything, if `fd` isn't open.
+pub unsafe fn take_fd(fd: libc::c_int) -> io::Result<File> {
+    if !BorrowedFfiFd(fd).is_valid() {
+        return Err(io::Error::from_raw_os_error(libc::EBADF));
+    }
+    Ok(File::from_raw_fd(fd))
+}
+
+/// A file descriptor owned by the Rust side, it is closed on drop
+#[derive(Debug, PartialEq, Eq)]
+pub struct OwnedFfiFd(RawFd);
+
+impl OwnedFfiFd {
+    /// The descriptor to hand to the host, which then needs to close it
+    pub fn into_raw(self) -> libc::c_int {
+        self.into_raw_fd()
+    }
+
+    pub fn borrow(&self) -> BorrowedFfiFd {
+        BorrowedFfiFd(self.0)
+    }
+
+    pub fn into_file(self) -> File {
+        unsafe { File::from_raw_fd(self.into_raw_fd()) }
+    }
+}
+
+impl From<File> for OwnedFfiFd {
+    fn from(file: File) -> Self {
+        OwnedFfiFd(file.into_raw_fd())
+    }
+}
+
+impl AsRawFd for OwnedFfiFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl IntoRawFd for OwnedFfiFd {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.0;
+        std::mem::forget(self);
+        fd
+    }
+}
+
+impl FromRawFd for OwnedFfiFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        OwnedFfiFd(fd)
+    }
+}
+
+impl Drop for OwnedFfiFd {
+    fn drop(&mut self) {
+        // Errors can't be reported from a drop, the descriptor is gone either way
+        unsafe { libc::close(self.0) };
+    }
+}
@@ -0,0 +1,35 @@
+//! Windows handles handed across the boundary, the equivalents of `borrow_fd()`/`take_fd()`.
+
+use std::fs::File;
+use std::io;
+use std::os::windows::io::{BorrowedHandle, FromRawHandle, RawHandle};
+
+// `INVALID_HANDLE_VALUE`, as returned by e.g. `CreateFileW()` on failure
+const INVALID_HANDLE_VALUE: RawHandle = -1isize as RawHandle;
+
+fn check(handle: RawHandle) -> io::Result<()> {
+    if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the handle is null or invalid",
+        ));
+    }
+    Ok(())
+}
+
+/// Duplicates the host's handle into a `File`, the host keeps (and closes) its own one
+///
+/// `handle` must be null, `INVALID_HANDLE_VALUE` or an open handle for the duration of the call.
+pub unsafe fn borrow_handle(handle: RawHandle) -> io::Result<File> {
+    check(handle)?;
+    let owned = BorrowedHandle::borrow_raw(handle).try_clone_to_owned()?;
+    Ok(File::from(owned))
+}
+
+/// Takes ownership of the host's handle, it is closed when the `File` is dropped
+///
+/// The host must neither use nor close `handle` afterwards.
+pub unsafe fn take_handle(handle: RawHandle) -> io::Result<File> {
+    check(handle)?;
+    Ok(File::from_raw_handle(handle))
+}
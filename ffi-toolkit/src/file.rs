@@ -0,0 +1,103 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{
+    c_str_to_path, ffi_precondition, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str,
+    CodeAndMessage, FCPResponseStatus, FfiIoErrorKind,
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct WriteFileResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub error_kind: FfiIoErrorKind,
+}
+
+impl Default for WriteFileResponse {
+    fn default() -> Self {
+        WriteFileResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            error_kind: FfiIoErrorKind::NoError,
+        }
+    }
+}
+
+crate::code_and_message_impl!(WriteFileResponse);
+
+/// Replaces the contents of the file at `path` with `bytes`, so that after a crash the file
+/// holds either the old or the new contents
+///
+/// The bytes are written to a temporary file next to `path`, which is synced to disk and then
+/// renamed to `path`.
+pub fn write_file_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let temp_path = dir.join(format!(
+        ".{}.tmp-{}-{}",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+    // Persist the rename itself
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Atomically replaces the contents of the file at `path`, see `write_file_atomic()`
+#[no_mangle]
+pub unsafe extern "C" fn fil_write_file_atomic(
+    path: *const libc::c_char,
+    bytes_ptr: *const u8,
+    bytes_len: usize,
+) -> *mut WriteFileResponse {
+    ffi_precondition!(ptr_non_null(path), slice_non_null(bytes_ptr, bytes_len));
+    let path = c_str_to_path(path);
+    let bytes = if bytes_len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(bytes_ptr, bytes_len)
+    };
+    let mut response = WriteFileResponse::default();
+    if let Err(err) = write_file_atomic(&path, bytes) {
+        response.status_code = FCPResponseStatus::FCPReceiverError;
+        response.error_msg =
+            rust_str_to_c_str(format!("failed to write {}: {}", path.display(), err));
+        response.error_kind = FfiIoErrorKind::from(&err);
+    }
+    raw_ptr(response)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_write_file_response(ptr: *mut WriteFileResponse) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,169 @@
+//! Fixed-size byte arrays: commitments and randomness (32 bytes), BLS public keys (48 bytes) and
+//! BLS signatures (96 bytes).
+//!
+//! Passed by value instead of as a pointer with an implied length, so that a buffer of the wrong
+//! length is a type error. They own no memory, hence `DropStructMacro` responses can have them
+//! as plain fields, and vectors of them as `*const Fil32` fields with a length field as usual.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::ct::ct_eq;
+use crate::{encoding, FCPResponseStatus, FfiCommitment, IntoFFIError};
+
+/// A slice that doesn't have the length of the fixed-size array it is converted into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteLengthError {
+    pub type_name: &'static str,
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for ByteLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` has {} bytes, got {}",
+            self.type_name, self.expected, self.actual
+        )
+    }
+}
+
+impl Error for ByteLengthError {}
+
+impl IntoFFIError for ByteLengthError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+macro_rules! fixed_bytes {
+    ($(#[$meta:meta])* $name:ident, $len:expr, $ct_eq:ident) => {
+        $(#[$meta])*
+        ///
+        /// Comparisons are constant-time.
+        #[repr(C)]
+        #[derive(Copy, Clone)]
+        pub struct $name(pub [u8; $len]);
+
+        impl $name {
+            pub const LEN: usize = $len;
+
+            pub fn try_from_slice(bytes: &[u8]) -> Result<Self, ByteLengthError> {
+                if bytes.len() != Self::LEN {
+                    return Err(ByteLengthError {
+                        type_name: stringify!($name),
+                        expected: Self::LEN,
+                        actual: bytes.len(),
+                    });
+                }
+                let mut array = [0; $len];
+                array.copy_from_slice(bytes);
+                Ok($name(array))
+            }
+
+            pub fn as_bytes(&self) -> &[u8; $len] {
+                &self.0
+            }
+
+            /// Lowercase hex digits
+            pub fn to_hex(&self) -> String {
+                encoding::to_hex(&self.0)
+            }
+
+            /// Compares in constant time, as `==` does, for code that shouldn't rely on that
+            pub fn ct_eq(&self, other: &Self) -> bool {
+                ct_eq(&self.0, &other.0)
+            }
+        }
+
+        #[doc = concat!("Compares two `", stringify!($name), "` in constant time")]
+        #[no_mangle]
+        pub extern "C" fn $ct_eq(a: $name, b: $name) -> bool {
+            a.ct_eq(&b)
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name([0; $len])
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                ct_eq(&self.0, &other.0)
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                $name(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(bytes: $name) -> Self {
+                bytes.0
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = ByteLengthError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+                Self::try_from_slice(bytes)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "({})"), self.to_hex())
+            }
+        }
+    };
+}
+
+fixed_bytes!(
+    /// 32 bytes, e.g. a commitment, a ticket or randomness
+    Fil32,
+    32,
+    fil_ct_eq_fil32
+);
+fixed_bytes!(
+    /// 48 bytes, e.g. a BLS public key
+    Fil48,
+    48,
+    fil_ct_eq_fil48
+);
+fixed_bytes!(
+    /// 96 bytes, e.g. a BLS signature
+    Fil96,
+    96,
+    fil_ct_eq_fil96
+);
+
+impl From<FfiCommitment> for Fil32 {
+    fn from(commitment: FfiCommitment) -> Self {
+        Fil32(commitment.0)
+    }
+}
+
+impl From<Fil32> for FfiCommitment {
+    fn from(bytes: Fil32) -> Self {
+        FfiCommitment(bytes.0)
+    }
+}
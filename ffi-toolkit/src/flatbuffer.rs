@@ -0,0 +1,72 @@
+//! Zero-copy views of flatbuffers provided by the host, for large read-mostly inputs such as
+//! sector metadata tables.
+//!
+//! The buffer is verified once up front, every offset in it is bounds-checked, afterwards the
+//! generated accessors read straight from the host's memory.
+
+use std::fmt;
+use std::slice;
+
+use flatbuffers::{Follow, InvalidFlatbuffer, Verifiable, VerifierOptions};
+
+use crate::{error_response, CodeAndMessage, FCPResponseStatus};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlatbufferError {
+    Null,
+    Invalid(InvalidFlatbuffer),
+}
+
+impl FlatbufferError {
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<T: Default + CodeAndMessage>(&self, name: &str) -> *mut T {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+impl fmt::Display for FlatbufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlatbufferError::Null => write!(f, "null flatbuffer"),
+            FlatbufferError::Invalid(err) => write!(f, "invalid flatbuffer: {}", err),
+        }
+    }
+}
+
+/// Verifies `bytes` and returns its root of type `T`, e.g. a table generated by `flatc`
+pub fn flatbuffer_view<'a, T>(bytes: &'a [u8]) -> Result<T::Inner, FlatbufferError>
+where
+    T: 'a + Follow<'a> + Verifiable,
+{
+    flatbuffer_view_with_opts::<T>(bytes, &VerifierOptions::default())
+}
+
+/// Like `flatbuffer_view()`, with custom limits for the verification
+pub fn flatbuffer_view_with_opts<'a, T>(
+    bytes: &'a [u8],
+    opts: &VerifierOptions,
+) -> Result<T::Inner, FlatbufferError>
+where
+    T: 'a + Follow<'a> + Verifiable,
+{
+    flatbuffers::root_with_opts::<T>(opts, bytes).map_err(FlatbufferError::Invalid)
+}
+
+/// Like `flatbuffer_view()` for the `len` bytes at `ptr`
+///
+/// The bytes need to stay valid and unchanged for `'a`, e.g. for the duration of the call.
+pub unsafe fn flatbuffer_view_raw<'a, T>(
+    ptr: *const u8,
+    len: usize,
+) -> Result<T::Inner, FlatbufferError>
+where
+    T: 'a + Follow<'a> + Verifiable,
+{
+    if ptr.is_null() {
+        return Err(FlatbufferError::Null);
+    }
+    flatbuffer_view::<T>(slice::from_raw_parts(ptr, len))
+}
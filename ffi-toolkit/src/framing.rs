@@ -0,0 +1,149 @@
+//! Length-delimited protobuf messages, as written by `writeDelimitedTo()` and prost's
+//! `encode_length_delimited()`: each message is prefixed with its length as a varint.
+//!
+//! The toolkit doesn't depend on a protobuf library, messages are encoded and decoded through
+//! `MessageCodec`, which is a few lines to implement for prost's `Message` types.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::FfiBytes;
+
+/// The default limit on the length of a single message, against hosts sending garbage lengths
+pub const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+// A `u64` takes at most 10 bytes as a varint
+const MAX_VARINT_LEN: usize = 10;
+
+/// A message type, e.g. generated by prost
+pub trait MessageCodec: Sized {
+    /// Appends the encoded message to `buf`
+    fn encode(&self, buf: &mut Vec<u8>);
+    fn decode(bytes: &[u8]) -> Result<Self, String>;
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    Io(io::Error),
+    /// The input ended within a length prefix or a message
+    Truncated,
+    /// The length prefix isn't a valid varint
+    InvalidLength,
+    TooLong {
+        len: u64,
+        max: usize,
+    },
+    /// The codec rejected the message
+    Decode(String),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Io(err) => write!(f, "{}", err),
+            FrameError::Truncated => write!(f, "truncated message"),
+            FrameError::InvalidLength => write!(f, "invalid length prefix"),
+            FrameError::TooLong { len, max } => {
+                write!(f, "message of {} bytes exceeds the limit of {}", len, max)
+            }
+            FrameError::Decode(message) => write!(f, "invalid message: {}", message),
+        }
+    }
+}
+
+pub(crate) fn write_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// returns `None` if `reader` is at its end before the first byte
+pub(crate) fn read_varint<R: Read>(reader: &mut R) -> Result<Option<u64>, FrameError> {
+    let mut value = 0u64;
+    for index in 0..MAX_VARINT_LEN {
+        let mut byte = [0];
+        match reader.read_exact(&mut byte) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return if index == 0 {
+                    Ok(None)
+                } else {
+                    Err(FrameError::Truncated)
+                };
+            }
+            Err(err) => return Err(FrameError::Io(err)),
+        }
+        let bits = (byte[0] & 0x7f) as u64;
+        if index == MAX_VARINT_LEN - 1 && bits > 1 {
+            return Err(FrameError::InvalidLength);
+        }
+        value |= bits << (7 * index);
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    Err(FrameError::InvalidLength)
+}
+
+/// Appends `message` with its length prefix to `buf`
+pub fn encode_length_delimited<M: MessageCodec>(message: &M, buf: &mut Vec<u8>) {
+    let mut encoded = Vec::new();
+    message.encode(&mut encoded);
+    write_varint(encoded.len() as u64, buf);
+    buf.extend_from_slice(&encoded);
+}
+
+/// The messages with their length prefixes, one after the other
+pub fn encode_length_delimited_all<M: MessageCodec>(messages: &[M]) -> FfiBytes {
+    let mut buf = Vec::new();
+    for message in messages {
+        encode_length_delimited(message, &mut buf);
+    }
+    FfiBytes::new(buf)
+}
+
+/// Decodes all the length-delimited messages in `bytes`
+pub fn decode_length_delimited_all<M: MessageCodec>(
+    mut bytes: &[u8],
+    max_len: usize,
+) -> Result<Vec<M>, FrameError> {
+    let mut messages = Vec::new();
+    while let Some(message) = read_length_delimited(&mut bytes, max_len)? {
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+/// Writes `message` with its length prefix
+pub fn write_length_delimited<M: MessageCodec, W: Write>(
+    writer: &mut W,
+    message: &M,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    encode_length_delimited(message, &mut buf);
+    writer.write_all(&buf)
+}
+
+/// Reads the next length-delimited message, `None` if `reader` is at its end
+pub fn read_length_delimited<M: MessageCodec, R: Read>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Option<M>, FrameError> {
+    let len = match read_varint(reader)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    if len > max_len as u64 {
+        return Err(FrameError::TooLong { len, max: max_len });
+    }
+    let mut encoded = vec![0; len as usize];
+    reader
+        .read_exact(&mut encoded)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => FrameError::Truncated,
+            _ => FrameError::Io(err),
+        })?;
+    M::decode(&encoded).map(Some).map_err(FrameError::Decode)
+}
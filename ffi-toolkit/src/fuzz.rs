@@ -0,0 +1,343 @@
+//! Deterministic fuzzing entry points for the conversion helpers, and generators of hostile
+//! arguments for exported functions.
+//!
+//! Every entry point accepts arbitrary bytes, never panics for any input and only fails an
+//! assertion if a conversion helper breaks its contract. They can be plugged into cargo-fuzz
+//! with `ffi_fuzz_target!`:
+//!
+//! ```ignore
+//! #![no_main]
+//! ffi_toolkit::ffi_fuzz_target!(fuzz_c_str_round_trip);
+//! ```
+//!
+//! Exported functions are fuzzed with the arguments a careless or hostile C caller passes:
+//! `FuzzCStr` and `FuzzSlice` are `arbitrary` generators of null pointers, invalid UTF-8,
+//! interior nuls, unaligned pointers and lengths that don't match the memory, and
+//! `fuzz_export()` calls a function with them:
+//!
+//! ```ignore
+//! #![no_main]
+//! use ffi_toolkit::fuzz::{fuzz_export, FuzzCStr, FuzzSlice};
+//!
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     fuzz_export(
+//!         data,
+//!         |(path, proof): &(FuzzCStr, FuzzSlice<u8>)| unsafe {
+//!             fil_verify_seal(path.as_ptr(), proof.as_ptr(), proof.len())
+//!         },
+//!         fil_destroy_verify_seal_response,
+//!     );
+//! });
+//! ```
+//!
+//! The generated pointers are only invalid in ways a function can detect: they are null,
+//! unaligned or have a length whose size overflows `isize`, but never point at memory that
+//! isn't allocated.
+
+use std::ffi::CStr;
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{
+    c_str_to_pbuf, c_str_to_rust_str, free_c_str, rust_str_to_c_str, FfiDuration, FfiI128,
+    FfiTimestamp, FfiU128, StringRef,
+};
+
+/// A fuzzing entry point
+pub type FuzzTarget = fn(&[u8]);
+
+/// All entry points, by name
+pub const TARGETS: &[(&str, FuzzTarget)] = &[
+    ("c_str_to_rust_str", fuzz_c_str_to_rust_str),
+    ("c_str_round_trip", fuzz_c_str_round_trip),
+    ("c_str_to_pbuf", fuzz_c_str_to_pbuf),
+];
+
+/// Generates a cargo-fuzz target calling one of the entry points of this module
+///
+/// The fuzz crate needs to be `#![no_main]` and depend on `libfuzzer-sys`.
+#[macro_export]
+macro_rules! ffi_fuzz_target {
+    ($target:ident) => {
+        ::libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+            $crate::fuzz::$target(data);
+        });
+    };
+}
+
+/// Inputs the helpers are known to be sensitive to, useful as a seed corpus
+pub fn hostile_inputs() -> Vec<Vec<u8>> {
+    vec![
+        vec![],
+        vec![0],
+        b"interior\0nul".to_vec(),
+        b"trailing nul\0".to_vec(),
+        vec![0xff, 0xfe, 0xfd],
+        // Overlong encoding of '/'
+        vec![0xc0, 0xaf],
+        // Unpaired surrogate
+        vec![0xed, 0xa0, 0x80],
+        // Truncated multi-byte sequence
+        vec![b'a', 0xe2, 0x82],
+        "unicode \u{1f980}".as_bytes().to_vec(),
+        vec![b'a'; 4096],
+    ]
+}
+
+// the bytes a C caller would pass: everything up to the first nul, nul-terminated
+fn to_c_bytes(data: &[u8]) -> Vec<u8> {
+    let end = data
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(data.len());
+    let mut c_bytes = data[..end].to_vec();
+    c_bytes.push(0);
+    c_bytes
+}
+
+pub fn fuzz_c_str_to_rust_str(data: &[u8]) {
+    let c_bytes = to_c_bytes(data);
+    let converted = unsafe { c_str_to_rust_str(c_bytes.as_ptr() as *const libc::c_char) };
+    let expected = String::from_utf8_lossy(&c_bytes[..c_bytes.len() - 1]);
+    assert_eq!(converted, expected);
+}
+
+pub fn fuzz_c_str_round_trip(data: &[u8]) {
+    let c_bytes = to_c_bytes(data);
+    unsafe {
+        let rust_str = c_str_to_rust_str(c_bytes.as_ptr() as *const libc::c_char);
+        let ptr = rust_str_to_c_str(rust_str.clone());
+        assert_eq!(CStr::from_ptr(ptr).to_string_lossy(), rust_str);
+        free_c_str(ptr);
+    }
+}
+
+pub fn fuzz_c_str_to_pbuf(data: &[u8]) {
+    let c_bytes = to_c_bytes(data);
+    let path = unsafe { c_str_to_pbuf(c_bytes.as_ptr() as *const libc::c_char) };
+    let expected = String::from_utf8_lossy(&c_bytes[..c_bytes.len() - 1]);
+    assert_eq!(path.to_string_lossy(), expected);
+}
+
+/// A C string argument: null, or nul-terminated bytes that may be invalid UTF-8 and may contain
+/// a nul byte, where the string C sees ends
+#[derive(Debug, Clone)]
+pub struct FuzzCStr {
+    // With the terminating nul, `None` for a null pointer
+    bytes: Option<Vec<u8>>,
+}
+
+impl FuzzCStr {
+    /// The pointer to pass, valid as long as `self`
+    pub fn as_ptr(&self) -> *const libc::c_char {
+        self.bytes
+            .as_ref()
+            .map_or(ptr::null(), |bytes| bytes.as_ptr() as *const libc::c_char)
+    }
+
+    /// The bytes without the terminating nul, `None` for a null pointer
+    pub fn bytes(&self) -> Option<&[u8]> {
+        self.bytes.as_ref().map(|bytes| &bytes[..bytes.len() - 1])
+    }
+}
+
+impl<'a> Arbitrary<'a> for FuzzCStr {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bytes = match u.int_in_range(0..=7)? {
+            0 => None,
+            1 => Some(u.choose(&hostile_inputs())?.clone()),
+            _ => Some(Vec::<u8>::arbitrary(u)?),
+        };
+        Ok(FuzzCStr {
+            bytes: bytes.map(|mut bytes| {
+                bytes.push(0);
+                bytes
+            }),
+        })
+    }
+}
+
+/// How a `FuzzSlice` passes its elements
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SliceShape {
+    /// The pointer and length of the elements
+    Valid,
+    /// A null pointer with the length 0
+    Null,
+    /// A null pointer with the length of the elements, at least 1
+    NullWithLength,
+    /// The elements at an address that isn't aligned for them, with their length
+    Unaligned,
+    /// The pointer of the elements with half their length, e.g. a stale length field
+    Shorter,
+    /// The pointer of the elements with a length whose size in bytes overflows `isize`
+    OverflowingLength,
+}
+
+const SHAPES: &[SliceShape] = &[
+    SliceShape::Valid,
+    SliceShape::Valid,
+    SliceShape::Valid,
+    SliceShape::Null,
+    SliceShape::NullWithLength,
+    SliceShape::Unaligned,
+    SliceShape::Shorter,
+    SliceShape::OverflowingLength,
+];
+
+/// An array argument passed as a pointer and a length, see `SliceShape`
+#[derive(Debug, Clone)]
+pub struct FuzzSlice<T> {
+    elements: Vec<T>,
+    shape: SliceShape,
+    // A copy of the elements one byte after an aligned address, for `SliceShape::Unaligned`
+    unaligned: Vec<MaybeUninit<u8>>,
+}
+
+impl<T: Copy> FuzzSlice<T> {
+    pub fn new(elements: Vec<T>, shape: SliceShape) -> Self {
+        // Bytes are always aligned
+        let shape = match shape {
+            SliceShape::Unaligned if mem::align_of::<T>() == 1 => SliceShape::Valid,
+            shape => shape,
+        };
+        let mut unaligned = Vec::new();
+        if shape == SliceShape::Unaligned {
+            let size = mem::size_of_val(elements.as_slice());
+            unaligned = vec![MaybeUninit::uninit(); size + 1];
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    elements.as_ptr() as *const MaybeUninit<u8>,
+                    unaligned.as_mut_ptr().add(1),
+                    size,
+                )
+            };
+        }
+        FuzzSlice {
+            elements,
+            shape,
+            unaligned,
+        }
+    }
+
+    /// The pointer to pass, valid as long as `self`
+    pub fn as_ptr(&self) -> *const T {
+        match self.shape {
+            SliceShape::Null | SliceShape::NullWithLength => ptr::null(),
+            SliceShape::Unaligned => unsafe { self.unaligned.as_ptr().add(1) as *const T },
+            _ => self.elements.as_ptr(),
+        }
+    }
+
+    /// The length to pass
+    pub fn len(&self) -> usize {
+        match self.shape {
+            SliceShape::Valid | SliceShape::Unaligned => self.elements.len(),
+            SliceShape::Null => 0,
+            SliceShape::NullWithLength => self.elements.len().max(1),
+            SliceShape::Shorter => self.elements.len() / 2,
+            SliceShape::OverflowingLength => usize::MAX / mem::size_of::<T>().max(1),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The elements the pointer and length are made of
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+    pub fn shape(&self) -> SliceShape {
+        self.shape
+    }
+}
+
+impl FuzzSlice<u8> {
+    /// The bytes as a `StringRef`, valid as long as `self`
+    pub fn as_string_ref(&self) -> StringRef {
+        StringRef {
+            ptr: self.as_ptr(),
+            len: self.len(),
+        }
+    }
+}
+
+impl<'a, T: Arbitrary<'a> + Copy> Arbitrary<'a> for FuzzSlice<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let shape = *u.choose(SHAPES)?;
+        Ok(FuzzSlice::new(Vec::arbitrary(u)?, shape))
+    }
+}
+
+// `nanos` isn't limited, values from C may be out of range
+impl<'a> Arbitrary<'a> for FfiDuration {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FfiDuration {
+            secs: u.arbitrary()?,
+            nanos: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for FfiTimestamp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FfiTimestamp {
+            secs: u.arbitrary()?,
+            nanos: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for FfiU128 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FfiU128 {
+            hi: u.arbitrary()?,
+            lo: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for FfiI128 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FfiI128 {
+            hi: u.arbitrary()?,
+            lo: u.arbitrary()?,
+        })
+    }
+}
+
+/// Generates arguments from `data` and calls `call` with them, `None` if `data` doesn't make
+/// up the arguments
+///
+/// The arguments own the memory their pointers point at, it stays valid for the call.
+pub fn fuzz_extern<'a, A, R, F>(data: &'a [u8], call: F) -> Option<R>
+where
+    A: Arbitrary<'a>,
+    F: FnOnce(&A) -> R,
+{
+    let args = A::arbitrary_take_rest(Unstructured::new(data)).ok()?;
+    Some(call(&args))
+}
+
+/// Like `fuzz_extern()`, for an exported function returning a response, which is freed with
+/// `destroy`
+///
+/// Fails an assertion if the function returns null instead of a response, an exported function
+/// reports even hostile arguments in its response.
+pub fn fuzz_export<'a, A, T, F>(data: &'a [u8], call: F, destroy: unsafe extern "C" fn(*mut T))
+where
+    A: Arbitrary<'a>,
+    F: FnOnce(&A) -> *mut T,
+{
+    if let Some(response) = fuzz_extern(data, call) {
+        assert!(
+            !response.is_null(),
+            "the exported function returned a null response"
+        );
+        unsafe { destroy(response) };
+    }
+}
@@ -0,0 +1,179 @@
+//! Opaque handles to Rust objects that are owned by the host.
+//!
+//! Instead of a raw pointer, C gets a `u64` handle, made of the index of a slot in the registry
+//! and the generation of that slot. Releasing a handle bumps the generation, so a stale handle
+//! (used after it was released, or released twice) is detected instead of dereferencing freed
+//! memory. The type of the object is checked as well. Handle 0 is never handed out.
+
+use std::any::{self, Any, TypeId};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{error_response, CodeAndMessage, FCPResponseStatus};
+
+type Object = Arc<Mutex<Box<dyn Any + Send>>>;
+
+#[derive(Default)]
+struct Slot {
+    // Odd while the slot is in use, so a handle never is 0
+    generation: u32,
+    object: Option<(TypeId, Object)>,
+}
+
+#[derive(Default)]
+struct Registry {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    cleanup_registered: bool,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+/// Why a handle can't be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandleError {
+    /// The handle was never handed out or was released already
+    Stale,
+    /// The handle refers to an object of a different type
+    WrongType { expected: &'static str },
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandleError::Stale => write!(f, "unknown or released handle"),
+            HandleError::WrongType { expected } => {
+                write!(f, "the handle doesn't refer to a `{}`", expected)
+            }
+        }
+    }
+}
+
+impl Error for HandleError {}
+
+impl HandleError {
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<R: Default + CodeAndMessage>(&self, name: &str) -> *mut R {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+fn split(handle: u64) -> (usize, u32) {
+    ((handle & 0xffff_ffff) as usize, (handle >> 32) as u32)
+}
+
+/// Hands out a handle to `value`, which lives until the handle is released
+pub fn register<T: Any + Send>(value: T) -> u64 {
+    let mut registry = REGISTRY.lock().unwrap();
+    let registry = registry.get_or_insert_with(Registry::default);
+    if !registry.cleanup_registered {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Registries, release_all);
+        registry.cleanup_registered = true;
+    }
+    let index = match registry.free.pop() {
+        Some(index) => index,
+        None => {
+            registry.slots.push(Slot::default());
+            u32::try_from(registry.slots.len() - 1).expect("too many handles")
+        }
+    };
+    let slot = &mut registry.slots[index as usize];
+    slot.generation = slot.generation.wrapping_add(1);
+    let object: Box<dyn Any + Send> = Box::new(value);
+    slot.object = Some((TypeId::of::<T>(), Arc::new(Mutex::new(object))));
+    (u64::from(slot.generation) << 32) | u64::from(index)
+}
+
+// the object behind a valid handle of a `T`
+fn lookup<T: Any>(registry: &Option<Registry>, handle: u64) -> Result<&Object, HandleError> {
+    let (index, generation) = split(handle);
+    match registry
+        .as_ref()
+        .and_then(|registry| registry.slots.get(index))
+    {
+        Some(Slot {
+            generation: current,
+            object: Some((type_id, object)),
+        }) if *current == generation => {
+            if *type_id == TypeId::of::<T>() {
+                Ok(object)
+            } else {
+                Err(HandleError::WrongType {
+                    expected: any::type_name::<T>(),
+                })
+            }
+        }
+        _ => Err(HandleError::Stale),
+    }
+}
+
+/// Calls `f` with the object behind `handle`
+///
+/// The registry isn't locked while `f` runs, only the object itself, so calls with different
+/// handles run concurrently. Calls with the same handle are serialized.
+pub fn with_handle<T: Any + Send, R>(
+    handle: u64,
+    f: impl FnOnce(&mut T) -> R,
+) -> Result<R, HandleError> {
+    let object = Arc::clone(lookup::<T>(&REGISTRY.lock().unwrap(), handle)?);
+    let mut object = object
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let value = object
+        .downcast_mut::<T>()
+        .expect("the type was checked on lookup");
+    Ok(f(value))
+}
+
+/// Invalidates `handle`, the object is dropped once no `with_handle()` call uses it anymore
+pub fn release<T: Any + Send>(handle: u64) -> Result<(), HandleError> {
+    let object = {
+        let mut registry = REGISTRY.lock().unwrap();
+        lookup::<T>(&registry, handle)?;
+        let (index, _) = split(handle);
+        let registry = registry.as_mut().expect("the handle was looked up");
+        free_slot(registry, index)
+    };
+    // Outside of the lock, as dropping the object may use other handles
+    drop(object);
+    Ok(())
+}
+
+fn free_slot(registry: &mut Registry, index: usize) -> Option<(TypeId, Object)> {
+    let slot = &mut registry.slots[index];
+    // Even, until the slot is reused
+    slot.generation = slot.generation.wrapping_add(1);
+    registry.free.push(index as u32);
+    slot.object.take()
+}
+
+/// Whether `handle` refers to a live object
+pub fn is_live(handle: u64) -> bool {
+    let (index, generation) = split(handle);
+    REGISTRY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|registry| registry.slots.get(index))
+        .is_some_and(|slot| slot.generation == generation && slot.object.is_some())
+}
+
+// The slots are kept, so that handles of before the shutdown stay stale afterwards
+fn release_all() {
+    let mut objects = Vec::new();
+    if let Some(registry) = REGISTRY.lock().unwrap().as_mut() {
+        registry.cleanup_registered = false;
+        for index in 0..registry.slots.len() {
+            if registry.slots[index].object.is_some() {
+                objects.extend(free_slot(registry, index));
+            }
+        }
+    }
+    drop(objects);
+}
@@ -0,0 +1,95 @@
+//! Hashing of files and streams in chunks, so that hashing a sector doesn't need the whole
+//! sector in memory.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::FfiBytes;
+
+/// The chunk size `hash_file_chunked()` reads with
+pub const HASH_CHUNK_SIZE: usize = 1 << 20;
+
+/// A hash function that is fed chunk by chunk
+///
+/// The toolkit doesn't ship hash functions, implement this for the one of your crypto library.
+pub trait ChunkedDigest {
+    fn update(&mut self, chunk: &[u8]);
+    fn finalize(self) -> Vec<u8>;
+}
+
+/// How far hashing got, passed to the progress callback after every chunk
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct HashProgress {
+    /// The number of bytes hashed so far
+    pub processed: u64,
+    /// The total number of bytes, if known up front
+    pub total: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum HashError {
+    Io(io::Error),
+    /// The progress callback asked to stop
+    Cancelled,
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashError::Io(err) => write!(f, "{}", err),
+            HashError::Cancelled => write!(f, "hashing was cancelled"),
+        }
+    }
+}
+
+impl From<io::Error> for HashError {
+    fn from(err: io::Error) -> Self {
+        HashError::Io(err)
+    }
+}
+
+/// Hashes everything `reader` returns in chunks of `chunk_size` bytes
+///
+/// `progress` is called after every chunk, returning `false` from it cancels the hashing.
+pub fn hash_chunked<R, D, P>(
+    mut reader: R,
+    mut digest: D,
+    chunk_size: usize,
+    total: Option<u64>,
+    mut progress: P,
+) -> Result<FfiBytes, HashError>
+where
+    R: Read,
+    D: ChunkedDigest,
+    P: FnMut(HashProgress) -> bool,
+{
+    let mut chunk = vec![0; chunk_size.max(1)];
+    let mut processed = 0;
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(HashError::Io(err)),
+        };
+        digest.update(&chunk[..read]);
+        processed += read as u64;
+        if !progress(HashProgress { processed, total }) {
+            return Err(HashError::Cancelled);
+        }
+    }
+    Ok(FfiBytes::new(digest.finalize()))
+}
+
+/// Hashes the file at `path` in chunks of `HASH_CHUNK_SIZE` bytes, see `hash_chunked()`
+pub fn hash_file_chunked<D, P>(path: &Path, digest: D, progress: P) -> Result<FfiBytes, HashError>
+where
+    D: ChunkedDigest,
+    P: FnMut(HashProgress) -> bool,
+{
+    let file = File::open(path)?;
+    let total = file.metadata()?.len();
+    hash_chunked(file, digest, HASH_CHUNK_SIZE, Some(total), progress)
+}
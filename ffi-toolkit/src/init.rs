@@ -0,0 +1,279 @@
+//! Initialization with one configuration, instead of a setter per knob.
+//!
+//! The host fills in a `FfiConfig` and calls `fil_init()` once, before anything else. The
+//! toolkit's own settings (task threads, allocator, panic backtraces, allocation statistics and,
+//! with the `log` feature, the log level) are applied, and the whole configuration, including the
+//! consumer's own `settings`, is available as `init_config()` from then on.
+//!
+//! The struct is versioned: the host sets `version` to the `FFI_CONFIG_VERSION` and `size` to the
+//! size of the struct it was built against. Fields are only ever appended, a library reads the
+//! fields it knows and ignores the ones of newer hosts.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::OnceLock;
+
+#[cfg(feature = "log")]
+use crate::FfiLogLevel;
+use crate::{
+    enable_alloc_stats, lifecycle, set_allocator, set_last_error, set_task_threads,
+    try_slice_from_raw, FCPResponseStatus, FfiFreeFn, FfiMallocFn, IntoFFIError, StringRef,
+};
+
+/// The version of `FfiConfig` this library was built with
+pub const FFI_CONFIG_VERSION: u32 = 1;
+
+/// `FfiConfig::log_level` to leave the log level as is
+pub const FIL_LOG_UNCHANGED: libc::c_int = -1;
+
+/// The configuration passed to `fil_init()`
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FfiConfig {
+    /// `FFI_CONFIG_VERSION` of the host
+    pub version: u32,
+    /// `sizeof(FfiConfig)` of the host
+    pub size: libc::size_t,
+    /// The number of task threads, 0 for one per CPU
+    pub task_threads: libc::size_t,
+    pub panic_backtraces: bool,
+    pub alloc_stats: bool,
+    /// A `FIL_LOG_*` level, or `FIL_LOG_UNCHANGED`
+    pub log_level: libc::c_int,
+    /// The allocator for everything handed out to the host, both or none of them are null
+    pub malloc: Option<FfiMallocFn>,
+    pub free: Option<FfiFreeFn>,
+    /// The consumer's settings, `settings_keys_ptr[i]` has the value `settings_values_ptr[i]`
+    pub settings_keys_ptr: *const StringRef,
+    pub settings_values_ptr: *const StringRef,
+    pub settings_len: libc::size_t,
+}
+
+impl Default for FfiConfig {
+    fn default() -> Self {
+        FfiConfig {
+            version: FFI_CONFIG_VERSION,
+            size: mem::size_of::<FfiConfig>(),
+            task_threads: 0,
+            panic_backtraces: false,
+            alloc_stats: false,
+            log_level: FIL_LOG_UNCHANGED,
+            malloc: None,
+            free: None,
+            settings_keys_ptr: std::ptr::null(),
+            settings_values_ptr: std::ptr::null(),
+            settings_len: 0,
+        }
+    }
+}
+
+/// The validated configuration, see `init_config()`
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// `None` for one per CPU
+    pub task_threads: Option<usize>,
+    pub panic_backtraces: bool,
+    pub alloc_stats: bool,
+    /// `None` to leave the log level as is
+    pub log_level: Option<libc::c_int>,
+    pub allocator: Option<(FfiMallocFn, FfiFreeFn)>,
+    pub settings: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn setting(&self, key: &str) -> Option<&str> {
+        self.settings.get(key).map(String::as_str)
+    }
+}
+
+/// Why `fil_init()` failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitError {
+    /// The host's `FfiConfig` is older than the first version or smaller than its size
+    UnsupportedConfig {
+        version: u32,
+        size: usize,
+    },
+    /// Only one of `malloc` and `free` is set
+    IncompleteAllocator,
+    InvalidLogLevel(libc::c_int),
+    /// A settings array is null, or a key or value isn't UTF-8
+    InvalidSettings(String),
+    AlreadyInitialized,
+    /// The allocator can't be changed after the first allocation
+    AllocatorInUse,
+    /// The task threads can't be changed while the pool is running
+    TaskPoolRunning,
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitError::UnsupportedConfig { version, size } => write!(
+                f,
+                "unsupported config version {} of {} bytes, expected version {}+ of {}+ bytes",
+                version,
+                size,
+                FFI_CONFIG_VERSION,
+                mem::size_of::<FfiConfig>()
+            ),
+            InitError::IncompleteAllocator => {
+                write!(f, "`malloc` and `free` must both be set or both be null")
+            }
+            InitError::InvalidLogLevel(level) => write!(f, "invalid log level {}", level),
+            InitError::InvalidSettings(message) => write!(f, "invalid settings: {}", message),
+            InitError::AlreadyInitialized => write!(f, "the library is initialized already"),
+            InitError::AllocatorInUse => {
+                write!(f, "the allocator can't be set after the first allocation")
+            }
+            InitError::TaskPoolRunning => {
+                write!(f, "the task threads can't be set while tasks are running")
+            }
+        }
+    }
+}
+
+impl Error for InitError {}
+
+impl IntoFFIError for InitError {
+    fn code(&self) -> FCPResponseStatus {
+        match self {
+            InitError::AllocatorInUse | InitError::TaskPoolRunning => {
+                FCPResponseStatus::FCPReceiverError
+            }
+            _ => FCPResponseStatus::FCPCallerError,
+        }
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+unsafe fn settings(config: &FfiConfig) -> Result<HashMap<String, String>, InitError> {
+    let array = |ptr, name| {
+        try_slice_from_raw(ptr, config.settings_len)
+            .ok_or_else(|| InitError::InvalidSettings(format!("`{}` is null", name)))
+    };
+    let keys = array(config.settings_keys_ptr, "settings_keys_ptr")?;
+    let values = array(config.settings_values_ptr, "settings_values_ptr")?;
+    keys.iter()
+        .zip(values)
+        .map(|(key, value)| {
+            let key = key
+                .as_str()
+                .map_err(|_| InitError::InvalidSettings("a key isn't UTF-8".to_string()))?;
+            let value = value.as_str().map_err(|_| {
+                InitError::InvalidSettings(format!("the value of `{}` isn't UTF-8", key))
+            })?;
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Validates the host's configuration and copies it
+///
+/// The settings arrays must be null or valid for reads of `settings_len` elements, and each of
+/// their strings as described by `StringRef::as_bytes()`.
+pub unsafe fn parse_ffi_config(config: &FfiConfig) -> Result<Config, InitError> {
+    if config.version == 0 || config.size < mem::size_of::<FfiConfig>() {
+        return Err(InitError::UnsupportedConfig {
+            version: config.version,
+            size: config.size,
+        });
+    }
+    let allocator = match (config.malloc, config.free) {
+        (Some(malloc), Some(free)) => Some((malloc, free)),
+        (None, None) => None,
+        _ => return Err(InitError::IncompleteAllocator),
+    };
+    let log_level = match config.log_level {
+        FIL_LOG_UNCHANGED => None,
+        level @ 0..=5 => Some(level),
+        level => return Err(InitError::InvalidLogLevel(level)),
+    };
+    Ok(Config {
+        task_threads: match config.task_threads {
+            0 => None,
+            threads => Some(threads),
+        },
+        panic_backtraces: config.panic_backtraces,
+        alloc_stats: config.alloc_stats,
+        log_level,
+        allocator,
+        settings: settings(config)?,
+    })
+}
+
+#[cfg(feature = "log")]
+fn set_log_level(level: libc::c_int) {
+    if let Some(&(level, _, _)) = FfiLogLevel::VARIANTS
+        .iter()
+        .find(|(_, _, value)| *value == level)
+    {
+        log::set_max_level(level.into());
+    }
+}
+
+#[cfg(not(feature = "log"))]
+fn set_log_level(_level: libc::c_int) {}
+
+/// Initializes the toolkit (see `lifecycle::init()`) and applies the configuration
+///
+/// Only the first successful call has an effect, later ones fail with `AlreadyInitialized`.
+pub fn init_with_config(config: Config) -> Result<&'static Config, InitError> {
+    if CONFIG.get().is_some() {
+        return Err(InitError::AlreadyInitialized);
+    }
+    // The allocator first, as it can only be set before the first allocation
+    if let Some((malloc, free)) = config.allocator {
+        if !set_allocator(malloc, free) {
+            return Err(InitError::AllocatorInUse);
+        }
+    }
+    if !set_task_threads(config.task_threads.unwrap_or(0)) {
+        return Err(InitError::TaskPoolRunning);
+    }
+    lifecycle::init();
+    lifecycle::enable_panic_backtraces(config.panic_backtraces);
+    enable_alloc_stats(config.alloc_stats);
+    if let Some(level) = config.log_level {
+        set_log_level(level);
+    }
+    let mut stored = false;
+    let config = CONFIG.get_or_init(|| {
+        stored = true;
+        config
+    });
+    if stored {
+        Ok(config)
+    } else {
+        Err(InitError::AlreadyInitialized)
+    }
+}
+
+/// The configuration of `fil_init()`, `None` before it succeeded
+pub fn init_config() -> Option<&'static Config> {
+    CONFIG.get()
+}
+
+/// Initializes the library with `config`, a null pointer is the default configuration
+///
+/// The error of a failure is the thread's last error. An invalid configuration applies nothing,
+/// so the host can fix it and try again.
+#[no_mangle]
+pub unsafe extern "C" fn fil_init(config: *const FfiConfig) -> FCPResponseStatus {
+    let default = FfiConfig::default();
+    let config = config.as_ref().unwrap_or(&default);
+    match parse_ffi_config(config).and_then(init_with_config) {
+        Ok(_) => FCPResponseStatus::FCPNoError,
+        Err(err) => {
+            set_last_error(err.code(), err.message());
+            err.code()
+        }
+    }
+}
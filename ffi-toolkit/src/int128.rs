@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::fmt;
+use std::num::{ParseIntError, TryFromIntError};
+use std::str::FromStr;
+
+use crate::FfiString;
+
+/// A `u128` that can cross the FFI boundary.
+///
+/// `u128` has no stable C ABI, so the value is split into two `u64` halves. The conversions
+/// below only move bits around, the arithmetic is done on the `u128`. Amounts are passed like
+/// this rather than as decimal strings, `to_string()` and `from_str()` are for the host's
+/// logs and configs.
+#[repr(C)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Default, Hash)]
+pub struct FfiU128 {
+    pub hi: u64,
+    pub lo: u64,
+}
+
+impl FfiU128 {
+    pub const ZERO: FfiU128 = FfiU128 { hi: 0, lo: 0 };
+    pub const MAX: FfiU128 = FfiU128 {
+        hi: u64::MAX,
+        lo: u64::MAX,
+    };
+
+    pub const fn new(hi: u64, lo: u64) -> Self {
+        FfiU128 { hi, lo }
+    }
+
+    pub const fn from_u64(value: u64) -> Self {
+        FfiU128 { hi: 0, lo: value }
+    }
+
+    /// Returns the value if it fits into a `u64`
+    pub const fn to_u64(self) -> Option<u64> {
+        if self.hi == 0 {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    pub const fn to_u128(self) -> u128 {
+        ((self.hi as u128) << 64) | self.lo as u128
+    }
+
+    pub const fn from_u128(value: u128) -> Self {
+        FfiU128 {
+            hi: (value >> 64) as u64,
+            lo: value as u64,
+        }
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 16] {
+        self.to_u128().to_be_bytes()
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 16]) -> Self {
+        Self::from_u128(u128::from_be_bytes(bytes))
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 16] {
+        self.to_u128().to_le_bytes()
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 16]) -> Self {
+        Self::from_u128(u128::from_le_bytes(bytes))
+    }
+}
+
+impl From<u128> for FfiU128 {
+    fn from(value: u128) -> Self {
+        FfiU128::from_u128(value)
+    }
+}
+
+impl From<FfiU128> for u128 {
+    fn from(value: FfiU128) -> Self {
+        value.to_u128()
+    }
+}
+
+impl From<u64> for FfiU128 {
+    fn from(value: u64) -> Self {
+        FfiU128::from_u64(value)
+    }
+}
+
+impl TryFrom<FfiU128> for u64 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: FfiU128) -> Result<Self, Self::Error> {
+        u64::try_from(value.to_u128())
+    }
+}
+
+/// An `i128` that can cross the FFI boundary, e.g. a signed token amount
+///
+/// The two's complement of the value is split into two `u64` halves like for `FfiU128`, i.e.
+/// the sign is the top bit of `hi`.
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default, Hash)]
+pub struct FfiI128 {
+    pub hi: u64,
+    pub lo: u64,
+}
+
+impl FfiI128 {
+    pub const ZERO: FfiI128 = FfiI128 { hi: 0, lo: 0 };
+    pub const MIN: FfiI128 = FfiI128::from_i128(i128::MIN);
+    pub const MAX: FfiI128 = FfiI128::from_i128(i128::MAX);
+
+    pub const fn new(hi: u64, lo: u64) -> Self {
+        FfiI128 { hi, lo }
+    }
+
+    pub const fn from_i64(value: i64) -> Self {
+        Self::from_i128(value as i128)
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        (self.hi as i64) < 0
+    }
+
+    pub const fn to_i128(self) -> i128 {
+        (((self.hi as u128) << 64) | self.lo as u128) as i128
+    }
+
+    pub const fn from_i128(value: i128) -> Self {
+        FfiI128 {
+            hi: ((value as u128) >> 64) as u64,
+            lo: value as u64,
+        }
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 16] {
+        self.to_i128().to_be_bytes()
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 16]) -> Self {
+        Self::from_i128(i128::from_be_bytes(bytes))
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 16] {
+        self.to_i128().to_le_bytes()
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 16]) -> Self {
+        Self::from_i128(i128::from_le_bytes(bytes))
+    }
+}
+
+// The halves don't compare like the value, `hi` is signed
+impl PartialOrd for FfiI128 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FfiI128 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_i128().cmp(&other.to_i128())
+    }
+}
+
+impl From<i128> for FfiI128 {
+    fn from(value: i128) -> Self {
+        FfiI128::from_i128(value)
+    }
+}
+
+impl From<FfiI128> for i128 {
+    fn from(value: FfiI128) -> Self {
+        value.to_i128()
+    }
+}
+
+impl From<i64> for FfiI128 {
+    fn from(value: i64) -> Self {
+        FfiI128::from_i64(value)
+    }
+}
+
+impl TryFrom<FfiI128> for i64 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: FfiI128) -> Result<Self, Self::Error> {
+        i64::try_from(value.to_i128())
+    }
+}
+
+impl TryFrom<FfiU128> for FfiI128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: FfiU128) -> Result<Self, Self::Error> {
+        i128::try_from(value.to_u128()).map(FfiI128::from_i128)
+    }
+}
+
+impl TryFrom<FfiI128> for FfiU128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: FfiI128) -> Result<Self, Self::Error> {
+        u128::try_from(value.to_i128()).map(FfiU128::from_u128)
+    }
+}
+
+// The arithmetic and formatting of both types, on the native integer
+macro_rules! int128_impls {
+    ($ffi:ident, $int:ident, $to:ident, $from:ident) => {
+        impl $ffi {
+            pub fn checked_add(self, other: $ffi) -> Option<$ffi> {
+                self.$to().checked_add(other.$to()).map($ffi::$from)
+            }
+
+            pub fn checked_sub(self, other: $ffi) -> Option<$ffi> {
+                self.$to().checked_sub(other.$to()).map($ffi::$from)
+            }
+
+            pub fn checked_mul(self, other: $ffi) -> Option<$ffi> {
+                self.$to().checked_mul(other.$to()).map($ffi::$from)
+            }
+
+            /// `None` for a zero divisor or an overflow
+            pub fn checked_div(self, other: $ffi) -> Option<$ffi> {
+                self.$to().checked_div(other.$to()).map($ffi::$from)
+            }
+
+            pub fn checked_rem(self, other: $ffi) -> Option<$ffi> {
+                self.$to().checked_rem(other.$to()).map($ffi::$from)
+            }
+
+            pub fn saturating_add(self, other: $ffi) -> $ffi {
+                $ffi::$from(self.$to().saturating_add(other.$to()))
+            }
+
+            pub fn saturating_sub(self, other: $ffi) -> $ffi {
+                $ffi::$from(self.$to().saturating_sub(other.$to()))
+            }
+        }
+
+        impl fmt::Display for $ffi {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.$to(), f)
+            }
+        }
+
+        /// Parses a decimal number
+        impl FromStr for $ffi {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse::<$int>().map($ffi::$from)
+            }
+        }
+    };
+}
+
+int128_impls!(FfiU128, u128, to_u128, from_u128);
+int128_impls!(FfiI128, i128, to_i128, from_i128);
+
+// Parses the decimal number `s` into `*out`, which is left alone on failure
+unsafe fn parse_c_str<T: FromStr>(s: *const libc::c_char, out: *mut T) -> bool {
+    if s.is_null() || out.is_null() {
+        return false;
+    }
+    match CStr::from_ptr(s).to_str().ok().and_then(|s| s.parse().ok()) {
+        Some(value) => {
+            *out = value;
+            true
+        }
+        None => false,
+    }
+}
+
+/// The decimal string of `value`, free it with `fil_free_string()`
+#[no_mangle]
+pub extern "C" fn fil_u128_to_string(value: FfiU128) -> FfiString {
+    FfiString::new(value.to_string())
+}
+
+/// Parses the decimal C string `s`, returns whether it is a valid `u128`
+///
+/// `*out` is only written on success.
+#[no_mangle]
+pub unsafe extern "C" fn fil_parse_u128(s: *const libc::c_char, out: *mut FfiU128) -> bool {
+    parse_c_str(s, out)
+}
+
+/// The decimal string of `value`, free it with `fil_free_string()`
+#[no_mangle]
+pub extern "C" fn fil_i128_to_string(value: FfiI128) -> FfiString {
+    FfiString::new(value.to_string())
+}
+
+/// Like `fil_parse_u128()`, for a `i128` with an optional sign
+#[no_mangle]
+pub unsafe extern "C" fn fil_parse_i128(s: *const libc::c_char, out: *mut FfiI128) -> bool {
+    parse_c_str(s, out)
+}
@@ -0,0 +1,58 @@
+//! Interned C strings for constant error messages, which are never freed.
+//!
+//! An error path that reports the same constant message over and over allocates a C string for
+//! every response, and the host frees each of them. `intern_c_str()` instead returns one
+//! `'static` C string per message, and `free_c_str()` (and so the derived `Drop` impls)
+//! recognizes interned pointers and leaves them alone. Responses set such messages with
+//! `CodeAndMessage::set_error_static()`.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use crate::truncated_c_string;
+
+// The interned strings by message, and their addresses for the lookups of `free_c_str()`
+static BY_MESSAGE: Mutex<Option<HashMap<&'static str, &'static CStr>>> = Mutex::new(None);
+static ADDRESSES: RwLock<Option<HashSet<usize>>> = RwLock::new(None);
+
+// Set with the first interned string, before that a free doesn't need the lock
+static ANY_INTERNED: AtomicBool = AtomicBool::new(false);
+
+/// The interned C string of `message`, the same pointer for every call with the same message
+///
+/// The string lives until the process exits, freeing it with `free_c_str()` does nothing. A
+/// message with a nul byte is truncated at it.
+pub fn intern_c_str(message: &'static str) -> *const libc::c_char {
+    let mut by_message = BY_MESSAGE.lock().unwrap();
+    let by_message = by_message.get_or_insert_with(HashMap::new);
+    if let Some(interned) = by_message.get(message) {
+        return interned.as_ptr();
+    }
+    let c_string = truncated_c_string(message.to_string());
+    let interned: &'static CStr = Box::leak(CString::into_boxed_c_str(c_string));
+    ADDRESSES
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(interned.as_ptr().addr());
+    ANY_INTERNED.store(true, Ordering::SeqCst);
+    by_message.insert(message, interned);
+    interned.as_ptr()
+}
+
+/// Whether `ptr` was returned by `intern_c_str()`
+pub fn is_interned_c_str(ptr: *const libc::c_char) -> bool {
+    ANY_INTERNED.load(Ordering::SeqCst)
+        && ADDRESSES
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|addresses| addresses.contains(&ptr.addr()))
+}
+
+/// The number of interned strings
+pub fn interned_c_str_count() -> usize {
+    BY_MESSAGE.lock().unwrap().as_ref().map_or(0, HashMap::len)
+}
@@ -0,0 +1,105 @@
+//! Config-style arguments passed across the boundary as JSON C strings.
+
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    alloc, error_response, error_response_with_payload, CodeAndMessage, FCPResponseStatus,
+    FfiBytes, StatusCode,
+};
+
+/// A JSON string that couldn't be parsed, or a value that couldn't be serialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    message: String,
+    /// The 1-based position of a parse error, if there is one
+    pub line: usize,
+    pub column: usize,
+}
+
+impl JsonError {
+    fn new<S: Into<String>>(message: S) -> Self {
+        JsonError {
+            message: message.into(),
+            line: 0,
+            column: 0,
+        }
+    }
+
+    /// An `FCPCallerError` response saying that the argument `name` is invalid
+    pub fn caller_error<T: Default + CodeAndMessage>(&self, name: &str) -> *mut T {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, self),
+        )
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(err: serde_json::Error) -> Self {
+        // The message of `serde_json` ends with the position, which we format ourselves
+        let message = err.to_string();
+        let suffix = format!(" at line {} column {}", err.line(), err.column());
+        JsonError {
+            message: message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string(),
+            line: err.line(),
+            column: err.column(),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "invalid JSON: {}", self.message)
+        } else {
+            write!(
+                f,
+                "invalid JSON at line {}, column {}: {}",
+                self.line, self.column, self.message
+            )
+        }
+    }
+}
+
+/// Parses a `T` from the JSON C string `ptr`
+pub unsafe fn json_c_str_to<T: DeserializeOwned>(ptr: *const libc::c_char) -> Result<T, JsonError> {
+    if ptr.is_null() {
+        return Err(JsonError::new("null string"));
+    }
+    Ok(serde_json::from_slice(CStr::from_ptr(ptr).to_bytes())?)
+}
+
+/// Serializes `value` into a JSON C string, which needs to be freed with `free_c_str()`
+pub fn to_json_c_str<T: Serialize>(value: &T) -> Result<*mut libc::c_char, JsonError> {
+    // JSON escapes nul bytes within strings, there can't be any in the output
+    let json = serde_json::to_vec(value)?;
+    Ok(alloc::c_str_into_raw(CString::new(json).unwrap()))
+}
+
+/// Serializes `value` into JSON bytes, without a nul terminator
+pub fn to_json_bytes<T: Serialize>(value: &T) -> Result<FfiBytes, JsonError> {
+    Ok(FfiBytes::new(serde_json::to_vec(value)?))
+}
+
+/// An error response with `payload` serialized as JSON as the details of the error
+///
+/// The payload is left out if it can't be serialized, the message says why.
+pub fn error_response_with_json<T, S, C, P>(code: C, message: S, payload: &P) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    S: Into<String>,
+    C: StatusCode,
+    P: Serialize,
+{
+    match to_json_bytes(payload) {
+        Ok(payload) => error_response_with_payload(code, message, payload),
+        Err(err) => error_response(code, format!("{} ({})", message.into(), err)),
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::ptr;
+use std::slice;
+
+use crate::StringRef;
+
+/// A list of string pairs, e.g. configuration or metadata, which owns the strings
+///
+/// The key `keys_ptr[i]` has the value `values_ptr[i]`. Unlike `FfiMap` the strings are passed
+/// by pointer and length, so they may contain nul bytes. Dropping it frees everything, so it can
+/// be a field of a `DropStructMacro` response. C frees a `KeyValueList` it owns with
+/// `fil_free_key_value_list()`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct KeyValueList {
+    pub keys_ptr: *const StringRef,
+    pub values_ptr: *const StringRef,
+    pub len: libc::size_t,
+}
+
+// Hands out a string as a `StringRef` of a boxed slice, so that it is freed with its length
+fn into_string_ref(string: String) -> StringRef {
+    let bytes = Box::into_raw(string.into_bytes().into_boxed_slice());
+    StringRef {
+        ptr: bytes as *const u8,
+        len: bytes.len(),
+    }
+}
+
+unsafe fn free_string_ref(string: StringRef) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+        string.ptr as *mut u8,
+        string.len,
+    )));
+}
+
+fn into_raw_refs(strings: Vec<StringRef>) -> *const StringRef {
+    Box::into_raw(strings.into_boxed_slice()) as *const StringRef
+}
+
+impl KeyValueList {
+    /// The entries in order, duplicate keys are kept
+    pub fn new<I, K, V>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let (keys, values): (Vec<StringRef>, Vec<StringRef>) = entries
+            .into_iter()
+            .map(|(key, value)| (into_string_ref(key.into()), into_string_ref(value.into())))
+            .unzip();
+        let len = keys.len();
+        KeyValueList {
+            keys_ptr: into_raw_refs(keys),
+            values_ptr: into_raw_refs(values),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn keys(&self) -> &[StringRef] {
+        unsafe { slice::from_raw_parts(self.keys_ptr, self.len) }
+    }
+
+    pub fn values(&self) -> &[StringRef] {
+        unsafe { slice::from_raw_parts(self.values_ptr, self.len) }
+    }
+
+    /// The entries in order, borrowing the strings
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        // The strings came from `String`s
+        let as_str = |string: &StringRef| unsafe { string.as_str().unwrap() };
+        self.keys()
+            .iter()
+            .map(as_str)
+            .zip(self.values().iter().map(as_str))
+    }
+
+    /// The value of the first entry with the given key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .find(|&(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Copies the entries, of duplicate keys the last one wins
+    pub fn to_hash_map(&self) -> HashMap<String, String> {
+        self.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+impl Default for KeyValueList {
+    fn default() -> Self {
+        Self::new(Vec::<(String, String)>::new())
+    }
+}
+
+/// The entries sorted by key, as the order of a `HashMap` is random
+impl From<HashMap<String, String>> for KeyValueList {
+    fn from(map: HashMap<String, String>) -> Self {
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort();
+        Self::new(entries)
+    }
+}
+
+impl From<&KeyValueList> for HashMap<String, String> {
+    fn from(list: &KeyValueList) -> Self {
+        list.to_hash_map()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for KeyValueList {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        Self::new(entries)
+    }
+}
+
+impl Drop for KeyValueList {
+    fn drop(&mut self) {
+        unsafe {
+            for (&key, &value) in self.keys().iter().zip(self.values()) {
+                free_string_ref(key);
+                free_string_ref(value);
+            }
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                self.keys_ptr as *mut StringRef,
+                self.len,
+            )));
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                self.values_ptr as *mut StringRef,
+                self.len,
+            )));
+        }
+    }
+}
+
+/// Stores the entry at `index` in `*key` and `*value`, false if null or out of bounds
+///
+/// The strings are owned by the list.
+#[no_mangle]
+pub unsafe extern "C" fn fil_key_value_list_get(
+    list: *const KeyValueList,
+    index: libc::size_t,
+    key: *mut StringRef,
+    value: *mut StringRef,
+) -> bool {
+    match list.as_ref() {
+        Some(list) if index < list.len && !key.is_null() && !value.is_null() => {
+            *key = list.keys()[index];
+            *value = list.values()[index];
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Stores the value of the first entry with the key `key` in `*value`, false if there is none
+///
+/// The value is owned by the list.
+#[no_mangle]
+pub unsafe extern "C" fn fil_key_value_list_find(
+    list: *const KeyValueList,
+    key: StringRef,
+    value: *mut StringRef,
+) -> bool {
+    let (list, key) = match (list.as_ref(), key.as_str()) {
+        (Some(list), Ok(key)) if !value.is_null() => (list, key),
+        _ => return false,
+    };
+    match list
+        .keys()
+        .iter()
+        .position(|entry| entry.as_str() == Ok(key))
+    {
+        Some(index) => {
+            *value = list.values()[index];
+            true
+        }
+        None => false,
+    }
+}
+
+/// Frees a `KeyValueList` that was handed out to the caller
+#[no_mangle]
+pub extern "C" fn fil_free_key_value_list(list: KeyValueList) {
+    drop(list);
+}
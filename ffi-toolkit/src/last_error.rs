@@ -0,0 +1,64 @@
+//! A per-thread error slot, for functions that return plain values instead of responses.
+//!
+//! Like `errno`, the slot is only written when something fails and is not reset by successful
+//! calls, C checks it after a call signalled a failure through its return value.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::ptr;
+
+use crate::{truncated_c_string, FCPResponseStatus};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(FCPResponseStatus, CString)>> = const { RefCell::new(None) };
+}
+
+/// Records an error of the current thread, replacing the previous one
+///
+/// Interior nul bytes truncate the message.
+pub fn set_last_error<S: Into<String>>(code: FCPResponseStatus, message: S) {
+    let message = truncated_c_string(message.into());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((code, message)));
+}
+
+pub fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// The last error recorded on the current thread
+pub fn last_error() -> Option<(FCPResponseStatus, String)> {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|(code, message)| (*code, message.to_string_lossy().into_owned()))
+    })
+}
+
+/// The code of the last error recorded on the current thread, `FCPNoError` if there is none
+#[no_mangle]
+pub extern "C" fn fil_last_error_code() -> FCPResponseStatus {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(FCPResponseStatus::FCPNoError, |(code, _)| *code)
+    })
+}
+
+/// The message of the last error recorded on the current thread, null if there is none
+///
+/// The string is owned by the toolkit and stays valid until the next error is recorded or
+/// `fil_clear_last_error()` is called on the same thread, C must not free it.
+#[no_mangle]
+pub extern "C" fn fil_last_error_message() -> *const libc::c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |(_, message)| message.as_ptr())
+    })
+}
+
+/// See `clear_last_error()`
+#[no_mangle]
+pub extern "C" fn fil_clear_last_error() {
+    clear_last_error();
+}
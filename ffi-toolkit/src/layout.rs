@@ -0,0 +1,70 @@
+/// Asserts the size, alignment and field offsets of a `#[repr(C)]` struct at compile time
+///
+/// The expected values are the ones the bindings were generated with, so reordering fields or
+/// adding one with another layout fails the build instead of corrupting memory on the other side
+/// of the FFI. `offsets` is optional and may list only some of the fields. `DropStructMacro` and
+/// `FFIResponse` emit the assertion for `#[ffi_drop(layout(size = 32, align = 8))]` on the
+/// struct, with the offsets given as `#[ffi_drop(offset = 16)]` on the fields.
+///
+/// ```
+/// use ffi_toolkit::{assert_ffi_layout, FCPResponseStatus};
+///
+/// #[repr(C)]
+/// pub struct SealResponse {
+///     pub status_code: FCPResponseStatus,
+///     pub error_msg: *const libc::c_char,
+///     pub proof_ptr: *const u8,
+///     pub proof_len: libc::size_t,
+/// }
+///
+/// # #[cfg(target_pointer_width = "64")]
+/// assert_ffi_layout!(SealResponse, size = 32, align = 8, offsets {
+///     status_code = 0,
+///     error_msg = 8,
+///     proof_len = 24,
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_ffi_layout {
+    (
+        $type:ty, size = $size:expr, align = $align:expr
+        $(, offsets { $($field:ident = $offset:expr),* $(,)? })? $(,)?
+    ) => {
+        const _: () = {
+            assert!(
+                ::core::mem::size_of::<$type>() == $size,
+                concat!("the size of `", stringify!($type), "` isn't ", stringify!($size))
+            );
+            assert!(
+                ::core::mem::align_of::<$type>() == $align,
+                concat!("the alignment of `", stringify!($type), "` isn't ", stringify!($align))
+            );
+            $($(
+                assert!(
+                    ::core::mem::offset_of!($type, $field) == $offset,
+                    concat!(
+                        "the offset of `",
+                        stringify!($type),
+                        "::",
+                        stringify!($field),
+                        "` isn't ",
+                        stringify!($offset)
+                    )
+                );
+            )*)?
+        };
+    };
+}
+
+/// The layout of a `#[repr(C)]` response struct as C sees it, for `c_harness`
+///
+/// Implemented by `DropStructMacro` and `FFIResponse` for `#[repr(C)]` structs that aren't
+/// generic. The size and alignment are the ones of the Rust type.
+pub trait ResponseLayout {
+    /// The name of the struct, also the one cbindgen declares it with
+    const NAME: &'static str;
+    /// The names and offsets of all fields, in declaration order
+    const FIELDS: &'static [(&'static str, usize)];
+    /// The name of the exported destructor, if it's generated
+    const DESTROY: Option<&'static str>;
+}
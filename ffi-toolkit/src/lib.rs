@@ -1,59 +1,848 @@
-use std::borrow::Cow;
-use std::ffi::{CStr, CString};
+#![allow(clippy::missing_safety_doc)]
+// Without `std` only the core is built: the status codes, `CodeAndMessage`, the string and bytes
+// types and what the derives need
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// The derives refer to `::ffi_toolkit`, also for the responses of the toolkit itself
+extern crate self as ffi_toolkit;
+// Renamed, `alloc` is the module of the toolkit's allocations
+extern crate alloc as alloc_crate;
+
+use alloc_crate::borrow::Cow;
+use alloc_crate::boxed::Box;
+use alloc_crate::ffi::CString;
+use alloc_crate::format;
+use alloc_crate::string::String;
+use alloc_crate::vec;
+use alloc_crate::vec::Vec;
+use core::ffi::CStr;
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(all(feature = "std", unix))]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::ffi::OsString;
+#[cfg(feature = "std")]
 use std::panic;
+#[cfg(feature = "std")]
 use std::path::PathBuf;
 
-#[repr(C)]
-#[derive(PartialEq, Debug, Copy, Clone)]
-pub enum FCPResponseStatus {
-    // Don't use FCPSuccess, since that complicates description of 'successful' verification.
-    FCPNoError = 0,
-    FCPUnclassifiedError = 1,
-    FCPCallerError = 2,
-    FCPReceiverError = 3,
+use crate::c_types::c_char;
+
+#[cfg(feature = "std")]
+#[macro_use]
+mod opaque;
+#[macro_use]
+mod status;
+#[cfg(feature = "std")]
+#[macro_use]
+mod sync;
+
+#[cfg(feature = "std")]
+pub mod c_harness;
+pub mod c_types;
+#[cfg(feature = "std")]
+pub mod cbindgen;
+#[cfg(feature = "std")]
+pub mod failpoints;
+#[cfg(feature = "fuzz-support")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod lifecycle;
+#[cfg(feature = "std")]
+pub mod precondition;
+#[cfg(feature = "std")]
+pub mod sanitizer;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "std")]
+mod abi;
+#[cfg(feature = "std")]
+mod alloc;
+#[cfg(feature = "std")]
+mod alloc_stats;
+#[cfg(feature = "std")]
+mod arena;
+#[cfg(feature = "std")]
+mod assert_send;
+#[cfg(feature = "std")]
+mod audit;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "bigint")]
+mod bigint;
+#[cfg(feature = "std")]
+mod buffer;
+#[cfg(feature = "std")]
+mod build_info;
+mod bytes;
+#[cfg(feature = "std")]
+mod c_enum;
+#[cfg(feature = "std")]
+mod callback;
+#[cfg(feature = "std")]
+mod cancel;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "std")]
+mod checksum;
+#[cfg(feature = "std")]
+mod commitment;
+#[cfg(feature = "zstd")]
+mod compression;
+#[cfg(feature = "std")]
+mod concurrency;
+#[cfg(feature = "toml")]
+mod config;
+#[cfg(feature = "std")]
+mod convert;
+#[cfg(feature = "std")]
+mod cpu;
+#[cfg(all(feature = "std", unix))]
+mod crash;
+#[cfg(all(feature = "std", unix))]
+mod crash_log;
+#[cfg(feature = "std")]
+mod ct;
+#[cfg(feature = "std")]
+mod cursor;
+#[cfg(feature = "std")]
+mod debug_id;
+#[cfg(feature = "std")]
+mod destroy;
+#[cfg(feature = "std")]
+mod device;
+#[cfg(feature = "std")]
+mod dir;
+#[cfg(feature = "std")]
+mod encoding;
+#[cfg(feature = "std")]
+mod endian;
+#[cfg(feature = "std")]
+mod env;
+mod error_chain;
+#[cfg(feature = "log")]
+mod error_log;
+#[cfg(all(feature = "std", unix))]
+mod fd;
+#[cfg(all(feature = "std", windows))]
+mod fd_windows;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "std")]
+mod fixed_bytes;
+#[cfg(feature = "flatbuffers")]
+mod flatbuffer;
+#[cfg(feature = "std")]
+mod framing;
+#[cfg(feature = "std")]
+mod handle;
+#[cfg(feature = "std")]
+mod hash;
+#[cfg(feature = "std")]
+mod init;
+#[cfg(feature = "std")]
+mod int128;
+#[cfg(feature = "std")]
+mod interned;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+mod key_value;
+#[cfg(feature = "std")]
+mod last_error;
+mod layout;
+#[cfg(all(feature = "std", unix))]
+mod lock;
+#[cfg(feature = "log")]
+mod logging;
+#[cfg(all(test, feature = "loom"))]
+mod loom_tests;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+mod mapped;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+mod once;
+#[cfg(feature = "std")]
+mod option;
+#[cfg(feature = "std")]
+mod out_ptr;
+#[cfg(feature = "std")]
+mod panic_report;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod rate_limit;
+mod raw_parts;
+#[cfg(feature = "std")]
+mod recording;
+#[cfg(feature = "std")]
+mod reentrancy;
+#[cfg(feature = "std")]
+mod result;
+#[cfg(feature = "std")]
+mod secret;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+mod size;
+#[cfg(feature = "std")]
+mod stream;
+mod string_array;
+mod string_ref;
+#[cfg(feature = "std")]
+mod task;
+#[cfg(feature = "std")]
+mod temp;
+#[cfg(feature = "std")]
+mod time;
+#[cfg(feature = "std")]
+mod token;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(all(feature = "std", kani))]
+mod verification;
+#[cfg(feature = "std")]
+mod vtable;
+#[cfg(feature = "std")]
+mod wide;
+
+// Used by the macros, not part of the API
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "std")]
+    pub use crate::debug_id::destroy_checked;
+    #[cfg(feature = "serde")]
+    pub use crate::serialize::{
+        SerializeCStr, SerializeNested, SerializeSlice, SerializeStringArray,
+    };
+    pub use crate::{destroy_or_abort, drop_without_unwinding};
+    pub use alloc_crate::format;
+    pub use alloc_crate::string::{String, ToString};
+    pub use drop_struct_macro_derive::FFIResponse;
+    #[cfg(feature = "serde")]
+    pub use serde;
+}
+
+#[cfg(feature = "std")]
+pub use crate::abi::{abi_check, AbiHash};
+#[cfg(feature = "std")]
+pub use crate::alloc::{
+    detected_double_frees, fil_detected_double_frees, fil_set_allocator, set_allocator, FfiFreeFn,
+    FfiMallocFn,
+};
+#[cfg(feature = "provenance")]
+pub use crate::alloc::{is_toolkit_pointer, take_provenance_violations};
+#[cfg(feature = "poison")]
+pub use crate::alloc::{POISON_BYTE, POISON_PTR};
+#[cfg(feature = "std")]
+pub use crate::alloc_stats::{
+    alloc_stats, alloc_stats_enabled, enable_alloc_stats, fil_alloc_stats,
+    fil_destroy_alloc_stats_response, fil_enable_alloc_stats, live_ffi_allocations,
+    AllocStatsResponse, AllocTypeStats, FfiAllocTypeStats,
+};
+#[cfg(feature = "std")]
+pub use crate::arena::{arena_slice, arena_str, free_arena, FfiArena};
+#[cfg(feature = "std")]
+pub use crate::assert_send::{AssertSend, AssertSync};
+#[cfg(feature = "std")]
+pub use crate::audit::{recent_ffi_calls, record_ffi_call, AUDIT_RING_LEN};
+#[cfg(feature = "std")]
+pub use crate::batch::{BatchBuilder, FfiBatch, FfiBatchItem};
+#[cfg(feature = "bigint")]
+pub use crate::bigint::{
+    biguint_from_le_bytes, biguint_from_le_bytes_padded, biguint_from_raw_le, biguint_to_le_bytes,
+    biguint_to_le_bytes_padded,
+};
+#[cfg(feature = "std")]
+pub use crate::buffer::{
+    write_to_buffer, write_to_caller_buffer, BufferError, FfiBufferStatus, FIL_BUFFER_NULL,
+    FIL_BUFFER_TOO_SMALL, FIL_BUFFER_WRITTEN,
+};
+#[cfg(feature = "std")]
+pub use crate::build_info::{
+    build_info, fil_toolkit_build_info, BuildInfo, FIL_FEATURE_CBOR, FIL_FEATURE_CTOR,
+    FIL_FEATURE_FAILPOINTS, FIL_FEATURE_FUZZ_SUPPORT, FIL_FEATURE_JSON, FIL_FEATURE_LOG,
+    FIL_FEATURE_POISON, FIL_FEATURE_PROVENANCE, FIL_FEATURE_SANITIZER, FIL_FEATURE_TRACING,
+    FIL_FEATURE_TRACKING, FIL_FEATURE_ZSTD,
+};
+pub use crate::bytes::{fil_free_bytes, FfiBytes};
+#[cfg(feature = "std")]
+pub use crate::c_enum::InvalidDiscriminant;
+#[cfg(feature = "std")]
+pub use crate::callback::{CCallback, CallbackArgs, SendCCallback};
+#[cfg(feature = "std")]
+pub use crate::cancel::{
+    fil_cancel_token_cancel, fil_cancel_token_free, fil_cancel_token_is_cancelled,
+    fil_cancel_token_new, CancellationToken, Cancelled, FfiCancelToken,
+};
+#[cfg(feature = "cbor")]
+pub use crate::cbor::{error_response_with_cbor, from_cbor, from_cbor_raw, to_cbor, CborError};
+#[cfg(feature = "std")]
+pub use crate::checksum::{
+    crc32c, frame_with_checksum, ChecksumError, ChecksummedFrame, FRAME_HEADER_LEN,
+};
+#[cfg(feature = "std")]
+pub use crate::commitment::FfiCommitment;
+#[cfg(feature = "zstd")]
+pub use crate::compression::{
+    compress_ffi, decompress_ffi, uncompressed_len, CompressionError, COMPRESSION_HEADER_LEN,
+    DEFAULT_COMPRESSION_LEVEL,
+};
+#[cfg(feature = "std")]
+pub use crate::concurrency::{
+    acquire_concurrency_permit, catch_panic_response_bounded, concurrency_stats,
+    configure_concurrency_limit, fil_concurrency_queue_length, fil_concurrency_stats,
+    fil_configure_concurrency_limit, try_acquire_concurrency_permit, ConcurrencyPermit,
+    FfiConcurrencyStats,
+};
+#[cfg(feature = "toml")]
+pub use crate::config::{
+    config, fil_destroy_parse_config_response, fil_parse_config_toml, parse_config_toml,
+    register_config_type, ConfigError, ParseConfigResponse,
+};
+#[cfg(feature = "std")]
+pub use crate::convert::{FfiFrom, FfiInto};
+#[cfg(feature = "std")]
+pub use crate::cpu::{
+    cpu_feature_names, cpu_features, fil_cpu_features, CPU_FEATURES, FIL_CPU_ADX, FIL_CPU_AES,
+    FIL_CPU_ARM_AES, FIL_CPU_ARM_SHA2, FIL_CPU_AVX, FIL_CPU_AVX2, FIL_CPU_AVX512F, FIL_CPU_BMI2,
+    FIL_CPU_NEON, FIL_CPU_PCLMULQDQ, FIL_CPU_SHA, FIL_CPU_SSE4_1, FIL_CPU_SSE4_2,
+};
+#[cfg(all(feature = "std", unix))]
+pub use crate::crash::{
+    fil_install_crash_handler, fil_uninstall_crash_handler, install_crash_handler,
+    uninstall_crash_handler,
+};
+#[cfg(all(feature = "std", unix))]
+pub use crate::crash_log::{
+    close_crash_log, crash_log_write, fil_set_crash_log_fd, open_crash_log, set_crash_log,
+    CrashLine, CRASH_LINE_LEN,
+};
+#[cfg(feature = "std")]
+pub use crate::ct::{ct_eq, fil_ct_eq};
+#[cfg(feature = "std")]
+pub use crate::cursor::{
+    cursor_free, cursor_new, cursor_next, Cursor, FfiCursorStatus, FIL_CURSOR_DONE,
+    FIL_CURSOR_ITEM, FIL_CURSOR_NULL, FIL_CURSOR_PANICKED,
+};
+#[cfg(feature = "std")]
+pub use crate::debug_id::{
+    check_debug_id, checked_response, checked_response_mut, fil_check_debug_id, DebugIdError,
+    DebugIdentified, FfiDebugId, FREED_MAGIC, LIVE_MAGIC,
+};
+#[cfg(feature = "std")]
+pub use crate::destroy::{
+    destroy_tagged, fil_destroy, register_destructor, DestroyError, TaggedResponse, TypeTag,
+};
+#[cfg(feature = "std")]
+pub use crate::device::{
+    fil_destroy_list_devices_response, fil_list_devices, list_devices, register_device_probe,
+    unregister_device_probe, DeviceInfo, DeviceProbe, FfiDevice, ListDevicesResponse,
+};
+#[cfg(feature = "std")]
+pub use crate::dir::{
+    fil_destroy_list_dir_response, fil_list_dir, list_dir, matches_pattern, FfiIoErrorKind,
+    ListDirResponse,
+};
+#[cfg(feature = "std")]
+pub use crate::encoding::{
+    bytes_to_c_base64, bytes_to_c_hex, c_base64_to_bytes, c_hex_to_bytes, parse_base64, parse_cid,
+    parse_cid_c_str, parse_hex, parse_hex_c_str, parse_multibase, parse_multibase_c_str, to_base64,
+    to_hex, Cid, ParseError, ParseErrorKind,
+};
+#[cfg(feature = "std")]
+pub use crate::endian::{from_be_ffi, to_be_bytes_ffi, FfiByteOrder, FfiEndian, FfiOrderedBytes};
+#[cfg(feature = "std")]
+pub use crate::env::{env_snapshot, fil_destroy_map, fil_env_snapshot};
+pub use crate::error_chain::FfiErrorChain;
+#[cfg(feature = "log")]
+pub use crate::error_log::{
+    configure_error_logging, fil_configure_error_logging, flush_error_log, ErrorLogging,
+    ERROR_LOG_TARGET,
+};
+#[cfg(all(feature = "std", unix))]
+pub use crate::fd::{borrow_fd, take_fd, BorrowedFfiFd, OwnedFfiFd};
+#[cfg(all(feature = "std", windows))]
+pub use crate::fd_windows::{borrow_handle, take_handle};
+#[cfg(feature = "std")]
+pub use crate::file::{
+    fil_destroy_write_file_response, fil_write_file_atomic, write_file_atomic, WriteFileResponse,
+};
+#[cfg(feature = "std")]
+pub use crate::fixed_bytes::{
+    fil_ct_eq_fil32, fil_ct_eq_fil48, fil_ct_eq_fil96, ByteLengthError, Fil32, Fil48, Fil96,
+};
+#[cfg(feature = "flatbuffers")]
+pub use crate::flatbuffer::{
+    flatbuffer_view, flatbuffer_view_raw, flatbuffer_view_with_opts, FlatbufferError,
+};
+#[cfg(feature = "std")]
+pub use crate::framing::{
+    decode_length_delimited_all, encode_length_delimited, encode_length_delimited_all,
+    read_length_delimited, write_length_delimited, FrameError, MessageCodec, MAX_MESSAGE_LEN,
+};
+#[cfg(feature = "std")]
+pub use crate::handle::{is_live, register, release, with_handle, HandleError};
+#[cfg(feature = "std")]
+pub use crate::hash::{
+    hash_chunked, hash_file_chunked, ChunkedDigest, HashError, HashProgress, HASH_CHUNK_SIZE,
+};
+#[cfg(feature = "std")]
+pub use crate::init::{
+    fil_init, init_config, init_with_config, parse_ffi_config, Config, FfiConfig, InitError,
+    FFI_CONFIG_VERSION, FIL_LOG_UNCHANGED,
+};
+#[cfg(feature = "std")]
+pub use crate::int128::{
+    fil_i128_to_string, fil_parse_i128, fil_parse_u128, fil_u128_to_string, FfiI128, FfiU128,
+};
+#[cfg(feature = "std")]
+pub use crate::interned::{intern_c_str, interned_c_str_count, is_interned_c_str};
+#[cfg(feature = "json")]
+pub use crate::json::{
+    error_response_with_json, json_c_str_to, to_json_bytes, to_json_c_str, JsonError,
+};
+#[cfg(feature = "std")]
+pub use crate::key_value::{
+    fil_free_key_value_list, fil_key_value_list_find, fil_key_value_list_get, KeyValueList,
+};
+#[cfg(feature = "std")]
+pub use crate::last_error::{
+    clear_last_error, fil_clear_last_error, fil_last_error_code, fil_last_error_message,
+    last_error, set_last_error,
+};
+pub use crate::layout::ResponseLayout;
+#[cfg(all(feature = "std", unix))]
+pub use crate::lock::{
+    fil_destroy_lock_file_response, fil_lock_file, fil_unlock_file, FfiLockStatus, FileLock,
+    LockError, LockFileResponse,
+};
+#[cfg(all(feature = "log", unix))]
+pub use crate::logging::fil_init_log_fd;
+#[cfg(feature = "log")]
+pub use crate::logging::{
+    close_log, fil_init_log_callback, fil_set_log_level, init_log, FfiLogCallback, FfiLogLevel,
+    LogSink, FIL_LOG_DEBUG, FIL_LOG_ERROR, FIL_LOG_INFO, FIL_LOG_OFF, FIL_LOG_TRACE, FIL_LOG_WARN,
+};
+#[cfg(feature = "std")]
+pub use crate::map::FfiMap;
+#[cfg(feature = "std")]
+pub use crate::mapped::{
+    fil_release_mapped_region, FfiMappedRegion, MappedRegion, MappedRegionGuard, RegionBytes,
+    RegionBytesMut,
+};
+#[cfg(feature = "log")]
+pub use crate::metrics::METRICS_LOG_TARGET;
+#[cfg(feature = "std")]
+pub use crate::metrics::{
+    call_metrics, call_metrics_enabled, catch_panic_response_named, catch_panic_result_named,
+    enable_call_metrics, fil_destroy_call_metrics_response, fil_enable_call_metrics,
+    fil_metrics_snapshot, fil_reset_metrics, reset_call_metrics, CallMetrics, CallMetricsResponse,
+    FfiCallMetrics,
+};
+#[cfg(feature = "std")]
+pub use crate::once::{FfiOnceCell, OnceCellError};
+#[cfg(feature = "std")]
+pub use crate::option::{FfiOption, FfiOptionBool, FfiOptionF64, FfiOptionU64};
+#[cfg(feature = "std")]
+pub use crate::out_ptr::{write_out_box, write_out_ptr};
+#[cfg(feature = "std")]
+pub use crate::panic_report::{
+    enable_panic_reports, fil_destroy_panic_reports_response, fil_enable_panic_reports,
+    fil_take_panic_reports, take_panic_reports, FfiPanicReport, PanicReport, PanicReportsResponse,
+    PANIC_REPORT_RING_LEN,
+};
+#[cfg(feature = "std")]
+pub use crate::pool::{enable_response_pool, pooled_response, response_pool_len};
+#[cfg(feature = "std")]
+pub use crate::progress::{FfiProgressCallback, ProgressSink};
+#[cfg(feature = "std")]
+pub use crate::rate_limit::{
+    catch_panic_response_limited, configure_rate_limit, fil_configure_rate_limit, try_acquire_call,
+    RateLimit,
+};
+pub use crate::raw_parts::{
+    vec_from_raw_parts_checked, vec_into_raw_parts, vec_into_raw_parts_exact,
+};
+#[cfg(feature = "std")]
+pub use crate::recording::{
+    catch_panic_response_recorded, catch_panic_result_recorded, fil_start_recording,
+    fil_stop_recording, is_recording, read_recording, start_recording, stop_recording, RecordedArg,
+    RecordedCall, ReplayError, ReplayHarness, RECORDING_MAGIC,
+};
+#[cfg(feature = "std")]
+pub use crate::reentrancy::{
+    assert_not_reentrant, catch_panic_response_not_reentrant, enter_ffi,
+    fil_reject_reentrant_calls, reentrancy_policy, set_reentrancy_policy, FfiCallGuard, GuardError,
+    ReentrancyPolicy, ThreadAffinity,
+};
+#[cfg(feature = "std")]
+pub use crate::secret::{fil_free_secret_bytes, SecretBytes};
+#[cfg(feature = "std")]
+pub use crate::shared::{
+    arc_from_shared, borrow_shared, clone_shared, outstanding_shared_refs, release_shared,
+    shared_raw_ptr, shared_ref_count,
+};
+#[cfg(feature = "std")]
+pub use crate::size::{
+    i64_to_ptrdiff, ptrdiff_to_i64, size_to_u64, u64_to_size, FfiPtrDiff, FfiSize, POINTER_WIDTH,
+};
+#[cfg(feature = "std")]
+pub use crate::stream::{CallbackStream, FfiReadFn, FfiSeekFn, FfiStream, FfiWriteFn};
+pub use crate::string_array::{fil_free_string_array, fil_string_array_get, FfiStringArray};
+pub use crate::string_ref::{fil_free_string, FfiString, StringRef};
+#[cfg(feature = "std")]
+pub use crate::task::{
+    catch_panic_response_with_timeout, fil_set_task_threads, fil_task_poll, fil_task_release,
+    fil_task_timing, fil_task_wait, fil_worker_pool_stats, fil_worker_queue_depth,
+    set_task_threads, spawn_cancellable_ffi_task, spawn_ffi_task, spawn_ffi_task_with_callback,
+    task_poll, task_release, task_take_response, task_timing, task_wait, worker_pool_stats,
+    worker_queue_depth, FfiTaskStatus, FfiTaskTiming, FfiWorkerPoolStats, SendUserData,
+    TaskDoneCallback, TaskHandle, FIL_TASK_FINISHED, FIL_TASK_INVALID_HANDLE, FIL_TASK_RUNNING,
+};
+#[cfg(feature = "std")]
+pub use crate::temp::{
+    fil_create_temp_dir, fil_destroy_temp_dir, fil_destroy_temp_dir_response, TempDir,
+    TempDirResponse,
+};
+#[cfg(feature = "std")]
+pub use crate::time::{
+    elapsed_since_ns, fil_instant_now, fil_monotonic_now_ns, monotonic_now_ns, FfiDuration,
+    FfiInstant, FfiTimestamp,
+};
+#[cfg(feature = "std")]
+pub use crate::token::{random_token_u128, random_token_u64};
+#[cfg(feature = "tracing")]
+pub use crate::trace::{fil_init_tracing, FfiTracingCallbacks, FfiTracingLayer};
+#[cfg(feature = "std")]
+pub use crate::vtable::FfiVTableHeader;
+#[cfg(feature = "std")]
+pub use crate::wide::{
+    free_w_str, rust_string_to_wstr, try_wstr_to_rust_string, wstr_to_os_string, wstr_to_pathbuf,
+    wstr_to_rust_string,
+};
+
+status_code_enum! {
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    pub enum FCPResponseStatus {
+        // Don't use FCPSuccess, since that complicates description of 'successful' verification.
+        FCPNoError = 0 => FCP_NO_ERROR,
+        FCPUnclassifiedError = 1 => FCP_UNCLASSIFIED_ERROR,
+        FCPCallerError = 2 => FCP_CALLER_ERROR,
+        FCPReceiverError = 3 => FCP_RECEIVER_ERROR,
+        // The call was rejected, e.g. by a rate limit, and can be retried later
+        FCPBusyError = 4 => FCP_BUSY_ERROR,
+    }
+}
+
+/// A status code enum responses can report, `FCPResponseStatus` unless a crate defines its own
+pub trait StatusCode: Copy {
+    /// The status of a successful call
+    const NO_ERROR: Self;
+    /// The status of a panic, or any other error that wasn't classified
+    const UNCLASSIFIED: Self;
+
+    /// The status an `FCPResponseStatus` of the toolkit (e.g. of a fail point) is reported as
+    ///
+    /// By default all errors are unclassified.
+    fn from_response_status(status: FCPResponseStatus) -> Self {
+        match status {
+            FCPResponseStatus::FCPNoError => Self::NO_ERROR,
+            _ => Self::UNCLASSIFIED,
+        }
+    }
+}
+
+impl StatusCode for FCPResponseStatus {
+    const NO_ERROR: Self = FCPResponseStatus::FCPNoError;
+    const UNCLASSIFIED: Self = FCPResponseStatus::FCPUnclassifiedError;
+
+    fn from_response_status(status: FCPResponseStatus) -> Self {
+        status
+    }
+}
+
+/// The contract hosts can implement retry policies against:
+///
+///  - `FCPCallerError`: the arguments are invalid, the same call fails again
+///  - `FCPReceiverError`: the library's environment failed (I/O, a full disk), the same call may
+///    succeed once that is fixed, but shouldn't be retried right away
+///  - `FCPBusyError`: the call was rejected for now (a rate limit, a timeout) and can be retried
+///  - `FCPUnclassifiedError`: anything else, including panics
+impl FCPResponseStatus {
+    pub fn is_ok(self) -> bool {
+        self == FCPResponseStatus::FCPNoError
+    }
+
+    pub fn is_error(self) -> bool {
+        !self.is_ok()
+    }
+
+    pub fn is_caller_error(self) -> bool {
+        self == FCPResponseStatus::FCPCallerError
+    }
+
+    pub fn is_receiver_error(self) -> bool {
+        self == FCPResponseStatus::FCPReceiverError
+    }
+
+    /// Whether the same call can be retried later, only `FCPBusyError`
+    pub fn is_retryable(self) -> bool {
+        self == FCPResponseStatus::FCPBusyError
+    }
+}
+
+/// Invalid arguments are caller errors, interruptions and timeouts busy errors and everything
+/// else receiver errors, e.g. a missing file or a full disk
+#[cfg(feature = "std")]
+impl From<std::io::ErrorKind> for FCPResponseStatus {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        use std::io::ErrorKind;
+
+        match kind {
+            ErrorKind::InvalidInput => FCPResponseStatus::FCPCallerError,
+            ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted => {
+                FCPResponseStatus::FCPBusyError
+            }
+            _ => FCPResponseStatus::FCPReceiverError,
+        }
+    }
+}
+
+// Strings come from the caller
+impl From<Utf8Error> for FCPResponseStatus {
+    fn from(_: Utf8Error) -> Self {
+        FCPResponseStatus::FCPCallerError
+    }
+}
+
+impl From<alloc_crate::string::FromUtf8Error> for FCPResponseStatus {
+    fn from(_: alloc_crate::string::FromUtf8Error) -> Self {
+        FCPResponseStatus::FCPCallerError
+    }
 }
 
 /// All FFI responses need to implement this trait in order to be able to use `catch_panic()`
-pub trait CodeAndMessage {
+///
+/// `C` is the type of the response's status code.
+pub trait CodeAndMessage<C: StatusCode = FCPResponseStatus> {
     /// Set the status code and error message
-    fn set_error(&mut self, code_and_message: (FCPResponseStatus, *const libc::c_char));
+    fn set_error(&mut self, code_and_message: (C, *const c_char));
+
+    /// Set the status code and a constant error message, which is interned instead of allocated,
+    /// see `intern_c_str()`
+    #[cfg(feature = "std")]
+    fn set_error_static(&mut self, code: C, message: &'static str) {
+        self.set_error((code, intern_c_str(message)));
+    }
+
+    /// Set the causes of the error, for responses that report them
+    fn set_error_chain(&mut self, _chain: FfiErrorChain) {}
+
+    /// Set the machine-readable details of the error (e.g. the failing sector or whether the
+    /// call can be retried), for responses with an `error_payload: FfiBytes` field
+    fn set_error_payload(&mut self, _payload: FfiBytes) {}
+
+    /// Set the status code and error message, and the details encoded with e.g.
+    /// `to_cbor()`, responses without a payload drop them
+    fn set_error_with_payload(&mut self, code_and_message: (C, *const c_char), payload: FfiBytes) {
+        self.set_error(code_and_message);
+        self.set_error_payload(payload);
+    }
+}
+
+/// Reads the status code and error message of a response, see `testing::assert_ffi_ok()`
+///
+/// Implemented by `#[derive(FFIResponse)]` and `code_and_message_impl!`.
+pub trait ResponseStatus<C: StatusCode = FCPResponseStatus> {
+    fn status_code(&self) -> C;
+    fn error_msg(&self) -> *const c_char;
+}
+
+/// An error that can be reported in a response, see `catch_panic_result()`
+pub trait IntoFFIError {
+    fn code(&self) -> FCPResponseStatus;
+    fn message(&self) -> String;
+
+    /// The error and its causes, see `FfiErrorChain`
+    fn chain(&self) -> Vec<(FCPResponseStatus, String)> {
+        vec![(self.code(), self.message())]
+    }
+
+    /// The machine-readable details of the error, see `CodeAndMessage::set_error_payload()`
+    fn payload(&self) -> Option<FfiBytes> {
+        None
+    }
+}
+
+impl IntoFFIError for (FCPResponseStatus, String) {
+    fn code(&self) -> FCPResponseStatus {
+        self.0
+    }
+
+    fn message(&self) -> String {
+        self.1.clone()
+    }
+}
+
+// I/O mostly fails on the side of the receiver, e.g. a full disk, see
+// `From<io::ErrorKind> for FCPResponseStatus`
+#[cfg(feature = "std")]
+impl IntoFFIError for std::io::Error {
+    fn code(&self) -> FCPResponseStatus {
+        self.kind().into()
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+// Also what `anyhow::Error` converts into
+#[cfg(feature = "std")]
+impl IntoFFIError for Box<dyn Error + Send + Sync> {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPUnclassifiedError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn chain(&self) -> Vec<(FCPResponseStatus, String)> {
+        let mut chain = vec![(self.code(), self.message())];
+        let mut source = self.source();
+        while let Some(cause) = source {
+            chain.push((FCPResponseStatus::FCPUnclassifiedError, cause.to_string()));
+            source = cause.source();
+        }
+        chain
+    }
 }
 
 /// A simple macro to create implementations for the `CodeAndMessage` trait
 ///
 /// The only requirement is that the response has an `status_code: FCPResponseStatus` and
-/// `error_msg: *const libc::c_char` field.
+/// `error_msg: *const libc::c_char` field. For a response with a custom status code enum, the
+/// type of its `status_code` is given as second argument, e.g.
+/// `code_and_message_impl!(SealResponse, SealStatus)`.
 #[macro_export]
 macro_rules! code_and_message_impl {
     { $response:ty } => {
-        impl CodeAndMessage for $response {
-            fn set_error(&mut self, (code, message): (FCPResponseStatus, *const libc::c_char)) {
+        $crate::code_and_message_impl! { $response, $crate::FCPResponseStatus }
+    };
+    { $response:ty, $code:ty } => {
+        impl CodeAndMessage<$code> for $response {
+            fn set_error(&mut self, (code, message): ($code, *const $crate::c_types::c_char)) {
                 self.status_code = code;
                 self.error_msg = message;
             }
         }
+
+        impl $crate::ResponseStatus<$code> for $response {
+            fn status_code(&self) -> $code {
+                self.status_code
+            }
+
+            fn error_msg(&self) -> *const $crate::c_types::c_char {
+                self.error_msg
+            }
+        }
+    };
+}
+
+// a C string of a Rust string that is truncated at the first interior nul byte
+#[cfg(feature = "std")]
+pub(crate) fn truncated_c_string(string: String) -> CString {
+    let mut bytes = string.into_bytes();
+    if let Some(nul) = bytes.iter().position(|&byte| byte == 0) {
+        bytes.truncate(nul);
     }
+    CString::new(bytes).expect("interior nul bytes were removed")
 }
 
 // produce a C string from a Rust string
-pub fn rust_str_to_c_str<T: Into<String>>(s: T) -> *mut libc::c_char {
-    CString::new(s.into()).unwrap().into_raw()
+pub fn rust_str_to_c_str<T: Into<String>>(s: T) -> *mut c_char {
+    let c_string = CString::new(s.into()).unwrap();
+    #[cfg(feature = "std")]
+    return alloc::c_str_into_raw(c_string);
+    // Without `std` there is neither a host allocator nor tracking
+    #[cfg(not(feature = "std"))]
+    return c_string.into_raw();
 }
 
-// consume a C string-pointer and free its memory
-pub unsafe fn free_c_str(ptr: *mut libc::c_char) {
+// consume a C string-pointer and free its memory, interned strings (see `intern_c_str()`) are
+// left alone
+pub unsafe fn free_c_str(ptr: *mut c_char) {
+    #[cfg(feature = "std")]
+    if !ptr.is_null() && !is_interned_c_str(ptr) {
+        alloc::free_c_str(ptr);
+    }
+    #[cfg(not(feature = "std"))]
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+// like `free_c_str()`, but zeroes the string first, for secrets
+pub unsafe fn free_secret_c_str(ptr: *mut c_char) {
+    #[cfg(feature = "std")]
+    if !ptr.is_null() && !is_interned_c_str(ptr) {
+        alloc::free_secret_c_str(ptr);
+    }
+    #[cfg(not(feature = "std"))]
     if !ptr.is_null() {
-        let _ = CString::from_raw(ptr);
+        for byte in CString::from_raw(ptr).into_bytes_with_nul().iter_mut() {
+            core::ptr::write_volatile(byte, 0);
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
     }
 }
 
 // return a forgotten raw pointer to something of type T
 pub fn raw_ptr<T>(thing: T) -> *mut T {
-    Box::into_raw(Box::new(thing))
+    #[cfg(feature = "std")]
+    return alloc::box_into_raw(thing);
+    #[cfg(not(feature = "std"))]
+    return Box::into_raw(Box::new(thing));
+}
+
+// consume a raw pointer created by `raw_ptr()` and free its memory
+pub unsafe fn free_raw_ptr<T>(ptr: *mut T) {
+    #[cfg(feature = "std")]
+    if !ptr.is_null() {
+        alloc::free_box(ptr);
+    }
+    #[cfg(not(feature = "std"))]
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+// like `free_raw_ptr()`, but the memory of the value stays allocated as a tombstone, so that
+// destroying it again is a no-op counted by `detected_double_frees()`, see
+// `#[ffi_drop(tombstone)]`
+#[cfg(feature = "std")]
+pub unsafe fn destroy_tombstoned<T>(ptr: *mut T) {
+    if !ptr.is_null() {
+        alloc::tombstone_box(ptr);
+    }
 }
 
 // transmutes a C string to a copy-on-write Rust string
-pub unsafe fn c_str_to_rust_str<'a>(x: *const libc::c_char) -> Cow<'a, str> {
+pub unsafe fn c_str_to_rust_str<'a>(x: *const c_char) -> Cow<'a, str> {
     if x.is_null() {
         Cow::from("")
     } else {
@@ -61,39 +850,356 @@ pub unsafe fn c_str_to_rust_str<'a>(x: *const libc::c_char) -> Cow<'a, str> {
     }
 }
 
+// borrows a C string as UTF-8 without replacing invalid sequences, for keys and CIDs that must
+// not be altered; null is the empty string, as with `c_str_to_rust_str()`
+pub unsafe fn try_c_str_to_rust_str<'a>(x: *const c_char) -> Result<&'a str, Utf8Error> {
+    if x.is_null() {
+        Ok("")
+    } else {
+        CStr::from_ptr(x).to_str()
+    }
+}
+
+// like `try_c_str_to_rust_str()` for the argument `name`, invalid UTF-8 is an `FCPCallerError`
+// response, e.g. `let key = c_str_arg_to_rust_str(key, "key")?;`
+pub unsafe fn c_str_arg_to_rust_str<'a, R: Default + CodeAndMessage>(
+    x: *const c_char,
+    name: &str,
+) -> Result<&'a str, *mut R> {
+    try_c_str_to_rust_str(x).map_err(|err| {
+        error_response(
+            FCPResponseStatus::FCPCallerError,
+            format!("invalid argument `{}`: {}", name, err),
+        )
+    })
+}
+
 // cast from mutable to constant reference
 pub unsafe fn cast_const<'a, T>(x: *mut T) -> &'a T {
     assert!(!x.is_null(), "Object argument was null");
-    (&(*x))
+    &*x
+}
+
+// whether `len` elements at `ptr` can be a slice, null is only accepted for an empty slice
+fn is_valid_slice<T>(ptr: *const T, len: usize) -> bool {
+    let fits = len
+        .checked_mul(core::mem::size_of::<T>())
+        .is_some_and(|size| size <= isize::MAX as usize);
+    if ptr.is_null() {
+        len == 0
+    } else {
+        fits && ptr.align_offset(core::mem::align_of::<T>()) == 0
+    }
+}
+
+// borrow the `len` elements at `ptr`, `None` if `ptr` is null (unless `len` is 0), unaligned or
+// the size of the elements overflows
+pub unsafe fn try_slice_from_raw<'a, T>(ptr: *const T, len: usize) -> Option<&'a [T]> {
+    if !is_valid_slice(ptr, len) {
+        None
+    } else if ptr.is_null() {
+        Some(&[])
+    } else {
+        Some(core::slice::from_raw_parts(ptr, len))
+    }
+}
+
+// mutably borrow the `len` elements at `ptr`, see `try_slice_from_raw()`
+pub unsafe fn try_mut_slice_from_raw<'a, T>(ptr: *mut T, len: usize) -> Option<&'a mut [T]> {
+    if !is_valid_slice(ptr, len) {
+        None
+    } else if ptr.is_null() {
+        Some(&mut [])
+    } else {
+        Some(core::slice::from_raw_parts_mut(ptr, len))
+    }
 }
 
-// transmutes a C string to a PathBuf
-pub unsafe fn c_str_to_pbuf(x: *const libc::c_char) -> PathBuf {
+// transmutes a C string to a PathBuf, lossily converting it to UTF-8, see `c_str_to_path()`
+#[cfg(feature = "std")]
+pub unsafe fn c_str_to_pbuf(x: *const c_char) -> PathBuf {
     PathBuf::from(String::from(c_str_to_rust_str(x)))
 }
 
+// converts a C string to an OsString, on Unix without any conversion of the bytes; null is the
+// empty string
+#[cfg(all(feature = "std", unix))]
+pub unsafe fn c_str_to_os_string(x: *const c_char) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+
+    if x.is_null() {
+        OsString::new()
+    } else {
+        OsStr::from_bytes(CStr::from_ptr(x).to_bytes()).to_os_string()
+    }
+}
+
+// converts a C string to an OsString, elsewhere than on Unix the bytes are UTF-8 (invalid
+// sequences are replaced), Windows hosts pass paths as UTF-16, see `wstr_to_os_string()`
+#[cfg(all(feature = "std", not(unix)))]
+pub unsafe fn c_str_to_os_string(x: *const c_char) -> OsString {
+    OsString::from(String::from(c_str_to_rust_str(x)))
+}
+
+// converts a C string to a PathBuf without lossy conversion, see `c_str_to_os_string()`
+#[cfg(feature = "std")]
+pub unsafe fn c_str_to_path(x: *const c_char) -> PathBuf {
+    PathBuf::from(c_str_to_os_string(x))
+}
+
+// return a forgotten raw pointer to a default response with the given error set
+pub fn error_response<T, S, C>(code: C, message: S) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    S: Into<String>,
+    C: StatusCode,
+{
+    let mut response = T::default();
+    response.set_error((code, rust_str_to_c_str(message)));
+    raw_ptr(response)
+}
+
+// like `error_response()`, with the details of the error, see `CodeAndMessage::set_error_payload()`
+pub fn error_response_with_payload<T, S, C>(code: C, message: S, payload: FfiBytes) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    S: Into<String>,
+    C: StatusCode,
+{
+    let mut response = T::default();
+    response.set_error_with_payload((code, rust_str_to_c_str(message)), payload);
+    raw_ptr(response)
+}
+
+// the message of a panic payload: a string (`panic!()`) or an error (`panic_any()`)
+#[cfg(feature = "std")]
+pub fn panic_payload_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        Some(message.to_string())
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        Some(message.clone())
+    } else if let Some(err) = payload.downcast_ref::<Box<dyn Error + Send + Sync>>() {
+        Some(err.to_string())
+    } else {
+        payload
+            .downcast_ref::<Box<dyn Error + Send>>()
+            .map(|err| err.to_string())
+    }
+}
+
 ///// Catch panics and return an error response
-pub fn catch_panic_response<F, T>(callback: F) -> *mut T
+///
+/// The response gets the `StatusCode::UNCLASSIFIED` status of its status code type. The panic is
+/// recorded as the thread's last error as well, see `last_error()`, and as a `PanicReport` if
+/// they are enabled.
+#[cfg(feature = "std")]
+pub fn catch_panic_response<F, T, C>(callback: F) -> *mut T
 where
-    T: Default + CodeAndMessage,
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+    F: FnOnce() -> *mut T,
+{
+    catch_panic_response_with(
+        |code, message| {
+            let mut response = T::default();
+            response.set_error((code, message));
+            response
+        },
+        callback,
+    )
+}
+
+/// Like `catch_panic_response()`, with the error response built by `new_error_response`
+///
+/// For responses without a `Default`, or whose error state needs more than a status code and a
+/// message. `new_error_response` gets both and owns the message, e.g. the
+/// `new_error_response()` generated by `FFIResponse` with `#[ffi_drop(error_response)]`.
+#[cfg(feature = "std")]
+pub fn catch_panic_response_with<E, F, T, C>(new_error_response: E, callback: F) -> *mut T
+where
+    C: StatusCode,
+    E: FnOnce(C, *const c_char) -> T,
     F: FnOnce() -> *mut T,
 {
     // Using AssertUnwindSafe is code smell. Though catching our panics here is really
     // last resort, so it should be OK.
-    let maybe_panic = panic::catch_unwind(panic::AssertUnwindSafe(callback));
+    let _call = lifecycle::CallGuard::enter();
+    if lifecycle::rejects_calls() {
+        return raw_ptr(new_error_response(
+            C::from_response_status(FCPResponseStatus::FCPBusyError),
+            rust_str_to_c_str("the library is shutting down"),
+        ));
+    }
+    let mut new_error_response = Some(new_error_response);
+    let maybe_panic = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        if let Some((code, message)) = failpoints::eval("catch_panic_response") {
+            let new_error_response = new_error_response.take().unwrap();
+            return raw_ptr(new_error_response(
+                C::from_response_status(code),
+                rust_str_to_c_str(message),
+            ));
+        }
+        callback()
+    }));
     match maybe_panic {
         Ok(return_value) => return_value,
         Err(panic) => {
-            let error_msg = match panic.downcast_ref::<&'static str>() {
-                Some(message) => message,
-                _ => "no unwind information",
-            };
+            panic_report::record_panic(&*panic);
+            let message = panic_error_message(&*panic);
+            #[cfg(feature = "log")]
+            error_log::report_error(FCPResponseStatus::FCPUnclassifiedError, &message);
+            set_last_error(FCPResponseStatus::FCPUnclassifiedError, message.clone());
+            match new_error_response.take() {
+                Some(new_error_response) => raw_ptr(new_error_response(
+                    C::UNCLASSIFIED,
+                    rust_str_to_c_str(message),
+                )),
+                // The error response of the failpoint panicked, there is none to return
+                None => std::ptr::null_mut(),
+            }
+        }
+    }
+}
+
+/// Like `catch_panic_response()`, for a callback returning the response or an error
+///
+/// An `Ok` response is returned as it is, an error is turned into an error response with the
+/// error's code and message. Responses that implement `CodeAndMessage::set_error_chain()` get the
+/// causes of the error as well, and the ones that implement `set_error_payload()` its
+/// `IntoFFIError::payload()`.
+///
+/// ```
+/// use drop_struct_macro_derive::FFIResponse;
+/// use ffi_toolkit::{catch_panic_result, free_raw_ptr, FCPResponseStatus};
+///
+/// #[repr(C)]
+/// #[derive(FFIResponse)]
+/// pub struct SizeResponse {
+///     pub status_code: FCPResponseStatus,
+///     pub error_msg: *const libc::c_char,
+///     pub size: u64,
+/// }
+///
+///
+/// let response: *mut SizeResponse = catch_panic_result(|| {
+///     let size = std::fs::metadata("/no/such/file")?.len();
+///     Ok::<_, std::io::Error>(SizeResponse { size, ..Default::default() })
+/// });
+/// assert_eq!(unsafe { (*response).status_code }, FCPResponseStatus::FCPReceiverError);
+/// unsafe { free_raw_ptr(response) };
+/// ```
+#[cfg(feature = "std")]
+pub fn catch_panic_result<F, T, E>(callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage,
+    E: IntoFFIError,
+    F: FnOnce() -> Result<T, E>,
+{
+    catch_panic_response(|| match callback() {
+        Ok(response) => raw_ptr(response),
+        Err(err) => {
+            let message = err.message();
+            #[cfg(feature = "log")]
+            error_log::report_error(err.code(), &message);
             let mut response = T::default();
-            let message = CString::new(format!("Rust panic: {}", error_msg))
-                .unwrap()
-                .into_raw();
-            response.set_error((FCPResponseStatus::FCPUnclassifiedError, message));
+            response.set_error((err.code(), rust_str_to_c_str(message)));
+            response.set_error_chain(FfiErrorChain::new(err.chain()));
+            if let Some(payload) = err.payload() {
+                response.set_error_payload(payload);
+            }
             raw_ptr(response)
         }
+    })
+}
+
+/// Catch panics of functions returning plain values, a panic returns `R::default()`
+///
+/// For `extern "C"` functions returning e.g. `bool`, `u64` or small `#[repr(C)]` structs by
+/// value. The panic is lost, `catch_panic_value_with_last_error()` records it.
+#[cfg(feature = "std")]
+pub fn catch_panic_value<F, R>(callback: F) -> R
+where
+    R: Default,
+    F: FnOnce() -> R,
+{
+    let _call = lifecycle::CallGuard::enter();
+    panic::catch_unwind(panic::AssertUnwindSafe(callback)).unwrap_or_else(|_| {
+        lifecycle::take_last_panic_backtrace();
+        R::default()
+    })
+}
+
+/// Like `catch_panic_value()`, but a panic is recorded as the thread's last error
+#[cfg(feature = "std")]
+pub fn catch_panic_value_with_last_error<F, R>(callback: F) -> R
+where
+    R: Default,
+    F: FnOnce() -> R,
+{
+    let _call = lifecycle::CallGuard::enter();
+    match panic::catch_unwind(panic::AssertUnwindSafe(callback)) {
+        Ok(return_value) => return_value,
+        Err(panic) => {
+            set_last_error(
+                FCPResponseStatus::FCPUnclassifiedError,
+                panic_error_message(&*panic),
+            );
+            R::default()
+        }
+    }
+}
+
+// the error message for a caught panic, with the backtrace if one was captured
+#[cfg(feature = "std")]
+fn panic_error_message(payload: &(dyn Any + Send)) -> String {
+    let error_msg =
+        panic_payload_message(payload).unwrap_or_else(|| "no unwind information".to_string());
+    let mut message = format!("Rust panic: {}", error_msg);
+    if let Some(backtrace) = lifecycle::take_last_panic_backtrace() {
+        message += &format!("\n\nbacktrace:\n{}", backtrace);
+    }
+    message
+}
+
+// Runs the fields' destructors of a derived `Drop`, a panic (e.g. of a corrupted pointer or a
+// `with` function) is reported and the remaining fields are leaked instead of unwinding
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub fn drop_without_unwinding<F: FnOnce()>(type_name: &str, drop_fields: F) {
+    if let Err(panic) = panic::catch_unwind(panic::AssertUnwindSafe(drop_fields)) {
+        let message = format!(
+            "dropping a `{}` panicked, its remaining fields were leaked: {}",
+            type_name,
+            panic_error_message(&*panic)
+        );
+        eprintln!("ffi-toolkit: {}", message);
+        set_last_error(FCPResponseStatus::FCPUnclassifiedError, message);
+    }
+}
+
+// Without `std` panics can't be caught, the panic handler decides what happens
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+pub fn drop_without_unwinding<F: FnOnce()>(_type_name: &str, drop_fields: F) {
+    drop_fields();
+}
+
+// Runs a derived exported destructor, a panic aborts the process rather than unwinding into C
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub fn destroy_or_abort<F: FnOnce()>(type_name: &str, destroy: F) {
+    if let Err(panic) = panic::catch_unwind(panic::AssertUnwindSafe(destroy)) {
+        eprintln!(
+            "ffi-toolkit: destroying a `{}` panicked, aborting: {}",
+            type_name,
+            panic_error_message(&*panic)
+        );
+        std::process::abort();
     }
 }
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+pub fn destroy_or_abort<F: FnOnce()>(_type_name: &str, destroy: F) {
+    destroy();
+}
@@ -0,0 +1,343 @@
+//! Initialization and teardown of the toolkit.
+//!
+//! Hosts are expected to call `init()` once before using the library and `shutdown()` (exported
+//! as `fil_shutdown_ordered()`) before unloading it. With the `ctor` feature enabled both run automatically when the shared library
+//! is loaded and unloaded, for hosts that `dlopen` the library and never call an init function.
+//! Hosts that can't wait for calls that hang use `shutdown_with_timeout()` (`fil_shutdown()`),
+//! which gives up instead and reports what is left.
+
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::panic;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use crate::sync::{AtomicBool, AtomicUsize, Condvar, Mutex};
+use crate::{alloc, FfiDuration, FfiOption};
+
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+// Whether the panic hook captures backtraces, see `enable_panic_backtraces()`
+static CAPTURE_BACKTRACES: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+thread_local! {
+    static LAST_PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+    static LAST_PANIC_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+    // The number of guarded calls the current thread is in
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+    // Whether the current thread is running a shutdown, whose hooks may still make calls
+    static SHUTTING_DOWN_HERE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The order in which the subsystems are torn down on `shutdown()`
+///
+/// Subsystems that produce work are stopped before the ones that consume it: first nothing calls
+/// back into the host anymore, then Rust-side workers stop, then the registries holding objects
+/// for the host are cleared, then remaining resources (temporary files, locks) are released and
+/// finally the logger is flushed, so that everything before can still log.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+pub enum ShutdownPhase {
+    CallbackPumps,
+    WorkerPools,
+    Registries,
+    Resources,
+    Logger,
+}
+
+type ShutdownHooks = Vec<(ShutdownPhase, fn())>;
+
+// Set in `Lifecycle::calls` while a shutdown waits for the in-flight calls, the remaining bits
+// are the number of in-flight calls. Keeping both in one atomic means a call exiting either
+// sees the flag or its exit is seen by the shutdown, a wake-up can't get lost.
+const SHUTTING_DOWN: usize = 1 << (usize::BITS - 1);
+
+/// The state behind `init()` and `shutdown()`
+pub(crate) struct Lifecycle {
+    initialized: AtomicBool,
+    calls: AtomicUsize,
+    idle_lock: Mutex<()>,
+    idle: Condvar,
+    shutdown_hooks: Mutex<ShutdownHooks>,
+}
+
+impl Lifecycle {
+    pub(crate) fn new() -> Self {
+        Lifecycle {
+            initialized: AtomicBool::new(false),
+            calls: AtomicUsize::new(0),
+            idle_lock: Mutex::new(()),
+            idle: Condvar::new(),
+            shutdown_hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    // returns whether this call did the initialization
+    pub(crate) fn init(&self) -> bool {
+        !self.initialized.swap(true, Ordering::SeqCst)
+    }
+
+    // `own_calls` is the number of guarded calls the shutting down thread is in itself, those
+    // can't be waited for. If the other calls don't finish within `timeout` the shutdown is
+    // called off, the hooks don't run and the number of calls still in flight is returned.
+    pub(crate) fn shutdown(
+        &self,
+        own_calls: usize,
+        timeout: Option<Duration>,
+    ) -> Result<(), usize> {
+        let was_initialized = self.initialized.swap(false, Ordering::SeqCst);
+        self.calls.fetch_or(SHUTTING_DOWN, Ordering::SeqCst);
+        {
+            let deadline = timeout.map(|timeout| Instant::now() + timeout);
+            let mut idle_lock = self.idle_lock.lock().unwrap();
+            while self.in_flight() > own_calls {
+                let deadline = match deadline {
+                    Some(deadline) => deadline,
+                    None => {
+                        idle_lock = self.idle.wait(idle_lock).unwrap();
+                        continue;
+                    }
+                };
+                let now = Instant::now();
+                if now >= deadline {
+                    let in_flight = self.in_flight() - own_calls;
+                    self.calls.fetch_and(!SHUTTING_DOWN, Ordering::SeqCst);
+                    self.initialized.store(was_initialized, Ordering::SeqCst);
+                    return Err(in_flight);
+                }
+                idle_lock = self.idle.wait_timeout(idle_lock, deadline - now).unwrap().0;
+            }
+        }
+
+        let mut hooks = std::mem::take(&mut *self.shutdown_hooks.lock().unwrap());
+        // Within a phase, the hooks run in reverse order of their registration
+        hooks.reverse();
+        hooks.sort_by_key(|&(phase, _)| phase);
+        for (_, hook) in hooks {
+            // A failing hook must not prevent the others from running, nor unwind into the host
+            let _ = panic::catch_unwind(hook);
+        }
+        self.calls.fetch_and(!SHUTTING_DOWN, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub(crate) fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn register_shutdown_hook(&self, phase: ShutdownPhase, hook: fn()) {
+        self.shutdown_hooks.lock().unwrap().push((phase, hook));
+    }
+
+    pub(crate) fn enter_call(&self) {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn exit_call(&self) {
+        if self.calls.fetch_sub(1, Ordering::SeqCst) & SHUTTING_DOWN != 0 {
+            let _idle_lock = self.idle_lock.lock().unwrap();
+            self.idle.notify_all();
+        }
+    }
+
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.calls.load(Ordering::SeqCst) & SHUTTING_DOWN != 0
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.calls.load(Ordering::SeqCst) & !SHUTTING_DOWN
+    }
+}
+
+toolkit_global!(fn lifecycle() -> Lifecycle = Lifecycle::new());
+
+/// Marks a guarded call (e.g. `catch_panic_response()`) as in flight for as long as it lives
+pub(crate) struct CallGuard(());
+
+impl CallGuard {
+    pub(crate) fn enter() -> Self {
+        lifecycle().enter_call();
+        CALL_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        CallGuard(())
+    }
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        lifecycle().exit_call();
+    }
+}
+
+/// Runs the lightweight toolkit initialization
+///
+/// It is safe to call this function several times, only the first call has an effect.
+pub fn init() {
+    if lifecycle().init() {
+        INSTALL_PANIC_HOOK.call_once(install_panic_hook);
+    }
+}
+
+/// Tears the toolkit down
+///
+/// Waits until all guarded calls on other threads have returned, then runs the registered
+/// shutdown hooks phase by phase (see `ShutdownPhase`). Within a phase, hooks run in reverse
+/// order of their registration. Calling `init()` afterwards initializes the toolkit again.
+///
+/// Meanwhile new calls of `catch_panic_response()` and the functions built on it are rejected
+/// with `FCPBusyError`, calls nested in the ones in flight (e.g. of host callbacks) and calls of
+/// the shutdown hooks still run.
+pub fn shutdown() {
+    // Without a timeout it can't be called off
+    let _ = run_shutdown(None);
+}
+
+fn run_shutdown(timeout: Option<Duration>) -> Result<(), usize> {
+    SHUTTING_DOWN_HERE.with(|here| here.set(true));
+    let result = lifecycle().shutdown(CALL_DEPTH.with(Cell::get), timeout);
+    SHUTTING_DOWN_HERE.with(|here| here.set(false));
+    result
+}
+
+/// What `shutdown_with_timeout()` did
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FfiShutdownReport {
+    /// Whether the toolkit was torn down, false if the guarded calls didn't finish in time
+    pub completed: bool,
+    /// The guarded calls that were still running when the shutdown was called off
+    pub in_flight_calls: libc::size_t,
+    /// The responses and other allocations that weren't freed yet, known if allocations are
+    /// counted by `enable_alloc_stats()` or tracked by the `testing` feature
+    pub live_allocations: FfiOption<u64>,
+}
+
+/// Like `shutdown()`, but gives up if the guarded calls on other threads don't return within
+/// `timeout`
+///
+/// The toolkit is left as it was then, and may be shut down again later.
+pub fn shutdown_with_timeout<D: Into<FfiDuration>>(timeout: D) -> FfiShutdownReport {
+    let result = run_shutdown(Some(timeout.into().to_duration_saturating()));
+    let live_allocations = alloc::live_allocations().map(|live| live as u64);
+    FfiShutdownReport {
+        completed: result.is_ok(),
+        in_flight_calls: result.err().unwrap_or(0),
+        live_allocations: live_allocations.into(),
+    }
+}
+
+// whether a new guarded call on the current thread is rejected, see `shutdown()`
+pub(crate) fn rejects_calls() -> bool {
+    is_shutting_down() && CALL_DEPTH.with(Cell::get) == 1 && !SHUTTING_DOWN_HERE.with(Cell::get)
+}
+
+pub fn is_initialized() -> bool {
+    lifecycle().is_initialized()
+}
+
+/// Registers a function that is called once in the given phase of the next `shutdown()`
+pub fn register_shutdown_hook(phase: ShutdownPhase, hook: fn()) {
+    lifecycle().register_shutdown_hook(phase, hook);
+}
+
+// whether a `shutdown()` is waiting for the in-flight calls or running the hooks
+pub(crate) fn is_shutting_down() -> bool {
+    lifecycle().is_shutting_down()
+}
+
+/// The number of guarded calls that are currently running
+pub fn in_flight_calls() -> usize {
+    lifecycle().in_flight()
+}
+
+/// The number of guarded calls the current thread is in, more than 1 for a host callback that
+/// called back into the library
+pub fn call_depth() -> usize {
+    CALL_DEPTH.with(Cell::get)
+}
+
+/// Tears the toolkit down in a documented order, see `lifecycle::shutdown()`
+#[no_mangle]
+pub extern "C" fn fil_shutdown_ordered() {
+    let _ = panic::catch_unwind(shutdown);
+}
+
+/// Shuts the toolkit down, waiting at most `timeout_ms` for the calls in flight, see
+/// `shutdown_with_timeout()`
+#[no_mangle]
+pub extern "C" fn fil_shutdown(timeout_ms: u64) -> FfiShutdownReport {
+    panic::catch_unwind(|| shutdown_with_timeout(FfiDuration::from_millis(timeout_ms))).unwrap_or(
+        FfiShutdownReport {
+            completed: false,
+            in_flight_calls: 0,
+            live_allocations: FfiOption::none(),
+        },
+    )
+}
+
+/// The location of the last panic on the current thread
+///
+/// Only recorded once `init()` installed the toolkit's panic hook.
+pub fn last_panic_location() -> Option<String> {
+    LAST_PANIC_LOCATION.with(|location| location.borrow().clone())
+}
+
+/// Makes `catch_panic_response()` append a backtrace of the panic to the error message
+///
+/// Capturing a backtrace is slow, so this is off by default. Installs the toolkit's panic hook
+/// if `init()` didn't already.
+pub fn enable_panic_backtraces(enabled: bool) {
+    CAPTURE_BACKTRACES.store(enabled, Ordering::SeqCst);
+    if enabled {
+        INSTALL_PANIC_HOOK.call_once(install_panic_hook);
+    }
+}
+
+// installs the toolkit's panic hook unless `init()` did already
+pub(crate) fn install_panic_hook_once() {
+    INSTALL_PANIC_HOOK.call_once(install_panic_hook);
+}
+
+/// See `enable_panic_backtraces()`
+#[no_mangle]
+pub extern "C" fn fil_enable_panic_backtraces(enabled: bool) {
+    enable_panic_backtraces(enabled);
+}
+
+// the backtrace of the last panic on the current thread, if one was captured
+pub(crate) fn take_last_panic_backtrace() -> Option<Backtrace> {
+    LAST_PANIC_BACKTRACE.with(|backtrace| backtrace.borrow_mut().take())
+}
+
+fn install_panic_hook() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // First, before anything allocates
+        #[cfg(unix)]
+        crate::crash_log::log_panic(info.location(), info.payload());
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()));
+        LAST_PANIC_LOCATION.with(|last| *last.borrow_mut() = location);
+        let backtrace = if CAPTURE_BACKTRACES.load(Ordering::SeqCst) {
+            Some(Backtrace::force_capture())
+        } else {
+            None
+        };
+        LAST_PANIC_BACKTRACE.with(|last| *last.borrow_mut() = backtrace);
+        previous_hook(info);
+    }));
+}
+
+#[cfg(feature = "ctor")]
+#[ctor::ctor]
+fn on_load() {
+    init();
+}
+
+#[cfg(feature = "ctor")]
+#[ctor::dtor]
+fn on_unload() {
+    shutdown();
+}
@@ -0,0 +1,204 @@
+//! Advisory file locks, e.g. to coordinate access to a parameter cache shared by several
+//! processes.
+//!
+//! Locks are released when their handle is destroyed, or at the latest on `shutdown()`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{
+    c_str_to_path, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str, CodeAndMessage,
+    FCPResponseStatus, FfiDuration,
+};
+
+// How often a contended lock is retried until the timeout is over
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Default)]
+struct Registry {
+    // The locked files by lock id, closing a file releases its lock
+    files: HashMap<u64, File>,
+    cleanup_registered: bool,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Why a lock couldn't be acquired
+#[derive(Debug)]
+pub enum LockError {
+    /// Another process (or handle) held the lock for the whole timeout
+    Contended,
+    Io(io::Error),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockError::Contended => write!(f, "the lock is held by someone else"),
+            LockError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// An advisory lock on a file, released on drop
+#[derive(Debug)]
+pub struct FileLock {
+    id: u64,
+}
+
+impl FileLock {
+    /// Locks the file at `path`, which is created if it doesn't exist
+    ///
+    /// Several shared locks can be held at the same time, an exclusive lock excludes all others.
+    /// Waits for up to `timeout` if the lock is contended.
+    pub fn acquire(path: &Path, exclusive: bool, timeout: Duration) -> Result<FileLock, LockError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(LockError::Io)?;
+        let operation = if exclusive {
+            libc::LOCK_EX
+        } else {
+            libc::LOCK_SH
+        } | libc::LOCK_NB;
+
+        let start = Instant::now();
+        loop {
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                break;
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EWOULDBLOCK) {
+                return Err(LockError::Io(err));
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(LockError::Contended);
+            }
+            thread::sleep(RETRY_INTERVAL.min(timeout - elapsed));
+        }
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let mut registry = REGISTRY.lock().unwrap();
+        let registry = registry.get_or_insert_with(Registry::default);
+        registry.files.insert(id, file);
+        if !registry.cleanup_registered {
+            lifecycle::register_shutdown_hook(ShutdownPhase::Resources, release_all);
+            registry.cleanup_registered = true;
+        }
+        Ok(FileLock { id })
+    }
+
+    /// Whether the lock is still held, it isn't after a shutdown
+    pub fn is_held(&self) -> bool {
+        REGISTRY
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|registry| registry.files.contains_key(&self.id))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let file = REGISTRY
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|registry| registry.files.remove(&self.id));
+        // Closing the file releases the lock
+        drop(file);
+    }
+}
+
+fn release_all() {
+    drop(REGISTRY.lock().unwrap().take());
+}
+
+/// The result of `fil_lock_file()`
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum FfiLockStatus {
+    Locked,
+    /// The lock was held by someone else for the whole timeout
+    Contended,
+    /// The file couldn't be opened or locked
+    IoError,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct LockFileResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub lock_status: FfiLockStatus,
+    /// Needs to be destroyed with `fil_unlock_file()`, not together with the response
+    #[ffi_drop(skip)]
+    pub handle: *mut FileLock,
+}
+
+impl Default for LockFileResponse {
+    fn default() -> Self {
+        LockFileResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            lock_status: FfiLockStatus::Locked,
+            handle: ptr::null_mut(),
+        }
+    }
+}
+
+crate::code_and_message_impl!(LockFileResponse);
+
+/// Locks the file at `path`, see `FileLock::acquire()`
+#[no_mangle]
+pub unsafe extern "C" fn fil_lock_file(
+    path: *const libc::c_char,
+    exclusive: bool,
+    timeout: FfiDuration,
+) -> *mut LockFileResponse {
+    let path = c_str_to_path(path);
+    let timeout = timeout.to_duration().unwrap_or(Duration::MAX);
+    let mut response = LockFileResponse::default();
+    match FileLock::acquire(&path, exclusive, timeout) {
+        Ok(lock) => response.handle = raw_ptr(lock),
+        Err(err) => {
+            response.status_code = FCPResponseStatus::FCPReceiverError;
+            response.lock_status = match err {
+                LockError::Contended => FfiLockStatus::Contended,
+                LockError::Io(_) => FfiLockStatus::IoError,
+            };
+            response.error_msg =
+                rust_str_to_c_str(format!("failed to lock {}: {}", path.display(), err));
+        }
+    }
+    raw_ptr(response)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_lock_file_response(ptr: *mut LockFileResponse) {
+    free_raw_ptr(ptr);
+}
+
+/// Releases the lock
+#[no_mangle]
+pub unsafe extern "C" fn fil_unlock_file(handle: *mut FileLock) {
+    free_raw_ptr(handle);
+}
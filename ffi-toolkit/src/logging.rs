@@ -0,0 +1,200 @@
+//! Forwarding of the `log` records of the library to the host.
+//!
+//! `init_log()` installs the toolkit's logger, which hands every record to a C callback or
+//! writes it as a line to a file descriptor. Records are filtered by a level that can be changed
+//! at any time with `fil_set_log_level()`.
+
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::io::Write;
+use std::sync::{Mutex, Once};
+
+#[cfg(unix)]
+use crate::fd::BorrowedFfiFd;
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::truncated_c_string;
+
+status_code_enum! {
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+    pub enum FfiLogLevel {
+        Off = 0 => FIL_LOG_OFF,
+        Error = 1 => FIL_LOG_ERROR,
+        Warn = 2 => FIL_LOG_WARN,
+        Info = 3 => FIL_LOG_INFO,
+        Debug = 4 => FIL_LOG_DEBUG,
+        Trace = 5 => FIL_LOG_TRACE,
+    }
+}
+
+impl From<FfiLogLevel> for log::LevelFilter {
+    fn from(level: FfiLogLevel) -> Self {
+        match level {
+            FfiLogLevel::Off => log::LevelFilter::Off,
+            FfiLogLevel::Error => log::LevelFilter::Error,
+            FfiLogLevel::Warn => log::LevelFilter::Warn,
+            FfiLogLevel::Info => log::LevelFilter::Info,
+            FfiLogLevel::Debug => log::LevelFilter::Debug,
+            FfiLogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+impl From<log::Level> for FfiLogLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => FfiLogLevel::Error,
+            log::Level::Warn => FfiLogLevel::Warn,
+            log::Level::Info => FfiLogLevel::Info,
+            log::Level::Debug => FfiLogLevel::Debug,
+            log::Level::Trace => FfiLogLevel::Trace,
+        }
+    }
+}
+
+/// Receives a record, the strings are only valid during the call
+///
+/// It's called on whatever thread logged the record, possibly on several at once. It must not
+/// call back into functions of the library that log.
+pub type FfiLogCallback = extern "C" fn(
+    level: FfiLogLevel,
+    target: *const libc::c_char,
+    message: *const libc::c_char,
+    user_data: *mut libc::c_void,
+);
+
+/// Where the records go
+pub enum LogSink {
+    Callback {
+        callback: FfiLogCallback,
+        user_data: *mut libc::c_void,
+    },
+    /// Every record is written as a `LEVEL target: message` line
+    #[cfg(unix)]
+    File(File),
+}
+
+// The host guarantees that `user_data` can be used from any thread
+unsafe impl Send for LogSink {}
+
+static SINK: Mutex<Option<LogSink>> = Mutex::new(None);
+static INSTALL_LOGGER: Once = Once::new();
+
+struct FfiLogger;
+
+static LOGGER: FfiLogger = FfiLogger;
+
+impl log::Log for FfiLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut sink = SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match sink.as_mut() {
+            Some(LogSink::Callback {
+                callback,
+                user_data,
+            }) => {
+                let target = truncated_c_string(record.target().to_string());
+                let message = truncated_c_string(record.args().to_string());
+                callback(
+                    record.level().into(),
+                    target.as_ptr(),
+                    message.as_ptr(),
+                    *user_data,
+                );
+            }
+            #[cfg(unix)]
+            Some(LogSink::File(file)) => {
+                let _ = writeln!(
+                    file,
+                    "{} {}: {}",
+                    record.level(),
+                    record.target(),
+                    record.args()
+                );
+            }
+            None => {}
+        }
+    }
+
+    fn flush(&self) {
+        #[cfg(unix)]
+        if let Some(LogSink::File(file)) = SINK.lock().unwrap().as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Installs the toolkit's logger, sending the records up to `level` to `sink`
+///
+/// Calling it again replaces the sink and the level. Returns false if another logger was
+/// installed before, e.g. by the host's `env_logger`. The sink is dropped in the
+/// `ShutdownPhase::Logger` phase of `shutdown()`.
+pub fn init_log(sink: LogSink, level: FfiLogLevel) -> bool {
+    let mut installed = true;
+    INSTALL_LOGGER.call_once(|| {
+        installed = log::set_logger(&LOGGER).is_ok();
+    });
+    if !installed || !is_installed() {
+        return false;
+    }
+    let previous_sink = SINK.lock().unwrap().replace(sink);
+    if previous_sink.is_none() {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Logger, close_log);
+    }
+    log::set_max_level(level.into());
+    true
+}
+
+// whether our logger is the one `log` uses
+fn is_installed() -> bool {
+    std::ptr::eq(
+        log::logger() as *const dyn log::Log as *const u8,
+        &LOGGER as *const FfiLogger as *const u8,
+    )
+}
+
+/// Flushes and drops the sink, records are discarded until the next `init_log()`
+pub fn close_log() {
+    log::logger().flush();
+    *SINK.lock().unwrap() = None;
+}
+
+/// Sends the records up to `level` to `callback`, see `init_log()`
+#[no_mangle]
+pub extern "C" fn fil_init_log_callback(
+    callback: FfiLogCallback,
+    user_data: *mut libc::c_void,
+    level: FfiLogLevel,
+) -> bool {
+    init_log(
+        LogSink::Callback {
+            callback,
+            user_data,
+        },
+        level,
+    )
+}
+
+/// Writes the records up to `level` to a duplicate of `fd`, see `init_log()`
+///
+/// Returns false as well if `fd` isn't valid.
+#[cfg(unix)]
+#[no_mangle]
+pub extern "C" fn fil_init_log_fd(fd: BorrowedFfiFd, level: FfiLogLevel) -> bool {
+    match fd.import() {
+        Ok(owned) => init_log(LogSink::File(owned.into_file()), level),
+        Err(_) => false,
+    }
+}
+
+/// Changes the level up to which records are forwarded
+#[no_mangle]
+pub extern "C" fn fil_set_log_level(level: FfiLogLevel) {
+    log::set_max_level(level.into());
+}
@@ -0,0 +1,67 @@
+//! Model checks of the toolkit's shared state, run with `--features loom`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use loom::thread;
+
+use crate::lifecycle::{Lifecycle, ShutdownPhase};
+
+static HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_hook_call() {
+    HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// A hook registered concurrently with a shutdown either runs as part of it or stays registered
+/// for the next one, it's never lost nor run twice.
+#[test]
+fn shutdown_races_hook_registration() {
+    loom::model(|| {
+        HOOK_CALLS.store(0, Ordering::SeqCst);
+        let lifecycle: &'static Lifecycle = Box::leak(Box::new(Lifecycle::new()));
+        lifecycle.init();
+
+        let registering = thread::spawn(move || {
+            lifecycle.register_shutdown_hook(ShutdownPhase::Resources, count_hook_call)
+        });
+        let shutting_down = thread::spawn(move || lifecycle.shutdown(0, None));
+        registering.join().unwrap();
+        shutting_down.join().unwrap().unwrap();
+
+        lifecycle.init();
+        lifecycle.shutdown(0, None).unwrap();
+        assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 1);
+    });
+}
+
+/// Concurrent initializations initialize exactly once.
+#[test]
+fn concurrent_init() {
+    loom::model(|| {
+        let lifecycle: &'static Lifecycle = Box::leak(Box::new(Lifecycle::new()));
+        let first = thread::spawn(move || lifecycle.init());
+        let second = thread::spawn(move || lifecycle.init());
+        let initialized = [first.join().unwrap(), second.join().unwrap()];
+        assert_eq!(initialized.iter().filter(|&&done| done).count(), 1);
+        assert!(lifecycle.is_initialized());
+    });
+}
+
+/// A shutdown returns only once an in-flight call exited, the wake-up can't get lost.
+#[test]
+fn shutdown_waits_for_in_flight_call() {
+    loom::model(|| {
+        let lifecycle: &'static Lifecycle = Box::leak(Box::new(Lifecycle::new()));
+        let finished: &'static loom::sync::atomic::AtomicBool =
+            Box::leak(Box::new(loom::sync::atomic::AtomicBool::new(false)));
+        lifecycle.enter_call();
+
+        let call = thread::spawn(move || {
+            finished.store(true, Ordering::SeqCst);
+            lifecycle.exit_call();
+        });
+        lifecycle.shutdown(0, None).unwrap();
+        assert!(finished.load(Ordering::SeqCst));
+        call.join().unwrap();
+    });
+}
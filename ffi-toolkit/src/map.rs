@@ -0,0 +1,54 @@
+use crate::FfiStringArray;
+
+/// A map of strings, as two arrays of the same length
+///
+/// The value of `keys[i]` is `values[i]`. Dropping it frees everything.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct FfiMap {
+    pub keys: FfiStringArray,
+    pub values: FfiStringArray,
+}
+
+impl FfiMap {
+    pub fn new<I, K, V>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let (keys, values): (Vec<String>, Vec<String>) = entries
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .unzip();
+        FfiMap {
+            keys: FfiStringArray::new(keys),
+            values: FfiStringArray::new(values),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Copies the entries, in order
+    pub fn to_vec(&self) -> Vec<(String, String)> {
+        self.keys
+            .to_vec()
+            .into_iter()
+            .zip(self.values.to_vec())
+            .collect()
+    }
+
+    /// The value of the first entry with the given key
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.to_vec()
+            .into_iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+}
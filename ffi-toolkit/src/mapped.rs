@@ -0,0 +1,182 @@
+//! Memory-mapped regions shared by the host.
+//!
+//! The host maps a (possibly multi-GB) file and passes the region as `FfiMappedRegion`. The
+//! Rust side imports it into a `MappedRegion` and hands a guard back to the host, which must
+//! keep the region mapped until it calls `fil_release_mapped_region()` with the guard. Releasing
+//! waits for ongoing accesses; afterwards all accesses fail, so that Rust code that still holds
+//! on to the region never touches unmapped memory.
+
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{free_raw_ptr, raw_ptr};
+
+/// A region mapped by the host
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FfiMappedRegion {
+    pub ptr: *mut u8,
+    pub len: libc::size_t,
+    /// Whether the region is mapped read-only, the Rust side won't write to it then
+    pub read_only: bool,
+}
+
+/// The Rust side of a host-provided region, see the module documentation
+#[derive(Debug)]
+pub struct MappedRegion {
+    ptr: *mut u8,
+    len: usize,
+    read_only: bool,
+    // Whether the host released the region
+    released: RwLock<bool>,
+}
+
+// The region is only accessed through the lock
+unsafe impl Send for MappedRegion {}
+unsafe impl Sync for MappedRegion {}
+
+/// The handle the host releases the region with, created by `MappedRegion::into_guard()`
+pub struct MappedRegionGuard(Arc<MappedRegion>);
+
+impl MappedRegion {
+    /// Validates the region passed by the host
+    ///
+    /// The region must stay mapped until the host releases the guard returned by
+    /// `into_guard()`.
+    pub unsafe fn import(region: FfiMappedRegion) -> Result<Arc<MappedRegion>, String> {
+        if region.ptr.is_null() && region.len != 0 {
+            return Err("mapped region must not be null".to_string());
+        }
+        if region.len > isize::MAX as usize
+            || (region.ptr as usize).checked_add(region.len).is_none()
+        {
+            return Err(format!(
+                "mapped region of {} bytes is too large",
+                region.len
+            ));
+        }
+        Ok(Arc::new(MappedRegion {
+            ptr: region.ptr,
+            len: region.len,
+            read_only: region.read_only,
+            released: RwLock::new(false),
+        }))
+    }
+
+    /// The guard to hand to the host, which releases it with `fil_release_mapped_region()`
+    pub fn into_guard(self: Arc<Self>) -> *mut MappedRegionGuard {
+        raw_ptr(MappedRegionGuard(self))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn is_released(&self) -> bool {
+        *self.released.read().unwrap()
+    }
+
+    /// The contents of the region, `None` once the host released it
+    ///
+    /// The host can't release the region while the returned value is alive.
+    pub fn bytes(&self) -> Option<RegionBytes<'_>> {
+        let released = self.released.read().unwrap();
+        if *released {
+            return None;
+        }
+        Some(RegionBytes {
+            _released: released,
+            bytes: self.as_slice(),
+        })
+    }
+
+    /// Mutable access to the region, `None` if it is read-only or released
+    ///
+    /// Excludes all other accesses while the returned value is alive.
+    pub fn bytes_mut(&self) -> Option<RegionBytesMut<'_>> {
+        if self.read_only {
+            return None;
+        }
+        let released = self.released.write().unwrap();
+        if *released {
+            return None;
+        }
+        let bytes = if self.len == 0 {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+        };
+        Some(RegionBytesMut {
+            _released: released,
+            bytes,
+        })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.ptr, self.len) }
+        }
+    }
+
+    // waits for all accesses to end
+    fn release(&self) {
+        *self.released.write().unwrap() = true;
+    }
+}
+
+/// Shared access to a region, see `MappedRegion::bytes()`
+pub struct RegionBytes<'a> {
+    _released: RwLockReadGuard<'a, bool>,
+    bytes: &'a [u8],
+}
+
+impl Deref for RegionBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+/// Exclusive access to a region, see `MappedRegion::bytes_mut()`
+pub struct RegionBytesMut<'a> {
+    _released: RwLockWriteGuard<'a, bool>,
+    bytes: &'a mut [u8],
+}
+
+impl Deref for RegionBytesMut<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl DerefMut for RegionBytesMut<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.bytes
+    }
+}
+
+/// Releases a region, the host may unmap it once this function returned
+///
+/// Blocks until ongoing accesses from the Rust side are done.
+#[no_mangle]
+pub unsafe extern "C" fn fil_release_mapped_region(guard: *mut MappedRegionGuard) {
+    if guard.is_null() {
+        return;
+    }
+    (*guard).0.release();
+    free_raw_ptr(guard);
+}
@@ -0,0 +1,263 @@
+//! Call counts and latencies of the exported functions, keyed by function name.
+//!
+//! Functions wrapped in `catch_panic_response_named()` or `catch_panic_result_named()`, which is
+//! what `#[ffi_catch_panic]` expands to, are measured once the metrics are enabled with
+//! `enable_call_metrics()` (exported as `fil_enable_call_metrics()`). The host reads them with
+//! `fil_metrics_snapshot()` and starts over with `fil_reset_metrics()`. Disabled, a call costs
+//! one atomic load.
+
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{
+    catch_panic_response, catch_panic_result, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str,
+    CodeAndMessage, FCPResponseStatus, FfiDuration, FfiInstant, IntoFFIError, StatusCode,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static METRICS: Mutex<Option<HashMap<&'static str, CallMetrics>>> = Mutex::new(None);
+
+/// The calls of one function since the metrics were enabled or reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallMetrics {
+    pub name: &'static str,
+    pub calls: u64,
+    /// Calls returning an error, only known for `catch_panic_result_named()`
+    pub errors: u64,
+    pub panics: u64,
+    pub total_ns: u64,
+    pub max_ns: u64,
+}
+
+impl CallMetrics {
+    fn new(name: &'static str) -> Self {
+        CallMetrics {
+            name,
+            calls: 0,
+            errors: 0,
+            panics: 0,
+            total_ns: 0,
+            max_ns: 0,
+        }
+    }
+
+    /// The mean latency, 0 without calls
+    pub fn mean_ns(&self) -> u64 {
+        self.total_ns.checked_div(self.calls).unwrap_or(0)
+    }
+
+    pub fn total(&self) -> FfiDuration {
+        FfiDuration::from_nanos(self.total_ns)
+    }
+
+    pub fn max(&self) -> FfiDuration {
+        FfiDuration::from_nanos(self.max_ns)
+    }
+
+    pub fn mean(&self) -> FfiDuration {
+        FfiDuration::from_nanos(self.mean_ns())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    Returned,
+    Failed,
+    // The callback didn't return, a panic or an injected fail point
+    Panicked,
+}
+
+fn record_call(name: &'static str, elapsed: FfiDuration, outcome: Outcome) {
+    let elapsed_ns = elapsed.to_nanos().unwrap_or(u64::MAX);
+    if let Some(metrics) = METRICS.lock().unwrap().as_mut() {
+        let metrics = metrics
+            .entry(name)
+            .or_insert_with(|| CallMetrics::new(name));
+        metrics.calls += 1;
+        match outcome {
+            Outcome::Returned => {}
+            Outcome::Failed => metrics.errors += 1,
+            Outcome::Panicked => metrics.panics += 1,
+        }
+        metrics.total_ns = metrics.total_ns.saturating_add(elapsed_ns);
+        metrics.max_ns = metrics.max_ns.max(elapsed_ns);
+    }
+}
+
+// Calls `call`, which sets the outcome once its callback returned, and records it if enabled
+fn measured<R>(name: &'static str, call: impl FnOnce(&mut Outcome) -> R) -> R {
+    let mut outcome = Outcome::Panicked;
+    if !ENABLED.load(Ordering::Relaxed) {
+        return call(&mut outcome);
+    }
+    let started = FfiInstant::now();
+    let return_value = call(&mut outcome);
+    record_call(name, started.elapsed(), outcome);
+    return_value
+}
+
+/// Like `catch_panic_response()`, recording the call as `name` if the metrics are enabled
+///
+/// The status of the response isn't known, so only panics are counted as failures.
+pub fn catch_panic_response_named<F, T, C>(name: &'static str, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+    F: FnOnce() -> *mut T,
+{
+    measured(name, |outcome| {
+        catch_panic_response(|| {
+            let response = callback();
+            *outcome = Outcome::Returned;
+            response
+        })
+    })
+}
+
+/// Like `catch_panic_result()`, recording the call as `name` if the metrics are enabled
+pub fn catch_panic_result_named<F, T, E>(name: &'static str, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage,
+    E: IntoFFIError,
+    F: FnOnce() -> Result<T, E>,
+{
+    measured(name, |outcome| {
+        catch_panic_result(|| {
+            let result = callback();
+            *outcome = match result {
+                Ok(_) => Outcome::Returned,
+                Err(_) => Outcome::Failed,
+            };
+            result
+        })
+    })
+}
+
+/// Starts or stops recording calls, stopping discards the metrics
+///
+/// `shutdown()` logs them (with the `log` feature) and stops recording.
+pub fn enable_call_metrics(enabled: bool) {
+    let mut metrics = METRICS.lock().unwrap();
+    *metrics = if enabled {
+        Some(metrics.take().unwrap_or_default())
+    } else {
+        None
+    };
+    let was_enabled = ENABLED.swap(enabled, Ordering::SeqCst);
+    if enabled && !was_enabled {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Registries, flush_call_metrics);
+    }
+}
+
+/// The target of the metrics logged on `shutdown()`
+#[cfg(feature = "log")]
+pub const METRICS_LOG_TARGET: &str = "ffi_toolkit::metrics";
+
+// Logs the metrics through the toolkit's logger on shutdown, before it is closed, and stops
+// recording
+fn flush_call_metrics() {
+    #[cfg(feature = "log")]
+    for metrics in call_metrics() {
+        log::info!(
+            target: METRICS_LOG_TARGET,
+            "{}: {} calls, {} errors, {} panics, mean {:?}, max {:?}",
+            metrics.name,
+            metrics.calls,
+            metrics.errors,
+            metrics.panics,
+            metrics.mean().to_duration_saturating(),
+            metrics.max().to_duration_saturating()
+        );
+    }
+    enable_call_metrics(false);
+}
+
+pub fn call_metrics_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// The metrics by function name, sorted by it, empty unless enabled
+pub fn call_metrics() -> Vec<CallMetrics> {
+    let mut by_name: Vec<_> = METRICS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(Vec::new, |metrics| metrics.values().cloned().collect());
+    by_name.sort_by_key(|metrics| metrics.name);
+    by_name
+}
+
+/// Discards the metrics recorded so far, they stay enabled
+pub fn reset_call_metrics() {
+    if let Some(metrics) = METRICS.lock().unwrap().as_mut() {
+        metrics.clear();
+    }
+}
+
+/// See `enable_call_metrics()`
+#[no_mangle]
+pub extern "C" fn fil_enable_call_metrics(enabled: bool) {
+    enable_call_metrics(enabled);
+}
+
+/// See `reset_call_metrics()`
+#[no_mangle]
+pub extern "C" fn fil_reset_metrics() {
+    reset_call_metrics();
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct FfiCallMetrics {
+    pub name: *const libc::c_char,
+    pub calls: u64,
+    pub errors: u64,
+    pub panics: u64,
+    pub total_ns: u64,
+    pub max_ns: u64,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct CallMetricsResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub enabled: bool,
+    pub functions_ptr: *const FfiCallMetrics,
+    pub functions_len: libc::size_t,
+}
+
+/// The metrics, free them with `fil_destroy_call_metrics_response()`
+#[no_mangle]
+pub extern "C" fn fil_metrics_snapshot() -> *mut CallMetricsResponse {
+    let functions: Box<[FfiCallMetrics]> = call_metrics()
+        .into_iter()
+        .map(|metrics| FfiCallMetrics {
+            name: rust_str_to_c_str(metrics.name),
+            calls: metrics.calls,
+            errors: metrics.errors,
+            panics: metrics.panics,
+            total_ns: metrics.total_ns,
+            max_ns: metrics.max_ns,
+        })
+        .collect();
+    let functions_len = functions.len();
+    raw_ptr(CallMetricsResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: ptr::null(),
+        enabled: call_metrics_enabled(),
+        functions_ptr: Box::into_raw(functions) as *const FfiCallMetrics,
+        functions_len,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_call_metrics_response(ptr: *mut CallMetricsResponse) {
+    free_raw_ptr(ptr);
+}
@@ -0,0 +1,179 @@
+//! Global state that the host initializes once over the FFI, e.g. a parameter cache or a logger.
+
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
+
+use crate::{panic_error_message, set_last_error, FCPResponseStatus, IntoFFIError};
+
+/// A value initialized once, whose initialization is reported to C as a status code
+///
+/// ```
+/// use ffi_toolkit::{FCPResponseStatus, FfiOnceCell};
+///
+/// static PARAMETERS: FfiOnceCell<Vec<u8>> = FfiOnceCell::new("the parameter cache");
+///
+/// #[no_mangle]
+/// pub extern "C" fn fil_init_parameters() -> FCPResponseStatus {
+///     PARAMETERS.init_from_ffi(|| Ok::<_, std::io::Error>(vec![1, 2, 3]))
+/// }
+///
+/// assert_eq!(fil_init_parameters(), FCPResponseStatus::FCPNoError);
+/// assert_eq!(fil_init_parameters(), FCPResponseStatus::FCPCallerError);
+/// assert_eq!(PARAMETERS.get_or_error().unwrap(), &[1, 2, 3]);
+/// ```
+pub struct FfiOnceCell<T> {
+    name: &'static str,
+    // A leaked `Box`, null until initialized
+    value: AtomicPtr<T>,
+    init: Mutex<()>,
+    _marker: PhantomData<*mut T>,
+}
+
+// Like `std::sync::OnceLock`: the value is shared, and is dropped by whichever thread drops the
+// cell
+unsafe impl<T: Send> Send for FfiOnceCell<T> {}
+unsafe impl<T: Send + Sync> Sync for FfiOnceCell<T> {}
+
+/// The error of a cell that isn't initialized yet, or that is initialized already
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnceCellError {
+    NotInitialized(&'static str),
+    AlreadyInitialized(&'static str),
+}
+
+impl fmt::Display for OnceCellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OnceCellError::NotInitialized(name) => write!(f, "{} is not initialized", name),
+            OnceCellError::AlreadyInitialized(name) => {
+                write!(f, "{} is already initialized", name)
+            }
+        }
+    }
+}
+
+impl Error for OnceCellError {}
+
+// Initializing is up to the caller
+impl IntoFFIError for OnceCellError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<T> FfiOnceCell<T> {
+    /// An empty cell, `name` is what errors call it
+    pub const fn new(name: &'static str) -> Self {
+        FfiOnceCell {
+            name,
+            value: AtomicPtr::new(ptr::null_mut()),
+            init: Mutex::new(()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        unsafe { self.value.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// The value, for use with `?` in functions returning a `Result` to `catch_panic_result()`
+    pub fn get_or_error(&self) -> Result<&T, OnceCellError> {
+        self.get().ok_or(OnceCellError::NotInitialized(self.name))
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.get().is_some()
+    }
+
+    /// Initializes the cell with `value`, which is returned if the cell is initialized already
+    pub fn set(&self, value: T) -> Result<&T, T> {
+        let mut value = Some(value);
+        match self.try_init(|| Ok::<_, OnceCellError>(value.take().unwrap())) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(value.take().unwrap()),
+        }
+    }
+
+    /// Initializes the cell with the value `ctor` returns, unless it is initialized already
+    ///
+    /// `ctor` runs at most once at a time, concurrent calls wait for it. If it fails the cell
+    /// stays empty.
+    pub fn try_init<F, E>(&self, ctor: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<OnceCellError>,
+    {
+        let _init = self
+            .init
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if self.is_initialized() {
+            return Err(OnceCellError::AlreadyInitialized(self.name).into());
+        }
+        let value = Box::into_raw(Box::new(ctor()?));
+        self.value.store(value, Ordering::Release);
+        Ok(unsafe { &*value })
+    }
+
+    /// Initializes the cell for an exported function, the status code is returned to C
+    ///
+    /// A cell that is initialized already is a `FCPCallerError`. An error (or panic) of `ctor`
+    /// leaves the cell empty, its message is recorded as the thread's last error, see
+    /// `last_error()`.
+    pub fn init_from_ffi<F, E>(&self, ctor: F) -> FCPResponseStatus
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: IntoFFIError,
+    {
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            self.try_init(|| ctor().map_err(|err| (err.code(), err.message())))
+                .map(|_| ())
+        }));
+        let (code, message) = match result {
+            Ok(Ok(())) => return FCPResponseStatus::FCPNoError,
+            Ok(Err(err)) => err,
+            Err(panic) => (
+                FCPResponseStatus::FCPUnclassifiedError,
+                panic_error_message(&*panic),
+            ),
+        };
+        set_last_error(code, message);
+        code
+    }
+
+    /// Empties the cell, so that tests can initialize it again
+    ///
+    /// The old value is leaked, references to it may still be around.
+    #[cfg(feature = "testing")]
+    pub fn reset_for_tests(&self) {
+        let _init = self
+            .init
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.value.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl From<OnceCellError> for (FCPResponseStatus, String) {
+    fn from(err: OnceCellError) -> Self {
+        (err.code(), err.message())
+    }
+}
+
+impl<T> Drop for FfiOnceCell<T> {
+    fn drop(&mut self) {
+        let value = *self.value.get_mut();
+        if !value.is_null() {
+            drop(unsafe { Box::from_raw(value) });
+        }
+    }
+}
@@ -0,0 +1,67 @@
+/// Declares an opaque handle type for a long-lived Rust object that is owned by C
+///
+/// The handle type is a `#[repr(C)]` struct C can't look into, so cbindgen emits it as an
+/// incomplete type. Generated are `wrap()`, boxing the object and handing out a pointer to the
+/// handle type, `borrow()`/`borrow_mut()`, which return `None` for null pointers, and the exported
+/// destructor, which ignores null pointers.
+///
+/// ```
+/// use ffi_toolkit::declare_opaque_handle;
+///
+/// pub struct Prover {
+///     proofs: u64,
+/// }
+///
+/// declare_opaque_handle! {
+///     /// A prover created by `fil_create_prover()`
+///     pub struct FfiProver(Prover);
+///     destroy = fil_destroy_prover;
+/// }
+///
+/// let prover = FfiProver::wrap(Prover { proofs: 0 });
+/// unsafe {
+///     FfiProver::borrow_mut(prover).unwrap().proofs += 1;
+///     assert_eq!(FfiProver::borrow(prover).unwrap().proofs, 1);
+///     assert!(FfiProver::borrow(std::ptr::null()).is_none());
+///     fil_destroy_prover(prover);
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_opaque_handle {
+    {
+        $(#[$meta:meta])*
+        pub struct $handle:ident($inner:ty);
+        destroy = $destroy:ident;
+    } => {
+        $(#[$meta])*
+        #[repr(C)]
+        pub struct $handle {
+            _private: [u8; 0],
+        }
+
+        impl $handle {
+            /// Hands ownership of `value` over to C, which frees it with the destructor
+            pub fn wrap(value: $inner) -> *mut $handle {
+                $crate::raw_ptr(value) as *mut $handle
+            }
+
+            /// The object behind `ptr`, `None` if it is null
+            ///
+            /// `ptr` must be null or come from `wrap()` and not be destroyed yet.
+            pub unsafe fn borrow<'a>(ptr: *const $handle) -> Option<&'a $inner> {
+                (ptr as *const $inner).as_ref()
+            }
+
+            /// Like `borrow()`, C must not use the handle concurrently
+            pub unsafe fn borrow_mut<'a>(ptr: *mut $handle) -> Option<&'a mut $inner> {
+                (ptr as *mut $inner).as_mut()
+            }
+        }
+
+        #[doc = concat!("Frees a `", stringify!($handle), "`, null pointers are ignored")]
+        #[no_mangle]
+        pub unsafe extern "C" fn $destroy(ptr: *mut $handle) {
+            $crate::free_raw_ptr(ptr as *mut $inner);
+        }
+    };
+}
@@ -0,0 +1,60 @@
+/// An optional scalar that can cross the FFI boundary, e.g. an optional parameter
+///
+/// `Option<T>` has no C layout for scalars, so the value comes with a tag. The value of a `None`
+/// is `T::default()` (so that the struct is always initialized) and is ignored. cbindgen emits a
+/// struct per instantiation, see the aliases below.
+#[repr(C)]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct FfiOption<T> {
+    pub is_some: bool,
+    pub value: T,
+}
+
+/// A tri-state boolean, `None` for e.g. "use the default"
+pub type FfiOptionBool = FfiOption<bool>;
+pub type FfiOptionU64 = FfiOption<u64>;
+pub type FfiOptionF64 = FfiOption<f64>;
+
+impl<T: Default> FfiOption<T> {
+    pub fn none() -> Self {
+        FfiOption {
+            is_some: false,
+            value: T::default(),
+        }
+    }
+}
+
+impl<T> FfiOption<T> {
+    pub fn some(value: T) -> Self {
+        FfiOption {
+            is_some: true,
+            value,
+        }
+    }
+
+    pub fn into_option(self) -> Option<T> {
+        if self.is_some {
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Default> Default for FfiOption<T> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<T: Default> From<Option<T>> for FfiOption<T> {
+    fn from(option: Option<T>) -> Self {
+        option.map_or_else(Self::none, Self::some)
+    }
+}
+
+impl<T> From<FfiOption<T>> for Option<T> {
+    fn from(option: FfiOption<T>) -> Self {
+        option.into_option()
+    }
+}
@@ -0,0 +1,50 @@
+//! Checked writes of results through out-parameters.
+//!
+//! Instead of dereferencing an out-parameter blindly, exported functions write through it with
+//! `write_out_ptr()`, which checks the pointer first. A failed check returns `FCPCallerError`
+//! and records the message as the thread's last error, see `last_error()`, so the function can
+//! return the status as it is.
+
+use std::ptr;
+
+use crate::precondition::{ptr_aligned, ptr_non_null};
+use crate::{raw_ptr, set_last_error, FCPResponseStatus};
+
+fn check_out_ptr<T>(out: *mut T, name: &str) -> Result<(), String> {
+    ptr_non_null(out, name)?;
+    ptr_aligned(out, name)
+}
+
+/// Writes `value` to `*out`, the out-parameter `name` of an exported function
+///
+/// Returns `FCPNoError`, or `FCPCallerError` if `out` is null or misaligned, `value` is dropped
+/// then. What `out` pointed to before isn't dropped, it usually is uninitialized. A non-null,
+/// aligned `out` must be valid for writes.
+pub unsafe fn write_out_ptr<T>(out: *mut T, name: &str, value: T) -> FCPResponseStatus {
+    match check_out_ptr(out, name) {
+        Ok(()) => {
+            ptr::write(out, value);
+            FCPResponseStatus::FCPNoError
+        }
+        Err(message) => {
+            set_last_error(FCPResponseStatus::FCPCallerError, message);
+            FCPResponseStatus::FCPCallerError
+        }
+    }
+}
+
+/// Hands ownership of `value` over to C, by writing a pointer to it to `*out`
+///
+/// C frees the object with the exported destructor of `T`, which calls `free_raw_ptr()` (e.g.
+/// one generated by `#[ffi_drop(destroy)]` or `declare_opaque_handle!`). Checks `out` like
+/// `write_out_ptr()`, `value` is dropped if the check fails, so it doesn't leak.
+pub unsafe fn write_out_box<T>(out: *mut *mut T, name: &str, value: T) -> FCPResponseStatus {
+    match check_out_ptr(out, name) {
+        Ok(()) => write_out_ptr(out, name, raw_ptr(value)),
+        Err(message) => {
+            drop(value);
+            set_last_error(FCPResponseStatus::FCPCallerError, message);
+            FCPResponseStatus::FCPCallerError
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Structured reports of caught panics, for the host's crash telemetry.
+//!
+//! The error message of a response flattens a panic into a string. Once enabled with
+//! `enable_panic_reports()` (exported as `fil_enable_panic_reports()`), every panic caught by
+//! `catch_panic_response()` also leaves a `PanicReport` with its location, thread and payload
+//! type in a ring of the last `PANIC_REPORT_RING_LEN` panics, which the host collects with
+//! `fil_take_panic_reports()`.
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::{
+    free_c_str, free_raw_ptr, lifecycle, monotonic_now_ns, panic_payload_message, raw_ptr,
+    rust_str_to_c_str, FCPResponseStatus,
+};
+
+/// The number of reports the ring keeps, older ones are dropped
+pub const PANIC_REPORT_RING_LEN: usize = 16;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static REPORTS: Mutex<VecDeque<PanicReport>> = Mutex::new(VecDeque::new());
+
+/// A caught panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicReport {
+    /// The message of the payload, without a backtrace
+    pub message: String,
+    /// `file:line`, `None` if the toolkit's panic hook isn't installed
+    pub location: Option<String>,
+    /// `None` for unnamed threads
+    pub thread_name: Option<String>,
+    /// The type of the payload, `"unknown"` for types other than strings and errors
+    pub payload_type: &'static str,
+    /// `monotonic_now_ns()` when the panic was caught
+    pub at_ns: u64,
+}
+
+fn payload_type(payload: &(dyn Any + Send)) -> &'static str {
+    if payload.is::<&'static str>() {
+        "&str"
+    } else if payload.is::<String>() {
+        "String"
+    } else if payload.is::<Box<dyn Error + Send + Sync>>() {
+        "Box<dyn Error + Send + Sync>"
+    } else if payload.is::<Box<dyn Error + Send>>() {
+        "Box<dyn Error + Send>"
+    } else {
+        "unknown"
+    }
+}
+
+// Records a caught panic if the reports are enabled
+pub(crate) fn record_panic(payload: &(dyn Any + Send)) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let report = PanicReport {
+        message: panic_payload_message(payload)
+            .unwrap_or_else(|| "no unwind information".to_string()),
+        location: lifecycle::last_panic_location(),
+        thread_name: thread::current().name().map(str::to_string),
+        payload_type: payload_type(payload),
+        at_ns: monotonic_now_ns(),
+    };
+    let mut reports = REPORTS.lock().unwrap();
+    if reports.len() == PANIC_REPORT_RING_LEN {
+        reports.pop_front();
+    }
+    reports.push_back(report);
+}
+
+/// Starts or stops recording panic reports, stopping discards the recorded ones
+///
+/// Installs the toolkit's panic hook, which records the locations, if `init()` didn't already.
+pub fn enable_panic_reports(enabled: bool) {
+    if enabled {
+        lifecycle::install_panic_hook_once();
+    } else {
+        REPORTS.lock().unwrap().clear();
+    }
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Removes and returns the recorded reports, oldest first
+pub fn take_panic_reports() -> Vec<PanicReport> {
+    REPORTS.lock().unwrap().drain(..).collect()
+}
+
+/// See `enable_panic_reports()`
+#[no_mangle]
+pub extern "C" fn fil_enable_panic_reports(enabled: bool) {
+    enable_panic_reports(enabled);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct FfiPanicReport {
+    pub message: *const libc::c_char,
+    /// Null if unknown
+    pub location: *const libc::c_char,
+    /// Null for unnamed threads
+    pub thread_name: *const libc::c_char,
+    pub payload_type: *const libc::c_char,
+    pub at_ns: u64,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct PanicReportsResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub reports_ptr: *const FfiPanicReport,
+    pub reports_len: libc::size_t,
+}
+
+/// Removes the recorded reports and hands them out, free them with
+/// `fil_destroy_panic_reports_response()`
+#[no_mangle]
+pub extern "C" fn fil_take_panic_reports() -> *mut PanicReportsResponse {
+    let optional_c_str = |string: Option<String>| match string {
+        Some(string) => rust_str_to_c_str(string),
+        None => ptr::null_mut(),
+    };
+    let reports: Box<[FfiPanicReport]> = take_panic_reports()
+        .into_iter()
+        .map(|report| FfiPanicReport {
+            message: rust_str_to_c_str(report.message),
+            location: optional_c_str(report.location),
+            thread_name: optional_c_str(report.thread_name),
+            payload_type: rust_str_to_c_str(report.payload_type),
+            at_ns: report.at_ns,
+        })
+        .collect();
+    let reports_len = reports.len();
+    raw_ptr(PanicReportsResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: ptr::null(),
+        reports_ptr: Box::into_raw(reports) as *const FfiPanicReport,
+        reports_len,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_panic_reports_response(ptr: *mut PanicReportsResponse) {
+    free_raw_ptr(ptr);
+}
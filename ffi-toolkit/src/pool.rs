@@ -0,0 +1,111 @@
+//! Pools of response boxes, for calls frequent enough that allocating every response shows up.
+//!
+//! A response type gets a pool with `enable_response_pool::<T>(capacity)`. From then on
+//! `pooled_response()` hands out a box from the pool when it has one, and freeing a `T` (with
+//! `free_raw_ptr()`, so also with the generated destroy functions) drops the value and puts its
+//! memory back into the pool instead of deallocating it, as long as the pool has room. Types
+//! without a pool are allocated and freed as usual.
+
+use std::alloc::Layout;
+use std::any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::alloc;
+
+struct Pool {
+    layout: Layout,
+    capacity: usize,
+    // Dropped values whose memory is kept for the next response
+    free: Vec<FreeBox>,
+}
+
+// The pointer itself rather than its address, so that the memory keeps its provenance (as Miri
+// checks)
+struct FreeBox(*mut u8);
+
+// Only the pool has the pointer, the value it pointed at was dropped
+unsafe impl Send for FreeBox {}
+
+// By type name, as the allocations are recorded by it
+static POOLS: Mutex<Option<HashMap<&'static str, Pool>>> = Mutex::new(None);
+
+// Set while any pool exists, until then a free doesn't need the lock
+static ANY_POOL: AtomicBool = AtomicBool::new(false);
+
+/// Keeps up to `capacity` freed boxes of `T` for `pooled_response()`, 0 removes the pool
+///
+/// Shrinking a pool deallocates the boxes it doesn't have room for anymore.
+pub fn enable_response_pool<T>(capacity: usize) {
+    let layout = Layout::new::<T>();
+    // Zero-sized values don't allocate
+    if layout.size() == 0 {
+        return;
+    }
+    let mut pools = POOLS.lock().unwrap();
+    let pools = pools.get_or_insert_with(HashMap::new);
+    let pool = pools.entry(any::type_name::<T>()).or_insert(Pool {
+        layout,
+        capacity,
+        free: Vec::new(),
+    });
+    pool.capacity = capacity;
+    while pool.free.len() > capacity {
+        let FreeBox(ptr) = pool.free.pop().unwrap();
+        unsafe { alloc::dealloc_raw(ptr, layout) };
+    }
+    if capacity == 0 {
+        pools.remove(any::type_name::<T>());
+    }
+    ANY_POOL.store(!pools.is_empty(), Ordering::SeqCst);
+}
+
+/// The number of boxes of `T` waiting in its pool
+pub fn response_pool_len<T>() -> usize {
+    POOLS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|pools| pools.get(any::type_name::<T>()))
+        .map_or(0, |pool| pool.free.len())
+}
+
+/// Like `raw_ptr()`, but reuses a box of the pool of `T` if it has one
+pub fn pooled_response<T>(response: T) -> *mut T {
+    alloc::pooled_box_into_raw(response)
+}
+
+// A box of the pool of `name`, if its layout is `layout`
+pub(crate) fn take(name: &'static str, layout: Layout) -> Option<*mut u8> {
+    if !ANY_POOL.load(Ordering::Relaxed) {
+        return None;
+    }
+    POOLS
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|pools| pools.get_mut(name))
+        .filter(|pool| pool.layout == layout)
+        .and_then(|pool| pool.free.pop())
+        .map(|FreeBox(ptr)| ptr)
+}
+
+// Keeps the memory of a dropped value in the pool of `name`, returns whether it was kept
+pub(crate) fn recycle(ptr: *mut u8, name: &'static str, layout: Layout) -> bool {
+    if !ANY_POOL.load(Ordering::Relaxed) {
+        return false;
+    }
+    match POOLS
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|pools| pools.get_mut(name))
+    {
+        Some(pool) if pool.layout == layout && pool.free.len() < pool.capacity => {
+            pool.free.push(FreeBox(ptr));
+            true
+        }
+        _ => false,
+    }
+}
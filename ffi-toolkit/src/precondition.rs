@@ -0,0 +1,179 @@
+//! Checks of the arguments of exported functions.
+//!
+//! `ffi_precondition!` runs the checks and returns an `FCPCallerError` response for the first
+//! one that fails. The messages name the argument, so they read the same across all exported
+//! functions. `ffi_debug_precondition!` only checks in debug builds, for hot paths.
+//!
+//! The functions of this module are what the checks expand to, they can be used directly by
+//! code that doesn't return a response.
+
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `ptr` must not be null
+pub fn ptr_non_null<T>(ptr: *const T, name: &str) -> Result<(), String> {
+    if ptr.is_null() {
+        Err(format!("invalid argument `{}`: must not be null", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// `ptr` must be aligned for a `T`, null counts as aligned
+pub fn ptr_aligned<T>(ptr: *const T, name: &str) -> Result<(), String> {
+    if !ptr.is_aligned() {
+        Err(format!(
+            "invalid argument `{}`: must be aligned to {} bytes",
+            name,
+            std::mem::align_of::<T>()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// `len` must fit into the type `T` (named `type_name`)
+pub fn len_fits<T, L>(len: L, name: &str, type_name: &str) -> Result<(), String>
+where
+    T: TryFrom<L>,
+    L: Copy + Display,
+{
+    match T::try_from(len) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "invalid argument `{}`: {} doesn't fit into {}",
+            name, len, type_name
+        )),
+    }
+}
+
+/// `ptr` must not be null unless `len` is 0
+pub fn slice_non_null<T>(ptr: *const T, len: usize, name: &str) -> Result<(), String> {
+    if ptr.is_null() && len != 0 {
+        Err(format!(
+            "invalid argument `{}`: must not be null for a length of {}",
+            name, len
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Converts the C string `ptr` into a canonical path, checking it up front
+///
+/// Unlike `c_str_to_pbuf()` this doesn't turn a null pointer or invalid UTF-8 into some other
+/// path, which would only fail deep down in library code. With `must_exist` the path needs to
+/// exist and be readable, with `must_be_dir` it needs to be a directory if it exists. A path
+/// that doesn't need to exist needs an existing parent directory. The error messages name the
+/// path and are meant for an `FCPCallerError` response.
+pub unsafe fn validate_input_path(
+    ptr: *const libc::c_char,
+    must_exist: bool,
+    must_be_dir: bool,
+) -> Result<PathBuf, String> {
+    ptr_non_null(ptr, "path")?;
+    let path = CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| "invalid argument `path`: must be valid UTF-8".to_string())?;
+    if path.is_empty() {
+        return Err("invalid argument `path`: must not be empty".to_string());
+    }
+    let path = Path::new(path);
+
+    match fs::canonicalize(path) {
+        Ok(canonical) => {
+            let path_error =
+                |message: &str| format!("invalid path `{}`: {}", path.display(), message);
+            let io_error = |err: io::Error| path_error(&err.to_string());
+            let metadata = fs::metadata(&canonical).map_err(io_error)?;
+            if must_be_dir && !metadata.is_dir() {
+                return Err(path_error("is not a directory"));
+            }
+            if must_exist {
+                // Opening the path is the only reliable check for the permissions
+                if metadata.is_dir() {
+                    fs::read_dir(&canonical).map_err(io_error)?;
+                } else {
+                    fs::File::open(&canonical).map_err(io_error)?;
+                }
+            }
+            Ok(canonical)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !must_exist => {
+            let missing_parent = || {
+                format!(
+                    "invalid path `{}`: parent directory does not exist",
+                    path.display()
+                )
+            };
+            let file_name = path.file_name().ok_or_else(missing_parent)?;
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let parent = fs::canonicalize(parent).map_err(|_| missing_parent())?;
+            if !parent.is_dir() {
+                return Err(format!(
+                    "invalid path `{}`: parent is not a directory",
+                    path.display()
+                ));
+            }
+            Ok(parent.join(file_name))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Err(format!("invalid path `{}`: does not exist", path.display()))
+        }
+        Err(err) => Err(format!("invalid path `{}`: {}", path.display(), err)),
+    }
+}
+
+/// Validates the arguments of an exported function returning a response
+///
+/// Each check names an argument, the first failing one makes the function return an
+/// `FCPCallerError` response (see `error_response()`) naming that argument. The available
+/// checks are:
+///
+///  - `ptr_non_null(ptr)`
+///  - `len_fits(len, Type)`: `len` can be converted into `Type`
+///  - `slice_non_null(ptr, len)`: `ptr` is only null if `len` is 0
+///
+/// ```ignore
+/// #[no_mangle]
+/// pub unsafe extern "C" fn fil_hash(data_ptr: *const u8, data_len: usize) -> *mut HashResponse {
+///     ffi_precondition!(slice_non_null(data_ptr, data_len), len_fits(data_len, u32));
+///     ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! ffi_precondition {
+    ($($check:ident($($args:tt)*)),+ $(,)?) => {
+        $(
+            if let Err(message) = $crate::ffi_precondition!(@check $check($($args)*)) {
+                return $crate::error_response($crate::FCPResponseStatus::FCPCallerError, message);
+            }
+        )+
+    };
+    (@check ptr_non_null($ptr:expr)) => {
+        $crate::precondition::ptr_non_null($ptr, stringify!($ptr))
+    };
+    (@check len_fits($len:expr, $ty:ty)) => {
+        $crate::precondition::len_fits::<$ty, _>($len, stringify!($len), stringify!($ty))
+    };
+    (@check slice_non_null($ptr:expr, $len:expr)) => {
+        $crate::precondition::slice_non_null($ptr, $len, stringify!($ptr))
+    };
+}
+
+/// Like `ffi_precondition!`, but the checks are compiled out unless `debug_assertions` are on
+#[macro_export]
+macro_rules! ffi_debug_precondition {
+    ($($checks:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::ffi_precondition!($($checks)*);
+        }
+    };
+}
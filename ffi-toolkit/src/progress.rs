@@ -0,0 +1,94 @@
+//! Progress reports from long-running operations to a host callback.
+//!
+//! The exported function takes the host's callback and `user_data` and runs the operation with
+//! `ProgressSink::scoped()`. The sink can be cloned and sent to other threads, so that code deep
+//! inside the operation can report its progress. Once `scoped()` returns the sink is closed:
+//! reports that are under way are waited for and later ones are dropped, so the host is never
+//! called back after the response was returned.
+
+use std::sync::{Arc, RwLock};
+
+use crate::{truncated_c_string, CCallback, SendCCallback};
+
+/// The host's progress callback, `stage` is only valid during the call
+pub type FfiProgressCallback = extern "C" fn(
+    stage: *const libc::c_char,
+    current: u64,
+    total: u64,
+    user_data: *mut libc::c_void,
+);
+
+type Target = SendCCallback<(*const libc::c_char, u64, u64), ()>;
+
+/// Where an operation reports its progress to, clones report to the same callback
+#[derive(Clone, Default)]
+pub struct ProgressSink {
+    // `None` without a callback, the target is `None` once the sink is closed
+    target: Option<Arc<RwLock<Option<Target>>>>,
+}
+
+// Closes the sink also if the operation panics
+struct CloseOnDrop<'a>(&'a ProgressSink);
+
+impl Drop for CloseOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Some(target) = &self.0.target {
+            // Waits for the reports that are under way
+            *target
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+        }
+    }
+}
+
+impl ProgressSink {
+    /// A sink dropping all reports, for callers without a callback
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` with a sink reporting to `callback`, which is not called anymore once this returns
+    ///
+    /// A null `callback` drops all reports. The host must guarantee that `callback` can be called
+    /// with `user_data` from any thread until then. The callback must not report progress to
+    /// the same sink itself.
+    pub unsafe fn scoped<R>(
+        callback: Option<FfiProgressCallback>,
+        user_data: *mut libc::c_void,
+        f: impl FnOnce(&ProgressSink) -> R,
+    ) -> R {
+        let sink = ProgressSink {
+            target: callback.map(|callback| {
+                let target = CCallback::new(Some(callback), user_data).assume_send();
+                Arc::new(RwLock::new(Some(target)))
+            }),
+        };
+        let _close = CloseOnDrop(&sink);
+        f(&sink)
+    }
+
+    /// Calls the host's callback, unless the sink is closed
+    ///
+    /// Interior nul bytes truncate `stage`.
+    pub fn report(&self, stage: &str, current: u64, total: u64) {
+        if let Some(target) = &self.target {
+            let target = target
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(target) = &*target {
+                let stage = truncated_c_string(stage.to_string());
+                target.call((stage.as_ptr(), current, total));
+            }
+        }
+    }
+
+    /// Whether reports still reach the host
+    pub fn is_open(&self) -> bool {
+        self.target.as_ref().is_some_and(|target| {
+            target
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .is_some()
+        })
+    }
+}
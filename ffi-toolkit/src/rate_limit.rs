@@ -0,0 +1,125 @@
+//! Token-bucket rate limits for groups of exported functions, so that a misbehaving host can't
+//! starve the worker pool with pathological call rates.
+//!
+//! A group without a configured limit is never limited. Calls exceeding the limit are rejected
+//! with `FCPBusyError`, the host is expected to retry later.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{
+    c_str_to_rust_str, catch_panic_response, error_response, monotonic_now_ns, CodeAndMessage,
+    FCPResponseStatus,
+};
+
+/// The limit of a group of functions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The sustained number of calls per second
+    pub calls_per_second: u32,
+    /// The number of calls that can be made at once after a quiet period
+    pub burst: u32,
+}
+
+struct Bucket {
+    limit: RateLimit,
+    // In billionths of a call, so that refilling doesn't need floating point
+    tokens: u64,
+    refilled_at_ns: u64,
+}
+
+const TOKEN: u64 = 1_000_000_000;
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Bucket {
+            limit,
+            tokens: limit.burst as u64 * TOKEN,
+            refilled_at_ns: monotonic_now_ns(),
+        }
+    }
+
+    fn try_acquire(&mut self, now_ns: u64) -> bool {
+        let elapsed_ns = now_ns.saturating_sub(self.refilled_at_ns);
+        let refill = elapsed_ns.saturating_mul(self.limit.calls_per_second as u64);
+        self.tokens = self
+            .tokens
+            .saturating_add(refill)
+            .min(self.limit.burst as u64 * TOKEN);
+        self.refilled_at_ns = now_ns;
+        if self.tokens >= TOKEN {
+            self.tokens -= TOKEN;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+static BUCKETS: Mutex<Option<HashMap<String, Bucket>>> = Mutex::new(None);
+
+/// Sets the limit of `group`, `None` removes it
+///
+/// The group starts with a full bucket.
+pub fn configure_rate_limit(group: &str, limit: Option<RateLimit>) {
+    let mut buckets = BUCKETS.lock().unwrap();
+    let buckets = buckets.get_or_insert_with(HashMap::new);
+    match limit {
+        Some(limit) => {
+            buckets.insert(group.to_string(), Bucket::new(limit));
+        }
+        None => {
+            buckets.remove(group);
+        }
+    }
+}
+
+/// Takes a call from the bucket of `group`, returns whether the call may proceed
+pub fn try_acquire_call(group: &str) -> bool {
+    let now_ns = monotonic_now_ns();
+    match BUCKETS
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|buckets| buckets.get_mut(group))
+    {
+        Some(bucket) => bucket.try_acquire(now_ns),
+        None => true,
+    }
+}
+
+/// Like `catch_panic_response()`, but first checks the rate limit of `group`
+///
+/// When the limit is exceeded, `callback` isn't called and an `FCPBusyError` response is
+/// returned.
+pub fn catch_panic_response_limited<F, T>(group: &str, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage,
+    F: FnOnce() -> *mut T,
+{
+    if !try_acquire_call(group) {
+        return error_response(
+            FCPResponseStatus::FCPBusyError,
+            format!("rate limit of `{}` exceeded, retry later", group),
+        );
+    }
+    catch_panic_response(callback)
+}
+
+/// Sets the limit of `group`, a `calls_per_second` of 0 removes the limit
+#[no_mangle]
+pub unsafe extern "C" fn fil_configure_rate_limit(
+    group: *const libc::c_char,
+    calls_per_second: u32,
+    burst: u32,
+) {
+    let limit = if calls_per_second == 0 {
+        None
+    } else {
+        Some(RateLimit {
+            calls_per_second,
+            burst: burst.max(1),
+        })
+    };
+    configure_rate_limit(&c_str_to_rust_str(group), limit);
+}
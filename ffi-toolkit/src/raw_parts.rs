@@ -0,0 +1,45 @@
+//! Vectors handed out as raw parts, and rebuilt from them with checks.
+//!
+//! A response returns an array as `<name>_ptr`, `<name>_len` and, unless the capacity is the
+//! length, `<name>_cap` fields. `vec_into_raw_parts()` and `vec_into_raw_parts_exact()` take
+//! the vector apart, `vec_from_raw_parts_checked()` puts it back together, which is also what
+//! the `Drop` derived by `DropStructMacro` does.
+
+use alloc_crate::vec::Vec;
+use core::mem::ManuallyDrop;
+
+/// Takes a vector apart into its pointer, length and capacity, without copying it
+pub fn vec_into_raw_parts<T>(vec: Vec<T>) -> (*mut T, usize, usize) {
+    let mut vec = ManuallyDrop::new(vec);
+    (vec.as_mut_ptr(), vec.len(), vec.capacity())
+}
+
+/// Like `vec_into_raw_parts()`, for arrays without a capacity field
+///
+/// The vector is shrunk to its length first (which reallocates it if it has spare capacity), so
+/// the length is the capacity when it is rebuilt.
+pub fn vec_into_raw_parts_exact<T>(vec: Vec<T>) -> (*mut T, usize) {
+    let (ptr, len, cap) = vec_into_raw_parts(vec.into_boxed_slice().into_vec());
+    debug_assert_eq!(len, cap);
+    (ptr, len)
+}
+
+/// Rebuilds a vector from `vec_into_raw_parts()`
+///
+/// The parts must be the ones of one vector, which wasn't rebuilt already. Debug builds check
+/// that the pointer isn't null and aligned, and that the capacity is at least the length.
+pub unsafe fn vec_from_raw_parts_checked<T>(ptr: *mut T, len: usize, cap: usize) -> Vec<T> {
+    debug_assert!(!ptr.is_null(), "the vector's pointer is null");
+    debug_assert!(
+        ptr.is_aligned(),
+        "the vector's pointer {:p} isn't aligned",
+        ptr
+    );
+    debug_assert!(
+        cap >= len,
+        "the vector's capacity {} is smaller than its length {}",
+        cap,
+        len
+    );
+    Vec::from_raw_parts(ptr, len, cap)
+}
@@ -0,0 +1,466 @@
+//! Recordings of the calls of exported functions and their arguments, to reproduce a problem a
+//! node operator reported with the exact inputs the host passed.
+//!
+//! Functions opt in by running their body in `catch_panic_response_recorded()` or
+//! `catch_panic_result_recorded()` with their name and their marshaled arguments. Nothing is
+//! recorded (nor marshaled) until `start_recording()`, then every call is appended to the file
+//! before the function runs, so that the call that crashed the process is in it as well. A test
+//! feeds the file to a `ReplayHarness`, which calls the closure registered for each function
+//! with the recorded arguments.
+//!
+//! A recording starts with `RECORDING_MAGIC`, followed by the calls as length-delimited messages
+//! (see `write_length_delimited()`). It holds the arguments verbatim, including secrets.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::framing::{read_varint, write_varint};
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{
+    c_str_to_path, c_str_to_rust_str, catch_panic_response_named, catch_panic_result_named,
+    decode_length_delimited_all, encode_length_delimited, try_slice_from_raw, CodeAndMessage,
+    FrameError, IntoFFIError, MessageCodec, StatusCode, MAX_MESSAGE_LEN,
+};
+
+/// The first bytes of a recording, the last two are the version of the format
+pub const RECORDING_MAGIC: &[u8; 8] = b"FILREC01";
+
+// The tags of the arguments in the encoded calls
+const TAG_NULL: u8 = 0;
+const TAG_U64: u8 = 1;
+const TAG_I64: u8 = 2;
+const TAG_BOOL: u8 = 3;
+const TAG_F64: u8 = 4;
+const TAG_STR: u8 = 5;
+const TAG_BYTES: u8 = 6;
+
+/// An argument of a recorded call
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedArg {
+    /// A null pointer, or a slice that wasn't valid
+    Null,
+    U64(u64),
+    I64(i64),
+    Bool(bool),
+    F64(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl RecordedArg {
+    /// The C string at `ptr`, lossily converted to UTF-8, `Null` for a null pointer
+    pub unsafe fn c_str(ptr: *const libc::c_char) -> Self {
+        if ptr.is_null() {
+            return RecordedArg::Null;
+        }
+        RecordedArg::Str(c_str_to_rust_str(ptr).into_owned())
+    }
+
+    /// The `len` bytes at `ptr`, `Null` if they aren't a valid slice
+    pub unsafe fn raw_bytes(ptr: *const u8, len: libc::size_t) -> Self {
+        match try_slice_from_raw(ptr, len) {
+            Some(bytes) => RecordedArg::Bytes(bytes.to_vec()),
+            None => RecordedArg::Null,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            RecordedArg::U64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            RecordedArg::I64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            RecordedArg::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            RecordedArg::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RecordedArg::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            RecordedArg::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == RecordedArg::Null
+    }
+}
+
+macro_rules! recorded_arg_from {
+    ($variant:ident, $($ty:ty),*) => {
+        $(
+            impl From<$ty> for RecordedArg {
+                fn from(value: $ty) -> Self {
+                    RecordedArg::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+recorded_arg_from!(U64, u8, u16, u32, u64);
+recorded_arg_from!(I64, i8, i16, i32, i64);
+recorded_arg_from!(Bool, bool);
+recorded_arg_from!(F64, f32, f64);
+recorded_arg_from!(Str, &str, String);
+recorded_arg_from!(Bytes, &[u8], Vec<u8>);
+
+impl From<usize> for RecordedArg {
+    fn from(value: usize) -> Self {
+        RecordedArg::U64(value as u64)
+    }
+}
+
+/// A call of the exported function `name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub name: String,
+    pub args: Vec<RecordedArg>,
+}
+
+fn encode_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    write_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+// the next `len` bytes of `input`
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if input.len() < len {
+        return Err("truncated call".to_string());
+    }
+    let (taken, rest) = input.split_at(len);
+    *input = rest;
+    Ok(taken)
+}
+
+fn decode_len(input: &mut &[u8]) -> Result<usize, String> {
+    match read_varint(input) {
+        Ok(Some(len)) if len <= input.len() as u64 => Ok(len as usize),
+        Ok(_) | Err(FrameError::Truncated) => Err("truncated call".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn decode_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = decode_len(input)?;
+    take(input, len)
+}
+
+fn decode_str(input: &mut &[u8]) -> Result<String, String> {
+    String::from_utf8(decode_bytes(input)?.to_vec()).map_err(|_| "invalid UTF-8".to_string())
+}
+
+fn decode_u64(input: &mut &[u8]) -> Result<u64, String> {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(take(input, 8)?);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+impl MessageCodec for RecordedCall {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_bytes(self.name.as_bytes(), buf);
+        write_varint(self.args.len() as u64, buf);
+        for arg in &self.args {
+            match arg {
+                RecordedArg::Null => buf.push(TAG_NULL),
+                RecordedArg::U64(value) => {
+                    buf.push(TAG_U64);
+                    buf.extend_from_slice(&value.to_le_bytes());
+                }
+                RecordedArg::I64(value) => {
+                    buf.push(TAG_I64);
+                    buf.extend_from_slice(&value.to_le_bytes());
+                }
+                RecordedArg::Bool(value) => buf.extend_from_slice(&[TAG_BOOL, *value as u8]),
+                RecordedArg::F64(value) => {
+                    buf.push(TAG_F64);
+                    buf.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+                RecordedArg::Str(value) => {
+                    buf.push(TAG_STR);
+                    encode_bytes(value.as_bytes(), buf);
+                }
+                RecordedArg::Bytes(value) => {
+                    buf.push(TAG_BYTES);
+                    encode_bytes(value, buf);
+                }
+            }
+        }
+    }
+
+    fn decode(mut bytes: &[u8]) -> Result<Self, String> {
+        let input = &mut bytes;
+        let name = decode_str(input)?;
+        // Every argument takes at least its tag
+        let count = decode_len(input)?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            args.push(match take(input, 1)?[0] {
+                TAG_NULL => RecordedArg::Null,
+                TAG_U64 => RecordedArg::U64(decode_u64(input)?),
+                TAG_I64 => RecordedArg::I64(decode_u64(input)? as i64),
+                TAG_BOOL => RecordedArg::Bool(take(input, 1)?[0] != 0),
+                TAG_F64 => RecordedArg::F64(f64::from_bits(decode_u64(input)?)),
+                TAG_STR => RecordedArg::Str(decode_str(input)?),
+                TAG_BYTES => RecordedArg::Bytes(decode_bytes(input)?.to_vec()),
+                tag => return Err(format!("unknown argument tag {}", tag)),
+            });
+        }
+        if !input.is_empty() {
+            return Err(format!("{} trailing bytes", input.len()));
+        }
+        Ok(RecordedCall { name, args })
+    }
+}
+
+struct Recorder {
+    file: File,
+    calls: usize,
+    // The first failed write, recording stopped then
+    error: Option<io::Error>,
+}
+
+// Checked before locking, so that recorded functions don't contend while nothing is recorded
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// Starts recording the calls to a new file at `path`, replacing a previous recording
+///
+/// The previous recording is stopped first, `shutdown()` stops this one.
+pub fn start_recording(path: &Path) -> io::Result<()> {
+    let _ = stop_recording();
+    let mut file = File::create(path)?;
+    file.write_all(RECORDING_MAGIC)?;
+    *RECORDER.lock().unwrap() = Some(Recorder {
+        file,
+        calls: 0,
+        error: None,
+    });
+    if !ENABLED.swap(true, Ordering::SeqCst) {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Registries, stop_recording_on_shutdown);
+    }
+    Ok(())
+}
+
+/// Stops recording, returns the number of calls recorded, or the error that stopped the
+/// recording early
+///
+/// Without a recording it returns 0.
+pub fn stop_recording() -> io::Result<usize> {
+    ENABLED.store(false, Ordering::SeqCst);
+    match RECORDER.lock().unwrap().take() {
+        Some(Recorder {
+            error: Some(err), ..
+        }) => Err(err),
+        Some(recorder) => {
+            recorder.file.sync_all()?;
+            Ok(recorder.calls)
+        }
+        None => Ok(0),
+    }
+}
+
+fn stop_recording_on_shutdown() {
+    let _ = stop_recording();
+}
+
+pub fn is_recording() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Appends the call to the recording, if there is one
+fn record(name: &str, args: impl FnOnce() -> Vec<RecordedArg>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let call = RecordedCall {
+        name: name.to_string(),
+        args: args(),
+    };
+    let mut buf = Vec::new();
+    encode_length_delimited(&call, &mut buf);
+    let mut recorder = RECORDER.lock().unwrap();
+    if let Some(recorder) = recorder
+        .as_mut()
+        .filter(|recorder| recorder.error.is_none())
+    {
+        // A single write, so that a crash right after it leaves whole calls behind
+        match recorder.file.write_all(&buf) {
+            Ok(()) => recorder.calls += 1,
+            Err(err) => recorder.error = Some(err),
+        }
+    }
+}
+
+/// Like `catch_panic_response_named()`, recording the call with the arguments `args` returns
+/// while recording
+pub fn catch_panic_response_recorded<F, T, C, A>(name: &'static str, args: A, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+    F: FnOnce() -> *mut T,
+    A: FnOnce() -> Vec<RecordedArg>,
+{
+    catch_panic_response_named(name, || {
+        record(name, args);
+        callback()
+    })
+}
+
+/// Like `catch_panic_result_named()`, recording the call with the arguments `args` returns
+/// while recording
+pub fn catch_panic_result_recorded<F, T, E, A>(name: &'static str, args: A, callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage,
+    E: IntoFFIError,
+    F: FnOnce() -> Result<T, E>,
+    A: FnOnce() -> Vec<RecordedArg>,
+{
+    catch_panic_result_named(name, || {
+        record(name, args);
+        callback()
+    })
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// The file doesn't start with `RECORDING_MAGIC`
+    NotARecording,
+    Frame(FrameError),
+    /// No closure was registered for the recorded function
+    UnknownEntrypoint(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::NotARecording => write!(f, "not a recording of calls"),
+            ReplayError::Frame(err) => write!(f, "invalid recording: {}", err),
+            ReplayError::UnknownEntrypoint(name) => {
+                write!(f, "no closure registered for `{}`", name)
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// The calls recorded in the file at `path`, in the order they were made
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedCall>, ReplayError> {
+    let bytes = fs::read(path).map_err(ReplayError::Io)?;
+    match bytes.strip_prefix(&RECORDING_MAGIC[..]) {
+        Some(calls) => {
+            decode_length_delimited_all(calls, MAX_MESSAGE_LEN).map_err(ReplayError::Frame)
+        }
+        None => Err(ReplayError::NotARecording),
+    }
+}
+
+type Entrypoint = Box<dyn Fn(&[RecordedArg])>;
+
+/// Calls the closures registered for the functions of a recording with the recorded arguments
+///
+/// The closures usually call the exported function itself, or the Rust function behind it,
+/// unwrapping the arguments with e.g. `RecordedArg::as_u64()`. Their panics aren't caught.
+#[derive(Default)]
+pub struct ReplayHarness {
+    entrypoints: HashMap<String, Entrypoint>,
+}
+
+impl ReplayHarness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the closure the calls of `name` are replayed with, replacing a previous one
+    pub fn register<F>(&mut self, name: &str, entrypoint: F) -> &mut Self
+    where
+        F: Fn(&[RecordedArg]) + 'static,
+    {
+        self.entrypoints
+            .insert(name.to_string(), Box::new(entrypoint));
+        self
+    }
+
+    pub fn replay_call(&self, call: &RecordedCall) -> Result<(), ReplayError> {
+        match self.entrypoints.get(&call.name) {
+            Some(entrypoint) => {
+                entrypoint(&call.args);
+                Ok(())
+            }
+            None => Err(ReplayError::UnknownEntrypoint(call.name.clone())),
+        }
+    }
+
+    /// Replays the calls of the recording at `path` in order, returns how many there were
+    ///
+    /// The functions of all calls need a closure, otherwise none of them is replayed.
+    pub fn replay_file(&self, path: &Path) -> Result<usize, ReplayError> {
+        let calls = read_recording(path)?;
+        if let Some(call) = calls
+            .iter()
+            .find(|call| !self.entrypoints.contains_key(&call.name))
+        {
+            return Err(ReplayError::UnknownEntrypoint(call.name.clone()));
+        }
+        for call in &calls {
+            self.replay_call(call)?;
+        }
+        Ok(calls.len())
+    }
+}
+
+impl fmt::Debug for ReplayHarness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<_> = self.entrypoints.keys().collect();
+        names.sort();
+        f.debug_struct("ReplayHarness")
+            .field("entrypoints", &names)
+            .finish()
+    }
+}
+
+/// Starts recording the calls to the file at `path`, see `start_recording()`
+#[no_mangle]
+pub unsafe extern "C" fn fil_start_recording(path: *const libc::c_char) -> bool {
+    !path.is_null() && start_recording(&c_str_to_path(path)).is_ok()
+}
+
+/// Stops recording, false if the recording failed and may be incomplete
+#[no_mangle]
+pub extern "C" fn fil_stop_recording() -> bool {
+    stop_recording().is_ok()
+}
@@ -0,0 +1,166 @@
+//! Guards against host callbacks calling back into the library, and against calls on the wrong
+//! thread.
+//!
+//! A host callback that calls an exported function on the same thread runs while the outer call
+//! may hold a lock, e.g. of the handle registry, and deadlocks. Every guarded call (the
+//! `catch_panic_*` wrappers or `enter_ffi()`) counts towards the thread's call depth. With the
+//! opt-in `ReentrancyPolicy::Reject` (exported as `fil_reject_reentrant_calls()`),
+//! `assert_not_reentrant()` fails inside a guarded call and `catch_panic_response_not_reentrant()`
+//! returns an error response instead of running the call.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+
+use crate::lifecycle::{self, CallGuard};
+use crate::{
+    catch_panic_response, error_response, CodeAndMessage, FCPResponseStatus, IntoFFIError,
+    StatusCode,
+};
+
+/// Whether re-entrant calls are rejected, see `set_reentrancy_policy()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReentrancyPolicy {
+    Allow,
+    Reject,
+}
+
+static REJECT: AtomicBool = AtomicBool::new(false);
+
+/// A call was made from where it mustn't be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardError {
+    /// The call was made from within `depth` guarded calls on the same thread
+    Reentrant { depth: usize },
+    /// The call was made on another thread than the one owning the object
+    WrongThread { owner: ThreadId, caller: ThreadId },
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuardError::Reentrant { depth } => write!(
+                f,
+                "re-entrant call from within {} call(s) into the library on the same thread",
+                depth
+            ),
+            GuardError::WrongThread { owner, caller } => write!(
+                f,
+                "called on thread {:?}, but it belongs to thread {:?}",
+                caller, owner
+            ),
+        }
+    }
+}
+
+impl Error for GuardError {}
+
+impl IntoFFIError for GuardError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPCallerError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+pub fn set_reentrancy_policy(policy: ReentrancyPolicy) {
+    REJECT.store(policy == ReentrancyPolicy::Reject, Ordering::SeqCst);
+}
+
+pub fn reentrancy_policy() -> ReentrancyPolicy {
+    if REJECT.load(Ordering::Relaxed) {
+        ReentrancyPolicy::Reject
+    } else {
+        ReentrancyPolicy::Allow
+    }
+}
+
+/// Marks the current thread as inside a call into the library for as long as it lives
+///
+/// For exported functions that don't use a `catch_panic_*` wrapper, which do the same. The call
+/// counts as in flight for `lifecycle::shutdown()` as well.
+pub struct FfiCallGuard(CallGuard);
+
+impl fmt::Debug for FfiCallGuard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FfiCallGuard").finish()
+    }
+}
+
+/// See `FfiCallGuard`
+pub fn enter_ffi() -> FfiCallGuard {
+    FfiCallGuard(CallGuard::enter())
+}
+
+/// Fails if the policy is `Reject` and the current thread is in a guarded call already
+///
+/// Call it before entering the call, e.g. before `catch_panic_response()`.
+pub fn assert_not_reentrant() -> Result<(), GuardError> {
+    let depth = lifecycle::call_depth();
+    if depth > 0 && reentrancy_policy() == ReentrancyPolicy::Reject {
+        Err(GuardError::Reentrant { depth })
+    } else {
+        Ok(())
+    }
+}
+
+/// Like `catch_panic_response()`, but first checks `assert_not_reentrant()`
+///
+/// A rejected call isn't run, an `FCPCallerError` response is returned instead.
+pub fn catch_panic_response_not_reentrant<F, T, C>(callback: F) -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+    F: FnOnce() -> *mut T,
+{
+    if let Err(err) = assert_not_reentrant() {
+        return error_response(C::from_response_status(err.code()), err.message());
+    }
+    catch_panic_response(callback)
+}
+
+/// The thread an object belongs to, for objects the host must only use on the thread that
+/// created them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ThreadAffinity {
+    owner: ThreadId,
+}
+
+impl ThreadAffinity {
+    /// Belongs to the current thread
+    pub fn current() -> Self {
+        ThreadAffinity {
+            owner: thread::current().id(),
+        }
+    }
+
+    pub fn owner(&self) -> ThreadId {
+        self.owner
+    }
+
+    /// Fails unless called on the owning thread
+    pub fn check(&self) -> Result<(), GuardError> {
+        let caller = thread::current().id();
+        if caller == self.owner {
+            Ok(())
+        } else {
+            Err(GuardError::WrongThread {
+                owner: self.owner,
+                caller,
+            })
+        }
+    }
+}
+
+/// Rejects re-entrant calls if `reject` is set, allows them again otherwise
+#[no_mangle]
+pub extern "C" fn fil_reject_reentrant_calls(reject: bool) {
+    set_reentrancy_policy(if reject {
+        ReentrancyPolicy::Reject
+    } else {
+        ReentrancyPolicy::Allow
+    });
+}
@@ -0,0 +1,55 @@
+/// Declares a response that is either a payload or an error
+///
+/// For functions whose whole response is "the value or why there is none", instead of writing the
+/// same struct for every value type. The struct has a `status_code`, an `error_msg` and the
+/// payload as `value`, and derives `FFIResponse`, so it gets its `Default`, `CodeAndMessage`,
+/// `Drop` and the exported destructor (`destroy_<snake_case name>`). `from_result()` builds it
+/// from a `Result`, the value of an error is the `Default` of the payload type.
+///
+/// The payload is freed by its own `Drop`, so it is a scalar or a type that owns its memory,
+/// e.g. `FfiBytes` or `FfiString`. As with `FFIResponse`, the crate needs `libc`.
+///
+/// ```
+/// use ffi_toolkit::{ffi_result_type, raw_ptr, FCPResponseStatus};
+///
+/// ffi_result_type!(
+///     /// The size of a sector in bytes
+///     SectorSizeResponse,
+///     u64
+/// );
+///
+/// let response = SectorSizeResponse::from_result(Ok::<_, std::io::Error>(2048));
+/// assert_eq!(response.status_code, FCPResponseStatus::FCPNoError);
+/// assert_eq!(response.value, 2048);
+/// unsafe { destroy_sector_size_response(raw_ptr(response)) };
+/// ```
+#[macro_export]
+macro_rules! ffi_result_type {
+    ($(#[$meta:meta])* $name:ident, $ok:ty $(,)?) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive($crate::__private::FFIResponse)]
+        pub struct $name {
+            pub status_code: $crate::FCPResponseStatus,
+            pub error_msg: *const libc::c_char,
+            pub value: $ok,
+        }
+
+        impl $name {
+            /// The response of the value, or of the error
+            pub fn from_result<E: $crate::IntoFFIError>(
+                result: ::std::result::Result<$ok, E>,
+            ) -> Self {
+                let mut response = <Self as ::std::default::Default>::default();
+                match result {
+                    Ok(value) => response.value = value,
+                    Err(err) => {
+                        response.status_code = err.code();
+                        response.error_msg = $crate::rust_str_to_c_str(err.message());
+                    }
+                }
+                response
+            }
+        }
+    };
+}
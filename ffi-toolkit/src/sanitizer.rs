@@ -0,0 +1,69 @@
+//! Annotations for AddressSanitizer and LeakSanitizer.
+//!
+//! With the `sanitizer` feature enabled the functions call into the sanitizer runtime, hence the
+//! library must then be built with `-Zsanitizer=address` (or `leak`). Without the feature they
+//! are no-ops, so they can be used unconditionally.
+//!
+//! ```console
+//! $ RUSTFLAGS="-Zsanitizer=address" cargo +nightly test --features sanitizer --target x86_64-unknown-linux-gnu
+//! ```
+
+#[cfg(feature = "sanitizer")]
+mod sys {
+    use libc::{c_int, c_void, size_t};
+
+    extern "C" {
+        pub fn __asan_poison_memory_region(addr: *const c_void, size: size_t);
+        pub fn __asan_unpoison_memory_region(addr: *const c_void, size: size_t);
+        pub fn __asan_address_is_poisoned(addr: *const c_void) -> c_int;
+        pub fn __lsan_ignore_object(p: *const c_void);
+    }
+}
+
+/// Whether the toolkit was built with the sanitizer annotations
+pub const fn is_enabled() -> bool {
+    cfg!(feature = "sanitizer")
+}
+
+/// Marks memory the toolkit still owns, but that must not be accessed (e.g. recycled buffers)
+pub fn poison_region(ptr: *const u8, len: usize) {
+    #[cfg(feature = "sanitizer")]
+    unsafe {
+        sys::__asan_poison_memory_region(ptr as *const libc::c_void, len)
+    }
+    #[cfg(not(feature = "sanitizer"))]
+    let _ = (ptr, len);
+}
+
+/// Makes a region that was poisoned with `poison_region()` accessible again
+pub fn unpoison_region(ptr: *const u8, len: usize) {
+    #[cfg(feature = "sanitizer")]
+    unsafe {
+        sys::__asan_unpoison_memory_region(ptr as *const libc::c_void, len)
+    }
+    #[cfg(not(feature = "sanitizer"))]
+    let _ = (ptr, len);
+}
+
+/// Whether the given address was poisoned, always `false` without the `sanitizer` feature
+pub fn is_poisoned(ptr: *const u8) -> bool {
+    #[cfg(feature = "sanitizer")]
+    unsafe {
+        sys::__asan_address_is_poisoned(ptr as *const libc::c_void) != 0
+    }
+    #[cfg(not(feature = "sanitizer"))]
+    {
+        let _ = ptr;
+        false
+    }
+}
+
+/// Tells LeakSanitizer that a heap object is leaked on purpose (e.g. for interned statics)
+pub fn ignore_leak<T>(ptr: *const T) {
+    #[cfg(feature = "sanitizer")]
+    unsafe {
+        sys::__lsan_ignore_object(ptr as *const libc::c_void)
+    }
+    #[cfg(not(feature = "sanitizer"))]
+    let _ = ptr;
+}
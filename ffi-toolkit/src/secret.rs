@@ -0,0 +1,105 @@
+//! Bytes holding secrets, such as private keys or ticket randomness, that must not linger in
+//! freed memory.
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::slice;
+
+use crate::{alloc, ct_eq};
+
+/// A byte array like `FfiBytes`, whose memory is zeroed before it's freed
+///
+/// It has the layout of `FfiBytes`, C frees one it owns with `fil_free_secret_bytes()`. The
+/// whole capacity is zeroed, not just the bytes in use. `Debug` doesn't show the bytes, and
+/// comparisons are constant-time.
+#[repr(C)]
+pub struct SecretBytes {
+    pub ptr: *const u8,
+    pub len: libc::size_t,
+    // The capacity of the `Vec` the bytes came from, C must not change it
+    pub cap: libc::size_t,
+}
+
+impl SecretBytes {
+    /// Takes over the memory of the vector, without copying or reallocating it
+    ///
+    /// Whatever earlier reallocations of the vector left behind isn't zeroed, so the vector
+    /// should be allocated with its final capacity.
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = ManuallyDrop::new(bytes);
+        SecretBytes {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            cap: bytes.capacity(),
+        }
+    }
+
+    /// Copies the bytes, the caller wipes its own copy
+    pub fn copy_from_slice(bytes: &[u8]) -> Self {
+        Self::from_vec(bytes.to_vec())
+    }
+
+    pub fn empty() -> Self {
+        Self::from_vec(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// Compares the bytes with `other` in constant time, see `ct_eq()`
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        ct_eq(self.as_slice(), other)
+    }
+}
+
+impl Default for SecretBytes {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from_vec(bytes)
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other.as_slice())
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretBytes(<{} bytes redacted>)", self.len)
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        unsafe {
+            let mut bytes = Vec::from_raw_parts(self.ptr as *mut u8, self.len, self.cap);
+            // The spare capacity is initialized first, it's only zeroed through a slice
+            bytes.resize(bytes.capacity(), 0);
+            alloc::zero(&mut bytes);
+        }
+    }
+}
+
+/// Frees a `SecretBytes` that was handed out to the caller, after zeroing it
+#[no_mangle]
+pub extern "C" fn fil_free_secret_bytes(bytes: SecretBytes) {
+    drop(bytes);
+}
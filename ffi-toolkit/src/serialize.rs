@@ -0,0 +1,248 @@
+//! `serde` implementations of the toolkit types, with the `serde` feature.
+//!
+//! They are meant for golden tests and debugging: responses are snapshotted as JSON (or any
+//! other format) and recorded inputs replayed. Status codes are written by the name of their
+//! variant, bytes as bytes, strings as strings and string arrays as sequences. `StringRef` is
+//! only serialized, as it borrows what it points at.
+//!
+//! `#[derive(FfiSerialize)]` serializes response structs, following their pointers with the
+//! helpers below.
+
+use std::ffi::CStr;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeSeq, Serializer};
+
+use crate::{
+    is_valid_slice, FCPResponseStatus, FfiBytes, FfiDebugId, FfiString, FfiStringArray,
+    SecretBytes, StringRef,
+};
+
+impl Serialize for FCPResponseStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", self))
+    }
+}
+
+impl<'de> Deserialize<'de> for FCPResponseStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        FCPResponseStatus::VARIANTS
+            .iter()
+            .map(|&(status, _, _)| status)
+            .find(|status| format!("{:?}", status) == name)
+            .ok_or_else(|| de::Error::custom(format!("unknown status code `{}`", name)))
+    }
+}
+
+impl Serialize for FfiBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.as_slice())
+    }
+}
+
+// Like the `secret` fields of `FfiSerialize`, so that snapshots don't contain secrets
+impl Serialize for SecretBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("<redacted>")
+    }
+}
+
+// Bytes are read from byte strings (e.g. CBOR) as well as from sequences (e.g. JSON)
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for FfiBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(FfiBytes::from_vec(
+            deserializer.deserialize_byte_buf(BytesVisitor)?,
+        ))
+    }
+}
+
+impl Serialize for FfiString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for FfiString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(FfiString::new(String::deserialize(deserializer)?))
+    }
+}
+
+impl Serialize for FfiStringArray {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FfiStringArray {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(FfiStringArray::new(Vec::<String>::deserialize(
+            deserializer,
+        )?))
+    }
+}
+
+// Reads the borrowed bytes, so the `StringRef` must point at valid memory like for `as_str()`.
+// Invalid UTF-8 is written as bytes.
+impl Serialize for FfiDebugId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut id = serializer.serialize_struct("FfiDebugId", 2)?;
+        id.serialize_field("magic", &self.magic)?;
+        id.serialize_field("sequence", &self.sequence)?;
+        id.end()
+    }
+}
+
+impl Serialize for StringRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !is_valid_slice(self.ptr, self.len) {
+            return Err(ser::Error::custom("the `StringRef` is invalid"));
+        }
+        match unsafe { self.as_str() } {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.serialize_bytes(unsafe { self.as_bytes() }),
+        }
+    }
+}
+
+// The helpers the code generated by `FfiSerialize` follows pointer fields with. Null pointers
+// are serialized as none, pointers that can't be valid (unaligned, or with a length whose size
+// overflows) fail the serialization.
+
+#[doc(hidden)]
+pub struct SerializeCStr(*const libc::c_char);
+
+impl SerializeCStr {
+    /// `ptr` must be null or point at a C string
+    pub unsafe fn new(ptr: *const libc::c_char) -> Self {
+        SerializeCStr(ptr)
+    }
+}
+
+impl Serialize for SerializeCStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_null() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(&unsafe { CStr::from_ptr(self.0) }.to_string_lossy())
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeSlice<T>(*const T, usize);
+
+impl<T> SerializeSlice<T> {
+    /// `ptr` must be null or point at `len` elements
+    pub unsafe fn new(ptr: *const T, len: usize) -> Self {
+        SerializeSlice(ptr, len)
+    }
+}
+
+impl<T: Serialize> Serialize for SerializeSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_null() && self.1 == 0 {
+            return serializer.serialize_none();
+        }
+        if !is_valid_slice(self.0, self.1) {
+            return Err(ser::Error::custom(format!(
+                "invalid array of {} elements at {:p}",
+                self.1, self.0
+            )));
+        }
+        serializer.serialize_some(unsafe { std::slice::from_raw_parts(self.0, self.1) })
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeStringArray(*const *const libc::c_char, usize);
+
+impl SerializeStringArray {
+    /// `ptr` must be null or point at `len` C strings
+    pub unsafe fn new(ptr: *const *const libc::c_char, len: usize) -> Self {
+        SerializeStringArray(ptr, len)
+    }
+}
+
+impl Serialize for SerializeStringArray {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_null() && self.1 == 0 {
+            return serializer.serialize_none();
+        }
+        if !is_valid_slice(self.0, self.1) {
+            return Err(ser::Error::custom(format!(
+                "invalid array of {} C strings at {:p}",
+                self.1, self.0
+            )));
+        }
+        let strings = unsafe { std::slice::from_raw_parts(self.0, self.1) };
+        serializer.serialize_some(&CStrs(strings))
+    }
+}
+
+struct CStrs<'a>(&'a [*const libc::c_char]);
+
+impl Serialize for CStrs<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for &ptr in self.0 {
+            seq.serialize_element(&unsafe { SerializeCStr::new(ptr) })?;
+        }
+        seq.end()
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeNested<T>(*const T);
+
+impl<T> SerializeNested<T> {
+    /// `ptr` must be null or point at a `T`
+    pub unsafe fn new(ptr: *const T) -> Self {
+        SerializeNested(ptr)
+    }
+}
+
+impl<T: Serialize> Serialize for SerializeNested<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_null() {
+            return serializer.serialize_none();
+        }
+        if !self.0.is_aligned() {
+            return Err(ser::Error::custom(format!(
+                "unaligned pointer {:p}",
+                self.0
+            )));
+        }
+        serializer.serialize_some(unsafe { &*self.0 })
+    }
+}
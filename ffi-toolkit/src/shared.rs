@@ -0,0 +1,80 @@
+//! Shared ownership of Rust objects by C.
+//!
+//! A pointer from `shared_raw_ptr()` holds one reference to an `Arc`. The host takes another
+//! reference for every goroutine or thread that uses the object with an exported function
+//! calling `clone_shared()`, and gives each reference back with one calling `release_shared()`.
+//! The object is dropped with its last reference.
+//!
+//! In debug builds the references held by C are counted, see `outstanding_shared_refs()`, so
+//! that tests can check that the host released all of them.
+
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::Arc;
+
+#[cfg(debug_assertions)]
+static OUTSTANDING: AtomicIsize = AtomicIsize::new(0);
+
+fn count_refs(_delta: isize) {
+    #[cfg(debug_assertions)]
+    OUTSTANDING.fetch_add(_delta, Ordering::SeqCst);
+}
+
+/// Hands a reference to `value` over to C
+pub fn shared_raw_ptr<T>(value: Arc<T>) -> *const T {
+    count_refs(1);
+    Arc::into_raw(value)
+}
+
+/// Takes another reference, returns `ptr` (null stays null)
+///
+/// `ptr` must be null or come from `shared_raw_ptr()` and still hold a reference.
+pub unsafe fn clone_shared<T>(ptr: *const T) -> *const T {
+    if !ptr.is_null() {
+        count_refs(1);
+        Arc::increment_strong_count(ptr);
+    }
+    ptr
+}
+
+/// Gives a reference back, the object is dropped with the last one, null is ignored
+pub unsafe fn release_shared<T>(ptr: *const T) {
+    if !ptr.is_null() {
+        count_refs(-1);
+        Arc::decrement_strong_count(ptr);
+    }
+}
+
+/// The object behind `ptr`, `None` if it is null
+pub unsafe fn borrow_shared<'a, T>(ptr: *const T) -> Option<&'a T> {
+    ptr.as_ref()
+}
+
+/// A new `Arc` of the object behind `ptr`, e.g. to keep it beyond the call, `None` if it is null
+pub unsafe fn arc_from_shared<T>(ptr: *const T) -> Option<Arc<T>> {
+    if ptr.is_null() {
+        None
+    } else {
+        Arc::increment_strong_count(ptr);
+        Some(Arc::from_raw(ptr))
+    }
+}
+
+/// The number of references to the object behind `ptr`, from Rust and C, 0 for null
+pub unsafe fn shared_ref_count<T>(ptr: *const T) -> usize {
+    match arc_from_shared(ptr) {
+        // Without the one just taken
+        Some(arc) => Arc::strong_count(&arc) - 1,
+        None => 0,
+    }
+}
+
+/// The number of references handed to C that weren't released yet, over all shared objects
+///
+/// Only counted in debug builds, `None` in release builds.
+pub fn outstanding_shared_refs() -> Option<isize> {
+    #[cfg(debug_assertions)]
+    return Some(OUTSTANDING.load(Ordering::SeqCst));
+    #[cfg(not(debug_assertions))]
+    None
+}
@@ -0,0 +1,40 @@
+use std::convert::TryFrom;
+use std::mem;
+
+/// The Rust side of a C `size_t`
+pub type FfiSize = libc::size_t;
+
+/// The Rust side of a C `ptrdiff_t`
+pub type FfiPtrDiff = libc::ptrdiff_t;
+
+// The toolkit (and the generated drop code) passes lengths as `usize`, which is only sound if
+// they are the same width as the C types on the target.
+const _: () = assert!(mem::size_of::<usize>() == mem::size_of::<libc::uintptr_t>());
+const _: () = assert!(mem::size_of::<usize>() == mem::size_of::<libc::size_t>());
+const _: () = assert!(mem::size_of::<isize>() == mem::size_of::<libc::ptrdiff_t>());
+const _: () = assert!(mem::size_of::<usize>() <= mem::size_of::<u64>());
+
+/// The pointer width of the target in bits
+pub const POINTER_WIDTH: u32 = usize::BITS;
+
+// convert a 64-bit length (e.g. a `uint64_t` from a host) into a `size_t`, fails on 32-bit
+// targets if the value doesn't fit
+pub fn u64_to_size(value: u64) -> Option<FfiSize> {
+    FfiSize::try_from(value).ok()
+}
+
+// convert a `size_t` into a 64-bit length, this can't fail on any supported target
+pub fn size_to_u64(value: FfiSize) -> u64 {
+    value as u64
+}
+
+// convert a 64-bit signed offset into a `ptrdiff_t`, fails on 32-bit targets if the value
+// doesn't fit
+pub fn i64_to_ptrdiff(value: i64) -> Option<FfiPtrDiff> {
+    FfiPtrDiff::try_from(value).ok()
+}
+
+// convert a `ptrdiff_t` into a 64-bit signed offset, this can't fail on any supported target
+pub fn ptrdiff_to_i64(value: FfiPtrDiff) -> i64 {
+    value as i64
+}
@@ -0,0 +1,76 @@
+/// Declares a `#[repr(C)]` status enum whose discriminants are mirrored as constants
+///
+/// Every variant is paired with the name of a constant, e.g. `FCPNoError = 0 => FCP_NO_ERROR`.
+/// The constants are emitted as `pub const` items (which cbindgen turns into `#define`s) and
+/// `c_header()` renders the same values as C source, so a C-side `switch` can be checked
+/// against the Rust enum whenever it changes.
+///
+/// ```
+/// use ffi_toolkit::status_code_enum;
+///
+/// status_code_enum! {
+///     #[derive(PartialEq, Debug, Copy, Clone)]
+///     pub enum SealStatus {
+///         Sealed = 0 => SEAL_STATUS_SEALED,
+///         Pending = 1 => SEAL_STATUS_PENDING,
+///     }
+/// }
+///
+/// assert_eq!(SealStatus::Pending as i32, SEAL_STATUS_PENDING);
+/// assert!(SealStatus::c_header().contains("#define SEAL_STATUS_PENDING 1"));
+/// ```
+#[macro_export]
+macro_rules! status_code_enum {
+    {
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $value:expr => $constant:ident,
+            )*
+        }
+    } => {
+        #[repr(C)]
+        $(#[$meta])*
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant = $value,
+            )*
+        }
+
+        $(
+            pub const $constant: ::core::ffi::c_int = $value;
+            const _: () = assert!($name::$variant as ::core::ffi::c_int == $constant);
+        )*
+
+        impl $name {
+            /// All variants together with the name and value of their mirrored constant
+            pub const VARIANTS: &'static [($name, &'static str, ::core::ffi::c_int)] = &[
+                $( ($name::$variant, stringify!($constant), $constant), )*
+            ];
+
+            /// Renders the discriminants as C `#define`s and as a C enum
+            pub fn c_header() -> $crate::__private::String {
+                let mut header = $crate::__private::String::new();
+                $(
+                    header.push_str(&$crate::__private::format!(
+                        "#define {} {}\n",
+                        stringify!($constant),
+                        $constant
+                    ));
+                )*
+                header.push_str(&$crate::__private::format!("\ntypedef enum {} {{\n", stringify!($name)));
+                $(
+                    header.push_str(&$crate::__private::format!(
+                        "  {} = {},\n",
+                        stringify!($variant),
+                        $constant
+                    ));
+                )*
+                header.push_str(&$crate::__private::format!("}} {};\n", stringify!($name)));
+                header
+            }
+        }
+    }
+}
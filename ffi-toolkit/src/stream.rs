@@ -0,0 +1,157 @@
+//! Host storage as `std::io::Read`, `Write` and `Seek`, through C callbacks.
+//!
+//! The host passes a `FfiStream`, its read, write and seek functions together with their
+//! `user_data`, and Rust streams through it like through a file, e.g. to seal a sector straight
+//! into the host's storage layer instead of a temporary file. Every callback may be null if the
+//! stream doesn't support it. Failures are `io::Error`s, which are reported as
+//! `FCPReceiverError` by `catch_panic_result()`.
+//!
+//! ```
+//! use std::io::Read;
+//!
+//! use ffi_toolkit::{CallbackStream, FfiStream};
+//!
+//! extern "C" fn read_zeros(buf: *mut u8, len: usize, _user_data: *mut libc::c_void) -> isize {
+//!     unsafe { std::ptr::write_bytes(buf, 0, len) };
+//!     len as isize
+//! }
+//!
+//! let mut stream = unsafe {
+//!     CallbackStream::new(FfiStream {
+//!         read_fn: Some(read_zeros),
+//!         write_fn: None,
+//!         seek_fn: None,
+//!         user_data: std::ptr::null_mut(),
+//!     })
+//! };
+//! let mut sector = [1u8; 32];
+//! stream.read_exact(&mut sector).unwrap();
+//! assert_eq!(sector, [0; 32]);
+//! ```
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::panic;
+
+use crate::{CCallback, CallbackArgs};
+
+/// Reads up to `len` bytes into `buf`, returns the number read, 0 at the end or negative on error
+pub type FfiReadFn =
+    extern "C" fn(buf: *mut u8, len: libc::size_t, user_data: *mut libc::c_void) -> isize;
+/// Writes up to `len` bytes from `buf`, returns the number written or negative on error
+pub type FfiWriteFn =
+    extern "C" fn(buf: *const u8, len: libc::size_t, user_data: *mut libc::c_void) -> isize;
+/// Seeks to `offset` from `whence` (`SEEK_SET`, `SEEK_CUR` or `SEEK_END`), returns the new
+/// position or negative on error
+pub type FfiSeekFn =
+    extern "C" fn(offset: i64, whence: libc::c_int, user_data: *mut libc::c_void) -> i64;
+
+/// The callbacks of a host stream, as passed across the boundary
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FfiStream {
+    pub read_fn: Option<FfiReadFn>,
+    pub write_fn: Option<FfiWriteFn>,
+    pub seek_fn: Option<FfiSeekFn>,
+    pub user_data: *mut libc::c_void,
+}
+
+/// A host stream, implementing `Read`, `Write` and `Seek` with its callbacks
+///
+/// Writes aren't buffered and `flush()` does nothing, wrap it in a `BufWriter` for small writes.
+#[derive(Debug)]
+pub struct CallbackStream {
+    read: CCallback<(*mut u8, libc::size_t), isize>,
+    write: CCallback<(*const u8, libc::size_t), isize>,
+    seek: CCallback<(i64, libc::c_int), i64>,
+}
+
+impl CallbackStream {
+    /// The host must guarantee that the callbacks can be called with `user_data` for as long as
+    /// the stream is used
+    pub unsafe fn new(stream: FfiStream) -> Self {
+        CallbackStream {
+            read: CCallback::new(stream.read_fn, stream.user_data),
+            write: CCallback::new(stream.write_fn, stream.user_data),
+            seek: CCallback::new(stream.seek_fn, stream.user_data),
+        }
+    }
+}
+
+// Calls a callback, a callback that unwinds (one defined in Rust) is an error instead of
+// unwinding through the caller's frames. `None` if the callback is null.
+fn call<Args, Ret>(
+    callback: &CCallback<Args, Ret>,
+    name: &str,
+    args: Args,
+) -> io::Result<Option<Ret>>
+where
+    Args: CallbackArgs<Ret>,
+{
+    panic::catch_unwind(panic::AssertUnwindSafe(|| callback.call(args)))
+        .map_err(|_| io::Error::other(format!("the host's {} callback panicked", name)))
+}
+
+fn unsupported(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("the host stream has no {} callback", name),
+    )
+}
+
+fn failed(name: &str, code: i64) -> io::Error {
+    io::Error::other(format!("the host's {} callback failed with {}", name, code))
+}
+
+// The count returned by a read or write callback, a count larger than the buffer is an error
+// rather than trusted
+fn checked_len(name: &str, returned: isize, len: usize) -> io::Result<usize> {
+    match usize::try_from(returned) {
+        Ok(n) if n <= len => Ok(n),
+        Ok(n) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the host's {} callback returned {} for a buffer of {} bytes",
+                name, n, len
+            ),
+        )),
+        Err(_) => Err(failed(name, returned as i64)),
+    }
+}
+
+impl Read for CallbackStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let returned = call(&self.read, "read", (buf.as_mut_ptr(), buf.len()))?
+            .ok_or_else(|| unsupported("read"))?;
+        checked_len("read", returned, buf.len())
+    }
+}
+
+impl Write for CallbackStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let returned = call(&self.write, "write", (buf.as_ptr(), buf.len()))?
+            .ok_or_else(|| unsupported("write"))?;
+        checked_len("write", returned, buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for CallbackStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "the offset is too large");
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (
+                i64::try_from(offset).map_err(|_| invalid())?,
+                libc::SEEK_SET,
+            ),
+            SeekFrom::Current(offset) => (offset, libc::SEEK_CUR),
+            SeekFrom::End(offset) => (offset, libc::SEEK_END),
+        };
+        let position =
+            call(&self.seek, "seek", (offset, whence))?.ok_or_else(|| unsupported("seek"))?;
+        u64::try_from(position).map_err(|_| failed("seek", position))
+    }
+}
@@ -0,0 +1,113 @@
+use alloc_crate::boxed::Box;
+use alloc_crate::string::String;
+use alloc_crate::vec::Vec;
+use core::iter::FromIterator;
+use core::ptr;
+use core::slice;
+
+use crate::c_types::{c_char, size_t};
+use crate::{c_str_to_rust_str, free_c_str, rust_str_to_c_str};
+
+/// An array of C strings, which owns the array as well as the strings
+///
+/// Dropping it frees everything, so it can be a field of a `DropStructMacro` response. C frees a
+/// `FfiStringArray` it owns with `fil_free_string_array()`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiStringArray {
+    pub ptr: *const *const c_char,
+    pub len: size_t,
+}
+
+impl FfiStringArray {
+    /// Strings with interior nul bytes are truncated at the first one
+    pub fn new<I, S>(strings: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let c_strs: Box<[*const c_char]> = strings
+            .into_iter()
+            .map(|string| {
+                let mut string = string.into();
+                if let Some(nul) = string.find('\0') {
+                    string.truncate(nul);
+                }
+                rust_str_to_c_str(string) as *const c_char
+            })
+            .collect();
+        let len = c_strs.len();
+        FfiStringArray {
+            ptr: Box::into_raw(c_strs) as *const *const c_char,
+            len,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(Vec::<String>::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies the strings, invalid UTF-8 is replaced
+    pub fn to_vec(&self) -> Vec<String> {
+        self.as_slice()
+            .iter()
+            .map(|&c_str| unsafe { c_str_to_rust_str(c_str) }.into_owned())
+            .collect()
+    }
+
+    fn as_slice(&self) -> &[*const c_char] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Default for FfiStringArray {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for FfiStringArray {
+    fn from_iter<I: IntoIterator<Item = S>>(strings: I) -> Self {
+        Self::new(strings)
+    }
+}
+
+impl Drop for FfiStringArray {
+    fn drop(&mut self) {
+        unsafe {
+            for &c_str in self.as_slice() {
+                free_c_str(c_str as *mut c_char);
+            }
+            let c_strs = slice::from_raw_parts_mut(self.ptr as *mut *const c_char, self.len);
+            drop(Box::from_raw(c_strs as *mut [*const c_char]));
+        }
+    }
+}
+
+/// The string at `index`, or null if `array` is null or `index` is out of bounds
+///
+/// The string is owned by the array.
+#[no_mangle]
+pub unsafe extern "C" fn fil_string_array_get(
+    array: *const FfiStringArray,
+    index: size_t,
+) -> *const c_char {
+    match array.as_ref() {
+        Some(array) => array.as_slice().get(index).copied().unwrap_or(ptr::null()),
+        None => ptr::null(),
+    }
+}
+
+/// Frees a `FfiStringArray` that was handed out to the caller
+#[no_mangle]
+pub extern "C" fn fil_free_string_array(array: FfiStringArray) {
+    drop(array);
+}
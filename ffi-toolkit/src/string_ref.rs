@@ -0,0 +1,141 @@
+use alloc_crate::borrow::Cow;
+use alloc_crate::string::{String, ToString};
+use core::mem::ManuallyDrop;
+use core::slice;
+use core::str::{self, Utf8Error};
+
+use crate::c_types::size_t;
+
+/// A string passed by pointer and length, e.g. from the host, borrowing its bytes
+///
+/// Unlike a C string it may contain nul bytes. The bytes are expected to be UTF-8, but aren't
+/// checked until the string is read with `as_str()`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct StringRef {
+    pub ptr: *const u8,
+    pub len: size_t,
+}
+
+impl StringRef {
+    /// Borrows `s`, which must outlive the `StringRef`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        StringRef {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+
+    /// The bytes, `ptr` may only be null if `len` is 0
+    ///
+    /// `ptr` must be valid for reads of `len` bytes for as long as the slice is used.
+    pub unsafe fn as_bytes<'a>(&self) -> &'a [u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(self.ptr, self.len)
+        }
+    }
+
+    /// The string, invalid UTF-8 is an error, see `as_bytes()`
+    pub unsafe fn as_str<'a>(&self) -> Result<&'a str, Utf8Error> {
+        str::from_utf8(self.as_bytes())
+    }
+
+    /// The string, invalid UTF-8 is replaced, see `as_bytes()`
+    pub unsafe fn to_string_lossy<'a>(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
+}
+
+impl Default for StringRef {
+    fn default() -> Self {
+        Self::from_str("")
+    }
+}
+
+/// A UTF-8 string passed by pointer and length, which owns its memory
+///
+/// Unlike a C string it may contain nul bytes, so messages and user data are never truncated.
+/// Dropping it frees the string, so it can be a field of a `DropStructMacro` or `FFIResponse`
+/// response, the derived `Drop` leaves it to its own. C frees a `FfiString` it owns with
+/// `fil_free_string()`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiString {
+    pub ptr: *const u8,
+    pub len: size_t,
+    // The capacity of the `String` the bytes came from, C must not change it
+    pub cap: size_t,
+}
+
+impl FfiString {
+    pub fn new(s: String) -> Self {
+        let mut bytes = ManuallyDrop::new(s.into_bytes());
+        FfiString {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            cap: bytes.capacity(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.ptr, self.len)) }
+    }
+
+    /// Borrows the string, the `StringRef` must not outlive it
+    pub fn as_string_ref(&self) -> StringRef {
+        StringRef::from_str(self.as_str())
+    }
+
+    /// Hands the memory back as the string it came from
+    pub fn into_string(self) -> String {
+        let s = ManuallyDrop::new(self);
+        unsafe { String::from_raw_parts(s.ptr as *mut u8, s.len, s.cap) }
+    }
+}
+
+impl Default for FfiString {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl From<String> for FfiString {
+    fn from(s: String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<&str> for FfiString {
+    fn from(s: &str) -> Self {
+        Self::new(s.to_string())
+    }
+}
+
+impl Drop for FfiString {
+    fn drop(&mut self) {
+        unsafe {
+            drop(String::from_raw_parts(
+                self.ptr as *mut u8,
+                self.len,
+                self.cap,
+            ));
+        }
+    }
+}
+
+/// Frees a `FfiString` that was handed out to the caller
+#[no_mangle]
+pub extern "C" fn fil_free_string(s: FfiString) {
+    drop(s);
+}
@@ -0,0 +1,41 @@
+//! The synchronization primitives used by the toolkit's shared state.
+//!
+//! With the `loom` feature they are replaced by their loom counterparts, so that the
+//! interleavings of the toolkit's internals can be model checked:
+//!
+//! ```console
+//! $ cargo test -p ffi-toolkit --features loom --release --lib
+//! ```
+//!
+//! The feature is only meant for running these model checks, loom types panic when used outside
+//! of `loom::model()`.
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicUsize};
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::{Condvar, Mutex};
+
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize};
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::sync::{Condvar, Mutex};
+
+/// Declares a function returning a lazily initialized global, e.g.
+/// `toolkit_global!(fn lifecycle() -> Lifecycle = Lifecycle::new());`
+macro_rules! toolkit_global {
+    (fn $name:ident() -> $ty:ty = $init:expr) => {
+        #[cfg(not(feature = "loom"))]
+        fn $name() -> &'static $ty {
+            static GLOBAL: ::std::sync::OnceLock<$ty> = ::std::sync::OnceLock::new();
+            GLOBAL.get_or_init(|| $init)
+        }
+
+        #[cfg(feature = "loom")]
+        fn $name() -> &'static $ty {
+            ::loom::lazy_static! {
+                static ref GLOBAL: $ty = $init;
+            }
+            &GLOBAL
+        }
+    };
+}
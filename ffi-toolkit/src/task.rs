@@ -0,0 +1,522 @@
+//! Long-running operations on a pool of Rust threads, polled or joined by C.
+//!
+//! `spawn_ffi_task()` queues a closure producing a response and returns a handle right away, so
+//! that e.g. a proof doesn't block the host's thread for minutes. The closure runs within
+//! `catch_panic_response()` on one of the pool's threads. C polls or waits for the task with the
+//! exported functions and takes the response with an exported function of the consumer, which
+//! calls `task_take_response()` for its response type. Alternatively,
+//! `spawn_ffi_task_with_callback()` hands the response to a C callback once the task finished.
+//! `catch_panic_response_with_timeout()` runs a task on the pool for a call that blocks, but
+//! returns an error response once a deadline passed.
+//!
+//! The pool is started with the first task. On `shutdown()` the running tasks are waited for
+//! and the queued ones finish with an error response instead of running. `worker_pool_stats()`
+//! and `task_timing()` tell how busy the pool is and how long tasks wait and run, so that
+//! operators can tune its size with `set_task_threads()`.
+
+use std::any::{self, Any};
+use std::convert::TryFrom;
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+use crate::handle::{self, HandleError};
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{
+    catch_panic_response, error_response, free_raw_ptr, AssertSend, CodeAndMessage,
+    FCPResponseStatus, FfiDuration, IntoFFIError, StatusCode,
+};
+
+/// A task, see `spawn_ffi_task()`
+pub type TaskHandle = u64;
+
+status_code_enum! {
+    #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+    pub enum FfiTaskStatus {
+        Running = 0 => FIL_TASK_RUNNING,
+        Finished = 1 => FIL_TASK_FINISHED,
+        InvalidHandle = 2 => FIL_TASK_INVALID_HANDLE,
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    sender: Sender<Job>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+static POOL: Mutex<Option<Pool>> = Mutex::new(None);
+
+// 0 for one thread per CPU
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// Jobs submitted and not picked up by a thread yet, and the ones running
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static TOTAL_RUN_US: AtomicU64 = AtomicU64::new(0);
+
+enum TaskResult {
+    Running,
+    Finished(Box<dyn Any + Send>),
+    Taken,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Times {
+    queued: Instant,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+struct TaskState {
+    result: Mutex<TaskResult>,
+    finished: Condvar,
+    times: Mutex<Times>,
+}
+
+// What the handle registry holds, the worker holds the state as well
+struct Task(Arc<TaskState>);
+
+// A response that isn't taken yet, freed if the task is released before. Responses aren't
+// `Send` because of their raw pointers, but they own what these point to and are handed to the
+// host's threads anyway.
+struct Response<T>(AssertSend<*mut T>);
+
+impl<T> Response<T> {
+    fn new(response: *mut T) -> Self {
+        Response(unsafe { AssertSend::new(response) })
+    }
+
+    fn into_raw(self) -> *mut T {
+        let ptr = *self.0.get();
+        mem::forget(self);
+        ptr
+    }
+}
+
+impl<T> Drop for Response<T> {
+    fn drop(&mut self) {
+        unsafe { free_raw_ptr(*self.0.get()) };
+    }
+}
+
+/// Sets the number of the pool's threads, 0 (the default) for one per CPU
+///
+/// Takes effect when the pool is started, returns false if it is running already. The pool is
+/// started with the first task and again with the first task after a `shutdown()`.
+pub fn set_task_threads(threads: usize) -> bool {
+    THREADS.store(threads, Ordering::SeqCst);
+    POOL.lock().unwrap().is_none()
+}
+
+fn start_pool() -> Pool {
+    let threads = match THREADS.load(Ordering::SeqCst) {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = (0..threads)
+        .map(|index| {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(format!("fil-task-{}", index))
+                .spawn(move || work(&receiver))
+                .expect("failed to spawn a task thread")
+        })
+        .collect();
+    lifecycle::register_shutdown_hook(ShutdownPhase::WorkerPools, stop_pool);
+    Pool { sender, workers }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // Not holding the lock while the job runs
+        let job = receiver.lock().unwrap().recv();
+        match job {
+            Ok(job) => {
+                QUEUED.fetch_sub(1, Ordering::SeqCst);
+                RUNNING.fetch_add(1, Ordering::SeqCst);
+                let started = Instant::now();
+                job();
+                TOTAL_RUN_US.fetch_add(micros(started.elapsed()), Ordering::SeqCst);
+                COMPLETED.fetch_add(1, Ordering::SeqCst);
+                RUNNING.fetch_sub(1, Ordering::SeqCst);
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+// The queued jobs see the shutdown and finish right away
+fn stop_pool() {
+    let pool = POOL.lock().unwrap().take();
+    if let Some(Pool { sender, workers }) = pool {
+        drop(sender);
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn submit(job: Job) {
+    QUEUED.fetch_add(1, Ordering::SeqCst);
+    POOL.lock()
+        .unwrap()
+        .get_or_insert_with(start_pool)
+        .sender
+        .send(job)
+        .expect("the task threads are running");
+}
+
+fn run<F, T, C>(task: F) -> *mut T
+where
+    F: FnOnce() -> *mut T,
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+{
+    if lifecycle::is_shutting_down() {
+        cancelled_response::<T, C>()
+    } else {
+        catch_panic_response(task)
+    }
+}
+
+fn cancelled_response<T, C>() -> *mut T
+where
+    T: Default + CodeAndMessage<C>,
+    C: StatusCode,
+{
+    error_response(C::UNCLASSIFIED, "the task was cancelled by shutdown")
+}
+
+/// Queues `task` on the pool and returns a handle to it
+///
+/// The handle stays valid until the response is taken or the task is released.
+pub fn spawn_ffi_task<F, T, C>(task: F) -> TaskHandle
+where
+    F: FnOnce() -> *mut T + Send + 'static,
+    T: Default + CodeAndMessage<C> + 'static,
+    C: StatusCode,
+{
+    let state = Arc::new(TaskState {
+        result: Mutex::new(TaskResult::Running),
+        finished: Condvar::new(),
+        times: Mutex::new(Times {
+            queued: Instant::now(),
+            started: None,
+            finished: None,
+        }),
+    });
+    let handle = handle::register(Task(Arc::clone(&state)));
+    submit(Box::new(move || {
+        state.times.lock().unwrap().started = Some(Instant::now());
+        let response = run(task);
+        state.times.lock().unwrap().finished = Some(Instant::now());
+        *state.result.lock().unwrap() = TaskResult::Finished(Box::new(Response::new(response)));
+        state.finished.notify_all();
+    }));
+    handle
+}
+
+/// Like `spawn_ffi_task()`, for a task that checks `token` and returns early once it is cancelled
+///
+/// A task that is cancelled before it started doesn't run, its response is a `FCPCallerError`.
+pub fn spawn_cancellable_ffi_task<F, T, C>(token: CancellationToken, task: F) -> TaskHandle
+where
+    F: FnOnce(&CancellationToken) -> *mut T + Send + 'static,
+    T: Default + CodeAndMessage<C> + 'static,
+    C: StatusCode,
+{
+    spawn_ffi_task(move || match token.check() {
+        Ok(()) => task(&token),
+        Err(cancelled) => error_response(
+            C::from_response_status(cancelled.code()),
+            cancelled.message(),
+        ),
+    })
+}
+
+/// Like `catch_panic_response()`, but returns a `FCPReceiverError` response once `timeout` passed
+///
+/// The timeout is a `Duration` or the `FfiDuration` the host passed. The task runs on the pool while the calling thread waits for it. If it doesn't finish in
+/// time, its token is cancelled and the task abandoned: it should check the token and return
+/// early, its response is freed whenever it finishes.
+pub fn catch_panic_response_with_timeout<D, F, T, C>(timeout: D, task: F) -> *mut T
+where
+    D: Into<FfiDuration>,
+    F: FnOnce(&CancellationToken) -> *mut T + Send + 'static,
+    T: Default + CodeAndMessage<C> + 'static,
+    C: StatusCode,
+{
+    let timeout = timeout.into().to_duration_saturating();
+    catch_panic_response(|| {
+        let token = CancellationToken::new();
+        let handle = spawn_cancellable_ffi_task(token.clone(), task);
+        let finished = task_state(handle).is_ok_and(|state| {
+            let result = state.result.lock().unwrap();
+            let (result, _) = state
+                .finished
+                .wait_timeout_while(result, timeout, |result| {
+                    matches!(result, TaskResult::Running)
+                })
+                .unwrap();
+            !matches!(*result, TaskResult::Running)
+        });
+        if finished {
+            if let Ok(Some(response)) = task_take_response::<T>(handle) {
+                return response;
+            }
+        }
+        token.cancel();
+        let _ = task_release(handle);
+        error_response(
+            C::from_response_status(FCPResponseStatus::FCPReceiverError),
+            format!("the call timed out after {:?}", timeout),
+        )
+    })
+}
+
+/// The callback of `spawn_ffi_task_with_callback()`, it owns the response
+pub type TaskDoneCallback<T> = extern "C" fn(user_data: *mut libc::c_void, response: *mut T);
+
+/// The `user_data` of a `TaskDoneCallback`, which is called on one of the pool's threads
+#[derive(Debug, Copy, Clone)]
+pub struct SendUserData(AssertSend<*mut libc::c_void>);
+
+impl SendUserData {
+    /// The host must guarantee that whatever `user_data` points to can be used from any thread
+    pub unsafe fn new(user_data: *mut libc::c_void) -> Self {
+        SendUserData(AssertSend::new(user_data))
+    }
+
+    pub fn as_ptr(self) -> *mut libc::c_void {
+        *self.0.get()
+    }
+}
+
+// Calls the callback exactly once, with the cancelled response if the job is dropped unrun
+struct Completion<T> {
+    done: TaskDoneCallback<T>,
+    user_data: SendUserData,
+    cancelled: fn() -> *mut T,
+}
+
+impl<T> Completion<T> {
+    fn complete(self, response: *mut T) {
+        (self.done)(self.user_data.as_ptr(), response);
+        mem::forget(self);
+    }
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        (self.done)(self.user_data.as_ptr(), (self.cancelled)());
+    }
+}
+
+/// Queues `task` on the pool, `done` is called with the response when it finished
+///
+/// `done` is called exactly once, on one of the pool's threads, also if the task panics (with
+/// an error response, as with `catch_panic_response()`) or is cancelled by `shutdown()`.
+pub fn spawn_ffi_task_with_callback<F, T, C>(
+    task: F,
+    done: TaskDoneCallback<T>,
+    user_data: SendUserData,
+) where
+    F: FnOnce() -> *mut T + Send + 'static,
+    T: Default + CodeAndMessage<C> + 'static,
+    C: StatusCode,
+{
+    let completion = Completion {
+        done,
+        user_data,
+        cancelled: cancelled_response::<T, C>,
+    };
+    submit(Box::new(move || completion.complete(run(task))));
+}
+
+fn task_state(handle: TaskHandle) -> Result<Arc<TaskState>, HandleError> {
+    handle::with_handle(handle, |task: &mut Task| Arc::clone(&task.0))
+}
+
+/// Whether the task finished
+pub fn task_poll(handle: TaskHandle) -> Result<bool, HandleError> {
+    let state = task_state(handle)?;
+    let finished = !matches!(*state.result.lock().unwrap(), TaskResult::Running);
+    Ok(finished)
+}
+
+/// Waits up to `timeout_ms` milliseconds for the task to finish, returns whether it did
+pub fn task_wait(handle: TaskHandle, timeout_ms: u64) -> Result<bool, HandleError> {
+    let state = task_state(handle)?;
+    let result = state.result.lock().unwrap();
+    let (result, _) = state
+        .finished
+        .wait_timeout_while(result, Duration::from_millis(timeout_ms), |result| {
+            matches!(result, TaskResult::Running)
+        })
+        .unwrap();
+    Ok(!matches!(*result, TaskResult::Running))
+}
+
+/// Takes the response of a finished task and releases the handle, `None` if it is still running
+pub fn task_take_response<T: 'static>(handle: TaskHandle) -> Result<Option<*mut T>, HandleError> {
+    let state = task_state(handle)?;
+    let mut result = state.result.lock().unwrap();
+    match mem::replace(&mut *result, TaskResult::Taken) {
+        TaskResult::Running => {
+            *result = TaskResult::Running;
+            Ok(None)
+        }
+        // Taken by another thread in the meantime
+        TaskResult::Taken => Err(HandleError::Stale),
+        TaskResult::Finished(response) => match response.downcast::<Response<T>>() {
+            Ok(response) => {
+                drop(result);
+                let _ = handle::release::<Task>(handle);
+                Ok(Some(response.into_raw()))
+            }
+            Err(response) => {
+                *result = TaskResult::Finished(response);
+                Err(HandleError::WrongType {
+                    expected: any::type_name::<T>(),
+                })
+            }
+        },
+    }
+}
+
+/// Releases the handle without taking the response
+///
+/// A running task still runs to completion, its response is freed then.
+pub fn task_release(handle: TaskHandle) -> Result<(), HandleError> {
+    handle::release::<Task>(handle)
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// How long a task waited for a thread and ran, in microseconds, so far if it isn't done
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FfiTaskTiming {
+    pub queued_us: u64,
+    /// 0 while it waits for a thread
+    pub run_us: u64,
+}
+
+/// The timing of a task whose response wasn't taken yet
+pub fn task_timing(handle: TaskHandle) -> Result<FfiTaskTiming, HandleError> {
+    let times = *task_state(handle)?.times.lock().unwrap();
+    let now = Instant::now();
+    let started = times.started.unwrap_or(now);
+    Ok(FfiTaskTiming {
+        queued_us: micros(started - times.queued),
+        run_us: times
+            .started
+            .map_or(0, |started| micros(times.finished.unwrap_or(now) - started)),
+    })
+}
+
+/// The load of the pool, see `worker_pool_stats()`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FfiWorkerPoolStats {
+    /// The threads of the running pool, 0 if it isn't started
+    pub threads: libc::size_t,
+    /// As set with `set_task_threads()`, 0 for one per CPU
+    pub configured_threads: libc::size_t,
+    /// Tasks waiting for a thread
+    pub queued: libc::size_t,
+    pub running: libc::size_t,
+    /// Tasks run since the library was loaded, and their total run time
+    pub completed: u64,
+    pub total_run_us: u64,
+}
+
+pub fn worker_pool_stats() -> FfiWorkerPoolStats {
+    FfiWorkerPoolStats {
+        threads: POOL
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |pool| pool.workers.len()),
+        configured_threads: THREADS.load(Ordering::SeqCst),
+        queued: QUEUED.load(Ordering::SeqCst),
+        running: RUNNING.load(Ordering::SeqCst),
+        completed: COMPLETED.load(Ordering::SeqCst),
+        total_run_us: TOTAL_RUN_US.load(Ordering::SeqCst),
+    }
+}
+
+/// The number of tasks waiting for a thread
+pub fn worker_queue_depth() -> usize {
+    QUEUED.load(Ordering::SeqCst)
+}
+
+fn task_status(finished: Result<bool, HandleError>) -> FfiTaskStatus {
+    match finished {
+        Ok(true) => FfiTaskStatus::Finished,
+        Ok(false) => FfiTaskStatus::Running,
+        Err(_) => FfiTaskStatus::InvalidHandle,
+    }
+}
+
+/// See `task_poll()`
+#[no_mangle]
+pub extern "C" fn fil_task_poll(handle: TaskHandle) -> FfiTaskStatus {
+    task_status(task_poll(handle))
+}
+
+/// See `task_wait()`, `FIL_TASK_RUNNING` if the timeout elapsed
+#[no_mangle]
+pub extern "C" fn fil_task_wait(handle: TaskHandle, timeout_ms: u64) -> FfiTaskStatus {
+    task_status(task_wait(handle, timeout_ms))
+}
+
+/// See `task_release()`, invalid handles are ignored
+#[no_mangle]
+pub extern "C" fn fil_task_release(handle: TaskHandle) {
+    let _ = task_release(handle);
+}
+
+/// See `set_task_threads()`
+#[no_mangle]
+pub extern "C" fn fil_set_task_threads(threads: usize) -> bool {
+    set_task_threads(threads)
+}
+
+/// See `task_timing()`, `*timing` is only written for valid handles
+#[no_mangle]
+pub unsafe extern "C" fn fil_task_timing(
+    handle: TaskHandle,
+    timing: *mut FfiTaskTiming,
+) -> FfiTaskStatus {
+    match (task_timing(handle), timing.as_mut()) {
+        (Ok(task_timing), Some(timing)) => {
+            *timing = task_timing;
+            task_status(task_poll(handle))
+        }
+        _ => FfiTaskStatus::InvalidHandle,
+    }
+}
+
+/// See `worker_pool_stats()`
+#[no_mangle]
+pub extern "C" fn fil_worker_pool_stats() -> FfiWorkerPoolStats {
+    worker_pool_stats()
+}
+
+/// See `worker_queue_depth()`
+#[no_mangle]
+pub extern "C" fn fil_worker_queue_depth() -> libc::size_t {
+    worker_queue_depth()
+}
@@ -0,0 +1,157 @@
+//! Temporary directories that are removed when they are destroyed, or at the latest on
+//! `shutdown()`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use drop_struct_macro_derive::DropStructMacro;
+
+use crate::lifecycle::{self, ShutdownPhase};
+use crate::{
+    c_str_to_rust_str, error_response, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str,
+    CodeAndMessage, FCPResponseStatus,
+};
+
+#[derive(Default)]
+struct Registry {
+    paths: HashSet<PathBuf>,
+    // Whether the cleanup is registered for the next shutdown
+    cleanup_registered: bool,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory, which is removed with all its contents on drop
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new directory within the system's temporary directory
+    pub fn create(prefix: &str) -> io::Result<TempDir> {
+        let base = std::env::temp_dir();
+        loop {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.subsec_nanos())
+                .unwrap_or(0);
+            let name = format!(
+                "{}-{}-{}-{:08x}",
+                prefix,
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+                nanos
+            );
+            let path = base.join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => {
+                    register(&path);
+                    return Ok(TempDir { path });
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let registered = REGISTRY
+            .lock()
+            .unwrap()
+            .as_mut()
+            .is_some_and(|registry| registry.paths.remove(&self.path));
+        // Otherwise it was removed on shutdown already
+        if registered {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+fn register(path: &Path) {
+    let mut registry = REGISTRY.lock().unwrap();
+    let registry = registry.get_or_insert_with(Registry::default);
+    registry.paths.insert(path.to_path_buf());
+    if !registry.cleanup_registered {
+        lifecycle::register_shutdown_hook(ShutdownPhase::Resources, remove_all);
+        registry.cleanup_registered = true;
+    }
+}
+
+// removes all temporary directories that weren't destroyed yet
+fn remove_all() {
+    let registry = REGISTRY.lock().unwrap().take();
+    for path in registry.map(|registry| registry.paths).unwrap_or_default() {
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct TempDirResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub path: *const libc::c_char,
+    /// Needs to be destroyed with `fil_destroy_temp_dir()`, not together with the response
+    #[ffi_drop(skip)]
+    pub handle: *mut TempDir,
+}
+
+impl Default for TempDirResponse {
+    fn default() -> Self {
+        TempDirResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            path: ptr::null(),
+            handle: ptr::null_mut(),
+        }
+    }
+}
+
+crate::code_and_message_impl!(TempDirResponse);
+
+/// Creates a temporary directory, whose name starts with `prefix` (`fil` if it's null)
+#[no_mangle]
+pub unsafe extern "C" fn fil_create_temp_dir(prefix: *const libc::c_char) -> *mut TempDirResponse {
+    let prefix = if prefix.is_null() {
+        "fil".into()
+    } else {
+        c_str_to_rust_str(prefix)
+    };
+    match TempDir::create(&prefix) {
+        Ok(temp_dir) => raw_ptr(TempDirResponse {
+            path: rust_str_to_c_str(temp_dir.path().to_string_lossy()),
+            handle: raw_ptr(temp_dir),
+            ..Default::default()
+        }),
+        Err(err) => error_response(
+            FCPResponseStatus::FCPReceiverError,
+            format!("failed to create temporary directory: {}", err),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_temp_dir_response(ptr: *mut TempDirResponse) {
+    free_raw_ptr(ptr);
+}
+
+/// Removes the directory with all its contents
+#[no_mangle]
+pub unsafe extern "C" fn fil_destroy_temp_dir(handle: *mut TempDir) {
+    free_raw_ptr(handle);
+}
@@ -0,0 +1,89 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    // `None` while the current thread isn't counting
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// A global allocator counting the allocations of the threads that are within `count_allocs()`
+///
+/// It forwards to the system allocator. A library can't install a global allocator on behalf of
+/// the binary, so the test crate needs to do it:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: ffi_toolkit::testing::CountingAllocator =
+///     ffi_toolkit::testing::CountingAllocator;
+/// ```
+pub struct CountingAllocator;
+
+fn record_allocation() {
+    // The thread local may be gone already while a thread is torn down
+    let _ = ALLOCATIONS.try_with(|count| {
+        if let Some(allocations) = count.get() {
+            count.set(Some(allocations + 1));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Runs `body` and returns the number of allocations (including reallocations) it made on the
+/// current thread
+///
+/// Always returns 0 unless `CountingAllocator` is the global allocator.
+pub fn count_allocs<F, R>(body: F) -> (R, usize)
+where
+    F: FnOnce() -> R,
+{
+    let outer = ALLOCATIONS.with(|count| count.replace(Some(0)));
+    let result = body();
+    let allocations = ALLOCATIONS.with(|count| count.replace(outer)).unwrap_or(0);
+    // Nested counts add up to the outer one
+    if let Some(outer) = outer {
+        ALLOCATIONS.with(|count| count.set(Some(outer + allocations)));
+    }
+    (result, allocations)
+}
+
+/// Fails the test if the body makes more than `max` allocations, see `count_allocs()`
+///
+/// Evaluates to the value of the body.
+///
+/// ```ignore
+/// let status = assert_max_allocs!(0, { fil_verify(&request) });
+/// ```
+#[macro_export]
+macro_rules! assert_max_allocs {
+    ($max:expr, $body:expr) => {{
+        let (result, allocations) = $crate::testing::count_allocs(|| $body);
+        let max: usize = $max;
+        if allocations > max {
+            panic!(
+                "expected at most {} allocation(s), but there were {}",
+                max, allocations
+            );
+        }
+        result
+    }};
+}
@@ -0,0 +1,124 @@
+use std::ffi::{CStr, CString};
+
+/// Something the simulated C caller owns and frees once it goes out of scope
+enum Allocation {
+    CStr(*mut libc::c_char),
+    Buffer(*mut [u8]),
+    Response(Box<dyn FnOnce()>),
+}
+
+/// Simulates a C caller of exported functions
+///
+/// All memory handed to the exported functions is owned by the `CCaller` and stays valid until
+/// it is dropped, just like memory a C host keeps around for the duration of a call. Responses
+/// returned by exported functions are freed with their destroy function when the `CCaller` is
+/// dropped, in reverse order of their creation.
+///
+/// Pointers are only ever created from raw allocations (`CString::into_raw()`,
+/// `Box::into_raw()`), so the caller itself is clean under Miri and any error Miri reports is
+/// in the code under test:
+///
+/// ```console
+/// $ cargo +nightly miri test --features testing
+/// ```
+#[derive(Default)]
+pub struct CCaller {
+    allocations: Vec<Allocation>,
+}
+
+impl CCaller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A nul-terminated copy of `string`
+    ///
+    /// Panics if the string contains a nul byte, use `c_bytes()` to forge such strings.
+    pub fn c_str(&mut self, string: &str) -> *const libc::c_char {
+        let c_string = CString::new(string).expect("string must not contain a nul byte");
+        let ptr = c_string.into_raw();
+        self.allocations.push(Allocation::CStr(ptr));
+        ptr
+    }
+
+    /// A nul-terminated string made from arbitrary bytes
+    ///
+    /// The bytes don't need to be valid UTF-8. If they contain a nul byte, the string C sees
+    /// ends there.
+    pub fn c_bytes(&mut self, bytes: &[u8]) -> *const libc::c_char {
+        let mut buffer = bytes.to_vec();
+        buffer.push(0);
+        let (ptr, _) = self.push_buffer(buffer);
+        ptr as *const libc::c_char
+    }
+
+    /// A copy of `bytes`, returned as pointer and length
+    pub fn buffer(&mut self, bytes: &[u8]) -> (*const u8, libc::size_t) {
+        let (ptr, len) = self.push_buffer(bytes.to_vec());
+        (ptr as *const u8, len)
+    }
+
+    /// A zeroed buffer the exported function can write into
+    pub fn buffer_mut(&mut self, len: libc::size_t) -> (*mut u8, libc::size_t) {
+        self.push_buffer(vec![0; len])
+    }
+
+    /// Calls an exported function and takes ownership of the response it returns
+    ///
+    /// The response is freed with `destroy` once the caller goes out of scope.
+    pub fn call<T, F>(&mut self, exported: F, destroy: unsafe extern "C" fn(*mut T)) -> *mut T
+    where
+        F: FnOnce() -> *mut T,
+        T: 'static,
+    {
+        let response = exported();
+        if !response.is_null() {
+            self.allocations
+                .push(Allocation::Response(Box::new(move || unsafe {
+                    destroy(response)
+                })));
+        }
+        response
+    }
+
+    /// Reads a C string the way a C caller would, `None` if the pointer is null
+    pub unsafe fn read_c_str(&self, ptr: *const libc::c_char) -> Option<Vec<u8>> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(ptr).to_bytes().to_vec())
+        }
+    }
+
+    /// Reads a buffer the way a C caller would, `None` if the pointer is null
+    pub unsafe fn read_buffer(&self, ptr: *const u8, len: libc::size_t) -> Option<Vec<u8>> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(std::slice::from_raw_parts(ptr, len).to_vec())
+        }
+    }
+
+    fn push_buffer(&mut self, buffer: Vec<u8>) -> (*mut u8, libc::size_t) {
+        let len = buffer.len();
+        let raw = Box::into_raw(buffer.into_boxed_slice());
+        self.allocations.push(Allocation::Buffer(raw));
+        (raw as *mut u8, len)
+    }
+}
+
+impl Drop for CCaller {
+    fn drop(&mut self) {
+        while let Some(allocation) = self.allocations.pop() {
+            match allocation {
+                Allocation::CStr(ptr) => unsafe {
+                    let _ = CString::from_raw(ptr);
+                },
+                Allocation::Buffer(ptr) => unsafe {
+                    let _ = Box::from_raw(ptr);
+                },
+                Allocation::Response(destroy) => destroy(),
+            }
+        }
+    }
+}
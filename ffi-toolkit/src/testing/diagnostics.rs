@@ -0,0 +1,17 @@
+/// Checks that every file matching `pattern` fails to compile with the expected diagnostics
+///
+/// The expected compiler output of `tests/ui/foo.rs` is stored in `tests/ui/foo.stderr`, run the
+/// tests with `TRYBUILD=overwrite` to create or update it. This is meant for macros built on top
+/// of the toolkit as well, to make sure their misuse produces errors pointing at the offending
+/// code.
+///
+/// ```ignore
+/// #[test]
+/// fn diagnostics() {
+///     ffi_toolkit::testing::assert_compile_fail("tests/ui/*.rs");
+/// }
+/// ```
+pub fn assert_compile_fail(pattern: &str) {
+    // The checks run when `TestCases` is dropped
+    trybuild::TestCases::new().compile_fail(pattern);
+}
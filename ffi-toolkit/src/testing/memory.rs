@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::thread;
+
+use crate::alloc::tracking;
+use crate::{alloc_stats, alloc_stats_enabled};
+
+/// The toolkit allocations that were leaked or freed twice within `track_ffi_memory()`
+#[derive(Debug, Default, PartialEq)]
+pub struct MemoryReport {
+    /// The type names of the leaked allocations, with their count
+    pub leaks: BTreeMap<&'static str, usize>,
+    /// The type names of the allocations that were freed twice
+    pub double_frees: Vec<&'static str>,
+}
+
+impl MemoryReport {
+    pub fn is_clean(&self) -> bool {
+        self.leaks.is_empty() && self.double_frees.is_empty()
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (type_name, count) in &self.leaks {
+            writeln!(f, "leaked {} allocation(s) of `{}`", count, type_name)?;
+        }
+        for type_name in &self.double_frees {
+            writeln!(f, "double free of `{}`", type_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `body` while tracking all toolkit allocations and frees on the current thread
+///
+/// The tracked allocations are C strings (`rust_str_to_c_str()`/`free_c_str()`) and boxes
+/// (`raw_ptr()`/`free_raw_ptr()`). A detected double free is reported instead of executed, so
+/// the test fails with a report rather than a crash.
+pub fn track_ffi_memory_report<F, R>(body: F) -> (R, MemoryReport)
+where
+    F: FnOnce() -> R,
+{
+    tracking::start();
+    let result = body();
+    (result, stop_tracking())
+}
+
+fn stop_tracking() -> MemoryReport {
+    let tracker = tracking::stop().expect("memory tracking isn't re-entrant");
+
+    let mut report = MemoryReport::default();
+    for type_name in tracker.live.values() {
+        *report.leaks.entry(type_name).or_insert(0) += 1;
+    }
+    report.double_frees = tracker.double_frees;
+    report
+}
+
+/// Tracks the toolkit allocations of the current thread for as long as it's alive
+///
+/// The fixture version of `track_ffi_memory()`, for tests that set up responses in several
+/// steps or share the setup: dropping it panics if there were leaks or double frees (unless the
+/// test panics already), `finish()` returns the report instead.
+pub struct LeakCheck {
+    // The tracking is per thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl LeakCheck {
+    pub fn start() -> Self {
+        tracking::start();
+        LeakCheck {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Stops the tracking and returns what it found
+    pub fn finish(self) -> MemoryReport {
+        mem::forget(self);
+        stop_tracking()
+    }
+}
+
+impl Drop for LeakCheck {
+    fn drop(&mut self) {
+        let report = stop_tracking();
+        if !report.is_clean() && !thread::panicking() {
+            panic!("FFI memory errors:\n{}", report);
+        }
+    }
+}
+
+/// Like `track_ffi_memory_report()`, but panics if there were leaks or double frees
+pub fn track_ffi_memory<F, R>(body: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let (result, report) = track_ffi_memory_report(body);
+    if !report.is_clean() {
+        panic!("FFI memory errors:\n{}", report);
+    }
+    result
+}
+
+/// Panics if allocations recorded since `enable_alloc_stats(true)` weren't freed yet
+///
+/// Unlike `track_ffi_memory()` this covers all threads, e.g. at the end of a consumer's test
+/// suite. The panic message lists the live allocations by type.
+pub fn assert_no_live_ffi_allocations() {
+    assert!(
+        alloc_stats_enabled(),
+        "allocation statistics are disabled, see `enable_alloc_stats()`"
+    );
+    let live: Vec<_> = alloc_stats()
+        .into_iter()
+        .filter(|stats| stats.live > 0)
+        .map(|stats| format!("{} live allocation(s) of `{}`", stats.live, stats.type_name))
+        .collect();
+    if !live.is_empty() {
+        panic!("FFI allocations weren't freed:\n{}", live.join("\n"));
+    }
+}
+
+/// Fails the test if the body leaks toolkit allocations or frees them twice
+///
+/// ```
+/// use ffi_toolkit::{free_c_str, rust_str_to_c_str, track_ffi_memory};
+///
+/// track_ffi_memory! {
+///     let message = rust_str_to_c_str("hello");
+///     unsafe { free_c_str(message) };
+/// }
+/// ```
+#[macro_export]
+macro_rules! track_ffi_memory {
+    ($($body:tt)*) => {
+        $crate::testing::track_ffi_memory(|| { $($body)* })
+    };
+}
@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use libc::c_void;
+
+type Implementation<Args, Ret> = Box<dyn FnMut(&Args) -> Ret + Send>;
+
+struct Inner<Args, Ret> {
+    calls: Mutex<Vec<Args>>,
+    scripted: Mutex<VecDeque<Ret>>,
+    implementation: Mutex<Implementation<Args, Ret>>,
+    panics: Mutex<Vec<String>>,
+}
+
+/// A C function pointer (plus `user_data`) backed by a Rust closure
+///
+/// All calls are recorded. Return values queued with `returning()` are used first, once they
+/// are exhausted the closure determines the return value. `Args` is a tuple of the callback's
+/// arguments, without the `user_data` pointer.
+///
+/// A panic in the closure doesn't unwind into the caller, it is recorded (see `panics()`) and the
+/// callback returns `Ret::default()`.
+///
+/// ```
+/// use ffi_toolkit::testing::MockCallback;
+///
+/// let progress = MockCallback::new(|&(current, total): &(u64, u64)| current < total);
+/// let callback = progress.user_data_last();
+/// assert!(callback(1, 2, progress.user_data()));
+/// assert_eq!(progress.calls(), vec![(1, 2)]);
+/// ```
+pub struct MockCallback<Args, Ret> {
+    inner: Box<Inner<Args, Ret>>,
+}
+
+impl<Args: Clone, Ret: Default> MockCallback<Args, Ret> {
+    pub fn new<F>(implementation: F) -> Self
+    where
+        F: FnMut(&Args) -> Ret + Send + 'static,
+    {
+        MockCallback {
+            inner: Box::new(Inner {
+                calls: Mutex::new(Vec::new()),
+                scripted: Mutex::new(VecDeque::new()),
+                implementation: Mutex::new(Box::new(implementation)),
+                panics: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// A mock that always returns `Ret::default()` unless return values are scripted
+    pub fn returning_default() -> Self {
+        Self::new(|_| Ret::default())
+    }
+
+    /// Queues return values for the next calls
+    pub fn returning<I: IntoIterator<Item = Ret>>(self, values: I) -> Self {
+        self.inner.scripted.lock().unwrap().extend(values);
+        self
+    }
+
+    /// The `user_data` pointer to pass along with the function pointer
+    ///
+    /// It is valid as long as the mock is alive.
+    pub fn user_data(&self) -> *mut c_void {
+        &*self.inner as *const Inner<Args, Ret> as *mut c_void
+    }
+
+    /// The arguments of all calls so far
+    pub fn calls(&self) -> Vec<Args> {
+        self.inner.calls.lock().unwrap().clone()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.inner.calls.lock().unwrap().len()
+    }
+
+    /// The messages of panics that happened within the closure
+    pub fn panics(&self) -> Vec<String> {
+        self.inner.panics.lock().unwrap().clone()
+    }
+}
+
+impl<Args, Ret: Default> Inner<Args, Ret> {
+    fn invoke(&self, args: Args) -> Ret
+    where
+        Args: Clone,
+    {
+        self.calls.lock().unwrap().push(args.clone());
+        if let Some(value) = self.scripted.lock().unwrap().pop_front() {
+            return value;
+        }
+        let mut implementation = self.implementation.lock().unwrap();
+        match panic::catch_unwind(AssertUnwindSafe(|| implementation(&args))) {
+            Ok(value) => value,
+            Err(payload) => {
+                let message = crate::panic_payload_message(&*payload)
+                    .unwrap_or_else(|| "no unwind information".to_string());
+                self.panics.lock().unwrap().push(message);
+                Ret::default()
+            }
+        }
+    }
+}
+
+macro_rules! mock_callback_arity {
+    ($first:ident, $last:ident; $($arg:ident: $ty:ident),*) => {
+        impl<$($ty: Clone + 'static,)* Ret: Default + 'static> MockCallback<($($ty,)*), Ret> {
+            /// The function pointer for callbacks that take `user_data` as first argument
+            pub fn user_data_first(&self) -> extern "C" fn(*mut c_void $(, $ty)*) -> Ret {
+                $first::<$($ty,)* Ret>
+            }
+
+            /// The function pointer for callbacks that take `user_data` as last argument
+            pub fn user_data_last(&self) -> extern "C" fn($($ty, )* *mut c_void) -> Ret {
+                $last::<$($ty,)* Ret>
+            }
+        }
+
+        extern "C" fn $first<$($ty: Clone,)* Ret: Default>(
+            user_data: *mut c_void $(, $arg: $ty)*
+        ) -> Ret {
+            let inner = unsafe { &*(user_data as *const Inner<($($ty,)*), Ret>) };
+            inner.invoke(($($arg,)*))
+        }
+
+        extern "C" fn $last<$($ty: Clone,)* Ret: Default>(
+            $($arg: $ty, )* user_data: *mut c_void
+        ) -> Ret {
+            let inner = unsafe { &*(user_data as *const Inner<($($ty,)*), Ret>) };
+            inner.invoke(($($arg,)*))
+        }
+    };
+}
+
+mock_callback_arity!(trampoline_first_0, trampoline_last_0;);
+mock_callback_arity!(trampoline_first_1, trampoline_last_1; a: A);
+mock_callback_arity!(trampoline_first_2, trampoline_last_2; a: A, b: B);
+mock_callback_arity!(trampoline_first_3, trampoline_last_3; a: A, b: B, c: C);
+mock_callback_arity!(trampoline_first_4, trampoline_last_4; a: A, b: B, c: C, d: D);
@@ -0,0 +1,25 @@
+//! Utilities for testing exported FFI functions from Rust.
+//!
+//! Only available with the `testing` feature, which is meant to be enabled for
+//! `[dev-dependencies]`.
+
+mod allocs;
+mod caller;
+mod diagnostics;
+mod memory;
+mod mock;
+mod response;
+mod snapshot;
+mod soak;
+
+pub use self::allocs::{count_allocs, CountingAllocator};
+pub use self::caller::CCaller;
+pub use self::diagnostics::assert_compile_fail;
+pub use self::memory::{
+    assert_no_live_ffi_allocations, track_ffi_memory, track_ffi_memory_report, LeakCheck,
+    MemoryReport,
+};
+pub use self::mock::MockCallback;
+pub use self::response::{assert_ffi_error, assert_ffi_ok, ResponseGuard};
+pub use self::snapshot::Snapshot;
+pub use self::soak::{Soak, SoakReport};
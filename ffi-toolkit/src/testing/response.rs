@@ -0,0 +1,150 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{free_raw_ptr, ResponseStatus, StatusCode};
+
+// the response at `ptr`, panics if it's null
+unsafe fn deref_response<'a, T>(ptr: *const T) -> &'a T {
+    ptr.as_ref()
+        .expect("the exported function returned a null response")
+}
+
+// the error message of a response, `None` if it's null
+unsafe fn error_message<T, C>(response: &T) -> Option<String>
+where
+    T: ResponseStatus<C>,
+    C: StatusCode,
+{
+    let message = response.error_msg();
+    if message.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(message).to_string_lossy().into_owned())
+    }
+}
+
+/// Derefs the response at `ptr` and panics with its error message unless the call succeeded
+///
+/// The pointer must be null or point at a valid response.
+pub unsafe fn assert_ffi_ok<'a, T, C>(ptr: *const T) -> &'a T
+where
+    T: ResponseStatus<C>,
+    C: StatusCode + PartialEq + fmt::Debug,
+{
+    let response = deref_response(ptr);
+    let code = response.status_code();
+    if code != C::NO_ERROR {
+        panic!(
+            "the call failed with {:?}: {}",
+            code,
+            error_message(response).unwrap_or_default()
+        );
+    }
+    response
+}
+
+/// Derefs the response at `ptr` and panics unless the call failed with `code` and an error
+/// message containing `message_contains`
+///
+/// The pointer must be null or point at a valid response.
+pub unsafe fn assert_ffi_error<'a, T, C>(ptr: *const T, code: C, message_contains: &str) -> &'a T
+where
+    T: ResponseStatus<C>,
+    C: StatusCode + PartialEq + fmt::Debug,
+{
+    let response = deref_response(ptr);
+    let message = error_message(response);
+    assert_eq!(
+        response.status_code(),
+        code,
+        "the call didn't fail as expected, its error message is {:?}",
+        message
+    );
+    match message {
+        Some(message) if message.contains(message_contains) => {}
+        message => panic!(
+            "the error message {:?} doesn't contain {:?}",
+            message, message_contains
+        ),
+    }
+    response
+}
+
+/// Owns a response returned by an exported function and frees it when it goes out of scope
+///
+/// Responses are freed with `free_raw_ptr()`, as the generated destroy functions do, or with the
+/// destroy function given to `with_destroy()`.
+pub struct ResponseGuard<T> {
+    ptr: *mut T,
+    destroy: unsafe extern "C" fn(*mut T),
+}
+
+unsafe extern "C" fn free_response<T>(ptr: *mut T) {
+    free_raw_ptr(ptr)
+}
+
+impl<T> ResponseGuard<T> {
+    /// Takes ownership of `ptr`, which must have been created with `raw_ptr()`
+    ///
+    /// Panics if the pointer is null.
+    pub fn new(ptr: *mut T) -> Self {
+        Self::with_destroy(ptr, free_response::<T>)
+    }
+
+    /// Takes ownership of `ptr`, which is freed with `destroy`
+    ///
+    /// Panics if the pointer is null.
+    pub fn with_destroy(ptr: *mut T, destroy: unsafe extern "C" fn(*mut T)) -> Self {
+        assert!(
+            !ptr.is_null(),
+            "the exported function returned a null response"
+        );
+        ResponseGuard { ptr, destroy }
+    }
+
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Panics with the error message of the response unless the call succeeded
+    pub fn assert_ok<C>(&self) -> &T
+    where
+        T: ResponseStatus<C>,
+        C: StatusCode + PartialEq + fmt::Debug,
+    {
+        unsafe { assert_ffi_ok(self.ptr) }
+    }
+
+    /// See `assert_ffi_error()`
+    pub fn assert_error<C>(&self, code: C, message_contains: &str) -> &T
+    where
+        T: ResponseStatus<C>,
+        C: StatusCode + PartialEq + fmt::Debug,
+    {
+        unsafe { assert_ffi_error(self.ptr, code, message_contains) }
+    }
+
+    /// The error message of the response, `None` if it's null
+    pub fn error_message<C>(&self) -> Option<String>
+    where
+        T: ResponseStatus<C>,
+        C: StatusCode,
+    {
+        unsafe { error_message(&**self) }
+    }
+}
+
+impl<T> Deref for ResponseGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T> Drop for ResponseGuard<T> {
+    fn drop(&mut self) {
+        unsafe { (self.destroy)(self.ptr) }
+    }
+}
@@ -0,0 +1,95 @@
+use std::ffi::CStr;
+use std::fmt;
+
+/// A stable textual rendering of a response, for comparing against a stored snapshot
+///
+/// The response is rendered with its pretty-printed `Debug` output. Pointer addresses differ
+/// from run to run, so they are replaced by `<ptr N>`, numbered in order of their first
+/// appearance (two fields pointing to the same memory get the same number), null pointers are
+/// rendered as `null`. The contents of C strings are appended with `c_str()`, so that exact
+/// error messages end up in the snapshot as well.
+///
+/// ```
+/// use ffi_toolkit::testing::Snapshot;
+///
+/// #[derive(Debug)]
+/// struct Response {
+///     error_msg: *const libc::c_char,
+/// }
+///
+/// let response = Response { error_msg: b"oops\0".as_ptr() as *const libc::c_char };
+/// let snapshot = unsafe { Snapshot::new(&response).c_str("error_msg", response.error_msg) };
+/// assert_eq!(
+///     snapshot.to_string(),
+///     "Response {\n    error_msg: <ptr 1>,\n}\nerror_msg: \"oops\"\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    rendered: String,
+}
+
+impl Snapshot {
+    pub fn new<T: fmt::Debug>(response: &T) -> Self {
+        Snapshot {
+            rendered: normalize_pointers(&format!("{:#?}", response)) + "\n",
+        }
+    }
+
+    /// Appends the contents of the C string `ptr` under `name`
+    ///
+    /// The pointer needs to be null or point to a nul-terminated string.
+    pub unsafe fn c_str(mut self, name: &str, ptr: *const libc::c_char) -> Self {
+        let contents = if ptr.is_null() {
+            "null".to_string()
+        } else {
+            format!("{:?}", CStr::from_ptr(ptr).to_string_lossy())
+        };
+        self.rendered += &format!("{}: {}\n", name, contents);
+        self
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.rendered)
+    }
+}
+
+// replace every `0x<hex digits>` that isn't part of a longer word
+fn normalize_pointers(rendered: &str) -> String {
+    let mut addresses: Vec<&str> = Vec::new();
+    let mut normalized = String::with_capacity(rendered.len());
+    let mut rest = rendered;
+    while let Some(start) = rest.find("0x") {
+        let digits = rest[start + 2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(rest.len() - start - 2);
+        let end = start + 2 + digits;
+        let standalone = !rest[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        normalized += &rest[..start];
+        let address = &rest[start..end];
+        if standalone && digits > 0 {
+            if address
+                .trim_start_matches("0x")
+                .trim_start_matches('0')
+                .is_empty()
+            {
+                normalized += "null";
+            } else {
+                let index = match addresses.iter().position(|&known| known == address) {
+                    Some(index) => index,
+                    None => {
+                        addresses.push(address);
+                        addresses.len() - 1
+                    }
+                };
+                normalized += &format!("<ptr {}>", index + 1);
+            }
+        } else {
+            normalized += address;
+        }
+        rest = &rest[end..];
+    }
+    normalized + rest
+}
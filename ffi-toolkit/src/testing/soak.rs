@@ -0,0 +1,138 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::alloc::tracking;
+
+type Probe = Box<dyn Fn() -> isize + Send + Sync>;
+
+/// Repeatedly runs a create/destroy cycle on several threads and checks that nothing drifts
+///
+/// A probe is a counter that must be back at its starting value once all cycles are done, e.g.
+/// the number of live handles of a registry. The number of live toolkit allocations is always
+/// probed. Other tests running in parallel change that number too, soak tests should hence run
+/// in a test binary of their own or with `--test-threads=1`.
+///
+/// ```ignore
+/// Soak::new()
+///     .threads(8)
+///     .duration(Duration::from_secs(10))
+///     .probe("open sectors", || open_sector_count() as isize)
+///     .run(|| unsafe { fil_destroy_sector(fil_open_sector(path)) });
+/// ```
+pub struct Soak {
+    threads: usize,
+    duration: Duration,
+    probes: Vec<(&'static str, Probe)>,
+}
+
+/// The result of a soak test
+#[derive(Debug, Default, PartialEq)]
+pub struct SoakReport {
+    /// How often the cycle ran in total
+    pub iterations: u64,
+    /// The probes that didn't return to their starting value, with the difference
+    pub drift: Vec<(&'static str, isize)>,
+}
+
+impl SoakReport {
+    pub fn is_clean(&self) -> bool {
+        self.drift.is_empty()
+    }
+}
+
+impl fmt::Display for SoakReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} iteration(s)", self.iterations)?;
+        for (name, drift) in &self.drift {
+            writeln!(f, "`{}` drifted by {}", name, drift)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Soak {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Soak {
+    /// A soak test on 4 threads for 1 second
+    pub fn new() -> Self {
+        Soak {
+            threads: 4,
+            duration: Duration::from_secs(1),
+            probes: vec![("toolkit allocations", Box::new(tracking::live))],
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Adds a counter that needs to be at the same value before and after the test
+    pub fn probe<F>(mut self, name: &'static str, probe: F) -> Self
+    where
+        F: Fn() -> isize + Send + Sync + 'static,
+    {
+        self.probes.push((name, Box::new(probe)));
+        self
+    }
+
+    /// Runs `cycle` on all threads until the duration is over
+    ///
+    /// Every thread runs the cycle at least once. A panicking cycle fails the test.
+    pub fn run_report<F>(&self, cycle: F) -> SoakReport
+    where
+        F: Fn() + Sync,
+    {
+        let before: Vec<isize> = self.probes.iter().map(|(_, probe)| probe()).collect();
+        let iterations = AtomicU64::new(0);
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| loop {
+                    cycle();
+                    iterations.fetch_add(1, Ordering::Relaxed);
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                });
+            }
+            thread::sleep(self.duration);
+            stop.store(true, Ordering::Relaxed);
+        });
+
+        let drift = self
+            .probes
+            .iter()
+            .zip(before)
+            .map(|((name, probe), before)| (*name, probe() - before))
+            .filter(|&(_, drift)| drift != 0)
+            .collect();
+        SoakReport {
+            iterations: iterations.into_inner(),
+            drift,
+        }
+    }
+
+    /// Like `run_report()`, but panics if a probe drifted
+    pub fn run<F>(&self, cycle: F) -> SoakReport
+    where
+        F: Fn() + Sync,
+    {
+        let report = self.run_report(cycle);
+        if !report.is_clean() {
+            panic!("soak test drifted:\n{}", report);
+        }
+        report
+    }
+}
@@ -0,0 +1,295 @@
+use std::convert::TryFrom;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+const NANOS_PER_MILLI: u32 = 1_000_000;
+
+/// A `Duration` that can cross the FFI boundary, e.g. a timeout
+///
+/// `nanos` is always below one second for values created on the Rust side. Values coming from
+/// C are checked when they are converted.
+#[repr(C)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Default, Hash)]
+pub struct FfiDuration {
+    pub secs: u64,
+    pub nanos: u32,
+}
+
+impl FfiDuration {
+    pub const ZERO: FfiDuration = FfiDuration { secs: 0, nanos: 0 };
+
+    /// Returns `None` if carrying the excess nanoseconds into the seconds overflows
+    pub const fn new(secs: u64, nanos: u32) -> Option<Self> {
+        match secs.checked_add((nanos / NANOS_PER_SEC) as u64) {
+            Some(secs) => Some(FfiDuration {
+                secs,
+                nanos: nanos % NANOS_PER_SEC,
+            }),
+            None => None,
+        }
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        FfiDuration { secs, nanos: 0 }
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        FfiDuration {
+            secs: millis / 1000,
+            nanos: (millis % 1000) as u32 * NANOS_PER_MILLI,
+        }
+    }
+
+    pub const fn from_micros(micros: u64) -> Self {
+        FfiDuration {
+            secs: micros / 1_000_000,
+            nanos: (micros % 1_000_000) as u32 * 1000,
+        }
+    }
+
+    pub const fn from_nanos(nanos: u64) -> Self {
+        FfiDuration {
+            secs: nanos / NANOS_PER_SEC as u64,
+            nanos: (nanos % NANOS_PER_SEC as u64) as u32,
+        }
+    }
+
+    /// Returns the duration in whole milliseconds if it fits into a `u64`
+    pub const fn to_millis(self) -> Option<u64> {
+        match self.secs.checked_mul(1000) {
+            Some(millis) => millis.checked_add((self.nanos / NANOS_PER_MILLI) as u64),
+            None => None,
+        }
+    }
+
+    /// Returns the duration in nanoseconds if it fits into a `u64`
+    pub const fn to_nanos(self) -> Option<u64> {
+        match self.secs.checked_mul(NANOS_PER_SEC as u64) {
+            Some(nanos) => nanos.checked_add(self.nanos as u64),
+            None => None,
+        }
+    }
+
+    /// Returns `None` if `nanos` isn't below one second
+    pub const fn to_duration(self) -> Option<Duration> {
+        if self.nanos < NANOS_PER_SEC {
+            Some(Duration::new(self.secs, self.nanos))
+        } else {
+            None
+        }
+    }
+
+    /// Like `to_duration()`, but carries excess nanoseconds into the seconds, up to
+    /// `Duration::MAX`, for values from C that don't need to be rejected
+    pub fn to_duration_saturating(self) -> Duration {
+        Duration::from_secs(self.secs).saturating_add(Duration::from_nanos(self.nanos as u64))
+    }
+
+    pub fn saturating_add(self, other: FfiDuration) -> Self {
+        self.to_duration_saturating()
+            .saturating_add(other.to_duration_saturating())
+            .into()
+    }
+
+    /// Zero if `other` is longer
+    pub fn saturating_sub(self, other: FfiDuration) -> Self {
+        self.to_duration_saturating()
+            .saturating_sub(other.to_duration_saturating())
+            .into()
+    }
+
+    pub fn saturating_mul(self, factor: u32) -> Self {
+        self.to_duration_saturating().saturating_mul(factor).into()
+    }
+}
+
+impl From<Duration> for FfiDuration {
+    fn from(duration: Duration) -> Self {
+        FfiDuration {
+            secs: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+        }
+    }
+}
+
+/// A point in time that can cross the FFI boundary, as the time since the Unix epoch
+///
+/// Times before the epoch have negative `secs`, `nanos` always counts forward, i.e. one
+/// nanosecond before the epoch is `{ secs: -1, nanos: 999_999_999 }`.
+#[repr(C)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Default, Hash)]
+pub struct FfiTimestamp {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
+impl FfiTimestamp {
+    pub const UNIX_EPOCH: FfiTimestamp = FfiTimestamp { secs: 0, nanos: 0 };
+
+    /// Returns `None` if `nanos` isn't below one second
+    pub const fn new(secs: i64, nanos: u32) -> Option<Self> {
+        if nanos < NANOS_PER_SEC {
+            Some(FfiTimestamp { secs, nanos })
+        } else {
+            None
+        }
+    }
+
+    pub const fn from_unix_secs(secs: i64) -> Self {
+        FfiTimestamp { secs, nanos: 0 }
+    }
+
+    /// Returns `None` if the time can't be represented
+    pub fn from_system_time(time: SystemTime) -> Option<Self> {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => Some(FfiTimestamp {
+                secs: i64::try_from(since.as_secs()).ok()?,
+                nanos: since.subsec_nanos(),
+            }),
+            Err(err) => {
+                let before = err.duration();
+                let mut secs = i64::try_from(before.as_secs()).ok()?.checked_neg()?;
+                let mut nanos = before.subsec_nanos();
+                if nanos > 0 {
+                    secs = secs.checked_sub(1)?;
+                    nanos = NANOS_PER_SEC - nanos;
+                }
+                Some(FfiTimestamp { secs, nanos })
+            }
+        }
+    }
+
+    /// Returns `None` if `nanos` isn't below one second or the platform can't represent the
+    /// time
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        if self.nanos >= NANOS_PER_SEC {
+            return None;
+        }
+        if self.secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(self.secs as u64, self.nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(self.secs.unsigned_abs()))?
+                .checked_add(Duration::from_nanos(self.nanos as u64))
+        }
+    }
+
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now()).expect("the current time is representable")
+    }
+}
+
+/// A point in time on the monotonic clock of `fil_monotonic_now_ns()`, e.g. a call's deadline
+///
+/// Unlike a `FfiTimestamp` it doesn't jump when the wall clock is adjusted, so it's meant for
+/// timeouts and measurements. Arithmetic saturates, instants are never before the clock's zero.
+#[repr(C)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Default, Hash)]
+pub struct FfiInstant {
+    pub monotonic_ns: u64,
+}
+
+impl FfiInstant {
+    pub fn now() -> Self {
+        FfiInstant {
+            monotonic_ns: monotonic_now_ns(),
+        }
+    }
+
+    pub const fn from_monotonic_ns(monotonic_ns: u64) -> Self {
+        FfiInstant { monotonic_ns }
+    }
+
+    /// The `Instant` at the same point in time, as far as both clocks agree
+    pub fn from_instant(instant: Instant) -> Self {
+        let now = Instant::now();
+        let now_ns = monotonic_now_ns();
+        let monotonic_ns = if instant <= now {
+            now_ns.saturating_sub(nanos(now - instant))
+        } else {
+            now_ns.saturating_add(nanos(instant - now))
+        };
+        FfiInstant { monotonic_ns }
+    }
+
+    /// Returns `None` if the `Instant` can't represent the point in time
+    pub fn to_instant(self) -> Option<Instant> {
+        let now = Instant::now();
+        let now_ns = monotonic_now_ns();
+        if self.monotonic_ns <= now_ns {
+            now.checked_sub(Duration::from_nanos(now_ns - self.monotonic_ns))
+        } else {
+            now.checked_add(Duration::from_nanos(self.monotonic_ns - now_ns))
+        }
+    }
+
+    /// The time that passed since the instant, zero for instants in the future
+    pub fn elapsed(self) -> FfiDuration {
+        FfiInstant::now().saturating_duration_since(self)
+    }
+
+    /// Zero if `earlier` is later
+    pub fn saturating_duration_since(self, earlier: FfiInstant) -> FfiDuration {
+        FfiDuration::from_nanos(self.monotonic_ns.saturating_sub(earlier.monotonic_ns))
+    }
+
+    pub fn saturating_add(self, duration: FfiDuration) -> Self {
+        let nanos = duration.to_nanos().unwrap_or(u64::MAX);
+        FfiInstant {
+            monotonic_ns: self.monotonic_ns.saturating_add(nanos),
+        }
+    }
+
+    pub fn saturating_sub(self, duration: FfiDuration) -> Self {
+        let nanos = duration.to_nanos().unwrap_or(u64::MAX);
+        FfiInstant {
+            monotonic_ns: self.monotonic_ns.saturating_sub(nanos),
+        }
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Nanoseconds on the monotonic clock that `fil_monotonic_now_ns()` exports
+///
+/// On Unix this is `CLOCK_MONOTONIC`, so hosts can read the same clock themselves. Elsewhere it
+/// counts from the first call.
+#[cfg(unix)]
+pub fn monotonic_now_ns() -> u64 {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Can't fail for a valid clock and pointer
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    now.tv_sec as u64 * NANOS_PER_SEC as u64 + now.tv_nsec as u64
+}
+
+#[cfg(not(unix))]
+pub fn monotonic_now_ns() -> u64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// The time that passed since `start_ns`, a value of `fil_monotonic_now_ns()` taken by the host
+///
+/// A start in the future (e.g. a garbage value from the host) counts as no time passed.
+pub fn elapsed_since_ns(start_ns: u64) -> Duration {
+    Duration::from_nanos(monotonic_now_ns().saturating_sub(start_ns))
+}
+
+#[no_mangle]
+pub extern "C" fn fil_monotonic_now_ns() -> u64 {
+    monotonic_now_ns()
+}
+
+/// The current instant, as `fil_monotonic_now_ns()`
+#[no_mangle]
+pub extern "C" fn fil_instant_now() -> FfiInstant {
+    FfiInstant::now()
+}
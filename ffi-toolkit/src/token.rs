@@ -0,0 +1,36 @@
+use crate::FfiU128;
+
+// random bytes from the operating system
+fn fill_random(bytes: &mut [u8]) {
+    getrandom::getrandom(bytes).expect("the operating system's random number generator failed");
+}
+
+/// A random, non-zero 64-bit token
+///
+/// Unlike sequential indices, tokens can't be guessed, so code that only knows its own handles
+/// can't forge the ones of other sessions. Zero is never returned, so it can stand for "no
+/// handle".
+///
+/// Panics if the operating system's random number generator fails.
+pub fn random_token_u64() -> u64 {
+    loop {
+        let mut bytes = [0; 8];
+        fill_random(&mut bytes);
+        let token = u64::from_ne_bytes(bytes);
+        if token != 0 {
+            return token;
+        }
+    }
+}
+
+/// A random, non-zero 128-bit token, see `random_token_u64()`
+pub fn random_token_u128() -> FfiU128 {
+    loop {
+        let mut bytes = [0; 16];
+        fill_random(&mut bytes);
+        let token = FfiU128::from_le_bytes(bytes);
+        if !token.is_zero() {
+            return token;
+        }
+    }
+}
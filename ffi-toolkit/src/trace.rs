@@ -0,0 +1,182 @@
+//! Forwarding of `tracing` spans and events to the host.
+//!
+//! `FfiTracingLayer` is a `tracing_subscriber` layer that calls the host's callbacks whenever a
+//! span is created, entered, exited or closed and for every event, so that long-running
+//! operations can be correlated with spans on the host side. Spans are identified by their
+//! `tracing` id, 0 stands for no span. Fields are passed as `key=value` pairs separated by
+//! spaces, the message of an event separately.
+
+use std::fmt::{self, Write};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{prelude::*, Registry};
+
+use crate::logging::FfiLogLevel;
+use crate::truncated_c_string;
+
+/// The callbacks of the host, each of them is optional
+///
+/// The strings are only valid during the call. The callbacks are called on whatever thread the
+/// span or event is on, possibly on several at once.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FfiTracingCallbacks {
+    pub new_span: Option<
+        extern "C" fn(
+            span_id: u64,
+            parent_id: u64,
+            name: *const libc::c_char,
+            fields: *const libc::c_char,
+            user_data: *mut libc::c_void,
+        ),
+    >,
+    pub enter: Option<extern "C" fn(span_id: u64, user_data: *mut libc::c_void)>,
+    pub exit: Option<extern "C" fn(span_id: u64, user_data: *mut libc::c_void)>,
+    pub close: Option<extern "C" fn(span_id: u64, user_data: *mut libc::c_void)>,
+    /// `span_id` is the span the event happened in
+    pub event: Option<
+        extern "C" fn(
+            span_id: u64,
+            level: FfiLogLevel,
+            target: *const libc::c_char,
+            message: *const libc::c_char,
+            fields: *const libc::c_char,
+            user_data: *mut libc::c_void,
+        ),
+    >,
+    pub user_data: *mut libc::c_void,
+}
+
+// The host guarantees that the callbacks and `user_data` can be used from any thread
+unsafe impl Send for FfiTracingCallbacks {}
+unsafe impl Sync for FfiTracingCallbacks {}
+
+/// The layer calling the host's callbacks
+#[derive(Debug)]
+pub struct FfiTracingLayer {
+    callbacks: FfiTracingCallbacks,
+}
+
+impl FfiTracingLayer {
+    pub fn new(callbacks: FfiTracingCallbacks) -> Self {
+        FfiTracingLayer { callbacks }
+    }
+}
+
+impl<S> Layer<S> for FfiTracingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(new_span) = self.callbacks.new_span {
+            let parent_id = if let Some(parent) = attrs.parent() {
+                parent.into_u64()
+            } else if attrs.is_contextual() {
+                ctx.current_span().id().map_or(0, Id::into_u64)
+            } else {
+                0
+            };
+            let mut fields = FieldString::default();
+            attrs.record(&mut fields);
+            let name = truncated_c_string(attrs.metadata().name().to_string());
+            let fields = truncated_c_string(fields.fields);
+            new_span(
+                id.into_u64(),
+                parent_id,
+                name.as_ptr(),
+                fields.as_ptr(),
+                self.callbacks.user_data,
+            );
+        }
+    }
+
+    fn on_enter(&self, id: &Id, _ctx: Context<'_, S>) {
+        if let Some(enter) = self.callbacks.enter {
+            enter(id.into_u64(), self.callbacks.user_data);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, _ctx: Context<'_, S>) {
+        if let Some(exit) = self.callbacks.exit {
+            exit(id.into_u64(), self.callbacks.user_data);
+        }
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        if let Some(close) = self.callbacks.close {
+            close(id.into_u64(), self.callbacks.user_data);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if let Some(on_event) = self.callbacks.event {
+            let span_id = ctx.event_span(event).map_or(0, |span| span.id().into_u64());
+            let mut fields = FieldString::default();
+            event.record(&mut fields);
+            let metadata = event.metadata();
+            let target = truncated_c_string(metadata.target().to_string());
+            let message = truncated_c_string(fields.message);
+            let fields = truncated_c_string(fields.fields);
+            on_event(
+                span_id,
+                level(metadata.level()),
+                target.as_ptr(),
+                message.as_ptr(),
+                fields.as_ptr(),
+                self.callbacks.user_data,
+            );
+        }
+    }
+}
+
+fn level(level: &tracing::Level) -> FfiLogLevel {
+    match *level {
+        tracing::Level::ERROR => FfiLogLevel::Error,
+        tracing::Level::WARN => FfiLogLevel::Warn,
+        tracing::Level::INFO => FfiLogLevel::Info,
+        tracing::Level::DEBUG => FfiLogLevel::Debug,
+        tracing::Level::TRACE => FfiLogLevel::Trace,
+    }
+}
+
+// The fields as `key=value` pairs, the `message` field separately
+#[derive(Default)]
+struct FieldString {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldString {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Makes a subscriber with only the `FfiTracingLayer` the global default
+///
+/// Returns false if a global default was set before. Hosts that want to combine the layer with
+/// others build their own subscriber with `FfiTracingLayer::new()`.
+#[no_mangle]
+pub extern "C" fn fil_init_tracing(callbacks: FfiTracingCallbacks) -> bool {
+    let subscriber = Registry::default().with(FfiTracingLayer::new(callbacks));
+    tracing::subscriber::set_global_default(subscriber).is_ok()
+}
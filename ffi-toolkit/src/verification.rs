@@ -0,0 +1,74 @@
+//! Kani proof harnesses for the pointer helpers every consumer relies on.
+//!
+//! ```console
+//! $ cargo kani -p ffi-toolkit
+//! ```
+
+use std::ptr;
+
+use crate::{c_str_to_rust_str, cast_const, free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str};
+
+const MAX_STR_LEN: usize = 4;
+
+#[kani::proof]
+fn c_str_to_rust_str_null_is_empty() {
+    let converted = unsafe { c_str_to_rust_str(ptr::null()) };
+    assert!(converted.is_empty());
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn c_str_to_rust_str_stops_at_nul() {
+    let mut bytes: [u8; MAX_STR_LEN + 1] = kani::any();
+    bytes[MAX_STR_LEN] = 0;
+    let len = bytes.iter().position(|&byte| byte == 0).unwrap();
+
+    let converted = unsafe { c_str_to_rust_str(bytes.as_ptr() as *const libc::c_char) };
+    if let Ok(expected) = std::str::from_utf8(&bytes[..len]) {
+        assert_eq!(converted, expected);
+    }
+    assert!(!converted.contains('\0'));
+}
+
+#[kani::proof]
+fn free_c_str_null_is_noop() {
+    unsafe { free_c_str(ptr::null_mut()) };
+}
+
+#[kani::proof]
+#[kani::unwind(6)]
+fn c_str_round_trip() {
+    let bytes: [u8; MAX_STR_LEN] = kani::any();
+    kani::assume(bytes.iter().all(|&byte| byte != 0 && byte.is_ascii()));
+    let string = std::str::from_utf8(&bytes).unwrap();
+
+    let ptr = rust_str_to_c_str(string);
+    assert!(!ptr.is_null());
+    unsafe {
+        assert_eq!(c_str_to_rust_str(ptr), string);
+        free_c_str(ptr);
+    }
+}
+
+#[kani::proof]
+fn cast_const_keeps_address() {
+    let value: u64 = kani::any();
+    let ptr = raw_ptr(value);
+    unsafe {
+        let reference = cast_const(ptr);
+        assert!(ptr::eq(reference, ptr));
+        assert_eq!(*reference, value);
+        free_raw_ptr(ptr);
+    }
+}
+
+#[kani::proof]
+#[kani::should_panic]
+fn cast_const_rejects_null() {
+    let _ = unsafe { cast_const::<u64>(ptr::null_mut()) };
+}
+
+#[kani::proof]
+fn free_raw_ptr_null_is_noop() {
+    unsafe { free_raw_ptr::<u64>(ptr::null_mut()) };
+}
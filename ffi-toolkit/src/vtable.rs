@@ -0,0 +1,69 @@
+/// The first field of every vtable declared with `ffi_vtable!`
+///
+/// Hosts can read it before knowing the concrete vtable type, to check that the library
+/// provides the version (and at least the number of functions) they were built against.
+#[repr(C)]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct FfiVTableHeader {
+    pub version: u32,
+    /// The size of the whole vtable in bytes
+    pub size: usize,
+}
+
+/// Gathers exported functions into a versioned `#[repr(C)]` vtable
+///
+/// The vtable is returned by a single `#[no_mangle]` function, so plugin hosts need only one
+/// `dlsym` call to get the full set of functions. Every function is assigned to a field of the
+/// declared type, hence a signature mismatch is a compile error.
+///
+/// ```
+/// use ffi_toolkit::ffi_vtable;
+///
+/// extern "C" fn add(a: u64, b: u64) -> u64 {
+///     a + b
+/// }
+///
+/// ffi_vtable! {
+///     pub struct MathVTable: version 1, export math_vtable {
+///         add: extern "C" fn(u64, u64) -> u64 = add,
+///     }
+/// }
+///
+/// let vtable = unsafe { &*math_vtable() };
+/// assert_eq!(vtable.header.version, 1);
+/// assert_eq!((vtable.add)(1, 2), 3);
+/// ```
+#[macro_export]
+macro_rules! ffi_vtable {
+    {
+        $(#[$meta:meta])*
+        pub struct $name:ident: version $version:expr, export $export:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $field_type:ty = $function:path,
+            )*
+        }
+    } => {
+        $(#[$meta])*
+        #[repr(C)]
+        pub struct $name {
+            pub header: $crate::FfiVTableHeader,
+            $(
+                $(#[$field_meta])*
+                pub $field: $field_type,
+            )*
+        }
+
+        #[no_mangle]
+        pub extern "C" fn $export() -> *const $name {
+            static VTABLE: $name = $name {
+                header: $crate::FfiVTableHeader {
+                    version: $version,
+                    size: ::std::mem::size_of::<$name>(),
+                },
+                $( $field: $function, )*
+            };
+            &VTABLE
+        }
+    }
+}
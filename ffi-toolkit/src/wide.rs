@@ -0,0 +1,77 @@
+//! UTF-16 strings, as Windows hosts pass paths and most other strings.
+//!
+//! The functions mirror the ones for C strings (`c_str_to_rust_str()`, `rust_str_to_c_str()`,
+//! `free_c_str()`), the strings are nul-terminated `u16` arrays (`wchar_t` on Windows). They are
+//! available on all platforms, only the conversion to paths differs.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::slice;
+use std::string::FromUtf16Error;
+
+use crate::alloc;
+
+// the number of code units before the nul
+unsafe fn wstr_len(x: *const u16) -> usize {
+    let mut len = 0;
+    while *x.add(len) != 0 {
+        len += 1;
+    }
+    len
+}
+
+// the code units before the nul, none for null
+unsafe fn wstr_units<'a>(x: *const u16) -> &'a [u16] {
+    if x.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(x, wstr_len(x))
+    }
+}
+
+// converts a UTF-16 string to a Rust string, unpaired surrogates are replaced; null is the empty
+// string
+pub unsafe fn wstr_to_rust_string(x: *const u16) -> String {
+    String::from_utf16_lossy(wstr_units(x))
+}
+
+// like `wstr_to_rust_string()`, unpaired surrogates are an error
+pub unsafe fn try_wstr_to_rust_string(x: *const u16) -> Result<String, FromUtf16Error> {
+    String::from_utf16(wstr_units(x))
+}
+
+// produce a UTF-16 string from a Rust string, free it with `free_w_str()`
+pub fn rust_string_to_wstr<T: Into<String>>(s: T) -> *mut u16 {
+    let s = s.into();
+    assert!(!s.contains('\0'), "the string contains a nul character");
+    let wide: Vec<u16> = s.encode_utf16().chain(Some(0)).collect();
+    alloc::wide_str_into_raw(wide)
+}
+
+// consume a UTF-16 string-pointer created by `rust_string_to_wstr()` and free its memory
+pub unsafe fn free_w_str(ptr: *mut u16) {
+    if !ptr.is_null() {
+        alloc::free_wide_str(ptr, wstr_len(ptr) + 1);
+    }
+}
+
+// converts a UTF-16 string to an OsString, on Windows keeping unpaired surrogates; null is the
+// empty string
+#[cfg(windows)]
+pub unsafe fn wstr_to_os_string(x: *const u16) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+
+    OsString::from_wide(wstr_units(x))
+}
+
+// converts a UTF-16 string to an OsString, unpaired surrogates are replaced as there is no way
+// to represent them outside of Windows
+#[cfg(not(windows))]
+pub unsafe fn wstr_to_os_string(x: *const u16) -> OsString {
+    OsString::from(wstr_to_rust_string(x))
+}
+
+// converts a UTF-16 string to a PathBuf, see `wstr_to_os_string()`
+pub unsafe fn wstr_to_pathbuf(x: *const u16) -> PathBuf {
+    PathBuf::from(wstr_to_os_string(x))
+}
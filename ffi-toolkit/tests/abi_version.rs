@@ -0,0 +1,54 @@
+use std::ffi::CStr;
+
+use ffi_toolkit::{ffi_abi_version, fil_last_error_message, AbiHash, FCPResponseStatus};
+
+#[repr(C)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub proof_ptr: *const u8,
+    pub proof_len: libc::size_t,
+}
+
+#[repr(C)]
+pub struct VerifyResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub is_valid: bool,
+}
+
+ffi_abi_version! {
+    version = 1;
+    SealResponse { status_code, error_msg, proof_ptr, proof_len },
+    VerifyResponse,
+}
+
+#[test]
+fn the_check_accepts_the_library_hash_only() {
+    assert_eq!(fil_abi_version(), FIL_ABI_VERSION);
+    assert!(fil_abi_check(FIL_ABI_VERSION));
+    assert!(!fil_abi_check(0x1234));
+    let message = unsafe { CStr::from_ptr(fil_last_error_message()) };
+    assert_eq!(
+        message.to_str().unwrap(),
+        format!(
+            "the library's ABI version is {:016x}, the bindings expect 0000000000001234",
+            FIL_ABI_VERSION
+        )
+    );
+}
+
+#[test]
+fn versions_and_layouts_change_the_hash() {
+    let hash = |version, size, offset| {
+        AbiHash::new(version)
+            .with_type("SealResponse", size, 8)
+            .with_field("proof_len", offset)
+            .value()
+    };
+    let base = hash(1, 32, 24);
+    assert_eq!(hash(1, 32, 24), base);
+    assert_ne!(hash(2, 32, 24), base);
+    assert_ne!(hash(1, 40, 24), base);
+    assert_ne!(hash(1, 32, 16), base);
+}
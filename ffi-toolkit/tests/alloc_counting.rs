@@ -0,0 +1,38 @@
+#![cfg(feature = "testing")]
+
+use ffi_toolkit::testing::{count_allocs, CountingAllocator};
+use ffi_toolkit::{assert_max_allocs, free_c_str, rust_str_to_c_str, FfiU128};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn counts_allocations_of_the_body() {
+    let (_, allocations) = count_allocs(|| {
+        let message = rust_str_to_c_str("hello");
+        unsafe { free_c_str(message) };
+    });
+    assert!(allocations >= 1, "{} allocations", allocations);
+}
+
+#[test]
+fn zero_allocation_path() {
+    let value = assert_max_allocs!(0, FfiU128::from_u128(u128::MAX).to_u128());
+    assert_eq!(value, u128::MAX);
+}
+
+#[test]
+#[should_panic(expected = "expected at most 0 allocation(s), but there were 1")]
+fn too_many_allocations() {
+    assert_max_allocs!(0, Box::new(5));
+}
+
+#[test]
+fn nested_counts_add_up() {
+    let (_, outer) = count_allocs(|| {
+        let (_, inner) = count_allocs(|| Box::new(1));
+        assert_eq!(inner, 1);
+        Box::new(2)
+    });
+    assert_eq!(outer, 2);
+}
@@ -0,0 +1,102 @@
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    alloc_stats, enable_alloc_stats, fil_alloc_stats, fil_destroy_alloc_stats_response, free_c_str,
+    free_raw_ptr, live_ffi_allocations, raw_ptr, rust_str_to_c_str, AllocTypeStats,
+    FCPResponseStatus,
+};
+
+// The statistics are global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn stats_of(type_name: &str) -> Option<AllocTypeStats> {
+    alloc_stats()
+        .into_iter()
+        .find(|stats| stats.type_name.ends_with(type_name))
+}
+
+#[test]
+fn counts_live_allocations_by_type() {
+    let _serial = serial();
+    enable_alloc_stats(true);
+    let responses: Vec<_> = (0..3).map(|_| raw_ptr(SealResponse::default())).collect();
+    let message = rust_str_to_c_str("sealed");
+
+    let seal = stats_of("SealResponse").unwrap();
+    assert_eq!((seal.live, seal.allocated), (3, 3));
+    assert_eq!(stats_of("c_char").unwrap().live, 1);
+    assert_eq!(live_ffi_allocations(), 4);
+
+    unsafe {
+        free_raw_ptr(responses[0]);
+        free_c_str(message);
+    }
+    let seal = stats_of("SealResponse").unwrap();
+    assert_eq!((seal.live, seal.allocated), (2, 3));
+    assert_eq!(stats_of("c_char").unwrap().live, 0);
+
+    for response in &responses[1..] {
+        unsafe { free_raw_ptr(*response) };
+    }
+    assert_eq!(live_ffi_allocations(), 0);
+    #[cfg(feature = "testing")]
+    ffi_toolkit::testing::assert_no_live_ffi_allocations();
+    enable_alloc_stats(false);
+    assert!(alloc_stats().is_empty());
+}
+
+#[test]
+fn over_ffi() {
+    let _serial = serial();
+    enable_alloc_stats(true);
+    let leaked = raw_ptr(SealResponse::default());
+
+    let stats = fil_alloc_stats();
+    unsafe {
+        assert!((*stats).enabled);
+        assert_eq!((*stats).live_total, 1);
+        let types = std::slice::from_raw_parts((*stats).types_ptr, (*stats).types_len);
+        assert_eq!(types.len(), 1);
+        let type_name = CStr::from_ptr(types[0].type_name).to_str().unwrap();
+        assert!(type_name.ends_with("SealResponse"));
+        assert_eq!((types[0].live, types[0].allocated), (1, 1));
+        fil_destroy_alloc_stats_response(stats);
+        free_raw_ptr(leaked);
+    }
+    enable_alloc_stats(false);
+
+    let stats = fil_alloc_stats();
+    unsafe {
+        assert!(!(*stats).enabled);
+        assert_eq!((*stats).types_len, 0);
+        fil_destroy_alloc_stats_response(stats);
+    }
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn leaks_fail_the_assertion() {
+    let _serial = serial();
+    enable_alloc_stats(true);
+    let leaked = raw_ptr(SealResponse::default());
+    let result = std::panic::catch_unwind(ffi_toolkit::testing::assert_no_live_ffi_allocations);
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("1 live allocation(s) of `alloc_stats::SealResponse`"));
+    unsafe { free_raw_ptr(leaked) };
+    enable_alloc_stats(false);
+}
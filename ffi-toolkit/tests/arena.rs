@@ -0,0 +1,79 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+use std::slice;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    arena_slice, arena_str, free_raw_ptr, raw_ptr, track_ffi_memory, FCPResponseStatus, FfiArena,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct ListPiecesResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(skip)]
+    pub cids_ptr: *const *const libc::c_char,
+    pub cids_len: libc::size_t,
+    #[ffi_drop(skip)]
+    pub sizes_ptr: *const u64,
+    pub sizes_len: libc::size_t,
+    #[ffi_drop(arena)]
+    pub arena: *mut FfiArena,
+}
+
+fn list_pieces(count: u64) -> ListPiecesResponse {
+    let mut arena = FfiArena::new();
+    let cids: Vec<_> = (0..count)
+        .map(|i| arena_str(&mut arena, &format!("baga6ea4seaq{}", i)))
+        .collect();
+    let sizes: Vec<u64> = (0..count).map(|i| 2048 << (i % 8)).collect();
+    ListPiecesResponse {
+        cids_ptr: arena_slice(&mut arena, &cids),
+        cids_len: cids.len(),
+        sizes_ptr: arena_slice(&mut arena, &sizes),
+        sizes_len: sizes.len(),
+        arena: arena.into_raw(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn strings_and_arrays_live_in_the_arena() {
+    let response = list_pieces(500);
+    unsafe {
+        let cids = slice::from_raw_parts(response.cids_ptr, response.cids_len);
+        let sizes = slice::from_raw_parts(response.sizes_ptr, response.sizes_len);
+        assert_eq!(CStr::from_ptr(cids[0]).to_str().unwrap(), "baga6ea4seaq0");
+        assert_eq!(
+            CStr::from_ptr(cids[499]).to_str().unwrap(),
+            "baga6ea4seaq499"
+        );
+        assert_eq!(sizes[3], 2048 << 3);
+        assert_eq!(response.sizes_ptr as usize % std::mem::align_of::<u64>(), 0);
+        // A few chunks rather than an allocation per string
+        assert!((*response.arena).allocated_bytes() < 64 * 1024);
+    }
+}
+
+#[test]
+fn the_arena_is_freed_with_the_response() {
+    track_ffi_memory! {
+        let response = raw_ptr(list_pieces(10));
+        unsafe { free_raw_ptr(response) };
+    };
+}
+
+#[test]
+fn strings_are_truncated_at_nul_bytes() {
+    let mut arena = FfiArena::new();
+    let string = arena_str(&mut arena, "sector\0id");
+    let empty = arena_str(&mut arena, "");
+    let no_sizes = arena_slice::<u64>(&mut arena, &[]);
+    unsafe {
+        assert_eq!(CStr::from_ptr(string).to_str().unwrap(), "sector");
+        assert_eq!(CStr::from_ptr(empty).to_str().unwrap(), "");
+    }
+    assert!(!no_sizes.is_null());
+}
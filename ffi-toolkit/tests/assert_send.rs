@@ -0,0 +1,35 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+use ffi_toolkit::{AssertSend, AssertSync};
+
+#[test]
+fn values_cross_threads() {
+    let user_data = Rc::new(Cell::new(1));
+    let mut sent = unsafe { AssertSend::new(Rc::clone(&user_data)) };
+    sent.get_mut().set(2);
+    let sent = thread::spawn(move || {
+        sent.get().set(3);
+        sent
+    })
+    .join()
+    .unwrap();
+    assert_eq!(user_data.get(), 3);
+    drop(sent.into_inner());
+    assert_eq!(Rc::strong_count(&user_data), 1);
+
+    let shared = Arc::new(unsafe { AssertSync::new(Cell::new(0)) });
+    let other = Arc::clone(&shared);
+    thread::spawn(move || other.get().set(4)).join().unwrap();
+    assert_eq!(shared.get().get(), 4);
+}
+
+#[test]
+fn bound_values_are_checked_in_debug_builds() {
+    let bound = unsafe { AssertSend::bound(5) };
+    assert_eq!(*bound.get(), 5);
+    let used_elsewhere = thread::spawn(move || bound.into_inner()).join();
+    assert_eq!(used_elsewhere.is_err(), cfg!(debug_assertions));
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+
+use drop_struct_macro_derive::{DropStructMacro, FFIResponse};
+use ffi_toolkit::{free_c_str, rust_str_to_c_str, track_ffi_memory, FCPResponseStatus, FfiBatch};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct UnsealedSector {
+    pub sector_id: u64,
+    pub path: *const libc::c_char,
+}
+
+impl Default for UnsealedSector {
+    fn default() -> Self {
+        UnsealedSector {
+            sector_id: 0,
+            path: std::ptr::null(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct UnsealBatchResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sectors: FfiBatch<UnsealedSector>,
+}
+
+fn unsealed(sector_id: u64) -> UnsealedSector {
+    UnsealedSector {
+        sector_id,
+        path: rust_str_to_c_str(format!("/unsealed/s-{}", sector_id)),
+    }
+}
+
+#[test]
+fn items_report_their_own_status() {
+    track_ffi_memory! {
+        let mut builder = FfiBatch::builder();
+        builder
+            .push_ok(unsealed(1))
+            .push_error(&(FCPResponseStatus::FCPReceiverError, "sector 2 is corrupt".to_string()))
+            .push_ok(unsealed(3));
+        let response = UnsealBatchResponse {
+            sectors: builder.build(),
+            ..Default::default()
+        };
+
+        let sectors = &response.sectors;
+        assert_eq!(sectors.len(), 3);
+        assert_eq!(sectors.failed(), 1);
+        let items = sectors.items();
+        assert!(items[0].is_ok() && items[2].is_ok());
+        assert_eq!(items[1].status_code, FCPResponseStatus::FCPReceiverError);
+        let message = unsafe { CStr::from_ptr(items[1].error_msg) };
+        assert_eq!(message.to_str(), Ok("sector 2 is corrupt"));
+        let payloads = sectors.payloads();
+        assert_eq!(payloads[2].sector_id, 3);
+        assert!(payloads[1].path.is_null());
+        drop(response);
+    };
+}
+
+#[test]
+fn batches_are_collected_from_results() {
+    track_ffi_memory! {
+        let batch: FfiBatch<u64> = (0..4u64)
+            .map(|id| {
+                if id % 2 == 0 {
+                    Ok(id * 10)
+                } else {
+                    Err((FCPResponseStatus::FCPCallerError, format!("odd sector {}", id)))
+                }
+            })
+            .collect();
+        assert_eq!(batch.payloads(), [0, 0, 20, 0]);
+        assert_eq!(batch.failed(), 2);
+    };
+}
+
+#[test]
+fn default_batches_are_empty() {
+    let response = UnsealBatchResponse::default();
+    assert!(response.sectors.is_empty());
+    assert!(response.sectors.items().is_empty());
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "bigint")]
+
+use std::ptr;
+
+use ffi_toolkit::{
+    biguint_from_le_bytes, biguint_from_le_bytes_padded, biguint_from_raw_le, biguint_to_le_bytes,
+    biguint_to_le_bytes_padded,
+};
+use num_bigint::BigUint;
+
+#[test]
+fn canonical_round_trip() {
+    let values = [
+        BigUint::default(),
+        BigUint::from(1u32),
+        BigUint::from(256u32),
+        BigUint::from(u128::MAX) * BigUint::from(3u32),
+    ];
+    for value in values.iter() {
+        let bytes = biguint_to_le_bytes(value);
+        assert_ne!(bytes.last(), Some(&0));
+        assert_eq!(&biguint_from_le_bytes(&bytes).unwrap(), value);
+    }
+    assert!(biguint_to_le_bytes(&BigUint::default()).is_empty());
+    assert_eq!(biguint_to_le_bytes(&BigUint::from(256u32)), vec![0, 1]);
+}
+
+#[test]
+fn non_canonical_is_rejected() {
+    assert!(biguint_from_le_bytes(&[0]).is_err());
+    assert_eq!(
+        biguint_from_le_bytes(&[1, 0]).unwrap_err(),
+        "2 byte integer isn't canonical, it has trailing zero bytes"
+    );
+}
+
+#[test]
+fn padded() {
+    let value = BigUint::from(0x0102u32);
+    let bytes = biguint_to_le_bytes_padded(&value, 32).unwrap();
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(&bytes[..3], &[2, 1, 0]);
+    assert_eq!(biguint_from_le_bytes_padded(&bytes, 32).unwrap(), value);
+    assert!(biguint_from_le_bytes_padded(&bytes, 31).is_err());
+    assert_eq!(biguint_to_le_bytes_padded(&value, 1), None);
+}
+
+#[test]
+fn raw() {
+    let bytes = [5u8, 1];
+    let value = unsafe { biguint_from_raw_le(bytes.as_ptr(), bytes.len()) }.unwrap();
+    assert_eq!(value, BigUint::from(261u32));
+    assert_eq!(
+        unsafe { biguint_from_raw_le(ptr::null(), 0) }.unwrap(),
+        BigUint::default()
+    );
+    assert!(unsafe { biguint_from_raw_le(ptr::null(), 1) }.is_err());
+}
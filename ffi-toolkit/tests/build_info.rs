@@ -0,0 +1,46 @@
+use std::ffi::CStr;
+
+use ffi_toolkit::{build_info, fil_toolkit_build_info, FIL_FEATURE_POISON, FIL_FEATURE_TRACKING};
+
+#[test]
+fn versions_are_captured() {
+    let info = build_info();
+    assert_eq!(info.crate_version(), env!("CARGO_PKG_VERSION"));
+    assert!(info.rustc_version().starts_with("rustc "));
+    assert!(!info.target().is_empty());
+    assert!(!info.git_commit().is_empty());
+    assert_eq!(info.profile() == "debug", cfg!(debug_assertions));
+    assert_eq!(info.debug_assertions(), cfg!(debug_assertions));
+    assert!(!info.host_allocator());
+}
+
+#[test]
+fn features_match_the_build() {
+    let info = build_info();
+    assert_eq!(
+        info.has_feature(FIL_FEATURE_TRACKING),
+        cfg!(feature = "testing")
+    );
+    assert_eq!(
+        info.has_feature(FIL_FEATURE_POISON),
+        cfg!(feature = "poison")
+    );
+    assert_eq!(
+        info.feature_names().contains(&"poison"),
+        cfg!(feature = "poison")
+    );
+    assert_eq!(fil_toolkit_build_info().features(), info.features());
+}
+
+#[test]
+fn c_sees_static_strings() {
+    #[repr(C)]
+    struct CBuildInfo {
+        crate_version: *const libc::c_char,
+    }
+
+    let info = fil_toolkit_build_info();
+    let c_info = unsafe { &*(&info as *const _ as *const CBuildInfo) };
+    let version = unsafe { CStr::from_ptr(c_info.crate_version) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+}
@@ -0,0 +1,41 @@
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{fil_free_bytes, free_c_str, rust_str_to_c_str, FfiBytes};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ProofResponse {
+    pub error_msg: *const libc::c_char,
+    pub proof: FfiBytes,
+}
+
+#[test]
+fn from_vec_takes_over_the_memory() {
+    let mut vec = Vec::with_capacity(64);
+    vec.extend_from_slice(b"proof");
+    let ptr = vec.as_ptr();
+    let bytes = FfiBytes::from_vec(vec);
+    assert_eq!(bytes.ptr, ptr);
+    assert_eq!(bytes.cap, 64);
+    assert_eq!(bytes.as_slice(), b"proof");
+
+    let vec = bytes.into_vec();
+    assert_eq!(vec.as_ptr(), ptr);
+    assert_eq!(vec.capacity(), 64);
+    assert_eq!(vec, b"proof");
+}
+
+#[test]
+fn free_bytes() {
+    fil_free_bytes(FfiBytes::from_vec(vec![1, 2, 3]));
+    fil_free_bytes(FfiBytes::default());
+}
+
+#[test]
+fn field_of_a_response() {
+    let response = ProofResponse {
+        error_msg: rust_str_to_c_str("none"),
+        proof: FfiBytes::from_vec(vec![7; 192]),
+    };
+    assert_eq!(response.proof.len(), 192);
+    drop(response);
+}
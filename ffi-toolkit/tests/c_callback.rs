@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use ffi_toolkit::CCallback;
+
+extern "C" fn add(amount: u64, user_data: *mut libc::c_void) -> u64 {
+    let total = unsafe { &*(user_data as *const AtomicU64) };
+    total.fetch_add(amount, Ordering::SeqCst) + amount
+}
+
+extern "C" fn ping(user_data: *mut libc::c_void) {
+    add(1, user_data);
+}
+
+extern "C" fn sum(a: u8, b: u16, c: u32, user_data: *mut libc::c_void) -> u64 {
+    assert!(user_data.is_null());
+    u64::from(a) + u64::from(b) + u64::from(c)
+}
+
+fn user_data(total: &AtomicU64) -> *mut libc::c_void {
+    total as *const _ as *mut libc::c_void
+}
+
+#[test]
+fn call() {
+    let total = AtomicU64::new(0);
+    let callback: CCallback<(u64,), u64> =
+        unsafe { CCallback::new(Some(add as _), user_data(&total)) };
+    assert!(!callback.is_null());
+    assert_eq!(callback.call((2,)), Some(2));
+    assert_eq!(callback.call((3,)), Some(5));
+    assert_eq!(callback.user_data(), user_data(&total));
+
+    let callback: CCallback<(), ()> = unsafe { CCallback::new(Some(ping as _), user_data(&total)) };
+    assert_eq!(callback.call(()), Some(()));
+    assert_eq!(total.load(Ordering::SeqCst), 6);
+
+    let callback: CCallback<(u8, u16, u32), u64> =
+        unsafe { CCallback::new(Some(sum as _), std::ptr::null_mut()) };
+    assert_eq!(callback.call((1, 2, 3)), Some(6));
+}
+
+#[test]
+fn null_function() {
+    let callback: CCallback<(u64,), u64> = unsafe { CCallback::new(None, std::ptr::null_mut()) };
+    assert!(callback.is_null());
+    assert_eq!(callback.call((1,)), None);
+}
+
+#[test]
+fn from_other_threads() {
+    let total = AtomicU64::new(0);
+    let callback =
+        unsafe { CCallback::<(u64,), u64>::new(Some(add as _), user_data(&total)).assume_send() };
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(move || callback.call((1,)));
+        }
+    });
+    assert_eq!(total.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+use std::slice;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::testing::CCaller;
+use ffi_toolkit::{c_str_to_rust_str, free_c_str, raw_ptr, rust_str_to_c_str, FCPResponseStatus};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct EchoResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub message: *const libc::c_char,
+    pub checksum: u64,
+}
+
+unsafe extern "C" fn echo(
+    message: *const libc::c_char,
+    data_ptr: *const u8,
+    data_len: libc::size_t,
+    out_ptr: *mut u8,
+    out_len: libc::size_t,
+) -> *mut EchoResponse {
+    let data = slice::from_raw_parts(data_ptr, data_len);
+    let out = slice::from_raw_parts_mut(out_ptr, out_len);
+    out.copy_from_slice(&data[..out_len]);
+    raw_ptr(EchoResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: ptr::null(),
+        message: rust_str_to_c_str(c_str_to_rust_str(message)),
+        checksum: data.iter().map(|&byte| u64::from(byte)).sum(),
+    })
+}
+
+unsafe extern "C" fn destroy_echo_response(ptr: *mut EchoResponse) {
+    let _ = Box::from_raw(ptr);
+}
+
+#[test]
+fn simulated_caller_round_trip() {
+    let mut caller = CCaller::new();
+    let message = caller.c_str("hello");
+    let (data_ptr, data_len) = caller.buffer(&[1, 2, 3]);
+    let (out_ptr, out_len) = caller.buffer_mut(2);
+
+    let response = caller.call(
+        || unsafe { echo(message, data_ptr, data_len, out_ptr, out_len) },
+        destroy_echo_response,
+    );
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).checksum, 6);
+        assert_eq!(
+            caller.read_c_str((*response).message),
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(caller.read_buffer(out_ptr, out_len), Some(vec![1, 2]));
+        assert_eq!(caller.read_c_str((*response).error_msg), None);
+    }
+}
+
+#[test]
+fn forged_strings() {
+    let mut caller = CCaller::new();
+    let invalid_utf8 = caller.c_bytes(&[b'a', 0xff, b'b']);
+    let interior_nul = caller.c_bytes(b"ab\0cd");
+    unsafe {
+        assert_eq!(c_str_to_rust_str(invalid_utf8), "a\u{fffd}b");
+        assert_eq!(c_str_to_rust_str(interior_nul), "ab");
+    }
+}
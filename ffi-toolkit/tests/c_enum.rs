@@ -0,0 +1,85 @@
+use std::convert::TryFrom;
+use std::ffi::CStr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_result, free_raw_ptr, try_from_c_enum, FCPResponseStatus, InvalidDiscriminant,
+};
+
+try_from_c_enum! {
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    pub enum RegisteredPoStProof {
+        StackedDrgWinning2KiBV1,
+        StackedDrgWinning32GiBV1 = 4,
+        StackedDrgWindow2KiBV1,
+    }
+}
+
+#[test]
+fn variants_are_converted() {
+    assert_eq!(
+        RegisteredPoStProof::try_from(0),
+        Ok(RegisteredPoStProof::StackedDrgWinning2KiBV1)
+    );
+    assert_eq!(
+        RegisteredPoStProof::try_from(4),
+        Ok(RegisteredPoStProof::StackedDrgWinning32GiBV1)
+    );
+    // Implicit discriminants count on from the previous one
+    assert_eq!(
+        RegisteredPoStProof::try_from(5),
+        Ok(RegisteredPoStProof::StackedDrgWindow2KiBV1)
+    );
+}
+
+#[test]
+fn out_of_range_values_are_errors() {
+    for value in [1, 6, u64::MAX] {
+        assert_eq!(
+            RegisteredPoStProof::try_from(value),
+            Err(InvalidDiscriminant {
+                type_name: "RegisteredPoStProof",
+                value,
+            })
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SectorSizeResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_size: u64,
+}
+
+fn sector_size(proof: u64) -> *mut SectorSizeResponse {
+    catch_panic_result(|| {
+        let sector_size = match RegisteredPoStProof::try_from(proof)? {
+            RegisteredPoStProof::StackedDrgWinning32GiBV1 => 32 << 30,
+            _ => 2 << 10,
+        };
+        Ok::<_, InvalidDiscriminant>(SectorSizeResponse {
+            sector_size,
+            ..Default::default()
+        })
+    })
+}
+
+#[test]
+fn invalid_values_are_caller_errors() {
+    unsafe {
+        let response = sector_size(4);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).sector_size, 32 << 30);
+        free_raw_ptr(response);
+
+        let response = sector_size(7);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(
+            CStr::from_ptr((*response).error_msg).to_str(),
+            Ok("7 is not a valid `RegisteredPoStProof`")
+        );
+        free_raw_ptr(response);
+    }
+}
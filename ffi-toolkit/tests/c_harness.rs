@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use drop_struct_macro_derive::{DropStructMacro, FFIResponse};
+use ffi_toolkit::c_harness::CHarness;
+use ffi_toolkit::{FCPResponseStatus, ResponseLayout, TempDir};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub proof_ptr: *const u8,
+    pub proof_len: libc::size_t,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct PieceInfo {
+    pub size: u64,
+    pub comm_p_ptr: *const u8,
+    pub comm_p_len: libc::size_t,
+}
+
+const HEADER: &str = "\
+#include <stdint.h>
+#include <stddef.h>
+
+typedef struct SealResponse {
+  int status_code;
+  const char *error_msg;
+  const uint8_t *proof_ptr;
+  size_t proof_len;
+} SealResponse;
+
+typedef struct PieceInfo {
+  uint64_t size;
+  const uint8_t *comm_p_ptr;
+  size_t comm_p_len;
+} PieceInfo;
+
+void destroy_seal_response(SealResponse *ptr);
+SealResponse *fil_seal(const char *path);
+";
+
+#[test]
+fn derived_layouts() {
+    assert_eq!(SealResponse::NAME, "SealResponse");
+    assert_eq!(SealResponse::DESTROY, Some("destroy_seal_response"));
+    assert_eq!(SealResponse::FIELDS[0], ("status_code", 0));
+    assert_eq!(
+        PieceInfo::FIELDS,
+        &[
+            ("size", 0),
+            ("comm_p_ptr", std::mem::offset_of!(PieceInfo, comm_p_ptr)),
+            ("comm_p_len", std::mem::offset_of!(PieceInfo, comm_p_len)),
+        ]
+    );
+    assert_eq!(PieceInfo::DESTROY, None);
+}
+
+fn harness() -> CHarness {
+    CHarness::new("responses.h")
+        .round_trip::<SealResponse>("fil_seal(NULL)")
+        .response::<PieceInfo>()
+        .with_main()
+}
+
+#[test]
+fn the_source_checks_layouts_and_destroys_responses() {
+    let source = harness().source();
+    assert!(source.contains("#include \"responses.h\"\n"));
+    assert!(source.contains(&format!(
+        "_Static_assert(sizeof(PieceInfo) == {0}, \"the size of PieceInfo isn't {0}\");\n",
+        std::mem::size_of::<PieceInfo>()
+    )));
+    assert!(source.contains("offsetof(SealResponse, error_msg)"));
+    assert!(source.contains("  destroy_seal_response(NULL);\n"));
+    assert!(source.contains("SealResponse *response = fil_seal(NULL);"));
+    assert!(source.contains("\"fil_seal(NULL) returned NULL\\n\""));
+    assert!(source.ends_with("return fil_harness_run() != 0;\n}\n"));
+}
+
+// Whether the harness compiles against `header`, `None` without a C compiler
+fn compiles(dir: &Path, header: &str) -> Option<bool> {
+    fs::write(dir.join("responses.h"), header).unwrap();
+    harness().write(dir.join("harness.c")).unwrap();
+    let status = Command::new("cc")
+        .args(["-std=c11", "-fsyntax-only", "harness.c"])
+        .current_dir(dir)
+        .status()
+        .ok()?;
+    Some(status.success())
+}
+
+#[test]
+fn mismatched_headers_fail_to_compile() {
+    let dir = TempDir::create("fil-harness").unwrap();
+    if compiles(dir.path(), HEADER).is_none() {
+        eprintln!("no C compiler, skipping");
+        return;
+    }
+    assert_eq!(compiles(dir.path(), HEADER), Some(true));
+    let swapped = HEADER.replace(
+        "  const uint8_t *comm_p_ptr;\n  size_t comm_p_len;",
+        "  size_t comm_p_len;\n  const uint8_t *comm_p_ptr;",
+    );
+    assert_eq!(compiles(dir.path(), &swapped), Some(false));
+}
@@ -0,0 +1,47 @@
+use std::ffi::{CString, OsString};
+use std::path::PathBuf;
+
+use ffi_toolkit::{c_str_to_os_string, c_str_to_path, c_str_to_pbuf};
+
+#[test]
+fn utf8_paths() {
+    let path = CString::new("/var/tmp/filecoin-proof-parameters").unwrap();
+    assert_eq!(
+        unsafe { c_str_to_path(path.as_ptr()) },
+        PathBuf::from("/var/tmp/filecoin-proof-parameters")
+    );
+    assert_eq!(
+        unsafe { c_str_to_os_string(std::ptr::null()) },
+        OsString::new()
+    );
+    assert_eq!(unsafe { c_str_to_path(std::ptr::null()) }, PathBuf::new());
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths_are_kept() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = b"/var/tmp/sector-\xff\xfe".to_vec();
+    let path = CString::new(bytes.clone()).unwrap();
+    let converted = unsafe { c_str_to_path(path.as_ptr()) };
+    assert_eq!(converted.as_os_str().as_bytes(), &bytes[..]);
+    // The lossy conversion points somewhere else
+    assert_ne!(unsafe { c_str_to_pbuf(path.as_ptr()) }, converted);
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths_can_be_opened() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = std::env::temp_dir().join(format!("c-str-path-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut bytes = dir.as_os_str().as_bytes().to_vec();
+    bytes.extend_from_slice(b"/sector-\xff");
+    let path = CString::new(bytes).unwrap();
+    let path = unsafe { c_str_to_path(path.as_ptr()) };
+    std::fs::write(&path, b"sealed").unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"sealed");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
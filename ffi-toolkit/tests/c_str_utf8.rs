@@ -0,0 +1,49 @@
+use std::ffi::{CStr, CString};
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    c_str_arg_to_rust_str, c_str_to_rust_str, free_raw_ptr, try_c_str_to_rust_str,
+    FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct GetResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn valid_utf8() {
+    let cid = CString::new("bafk2bzaceé").unwrap();
+    assert_eq!(
+        unsafe { try_c_str_to_rust_str(cid.as_ptr()) },
+        Ok("bafk2bzaceé")
+    );
+    assert_eq!(unsafe { try_c_str_to_rust_str(std::ptr::null()) }, Ok(""));
+}
+
+#[test]
+fn invalid_utf8_isnt_replaced() {
+    let key = CString::new(vec![b'k', 0xff, b'y']).unwrap();
+    assert!(unsafe { try_c_str_to_rust_str(key.as_ptr()) }.is_err());
+    // The lossy variant replaces it
+    assert_eq!(unsafe { c_str_to_rust_str(key.as_ptr()) }, "k\u{fffd}y");
+}
+
+#[test]
+fn caller_error_response() {
+    let key = CString::new(vec![b'k', 0xff, b'y']).unwrap();
+    let response =
+        unsafe { c_str_arg_to_rust_str::<GetResponse>(key.as_ptr(), "key") }.unwrap_err();
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+        assert!(message.starts_with("invalid argument `key`: invalid utf-8"));
+        free_raw_ptr(response);
+    }
+
+    let key = CString::new("key").unwrap();
+    let key = unsafe { c_str_arg_to_rust_str::<GetResponse>(key.as_ptr(), "key") };
+    assert_eq!(key.ok(), Some("key"));
+}
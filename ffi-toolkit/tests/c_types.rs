@@ -0,0 +1,58 @@
+#![cfg(feature = "testing")]
+
+use drop_struct_macro_derive::{DropStructMacro, FFIResponse};
+use ffi_toolkit::c_types::{c_char, size_t};
+use ffi_toolkit::{
+    free_c_str, free_raw_ptr, free_secret_c_str, raw_ptr, rust_str_to_c_str, track_ffi_memory,
+};
+use ffi_toolkit::{vec_into_raw_parts_exact, FCPResponseStatus};
+
+// Declared without libc, as on `wasm32-unknown-unknown`
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct CidResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const c_char,
+    pub cid: *const c_char,
+    pub piece_cids_ptr: *const *const c_char,
+    pub piece_cids_len: size_t,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct KeyResponse {
+    #[ffi_drop(secret)]
+    pub private_key: *const core::ffi::c_char,
+}
+
+#[test]
+fn c_strings_of_other_paths_are_freed() {
+    track_ffi_memory! {
+        let (piece_cids_ptr, piece_cids_len) = vec_into_raw_parts_exact(vec![
+            rust_str_to_c_str("baga6ea4seaqa") as *const c_char,
+            rust_str_to_c_str("baga6ea4seaqb") as *const c_char,
+        ]);
+        let response = raw_ptr(CidResponse {
+            error_msg: rust_str_to_c_str("no commD"),
+            cid: rust_str_to_c_str("bafk2bzace"),
+            piece_cids_ptr,
+            piece_cids_len,
+            ..Default::default()
+        });
+        let key = raw_ptr(KeyResponse {
+            private_key: rust_str_to_c_str("7b2254797065223a22626c73227d"),
+        });
+        unsafe {
+            free_raw_ptr(response);
+            free_raw_ptr(key);
+        }
+    };
+}
+
+#[test]
+fn the_types_are_the_ones_of_libc() {
+    let c_str: *const libc::c_char = rust_str_to_c_str("bafy") as *const c_char;
+    let len: libc::size_t = 4 as size_t;
+    assert_eq!(len, 4);
+    unsafe { free_c_str(c_str as *mut c_char) };
+}
@@ -0,0 +1,12 @@
+use ffi_toolkit::{recent_ffi_calls, record_ffi_call, AUDIT_RING_LEN};
+
+#[test]
+fn ring_keeps_the_most_recent_calls() {
+    let names: Vec<&'static str> = (0..AUDIT_RING_LEN + 5)
+        .map(|i| &*Box::leak(format!("fil_call_{}", i).into_boxed_str()))
+        .collect();
+    for &name in names.iter() {
+        record_ffi_call(name);
+    }
+    assert_eq!(recent_ffi_calls(), names[5..].to_vec());
+}
@@ -0,0 +1,109 @@
+use std::ffi::CStr;
+use std::slice;
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::{ffi_catch_panic, FFIResponse};
+use ffi_toolkit::{
+    call_metrics, catch_panic_response_named, catch_panic_result_named, enable_call_metrics,
+    fil_destroy_call_metrics_response, fil_metrics_snapshot, fil_reset_metrics, free_raw_ptr,
+    raw_ptr, CallMetrics, FCPResponseStatus,
+};
+
+// The metrics are global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn metrics_of(name: &str) -> Option<CallMetrics> {
+    call_metrics()
+        .into_iter()
+        .find(|metrics| metrics.name == name)
+}
+
+fn seal(sector_id: u64) -> *mut SealResponse {
+    catch_panic_result_named("seal", || match sector_id {
+        0 => Err((FCPResponseStatus::FCPCallerError, "no sector".to_string())),
+        1 => panic!("sector is corrupt"),
+        _ => Ok(SealResponse {
+            sector_id,
+            ..Default::default()
+        }),
+    })
+}
+
+#[ffi_catch_panic]
+extern "C" fn fil_unseal(sector_id: u64) -> *mut SealResponse {
+    raw_ptr(SealResponse {
+        sector_id,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn calls_errors_and_panics_are_counted_by_name() {
+    let _serial = serial();
+    enable_call_metrics(true);
+    for sector_id in [2, 3, 0, 1] {
+        unsafe { free_raw_ptr(seal(sector_id)) };
+    }
+    let response = catch_panic_response_named("verify", || raw_ptr(SealResponse::default()));
+    unsafe { free_raw_ptr(response) };
+
+    let seal = metrics_of("seal").unwrap();
+    assert_eq!((seal.calls, seal.errors, seal.panics), (4, 1, 1));
+    assert!(seal.max_ns <= seal.total_ns);
+    assert_eq!(seal.mean_ns(), seal.total_ns / 4);
+    assert_eq!(metrics_of("verify").unwrap().calls, 1);
+    enable_call_metrics(false);
+}
+
+#[test]
+fn ffi_catch_panic_functions_are_keyed_by_their_name() {
+    let _serial = serial();
+    enable_call_metrics(true);
+    unsafe { free_raw_ptr(fil_unseal(7)) };
+    assert_eq!(metrics_of("fil_unseal").unwrap().calls, 1);
+    enable_call_metrics(false);
+}
+
+#[test]
+fn nothing_is_recorded_unless_enabled() {
+    let _serial = serial();
+    enable_call_metrics(false);
+    unsafe { free_raw_ptr(seal(2)) };
+    assert!(call_metrics().is_empty());
+}
+
+#[test]
+fn the_snapshot_is_exported_and_can_be_reset() {
+    let _serial = serial();
+    enable_call_metrics(true);
+    unsafe { free_raw_ptr(seal(0)) };
+    unsafe {
+        let snapshot = fil_metrics_snapshot();
+        assert!((*snapshot).enabled);
+        let functions = slice::from_raw_parts((*snapshot).functions_ptr, (*snapshot).functions_len);
+        assert_eq!(functions.len(), 1);
+        assert_eq!(CStr::from_ptr(functions[0].name).to_str().unwrap(), "seal");
+        assert_eq!((functions[0].calls, functions[0].errors), (1, 1));
+        fil_destroy_call_metrics_response(snapshot);
+
+        fil_reset_metrics();
+        let snapshot = fil_metrics_snapshot();
+        assert_eq!((*snapshot).functions_len, 0);
+        fil_destroy_call_metrics_response(snapshot);
+    }
+    enable_call_metrics(false);
+}
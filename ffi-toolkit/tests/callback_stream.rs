@@ -0,0 +1,135 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::ptr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{catch_panic_result, free_raw_ptr, CallbackStream, FCPResponseStatus, FfiStream};
+
+// The host's storage, a buffer behind `user_data`
+fn storage(user_data: *mut libc::c_void) -> &'static mut Cursor<Vec<u8>> {
+    unsafe { &mut *(user_data as *mut Cursor<Vec<u8>>) }
+}
+
+extern "C" fn read_fn(buf: *mut u8, len: usize, user_data: *mut libc::c_void) -> isize {
+    let buf = unsafe { std::slice::from_raw_parts_mut(buf, len) };
+    storage(user_data).read(buf).map_or(-1, |n| n as isize)
+}
+
+extern "C" fn write_fn(buf: *const u8, len: usize, user_data: *mut libc::c_void) -> isize {
+    let buf = unsafe { std::slice::from_raw_parts(buf, len) };
+    storage(user_data).write(buf).map_or(-1, |n| n as isize)
+}
+
+extern "C" fn seek_fn(offset: i64, whence: libc::c_int, user_data: *mut libc::c_void) -> i64 {
+    let pos = match whence {
+        libc::SEEK_SET => SeekFrom::Start(offset as u64),
+        libc::SEEK_CUR => SeekFrom::Current(offset),
+        _ => SeekFrom::End(offset),
+    };
+    storage(user_data).seek(pos).map_or(-1, |pos| pos as i64)
+}
+
+extern "C" fn failing_write_fn(
+    _buf: *const u8,
+    _len: usize,
+    _user_data: *mut libc::c_void,
+) -> isize {
+    -28
+}
+
+extern "C" fn overlong_read_fn(_buf: *mut u8, len: usize, _user_data: *mut libc::c_void) -> isize {
+    len as isize + 1
+}
+
+fn stream(storage: &mut Cursor<Vec<u8>>) -> CallbackStream {
+    unsafe {
+        CallbackStream::new(FfiStream {
+            read_fn: Some(read_fn),
+            write_fn: Some(write_fn),
+            seek_fn: Some(seek_fn),
+            user_data: storage as *mut _ as *mut libc::c_void,
+        })
+    }
+}
+
+#[test]
+fn reads_writes_and_seeks_through_the_host() {
+    let mut storage = Cursor::new(Vec::new());
+    let mut stream = stream(&mut storage);
+    stream.write_all(b"sealed sector").unwrap();
+    assert_eq!(stream.seek(SeekFrom::Start(7)).unwrap(), 7);
+    let mut word = String::new();
+    stream.read_to_string(&mut word).unwrap();
+    assert_eq!(word, "sector");
+    assert_eq!(stream.seek(SeekFrom::End(-13)).unwrap(), 0);
+    assert_eq!(stream.seek(SeekFrom::Current(6)).unwrap(), 6);
+    assert_eq!(storage.into_inner(), b"sealed sector");
+}
+
+#[test]
+fn missing_callbacks_are_unsupported() {
+    let mut stream = unsafe {
+        CallbackStream::new(FfiStream {
+            read_fn: None,
+            write_fn: None,
+            seek_fn: None,
+            user_data: ptr::null_mut(),
+        })
+    };
+    let err = stream.read(&mut [0; 4]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    assert_eq!(err.to_string(), "the host stream has no read callback");
+    assert_eq!(
+        stream.seek(SeekFrom::Start(0)).unwrap_err().kind(),
+        io::ErrorKind::Unsupported
+    );
+}
+
+#[test]
+fn host_counts_larger_than_the_buffer_are_rejected() {
+    let mut stream = unsafe {
+        CallbackStream::new(FfiStream {
+            read_fn: Some(overlong_read_fn),
+            write_fn: None,
+            seek_fn: None,
+            user_data: ptr::null_mut(),
+        })
+    };
+    let err = stream.read(&mut [0; 4]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct WriteSectorResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub written: u64,
+}
+
+#[test]
+fn host_failures_are_receiver_errors() {
+    let mut stream = unsafe {
+        CallbackStream::new(FfiStream {
+            read_fn: None,
+            write_fn: Some(failing_write_fn),
+            seek_fn: None,
+            user_data: ptr::null_mut(),
+        })
+    };
+    let response: *mut WriteSectorResponse = catch_panic_result(|| {
+        stream.write_all(&[0; 128])?;
+        Ok::<_, io::Error>(WriteSectorResponse {
+            written: 128,
+            ..Default::default()
+        })
+    });
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+        let message = std::ffi::CStr::from_ptr((*response).error_msg);
+        assert_eq!(
+            message.to_str(),
+            Ok("the host's write callback failed with -28")
+        );
+        free_raw_ptr(response);
+    }
+}
@@ -0,0 +1,52 @@
+use ffi_toolkit::{
+    write_to_buffer, write_to_caller_buffer, BufferError, FCPResponseStatus, FfiBufferStatus,
+    IntoFFIError,
+};
+
+#[test]
+fn write_fits() {
+    let mut buffer = [0u8; 8];
+    let written = unsafe { write_to_buffer(b"proof", buffer.as_mut_ptr(), buffer.len()) };
+    assert_eq!(written, Ok(5));
+    assert_eq!(&buffer, b"proof\0\0\0");
+}
+
+#[test]
+fn too_small_writes_nothing() {
+    let mut buffer = [0u8; 4];
+    let result = unsafe { write_to_buffer(b"proof", buffer.as_mut_ptr(), buffer.len()) };
+    assert_eq!(result, Err(BufferError::TooSmall { required: 5 }));
+    assert_eq!(buffer, [0; 4]);
+    assert_eq!(
+        result.unwrap_err().code(),
+        FCPResponseStatus::FCPCallerError
+    );
+}
+
+#[test]
+fn null_buffers() {
+    let query = unsafe { write_to_buffer(b"proof", std::ptr::null_mut(), 0) };
+    assert_eq!(query, Err(BufferError::TooSmall { required: 5 }));
+    let empty = unsafe { write_to_buffer(b"", std::ptr::null_mut(), 0) };
+    assert_eq!(empty, Ok(0));
+    let invalid = unsafe { write_to_buffer(b"proof", std::ptr::null_mut(), 8) };
+    assert_eq!(invalid, Err(BufferError::NullBuffer));
+}
+
+#[test]
+fn two_phase() {
+    let proof: Vec<u8> = (0..=255).collect();
+    let mut len = 0;
+    let status = unsafe { write_to_caller_buffer(&proof, std::ptr::null_mut(), 0, &mut len) };
+    assert_eq!((status, len), (FfiBufferStatus::TooSmall, 256));
+
+    let mut buffer = vec![0; len];
+    let status =
+        unsafe { write_to_caller_buffer(&proof, buffer.as_mut_ptr(), buffer.len(), &mut len) };
+    assert_eq!((status, len), (FfiBufferStatus::Written, 256));
+    assert_eq!(buffer, proof);
+
+    let status =
+        unsafe { write_to_caller_buffer(&proof, std::ptr::null_mut(), 1, std::ptr::null_mut()) };
+    assert_eq!(status, FfiBufferStatus::NullBuffer);
+}
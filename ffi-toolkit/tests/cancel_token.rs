@@ -0,0 +1,128 @@
+use std::sync::mpsc;
+use std::thread;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_result, fil_cancel_token_cancel, fil_cancel_token_free,
+    fil_cancel_token_is_cancelled, fil_cancel_token_new, fil_task_wait, free_raw_ptr, raw_ptr,
+    spawn_cancellable_ffi_task, task_take_response, CancellationToken, Cancelled,
+    FCPResponseStatus, FfiTaskStatus,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub layers: u64,
+}
+
+// Seals layer after layer until it is cancelled
+fn seal(token: &CancellationToken, layers: u64) -> Result<SealResponse, Cancelled> {
+    for _ in 0..layers {
+        token.check()?;
+    }
+    Ok(SealResponse {
+        layers,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn cancelled_by_c() {
+    let token = fil_cancel_token_new();
+    let rust_token = unsafe { CancellationToken::from_ffi(token) };
+    assert!(!rust_token.is_cancelled());
+    assert_eq!(rust_token.check(), Ok(()));
+    unsafe {
+        assert!(!fil_cancel_token_is_cancelled(token));
+        fil_cancel_token_cancel(token);
+        assert!(fil_cancel_token_is_cancelled(token));
+        fil_cancel_token_free(token);
+    }
+    // The Rust side still holds a reference
+    assert!(rust_token.is_cancelled());
+    assert_eq!(rust_token.check(), Err(Cancelled));
+
+    let response = catch_panic_result(|| seal(&rust_token, 11));
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn null_is_never_cancelled() {
+    let token = unsafe { CancellationToken::from_ffi(std::ptr::null()) };
+    assert!(!token.is_cancelled());
+    unsafe {
+        fil_cancel_token_cancel(std::ptr::null());
+        assert!(!fil_cancel_token_is_cancelled(std::ptr::null()));
+        fil_cancel_token_free(std::ptr::null());
+    }
+}
+
+#[test]
+fn rust_tokens_handed_to_c() {
+    let token = CancellationToken::new();
+    let ffi_token = token.clone().into_ffi();
+    token.cancel();
+    unsafe {
+        assert!(fil_cancel_token_is_cancelled(ffi_token));
+        fil_cancel_token_free(ffi_token);
+    }
+}
+
+#[test]
+fn cancellable_tasks() {
+    let token = CancellationToken::new();
+    let (started, running) = mpsc::channel();
+    let (release, blocked) = mpsc::channel::<()>();
+    let task = spawn_cancellable_ffi_task(token.clone(), move |token| {
+        started.send(()).unwrap();
+        blocked.recv().unwrap();
+        match seal(token, 11) {
+            Ok(response) => raw_ptr(response),
+            Err(_) => raw_ptr(SealResponse {
+                status_code: FCPResponseStatus::FCPCallerError,
+                ..Default::default()
+            }),
+        }
+    });
+    running.recv().unwrap();
+    token.cancel();
+    release.send(()).unwrap();
+    assert_eq!(fil_task_wait(task, 60_000), FfiTaskStatus::Finished);
+    let response = task_take_response::<SealResponse>(task).unwrap().unwrap();
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        free_raw_ptr(response);
+    }
+
+    // Cancelled before it started
+    let task = spawn_cancellable_ffi_task(token, |_| -> *mut SealResponse {
+        unreachable!("the task was cancelled")
+    });
+    assert_eq!(fil_task_wait(task, 60_000), FfiTaskStatus::Finished);
+    let response = task_take_response::<SealResponse>(task).unwrap().unwrap();
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn cancelled_from_another_thread() {
+    let token = fil_cancel_token_new();
+    let sealer = {
+        let token = unsafe { CancellationToken::from_ffi(token) };
+        thread::spawn(move || {
+            while !token.is_cancelled() {
+                thread::yield_now();
+            }
+        })
+    };
+    unsafe { fil_cancel_token_cancel(token) };
+    sealer.join().unwrap();
+    unsafe { fil_cancel_token_free(token) };
+}
@@ -1,4 +1,6 @@
+use std::error::Error;
 use std::ffi::CString;
+use std::panic;
 use std::ptr;
 
 use drop_struct_macro_derive::DropStructMacro;
@@ -30,15 +32,19 @@ impl Default for BasicResponse {
 code_and_message_impl!(BasicResponse);
 
 unsafe extern "C" fn fn_does_not_panic() -> *mut BasicResponse {
-    let mut response = BasicResponse::default();
-    response.is_valid = true;
+    let response = BasicResponse {
+        is_valid: true,
+        ..Default::default()
+    };
     raw_ptr(response)
 }
 
 unsafe extern "C" fn fn_does_not_panic_with_catch_panic() -> *mut BasicResponse {
     catch_panic_response(|| {
-        let mut response = BasicResponse::default();
-        response.is_valid = true;
+        let response = BasicResponse {
+            is_valid: true,
+            ..Default::default()
+        };
         raw_ptr(response)
     })
 }
@@ -89,3 +95,31 @@ fn does_panic_with_catch_panic_response() {
         assert_eq!(error_message, "Rust panic: I do panic");
     }
 }
+
+// the error message of a response for a panic with `payload`
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    let response: *mut BasicResponse = catch_panic_response(|| panic::resume_unwind(payload));
+    unsafe {
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        CString::from_raw((*response).error_msg as *mut _)
+            .into_string()
+            .unwrap()
+    }
+}
+
+#[test]
+fn formatted_and_error_payloads() {
+    assert_eq!(
+        panic_message(Box::new(format!("sector {} is corrupt", 7))),
+        "Rust panic: sector 7 is corrupt"
+    );
+    let err: Box<dyn Error + Send + Sync> = "disk full".into();
+    assert_eq!(panic_message(Box::new(err)), "Rust panic: disk full");
+    assert_eq!(
+        panic_message(Box::new(42u8)),
+        "Rust panic: no unwind information"
+    );
+}
@@ -0,0 +1,99 @@
+use std::ffi::CStr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{catch_panic_response_with, free_raw_ptr, raw_ptr, FCPResponseStatus};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(C)]
+pub enum SealPhase {
+    PreCommit1,
+    PreCommit2,
+    Failed,
+}
+
+// `SealPhase` has no `Default`, neither has `[u8; 64]`
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(error_response)]
+pub struct SealStatusResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(error_value = "SealPhase::Failed")]
+    pub phase: SealPhase,
+    #[ffi_drop(error_value = "[0; 64]")]
+    pub ticket: [u8; 64],
+    pub sector_id: u64,
+}
+
+fn seal_status(phase: Option<SealPhase>) -> *mut SealStatusResponse {
+    catch_panic_response_with(SealStatusResponse::new_error_response, || {
+        let phase = phase.expect("no sector");
+        raw_ptr(SealStatusResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: std::ptr::null(),
+            phase,
+            ticket: [7; 64],
+            sector_id: 42,
+        })
+    })
+}
+
+#[test]
+fn responses_are_returned_as_they_are() {
+    let response = seal_status(Some(SealPhase::PreCommit2));
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).phase, SealPhase::PreCommit2);
+        assert_eq!((*response).ticket, [7; 64]);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn panics_use_the_generated_error_response() {
+    let response = seal_status(None);
+    unsafe {
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        assert_eq!(
+            CStr::from_ptr((*response).error_msg).to_str().unwrap(),
+            "Rust panic: no sector"
+        );
+        assert_eq!((*response).phase, SealPhase::Failed);
+        assert_eq!((*response).ticket, [0; 64]);
+        assert_eq!((*response).sector_id, 0);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn error_responses_can_be_built_by_hand() {
+    #[repr(C)]
+    #[derive(FFIResponse)]
+    #[ffi_drop(error_response)]
+    pub struct RetryResponse {
+        pub status_code: FCPResponseStatus,
+        pub error_msg: *const libc::c_char,
+        pub retry_after_ms: u64,
+    }
+
+    // The error state is more than the status code and message
+    let response: *mut RetryResponse = catch_panic_response_with(
+        |code, message| {
+            let mut response = RetryResponse::new_error_response(code, message);
+            response.retry_after_ms = 500;
+            response
+        },
+        || panic!("worker pool is gone"),
+    );
+    unsafe {
+        assert_eq!((*response).retry_after_ms, 500);
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        free_raw_ptr(response);
+    }
+}
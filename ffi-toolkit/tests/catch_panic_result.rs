@@ -0,0 +1,126 @@
+use std::ffi::CStr;
+use std::io;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{catch_panic_result, free_raw_ptr, FCPResponseStatus, IntoFFIError};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+#[derive(Debug)]
+enum SealError {
+    InvalidSectorSize(u64),
+    OutOfSpace,
+}
+
+impl IntoFFIError for SealError {
+    fn code(&self) -> FCPResponseStatus {
+        match self {
+            SealError::InvalidSectorSize(_) => FCPResponseStatus::FCPCallerError,
+            SealError::OutOfSpace => FCPResponseStatus::FCPReceiverError,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            SealError::InvalidSectorSize(size) => format!("invalid sector size {}", size),
+            SealError::OutOfSpace => "out of space".to_string(),
+        }
+    }
+}
+
+fn seal(sector_size: u64) -> Result<SealResponse, SealError> {
+    match sector_size {
+        2048 => Ok(SealResponse {
+            sector_id: 7,
+            ..Default::default()
+        }),
+        0 => Err(SealError::OutOfSpace),
+        size => Err(SealError::InvalidSectorSize(size)),
+    }
+}
+
+// the status code and error message of the response, which is freed
+fn status(response: *mut SealResponse) -> (FCPResponseStatus, Option<String>) {
+    unsafe {
+        let status = (
+            (*response).status_code,
+            (*response).error_msg.as_ref().map(|_| {
+                CStr::from_ptr((*response).error_msg)
+                    .to_string_lossy()
+                    .into_owned()
+            }),
+        );
+        free_raw_ptr(response);
+        status
+    }
+}
+
+#[test]
+fn ok_returns_the_response() {
+    let response = catch_panic_result(|| seal(2048));
+    assert_eq!(unsafe { (*response).sector_id }, 7);
+    assert_eq!(status(response), (FCPResponseStatus::FCPNoError, None));
+}
+
+#[test]
+fn errors_fill_the_response() {
+    assert_eq!(
+        status(catch_panic_result(|| seal(1000))),
+        (
+            FCPResponseStatus::FCPCallerError,
+            Some("invalid sector size 1000".to_string())
+        )
+    );
+    assert_eq!(
+        status(catch_panic_result(|| seal(0))),
+        (
+            FCPResponseStatus::FCPReceiverError,
+            Some("out of space".to_string())
+        )
+    );
+}
+
+#[test]
+fn provided_error_impls() {
+    let response = catch_panic_result(|| -> Result<SealResponse, io::Error> {
+        Err(io::Error::other("disk failed"))
+    });
+    assert_eq!(
+        status(response),
+        (
+            FCPResponseStatus::FCPReceiverError,
+            Some("disk failed".to_string())
+        )
+    );
+
+    let response = catch_panic_result(
+        || -> Result<SealResponse, Box<dyn std::error::Error + Send + Sync>> {
+            Err("no proof".into())
+        },
+    );
+    assert_eq!(
+        status(response),
+        (
+            FCPResponseStatus::FCPUnclassifiedError,
+            Some("no proof".to_string())
+        )
+    );
+}
+
+#[test]
+fn panics_are_caught() {
+    let response = catch_panic_result(|| -> Result<SealResponse, SealError> { panic!("boom") });
+    assert_eq!(
+        status(response),
+        (
+            FCPResponseStatus::FCPUnclassifiedError,
+            Some("Rust panic: boom".to_string())
+        )
+    );
+}
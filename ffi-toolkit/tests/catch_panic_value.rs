@@ -0,0 +1,55 @@
+use ffi_toolkit::{
+    catch_panic_value, catch_panic_value_with_last_error, clear_last_error, last_error,
+    FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SectorCounts {
+    pub sealed: u64,
+    pub failed: u64,
+}
+
+#[test]
+fn returns_the_value() {
+    assert!(catch_panic_value(|| true));
+    assert_eq!(catch_panic_value(|| 42u64), 42);
+    assert_eq!(
+        catch_panic_value(|| SectorCounts {
+            sealed: 3,
+            failed: 1
+        }),
+        SectorCounts {
+            sealed: 3,
+            failed: 1
+        }
+    );
+}
+
+#[test]
+fn panics_return_the_default() {
+    assert!(!catch_panic_value(|| -> bool { panic!("boom") }));
+    assert_eq!(catch_panic_value(|| -> u64 { panic!("boom") }), 0);
+    assert_eq!(
+        catch_panic_value(|| -> SectorCounts { panic!("boom") }),
+        SectorCounts::default()
+    );
+}
+
+#[test]
+fn panics_are_recorded_as_last_error() {
+    clear_last_error();
+    assert_eq!(catch_panic_value_with_last_error(|| 7u64), 7);
+    assert_eq!(last_error(), None);
+
+    let handle = catch_panic_value_with_last_error(|| -> u64 { panic!("no such sector") });
+    assert_eq!(handle, 0);
+    assert_eq!(
+        last_error(),
+        Some((
+            FCPResponseStatus::FCPUnclassifiedError,
+            "Rust panic: no such sector".to_string()
+        ))
+    );
+    clear_last_error();
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::mem::{align_of, offset_of, size_of};
+
+use ffi_toolkit::cbindgen::{
+    emit_toolkit_header, toolkit_header, FfiBytes, FfiDuration, FfiErrorChain, FfiI128, FfiString,
+    FfiStringArray, FfiTimestamp, FfiU128, FfiVTableHeader, StringRef, TOOLKIT_HEADER_NAME,
+    TOOLKIT_TYPES,
+};
+use ffi_toolkit::TempDir;
+
+// The layouts of the declarations in the header, on the 64-bit targets the bindings are built for
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn layouts_are_stable() {
+    assert_eq!((size_of::<FfiBytes>(), align_of::<FfiBytes>()), (24, 8));
+    assert_eq!(
+        (offset_of!(FfiBytes, len), offset_of!(FfiBytes, cap)),
+        (8, 16)
+    );
+    assert_eq!(
+        (offset_of!(FfiString, len), offset_of!(FfiString, cap)),
+        (8, 16)
+    );
+    assert_eq!(offset_of!(StringRef, len), 8);
+    assert_eq!(offset_of!(FfiStringArray, len), 8);
+    assert_eq!(
+        (
+            offset_of!(FfiErrorChain, codes_ptr),
+            offset_of!(FfiErrorChain, codes_len)
+        ),
+        (16, 24)
+    );
+    assert_eq!(
+        (size_of::<FfiDuration>(), offset_of!(FfiDuration, nanos)),
+        (16, 8)
+    );
+    assert_eq!(
+        (size_of::<FfiTimestamp>(), offset_of!(FfiTimestamp, nanos)),
+        (16, 8)
+    );
+    assert_eq!(offset_of!(FfiU128, lo), 8);
+    assert_eq!(offset_of!(FfiI128, lo), 8);
+    assert_eq!(
+        (
+            size_of::<FfiVTableHeader>(),
+            offset_of!(FfiVTableHeader, size)
+        ),
+        (16, 8)
+    );
+}
+
+#[test]
+fn the_header_declares_all_toolkit_types() {
+    let header = toolkit_header();
+    for name in TOOLKIT_TYPES {
+        assert!(
+            header.contains(&format!("}} {};\n", name)),
+            "`{}` isn't declared",
+            name
+        );
+    }
+    assert!(header.contains("#define FCP_CALLER_ERROR 2\n"));
+    assert!(header.starts_with("/* Generated by ffi-toolkit"));
+    assert!(header.ends_with("#endif /* FFI_TOOLKIT_H */\n"));
+}
+
+#[test]
+fn unchanged_headers_are_not_written_again() {
+    let dir = TempDir::create("fil-header").unwrap();
+    let path = emit_toolkit_header(dir.path()).unwrap();
+    assert_eq!(path, dir.path().join(TOOLKIT_HEADER_NAME));
+    assert_eq!(fs::read_to_string(&path).unwrap(), toolkit_header());
+
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    emit_toolkit_header(dir.path()).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+    fs::write(&path, "stale").unwrap();
+    emit_toolkit_header(dir.path()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), toolkit_header());
+}
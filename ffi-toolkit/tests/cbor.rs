@@ -0,0 +1,90 @@
+#![cfg(feature = "cbor")]
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    code_and_message_impl, free_c_str, free_raw_ptr, from_cbor, from_cbor_raw, to_cbor,
+    CodeAndMessage, FCPResponseStatus,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Deal {
+    piece_cid: String,
+    size: u64,
+    verified: bool,
+    labels: BTreeMap<String, Vec<u8>>,
+}
+
+fn deal() -> Deal {
+    let mut labels = BTreeMap::new();
+    labels.insert("client".to_string(), vec![1, 2, 3]);
+    Deal {
+        piece_cid: "baga6ea4seaqao7s73y24kcutaosvacpdjgfe5pw76ooefnyqw4ynr3d2y6x2mpq".to_string(),
+        size: 1 << 35,
+        verified: true,
+        labels,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let bytes = to_cbor(&vec![deal(), deal()]).unwrap();
+    let decoded: Vec<Deal> = unsafe { from_cbor_raw(bytes.ptr, bytes.len) }.unwrap();
+    assert_eq!(decoded, vec![deal(), deal()]);
+}
+
+#[test]
+fn decode_errors() {
+    let bytes = to_cbor(&deal()).unwrap();
+    let truncated = &bytes.as_slice()[..bytes.len() - 1];
+    assert_eq!(
+        from_cbor::<Deal>(truncated).unwrap_err().to_string(),
+        "invalid CBOR payload: unexpected end of input"
+    );
+    let mut trailing = bytes.to_vec();
+    trailing.push(0);
+    assert_eq!(
+        from_cbor::<Deal>(&trailing).unwrap_err().to_string(),
+        "invalid CBOR payload: 1 trailing bytes"
+    );
+    assert!(from_cbor::<u64>(bytes.as_slice()).is_err());
+    assert!(unsafe { from_cbor_raw::<u64>(ptr::null(), 0) }.is_err());
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct DealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for DealResponse {
+    fn default() -> Self {
+        DealResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(DealResponse);
+
+#[test]
+fn caller_error() {
+    let err = from_cbor::<Deal>(&[0xff]).unwrap_err();
+    let response: *mut DealResponse = err.caller_error("deal_ptr");
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+        assert!(
+            message.starts_with("invalid argument `deal_ptr`: invalid CBOR payload: "),
+            "{}",
+            message
+        );
+        free_raw_ptr(response);
+    }
+}
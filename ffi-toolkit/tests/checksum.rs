@@ -0,0 +1,40 @@
+use ffi_toolkit::{crc32c, frame_with_checksum, ChecksumError, ChecksummedFrame, FRAME_HEADER_LEN};
+
+#[test]
+fn crc32c_check_value() {
+    assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    assert_eq!(crc32c(b""), 0);
+}
+
+#[test]
+fn round_trip() {
+    let frame = frame_with_checksum(b"replica");
+    assert_eq!(frame.len(), FRAME_HEADER_LEN + 7);
+    let received = unsafe { ChecksummedFrame::from_raw(frame.ptr, frame.len) }.unwrap();
+    assert_eq!(received.payload(), b"replica");
+}
+
+#[test]
+fn truncation_and_corruption() {
+    let frame = frame_with_checksum(b"replica").to_vec();
+    assert_eq!(
+        ChecksummedFrame::new(&frame[..frame.len() - 1]).unwrap_err(),
+        ChecksumError::LengthMismatch {
+            expected: 7,
+            actual: 6
+        }
+    );
+    assert!(matches!(
+        ChecksummedFrame::new(&frame[..4]),
+        Err(ChecksumError::LengthMismatch { .. })
+    ));
+    let mut corrupt = frame.clone();
+    corrupt[FRAME_HEADER_LEN] ^= 1;
+    let err = ChecksummedFrame::new(&corrupt).unwrap_err();
+    assert!(matches!(err, ChecksumError::ChecksumMismatch { .. }));
+    assert!(err.to_string().starts_with("frame has checksum "));
+    assert_eq!(
+        unsafe { ChecksummedFrame::from_raw(std::ptr::null(), 0) }.unwrap_err(),
+        ChecksumError::Null
+    );
+}
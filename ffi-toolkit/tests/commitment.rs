@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+use std::ptr;
+
+use ffi_toolkit::FfiCommitment;
+
+const COMM_R: &str = "0b0a0908070605040302010000000000000000000000000000000000000000ff";
+
+#[test]
+fn hex_round_trip() {
+    let commitment = FfiCommitment::from_hex(COMM_R).unwrap();
+    assert_eq!(commitment.0[0], 0x0b);
+    assert_eq!(commitment.0[31], 0xff);
+    assert_eq!(commitment.to_hex(), COMM_R);
+    assert_eq!(commitment.to_string(), COMM_R);
+    assert_eq!(
+        FfiCommitment::from_hex(&COMM_R.to_uppercase()).unwrap(),
+        commitment
+    );
+    assert_eq!(
+        format!("{:?}", commitment),
+        format!("FfiCommitment({})", COMM_R)
+    );
+}
+
+#[test]
+fn invalid_hex() {
+    assert_eq!(
+        FfiCommitment::from_hex(&COMM_R[..62]).unwrap_err(),
+        "commitment has 31 bytes, expected 32"
+    );
+    assert_eq!(
+        FfiCommitment::from_hex("abc").unwrap_err(),
+        "invalid hex: invalid length at offset 3"
+    );
+    let invalid = format!("{}zz", &COMM_R[..62]);
+    assert_eq!(
+        FfiCommitment::from_hex(&invalid).unwrap_err(),
+        "invalid hex: invalid character 'z' at offset 62"
+    );
+}
+
+#[test]
+fn from_raw() {
+    let bytes = [7u8; 32];
+    let commitment = unsafe { FfiCommitment::from_raw(bytes.as_ptr()) }.unwrap();
+    assert_eq!(commitment, FfiCommitment::from(bytes));
+    assert!(unsafe { FfiCommitment::from_raw(ptr::null()) }.is_err());
+    assert!(FfiCommitment::from_slice(&bytes[1..]).is_err());
+}
+
+#[test]
+fn equality_and_hashing() {
+    let mut other = [7u8; 32];
+    other[31] = 8;
+    let a = FfiCommitment::from([7u8; 32]);
+    let b = FfiCommitment::from(other);
+    assert_ne!(a, b);
+    let set: HashSet<_> = vec![a, b, a].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "zstd")]
+
+use ffi_toolkit::{
+    compress_ffi, decompress_ffi, uncompressed_len, CompressionError, COMPRESSION_HEADER_LEN,
+    DEFAULT_COMPRESSION_LEVEL,
+};
+
+fn proof() -> Vec<u8> {
+    (0..1 << 16).map(|i| (i % 7) as u8).collect()
+}
+
+#[test]
+fn round_trip() {
+    let proof = proof();
+    let payload = compress_ffi(&proof, DEFAULT_COMPRESSION_LEVEL).unwrap();
+    assert!(payload.len() < proof.len() / 10);
+    assert_eq!(&payload.as_slice()[..4], b"FILZ");
+    assert_eq!(
+        uncompressed_len(payload.as_slice()).unwrap(),
+        proof.len() as u64
+    );
+    let decompressed = decompress_ffi(payload.as_slice(), proof.len()).unwrap();
+    assert_eq!(decompressed.as_slice(), &proof[..]);
+
+    let empty = compress_ffi(&[], DEFAULT_COMPRESSION_LEVEL).unwrap();
+    assert!(decompress_ffi(empty.as_slice(), 0).unwrap().is_empty());
+}
+
+#[test]
+fn invalid_payloads() {
+    let proof = proof();
+    let payload = compress_ffi(&proof, DEFAULT_COMPRESSION_LEVEL).unwrap();
+    assert!(matches!(
+        decompress_ffi(payload.as_slice(), proof.len() - 1),
+        Err(CompressionError::TooLarge { .. })
+    ));
+    assert!(matches!(
+        decompress_ffi(&proof, usize::MAX),
+        Err(CompressionError::InvalidHeader)
+    ));
+
+    // A header claiming more than the frame holds
+    let mut lying = payload.to_vec();
+    lying[4..12].copy_from_slice(&(proof.len() as u64 + 1).to_le_bytes());
+    assert!(matches!(
+        decompress_ffi(&lying, usize::MAX),
+        Err(CompressionError::LengthMismatch { .. })
+    ));
+
+    let truncated = &payload.as_slice()[..COMPRESSION_HEADER_LEN + 4];
+    assert!(matches!(
+        decompress_ffi(truncated, usize::MAX),
+        Err(CompressionError::Zstd(_))
+    ));
+}
@@ -0,0 +1,78 @@
+use std::ffi::CString;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    acquire_concurrency_permit, catch_panic_response_bounded, concurrency_stats,
+    configure_concurrency_limit, fil_concurrency_queue_length, fil_configure_concurrency_limit,
+    free_raw_ptr, raw_ptr, try_acquire_concurrency_permit, FCPResponseStatus, FfiConcurrencyStats,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct ProveResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+// Waits until `waiting` calls of `class` wait
+fn wait_for_waiters(class: &str, waiting: usize) {
+    while concurrency_stats(class).waiting < waiting {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn unlimited_classes() {
+    let _first = acquire_concurrency_permit("unlimited");
+    assert!(try_acquire_concurrency_permit("unlimited").is_some());
+    assert_eq!(
+        concurrency_stats("unlimited"),
+        FfiConcurrencyStats::default()
+    );
+}
+
+#[test]
+fn calls_beyond_the_limit_wait() {
+    configure_concurrency_limit("seal", Some(1));
+    let permit = acquire_concurrency_permit("seal");
+    assert!(try_acquire_concurrency_permit("seal").is_none());
+
+    let (done, finished) = mpsc::channel();
+    let waiter = thread::spawn(move || {
+        let response = catch_panic_response_bounded("seal", || raw_ptr(ProveResponse::default()));
+        done.send(()).unwrap();
+        unsafe { free_raw_ptr(response) };
+    });
+    wait_for_waiters("seal", 1);
+    let class = CString::new("seal").unwrap();
+    assert_eq!(unsafe { fil_concurrency_queue_length(class.as_ptr()) }, 1);
+    assert_eq!(
+        concurrency_stats("seal"),
+        FfiConcurrencyStats {
+            limit: 1,
+            running: 1,
+            waiting: 1
+        }
+    );
+    assert!(finished.try_recv().is_err());
+
+    drop(permit);
+    finished.recv().unwrap();
+    waiter.join().unwrap();
+    assert_eq!(concurrency_stats("seal").running, 0);
+}
+
+#[test]
+fn removing_the_limit_releases_waiters() {
+    let class = CString::new("prove").unwrap();
+    unsafe { fil_configure_concurrency_limit(class.as_ptr(), 1) };
+    let _permit = acquire_concurrency_permit("prove");
+    let waiter = thread::spawn(|| drop(acquire_concurrency_permit("prove")));
+    wait_for_waiters("prove", 1);
+    unsafe { fil_configure_concurrency_limit(class.as_ptr(), 0) };
+    waiter.join().unwrap();
+    assert_eq!(concurrency_stats("prove").limit, 0);
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "toml")]
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use ffi_toolkit::{
+    config, fil_destroy_parse_config_response, fil_parse_config_toml, parse_config_toml,
+    register_config_type, FCPResponseStatus,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct ProverConfig {
+    parameter_cache: String,
+    gpu: bool,
+    max_threads: u32,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        ProverConfig {
+            parameter_cache: "/var/tmp/filecoin-proof-parameters".to_string(),
+            gpu: false,
+            max_threads: 4,
+        }
+    }
+}
+
+// the status code and error message of parsing `input`
+fn parse(input: Option<&str>) -> (FCPResponseStatus, Option<String>) {
+    let input = input.map(|input| CString::new(input).unwrap());
+    unsafe {
+        let response =
+            fil_parse_config_toml(input.as_ref().map_or(ptr::null(), |input| input.as_ptr()));
+        let message = if (*response).error_msg.is_null() {
+            None
+        } else {
+            Some(
+                CStr::from_ptr((*response).error_msg)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+        let status_code = (*response).status_code;
+        fil_destroy_parse_config_response(response);
+        (status_code, message)
+    }
+}
+
+#[test]
+fn registered_config() {
+    // Only test touching the registration
+    assert_eq!(
+        parse(Some("gpu = true")).0,
+        FCPResponseStatus::FCPReceiverError
+    );
+
+    register_config_type::<ProverConfig>();
+    assert_eq!(*config::<ProverConfig>().unwrap(), ProverConfig::default());
+    assert!(config::<String>().is_none());
+
+    assert_eq!(
+        parse(Some("gpu = true\nmax_threads = 16\n")),
+        (FCPResponseStatus::FCPNoError, None)
+    );
+    let current = config::<ProverConfig>().unwrap();
+    assert!(current.gpu);
+    assert_eq!(current.max_threads, 16);
+    // Defaults are applied
+    assert_eq!(
+        current.parameter_cache,
+        "/var/tmp/filecoin-proof-parameters"
+    );
+
+    // A failed parse leaves the configuration as is
+    assert_eq!(
+        parse(Some("gpu = true\nmax_threads = \"many\"\n")),
+        (
+            FCPResponseStatus::FCPCallerError,
+            Some(
+                "invalid config at line 2, column 15: invalid type: string \"many\", expected u32"
+                    .to_string()
+            )
+        )
+    );
+    assert_eq!(config::<ProverConfig>().unwrap().max_threads, 16);
+
+    assert_eq!(parse(None), (FCPResponseStatus::FCPNoError, None));
+    assert_eq!(*config::<ProverConfig>().unwrap(), ProverConfig::default());
+}
+
+#[test]
+fn error_locations() {
+    let err = parse_config_toml::<ProverConfig>("gpu = true\nthreads = 2\n").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 1));
+    assert!(
+        err.message.starts_with("unknown field `threads`"),
+        "{}",
+        err.message
+    );
+
+    let err = parse_config_toml::<ProverConfig>("gpu = \n").unwrap_err();
+    assert_eq!(err.line, 1);
+}
@@ -0,0 +1,38 @@
+use ffi_toolkit::{
+    cpu_feature_names, cpu_features, fil_cpu_features, CPU_FEATURES, FIL_CPU_AVX2, FIL_CPU_NEON,
+    FIL_CPU_SSE4_1,
+};
+
+#[test]
+fn flags_are_distinct() {
+    let all = CPU_FEATURES.iter().fold(0u64, |all, &(flag, _)| {
+        assert_eq!(all & flag, 0);
+        assert_eq!(flag.count_ones(), 1);
+        all | flag
+    });
+    assert_eq!(all.count_ones() as usize, CPU_FEATURES.len());
+}
+
+#[test]
+fn detection() {
+    assert_eq!(fil_cpu_features(), cpu_features());
+    #[cfg(target_arch = "x86_64")]
+    {
+        assert_eq!(cpu_features() & FIL_CPU_NEON, 0);
+        assert_eq!(
+            cpu_features() & FIL_CPU_SSE4_1 != 0,
+            is_x86_feature_detected!("sse4.1")
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    assert_ne!(cpu_features() & FIL_CPU_NEON, 0);
+}
+
+#[test]
+fn names() {
+    assert_eq!(
+        cpu_feature_names(FIL_CPU_SSE4_1 | FIL_CPU_AVX2),
+        vec!["sse4.1", "avx2"]
+    );
+    assert!(cpu_feature_names(0).is_empty());
+}
@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+
+use ffi_toolkit::{install_crash_handler, record_ffi_call, TempDir};
+
+// Set for the child process that crashes, to the path of the crash log
+const CRASH_LOG_VAR: &str = "FIL_TEST_CRASH_LOG";
+
+// Only does something in the child process started by `crash_is_reported()`
+#[test]
+fn crashing_child() {
+    if let Ok(path) = env::var(CRASH_LOG_VAR) {
+        install_crash_handler(path.as_ref()).unwrap();
+        record_ffi_call("fil_generate_winning_post");
+        record_ffi_call("fil_seal_commit_phase2");
+        unsafe { libc::abort() };
+    }
+}
+
+#[test]
+fn crash_is_reported() {
+    let dir = TempDir::create("fil-crash-handler").unwrap();
+    let path = dir.path().join("crash.log");
+    let status = Command::new(env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "crashing_child",
+            "--nocapture",
+            "--test-threads",
+            "1",
+        ])
+        .env(CRASH_LOG_VAR, &path)
+        .status()
+        .unwrap();
+    // The signal is re-raised
+    assert_eq!(status.signal(), Some(libc::SIGABRT));
+
+    let log = fs::read_to_string(&path).unwrap();
+    assert!(log.starts_with("fatal signal 6 (SIGABRT)\n"), "{}", log);
+    let calls = log.split("recent FFI calls:\n").nth(1).unwrap();
+    let calls: Vec<&str> = calls
+        .lines()
+        .map(|line| line.trim().split(' ').next().unwrap())
+        .collect();
+    assert_eq!(
+        calls,
+        vec!["fil_generate_winning_post", "fil_seal_commit_phase2"]
+    );
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    assert!(log.contains("backtrace:\n"));
+}
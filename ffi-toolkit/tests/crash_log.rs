@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use std::fmt::Write;
+use std::fs;
+use std::os::unix::io::AsRawFd;
+use std::panic;
+use std::sync::Mutex;
+
+use ffi_toolkit::lifecycle;
+use ffi_toolkit::{
+    close_crash_log, fil_set_crash_log_fd, open_crash_log, BorrowedFfiFd, CrashLine, TempDir,
+    CRASH_LINE_LEN,
+};
+
+// The crash log is global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn lines() {
+    let mut line = CrashLine::new();
+    line.push_str("signal ")
+        .push_u64(11)
+        .push_str(" at ")
+        .push_hex(0xdead_beef);
+    assert_eq!(line.as_bytes(), b"signal 11 at 0x00000000deadbeef");
+    write!(line, " in fil_seal_{}", 2).unwrap();
+    assert!(line.as_bytes().ends_with(b" in fil_seal_2"));
+
+    let mut long = CrashLine::new();
+    long.push_bytes(&[b'a'; CRASH_LINE_LEN + 10]);
+    assert_eq!(long.as_bytes().len(), CRASH_LINE_LEN);
+    let mut zero = CrashLine::new();
+    assert_eq!(zero.push_u64(0).as_bytes(), b"0");
+}
+
+#[test]
+fn panics_are_logged() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-crash").unwrap();
+    let path = dir.path().join("crash.log");
+    lifecycle::init();
+    open_crash_log(&path).unwrap();
+    let _ = panic::catch_unwind(|| panic!("sector {} is corrupt", 7));
+    CrashLine::new().push_str("after the panic").write();
+    close_crash_log();
+    // Nothing is written without a crash log
+    CrashLine::new().push_str("not logged").write();
+
+    let log = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", log);
+    assert!(lines[0].starts_with("panic at "), "{}", lines[0]);
+    assert!(lines[0].ends_with(": sector 7 is corrupt"), "{}", lines[0]);
+    assert_eq!(lines[1], "after the panic");
+}
+
+#[test]
+fn set_from_host_fd() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-crash").unwrap();
+    let path = dir.path().join("crash.log");
+    let file = fs::File::create(&path).unwrap();
+    assert!(fil_set_crash_log_fd(BorrowedFfiFd(file.as_raw_fd())));
+    // The log uses a duplicate
+    drop(file);
+    CrashLine::new().push_str("still open").write();
+    assert!(!fil_set_crash_log_fd(BorrowedFfiFd(-1)));
+    close_crash_log();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "still open\n");
+}
@@ -0,0 +1,43 @@
+use std::ptr;
+
+use ffi_toolkit::{
+    ct_eq, fil_ct_eq, fil_ct_eq_fil32, fil_ct_eq_fil48, fil_ct_eq_fil96, Fil32, Fil48, Fil96,
+};
+
+#[test]
+fn rust_helper() {
+    assert!(ct_eq(b"ticket", b"ticket"));
+    assert!(!ct_eq(b"ticket", b"tickeT"));
+    assert!(!ct_eq(b"ticket", b"ticke"));
+    assert!(ct_eq(b"", b""));
+}
+
+#[test]
+fn exported() {
+    let a = [1u8, 2, 3];
+    let b = [1u8, 2, 3];
+    let c = [1u8, 2, 4];
+    unsafe {
+        assert!(fil_ct_eq(a.as_ptr(), a.len(), b.as_ptr(), b.len()));
+        assert!(!fil_ct_eq(a.as_ptr(), a.len(), c.as_ptr(), c.len()));
+        assert!(!fil_ct_eq(a.as_ptr(), 2, b.as_ptr(), 3));
+        assert!(fil_ct_eq(ptr::null(), 0, a.as_ptr(), 0));
+        assert!(!fil_ct_eq(ptr::null(), 3, a.as_ptr(), 3));
+    }
+}
+
+#[test]
+fn fixed_size_arrays() {
+    let ticket = Fil32([7; 32]);
+    let mut other = ticket;
+    assert!(ticket.ct_eq(&other));
+    other.0[31] = 8;
+    assert!(!ticket.ct_eq(&other));
+    assert!(Fil48([1; 48]).ct_eq(&Fil48([1; 48])));
+    assert!(!Fil96([1; 96]).ct_eq(&Fil96::default()));
+
+    assert!(fil_ct_eq_fil32(ticket, ticket));
+    assert!(!fil_ct_eq_fil32(ticket, other));
+    assert!(fil_ct_eq_fil48(Fil48::default(), Fil48::default()));
+    assert!(!fil_ct_eq_fil96(Fil96([1; 96]), Fil96::default()));
+}
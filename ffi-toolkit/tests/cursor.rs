@@ -0,0 +1,101 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use ffi_toolkit::{
+    declare_cursor, fil_free_string, last_error, track_ffi_memory, FfiCursorStatus, FfiString,
+};
+
+declare_cursor! {
+    /// Challenges streamed to C
+    pub struct FfiChallenges(u64);
+    next = fil_challenges_next;
+    destroy = fil_destroy_challenges;
+}
+
+declare_cursor! {
+    /// Paths streamed to C, which frees each of them
+    pub struct FfiPaths(FfiString);
+    next = fil_paths_next;
+    destroy = fil_destroy_paths;
+}
+
+#[test]
+fn items_are_streamed_until_done() {
+    let cursor = FfiChallenges::wrap(vec![3, 1, 4]);
+    let mut challenge = 0;
+    let mut challenges = Vec::new();
+    unsafe {
+        while fil_challenges_next(cursor, &mut challenge) == FfiCursorStatus::Item {
+            challenges.push(challenge);
+        }
+        // An exhausted cursor stays exhausted
+        assert_eq!(
+            fil_challenges_next(cursor, &mut challenge),
+            FfiCursorStatus::Done
+        );
+        fil_destroy_challenges(cursor);
+    }
+    assert_eq!(challenges, [3, 1, 4]);
+}
+
+#[test]
+fn streamed_items_are_owned_by_the_caller() {
+    track_ffi_memory! {
+        let cursor = FfiPaths::wrap((0..3).map(|i| FfiString::from(format!("/sealed/s-{}", i))));
+        let mut path = std::mem::MaybeUninit::<FfiString>::uninit();
+        unsafe {
+            assert_eq!(fil_paths_next(cursor, path.as_mut_ptr()), FfiCursorStatus::Item);
+            let path = path.assume_init();
+            assert_eq!(path.as_str(), "/sealed/s-0");
+            fil_free_string(path);
+            // The remaining paths are freed with the cursor
+            fil_destroy_paths(cursor);
+        }
+    };
+}
+
+#[test]
+fn a_panicking_iterator_ends_the_cursor() {
+    let cursor = FfiChallenges::wrap((0..3u64).map(|i| match i {
+        1 => panic!("challenge {} is out of range", i),
+        _ => i,
+    }));
+    let mut challenge = 0;
+    unsafe {
+        assert_eq!(
+            fil_challenges_next(cursor, &mut challenge),
+            FfiCursorStatus::Item
+        );
+        assert_eq!(
+            fil_challenges_next(cursor, &mut challenge),
+            FfiCursorStatus::Panicked
+        );
+        let (_, message) = last_error().unwrap();
+        assert!(message.contains("challenge 1 is out of range"));
+        assert_eq!(
+            fil_challenges_next(cursor, &mut challenge),
+            FfiCursorStatus::Done
+        );
+        assert_eq!(challenge, 0);
+        fil_destroy_challenges(cursor);
+    }
+}
+
+#[test]
+fn null_pointers_are_caller_errors() {
+    let cursor = FfiChallenges::wrap(vec![1]);
+    let mut challenge = 0;
+    unsafe {
+        assert_eq!(
+            fil_challenges_next(ptr::null_mut(), &mut challenge),
+            FfiCursorStatus::NullPointer
+        );
+        assert_eq!(
+            fil_challenges_next(cursor, ptr::null_mut()),
+            FfiCursorStatus::NullPointer
+        );
+        fil_destroy_challenges(cursor);
+        fil_destroy_challenges(ptr::null_mut());
+    }
+}
@@ -0,0 +1,75 @@
+use std::ptr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_response, code_and_message_impl, error_response, free_c_str, free_raw_ptr,
+    status_code_enum, CodeAndMessage, StatusCode,
+};
+
+status_code_enum! {
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    pub enum SealStatus {
+        Sealed = 0 => SEAL_STATUS_SEALED,
+        Panicked = 1 => SEAL_STATUS_PANICKED,
+        OutOfSpace = 2 => SEAL_STATUS_OUT_OF_SPACE,
+    }
+}
+
+impl StatusCode for SealStatus {
+    const NO_ERROR: Self = SealStatus::Sealed;
+    const UNCLASSIFIED: Self = SealStatus::Panicked;
+}
+
+#[repr(C)]
+pub struct SealResponse {
+    pub status_code: SealStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for SealResponse {
+    fn default() -> Self {
+        SealResponse {
+            status_code: SealStatus::Sealed,
+            error_msg: ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(SealResponse, SealStatus);
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct UnsealResponse {
+    pub status_code: SealStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn panics_use_the_unclassified_status() {
+    let response: *mut SealResponse = catch_panic_response(|| panic!("boom"));
+    unsafe {
+        assert_eq!((*response).status_code, SealStatus::Panicked);
+        free_c_str((*response).error_msg as *mut libc::c_char);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn error_responses_with_domain_codes() {
+    let response: *mut SealResponse = error_response(SealStatus::OutOfSpace, "disk full");
+    unsafe {
+        assert_eq!((*response).status_code, SealStatus::OutOfSpace);
+        free_c_str((*response).error_msg as *mut libc::c_char);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn ffi_response_with_a_custom_status() {
+    assert_eq!(UnsealResponse::default().status_code, SealStatus::Sealed);
+    let response: *mut UnsealResponse = catch_panic_response(|| panic!("boom"));
+    unsafe {
+        assert_eq!((*response).status_code, SealStatus::Panicked);
+        destroy_unseal_response(response);
+    }
+}
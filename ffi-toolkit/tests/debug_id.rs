@@ -0,0 +1,112 @@
+use std::mem::ManuallyDrop;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    check_debug_id, checked_response, fil_check_debug_id, last_error, DebugIdError,
+    FCPResponseStatus, FfiDebugId, FREED_MAGIC, LIVE_MAGIC,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(destroy = "destroy_sector_response")]
+pub struct SectorResponse {
+    pub debug_id: FfiDebugId,
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+fn header(magic: u32, sequence: u32) -> FfiDebugId {
+    FfiDebugId { magic, sequence }
+}
+
+#[test]
+fn responses_get_increasing_sequence_numbers() {
+    let first = SectorResponse::default();
+    let second = SectorResponse::default();
+    assert!(first.debug_id.is_live());
+    assert!(second.debug_id.sequence > first.debug_id.sequence);
+    let checked = unsafe { checked_response(&first as *const SectorResponse) }.unwrap();
+    assert_eq!(checked.debug_id, first.debug_id);
+}
+
+#[test]
+fn destroyed_responses_are_stale() {
+    let mut response = ManuallyDrop::new(SectorResponse::default());
+    let sequence = response.debug_id.sequence;
+    unsafe { ManuallyDrop::drop(&mut response) };
+    let ptr = &mut *response as *mut SectorResponse;
+    assert_eq!(
+        unsafe { check_debug_id(ptr as *const libc::c_void) },
+        Err(DebugIdError::Stale(sequence))
+    );
+
+    // Destroying it again is refused rather than freeing memory that isn't the toolkit's
+    unsafe { destroy_sector_response(ptr) };
+    let (code, message) = last_error().unwrap();
+    assert_eq!(code, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        message,
+        format!(
+            "refusing to destroy a `SectorResponse`: response #{} was destroyed already",
+            sequence
+        )
+    );
+}
+
+#[test]
+fn destroying_live_and_null_responses() {
+    let response = ffi_toolkit::raw_ptr(SectorResponse::default());
+    unsafe { destroy_sector_response(response) };
+    unsafe { destroy_sector_response(std::ptr::null_mut()) };
+}
+
+#[test]
+fn wild_pointers_are_diagnosed() {
+    let check = |id: &FfiDebugId| unsafe { check_debug_id(id as *const _ as *const libc::c_void) };
+    assert_eq!(
+        check(&header(0, 7)),
+        Err(DebugIdError::Foreign(header(0, 7)))
+    );
+    assert_eq!(
+        check(&header(LIVE_MAGIC ^ 0x100, 7)),
+        Err(DebugIdError::Corrupted(header(LIVE_MAGIC ^ 0x100, 7)))
+    );
+    assert_eq!(
+        check(&header(FREED_MAGIC ^ 1, 7)),
+        Err(DebugIdError::Corrupted(header(FREED_MAGIC ^ 1, 7)))
+    );
+    assert_eq!(
+        check(&header(LIVE_MAGIC, 0)),
+        Err(DebugIdError::Corrupted(header(LIVE_MAGIC, 0)))
+    );
+    assert_eq!(
+        unsafe { check_debug_id(std::ptr::null()) },
+        Err(DebugIdError::Null)
+    );
+
+    let words = [header(LIVE_MAGIC, 1); 2];
+    let misaligned = unsafe { (words.as_ptr() as *const u8).add(1) };
+    assert!(matches!(
+        unsafe { check_debug_id(misaligned as *const libc::c_void) },
+        Err(DebugIdError::Misaligned(_))
+    ));
+}
+
+#[test]
+fn c_callers_get_a_status_code() {
+    let response = SectorResponse::default();
+    assert_eq!(
+        unsafe { fil_check_debug_id(&response as *const _ as *const libc::c_void) },
+        FCPResponseStatus::FCPNoError
+    );
+    let foreign = [0u64; 2];
+    assert_eq!(
+        unsafe { fil_check_debug_id(foreign.as_ptr() as *const libc::c_void) },
+        FCPResponseStatus::FCPCallerError
+    );
+    assert_eq!(
+        last_error().unwrap().1,
+        "not a response of the toolkit, magic number 0x00000000"
+    );
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "testing")]
+
+#[test]
+fn drop_struct_macro_misuse() {
+    ffi_toolkit::testing::assert_compile_fail("tests/ui/drop_struct_*.rs");
+}
+
+#[test]
+fn ffi_catch_panic_misuse() {
+    ffi_toolkit::testing::assert_compile_fail("tests/ui/ffi_catch_panic_*.rs");
+}
+
+#[test]
+fn ffi_response_misuse() {
+    ffi_toolkit::testing::assert_compile_fail("tests/ui/ffi_response_*.rs");
+}
+
+#[test]
+fn ffi_error_code_misuse() {
+    ffi_toolkit::testing::assert_compile_fail("tests/ui/ffi_error_code_*.rs");
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    detected_double_frees, free_c_str, raw_ptr, rust_str_to_c_str, track_ffi_memory,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+#[ffi_drop(destroy)]
+pub struct SealResponse {
+    pub error_msg: *const libc::c_char,
+    pub sector_size: u64,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+#[ffi_drop(destroy = "fil_destroy_ffi_sector_info")]
+pub struct FFISectorInfo {
+    pub comm_r_ptr: *const u8,
+    pub comm_r_len: libc::size_t,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+#[ffi_drop(destroy, tombstone)]
+pub struct PieceResponse {
+    pub error_msg: *const libc::c_char,
+    pub comm_p_ptr: *const u8,
+    pub comm_p_len: libc::size_t,
+}
+
+#[test]
+fn destroy_frees_the_response_and_its_fields() {
+    track_ffi_memory! {
+        let response = raw_ptr(SealResponse {
+            error_msg: rust_str_to_c_str("disk full"),
+            sector_size: 2048,
+        });
+        unsafe { destroy_seal_response(response) };
+    };
+}
+
+#[test]
+fn destroy_with_a_custom_name() {
+    let mut comm_r = vec![7u8; 32];
+    comm_r.shrink_to_fit();
+    let response = raw_ptr(FFISectorInfo {
+        comm_r_len: comm_r.len(),
+        comm_r_ptr: comm_r.leak().as_mut_ptr(),
+    });
+    unsafe { fil_destroy_ffi_sector_info(response) };
+}
+
+#[test]
+fn destroy_ignores_null() {
+    unsafe { destroy_seal_response(ptr::null_mut()) };
+}
+
+#[test]
+fn destroying_a_tombstone_again_is_a_no_op() {
+    let mut comm_p = vec![7u8; 32];
+    comm_p.shrink_to_fit();
+    let response = raw_ptr(PieceResponse {
+        error_msg: rust_str_to_c_str("no error"),
+        comm_p_len: comm_p.len(),
+        comm_p_ptr: comm_p.leak().as_mut_ptr(),
+    });
+    let double_frees = detected_double_frees();
+
+    unsafe { destroy_piece_response(response) };
+    let tombstone = unsafe { &*response };
+    assert!(tombstone.error_msg.is_null());
+    assert!(tombstone.comm_p_ptr.is_null());
+    assert_eq!(detected_double_frees(), double_frees);
+
+    unsafe { destroy_piece_response(response) };
+    assert_eq!(detected_double_frees(), double_frees + 1);
+}
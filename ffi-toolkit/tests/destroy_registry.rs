@@ -0,0 +1,99 @@
+use std::ptr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    fil_destroy, last_error, raw_ptr, register_destructor, rust_str_to_c_str, DestroyError,
+    FCPResponseStatus, TaggedResponse,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(tag = 1)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(tag = 2)]
+pub struct UnsealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(tag = 1)]
+pub struct VerifyResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+fn register() {
+    register_destructor::<SealResponse>().unwrap();
+    register_destructor::<UnsealResponse>().unwrap();
+}
+
+fn seal_response() -> *mut libc::c_void {
+    raw_ptr(SealResponse {
+        status_code: FCPResponseStatus::FCPNoError,
+        error_msg: rust_str_to_c_str("sealed"),
+    }) as *mut libc::c_void
+}
+
+#[test]
+fn responses_are_destroyed_by_tag() {
+    register();
+    assert_eq!(SealResponse::TYPE_TAG, 1);
+    let response = seal_response();
+    assert_eq!(
+        unsafe { fil_destroy(1, response) },
+        FCPResponseStatus::FCPNoError
+    );
+    assert_eq!(
+        unsafe { fil_destroy(2, ptr::null_mut()) },
+        FCPResponseStatus::FCPNoError
+    );
+}
+
+#[test]
+fn wrong_tags_are_rejected() {
+    register();
+    let response = seal_response();
+    assert_eq!(
+        unsafe { fil_destroy(2, response) },
+        FCPResponseStatus::FCPCallerError
+    );
+    let (_, message) = last_error().unwrap();
+    assert!(message.contains("SealResponse` (tag 1)"), "{}", message);
+    assert_eq!(
+        unsafe { fil_destroy(99, response) },
+        FCPResponseStatus::FCPCallerError
+    );
+    assert_eq!(last_error().unwrap().1, "no type is registered for tag 99");
+
+    // Still alive, and freed once only
+    assert_eq!(
+        unsafe { fil_destroy(1, response) },
+        FCPResponseStatus::FCPNoError
+    );
+    assert_eq!(
+        unsafe { fil_destroy(1, response) },
+        FCPResponseStatus::FCPCallerError
+    );
+    assert!(last_error().unwrap().1.contains("destroyed already"));
+}
+
+#[test]
+fn tags_are_unique() {
+    register();
+    assert_eq!(
+        register_destructor::<VerifyResponse>(),
+        Err(DestroyError::TagInUse {
+            tag: 1,
+            type_name: std::any::type_name::<SealResponse>(),
+        })
+    );
+}
@@ -0,0 +1,78 @@
+use std::ffi::CStr;
+use std::slice;
+use std::sync::Mutex;
+
+use ffi_toolkit::{
+    fil_destroy_list_devices_response, fil_list_devices, list_devices, register_device_probe,
+    unregister_device_probe, DeviceInfo, FCPResponseStatus,
+};
+
+// The probes are global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn gpu_probe() -> Result<Vec<DeviceInfo>, String> {
+    Ok(vec![DeviceInfo {
+        kind: "gpu".to_string(),
+        name: "GeForce RTX 3090".to_string(),
+        id: "0000:01:00.0".to_string(),
+        memory_bytes: 24 << 30,
+    }])
+}
+
+fn no_gpu_probe() -> Result<Vec<DeviceInfo>, String> {
+    Ok(Vec::new())
+}
+
+fn failing_probe() -> Result<Vec<DeviceInfo>, String> {
+    Err("no OpenCL platform".to_string())
+}
+
+fn panicking_probe() -> Result<Vec<DeviceInfo>, String> {
+    panic!("driver crashed")
+}
+
+#[test]
+fn lists_devices_of_all_probes() {
+    let _serial = SERIAL.lock().unwrap();
+    register_device_probe("cuda", gpu_probe);
+    register_device_probe("opencl", failing_probe);
+    register_device_probe("broken", panicking_probe);
+
+    unsafe {
+        let response = fil_list_devices();
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        let devices = slice::from_raw_parts((*response).devices_ptr, (*response).devices_len);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(CStr::from_ptr(devices[0].kind).to_str().unwrap(), "gpu");
+        assert_eq!(
+            CStr::from_ptr(devices[0].id).to_str().unwrap(),
+            "0000:01:00.0"
+        );
+        assert_eq!(devices[0].memory_bytes, 24 << 30);
+        assert_eq!(
+            (*response).probe_errors.to_vec(),
+            vec!["opencl: no OpenCL platform", "broken: probe panicked"]
+        );
+        fil_destroy_list_devices_response(response);
+    }
+
+    for name in &["cuda", "opencl", "broken"] {
+        unregister_device_probe(name);
+    }
+}
+
+#[test]
+fn registering_again_replaces() {
+    let _serial = SERIAL.lock().unwrap();
+    register_device_probe("cuda", gpu_probe);
+    register_device_probe("cuda", no_gpu_probe);
+    assert_eq!(list_devices(), (Vec::new(), Vec::new()));
+    unregister_device_probe("cuda");
+
+    unsafe {
+        let response = fil_list_devices();
+        assert_eq!((*response).devices_len, 0);
+        assert!((*response).probe_errors.is_empty());
+        fil_destroy_list_devices_response(response);
+    }
+}
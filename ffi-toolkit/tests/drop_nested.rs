@@ -0,0 +1,50 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{free_c_str, raw_ptr, rust_str_to_c_str, track_ffi_memory};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct SectorResult {
+    pub sector_id: u64,
+    pub error_msg: *const libc::c_char,
+}
+
+fn sector_result(sector_id: u64) -> SectorResult {
+    SectorResult {
+        sector_id,
+        error_msg: rust_str_to_c_str(format!("sector {} failed", sector_id)),
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct BatchResponse {
+    #[ffi_drop(nested)]
+    pub first: *mut SectorResult,
+    #[ffi_drop(nested)]
+    pub missing: *const SectorResult,
+    #[ffi_drop(nested, vec(len = "results_len"))]
+    pub results_ptr: *mut SectorResult,
+    pub results_len: libc::size_t,
+    #[ffi_drop(skip)]
+    pub caller_context: *mut libc::c_void,
+}
+
+#[test]
+fn nested_structs_are_freed_recursively() {
+    track_ffi_memory! {
+        let results: Box<[SectorResult]> = (2..5).map(sector_result).collect();
+        let results_len = results.len();
+        let mut context = 0u8;
+        drop(BatchResponse {
+            first: raw_ptr(sector_result(1)),
+            missing: ptr::null(),
+            results_ptr: Box::into_raw(results) as *mut SectorResult,
+            results_len,
+            caller_context: &mut context as *mut u8 as *mut libc::c_void,
+        });
+    };
+}
@@ -0,0 +1,89 @@
+use std::env;
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{free_c_str, last_error, raw_ptr, FCPResponseStatus};
+
+static CLOSED: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+// Set for the child process that aborts
+const ABORT_VAR: &str = "FIL_TEST_DESTROY_ABORT";
+
+fn close(handle: u64) {
+    if handle == 0 {
+        panic!("handle 0 is corrupt");
+    }
+    CLOSED.lock().unwrap().push(handle);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+#[ffi_drop(destroy = "fil_destroy_handles_response")]
+pub struct HandlesResponse {
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(with = "close")]
+    pub first: u64,
+    #[ffi_drop(with = "close")]
+    pub second: u64,
+}
+
+fn response(first: u64, second: u64) -> HandlesResponse {
+    HandlesResponse {
+        error_msg: std::ptr::null(),
+        first,
+        second,
+    }
+}
+
+#[test]
+fn a_panicking_destructor_does_not_unwind() {
+    unsafe { fil_destroy_handles_response(raw_ptr(response(0, 11))) };
+    let (code, message) = last_error().unwrap();
+    assert_eq!(code, FCPResponseStatus::FCPUnclassifiedError);
+    assert!(
+        message.starts_with("dropping a `HandlesResponse` panicked"),
+        "{}",
+        message
+    );
+    assert!(message.contains("handle 0 is corrupt"), "{}", message);
+    // The fields after the panic are leaked rather than dropped
+    assert!(!CLOSED.lock().unwrap().contains(&11));
+
+    drop(response(12, 13));
+    let closed = CLOSED.lock().unwrap();
+    assert!(closed.contains(&12) && closed.contains(&13));
+}
+
+// Only does something in the child process started by `a_panicking_destroy_function_aborts()`
+#[test]
+fn aborting_child() {
+    if env::var(ABORT_VAR).is_ok() {
+        ffi_toolkit::__private::destroy_or_abort("HandlesResponse", || panic!("freed twice"));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn a_panicking_destroy_function_aborts() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "aborting_child",
+            "--nocapture",
+            "--test-threads",
+            "1",
+        ])
+        .env(ABORT_VAR, "1")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.signal(), Some(libc::SIGABRT));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("destroying a `HandlesResponse` panicked, aborting"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,32 @@
+use std::ffi::{CStr, CString};
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{free_c_str, rust_str_to_c_str};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct VerifyResponse {
+    pub error_msg: *const libc::c_char,
+    // Echoes of the caller's inputs
+    #[ffi_drop(skip)]
+    pub prover_id: *const libc::c_char,
+    // No length field needed
+    #[ffi_drop(skip)]
+    pub proof_ptr: *const u8,
+}
+
+#[test]
+fn skipped_fields_are_not_freed() {
+    let prover_id = CString::new("t01000").unwrap();
+    let proof = [7u8; 192];
+    drop(VerifyResponse {
+        error_msg: rust_str_to_c_str("invalid proof"),
+        prover_id: prover_id.as_ptr(),
+        proof_ptr: proof.as_ptr(),
+    });
+    assert_eq!(
+        prover_id.as_c_str(),
+        CStr::from_bytes_with_nul(b"t01000\0").unwrap()
+    );
+    assert_eq!(proof, [7u8; 192]);
+}
@@ -0,0 +1,46 @@
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use drop_struct_macro_derive::DropStructMacro;
+
+static PROOFS_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Proof(#[allow(dead_code)] Vec<u8>);
+
+impl Drop for Proof {
+    fn drop(&mut self) {
+        PROOFS_DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct AggregateResponse {
+    #[ffi_drop(vec(ptr = "proofs", len = "proofs_count", cap = "proofs_cap"))]
+    pub proofs: *const Proof,
+    pub proofs_count: libc::size_t,
+    pub proofs_cap: libc::size_t,
+    // Bytes, not a C string
+    #[ffi_drop(vec(len = "label_len"))]
+    pub label: *const libc::c_char,
+    pub label_len: libc::size_t,
+}
+
+#[test]
+fn vectors_with_a_separate_capacity_are_dropped() {
+    let mut proofs = Vec::with_capacity(8);
+    proofs.push(Proof(vec![1; 192]));
+    proofs.push(Proof(vec![2; 192]));
+    let mut proofs = ManuallyDrop::new(proofs);
+    let mut label = ManuallyDrop::new(b"no nul".map(|byte| byte as libc::c_char).to_vec());
+    label.shrink_to_fit();
+
+    drop(AggregateResponse {
+        proofs: proofs.as_mut_ptr(),
+        proofs_count: proofs.len(),
+        proofs_cap: proofs.capacity(),
+        label: label.as_mut_ptr(),
+        label_len: label.len(),
+    });
+    assert_eq!(PROOFS_DROPPED.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,36 @@
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{free_c_str, rust_str_to_c_str};
+
+static CLOSED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+mod resources {
+    pub unsafe fn close_fd(fd: libc::c_int) {
+        super::CLOSED.lock().unwrap().push(format!("fd {}", fd));
+    }
+}
+
+fn unregister_handle(handle: u64) {
+    CLOSED.lock().unwrap().push(format!("handle {}", handle));
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct OpenSectorResponse {
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(with = "resources::close_fd")]
+    pub sector_fd: libc::c_int,
+    #[ffi_drop(with = "unregister_handle")]
+    pub handle: u64,
+}
+
+#[test]
+fn handlers_free_their_fields() {
+    drop(OpenSectorResponse {
+        error_msg: rust_str_to_c_str("no error"),
+        sector_fd: 7,
+        handle: 42,
+    });
+    assert_eq!(*CLOSED.lock().unwrap(), ["fd 7", "handle 42"]);
+}
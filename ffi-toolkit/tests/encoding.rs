@@ -0,0 +1,241 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    bytes_to_c_base64, bytes_to_c_hex, c_base64_to_bytes, c_hex_to_bytes, catch_panic_result,
+    code_and_message_impl, free_c_str, free_raw_ptr, parse_base64, parse_cid, parse_cid_c_str,
+    parse_hex, parse_hex_c_str, parse_multibase, to_base64, to_hex, CodeAndMessage,
+    FCPResponseStatus, ParseError, ParseErrorKind,
+};
+
+#[test]
+fn hex() {
+    assert_eq!(parse_hex("00ffAb").unwrap(), vec![0, 0xff, 0xab]);
+    assert_eq!(to_hex(&[0, 0xff, 0xab]), "00ffab");
+    assert_eq!(
+        parse_hex("0g").unwrap_err(),
+        ParseError {
+            offset: 1,
+            kind: ParseErrorKind::InvalidCharacter('g')
+        }
+    );
+    assert_eq!(
+        parse_hex("abc").unwrap_err().to_string(),
+        "invalid length at offset 3"
+    );
+}
+
+#[test]
+fn base64() {
+    let cases: [(&[u8], &str); 4] = [
+        (b"", ""),
+        (b"h", "aA=="),
+        (b"he", "aGU="),
+        (b"hello", "aGVsbG8="),
+    ];
+    for (bytes, encoded) in cases.iter() {
+        assert_eq!(to_base64(bytes), *encoded);
+        assert_eq!(parse_base64(encoded).unwrap(), *bytes);
+    }
+    assert_eq!(parse_base64("aGVsbG8").unwrap(), b"hello");
+    assert_eq!(
+        parse_base64("aGVsbG8==").unwrap_err().kind,
+        ParseErrorKind::InvalidLength
+    );
+    assert_eq!(
+        parse_base64("aG=sbG8=").unwrap_err(),
+        ParseError {
+            offset: 2,
+            kind: ParseErrorKind::InvalidCharacter('=')
+        }
+    );
+    assert_eq!(
+        parse_base64("aGVsbG9=").unwrap_err().kind,
+        ParseErrorKind::NonCanonical
+    );
+}
+
+#[test]
+fn multibase() {
+    for encoded in &[
+        "f68656c6c6f",
+        "F68656C6C6F",
+        "bnbswy3dp",
+        "BNBSWY3DP",
+        "zCn8eVZg",
+        "maGVsbG8",
+        "uaGVsbG8",
+    ] {
+        assert_eq!(parse_multibase(encoded).unwrap(), b"hello", "{}", encoded);
+    }
+    assert_eq!(parse_multibase("z11").unwrap(), vec![0, 0]);
+    assert_eq!(parse_multibase("b").unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn multibase_errors() {
+    let cases = [
+        ("", 0, ParseErrorKind::Empty),
+        ("xabc", 0, ParseErrorKind::UnsupportedBase('x')),
+        ("bnbs1", 4, ParseErrorKind::InvalidCharacter('1')),
+        ("z0", 1, ParseErrorKind::InvalidCharacter('0')),
+        ("maGVsbG8=", 8, ParseErrorKind::InvalidCharacter('=')),
+        ("bn", 2, ParseErrorKind::InvalidLength),
+        // The unused bits of the last character are set
+        ("maGVsbG9", 7, ParseErrorKind::NonCanonical),
+        ("f\u{e9}00", 1, ParseErrorKind::InvalidCharacter('\u{e9}')),
+    ];
+    for (input, offset, kind) in cases.iter() {
+        assert_eq!(
+            parse_multibase(input).unwrap_err(),
+            ParseError {
+                offset: *offset,
+                kind: kind.clone()
+            },
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn cids() {
+    let v1 = parse_cid("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi").unwrap();
+    assert_eq!((v1.version, v1.codec, v1.hash_code), (1, 0x70, 0x12));
+    assert_eq!(v1.digest.len(), 32);
+
+    let v0 = parse_cid("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG").unwrap();
+    assert_eq!((v0.version, v0.codec, v0.hash_code), (0, 0x70, 0x12));
+    assert_eq!(v0.digest.len(), 32);
+}
+
+#[test]
+fn cid_errors() {
+    // Version 2
+    assert_eq!(
+        parse_cid("f02").unwrap_err().kind,
+        ParseErrorKind::UnsupportedCidVersion(2)
+    );
+    // The varint of the codec is cut off
+    assert_eq!(
+        parse_cid("f0180").unwrap_err(),
+        ParseError {
+            offset: 2,
+            kind: ParseErrorKind::Truncated
+        }
+    );
+    // A sha2-256 multihash claiming 32 bytes but containing 2
+    assert_eq!(
+        parse_cid("f017012200102").unwrap_err(),
+        ParseError {
+            offset: 4,
+            kind: ParseErrorKind::WrongLength {
+                expected: 32,
+                actual: 2
+            }
+        }
+    );
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct CidResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub codec: u64,
+}
+
+impl Default for CidResponse {
+    fn default() -> Self {
+        CidResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            codec: 0,
+        }
+    }
+}
+
+code_and_message_impl!(CidResponse);
+
+unsafe extern "C" fn cid_codec(cid: *const libc::c_char) -> *mut CidResponse {
+    match parse_cid_c_str(cid) {
+        Ok(cid) => ffi_toolkit::raw_ptr(CidResponse {
+            codec: cid.codec,
+            ..Default::default()
+        }),
+        Err(err) => err.caller_error("cid"),
+    }
+}
+
+#[test]
+fn caller_errors() {
+    let cases: [(&[u8], &str); 3] = [
+        (
+            b"bafy\xff\0",
+            "invalid argument `cid`: invalid UTF-8 at offset 4",
+        ),
+        (
+            b"f01zz\0",
+            "invalid argument `cid`: invalid character 'z' at offset 3",
+        ),
+        (
+            b"bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi\0",
+            "",
+        ),
+    ];
+    for (input, expected) in cases.iter() {
+        unsafe {
+            let response = cid_codec(input.as_ptr() as *const libc::c_char);
+            if expected.is_empty() {
+                assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+                assert_eq!((*response).codec, 0x70);
+            } else {
+                assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+                let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+                assert_eq!(message, *expected);
+            }
+            free_raw_ptr(response);
+        }
+    }
+    assert_eq!(
+        unsafe { parse_hex_c_str(ptr::null()) }.unwrap_err().kind,
+        ParseErrorKind::Null
+    );
+}
+
+#[test]
+fn c_strings_round_trip() {
+    unsafe {
+        let hex = bytes_to_c_hex(&[0xde, 0xad]);
+        assert_eq!(CStr::from_ptr(hex).to_str().unwrap(), "dead");
+        assert_eq!(c_hex_to_bytes(hex).unwrap(), vec![0xde, 0xad]);
+        free_c_str(hex);
+
+        let base64 = bytes_to_c_base64(b"hello");
+        assert_eq!(CStr::from_ptr(base64).to_str().unwrap(), "aGVsbG8=");
+        assert_eq!(c_base64_to_bytes(base64).unwrap(), b"hello");
+        free_c_str(base64);
+    }
+}
+
+#[test]
+fn malformed_c_strings_are_caller_errors() {
+    let response: *mut CidResponse = catch_panic_result(|| {
+        let bytes = unsafe { c_base64_to_bytes(b"a!\0".as_ptr() as *const libc::c_char) }?;
+        Ok::<_, ParseError>(CidResponse {
+            codec: bytes.len() as u64,
+            ..Default::default()
+        })
+    });
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+        assert_eq!(message, "invalid character '!' at offset 1");
+        free_raw_ptr(response);
+    }
+    assert_eq!(
+        unsafe { c_hex_to_bytes(ptr::null()) }.unwrap_err().kind,
+        ParseErrorKind::Null
+    );
+}
@@ -0,0 +1,36 @@
+use ffi_toolkit::{from_be_ffi, to_be_bytes_ffi, FfiByteOrder, FfiOrderedBytes};
+
+#[test]
+fn network_order() {
+    let bytes = to_be_bytes_ffi(0x0102_0304u32);
+    assert_eq!(bytes.as_slice(), &[1, 2, 3, 4]);
+    let value: u32 = unsafe { from_be_ffi(bytes.ptr, bytes.len) }.unwrap();
+    assert_eq!(value, 0x0102_0304);
+    let negative: i16 = unsafe { from_be_ffi([0xff, 0xfe].as_ptr(), 2) }.unwrap();
+    assert_eq!(negative, -2);
+}
+
+#[test]
+fn wrong_length_is_rejected() {
+    let bytes = to_be_bytes_ffi(7u16);
+    let result: Result<u32, _> = unsafe { from_be_ffi(bytes.ptr, bytes.len) };
+    assert_eq!(result, Err("expected 4 bytes, got 2".to_string()));
+    let result: Result<u32, _> = unsafe { from_be_ffi(std::ptr::null(), 4) };
+    assert_eq!(result, Err("bytes must not be null".to_string()));
+}
+
+#[test]
+fn tagged_buffers() {
+    let big = FfiOrderedBytes::new(0x0102u16, FfiByteOrder::BigEndian);
+    assert_eq!(big.bytes.as_slice(), &[1, 2]);
+    let little = big.to_order(FfiByteOrder::LittleEndian);
+    assert_eq!(little.bytes.as_slice(), &[2, 1]);
+    // Converting twice doesn't swap twice
+    let again = little.to_order(FfiByteOrder::LittleEndian);
+    assert_eq!(again.get::<u16>(), Ok(0x0102));
+    assert_eq!(big.get::<u16>(), Ok(0x0102));
+    assert!(big.get::<u64>().is_err());
+
+    let native = FfiOrderedBytes::new(u64::MAX - 1, FfiByteOrder::NATIVE);
+    assert_eq!(native.bytes.as_slice(), &(u64::MAX - 1).to_ne_bytes()[..]);
+}
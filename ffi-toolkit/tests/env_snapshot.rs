@@ -0,0 +1,38 @@
+use std::env;
+
+use ffi_toolkit::{env_snapshot, fil_destroy_map, fil_env_snapshot, FfiMap};
+
+#[test]
+fn map() {
+    let map = FfiMap::new(vec![("a", "1"), ("b", "2")]);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get("b"), Some("2".to_string()));
+    assert_eq!(map.get("c"), None);
+    assert!(FfiMap::default().is_empty());
+}
+
+#[test]
+fn allow_listed_and_redacted() {
+    env::set_var("FIL_PROOFS_PARAMETER_CACHE", "/var/tmp/params");
+    env::set_var("FIL_API_TOKEN", "hunter2");
+    env::set_var("UNRELATED_VARIABLE", "ignored");
+
+    let snapshot = env_snapshot();
+    let get = |name: &str| {
+        snapshot
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(get("FIL_PROOFS_PARAMETER_CACHE"), Some("/var/tmp/params"));
+    assert_eq!(get("FIL_API_TOKEN"), Some("<redacted>"));
+    assert_eq!(get("UNRELATED_VARIABLE"), None);
+    assert!(snapshot.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let map = fil_env_snapshot();
+    unsafe {
+        assert_eq!((*map).get("FIL_API_TOKEN"), Some("<redacted>".to_string()));
+        assert_eq!((*map).keys.len(), (*map).values.len());
+        fil_destroy_map(map);
+    }
+}
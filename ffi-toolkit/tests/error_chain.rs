@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_result, free_raw_ptr, FCPResponseStatus, FfiErrorChain, IntoFFIError,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub error_chain: FfiErrorChain,
+}
+
+#[derive(Debug)]
+struct Cause(&'static str, Option<Box<Cause>>);
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Error for Cause {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1
+            .as_ref()
+            .map(|cause| &**cause as &(dyn Error + 'static))
+    }
+}
+
+#[test]
+fn round_trip() {
+    let chain = FfiErrorChain::new(vec![
+        (FCPResponseStatus::FCPCallerError, "invalid proof"),
+        (FCPResponseStatus::FCPUnclassifiedError, "bad length"),
+    ]);
+    assert_eq!(chain.len(), 2);
+    assert_eq!(
+        chain.codes(),
+        &[
+            FCPResponseStatus::FCPCallerError,
+            FCPResponseStatus::FCPUnclassifiedError
+        ]
+    );
+    let second = unsafe { CStr::from_ptr(*chain.messages.ptr.add(1)) };
+    assert_eq!(second.to_str().unwrap(), "bad length");
+    assert!(FfiErrorChain::default().is_empty());
+}
+
+#[test]
+fn catch_panic_result_fills_the_chain() {
+    let response: *mut SealResponse = catch_panic_result(|| {
+        let err: Box<dyn Error + Send + Sync> = Box::new(Cause(
+            "sealing failed",
+            Some(Box::new(Cause(
+                "cannot open sector",
+                Some(Box::new(Cause("permission denied", None))),
+            ))),
+        ));
+        Err(err)
+    });
+    unsafe {
+        assert_eq!(
+            (*response).error_chain.to_vec(),
+            vec![
+                (
+                    FCPResponseStatus::FCPUnclassifiedError,
+                    "sealing failed".to_string()
+                ),
+                (
+                    FCPResponseStatus::FCPUnclassifiedError,
+                    "cannot open sector".to_string()
+                ),
+                (
+                    FCPResponseStatus::FCPUnclassifiedError,
+                    "permission denied".to_string()
+                ),
+            ]
+        );
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn single_errors_have_a_chain_of_one() {
+    let err = (
+        FCPResponseStatus::FCPCallerError,
+        "no such sector".to_string(),
+    );
+    assert_eq!(err.chain(), vec![err.clone()]);
+
+    let response: *mut SealResponse = catch_panic_result(|| Err(err));
+    unsafe {
+        assert_eq!((*response).error_chain.len(), 1);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn successful_responses_have_an_empty_chain() {
+    let response: *mut SealResponse =
+        catch_panic_result(|| Ok::<_, std::io::Error>(SealResponse::default()));
+    unsafe {
+        assert!((*response).error_chain.is_empty());
+        free_raw_ptr(response);
+    }
+}
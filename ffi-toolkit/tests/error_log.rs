@@ -0,0 +1,105 @@
+#![cfg(feature = "log")]
+
+use std::ffi::CStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    c_str_to_rust_str, catch_panic_result, close_log, configure_error_logging,
+    fil_init_log_callback, flush_error_log, free_raw_ptr, ErrorLogging, FCPResponseStatus,
+    FfiDuration, FfiLogLevel, ERROR_LOG_TARGET,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SizeResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub size: u64,
+}
+
+extern "C" fn collect(
+    _level: FfiLogLevel,
+    target: *const libc::c_char,
+    message: *const libc::c_char,
+    user_data: *mut libc::c_void,
+) {
+    let records = unsafe { &*(user_data as *const Mutex<Vec<String>>) };
+    let (target, message) = unsafe { (CStr::from_ptr(target), CStr::from_ptr(message)) };
+    if target.to_str() == Ok(ERROR_LOG_TARGET) {
+        records
+            .lock()
+            .unwrap()
+            .push(message.to_string_lossy().into_owned());
+    }
+}
+
+fn fail(message: &str) {
+    let response: *mut SizeResponse = catch_panic_result(|| {
+        Err::<SizeResponse, _>((FCPResponseStatus::FCPCallerError, message.to_string()))
+    });
+    // The response gets its error whether or not it is logged
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(c_str_to_rust_str((*response).error_msg), message);
+        free_raw_ptr(response);
+    }
+}
+
+fn take(records: &Mutex<Vec<String>>) -> Vec<String> {
+    std::mem::take(&mut *records.lock().unwrap())
+}
+
+// The logger and the configuration are global, so everything is checked in one test
+#[test]
+fn repeated_errors_are_summarized() {
+    let records: &'static Mutex<Vec<String>> = Box::leak(Box::new(Mutex::new(Vec::new())));
+    assert!(fil_init_log_callback(
+        collect,
+        records as *const _ as *mut libc::c_void,
+        FfiLogLevel::Error
+    ));
+
+    // Off by default
+    fail("invalid sector");
+    assert!(take(records).is_empty());
+
+    configure_error_logging(ErrorLogging::Every);
+    fail("invalid sector");
+    fail("invalid sector");
+    assert_eq!(take(records).len(), 2);
+
+    let window = Duration::from_millis(200);
+    configure_error_logging(ErrorLogging::Deduplicated(FfiDuration::from(window)));
+    for _ in 0..5 {
+        fail("invalid sector");
+    }
+    fail("invalid proof");
+    assert_eq!(
+        take(records),
+        vec![
+            "FCPCallerError: invalid sector".to_string(),
+            "FCPCallerError: invalid proof".to_string(),
+        ]
+    );
+
+    thread::sleep(window);
+    fail("invalid sector");
+    let logged = take(records);
+    assert_eq!(logged.len(), 2, "{:?}", logged);
+    assert!(logged[0].starts_with("FCPCallerError: invalid sector (repeated 4 times in "));
+    assert_eq!(logged[1], "FCPCallerError: invalid sector");
+
+    fail("invalid sector");
+    flush_error_log();
+    let logged = take(records);
+    assert_eq!(logged.len(), 1, "{:?}", logged);
+    assert!(logged[0].starts_with("FCPCallerError: invalid sector (repeated 1 times in "));
+
+    configure_error_logging(ErrorLogging::Off);
+    fail("invalid sector");
+    assert!(take(records).is_empty());
+    close_log();
+}
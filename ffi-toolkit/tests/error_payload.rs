@@ -0,0 +1,127 @@
+use std::fmt;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    c_str_to_rust_str, catch_panic_result, error_response_with_payload, CodeAndMessage,
+    FCPResponseStatus, FfiBytes, IntoFFIError,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub error_payload: FfiBytes,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SizeResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+struct SectorError(u8);
+
+impl fmt::Display for SectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sector {} is corrupted", self.0)
+    }
+}
+
+impl IntoFFIError for SectorError {
+    fn code(&self) -> FCPResponseStatus {
+        FCPResponseStatus::FCPReceiverError
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn payload(&self) -> Option<FfiBytes> {
+        Some(FfiBytes::new(vec![self.0]))
+    }
+}
+
+#[test]
+fn the_payload_is_set_with_the_error() {
+    let response: *mut SealResponse = error_response_with_payload(
+        FCPResponseStatus::FCPCallerError,
+        "invalid sector",
+        FfiBytes::new(vec![1, 2]),
+    );
+    let response = unsafe { Box::from_raw(response) };
+    assert_eq!(response.status_code, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        unsafe { c_str_to_rust_str(response.error_msg) },
+        "invalid sector"
+    );
+    assert_eq!(response.error_payload.as_slice(), &[1, 2]);
+}
+
+#[test]
+fn responses_without_a_payload_drop_it() {
+    let mut response = SizeResponse::default();
+    response.set_error_with_payload(
+        (FCPResponseStatus::FCPCallerError, std::ptr::null()),
+        FfiBytes::new(vec![1]),
+    );
+    assert_eq!(response.status_code, FCPResponseStatus::FCPCallerError);
+}
+
+#[test]
+fn catch_panic_result_sets_the_payload_of_errors() {
+    let response: *mut SealResponse = catch_panic_result(|| Err(SectorError(7)));
+    let response = unsafe { Box::from_raw(response) };
+    assert_eq!(response.status_code, FCPResponseStatus::FCPReceiverError);
+    assert_eq!(response.error_payload.as_slice(), &[7]);
+
+    let response: *mut SealResponse =
+        catch_panic_result(|| Ok::<_, SectorError>(SealResponse::default()));
+    let response = unsafe { Box::from_raw(response) };
+    assert!(response.error_payload.is_empty());
+}
+
+#[cfg(any(feature = "cbor", feature = "json"))]
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct SectorDetails {
+    sector_id: u64,
+    retryable: bool,
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_payloads() {
+    let details = SectorDetails {
+        sector_id: 42,
+        retryable: true,
+    };
+    let response: *mut SealResponse = ffi_toolkit::error_response_with_cbor(
+        FCPResponseStatus::FCPReceiverError,
+        "sealing failed",
+        &details,
+    );
+    let response = unsafe { Box::from_raw(response) };
+    let decoded: SectorDetails = ffi_toolkit::from_cbor(response.error_payload.as_slice()).unwrap();
+    assert_eq!(decoded, details);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_payloads() {
+    let response: *mut SealResponse = ffi_toolkit::error_response_with_json(
+        FCPResponseStatus::FCPReceiverError,
+        "sealing failed",
+        &SectorDetails {
+            sector_id: 42,
+            retryable: false,
+        },
+    );
+    let response = unsafe { Box::from_raw(response) };
+    assert_eq!(
+        response.error_payload.as_slice(),
+        br#"{"sector_id":42,"retryable":false}"#
+    );
+}
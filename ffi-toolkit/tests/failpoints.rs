@@ -0,0 +1,86 @@
+#![cfg(feature = "failpoints")]
+
+use std::ffi::CStr;
+use std::ptr;
+
+use ffi_toolkit::failpoints::{self, FailAction};
+use ffi_toolkit::{
+    catch_panic_response, code_and_message_impl, fail_point, raw_ptr, CodeAndMessage,
+    FCPResponseStatus,
+};
+
+#[repr(C)]
+pub struct Response {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub replicated: bool,
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Response {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            replicated: false,
+        }
+    }
+}
+
+code_and_message_impl!(Response);
+
+fn replicate() -> *mut Response {
+    catch_panic_response(|| {
+        fail_point!("replicate::before_write");
+        raw_ptr(Response {
+            replicated: true,
+            ..Default::default()
+        })
+    })
+}
+
+unsafe fn error_message(response: *mut Response) -> String {
+    CStr::from_ptr((*response).error_msg)
+        .to_string_lossy()
+        .into_owned()
+}
+
+// The configuration is global, hence all cases run within a single test
+#[test]
+fn injected_failures() {
+    unsafe {
+        let response = replicate();
+        assert!((*response).replicated);
+
+        failpoints::configure(
+            "replicate::before_write",
+            FailAction::Error(FCPResponseStatus::FCPReceiverError, "disk full".to_string()),
+        );
+        let response = replicate();
+        assert!(!(*response).replicated);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+        assert_eq!(error_message(response), "disk full");
+
+        failpoints::configure(
+            "replicate::before_write",
+            FailAction::Panic("injected".to_string()),
+        );
+        let response = replicate();
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        failpoints::remove("replicate::before_write");
+
+        failpoints::configure(
+            "catch_panic_response",
+            FailAction::Error(FCPResponseStatus::FCPCallerError, "rejected".to_string()),
+        );
+        let response = replicate();
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(error_message(response), "rejected");
+
+        failpoints::clear();
+        let response = replicate();
+        assert!((*response).replicated);
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+
+use ffi_toolkit::{borrow_fd, take_fd, BorrowedFfiFd, OwnedFfiFd};
+
+fn pipe() -> (File, OwnedFfiFd) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    unsafe { (File::from_raw_fd(fds[0]), OwnedFfiFd::from_raw_fd(fds[1])) }
+}
+
+#[test]
+fn import_duplicates() {
+    let path = std::env::temp_dir().join(format!("ffi-toolkit-fd-{}", std::process::id()));
+    fs::write(&path, b"unsealed").unwrap();
+    let host_file = File::open(&path).unwrap();
+
+    let borrowed = BorrowedFfiFd(host_file.as_raw_fd());
+    assert!(borrowed.is_valid());
+    let owned = borrowed.import().unwrap();
+    assert_ne!(owned.as_raw_fd(), host_file.as_raw_fd());
+    // The host closing its descriptor doesn't affect the imported one
+    drop(host_file);
+
+    let mut contents = String::new();
+    owned.into_file().read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "unsealed");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_descriptors() {
+    assert!(!BorrowedFfiFd(-1).is_valid());
+    assert_eq!(
+        BorrowedFfiFd(-1).import().unwrap_err().raw_os_error(),
+        Some(libc::EBADF)
+    );
+}
+
+#[test]
+fn closes_on_drop() {
+    let (mut reader, writer) = pipe();
+    let mut file = writer.borrow().import().unwrap().into_file();
+    file.write_all(b"x").unwrap();
+    drop(file);
+    drop(writer);
+    // All write ends are closed, so the read ends after the written byte
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"x");
+}
+
+#[test]
+fn borrowed_descriptors_stay_open() {
+    let (mut reader, writer) = pipe();
+    let mut file = borrow_fd(writer.as_raw_fd()).unwrap();
+    file.write_all(b"sealed").unwrap();
+    drop(file);
+    // The host's descriptor is still open and usable
+    let mut host_file = writer.into_file();
+    host_file.write_all(b" sector").unwrap();
+    drop(host_file);
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "sealed sector");
+}
+
+#[test]
+fn taken_descriptors_are_closed_with_the_file() {
+    let (mut reader, writer) = pipe();
+    let mut file = unsafe { take_fd(writer.into_raw()) }.unwrap();
+    file.write_all(b"x").unwrap();
+    drop(file);
+    // The only write end was closed with the file
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"x");
+
+    assert_eq!(
+        unsafe { take_fd(-1) }.unwrap_err().raw_os_error(),
+        Some(libc::EBADF)
+    );
+    assert_eq!(borrow_fd(-1).unwrap_err().raw_os_error(), Some(libc::EBADF));
+}
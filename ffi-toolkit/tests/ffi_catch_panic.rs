@@ -0,0 +1,73 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::{ffi_catch_panic, DropStructMacro};
+use ffi_toolkit::{
+    code_and_message_impl, free_c_str, free_raw_ptr, raw_ptr, CodeAndMessage, FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+impl Default for SealResponse {
+    fn default() -> Self {
+        SealResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            sector_id: 0,
+        }
+    }
+}
+
+code_and_message_impl!(SealResponse);
+
+#[ffi_catch_panic]
+unsafe extern "C" fn seal(sector_id: u64, fail: bool) -> *mut SealResponse {
+    if sector_id == 0 {
+        return raw_ptr(SealResponse::default());
+    }
+    if fail {
+        panic!("sector is corrupt");
+    }
+    raw_ptr(SealResponse {
+        sector_id,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn body_runs_unchanged() {
+    let prototype: unsafe extern "C" fn(u64, bool) -> *mut SealResponse = seal;
+    unsafe {
+        let response = prototype(7, false);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).sector_id, 7);
+        free_raw_ptr(response);
+
+        // Early returns return from the function
+        let response = seal(0, true);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn panics_become_error_responses() {
+    unsafe {
+        let response = seal(7, true);
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        assert_eq!(
+            CStr::from_ptr((*response).error_msg).to_str().unwrap(),
+            "Rust panic: sector is corrupt"
+        );
+        free_raw_ptr(response);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use drop_struct_macro_derive::{FFIErrorCode, FFIResponse};
+use ffi_toolkit::{catch_panic_result, free_raw_ptr, FCPResponseStatus, IntoFFIError};
+
+#[derive(Debug, FFIErrorCode)]
+#[ffi_error(code = "FCPReceiverError")]
+enum SealError {
+    #[ffi_error(code = "FCPCallerError")]
+    InvalidSectorSize(u64),
+    #[ffi_error(code = "FCPBusyError")]
+    Busy {
+        retry_after_ms: u64,
+    },
+    OutOfSpace,
+}
+
+impl fmt::Display for SealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SealError::InvalidSectorSize(size) => write!(f, "invalid sector size {}", size),
+            SealError::Busy { retry_after_ms } => write!(f, "busy, retry in {}ms", retry_after_ms),
+            SealError::OutOfSpace => write!(f, "out of space"),
+        }
+    }
+}
+
+#[derive(Debug, FFIErrorCode)]
+enum UnsealError {
+    Corrupted,
+}
+
+impl fmt::Display for UnsealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "corrupted")
+    }
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn variants_map_to_their_code() {
+    let err = SealError::InvalidSectorSize(1000);
+    assert_eq!(err.code(), FCPResponseStatus::FCPCallerError);
+    assert_eq!(err.message(), "invalid sector size 1000");
+    let err = SealError::Busy { retry_after_ms: 50 };
+    assert_eq!(err.code(), FCPResponseStatus::FCPBusyError);
+    assert_eq!(err.message(), "busy, retry in 50ms");
+}
+
+#[test]
+fn defaults() {
+    assert_eq!(
+        SealError::OutOfSpace.code(),
+        FCPResponseStatus::FCPReceiverError
+    );
+    assert_eq!(
+        UnsealError::Corrupted.code(),
+        FCPResponseStatus::FCPUnclassifiedError
+    );
+}
+
+#[test]
+fn composes_with_catch_panic_result() {
+    let response: *mut SealResponse = catch_panic_result(|| Err(SealError::InvalidSectorSize(7)));
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        free_raw_ptr(response);
+    }
+}
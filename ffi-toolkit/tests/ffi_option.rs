@@ -0,0 +1,32 @@
+use std::mem;
+
+use ffi_toolkit::{FfiOption, FfiOptionBool, FfiOptionF64, FfiOptionU64};
+
+#[test]
+fn round_trips_options() {
+    for option in [None, Some(false), Some(true)] {
+        assert_eq!(Option::from(FfiOptionBool::from(option)), option);
+    }
+    for option in [None, Some(0), Some(u64::MAX)] {
+        assert_eq!(FfiOptionU64::from(option).into_option(), option);
+    }
+    assert_eq!(FfiOptionF64::from(Some(0.5)).into_option(), Some(0.5));
+}
+
+#[test]
+fn none_ignores_the_value() {
+    assert_eq!(FfiOptionU64::default(), FfiOption::none());
+    assert_eq!(FfiOptionU64::none().value, 0);
+    let from_c = FfiOptionU64 {
+        is_some: false,
+        value: 42,
+    };
+    assert_eq!(from_c.into_option(), None);
+}
+
+#[test]
+fn has_a_c_layout() {
+    assert_eq!(mem::size_of::<FfiOptionBool>(), 2);
+    assert_eq!(mem::size_of::<FfiOptionU64>(), 16);
+    assert_eq!(mem::align_of::<FfiOptionF64>(), mem::align_of::<f64>());
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{catch_panic_response, raw_ptr, track_ffi_memory, FCPResponseStatus};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(destroy = "fil_destroy_seal_response")]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub proof_ptr: *const u8,
+    pub proof_len: libc::size_t,
+    pub sector_id: u64,
+    #[ffi_drop(skip)]
+    pub scratch: *mut u8,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct UnsealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn default_is_empty() {
+    let response = SealResponse::default();
+    assert_eq!(response.status_code, FCPResponseStatus::FCPNoError);
+    assert!(response.error_msg.is_null());
+    assert!(response.proof_ptr.is_null());
+    assert_eq!(response.proof_len, 0);
+    assert_eq!(response.sector_id, 0);
+    assert!(response.scratch.is_null());
+}
+
+#[test]
+fn panics_become_error_responses() {
+    track_ffi_memory! {
+        let response: *mut SealResponse = catch_panic_response(|| panic!("out of space"));
+        unsafe {
+            assert_eq!((*response).status_code, FCPResponseStatus::FCPUnclassifiedError);
+            assert_eq!(
+                CStr::from_ptr((*response).error_msg).to_str().unwrap(),
+                "Rust panic: out of space"
+            );
+            fil_destroy_seal_response(response);
+        }
+    };
+}
+
+#[test]
+fn destroy_frees_the_payload() {
+    track_ffi_memory! {
+        let mut proof = vec![1u8; 192];
+        proof.shrink_to_fit();
+        let response = raw_ptr(SealResponse {
+            proof_len: proof.len(),
+            proof_ptr: proof.leak().as_mut_ptr(),
+            ..Default::default()
+        });
+        unsafe { fil_destroy_seal_response(response) };
+    };
+}
+
+#[test]
+fn destroy_has_a_default_name() {
+    unsafe {
+        destroy_unseal_response(raw_ptr(UnsealResponse::default()));
+        destroy_unseal_response(ptr::null_mut());
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+
+use ffi_toolkit::{
+    catch_panic_result, ffi_result_type, raw_ptr, track_ffi_memory, FCPResponseStatus, FfiBytes,
+};
+
+ffi_result_type!(
+    /// The replica of a sealed sector
+    ReplicaResponse,
+    FfiBytes
+);
+
+ffi_result_type!(SectorCountResponse, u64);
+
+#[test]
+fn values_are_payloads() {
+    track_ffi_memory! {
+        let replica = FfiBytes::from(vec![7u8; 32]);
+        let response = ReplicaResponse::from_result(Ok::<_, std::io::Error>(replica));
+        assert_eq!(response.status_code, FCPResponseStatus::FCPNoError);
+        assert!(response.error_msg.is_null());
+        assert_eq!(response.value.as_slice(), [7u8; 32]);
+        unsafe { destroy_replica_response(raw_ptr(response)) };
+    };
+}
+
+#[test]
+fn errors_have_the_default_payload() {
+    track_ffi_memory! {
+        let err = (FCPResponseStatus::FCPCallerError, "unknown sector".to_string());
+        let response = SectorCountResponse::from_result(Err(err));
+        assert_eq!(response.status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(
+            unsafe { CStr::from_ptr(response.error_msg) }.to_str(),
+            Ok("unknown sector")
+        );
+        assert_eq!(response.value, 0);
+    };
+}
+
+#[test]
+fn works_with_catch_panic_result() {
+    track_ffi_memory! {
+        let response: *mut SectorCountResponse = catch_panic_result(|| {
+            Err::<SectorCountResponse, _>(std::io::Error::other("disk full"))
+        });
+        unsafe {
+            assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+            destroy_sector_count_response(response);
+        }
+    };
+}
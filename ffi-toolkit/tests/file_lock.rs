@@ -0,0 +1,122 @@
+#![cfg(unix)]
+
+use std::ffi::CString;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ffi_toolkit::lifecycle;
+use ffi_toolkit::{
+    fil_destroy_lock_file_response, fil_lock_file, fil_unlock_file, FCPResponseStatus, FfiDuration,
+    FfiLockStatus, FileLock, LockError, TempDir,
+};
+
+// `shutdown()` releases the locks of all tests
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// returns the lock status and the handle
+unsafe fn lock(path: &CString, exclusive: bool, millis: u64) -> (FfiLockStatus, *mut FileLock) {
+    let timeout = FfiDuration::from(Duration::from_millis(millis));
+    let response = fil_lock_file(path.as_ptr(), exclusive, timeout);
+    let result = ((*response).lock_status, (*response).handle);
+    match result.0 {
+        FfiLockStatus::Locked => assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError),
+        _ => {
+            assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+            assert!(!(*response).error_msg.is_null());
+        }
+    }
+    fil_destroy_lock_file_response(response);
+    result
+}
+
+fn lock_path(dir: &TempDir) -> CString {
+    CString::new(dir.path().join("params.lock").to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn exclusive_lock_is_contended_until_unlocked() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-lock").unwrap();
+    let path = lock_path(&dir);
+    unsafe {
+        let (status, handle) = lock(&path, true, 0);
+        assert_eq!(status, FfiLockStatus::Locked);
+
+        let start = Instant::now();
+        let (status, contended) = lock(&path, true, 50);
+        assert_eq!(status, FfiLockStatus::Contended);
+        assert!(contended.is_null());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(lock(&path, false, 0).0, FfiLockStatus::Contended);
+
+        fil_unlock_file(handle);
+        let (status, handle) = lock(&path, true, 0);
+        assert_eq!(status, FfiLockStatus::Locked);
+        fil_unlock_file(handle);
+    }
+}
+
+#[test]
+fn shared_locks_coexist() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-lock").unwrap();
+    let path = dir.path().join("params.lock");
+    let first = FileLock::acquire(&path, false, Duration::ZERO).unwrap();
+    let second = FileLock::acquire(&path, false, Duration::ZERO).unwrap();
+    assert!(matches!(
+        FileLock::acquire(&path, true, Duration::ZERO),
+        Err(LockError::Contended)
+    ));
+    drop((first, second));
+    FileLock::acquire(&path, true, Duration::ZERO).unwrap();
+}
+
+#[test]
+fn waits_for_release_within_timeout() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-lock").unwrap();
+    let path = dir.path().join("params.lock");
+    let held = FileLock::acquire(&path, true, Duration::ZERO).unwrap();
+    let waiter = {
+        let path = path.clone();
+        std::thread::spawn(move || FileLock::acquire(&path, true, Duration::from_secs(10)))
+    };
+    std::thread::sleep(Duration::from_millis(30));
+    drop(held);
+    assert!(waiter.join().unwrap().is_ok());
+}
+
+#[test]
+fn io_error_is_distinguished_from_contention() {
+    let _serial = SERIAL.lock().unwrap();
+    let dir = TempDir::create("fil-lock").unwrap();
+    let path = CString::new(
+        dir.path()
+            .join("missing")
+            .join("params.lock")
+            .to_str()
+            .unwrap(),
+    )
+    .unwrap();
+    let (status, handle) = unsafe { lock(&path, true, 0) };
+    assert_eq!(status, FfiLockStatus::IoError);
+    assert!(handle.is_null());
+}
+
+#[test]
+fn shutdown_releases_remaining_locks() {
+    let _serial = SERIAL.lock().unwrap();
+    // Not in a `TempDir`, the shutdown would remove it
+    let path = std::env::temp_dir().join(format!("fil-lock-{}.lock", std::process::id()));
+    let held = FileLock::acquire(&path, true, Duration::ZERO).unwrap();
+    assert!(held.is_held());
+    lifecycle::shutdown();
+    assert!(!held.is_held());
+    let again = FileLock::acquire(&path, true, Duration::ZERO).unwrap();
+    assert!(again.is_held());
+    // Dropping a handle released by the shutdown is fine
+    drop(held);
+    assert!(again.is_held());
+    drop(again);
+    std::fs::remove_file(path).unwrap();
+}
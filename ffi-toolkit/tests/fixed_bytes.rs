@@ -0,0 +1,61 @@
+#![cfg(feature = "testing")]
+
+use std::convert::TryFrom;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{track_ffi_memory, ByteLengthError, FfiCommitment, Fil32, Fil48, Fil96};
+
+#[test]
+fn slices_of_the_wrong_length_are_rejected() {
+    assert_eq!(
+        Fil48::try_from_slice(&[1; 48]).unwrap(),
+        Fil48::from([1; 48])
+    );
+    assert_eq!(
+        Fil96::try_from(&[0u8; 95][..]).unwrap_err(),
+        ByteLengthError {
+            type_name: "Fil96",
+            expected: 96,
+            actual: 95,
+        }
+    );
+    assert_eq!(
+        Fil32::try_from_slice(&[]).unwrap_err().to_string(),
+        "`Fil32` has 32 bytes, got 0"
+    );
+}
+
+#[test]
+fn conversions() {
+    let randomness = Fil32::from([9; 32]);
+    assert_eq!(<[u8; 32]>::from(randomness), [9; 32]);
+    assert_eq!(Fil32::from(FfiCommitment::from(randomness)), randomness);
+    assert_ne!(randomness, Fil32::default());
+    assert_eq!(Fil96::default().as_bytes(), &[0; 96]);
+    assert_eq!(
+        format!("{:?}", Fil32::from([0xab; 32])),
+        format!("Fil32({})", "ab".repeat(32))
+    );
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct AggregateResponse {
+    pub signature: Fil96,
+    pub public_keys_ptr: *const Fil48,
+    pub public_keys_len: libc::size_t,
+}
+
+#[test]
+fn responses_can_hold_them() {
+    track_ffi_memory! {
+        let public_keys = vec![Fil48::from([1; 48]), Fil48::from([2; 48])].into_boxed_slice();
+        let public_keys_len = public_keys.len();
+        let response = AggregateResponse {
+            signature: Fil96::from([3; 96]),
+            public_keys_ptr: Box::into_raw(public_keys) as *const Fil48,
+            public_keys_len,
+        };
+        drop(response);
+    };
+}
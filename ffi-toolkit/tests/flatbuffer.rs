@@ -0,0 +1,68 @@
+#![cfg(feature = "flatbuffers")]
+
+use ffi_toolkit::{
+    flatbuffer_view, flatbuffer_view_raw, flatbuffer_view_with_opts, FlatbufferError,
+};
+use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector, VerifierOptions};
+
+// A buffer whose root is a vector of sector numbers
+fn sector_numbers(numbers: &[u64]) -> Vec<u8> {
+    let mut builder = FlatBufferBuilder::new();
+    let vector = builder.create_vector(numbers);
+    builder.finish_minimal(vector);
+    builder.finished_data().to_vec()
+}
+
+#[test]
+fn view_without_copy() {
+    let bytes = sector_numbers(&[1, 2, 3]);
+    let view = flatbuffer_view::<Vector<u64>>(&bytes).unwrap();
+    assert_eq!(view.iter().collect::<Vec<u64>>(), vec![1, 2, 3]);
+    // The view points into the buffer
+    let range = bytes.as_ptr_range();
+    assert!(range.contains(&view.bytes().as_ptr()));
+
+    let names = {
+        let mut builder = FlatBufferBuilder::new();
+        let names = [builder.create_string("a"), builder.create_string("bc")];
+        let vector = builder.create_vector(&names);
+        builder.finish_minimal(vector);
+        builder.finished_data().to_vec()
+    };
+    let view = unsafe {
+        flatbuffer_view_raw::<Vector<ForwardsUOffset<&str>>>(names.as_ptr(), names.len())
+    }
+    .unwrap();
+    assert_eq!(view.iter().collect::<Vec<&str>>(), vec!["a", "bc"]);
+}
+
+#[test]
+fn out_of_bounds_is_rejected() {
+    let bytes = sector_numbers(&[1, 2, 3]);
+    let truncated = &bytes[..bytes.len() - 4];
+    let err = flatbuffer_view::<Vector<u64>>(truncated).unwrap_err();
+    assert!(matches!(err, FlatbufferError::Invalid(_)));
+    assert!(
+        err.to_string().starts_with("invalid flatbuffer: "),
+        "{}",
+        err
+    );
+
+    let mut corrupt = bytes.clone();
+    // The offset of the root points beyond the end
+    corrupt[0] = 0xff;
+    assert!(flatbuffer_view::<Vector<u64>>(&corrupt).is_err());
+
+    let err = unsafe { flatbuffer_view_raw::<Vector<u64>>(std::ptr::null(), 0) }.unwrap_err();
+    assert_eq!(err, FlatbufferError::Null);
+}
+
+#[test]
+fn limits() {
+    let bytes = sector_numbers(&[0; 100]);
+    let opts = VerifierOptions {
+        max_apparent_size: 64,
+        ..Default::default()
+    };
+    assert!(flatbuffer_view_with_opts::<Vector<u64>>(&bytes, &opts).is_err());
+}
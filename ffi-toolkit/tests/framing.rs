@@ -0,0 +1,80 @@
+use std::io::Cursor;
+
+use ffi_toolkit::{
+    decode_length_delimited_all, encode_length_delimited_all, read_length_delimited,
+    write_length_delimited, FrameError, MessageCodec, MAX_MESSAGE_LEN,
+};
+
+// A message with a single string field 1, encoded like protobuf would
+#[derive(PartialEq, Debug)]
+struct Name(String);
+
+impl MessageCodec for Name {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(0x0a);
+        buf.push(self.0.len() as u8);
+        buf.extend_from_slice(self.0.as_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        match bytes {
+            [0x0a, len, rest @ ..] if *len as usize == rest.len() => {
+                String::from_utf8(rest.to_vec())
+                    .map(Name)
+                    .map_err(|err| err.to_string())
+            }
+            _ => Err("unexpected field".to_string()),
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    let messages = vec![Name("sector".to_string()), Name(String::new())];
+    let bytes = encode_length_delimited_all(&messages);
+    assert_eq!(&bytes.as_slice()[..3], &[8, 0x0a, 6]);
+    let decoded: Vec<Name> =
+        decode_length_delimited_all(bytes.as_slice(), MAX_MESSAGE_LEN).unwrap();
+    assert_eq!(decoded, messages);
+}
+
+#[test]
+fn stream() {
+    let mut stream = Vec::new();
+    let long = Name("x".repeat(127));
+    write_length_delimited(&mut stream, &long).unwrap();
+    // 129 bytes need two bytes of length prefix
+    assert_eq!(&stream[..2], &[0x81, 0x01]);
+    let mut reader = Cursor::new(stream);
+    assert_eq!(
+        read_length_delimited::<Name, _>(&mut reader, MAX_MESSAGE_LEN).unwrap(),
+        Some(long)
+    );
+    assert!(
+        read_length_delimited::<Name, _>(&mut reader, MAX_MESSAGE_LEN)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn errors() {
+    let bytes = encode_length_delimited_all(&[Name("abc".to_string())]);
+    let truncated = &bytes.as_slice()[..bytes.len() - 1];
+    assert!(matches!(
+        decode_length_delimited_all::<Name>(truncated, MAX_MESSAGE_LEN),
+        Err(FrameError::Truncated)
+    ));
+    assert!(matches!(
+        decode_length_delimited_all::<Name>(bytes.as_slice(), 2),
+        Err(FrameError::TooLong { len: 5, max: 2 })
+    ));
+    assert!(matches!(
+        decode_length_delimited_all::<Name>(&[0xff; 11], MAX_MESSAGE_LEN),
+        Err(FrameError::InvalidLength)
+    ));
+    assert!(matches!(
+        decode_length_delimited_all::<Name>(&[1, 0x12], MAX_MESSAGE_LEN),
+        Err(FrameError::Decode(_))
+    ));
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "fuzz-support")]
+
+use std::ffi::CStr;
+
+use arbitrary::{Arbitrary, Unstructured};
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::fuzz::{self, fuzz_export, fuzz_extern, FuzzCStr, FuzzSlice, SliceShape};
+use ffi_toolkit::{
+    c_str_to_rust_str, catch_panic_response, free_raw_ptr, raw_ptr, rust_str_to_c_str,
+    try_slice_from_raw, CodeAndMessage, FCPResponseStatus, FfiDuration,
+};
+
+#[test]
+fn targets_accept_hostile_inputs() {
+    for input in fuzz::hostile_inputs() {
+        for (_, target) in fuzz::TARGETS {
+            target(&input);
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct CountResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub count: u64,
+}
+
+// Counts the bytes of `name` and the elements of `values`, rejecting what it can't read
+#[no_mangle]
+unsafe extern "C" fn fil_fuzzed_count(
+    name: *const libc::c_char,
+    values_ptr: *const u64,
+    values_len: libc::size_t,
+) -> *mut CountResponse {
+    catch_panic_response(|| {
+        let mut response = CountResponse::default();
+        match try_slice_from_raw(values_ptr, values_len) {
+            Some(values) if !name.is_null() => {
+                response.count = values.len() as u64 + c_str_to_rust_str(name).len() as u64;
+            }
+            _ => response.set_error((
+                FCPResponseStatus::FCPCallerError,
+                rust_str_to_c_str("invalid arguments"),
+            )),
+        }
+        raw_ptr(response)
+    })
+}
+
+unsafe extern "C" fn fil_destroy_fuzzed_count(ptr: *mut CountResponse) {
+    free_raw_ptr(ptr)
+}
+
+#[test]
+fn slices_are_generated_in_all_shapes() {
+    let elements = vec![1u64, 2, 3];
+    let unaligned = FuzzSlice::new(elements.clone(), SliceShape::Unaligned);
+    assert_ne!(unaligned.as_ptr() as usize % std::mem::align_of::<u64>(), 0);
+    assert_eq!(unaligned.len(), 3);
+    assert_eq!(
+        FuzzSlice::new(elements.clone(), SliceShape::Shorter).len(),
+        1
+    );
+    assert!(FuzzSlice::new(elements.clone(), SliceShape::NullWithLength)
+        .as_ptr()
+        .is_null());
+    let overflowing = FuzzSlice::new(elements, SliceShape::OverflowingLength);
+    assert!(overflowing.len().saturating_mul(8) > isize::MAX as usize);
+    assert!(unsafe { try_slice_from_raw(overflowing.as_ptr(), overflowing.len()) }.is_none());
+
+    // Bytes are always aligned
+    let bytes = FuzzSlice::new(b"abc".to_vec(), SliceShape::Unaligned);
+    assert_eq!(bytes.shape(), SliceShape::Valid);
+    assert_eq!(unsafe { bytes.as_string_ref().as_bytes() }, b"abc");
+}
+
+#[test]
+fn c_strings_include_nulls_and_hostile_bytes() {
+    let mut nulls = 0;
+    let mut invalid_utf8 = 0;
+    for seed in 0..256u32 {
+        let data: Vec<u8> = (0..64u32)
+            .map(|i| (seed.wrapping_mul(31).wrapping_add(i * 17) % 251) as u8)
+            .collect();
+        let c_str = FuzzCStr::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        match c_str.bytes() {
+            None => {
+                assert!(c_str.as_ptr().is_null());
+                nulls += 1;
+            }
+            Some(bytes) => {
+                let c_bytes = unsafe { CStr::from_ptr(c_str.as_ptr()) }.to_bytes();
+                assert!(bytes.starts_with(c_bytes));
+                if std::str::from_utf8(bytes).is_err() {
+                    invalid_utf8 += 1;
+                }
+            }
+        }
+    }
+    assert!(nulls > 0);
+    assert!(invalid_utf8 > 0);
+}
+
+#[test]
+fn exports_are_driven_with_generated_arguments() {
+    for seed in 0..128u8 {
+        let data: Vec<u8> = (0..96).map(|i: u8| i.wrapping_mul(seed) ^ seed).collect();
+        fuzz_export(
+            &data,
+            |(name, values): &(FuzzCStr, FuzzSlice<u64>)| unsafe {
+                fil_fuzzed_count(name.as_ptr(), values.as_ptr(), values.len())
+            },
+            fil_destroy_fuzzed_count,
+        );
+    }
+    assert_eq!(
+        fuzz_extern(&[], |duration: &FfiDuration| duration.secs),
+        Some(0)
+    );
+}
@@ -0,0 +1,119 @@
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    free_raw_ptr, is_live, lifecycle, register, release, with_handle, FCPResponseStatus,
+    HandleError,
+};
+
+// `shutdown()` releases all handles
+static SERIAL: Mutex<()> = Mutex::new(());
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Prover {
+    proofs: u64,
+}
+
+impl Drop for Prover {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct ProveResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[test]
+fn register_use_release() {
+    let _serial = serial();
+    let handle = register(Prover { proofs: 0 });
+    assert_ne!(handle, 0);
+    assert!(is_live(handle));
+    with_handle(handle, |prover: &mut Prover| prover.proofs += 1).unwrap();
+    assert_eq!(
+        with_handle(handle, |prover: &mut Prover| prover.proofs),
+        Ok(1)
+    );
+
+    let dropped = DROPPED.load(Ordering::SeqCst);
+    release::<Prover>(handle).unwrap();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), dropped + 1);
+    assert!(!is_live(handle));
+}
+
+#[test]
+fn stale_handles_are_rejected() {
+    let _serial = serial();
+    let handle = register(Prover { proofs: 0 });
+    release::<Prover>(handle).unwrap();
+    assert_eq!(release::<Prover>(handle), Err(HandleError::Stale));
+    assert_eq!(
+        with_handle(handle, |prover: &mut Prover| prover.proofs),
+        Err(HandleError::Stale)
+    );
+
+    // The slot is reused with a new generation
+    let reused = register(Prover { proofs: 5 });
+    assert_ne!(reused, handle);
+    assert_eq!(
+        with_handle(handle, |prover: &mut Prover| prover.proofs),
+        Err(HandleError::Stale)
+    );
+    release::<Prover>(reused).unwrap();
+
+    assert_eq!(release::<Prover>(0), Err(HandleError::Stale));
+    assert_eq!(release::<Prover>(u64::MAX), Err(HandleError::Stale));
+}
+
+#[test]
+fn types_are_checked() {
+    let _serial = serial();
+    let handle = register(String::from("cache"));
+    let err = with_handle(handle, |prover: &mut Prover| prover.proofs).unwrap_err();
+    assert!(matches!(err, HandleError::WrongType { expected } if expected.ends_with("Prover")));
+    assert!(release::<Prover>(handle).is_err());
+    assert!(is_live(handle));
+    release::<String>(handle).unwrap();
+}
+
+#[test]
+fn stale_handles_are_caller_errors() {
+    let response: *mut ProveResponse = HandleError::Stale.caller_error("prover");
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(
+            CStr::from_ptr((*response).error_msg).to_str().unwrap(),
+            "invalid argument `prover`: unknown or released handle"
+        );
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn shutdown_releases_all_handles() {
+    let _serial = serial();
+    let handle = register(Prover { proofs: 0 });
+    let dropped = DROPPED.load(Ordering::SeqCst);
+    lifecycle::shutdown();
+    assert_eq!(DROPPED.load(Ordering::SeqCst), dropped + 1);
+    assert!(!is_live(handle));
+
+    // Handles of before the shutdown stay stale
+    let after = register(Prover { proofs: 0 });
+    assert_ne!(after, handle);
+    assert_eq!(release::<Prover>(handle), Err(HandleError::Stale));
+    release::<Prover>(after).unwrap();
+}
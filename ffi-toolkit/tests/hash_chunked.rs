@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::Cursor;
+
+use ffi_toolkit::{
+    hash_chunked, hash_file_chunked, ChunkedDigest, FfiBytes, HashError, HashProgress, TempDir,
+};
+
+// Not a real hash function, but it sees every byte and the chunk boundaries
+#[derive(Default)]
+struct Fletcher {
+    sum: u8,
+    sum_of_sums: u8,
+    chunks: u8,
+}
+
+impl ChunkedDigest for Fletcher {
+    fn update(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            self.sum = self.sum.wrapping_add(byte);
+            self.sum_of_sums = self.sum_of_sums.wrapping_add(self.sum);
+        }
+        self.chunks += 1;
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        vec![self.sum, self.sum_of_sums, self.chunks]
+    }
+}
+
+#[test]
+fn hashes_in_chunks_with_progress() {
+    let data: Vec<u8> = (0..10).collect();
+    let mut reported = Vec::new();
+    let digest = hash_chunked(
+        Cursor::new(&data),
+        Fletcher::default(),
+        4,
+        Some(10),
+        |progress| {
+            reported.push(progress);
+            true
+        },
+    )
+    .unwrap();
+    assert_eq!(digest.as_slice(), &[45, 165, 3]);
+    let processed: Vec<u64> = reported.iter().map(|progress| progress.processed).collect();
+    assert_eq!(processed, vec![4, 8, 10]);
+    assert_eq!(reported[0].total, Some(10));
+}
+
+#[test]
+fn cancellation() {
+    let data = vec![1u8; 100];
+    let result = hash_chunked(
+        Cursor::new(&data),
+        Fletcher::default(),
+        10,
+        None,
+        |progress| progress.processed < 30,
+    );
+    assert!(matches!(result, Err(HashError::Cancelled)));
+}
+
+#[test]
+fn hash_file() {
+    let dir = TempDir::create("fil-hash").unwrap();
+    let path = dir.path().join("sector");
+    fs::write(&path, (0..10).collect::<Vec<u8>>()).unwrap();
+    let mut last = None;
+    let digest = hash_file_chunked(&path, Fletcher::default(), |progress| {
+        last = Some(progress);
+        true
+    })
+    .unwrap();
+    assert_eq!(digest.to_vec(), vec![45, 165, 1]);
+    assert_eq!(
+        last,
+        Some(HashProgress {
+            processed: 10,
+            total: Some(10)
+        })
+    );
+
+    let missing = hash_file_chunked(&dir.path().join("missing"), Fletcher::default(), |_| true);
+    assert!(matches!(missing, Err(HashError::Io(_))));
+}
+
+#[test]
+fn ffi_bytes() {
+    let bytes = FfiBytes::from(vec![1, 2, 3]);
+    assert_eq!(bytes.len(), 3);
+    assert_eq!(bytes.as_slice(), &[1, 2, 3]);
+    let empty = FfiBytes::default();
+    assert!(empty.is_empty());
+    assert!(!empty.ptr.is_null());
+}
@@ -0,0 +1,76 @@
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    error_response, fil_set_allocator, free_c_str, free_raw_ptr, free_secret_c_str, free_w_str,
+    raw_ptr, rust_str_to_c_str, rust_string_to_wstr, wstr_to_rust_string, FCPResponseStatus,
+};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static FREED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn host_malloc(size: libc::size_t, align: libc::size_t) -> *mut libc::c_void {
+    ALLOCATED.fetch_add(1, Ordering::SeqCst);
+    let mut ptr = std::ptr::null_mut();
+    let align = align.max(std::mem::size_of::<*mut libc::c_void>());
+    if unsafe { libc::posix_memalign(&mut ptr, align, size) } != 0 {
+        return std::ptr::null_mut();
+    }
+    ptr
+}
+
+extern "C" fn host_free(ptr: *mut libc::c_void) {
+    FREED.fetch_add(1, Ordering::SeqCst);
+    unsafe { libc::free(ptr) };
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub comm_r: *const libc::c_char,
+}
+
+#[repr(align(64))]
+struct Aligned([u8; 64]);
+
+// A single test, the allocator has to be set before anything is allocated
+#[test]
+fn allocations_go_through_the_host() {
+    assert!(fil_set_allocator(host_malloc, host_free));
+    assert!(!fil_set_allocator(host_malloc, host_free));
+
+    let c_str = rust_str_to_c_str("bafk2bzace");
+    assert_eq!(unsafe { CStr::from_ptr(c_str) }.to_str(), Ok("bafk2bzace"));
+    unsafe { free_c_str(c_str) };
+    let secret = rust_str_to_c_str("private key");
+    unsafe { free_secret_c_str(secret) };
+    let wide = rust_string_to_wstr("C:\\proofs");
+    assert_eq!(unsafe { wstr_to_rust_string(wide) }, "C:\\proofs");
+    unsafe { free_w_str(wide) };
+    assert_eq!(ALLOCATED.load(Ordering::SeqCst), 3);
+    assert_eq!(FREED.load(Ordering::SeqCst), 3);
+
+    // The response and both of its strings, freed by the derived `Drop`
+    let response: *mut SealResponse =
+        error_response(FCPResponseStatus::FCPReceiverError, "disk full");
+    unsafe {
+        (*response).comm_r = rust_str_to_c_str("comm_r");
+        free_raw_ptr(response);
+    }
+    assert_eq!(ALLOCATED.load(Ordering::SeqCst), 6);
+    assert_eq!(FREED.load(Ordering::SeqCst), 6);
+
+    let aligned = raw_ptr(Aligned([7; 64]));
+    assert_eq!(aligned as usize % 64, 0);
+    unsafe {
+        assert_eq!((*aligned).0, [7; 64]);
+        free_raw_ptr(aligned);
+    }
+    let unit = raw_ptr(());
+    unsafe { free_raw_ptr(unit) };
+    assert_eq!(ALLOCATED.load(Ordering::SeqCst), 8);
+    assert_eq!(FREED.load(Ordering::SeqCst), 8);
+}
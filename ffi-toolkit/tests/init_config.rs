@@ -0,0 +1,91 @@
+use std::ptr;
+
+use ffi_toolkit::{
+    alloc_stats_enabled, fil_init, init_config, last_error, lifecycle, parse_ffi_config,
+    FCPResponseStatus, FfiConfig, InitError, StringRef, FFI_CONFIG_VERSION,
+};
+
+extern "C" fn host_free(_ptr: *mut libc::c_void) {}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let too_small = FfiConfig {
+        size: 8,
+        ..Default::default()
+    };
+    assert_eq!(
+        unsafe { parse_ffi_config(&too_small) }.unwrap_err(),
+        InitError::UnsupportedConfig {
+            version: FFI_CONFIG_VERSION,
+            size: 8
+        }
+    );
+    let half_an_allocator = FfiConfig {
+        free: Some(host_free),
+        ..Default::default()
+    };
+    assert_eq!(
+        unsafe { parse_ffi_config(&half_an_allocator) }.unwrap_err(),
+        InitError::IncompleteAllocator
+    );
+    let log_level = FfiConfig {
+        log_level: 9,
+        ..Default::default()
+    };
+    assert_eq!(
+        unsafe { parse_ffi_config(&log_level) }.unwrap_err(),
+        InitError::InvalidLogLevel(9)
+    );
+    let keys = [StringRef::from_str("a")];
+    let missing_values = FfiConfig {
+        settings_keys_ptr: keys.as_ptr(),
+        settings_len: 1,
+        ..Default::default()
+    };
+    assert!(matches!(
+        unsafe { parse_ffi_config(&missing_values) }.unwrap_err(),
+        InitError::InvalidSettings(_)
+    ));
+}
+
+#[test]
+fn newer_hosts_are_accepted() {
+    let newer = FfiConfig {
+        version: FFI_CONFIG_VERSION + 1,
+        size: std::mem::size_of::<FfiConfig>() + 16,
+        ..Default::default()
+    };
+    assert!(unsafe { parse_ffi_config(&newer) }.is_ok());
+}
+
+#[test]
+fn init_applies_the_config_once() {
+    let keys = [StringRef::from_str("parents_cache")];
+    let values = [StringRef::from_str("/var/tmp/parents")];
+    let config = FfiConfig {
+        task_threads: 2,
+        alloc_stats: true,
+        settings_keys_ptr: keys.as_ptr(),
+        settings_values_ptr: values.as_ptr(),
+        settings_len: keys.len(),
+        ..Default::default()
+    };
+    unsafe {
+        assert_eq!(fil_init(&config), FCPResponseStatus::FCPNoError);
+        assert_eq!(fil_init(ptr::null()), FCPResponseStatus::FCPCallerError);
+    }
+    assert_eq!(
+        last_error(),
+        Some((
+            FCPResponseStatus::FCPCallerError,
+            "the library is initialized already".to_string()
+        ))
+    );
+
+    let config = init_config().unwrap();
+    assert_eq!(config.task_threads, Some(2));
+    assert_eq!(config.setting("parents_cache"), Some("/var/tmp/parents"));
+    assert_eq!(config.setting("missing"), None);
+    assert!(alloc_stats_enabled());
+    assert!(lifecycle::is_initialized());
+}
@@ -0,0 +1,108 @@
+use std::convert::TryFrom;
+use std::ffi::CString;
+
+use ffi_toolkit::{
+    fil_free_string, fil_i128_to_string, fil_parse_i128, fil_parse_u128, fil_u128_to_string,
+    FfiI128, FfiU128,
+};
+
+#[test]
+fn u128_round_trip() {
+    let values = [0u128, 1, u64::MAX as u128, u64::MAX as u128 + 1, u128::MAX];
+    for &value in values.iter() {
+        let ffi = FfiU128::from(value);
+        assert_eq!(u128::from(ffi), value);
+        assert_eq!(FfiU128::from_be_bytes(ffi.to_be_bytes()), ffi);
+        assert_eq!(FfiU128::from_le_bytes(ffi.to_le_bytes()), ffi);
+    }
+}
+
+#[test]
+fn u128_halves() {
+    let ffi = FfiU128::from(u64::MAX as u128 + 1);
+    assert_eq!(ffi, FfiU128::new(1, 0));
+    assert_eq!(ffi.to_u64(), None);
+    assert_eq!(FfiU128::from_u64(42).to_u64(), Some(42));
+    assert!(FfiU128::ZERO.is_zero());
+    assert_eq!(u128::from(FfiU128::MAX), u128::MAX);
+}
+
+#[test]
+fn i128_round_trip() {
+    let values = [0i128, -1, 1, i64::MIN as i128 - 1, i128::MIN, i128::MAX];
+    for &value in values.iter() {
+        let ffi = FfiI128::from(value);
+        assert_eq!(i128::from(ffi), value);
+        assert_eq!(ffi.is_negative(), value < 0);
+        assert_eq!(FfiI128::from_be_bytes(ffi.to_be_bytes()), ffi);
+        assert_eq!(FfiI128::from_le_bytes(ffi.to_le_bytes()), ffi);
+    }
+    assert_eq!(FfiI128::from(-1i64), FfiI128::new(u64::MAX, u64::MAX));
+    assert!(FfiI128::from(-1i64) < FfiI128::ZERO);
+    assert!(FfiI128::MIN < FfiI128::MAX);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(u64::try_from(FfiU128::from_u64(7)), Ok(7));
+    assert!(u64::try_from(FfiU128::MAX).is_err());
+    assert_eq!(i64::try_from(FfiI128::from(-7i64)), Ok(-7));
+    assert!(FfiI128::try_from(FfiU128::MAX).is_err());
+    assert_eq!(
+        FfiU128::try_from(FfiI128::from(5i64)),
+        Ok(FfiU128::from_u64(5))
+    );
+    assert!(FfiU128::try_from(FfiI128::from(-5i64)).is_err());
+}
+
+#[test]
+fn checked_arithmetic() {
+    let one = FfiU128::from_u64(1);
+    assert_eq!(
+        FfiU128::from_u64(u64::MAX).checked_add(one),
+        Some(FfiU128::new(1, 0))
+    );
+    assert_eq!(FfiU128::MAX.checked_add(one), None);
+    assert_eq!(FfiU128::ZERO.checked_sub(one), None);
+    assert_eq!(FfiU128::MAX.checked_mul(FfiU128::from_u64(2)), None);
+    assert_eq!(one.checked_div(FfiU128::ZERO), None);
+    assert_eq!(
+        FfiU128::from_u64(7).checked_rem(FfiU128::from_u64(4)),
+        Some(FfiU128::from_u64(3))
+    );
+    assert_eq!(FfiU128::MAX.saturating_add(one), FfiU128::MAX);
+    assert_eq!(FfiU128::ZERO.saturating_sub(one), FfiU128::ZERO);
+    assert_eq!(FfiI128::MIN.checked_div(FfiI128::from(-1i64)), None);
+    assert_eq!(
+        FfiI128::MIN.saturating_sub(FfiI128::from(1i64)),
+        FfiI128::MIN
+    );
+}
+
+#[test]
+fn strings() {
+    assert_eq!(FfiU128::MAX.to_string(), u128::MAX.to_string());
+    assert_eq!("-42".parse::<FfiI128>(), Ok(FfiI128::from(-42i64)));
+    assert!("-42".parse::<FfiU128>().is_err());
+
+    let s = fil_u128_to_string(FfiU128::new(1, 0));
+    assert_eq!(s.as_str(), "18446744073709551616");
+    fil_free_string(s);
+    let s = fil_i128_to_string(FfiI128::MIN);
+    assert_eq!(s.as_str(), i128::MIN.to_string());
+    fil_free_string(s);
+
+    let mut out = FfiU128::from_u64(9);
+    let valid = CString::new("18446744073709551616").unwrap();
+    assert!(unsafe { fil_parse_u128(valid.as_ptr(), &mut out) });
+    assert_eq!(out, FfiU128::new(1, 0));
+    let invalid = CString::new("1e3").unwrap();
+    assert!(!unsafe { fil_parse_u128(invalid.as_ptr(), &mut out) });
+    assert_eq!(out, FfiU128::new(1, 0));
+
+    let mut out = FfiI128::ZERO;
+    let negative = CString::new("-1").unwrap();
+    assert!(unsafe { fil_parse_i128(negative.as_ptr(), &mut out) });
+    assert_eq!(out, FfiI128::from(-1i64));
+    assert!(!unsafe { fil_parse_i128(std::ptr::null(), &mut out) });
+}
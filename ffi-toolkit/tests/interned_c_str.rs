@@ -0,0 +1,59 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    free_c_str, free_raw_ptr, intern_c_str, is_interned_c_str, raw_ptr, rust_str_to_c_str,
+    track_ffi_memory, CodeAndMessage, FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct VerifyResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn messages_are_interned_once() {
+    let first = intern_c_str("proof is malformed");
+    let second = intern_c_str("proof is malformed");
+    assert_eq!(first, second);
+    assert_ne!(first, intern_c_str("sector is missing"));
+    assert_eq!(
+        unsafe { CStr::from_ptr(first) }.to_str().unwrap(),
+        "proof is malformed"
+    );
+    assert!(is_interned_c_str(first));
+}
+
+#[test]
+fn interned_strings_are_not_freed() {
+    let message = intern_c_str("ticket expired");
+    unsafe { free_c_str(message as *mut libc::c_char) };
+    assert_eq!(
+        unsafe { CStr::from_ptr(message) }.to_str().unwrap(),
+        "ticket expired"
+    );
+
+    let allocated = rust_str_to_c_str("ticket expired");
+    assert!(!is_interned_c_str(allocated));
+    unsafe { free_c_str(allocated) };
+}
+
+#[test]
+fn static_errors_allocate_no_message() {
+    intern_c_str("proof is invalid");
+    track_ffi_memory! {
+        let mut response = VerifyResponse::default();
+        response.set_error_static(FCPResponseStatus::FCPCallerError, "proof is invalid");
+        assert_eq!(response.status_code, FCPResponseStatus::FCPCallerError);
+        let message = response.error_msg;
+        unsafe { free_raw_ptr(raw_ptr(response)) };
+        assert_eq!(
+            unsafe { CStr::from_ptr(message) }.to_str().unwrap(),
+            "proof is invalid"
+        );
+    };
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "json")]
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use ffi_toolkit::{free_c_str, json_c_str_to, to_json_c_str};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Config {
+    parameter_cache: String,
+    gpu: bool,
+    #[serde(default)]
+    threads: Option<u32>,
+}
+
+#[test]
+fn round_trip() {
+    let config = Config {
+        parameter_cache: "/var/tmp/filecoin-proof-parameters".to_string(),
+        gpu: true,
+        threads: Some(8),
+    };
+    let json = to_json_c_str(&config).unwrap();
+    assert_eq!(
+        unsafe { CStr::from_ptr(json) }.to_str().unwrap(),
+        "{\"parameter_cache\":\"/var/tmp/filecoin-proof-parameters\",\"gpu\":true,\"threads\":8}"
+    );
+    assert_eq!(unsafe { json_c_str_to::<Config>(json) }, Ok(config));
+    unsafe { free_c_str(json) };
+}
+
+#[test]
+fn parse_errors_have_a_position() {
+    let json = CString::new("{\n  \"parameter_cache\": \"/tmp\",\n  \"gpu\": yes\n}").unwrap();
+    let err = unsafe { json_c_str_to::<Config>(json.as_ptr()) }.unwrap_err();
+    assert_eq!((err.line, err.column), (3, 10));
+    assert_eq!(
+        err.to_string(),
+        "invalid JSON at line 3, column 10: expected value"
+    );
+
+    let json = CString::new("{\"gpu\": false}").unwrap();
+    let err = unsafe { json_c_str_to::<Config>(json.as_ptr()) }.unwrap_err();
+    assert!(
+        err.to_string().contains("missing field `parameter_cache`"),
+        "{}",
+        err
+    );
+
+    let err = unsafe { json_c_str_to::<Config>(ptr::null()) }.unwrap_err();
+    assert_eq!(err.to_string(), "invalid JSON: null string");
+}
+
+#[test]
+fn nul_bytes_are_escaped() {
+    let json = to_json_c_str(&"a\0b").unwrap();
+    assert_eq!(
+        unsafe { CStr::from_ptr(json) }.to_str().unwrap(),
+        "\"a\\u0000b\""
+    );
+    unsafe { free_c_str(json) };
+}
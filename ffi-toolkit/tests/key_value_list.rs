@@ -0,0 +1,88 @@
+#![cfg(feature = "testing")]
+
+use std::collections::HashMap;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    fil_free_key_value_list, fil_key_value_list_find, fil_key_value_list_get, track_ffi_memory,
+    KeyValueList, StringRef,
+};
+
+#[test]
+fn hash_map_round_trip() {
+    let map: HashMap<String, String> = vec![
+        ("sector_size".to_string(), "32GiB".to_string()),
+        ("cache".to_string(), "/var/tmp/cache".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    let list = KeyValueList::from(map.clone());
+    assert_eq!(list.len(), 2);
+    // Sorted by key
+    assert_eq!(
+        list.iter().collect::<Vec<_>>(),
+        [("cache", "/var/tmp/cache"), ("sector_size", "32GiB")]
+    );
+    assert_eq!(HashMap::from(&list), map);
+    assert_eq!(list.get("sector_size"), Some("32GiB"));
+    assert_eq!(list.get("missing"), None);
+}
+
+#[test]
+fn strings_may_contain_nul_bytes() {
+    let list: KeyValueList = vec![("label", "a\0b"), ("", "")].into_iter().collect();
+    assert_eq!(list.get("label"), Some("a\0b"));
+    assert_eq!(list.get(""), Some(""));
+    assert!(KeyValueList::default().is_empty());
+}
+
+#[test]
+fn c_iteration() {
+    let list = KeyValueList::new(vec![("a", "1"), ("b", "2"), ("a", "3")]);
+    let mut key = StringRef::default();
+    let mut value = StringRef::default();
+    unsafe {
+        assert!(fil_key_value_list_get(&list, 1, &mut key, &mut value));
+        assert_eq!((key.as_str(), value.as_str()), (Ok("b"), Ok("2")));
+        assert!(!fil_key_value_list_get(&list, 3, &mut key, &mut value));
+        assert!(!fil_key_value_list_get(
+            ptr::null(),
+            0,
+            &mut key,
+            &mut value
+        ));
+
+        // The first entry of a duplicate key
+        assert!(fil_key_value_list_find(
+            &list,
+            StringRef::from_str("a"),
+            &mut value
+        ));
+        assert_eq!(value.as_str(), Ok("1"));
+        assert!(!fil_key_value_list_find(
+            &list,
+            StringRef::from_str("c"),
+            &mut value
+        ));
+    }
+    fil_free_key_value_list(list);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct SectorMetadataResponse {
+    pub sector_id: u64,
+    pub metadata: KeyValueList,
+}
+
+#[test]
+fn responses_free_their_lists() {
+    track_ffi_memory! {
+        let response = SectorMetadataResponse {
+            sector_id: 1,
+            metadata: KeyValueList::new(vec![("miner", "t01000")]),
+        };
+        drop(response);
+    };
+}
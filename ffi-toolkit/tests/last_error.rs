@@ -0,0 +1,75 @@
+use std::ffi::CStr;
+use std::thread;
+
+use ffi_toolkit::{
+    catch_panic_response, clear_last_error, code_and_message_impl, fil_clear_last_error,
+    fil_last_error_code, fil_last_error_message, free_c_str, free_raw_ptr, last_error,
+    set_last_error, CodeAndMessage, FCPResponseStatus,
+};
+
+#[repr(C)]
+pub struct Response {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Response {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: std::ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(Response);
+
+#[test]
+fn set_and_read_from_c() {
+    set_last_error(FCPResponseStatus::FCPCallerError, "invalid sector id");
+    assert_eq!(fil_last_error_code(), FCPResponseStatus::FCPCallerError);
+    let message = unsafe { CStr::from_ptr(fil_last_error_message()) };
+    assert_eq!(message.to_str().unwrap(), "invalid sector id");
+
+    fil_clear_last_error();
+    assert_eq!(fil_last_error_code(), FCPResponseStatus::FCPNoError);
+    assert!(fil_last_error_message().is_null());
+    assert_eq!(last_error(), None);
+}
+
+#[test]
+fn per_thread() {
+    set_last_error(FCPResponseStatus::FCPReceiverError, "disk full");
+    thread::spawn(|| assert_eq!(last_error(), None))
+        .join()
+        .unwrap();
+    assert_eq!(
+        last_error(),
+        Some((FCPResponseStatus::FCPReceiverError, "disk full".to_string()))
+    );
+    clear_last_error();
+}
+
+#[test]
+fn interior_nul_truncates() {
+    set_last_error(FCPResponseStatus::FCPCallerError, "bad\0path");
+    assert_eq!(last_error().unwrap().1, "bad");
+    clear_last_error();
+}
+
+#[test]
+fn panics_are_recorded() {
+    let response: *mut Response = catch_panic_response(|| panic!("out of space"));
+    assert_eq!(
+        last_error(),
+        Some((
+            FCPResponseStatus::FCPUnclassifiedError,
+            "Rust panic: out of space".to_string()
+        ))
+    );
+    unsafe {
+        free_c_str((*response).error_msg as *mut libc::c_char);
+        free_raw_ptr(response);
+    }
+    clear_last_error();
+}
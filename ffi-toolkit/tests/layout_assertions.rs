@@ -0,0 +1,44 @@
+#![cfg(target_pointer_width = "64")]
+
+use drop_struct_macro_derive::{DropStructMacro, FFIResponse};
+use ffi_toolkit::{assert_ffi_layout, free_c_str, FCPResponseStatus, FfiBytes, FfiVTableHeader};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(layout(size = 32, align = 8))]
+pub struct SealResponse {
+    #[ffi_drop(offset = 0)]
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(offset = 16)]
+    pub proof_ptr: *const u8,
+    #[ffi_drop(offset = 24)]
+    pub proof_len: libc::size_t,
+}
+
+#[repr(C, align(16))]
+#[derive(DropStructMacro)]
+#[ffi_drop(layout(size = 16, align = 16))]
+pub struct AlignedPath {
+    pub path: *const libc::c_char,
+}
+
+// A field named like the size is fine
+assert_ffi_layout!(FfiVTableHeader, size = 16, align = 8, offsets {
+    version = 0,
+    size = 8,
+});
+assert_ffi_layout!(FfiBytes, size = 24, align = 8);
+
+#[test]
+fn structs_with_asserted_layouts_work_as_usual() {
+    let response = SealResponse {
+        proof_len: 192,
+        ..Default::default()
+    };
+    assert_eq!(response.proof_len, 192);
+    let path = AlignedPath {
+        path: ffi_toolkit::rust_str_to_c_str("/sealed/s-t01000-1"),
+    };
+    drop(path);
+}
@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ffi_toolkit::lifecycle::{self, ShutdownPhase};
+use ffi_toolkit::{catch_panic_response, code_and_message_impl, CodeAndMessage, FCPResponseStatus};
+
+// All tests in here share the global lifecycle
+static SERIAL: Mutex<()> = Mutex::new(());
+
+static SHUTDOWN_ORDER: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+static CALL_FINISHED: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn logger_hook() {
+    SHUTDOWN_ORDER.lock().unwrap().push("logger");
+}
+
+fn registry_hook() {
+    SHUTDOWN_ORDER.lock().unwrap().push("registry");
+}
+
+fn first_pump_hook() {
+    SHUTDOWN_ORDER.lock().unwrap().push("first pump");
+}
+
+fn second_pump_hook() {
+    SHUTDOWN_ORDER.lock().unwrap().push("second pump");
+}
+
+fn panicking_hook() {
+    panic!("shutdown hook failed");
+}
+
+fn count_shutdown() {
+    SHUTDOWN_CALLS.fetch_add(1, Ordering::SeqCst);
+}
+
+fn check_call_finished() {
+    assert!(CALL_FINISHED.load(Ordering::SeqCst));
+    SHUTDOWN_CALLS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[repr(C)]
+pub struct Response {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Response {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: std::ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(Response);
+
+#[test]
+fn init_and_shutdown() {
+    let _serial = SERIAL.lock().unwrap();
+    SHUTDOWN_CALLS.store(0, Ordering::SeqCst);
+
+    lifecycle::init();
+    lifecycle::init();
+    assert!(lifecycle::is_initialized());
+
+    lifecycle::register_shutdown_hook(ShutdownPhase::Resources, count_shutdown);
+    lifecycle::register_shutdown_hook(ShutdownPhase::Resources, panicking_hook);
+    lifecycle::shutdown();
+    lifecycle::shutdown();
+    assert!(!lifecycle::is_initialized());
+    assert_eq!(SHUTDOWN_CALLS.load(Ordering::SeqCst), 1);
+
+    lifecycle::init();
+    let _ = std::panic::catch_unwind(|| panic!("recorded"));
+    assert!(lifecycle::last_panic_location()
+        .unwrap()
+        .contains("lifecycle.rs"));
+}
+
+#[test]
+fn shutdown_runs_phases_in_order() {
+    let _serial = SERIAL.lock().unwrap();
+    SHUTDOWN_ORDER.lock().unwrap().clear();
+
+    lifecycle::register_shutdown_hook(ShutdownPhase::Logger, logger_hook);
+    lifecycle::register_shutdown_hook(ShutdownPhase::Registries, registry_hook);
+    lifecycle::register_shutdown_hook(ShutdownPhase::CallbackPumps, first_pump_hook);
+    lifecycle::register_shutdown_hook(ShutdownPhase::CallbackPumps, second_pump_hook);
+    lifecycle::fil_shutdown_ordered();
+
+    assert_eq!(
+        *SHUTDOWN_ORDER.lock().unwrap(),
+        vec!["second pump", "first pump", "registry", "logger"]
+    );
+}
+
+#[test]
+fn shutdown_waits_for_in_flight_calls() {
+    let _serial = SERIAL.lock().unwrap();
+    CALL_FINISHED.store(false, Ordering::SeqCst);
+    SHUTDOWN_CALLS.store(0, Ordering::SeqCst);
+    lifecycle::register_shutdown_hook(ShutdownPhase::WorkerPools, check_call_finished);
+
+    let (started_sender, started) = std::sync::mpsc::channel();
+    let call = thread::spawn(move || {
+        catch_panic_response(|| {
+            started_sender.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            CALL_FINISHED.store(true, Ordering::SeqCst);
+            ffi_toolkit::raw_ptr(Response::default())
+        }) as usize
+    });
+    started.recv().unwrap();
+    assert_eq!(lifecycle::in_flight_calls(), 1);
+
+    lifecycle::shutdown();
+    assert_eq!(SHUTDOWN_CALLS.load(Ordering::SeqCst), 1);
+    let response = call.join().unwrap() as *mut Response;
+    unsafe { ffi_toolkit::free_raw_ptr(response) };
+}
+
+#[test]
+fn shutdown_from_within_a_guarded_call() {
+    let _serial = SERIAL.lock().unwrap();
+    let response = catch_panic_response(|| {
+        lifecycle::shutdown();
+        ffi_toolkit::raw_ptr(Response::default())
+    });
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        ffi_toolkit::free_raw_ptr(response);
+    }
+}
+
+fn blocking_call(release: mpsc::Receiver<()>) -> (thread::JoinHandle<usize>, mpsc::Receiver<()>) {
+    let (started_sender, started) = mpsc::channel();
+    let call = thread::spawn(move || {
+        catch_panic_response(|| {
+            started_sender.send(()).unwrap();
+            release.recv().unwrap();
+            ffi_toolkit::raw_ptr(Response::default())
+        }) as usize
+    });
+    (call, started)
+}
+
+#[test]
+fn shutdown_with_timeout_gives_up_on_hanging_calls() {
+    let _serial = SERIAL.lock().unwrap();
+    SHUTDOWN_CALLS.store(0, Ordering::SeqCst);
+    lifecycle::init();
+    lifecycle::register_shutdown_hook(ShutdownPhase::Resources, count_shutdown);
+
+    let (release, released) = mpsc::channel();
+    let (call, started) = blocking_call(released);
+    started.recv().unwrap();
+    let report = lifecycle::fil_shutdown(20);
+    assert!(!report.completed);
+    assert_eq!(report.in_flight_calls, 1);
+    assert_eq!(report.live_allocations.is_some, cfg!(feature = "testing"));
+    // Nothing was torn down
+    assert_eq!(SHUTDOWN_CALLS.load(Ordering::SeqCst), 0);
+    assert!(lifecycle::is_initialized());
+
+    release.send(()).unwrap();
+    let response = call.join().unwrap() as *mut Response;
+    unsafe { ffi_toolkit::free_raw_ptr(response) };
+    let report = lifecycle::shutdown_with_timeout(Duration::from_secs(10));
+    assert!(report.completed);
+    assert_eq!(report.in_flight_calls, 0);
+    assert_eq!(SHUTDOWN_CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn new_calls_are_rejected_during_shutdown() {
+    let _serial = SERIAL.lock().unwrap();
+    let (release, released) = mpsc::channel();
+    let (call, started) = blocking_call(released);
+    started.recv().unwrap();
+    let shutdown = thread::spawn(|| lifecycle::shutdown_with_timeout(Duration::from_secs(10)));
+
+    // Once the shutdown waits for the blocked call, new calls are turned away
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let response = catch_panic_response(|| ffi_toolkit::raw_ptr(Response::default()));
+        let status = unsafe { (*response).status_code };
+        unsafe { ffi_toolkit::free_raw_ptr(response) };
+        if status == FCPResponseStatus::FCPBusyError {
+            break;
+        }
+        assert!(Instant::now() < deadline, "calls were never rejected");
+        thread::yield_now();
+    }
+
+    release.send(()).unwrap();
+    let response = call.join().unwrap() as *mut Response;
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        ffi_toolkit::free_raw_ptr(response);
+    }
+    assert!(shutdown.join().unwrap().completed);
+}
@@ -0,0 +1,9 @@
+#![cfg(feature = "ctor")]
+
+use ffi_toolkit::lifecycle;
+
+/// With the `ctor` feature, the toolkit is initialized before any test runs.
+#[test]
+fn initialized_on_load() {
+    assert!(lifecycle::is_initialized());
+}
@@ -0,0 +1,69 @@
+use std::ffi::CString;
+use std::fs;
+use std::ptr;
+
+use ffi_toolkit::{
+    fil_destroy_list_dir_response, fil_list_dir, matches_pattern, FCPResponseStatus,
+    FfiIoErrorKind, TempDir,
+};
+
+#[test]
+fn patterns() {
+    assert!(matches_pattern("*", ""));
+    assert!(matches_pattern("*.dat", "sc-02-data-tree-d.dat"));
+    assert!(matches_pattern("sc-??-*", "sc-02-data"));
+    assert!(matches_pattern("a*b*c", "aXbYbZc"));
+    assert!(!matches_pattern("*.dat", "tree.dat.tmp"));
+    assert!(!matches_pattern("?", ""));
+    assert!(!matches_pattern("abc", "ab"));
+}
+
+#[test]
+fn lists_matching_entries() {
+    let dir = TempDir::create("fil-list-dir").unwrap();
+    for name in &["b.dat", "a.dat", "c.tmp"] {
+        fs::write(dir.path().join(name), b"").unwrap();
+    }
+    fs::create_dir(dir.path().join("d.dat")).unwrap();
+    let path = CString::new(dir.path().to_str().unwrap()).unwrap();
+    let pattern = CString::new("*.dat").unwrap();
+
+    unsafe {
+        let response = fil_list_dir(path.as_ptr(), pattern.as_ptr());
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).error_kind, FfiIoErrorKind::NoError);
+        assert_eq!(
+            (*response).entries.to_vec(),
+            vec!["a.dat", "b.dat", "d.dat"]
+        );
+        fil_destroy_list_dir_response(response);
+
+        let response = fil_list_dir(path.as_ptr(), ptr::null());
+        assert_eq!((*response).entries.len(), 4);
+        fil_destroy_list_dir_response(response);
+    }
+}
+
+#[test]
+fn typed_errors() {
+    let dir = TempDir::create("fil-list-dir").unwrap();
+    let file = dir.path().join("file");
+    fs::write(&file, b"").unwrap();
+    let cases = [
+        (dir.path().join("missing"), FfiIoErrorKind::NotFound),
+        (file, FfiIoErrorKind::NotADirectory),
+    ];
+
+    for (path, expected) in cases.iter() {
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        unsafe {
+            let response = fil_list_dir(path.as_ptr(), ptr::null());
+            assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+            assert_eq!((*response).error_kind, *expected);
+            assert!((*response).entries.is_empty());
+            let message = std::ffi::CStr::from_ptr((*response).error_msg).to_string_lossy();
+            assert!(message.starts_with("failed to list"), "{}", message);
+            fil_destroy_list_dir_response(response);
+        }
+    }
+}
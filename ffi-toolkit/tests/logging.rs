@@ -0,0 +1,92 @@
+#![cfg(feature = "log")]
+
+use std::ffi::CStr;
+use std::fs;
+use std::sync::Mutex;
+
+use ffi_toolkit::{close_log, fil_init_log_callback, fil_set_log_level, FfiLogLevel};
+
+// The logger is global, the tests must not run concurrently
+static SERIAL: Mutex<()> = Mutex::new(());
+
+type Records = Mutex<Vec<(FfiLogLevel, String, String)>>;
+
+extern "C" fn collect(
+    level: FfiLogLevel,
+    target: *const libc::c_char,
+    message: *const libc::c_char,
+    user_data: *mut libc::c_void,
+) {
+    let records = unsafe { &*(user_data as *const Records) };
+    let (target, message) = unsafe {
+        (
+            CStr::from_ptr(target).to_string_lossy().into_owned(),
+            CStr::from_ptr(message).to_string_lossy().into_owned(),
+        )
+    };
+    records.lock().unwrap().push((level, target, message));
+}
+
+#[test]
+fn records_go_to_the_callback() {
+    let _serial = SERIAL.lock().unwrap();
+    let records: &'static Records = Box::leak(Box::new(Mutex::new(Vec::new())));
+    assert!(fil_init_log_callback(
+        collect,
+        records as *const Records as *mut libc::c_void,
+        FfiLogLevel::Info
+    ));
+
+    log::info!(target: "seal", "sealing sector {}", 7);
+    log::debug!(target: "seal", "filtered");
+    fil_set_log_level(FfiLogLevel::Debug);
+    log::debug!(target: "seal", "not filtered anymore");
+    fil_set_log_level(FfiLogLevel::Off);
+    log::error!("off");
+    close_log();
+
+    assert_eq!(
+        *records.lock().unwrap(),
+        vec![
+            (
+                FfiLogLevel::Info,
+                "seal".to_string(),
+                "sealing sector 7".to_string()
+            ),
+            (
+                FfiLogLevel::Debug,
+                "seal".to_string(),
+                "not filtered anymore".to_string()
+            ),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn records_go_to_the_fd() {
+    use std::os::unix::io::AsRawFd;
+
+    use ffi_toolkit::{fil_init_log_fd, BorrowedFfiFd};
+
+    let _serial = SERIAL.lock().unwrap();
+    let path = std::env::temp_dir().join(format!("ffi-toolkit-log-{}", std::process::id()));
+    let file = fs::File::create(&path).unwrap();
+    assert!(fil_init_log_fd(
+        BorrowedFfiFd(file.as_raw_fd()),
+        FfiLogLevel::Warn
+    ));
+    // The logger writes to its own duplicate
+    drop(file);
+
+    log::warn!(target: "proofs", "low disk space");
+    log::info!(target: "proofs", "filtered");
+    close_log();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "WARN proofs: low disk space\n"
+    );
+    fs::remove_file(&path).unwrap();
+    assert!(!fil_init_log_fd(BorrowedFfiFd(-1), FfiLogLevel::Warn));
+}
@@ -0,0 +1,93 @@
+use std::ptr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use ffi_toolkit::{fil_release_mapped_region, FfiMappedRegion, MappedRegion};
+
+// stands in for a region the host mapped
+fn host_region(bytes: &mut [u8], read_only: bool) -> FfiMappedRegion {
+    FfiMappedRegion {
+        ptr: bytes.as_mut_ptr(),
+        len: bytes.len(),
+        read_only,
+    }
+}
+
+#[test]
+fn read_and_write() {
+    let mut mapping = b"sector".to_vec();
+    let region = unsafe { MappedRegion::import(host_region(&mut mapping, false)) }.unwrap();
+    let guard = region.clone().into_guard();
+
+    assert_eq!(&*region.bytes().unwrap(), b"sector");
+    region.bytes_mut().unwrap()[0] = b'S';
+    assert_eq!(&*region.bytes().unwrap(), b"Sector");
+
+    unsafe { fil_release_mapped_region(guard) };
+    assert!(region.is_released());
+    assert!(region.bytes().is_none());
+    assert!(region.bytes_mut().is_none());
+    assert_eq!(mapping, b"Sector");
+}
+
+#[test]
+fn read_only() {
+    let mut mapping = vec![1u8, 2, 3];
+    let region = unsafe { MappedRegion::import(host_region(&mut mapping, true)) }.unwrap();
+    assert!(region.is_read_only());
+    assert!(region.bytes_mut().is_none());
+    assert_eq!(region.bytes().unwrap().len(), 3);
+    unsafe { fil_release_mapped_region(region.into_guard()) };
+}
+
+#[test]
+fn validation() {
+    let null = FfiMappedRegion {
+        ptr: ptr::null_mut(),
+        len: 1,
+        read_only: true,
+    };
+    assert!(unsafe { MappedRegion::import(null) }.is_err());
+
+    let too_large = FfiMappedRegion {
+        ptr: 16 as *mut u8,
+        len: usize::MAX,
+        read_only: true,
+    };
+    assert!(unsafe { MappedRegion::import(too_large) }.is_err());
+
+    let empty = FfiMappedRegion {
+        ptr: ptr::null_mut(),
+        len: 0,
+        read_only: true,
+    };
+    let region = unsafe { MappedRegion::import(empty) }.unwrap();
+    assert!(region.bytes().unwrap().is_empty());
+}
+
+#[test]
+fn release_waits_for_accesses() {
+    let mut mapping = vec![0u8; 16];
+    let region = unsafe { MappedRegion::import(host_region(&mut mapping, true)) }.unwrap();
+    let guard = region.clone().into_guard() as usize;
+
+    let bytes = region.bytes().unwrap();
+    let (released_tx, released_rx) = mpsc::channel();
+    let host = thread::spawn(move || {
+        unsafe { fil_release_mapped_region(guard as *mut _) };
+        released_tx.send(()).unwrap();
+    });
+    thread::sleep(Duration::from_millis(50));
+    assert!(released_rx.try_recv().is_err());
+
+    drop(bytes);
+    released_rx.recv().unwrap();
+    host.join().unwrap();
+    assert!(region.bytes().is_none());
+}
+
+#[test]
+fn null_guard() {
+    unsafe { fil_release_mapped_region(ptr::null_mut()) };
+}
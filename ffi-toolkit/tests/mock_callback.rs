@@ -0,0 +1,51 @@
+#![cfg(feature = "testing")]
+
+use std::ffi::CStr;
+
+use ffi_toolkit::testing::MockCallback;
+
+type LogCallback = extern "C" fn(*mut libc::c_void, u32, *const libc::c_char);
+
+// The kind of function under test: it reports through a host callback
+fn log_twice(callback: LogCallback, user_data: *mut libc::c_void) {
+    callback(user_data, 1, b"first\0".as_ptr() as *const libc::c_char);
+    callback(user_data, 2, b"second\0".as_ptr() as *const libc::c_char);
+}
+
+#[test]
+fn records_calls() {
+    let messages = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = messages.clone();
+    let mock = MockCallback::new(move |&(level, message): &(u32, *const libc::c_char)| {
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        recorded
+            .lock()
+            .unwrap()
+            .push(format!("{}: {}", level, message));
+    });
+
+    log_twice(mock.user_data_first(), mock.user_data());
+
+    assert_eq!(mock.call_count(), 2);
+    assert_eq!(*messages.lock().unwrap(), vec!["1: first", "2: second"]);
+}
+
+#[test]
+fn scripted_return_values() {
+    let mock = MockCallback::<(u64,), i32>::new(|&(value,)| value as i32).returning(vec![-1, -2]);
+    let callback = mock.user_data_last();
+
+    assert_eq!(callback(10, mock.user_data()), -1);
+    assert_eq!(callback(10, mock.user_data()), -2);
+    assert_eq!(callback(10, mock.user_data()), 10);
+    assert_eq!(mock.calls(), vec![(10,), (10,), (10,)]);
+}
+
+#[test]
+fn panics_do_not_unwind_into_the_caller() {
+    let mock = MockCallback::<(), bool>::new(|_| panic!("host callback exploded"));
+    let callback = mock.user_data_first();
+
+    assert!(!callback(mock.user_data()));
+    assert_eq!(mock.panics(), vec!["host callback exploded"]);
+}
@@ -0,0 +1,83 @@
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+use ffi_toolkit::{last_error, FCPResponseStatus, FfiOnceCell, OnceCellError};
+
+#[test]
+fn initialized_once() {
+    static CACHE: FfiOnceCell<String> = FfiOnceCell::new("the parameter cache");
+    assert_eq!(
+        CACHE.get_or_error(),
+        Err(OnceCellError::NotInitialized("the parameter cache"))
+    );
+    assert_eq!(
+        CACHE.init_from_ffi(|| Ok::<_, io::Error>("v28".to_string())),
+        FCPResponseStatus::FCPNoError
+    );
+    assert_eq!(CACHE.get_or_error().unwrap(), "v28");
+
+    assert_eq!(
+        CACHE.init_from_ffi(|| -> Result<String, io::Error> { unreachable!() }),
+        FCPResponseStatus::FCPCallerError
+    );
+    assert_eq!(
+        last_error(),
+        Some((
+            FCPResponseStatus::FCPCallerError,
+            "the parameter cache is already initialized".to_string()
+        ))
+    );
+    assert_eq!(CACHE.set("v29".to_string()), Err("v29".to_string()));
+}
+
+#[test]
+fn failed_initializations_leave_the_cell_empty() {
+    static LOGGER: FfiOnceCell<u32> = FfiOnceCell::new("the logger");
+    let status =
+        LOGGER.init_from_ffi(|| Err(io::Error::new(io::ErrorKind::InvalidInput, "bad fd")));
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        last_error(),
+        Some((FCPResponseStatus::FCPCallerError, "bad fd".to_string()))
+    );
+    assert!(!LOGGER.is_initialized());
+
+    let status = LOGGER.init_from_ffi(|| -> Result<u32, io::Error> { panic!("no terminal") });
+    assert_eq!(status, FCPResponseStatus::FCPUnclassifiedError);
+    assert!(last_error()
+        .unwrap()
+        .1
+        .starts_with("Rust panic: no terminal"));
+    assert!(!LOGGER.is_initialized());
+
+    assert_eq!(LOGGER.set(2), Ok(&2));
+}
+
+#[test]
+fn concurrent_initializations() {
+    let cell = Arc::new(FfiOnceCell::new("the cell"));
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            let cell = Arc::clone(&cell);
+            thread::spawn(move || cell.set(i).is_ok())
+        })
+        .collect();
+    let initialized = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .filter(|&initialized| initialized)
+        .count();
+    assert_eq!(initialized, 1);
+    assert!(cell.get().is_some());
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn reset_for_tests() {
+    static CELL: FfiOnceCell<u32> = FfiOnceCell::new("the cell");
+    assert!(CELL.set(1).is_ok());
+    CELL.reset_for_tests();
+    assert!(CELL.get().is_none());
+    assert_eq!(CELL.set(2), Ok(&2));
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use ffi_toolkit::{declare_opaque_handle, track_ffi_memory};
+
+pub struct Cache {
+    entries: Vec<u64>,
+}
+
+declare_opaque_handle! {
+    /// A cache owned by C
+    pub struct FfiCache(Cache);
+    destroy = fil_destroy_cache;
+}
+
+#[test]
+fn wrap_borrow_destroy() {
+    track_ffi_memory! {
+        let cache = FfiCache::wrap(Cache { entries: vec![1] });
+        unsafe {
+            FfiCache::borrow_mut(cache).unwrap().entries.push(2);
+            assert_eq!(FfiCache::borrow(cache).unwrap().entries, vec![1, 2]);
+            fil_destroy_cache(cache);
+        }
+    };
+}
+
+#[test]
+fn null_handles() {
+    unsafe {
+        assert!(FfiCache::borrow(ptr::null()).is_none());
+        assert!(FfiCache::borrow_mut(ptr::null_mut()).is_none());
+        fil_destroy_cache(ptr::null_mut());
+    }
+}
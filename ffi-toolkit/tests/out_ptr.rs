@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ffi_toolkit::{
+    clear_last_error, free_raw_ptr, last_error, write_out_box, write_out_ptr, FCPResponseStatus,
+};
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Prover {
+    proofs: u64,
+}
+
+impl Drop for Prover {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn write_value() {
+    let mut sector_size = 0u64;
+    let status = unsafe { write_out_ptr(&mut sector_size, "sector_size", 2048) };
+    assert_eq!(status, FCPResponseStatus::FCPNoError);
+    assert_eq!(sector_size, 2048);
+}
+
+#[test]
+fn null_and_misaligned() {
+    clear_last_error();
+    let status = unsafe { write_out_ptr(std::ptr::null_mut::<u64>(), "sector_size", 2048) };
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        last_error(),
+        Some((
+            FCPResponseStatus::FCPCallerError,
+            "invalid argument `sector_size`: must not be null".to_string()
+        ))
+    );
+
+    let mut bytes = [0u64; 2];
+    let misaligned = unsafe { (bytes.as_mut_ptr() as *mut u8).add(1) } as *mut u64;
+    let status = unsafe { write_out_ptr(misaligned, "sector_size", u64::MAX) };
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        last_error().unwrap().1,
+        "invalid argument `sector_size`: must be aligned to 8 bytes"
+    );
+    assert_eq!(bytes, [0, 0]);
+}
+
+#[test]
+fn ownership_transfer() {
+    let before = DROPPED.load(Ordering::SeqCst);
+    let mut prover: *mut Prover = std::ptr::null_mut();
+    let status = unsafe { write_out_box(&mut prover, "prover", Prover { proofs: 3 }) };
+    assert_eq!(status, FCPResponseStatus::FCPNoError);
+    unsafe {
+        assert_eq!((*prover).proofs, 3);
+        free_raw_ptr(prover);
+    }
+
+    let status = unsafe { write_out_box(std::ptr::null_mut(), "prover", Prover { proofs: 4 }) };
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    // Both were dropped, none leaked
+    assert_eq!(DROPPED.load(Ordering::SeqCst), before + 2);
+}
@@ -0,0 +1,55 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::lifecycle;
+use ffi_toolkit::{
+    catch_panic_response, code_and_message_impl, free_c_str, free_raw_ptr, CodeAndMessage,
+    FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ProveResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for ProveResponse {
+    fn default() -> Self {
+        ProveResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(ProveResponse);
+
+fn panicking_call() -> String {
+    let response: *mut ProveResponse = catch_panic_response(|| panic!("invalid proof"));
+    unsafe {
+        let message = CStr::from_ptr((*response).error_msg)
+            .to_string_lossy()
+            .into_owned();
+        free_raw_ptr(response);
+        message
+    }
+}
+
+// One test, as the setting is global
+#[test]
+fn backtraces_are_opt_in() {
+    assert_eq!(panicking_call(), "Rust panic: invalid proof");
+
+    lifecycle::enable_panic_backtraces(true);
+    let message = panicking_call();
+    assert!(
+        message.starts_with("Rust panic: invalid proof\n\nbacktrace:\n"),
+        "{}",
+        message
+    );
+
+    lifecycle::fil_enable_panic_backtraces(false);
+    assert_eq!(panicking_call(), "Rust panic: invalid proof");
+}
@@ -0,0 +1,110 @@
+use std::ffi::CStr;
+use std::slice;
+use std::sync::Mutex;
+use std::thread;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_response, enable_panic_reports, fil_destroy_panic_reports_response,
+    fil_take_panic_reports, free_raw_ptr, raw_ptr, take_panic_reports, FCPResponseStatus,
+    PANIC_REPORT_RING_LEN,
+};
+
+// The reports are global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct UnsealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn unseal(sector_id: u64) {
+    let response: *mut UnsealResponse = catch_panic_response(|| {
+        if sector_id > 0 {
+            panic!("sector {} is missing", sector_id);
+        }
+        raw_ptr(UnsealResponse::default())
+    });
+    unsafe { free_raw_ptr(response) };
+}
+
+#[test]
+fn caught_panics_are_reported() {
+    let _serial = serial();
+    enable_panic_reports(true);
+    thread::Builder::new()
+        .name("unsealer".to_string())
+        .spawn(|| {
+            unseal(0);
+            unseal(3);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let reports = take_panic_reports();
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.message, "sector 3 is missing");
+    assert_eq!(report.thread_name.as_deref(), Some("unsealer"));
+    assert_eq!(report.payload_type, "String");
+    assert!(report
+        .location
+        .as_deref()
+        .unwrap()
+        .starts_with("ffi-toolkit/tests/panic_reports.rs:"));
+    assert!(take_panic_reports().is_empty());
+    enable_panic_reports(false);
+}
+
+#[test]
+fn only_the_latest_reports_are_kept() {
+    let _serial = serial();
+    enable_panic_reports(true);
+    for sector_id in 1..=PANIC_REPORT_RING_LEN as u64 + 2 {
+        unseal(sector_id);
+    }
+    let reports = take_panic_reports();
+    assert_eq!(reports.len(), PANIC_REPORT_RING_LEN);
+    assert_eq!(reports[0].message, "sector 3 is missing");
+    enable_panic_reports(false);
+}
+
+#[test]
+fn nothing_is_reported_unless_enabled() {
+    let _serial = serial();
+    enable_panic_reports(false);
+    unseal(1);
+    assert!(take_panic_reports().is_empty());
+}
+
+#[test]
+fn reports_are_exported() {
+    let _serial = serial();
+    enable_panic_reports(true);
+    unseal(7);
+    unsafe {
+        let response = fil_take_panic_reports();
+        let reports = slice::from_raw_parts((*response).reports_ptr, (*response).reports_len);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            CStr::from_ptr(reports[0].message).to_str().unwrap(),
+            "sector 7 is missing"
+        );
+        assert_eq!(
+            CStr::from_ptr(reports[0].payload_type).to_str().unwrap(),
+            "String"
+        );
+        assert!(!reports[0].location.is_null());
+        fil_destroy_panic_reports_response(response);
+    }
+    enable_panic_reports(false);
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "poison")]
+
+use std::collections::HashMap;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str, set_allocator, POISON_BYTE, POISON_PTR,
+};
+
+// The sizes of the live allocations and the contents of the freed ones, by address
+static SIZES: Mutex<Option<HashMap<usize, usize>>> = Mutex::new(None);
+static FREED: Mutex<Option<HashMap<usize, Vec<u8>>>> = Mutex::new(None);
+
+extern "C" fn host_malloc(size: libc::size_t, align: libc::size_t) -> *mut libc::c_void {
+    let mut ptr = ptr::null_mut();
+    let align = align.max(std::mem::size_of::<*mut libc::c_void>());
+    if unsafe { libc::posix_memalign(&mut ptr, align, size) } != 0 {
+        return ptr::null_mut();
+    }
+    let mut sizes = SIZES.lock().unwrap();
+    sizes
+        .get_or_insert_with(HashMap::new)
+        .insert(ptr as usize, size);
+    ptr
+}
+
+// Keeps what was freed, to check that it was poisoned
+extern "C" fn host_free(ptr: *mut libc::c_void) {
+    let size = SIZES
+        .lock()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .remove(&(ptr as usize));
+    let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size.unwrap()) };
+    let mut freed = FREED.lock().unwrap();
+    freed
+        .get_or_insert_with(HashMap::new)
+        .insert(ptr as usize, bytes.to_vec());
+    unsafe { libc::free(ptr) };
+}
+
+// The allocator has to be set before anything is allocated
+fn host_allocator() {
+    static SET: Once = Once::new();
+    SET.call_once(|| assert!(set_allocator(host_malloc, host_free)));
+}
+
+fn freed<T>(ptr: *const T) -> Vec<u8> {
+    FREED
+        .lock()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .remove(&(ptr as usize))
+        .unwrap()
+}
+
+static ELEMENTS_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Element(u64);
+
+impl Drop for Element {
+    fn drop(&mut self) {
+        assert_eq!(self.0, 42, "dropped after poisoning");
+        ELEMENTS_DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct PieceResponse {
+    pub error_msg: *const libc::c_char,
+    pub elements_len: libc::size_t,
+    pub elements_ptr: *const Element,
+    pub cids_len: libc::size_t,
+    pub cids_ptr: *const *const libc::c_char,
+}
+
+fn piece_response() -> PieceResponse {
+    let elements: Box<[Element]> = vec![Element(42), Element(42)].into_boxed_slice();
+    let cids: Box<[*const libc::c_char]> =
+        vec![rust_str_to_c_str("bafk") as *const libc::c_char].into_boxed_slice();
+    PieceResponse {
+        error_msg: rust_str_to_c_str("no error"),
+        elements_len: elements.len(),
+        elements_ptr: Box::into_raw(elements) as *const Element,
+        cids_len: cids.len(),
+        cids_ptr: Box::into_raw(cids) as *const *const libc::c_char,
+    }
+}
+
+#[test]
+fn freed_pointer_fields_are_poisoned() {
+    host_allocator();
+    let mut response = ManuallyDrop::new(piece_response());
+    let error_msg = response.error_msg;
+    unsafe { ManuallyDrop::drop(&mut response) };
+
+    assert_eq!(response.error_msg as usize, POISON_PTR);
+    assert_eq!(response.elements_ptr as usize, POISON_PTR);
+    assert_eq!(response.cids_ptr as usize, POISON_PTR);
+    assert_eq!(ELEMENTS_DROPPED.load(Ordering::SeqCst), 2);
+    assert!(freed(error_msg).iter().all(|&byte| byte == POISON_BYTE));
+}
+
+#[test]
+fn freed_responses_are_poisoned() {
+    host_allocator();
+    let ptr = raw_ptr([7u64; 4]);
+    unsafe { free_raw_ptr(ptr) };
+    assert_eq!(freed(ptr), vec![POISON_BYTE; 32]);
+}
+
+#[test]
+#[should_panic(expected = "freed twice")]
+fn poisoned_pointers_are_not_freed_again() {
+    host_allocator();
+    unsafe { free_c_str(ptr::without_provenance_mut(POISON_PTR)) };
+}
@@ -0,0 +1,193 @@
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::precondition::validate_input_path;
+use ffi_toolkit::{
+    code_and_message_impl, ffi_debug_precondition, ffi_precondition, free_c_str, raw_ptr,
+    CodeAndMessage, FCPResponseStatus, TempDir,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct LenResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub len: u32,
+}
+
+impl Default for LenResponse {
+    fn default() -> Self {
+        LenResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            len: 0,
+        }
+    }
+}
+
+code_and_message_impl!(LenResponse);
+
+unsafe extern "C" fn checked_len(
+    name: *const libc::c_char,
+    data_ptr: *const u8,
+    data_len: usize,
+) -> *mut LenResponse {
+    ffi_precondition!(
+        ptr_non_null(name),
+        slice_non_null(data_ptr, data_len),
+        len_fits(data_len, u32),
+    );
+    raw_ptr(LenResponse {
+        len: data_len as u32,
+        ..Default::default()
+    })
+}
+
+unsafe extern "C" fn debug_checked(name: *const libc::c_char) -> *mut LenResponse {
+    ffi_debug_precondition!(ptr_non_null(name));
+    raw_ptr(LenResponse::default())
+}
+
+// the status code and message of the response, which is freed
+unsafe fn consume(response: *mut LenResponse) -> (FCPResponseStatus, Option<String>, u32) {
+    let response = Box::from_raw(response);
+    let message = if response.error_msg.is_null() {
+        None
+    } else {
+        Some(
+            CStr::from_ptr(response.error_msg)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    };
+    (response.status_code, message, response.len)
+}
+
+#[test]
+fn valid_arguments() {
+    let data = [1u8, 2, 3];
+    let name = b"name\0".as_ptr() as *const libc::c_char;
+    let result = unsafe { consume(checked_len(name, data.as_ptr(), data.len())) };
+    assert_eq!(result, (FCPResponseStatus::FCPNoError, None, 3));
+    // An empty slice may be null
+    let result = unsafe { consume(checked_len(name, ptr::null(), 0)) };
+    assert_eq!(result, (FCPResponseStatus::FCPNoError, None, 0));
+}
+
+#[test]
+fn messages_name_the_argument() {
+    let name = b"name\0".as_ptr() as *const libc::c_char;
+    let cases = [
+        (
+            unsafe { checked_len(ptr::null(), ptr::null(), 0) },
+            "invalid argument `name`: must not be null",
+        ),
+        (
+            unsafe { checked_len(name, ptr::null(), 2) },
+            "invalid argument `data_ptr`: must not be null for a length of 2",
+        ),
+    ];
+    for (response, expected) in cases.iter() {
+        let (status_code, message, _) = unsafe { consume(*response) };
+        assert_eq!(status_code, FCPResponseStatus::FCPCallerError);
+        assert_eq!(message.as_deref(), Some(*expected));
+    }
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn len_too_large() {
+    let name = b"name\0".as_ptr() as *const libc::c_char;
+    let data = [0u8];
+    let too_large = u32::MAX as usize + 1;
+    // The pointer isn't read
+    let (status_code, message, _) = unsafe { consume(checked_len(name, data.as_ptr(), too_large)) };
+    assert_eq!(status_code, FCPResponseStatus::FCPCallerError);
+    assert_eq!(
+        message.unwrap(),
+        "invalid argument `data_len`: 4294967296 doesn't fit into u32"
+    );
+}
+
+#[test]
+fn debug_precondition() {
+    let (status_code, _, _) = unsafe { consume(debug_checked(ptr::null())) };
+    let expected = if cfg!(debug_assertions) {
+        FCPResponseStatus::FCPCallerError
+    } else {
+        FCPResponseStatus::FCPNoError
+    };
+    assert_eq!(status_code, expected);
+}
+
+fn validate(path: &str, must_exist: bool, must_be_dir: bool) -> Result<std::path::PathBuf, String> {
+    let path = CString::new(path).unwrap();
+    unsafe { validate_input_path(path.as_ptr(), must_exist, must_be_dir) }
+}
+
+#[test]
+fn input_paths() {
+    let dir = TempDir::create("fil-paths").unwrap();
+    let file = dir.path().join("sealed");
+    fs::write(&file, b"sector").unwrap();
+    let canonical_dir = fs::canonicalize(dir.path()).unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    let nested = format!(
+        "{}/../{}/sealed",
+        dir,
+        canonical_dir.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!(
+        validate(&nested, true, false),
+        Ok(canonical_dir.join("sealed"))
+    );
+    assert_eq!(validate(dir, true, true), Ok(canonical_dir.clone()));
+    // A path that doesn't need to exist is canonicalized through its parent
+    let missing = format!("{}/missing", dir);
+    assert_eq!(
+        validate(&missing, false, false),
+        Ok(canonical_dir.join("missing"))
+    );
+
+    let file = file.to_str().unwrap();
+    let cases = [
+        (
+            validate(&missing, true, false),
+            format!("invalid path `{}`: does not exist", missing),
+        ),
+        (
+            validate(file, true, true),
+            format!("invalid path `{}`: is not a directory", file),
+        ),
+        (
+            validate(&format!("{}/missing/cache", dir), false, false),
+            format!(
+                "invalid path `{}/missing/cache`: parent directory does not exist",
+                dir
+            ),
+        ),
+        (
+            validate("", false, false),
+            "invalid argument `path`: must not be empty".to_string(),
+        ),
+    ];
+    for (result, expected) in cases.iter() {
+        assert_eq!(result.as_ref(), Err(expected));
+    }
+}
+
+#[test]
+fn invalid_input_path_pointers() {
+    assert_eq!(
+        unsafe { validate_input_path(ptr::null(), false, false) },
+        Err("invalid argument `path`: must not be null".to_string())
+    );
+    let invalid = b"/tmp/\xff\0".as_ptr() as *const libc::c_char;
+    assert_eq!(
+        unsafe { validate_input_path(invalid, false, false) },
+        Err("invalid argument `path`: must be valid UTF-8".to_string())
+    );
+}
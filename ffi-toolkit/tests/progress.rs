@@ -0,0 +1,102 @@
+use std::sync::Mutex;
+use std::thread;
+
+use ffi_toolkit::{catch_panic_value, ProgressSink};
+
+type Reports = Mutex<Vec<(String, u64, u64)>>;
+
+extern "C" fn record(
+    stage: *const libc::c_char,
+    current: u64,
+    total: u64,
+    user_data: *mut libc::c_void,
+) {
+    let reports = unsafe { &*(user_data as *const Reports) };
+    let stage = unsafe { std::ffi::CStr::from_ptr(stage) };
+    reports
+        .lock()
+        .unwrap()
+        .push((stage.to_str().unwrap().to_string(), current, total));
+}
+
+fn user_data(reports: &Reports) -> *mut libc::c_void {
+    reports as *const _ as *mut libc::c_void
+}
+
+// Encodes the layers on several threads
+fn encode(progress: &ProgressSink, layers: u64) -> u64 {
+    let workers: Vec<_> = (1..=layers)
+        .map(|layer| {
+            let progress = progress.clone();
+            thread::spawn(move || progress.report("encode", layer, layers))
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    progress.report("finalize", 1, 1);
+    layers
+}
+
+#[test]
+fn reports_reach_the_callback() {
+    let reports = Reports::default();
+    let layers = unsafe {
+        ProgressSink::scoped(Some(record), user_data(&reports), |progress| {
+            assert!(progress.is_open());
+            encode(progress, 4)
+        })
+    };
+    assert_eq!(layers, 4);
+
+    let mut reports = reports.into_inner().unwrap();
+    assert_eq!(reports.pop(), Some(("finalize".to_string(), 1, 1)));
+    reports.sort();
+    let expected: Vec<_> = (1..=4)
+        .map(|layer| ("encode".to_string(), layer, 4))
+        .collect();
+    assert_eq!(reports, expected);
+}
+
+#[test]
+fn no_reports_after_return() {
+    let reports = Reports::default();
+    let leaked = unsafe {
+        ProgressSink::scoped(Some(record), user_data(&reports), |progress| {
+            progress.report("seal", 0, 1);
+            progress.clone()
+        })
+    };
+    assert!(!leaked.is_open());
+    leaked.report("seal", 1, 1);
+    assert_eq!(reports.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn closed_on_panic() {
+    let reports = Reports::default();
+    let leaked = Mutex::new(None);
+    let proofs: u64 = catch_panic_value(|| unsafe {
+        ProgressSink::scoped(Some(record), user_data(&reports), |progress| {
+            *leaked.lock().unwrap() = Some(progress.clone());
+            panic!("prover crashed")
+        })
+    });
+    assert_eq!(proofs, 0);
+    let leaked = leaked.into_inner().unwrap().unwrap();
+    assert!(!leaked.is_open());
+    leaked.report("prove", 1, 1);
+    assert!(reports.lock().unwrap().is_empty());
+}
+
+#[test]
+fn without_callback() {
+    let layers = unsafe {
+        ProgressSink::scoped(None, std::ptr::null_mut(), |progress| {
+            assert!(!progress.is_open());
+            encode(progress, 2)
+        })
+    };
+    assert_eq!(layers, 2);
+    ProgressSink::none().report("encode", 1, 1);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "provenance")]
+
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use ffi_toolkit::{
+    free_c_str, free_raw_ptr, is_toolkit_pointer, last_error, raw_ptr, rust_str_to_c_str,
+    take_provenance_violations, FCPResponseStatus,
+};
+
+// The violations are global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[test]
+fn toolkit_pointers_are_freed() {
+    let _serial = serial();
+    take_provenance_violations();
+
+    let ptr = raw_ptr(42u64);
+    assert!(is_toolkit_pointer(ptr));
+    unsafe { free_raw_ptr(ptr) };
+    assert!(!is_toolkit_pointer(ptr));
+
+    let s = rust_str_to_c_str("sealed");
+    assert!(is_toolkit_pointer(s));
+    unsafe { free_c_str(s as *mut libc::c_char) };
+
+    assert!(take_provenance_violations().is_empty());
+}
+
+#[test]
+fn foreign_pointers_are_refused() {
+    let _serial = serial();
+    take_provenance_violations();
+
+    let foreign = Box::into_raw(Box::new(7u64));
+    assert!(!is_toolkit_pointer(foreign));
+    unsafe { free_raw_ptr(foreign) };
+
+    let violations = take_provenance_violations();
+    assert_eq!(violations.len(), 1);
+    assert!(
+        violations[0].contains("didn't hand it out"),
+        "{}",
+        violations[0]
+    );
+    let (code, message) = last_error().unwrap();
+    assert_eq!(code, FCPResponseStatus::FCPCallerError);
+    assert_eq!(message, violations[0]);
+
+    // Still ours to free
+    unsafe { drop(Box::from_raw(foreign)) };
+}
+
+#[test]
+fn double_frees_are_refused() {
+    let _serial = serial();
+    take_provenance_violations();
+
+    let ptr = raw_ptr(42u64);
+    unsafe {
+        free_raw_ptr(ptr);
+        free_raw_ptr(ptr);
+    }
+    assert_eq!(take_provenance_violations().len(), 1);
+}
+
+#[test]
+fn frees_as_another_type_are_refused() {
+    let _serial = serial();
+    take_provenance_violations();
+
+    let s = rust_str_to_c_str("sealed");
+    unsafe { free_raw_ptr(s as *mut u8) };
+    let violations = take_provenance_violations();
+    assert_eq!(violations.len(), 1);
+    assert!(
+        violations[0].contains("it was handed out as"),
+        "{}",
+        violations[0]
+    );
+
+    // Untouched by the refused free
+    assert_eq!(unsafe { CStr::from_ptr(s) }.to_str().unwrap(), "sealed");
+    unsafe { free_c_str(s as *mut libc::c_char) };
+    assert!(take_provenance_violations().is_empty());
+}
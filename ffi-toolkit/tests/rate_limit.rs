@@ -0,0 +1,80 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    catch_panic_response_limited, code_and_message_impl, configure_rate_limit,
+    fil_configure_rate_limit, free_c_str, free_raw_ptr, raw_ptr, try_acquire_call, CodeAndMessage,
+    FCPResponseStatus, RateLimit,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ProveResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+impl Default for ProveResponse {
+    fn default() -> Self {
+        ProveResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+        }
+    }
+}
+
+code_and_message_impl!(ProveResponse);
+
+fn prove() -> (FCPResponseStatus, Option<String>) {
+    let response = catch_panic_response_limited("proving", || raw_ptr(ProveResponse::default()));
+    unsafe {
+        let message = if (*response).error_msg.is_null() {
+            None
+        } else {
+            Some(
+                CStr::from_ptr((*response).error_msg)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+        let status_code = (*response).status_code;
+        free_raw_ptr(response);
+        (status_code, message)
+    }
+}
+
+#[test]
+fn busy_once_the_burst_is_used() {
+    configure_rate_limit(
+        "proving",
+        Some(RateLimit {
+            calls_per_second: 1,
+            burst: 2,
+        }),
+    );
+    assert_eq!(prove().0, FCPResponseStatus::FCPNoError);
+    assert_eq!(prove().0, FCPResponseStatus::FCPNoError);
+    assert_eq!(
+        prove(),
+        (
+            FCPResponseStatus::FCPBusyError,
+            Some("rate limit of `proving` exceeded, retry later".to_string())
+        )
+    );
+    configure_rate_limit("proving", None);
+    for _ in 0..10 {
+        assert_eq!(prove().0, FCPResponseStatus::FCPNoError);
+    }
+}
+
+#[test]
+fn buckets_refill() {
+    unsafe { fil_configure_rate_limit(b"sealing\0".as_ptr() as *const libc::c_char, 1000, 1) };
+    assert!(try_acquire_call("sealing"));
+    assert!(!try_acquire_call("sealing"));
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert!(try_acquire_call("sealing"));
+    // Other groups aren't limited
+    assert!(try_acquire_call("unlimited"));
+}
@@ -0,0 +1,46 @@
+use std::slice;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{
+    free_c_str, vec_from_raw_parts_checked, vec_into_raw_parts, vec_into_raw_parts_exact,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ChallengesResponse {
+    pub error_msg: *const libc::c_char,
+    pub challenges_ptr: *const u64,
+    pub challenges_len: libc::size_t,
+}
+
+#[test]
+fn vectors_are_rebuilt_from_their_parts() {
+    let mut challenges = Vec::with_capacity(16);
+    challenges.extend([3u64, 1, 4]);
+    let (ptr, len, cap) = vec_into_raw_parts(challenges);
+    assert_eq!((len, cap), (3, 16));
+    let challenges = unsafe { vec_from_raw_parts_checked(ptr, len, cap) };
+    assert_eq!(challenges, [3, 1, 4]);
+}
+
+#[test]
+fn exact_parts_have_no_spare_capacity() {
+    let mut challenges = Vec::with_capacity(16);
+    challenges.extend([2u64, 7]);
+    let (ptr, len) = vec_into_raw_parts_exact(challenges);
+    assert_eq!(unsafe { slice::from_raw_parts(ptr, len) }, [2, 7]);
+    // Freed by the derived `Drop` with the length as capacity
+    drop(ChallengesResponse {
+        error_msg: std::ptr::null(),
+        challenges_ptr: ptr,
+        challenges_len: len,
+    });
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "the vector's capacity 1 is smaller than its length 2")]
+fn mismatched_parts_are_detected() {
+    let (ptr, _, _) = vec_into_raw_parts(vec![1u8, 2]);
+    unsafe { vec_from_raw_parts_checked(ptr, 2, 1) };
+}
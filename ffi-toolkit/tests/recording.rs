@@ -0,0 +1,226 @@
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fs;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_response_recorded, catch_panic_result_recorded, encode_length_delimited,
+    fil_start_recording, fil_stop_recording, free_raw_ptr, is_recording, raw_ptr, read_recording,
+    start_recording, stop_recording, FCPResponseStatus, MessageCodec, RecordedArg, RecordedCall,
+    ReplayError, ReplayHarness, TempDir, RECORDING_MAGIC,
+};
+
+// The recording is global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_id: u64,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// An exported function as a consumer would write it
+unsafe extern "C" fn fil_seal(
+    sector_id: u64,
+    path: *const libc::c_char,
+    ticket_ptr: *const u8,
+    ticket_len: libc::size_t,
+) -> *mut SealResponse {
+    catch_panic_result_recorded(
+        "fil_seal",
+        || {
+            vec![
+                sector_id.into(),
+                RecordedArg::c_str(path),
+                RecordedArg::raw_bytes(ticket_ptr, ticket_len),
+            ]
+        },
+        || match sector_id {
+            0 => Err((FCPResponseStatus::FCPCallerError, "no sector".to_string())),
+            _ => Ok(SealResponse {
+                sector_id,
+                ..Default::default()
+            }),
+        },
+    )
+}
+
+fn fil_verify(valid: bool) -> *mut SealResponse {
+    catch_panic_response_recorded(
+        "fil_verify",
+        || vec![valid.into()],
+        || raw_ptr(SealResponse::default()),
+    )
+}
+
+#[test]
+fn calls_are_recorded_and_replayed() {
+    let _serial = serial();
+    let dir = TempDir::create("fil-recording").unwrap();
+    let path = dir.path().join("calls.rec");
+    let c_path = CString::new("/var/tmp/sealed").unwrap();
+    let ticket = [7u8; 32];
+
+    // Not recorded without a recording
+    unsafe { free_raw_ptr(fil_seal(9, c_path.as_ptr(), ticket.as_ptr(), 32)) };
+
+    start_recording(&path).unwrap();
+    assert!(is_recording());
+    unsafe {
+        free_raw_ptr(fil_seal(1, c_path.as_ptr(), ticket.as_ptr(), 32));
+        free_raw_ptr(fil_seal(0, std::ptr::null(), std::ptr::null(), 32));
+        free_raw_ptr(fil_verify(true));
+    }
+    assert_eq!(stop_recording().unwrap(), 3);
+    assert!(!is_recording());
+    // Not recorded after it stopped
+    unsafe { free_raw_ptr(fil_verify(false)) };
+
+    let calls = read_recording(&path).unwrap();
+    assert_eq!(
+        calls,
+        vec![
+            RecordedCall {
+                name: "fil_seal".to_string(),
+                args: vec![
+                    RecordedArg::U64(1),
+                    RecordedArg::Str("/var/tmp/sealed".to_string()),
+                    RecordedArg::Bytes(ticket.to_vec()),
+                ],
+            },
+            RecordedCall {
+                name: "fil_seal".to_string(),
+                args: vec![RecordedArg::U64(0), RecordedArg::Null, RecordedArg::Null],
+            },
+            RecordedCall {
+                name: "fil_verify".to_string(),
+                args: vec![RecordedArg::Bool(true)],
+            },
+        ]
+    );
+
+    // The replayed calls go through the exported function again
+    let statuses = Rc::new(RefCell::new(Vec::new()));
+    let mut harness = ReplayHarness::new();
+    let seal_statuses = statuses.clone();
+    harness
+        .register("fil_seal", move |args| {
+            let path = args[1].as_str().map(|path| CString::new(path).unwrap());
+            let ticket = args[2].as_bytes().unwrap_or(&[]);
+            unsafe {
+                let response = fil_seal(
+                    args[0].as_u64().unwrap(),
+                    path.as_ref().map_or(std::ptr::null(), |path| path.as_ptr()),
+                    ticket.as_ptr(),
+                    ticket.len(),
+                );
+                seal_statuses.borrow_mut().push((*response).status_code);
+                free_raw_ptr(response);
+            }
+        })
+        .register("fil_verify", |args| {
+            assert_eq!(args, [RecordedArg::Bool(true)]);
+        });
+    assert_eq!(harness.replay_file(&path).unwrap(), 3);
+    assert_eq!(
+        *statuses.borrow(),
+        [
+            FCPResponseStatus::FCPNoError,
+            FCPResponseStatus::FCPCallerError
+        ]
+    );
+
+    // A recording with a function the harness doesn't know isn't replayed at all
+    let mut partial = ReplayHarness::new();
+    partial.register("fil_seal", |_| panic!("replayed"));
+    match partial.replay_file(&path) {
+        Err(ReplayError::UnknownEntrypoint(name)) => assert_eq!(name, "fil_verify"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn exported_recording() {
+    let _serial = serial();
+    let dir = TempDir::create("fil-recording").unwrap();
+    let path = CString::new(dir.path().join("calls.rec").to_str().unwrap()).unwrap();
+    unsafe {
+        assert!(!fil_start_recording(std::ptr::null()));
+        assert!(fil_start_recording(path.as_ptr()));
+        free_raw_ptr(fil_verify(false));
+    }
+    assert!(fil_stop_recording());
+    // Stopping again is fine
+    assert_eq!(stop_recording().unwrap(), 0);
+    assert_eq!(
+        read_recording(dir.path().join("calls.rec").as_path())
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn calls_round_trip() {
+    let call = RecordedCall {
+        name: "fil_generate_winning_post".to_string(),
+        args: vec![
+            RecordedArg::Null,
+            RecordedArg::U64(u64::MAX),
+            RecordedArg::I64(-42),
+            RecordedArg::Bool(false),
+            RecordedArg::F64(0.5),
+            RecordedArg::Str("ünïcode".to_string()),
+            RecordedArg::Bytes(vec![0, 1, 255]),
+            RecordedArg::Bytes(Vec::new()),
+        ],
+    };
+    let mut encoded = Vec::new();
+    call.encode(&mut encoded);
+    assert_eq!(RecordedCall::decode(&encoded).unwrap(), call);
+
+    for len in 0..encoded.len() {
+        assert!(RecordedCall::decode(&encoded[..len]).is_err());
+    }
+    encoded.push(0);
+    assert!(RecordedCall::decode(&encoded).is_err());
+}
+
+#[test]
+fn invalid_recordings_are_rejected() {
+    let dir = TempDir::create("fil-recording").unwrap();
+    let path = dir.path().join("calls.rec");
+
+    fs::write(&path, b"not a recording").unwrap();
+    assert!(matches!(
+        read_recording(&path),
+        Err(ReplayError::NotARecording)
+    ));
+
+    let mut bytes = RECORDING_MAGIC.to_vec();
+    encode_length_delimited(
+        &RecordedCall {
+            name: "fil_seal".to_string(),
+            args: Vec::new(),
+        },
+        &mut bytes,
+    );
+    bytes.pop();
+    fs::write(&path, &bytes).unwrap();
+    assert!(matches!(read_recording(&path), Err(ReplayError::Frame(_))));
+
+    assert!(matches!(
+        read_recording(&dir.path().join("missing.rec")),
+        Err(ReplayError::Io(_))
+    ));
+}
@@ -0,0 +1,103 @@
+use std::ffi::CStr;
+use std::sync::Mutex;
+use std::thread;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    assert_not_reentrant, catch_panic_response_not_reentrant, enter_ffi, free_raw_ptr, lifecycle,
+    raw_ptr, set_reentrancy_policy, FCPResponseStatus, GuardError, ReentrancyPolicy,
+    ThreadAffinity,
+};
+
+// The policy is global
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct HandleResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub handle: u64,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lookup(handle: u64) -> *mut HandleResponse {
+    catch_panic_response_not_reentrant(|| {
+        raw_ptr(HandleResponse {
+            handle,
+            ..Default::default()
+        })
+    })
+}
+
+// A call whose host callback calls `lookup()` again
+fn lookup_with_callback() -> (FCPResponseStatus, String) {
+    let outer = lookup(1);
+    let _call = enter_ffi();
+    let inner = lookup(2);
+    let result = unsafe {
+        let message = if (*inner).error_msg.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr((*inner).error_msg)
+                .to_string_lossy()
+                .into_owned()
+        };
+        ((*inner).status_code, message)
+    };
+    unsafe {
+        free_raw_ptr(outer);
+        free_raw_ptr(inner);
+    }
+    result
+}
+
+#[test]
+fn the_depth_counts_guarded_calls() {
+    assert_eq!(lifecycle::call_depth(), 0);
+    let outer = enter_ffi();
+    {
+        let _inner = enter_ffi();
+        assert_eq!(lifecycle::call_depth(), 2);
+    }
+    assert_eq!(lifecycle::call_depth(), 1);
+    drop(outer);
+    assert_eq!(lifecycle::call_depth(), 0);
+}
+
+#[test]
+fn reentrant_calls_are_allowed_by_default() {
+    let _serial = serial();
+    set_reentrancy_policy(ReentrancyPolicy::Allow);
+    let call = enter_ffi();
+    assert_eq!(assert_not_reentrant(), Ok(()));
+    drop(call);
+    assert_eq!(lookup_with_callback().0, FCPResponseStatus::FCPNoError);
+}
+
+#[test]
+fn reentrant_calls_can_be_rejected() {
+    let _serial = serial();
+    set_reentrancy_policy(ReentrancyPolicy::Reject);
+    assert_eq!(assert_not_reentrant(), Ok(()));
+    let (status, message) = lookup_with_callback();
+    set_reentrancy_policy(ReentrancyPolicy::Allow);
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    assert!(message.contains("re-entrant call"), "{}", message);
+}
+
+#[test]
+fn objects_can_be_bound_to_their_thread() {
+    let affinity = ThreadAffinity::current();
+    assert_eq!(affinity.check(), Ok(()));
+    let err = thread::spawn(move || affinity.check())
+        .join()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(err, GuardError::WrongThread { owner, .. } if owner == affinity.owner()));
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "testing")]
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::testing::{assert_ffi_error, assert_ffi_ok, LeakCheck, ResponseGuard};
+use ffi_toolkit::{catch_panic_response, raw_ptr, rust_str_to_c_str, FCPResponseStatus};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(destroy = "fil_destroy_sector_size_response")]
+pub struct SectorSizeResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_size: u64,
+}
+
+#[no_mangle]
+pub extern "C" fn fil_sector_size(sector_id: u64) -> *mut SectorSizeResponse {
+    catch_panic_response(|| {
+        if sector_id == 0 {
+            panic!("unknown sector {}", sector_id);
+        }
+        raw_ptr(SectorSizeResponse {
+            sector_size: 2048,
+            ..Default::default()
+        })
+    })
+}
+
+#[test]
+fn successful_calls() {
+    let _leaks = LeakCheck::start();
+    let response =
+        ResponseGuard::with_destroy(fil_sector_size(1), fil_destroy_sector_size_response);
+    assert_eq!(response.assert_ok().sector_size, 2048);
+    assert_eq!(response.error_message(), None);
+    assert_eq!(
+        unsafe { assert_ffi_ok(response.as_ptr()) }.sector_size,
+        2048
+    );
+}
+
+#[test]
+fn failed_calls() {
+    let _leaks = LeakCheck::start();
+    let response = ResponseGuard::new(fil_sector_size(0));
+    response.assert_error(FCPResponseStatus::FCPUnclassifiedError, "unknown sector 0");
+    unsafe {
+        assert_ffi_error(
+            response.as_ptr(),
+            FCPResponseStatus::FCPUnclassifiedError,
+            "",
+        )
+    };
+}
+
+#[test]
+#[should_panic(
+    expected = "the call failed with FCPUnclassifiedError: Rust panic: unknown sector 0"
+)]
+fn assert_ok_reports_the_error() {
+    let response = ResponseGuard::new(fil_sector_size(0));
+    response.assert_ok();
+}
+
+#[test]
+#[should_panic(expected = "doesn't contain \"out of space\"")]
+fn assert_error_checks_the_message() {
+    let response = ResponseGuard::new(fil_sector_size(0));
+    response.assert_error(FCPResponseStatus::FCPUnclassifiedError, "out of space");
+}
+
+#[test]
+fn leak_checks_report_leaks() {
+    let leaks = LeakCheck::start();
+    let leaked = rust_str_to_c_str("leaked");
+    let report = leaks.finish();
+    assert_eq!(report.leaks.get("c_char"), Some(&1));
+    unsafe { ffi_toolkit::free_c_str(leaked) };
+}
+
+#[test]
+#[should_panic(expected = "leaked 1 allocation(s) of `c_char`")]
+fn leak_checks_fail_when_dropped() {
+    let _leaks = LeakCheck::start();
+    std::mem::forget(ResponseGuard::new(raw_ptr(SectorSizeResponse {
+        error_msg: rust_str_to_c_str("leaked"),
+        ..Default::default()
+    })));
+}
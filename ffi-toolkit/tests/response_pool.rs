@@ -0,0 +1,127 @@
+use std::ffi::CStr;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    enable_response_pool, free_raw_ptr, pooled_response, raw_ptr, response_pool_len,
+    rust_str_to_c_str, FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+#[ffi_drop(destroy = "fil_destroy_verify_response")]
+pub struct VerifyResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub valid: bool,
+}
+
+// A type of its own per test, as the pools are global
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct SignResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub signature: u64,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct AggregateResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+#[test]
+fn destroyed_responses_are_recycled() {
+    enable_response_pool::<VerifyResponse>(4);
+    let first = pooled_response(VerifyResponse {
+        error_msg: rust_str_to_c_str("bad signature"),
+        ..Default::default()
+    });
+    unsafe { fil_destroy_verify_response(first) };
+    assert_eq!(response_pool_len::<VerifyResponse>(), 1);
+
+    let second = pooled_response(VerifyResponse {
+        valid: true,
+        ..Default::default()
+    });
+    assert_eq!(second, first);
+    assert_eq!(response_pool_len::<VerifyResponse>(), 0);
+    unsafe {
+        assert!((*second).valid);
+        assert!((*second).error_msg.is_null());
+        fil_destroy_verify_response(second);
+    }
+}
+
+#[test]
+fn the_pool_is_bounded() {
+    enable_response_pool::<SignResponse>(2);
+    let responses: Vec<_> = (0..5)
+        .map(|signature| {
+            raw_ptr(SignResponse {
+                signature,
+                ..Default::default()
+            })
+        })
+        .collect();
+    for response in responses {
+        unsafe { free_raw_ptr(response) };
+    }
+    assert_eq!(response_pool_len::<SignResponse>(), 2);
+
+    // Shrinking or removing the pool frees the boxes
+    enable_response_pool::<SignResponse>(1);
+    assert_eq!(response_pool_len::<SignResponse>(), 1);
+    enable_response_pool::<SignResponse>(0);
+    assert_eq!(response_pool_len::<SignResponse>(), 0);
+}
+
+#[test]
+fn types_without_a_pool_are_freed() {
+    let response = pooled_response(AggregateResponse {
+        error_msg: rust_str_to_c_str("no proofs"),
+        ..Default::default()
+    });
+    unsafe {
+        assert_eq!(
+            CStr::from_ptr((*response).error_msg).to_str().unwrap(),
+            "no proofs"
+        );
+        free_raw_ptr(response);
+    }
+    assert_eq!(response_pool_len::<AggregateResponse>(), 0);
+}
+
+#[test]
+fn responses_are_recycled_across_threads() {
+    #[repr(C)]
+    #[derive(FFIResponse)]
+    pub struct ProveResponse {
+        pub status_code: FCPResponseStatus,
+        pub error_msg: *const libc::c_char,
+        pub proof: [u8; 32],
+    }
+
+    enable_response_pool::<ProveResponse>(8);
+    let threads: Vec<_> = (0..4u8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    let response = pooled_response(ProveResponse {
+                        proof: [i; 32],
+                        ..Default::default()
+                    });
+                    unsafe {
+                        assert_eq!((*response).proof, [i; 32]);
+                        free_raw_ptr(response);
+                    }
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(response_pool_len::<ProveResponse>() <= 8);
+}
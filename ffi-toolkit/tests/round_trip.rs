@@ -0,0 +1,47 @@
+use std::slice;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{ffi_round_trip_test, free_c_str, FfiFrom, FfiInto, FfiU128};
+use proptest::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct FFIPiece {
+    pub name: *const libc::c_char,
+    pub data_len: libc::size_t,
+    pub data_ptr: *const u8,
+}
+
+impl FfiInto<FFIPiece> for Piece {
+    fn ffi_into(self) -> FFIPiece {
+        let (data_ptr, data_len) = ffi_toolkit::vec_into_raw_parts_exact(self.data);
+        FFIPiece {
+            name: ffi_toolkit::rust_str_to_c_str(self.name),
+            data_len,
+            data_ptr,
+        }
+    }
+}
+
+impl FfiFrom<FFIPiece> for Piece {
+    unsafe fn ffi_from(c_repr: &FFIPiece) -> Self {
+        Piece {
+            name: ffi_toolkit::c_str_to_rust_str(c_repr.name).into_owned(),
+            data: slice::from_raw_parts(c_repr.data_ptr, c_repr.data_len).to_vec(),
+        }
+    }
+}
+
+fn piece() -> impl Strategy<Value = Piece> {
+    ("[^\0]*", proptest::collection::vec(any::<u8>(), 0..64))
+        .prop_map(|(name, data)| Piece { name, data })
+}
+
+ffi_round_trip_test!(u128_round_trip, u128 => FfiU128, any::<u128>());
+ffi_round_trip_test!(piece_round_trip, Piece => FFIPiece, piece());
@@ -0,0 +1,12 @@
+#![cfg(feature = "sanitizer")]
+
+use ffi_toolkit::sanitizer;
+
+#[test]
+fn poison_and_unpoison() {
+    let buffer = vec![0u8; 64];
+    sanitizer::poison_region(buffer.as_ptr(), buffer.len());
+    assert!(sanitizer::is_poisoned(buffer.as_ptr()));
+    sanitizer::unpoison_region(buffer.as_ptr(), buffer.len());
+    assert!(!sanitizer::is_poisoned(buffer.as_ptr()));
+}
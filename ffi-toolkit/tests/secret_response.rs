@@ -0,0 +1,164 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use drop_struct_macro_derive::{DropStructMacro, FfiDebug};
+use ffi_toolkit::{
+    fil_free_secret_bytes, free_c_str, free_secret_c_str, rust_str_to_c_str, Fil32, SecretBytes,
+};
+
+// Sizes no other allocation of this test binary has
+const KEY_LEN: usize = 7_771;
+const SEED_LEN: usize = 3_331;
+// The capacity of the `SecretBytes`, which have fewer bytes in use
+const SECRET_BYTES_CAP: usize = 5_113;
+
+// The number of freed secret allocations, and how many of them were zeroed
+static SECRETS_FREED: AtomicUsize = AtomicUsize::new(0);
+static SECRETS_ZEROED: AtomicUsize = AtomicUsize::new(0);
+// The same for the `SecretBytes`, which are tested on their own
+static SECRET_BYTES_FREED: AtomicUsize = AtomicUsize::new(0);
+static SECRET_BYTES_ZEROED: AtomicUsize = AtomicUsize::new(0);
+
+// checks the contents of the secret allocations when they are freed
+struct InspectingAllocator;
+
+unsafe impl GlobalAlloc for InspectingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == SECRET_BYTES_CAP {
+            SECRET_BYTES_FREED.fetch_add(1, Ordering::SeqCst);
+            if (0..SECRET_BYTES_CAP).all(|offset| *ptr.add(offset) == 0) {
+                SECRET_BYTES_ZEROED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let secret_len = match layout.size() {
+            KEY_LEN => Some(KEY_LEN),
+            // Without the nul terminator
+            size if size == SEED_LEN + 1 => Some(SEED_LEN),
+            _ => None,
+        };
+        if let Some(len) = secret_len {
+            SECRETS_FREED.fetch_add(1, Ordering::SeqCst);
+            if (0..len).all(|offset| *ptr.add(offset) == 0) {
+                SECRETS_ZEROED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: InspectingAllocator = InspectingAllocator;
+
+#[repr(C)]
+#[derive(DropStructMacro, FfiDebug)]
+pub struct KeyResponse {
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(secret)]
+    pub private_key_ptr: *const u8,
+    pub private_key_len: libc::size_t,
+    #[ffi_drop(secret)]
+    pub seed: *const libc::c_char,
+    pub public_key: u64,
+}
+
+#[test]
+fn secrets_are_zeroed_and_redacted() {
+    let mut private_key = vec![0xab_u8; KEY_LEN];
+    private_key.shrink_to_fit();
+    let response = KeyResponse {
+        error_msg: ptr::null(),
+        private_key_len: private_key.len(),
+        private_key_ptr: private_key.leak().as_mut_ptr(),
+        seed: rust_str_to_c_str("s".repeat(SEED_LEN)),
+        public_key: 42,
+    };
+
+    let debug = format!("{:?}", response);
+    assert!(debug.starts_with("KeyResponse { error_msg: 0x0, private_key_ptr: <redacted>"));
+    assert!(debug.contains("seed: <redacted>, public_key: 42 }"));
+    assert!(debug.contains(&format!("private_key_len: {}", KEY_LEN)));
+
+    drop(response);
+    assert_eq!(SECRETS_FREED.load(Ordering::SeqCst), 2);
+    assert_eq!(SECRETS_ZEROED.load(Ordering::SeqCst), 2);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct NullSecretResponse {
+    #[ffi_drop(secret)]
+    pub seed: *const libc::c_char,
+    #[ffi_drop(secret)]
+    pub key_ptr: *const u8,
+    pub key_len: libc::size_t,
+}
+
+#[test]
+fn null_and_empty_secrets() {
+    drop(NullSecretResponse {
+        seed: ptr::null(),
+        key_ptr: Vec::<u8>::new().leak().as_mut_ptr(),
+        key_len: 0,
+    });
+}
+
+#[repr(C)]
+#[derive(DropStructMacro, FfiDebug)]
+pub struct TicketResponse {
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(secret)]
+    pub ticket: Fil32,
+    #[ffi_drop(secret)]
+    pub nonce: [u8; 16],
+    pub private_key: SecretBytes,
+    pub sector_id: u64,
+}
+
+// a `SecretBytes` with spare capacity
+fn secret_bytes(len: usize) -> SecretBytes {
+    let mut bytes = Vec::with_capacity(SECRET_BYTES_CAP);
+    bytes.resize(len, 0xcd);
+    SecretBytes::from_vec(bytes)
+}
+
+#[test]
+fn secret_values_are_zeroed_in_place() {
+    let mut response = ManuallyDrop::new(TicketResponse {
+        error_msg: ptr::null(),
+        ticket: Fil32([0xab; 32]),
+        nonce: [0xcd; 16],
+        private_key: SecretBytes::copy_from_slice(b"key"),
+        sector_id: 7,
+    });
+    let debug = format!("{:?}", *response);
+    assert!(debug.contains("ticket: <redacted>, nonce: <redacted>"));
+    assert!(debug.contains("private_key: SecretBytes(<3 bytes redacted>), sector_id: 7"));
+
+    unsafe { ManuallyDrop::drop(&mut response) };
+    // The fields are `Copy` and the memory is still the response's
+    assert_eq!(response.ticket, Fil32([0; 32]));
+    assert_eq!(response.nonce, [0; 16]);
+    assert_eq!(response.sector_id, 7);
+}
+
+#[test]
+fn secret_bytes_are_zeroed_with_their_spare_capacity() {
+    let bytes = secret_bytes(100);
+    assert_eq!(bytes.len(), 100);
+    assert!(bytes.ct_eq(&[0xcd; 100]));
+    assert!(!bytes.ct_eq(&[0xcd; 99]));
+    assert_eq!(bytes, secret_bytes(100));
+    assert_ne!(bytes, secret_bytes(99));
+    drop(bytes);
+
+    fil_free_secret_bytes(secret_bytes(SECRET_BYTES_CAP));
+    fil_free_secret_bytes(SecretBytes::default());
+    assert_eq!(SECRET_BYTES_FREED.load(Ordering::SeqCst), 4);
+    assert_eq!(SECRET_BYTES_ZEROED.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "serde")]
+
+use std::ptr;
+
+use drop_struct_macro_derive::{DropStructMacro, FFIResponse, FfiSerialize};
+use ffi_toolkit::{
+    raw_ptr, rust_str_to_c_str, vec_into_raw_parts_exact, FCPResponseStatus, FfiBytes, FfiString,
+    FfiStringArray, SecretBytes, StringRef,
+};
+use serde_json::json;
+
+#[test]
+fn status_codes_round_trip_by_name() {
+    let json = serde_json::to_string(&FCPResponseStatus::FCPCallerError).unwrap();
+    assert_eq!(json, "\"FCPCallerError\"");
+    let status: FCPResponseStatus = serde_json::from_str(&json).unwrap();
+    assert_eq!(status, FCPResponseStatus::FCPCallerError);
+    assert!(serde_json::from_str::<FCPResponseStatus>("\"FCPNoSuchError\"").is_err());
+}
+
+#[test]
+fn toolkit_types_round_trip() {
+    let bytes = FfiBytes::from_vec(vec![1, 2, 3]);
+    let json = serde_json::to_string(&bytes).unwrap();
+    assert_eq!(json, "[1,2,3]");
+    assert_eq!(
+        serde_json::from_str::<FfiBytes>(&json).unwrap().as_slice(),
+        &[1, 2, 3]
+    );
+    let secret = SecretBytes::copy_from_slice(b"key");
+    assert_eq!(serde_json::to_string(&secret).unwrap(), "\"<redacted>\"");
+
+    let s = FfiString::new("/var/tmp/sealed".to_string());
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(json, "\"/var/tmp/sealed\"");
+    assert_eq!(
+        serde_json::from_str::<FfiString>(&json).unwrap().as_str(),
+        "/var/tmp/sealed"
+    );
+
+    let strings = FfiStringArray::new(vec!["a", "b"]);
+    let json = serde_json::to_string(&strings).unwrap();
+    assert_eq!(json, "[\"a\",\"b\"]");
+    assert_eq!(
+        serde_json::from_str::<FfiStringArray>(&json)
+            .unwrap()
+            .to_vec(),
+        vec!["a", "b"]
+    );
+}
+
+#[test]
+fn string_refs_serialize_what_they_borrow() {
+    let s = String::from("cache");
+    assert_eq!(
+        serde_json::to_value(StringRef::from_str(&s)).unwrap(),
+        json!("cache")
+    );
+}
+
+#[repr(C)]
+#[derive(DropStructMacro, FfiSerialize)]
+pub struct PieceInfo {
+    pub size: u64,
+    pub comm_p_ptr: *const u8,
+    pub comm_p_len: libc::size_t,
+}
+
+#[repr(C)]
+#[derive(FFIResponse, FfiSerialize)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub proof_ptr: *const u8,
+    pub proof_len: libc::size_t,
+    pub paths_ptr: *const *const libc::c_char,
+    pub paths_len: libc::size_t,
+    #[ffi_drop(nested)]
+    pub piece: *const PieceInfo,
+    #[ffi_drop(secret)]
+    pub seed_ptr: *const u8,
+    pub seed_len: libc::size_t,
+}
+
+#[test]
+fn derived_serialization_follows_pointers() {
+    let (proof_ptr, proof_len) = vec_into_raw_parts_exact(vec![7u8, 8]);
+    let (paths_ptr, paths_len) = vec_into_raw_parts_exact(vec![
+        rust_str_to_c_str("/sealed") as *const libc::c_char,
+        ptr::null(),
+    ]);
+    let (seed_ptr, seed_len) = vec_into_raw_parts_exact(vec![42u8; 4]);
+    let response = SealResponse {
+        proof_ptr,
+        proof_len,
+        paths_ptr: paths_ptr as *const _,
+        paths_len,
+        piece: raw_ptr(PieceInfo {
+            size: 2048,
+            comm_p_ptr: ptr::null(),
+            comm_p_len: 0,
+        }),
+        seed_ptr,
+        seed_len,
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_value(&response).unwrap(),
+        json!({
+            "status_code": "FCPNoError",
+            "error_msg": null,
+            "proof_ptr": [7, 8],
+            "proof_len": 2,
+            "paths_ptr": ["/sealed", null],
+            "paths_len": 2,
+            "piece": { "size": 2048, "comm_p_ptr": null, "comm_p_len": 0 },
+            "seed_ptr": "<redacted>",
+            "seed_len": 4,
+        })
+    );
+}
+
+#[test]
+fn invalid_pointers_fail_the_serialization() {
+    let response = PieceInfo {
+        size: 0,
+        comm_p_ptr: ptr::null(),
+        comm_p_len: 32,
+    };
+    let err = serde_json::to_string(&response).unwrap_err();
+    assert!(err.to_string().contains("invalid array of 32 elements"));
+}
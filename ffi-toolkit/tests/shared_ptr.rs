@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use ffi_toolkit::{
+    arc_from_shared, borrow_shared, clone_shared, outstanding_shared_refs, release_shared,
+    shared_raw_ptr, shared_ref_count,
+};
+
+struct ParameterCache {
+    dropped: Arc<AtomicBool>,
+}
+
+impl Drop for ParameterCache {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn last_release_drops_the_object() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let cache = shared_raw_ptr(Arc::new(ParameterCache {
+        dropped: Arc::clone(&dropped),
+    }));
+    unsafe {
+        let other = clone_shared(cache);
+        assert_eq!(other, cache);
+        assert_eq!(shared_ref_count(cache), 2);
+
+        let users: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = clone_shared(cache) as usize;
+                thread::spawn(move || {
+                    let cache = cache as *const ParameterCache;
+                    assert!(borrow_shared(cache).is_some());
+                    release_shared(cache);
+                })
+            })
+            .collect();
+        for user in users {
+            user.join().unwrap();
+        }
+
+        release_shared(cache);
+        assert!(!dropped.load(Ordering::SeqCst));
+        release_shared(other);
+    }
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn arcs_outlive_the_handle() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let cache = shared_raw_ptr(Arc::new(ParameterCache {
+        dropped: Arc::clone(&dropped),
+    }));
+    let arc = unsafe { arc_from_shared(cache) }.unwrap();
+    unsafe { release_shared(cache) };
+    assert!(!dropped.load(Ordering::SeqCst));
+    assert_eq!(Arc::strong_count(&arc), 1);
+    drop(arc);
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn null() {
+    let null: *const ParameterCache = std::ptr::null();
+    unsafe {
+        assert!(clone_shared(null).is_null());
+        release_shared(null);
+        assert!(borrow_shared(null).is_none());
+        assert!(arc_from_shared(null).is_none());
+        assert_eq!(shared_ref_count(null), 0);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn outstanding_refs_are_counted() {
+    // The only test that leaves a reference outstanding while it runs, so the count only rises
+    let before = outstanding_shared_refs().unwrap();
+    let cache = shared_raw_ptr(Arc::new(0u64));
+    assert!(outstanding_shared_refs().unwrap() > before);
+    unsafe { release_shared(cache) };
+}
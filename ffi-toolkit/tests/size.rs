@@ -0,0 +1,15 @@
+use ffi_toolkit::{i64_to_ptrdiff, size_to_u64, u64_to_size, POINTER_WIDTH};
+
+#[test]
+fn sizes_fit_the_target() {
+    assert_eq!(u64_to_size(42), Some(42));
+    assert_eq!(size_to_u64(usize::MAX), usize::MAX as u64);
+    assert_eq!(i64_to_ptrdiff(-1), Some(-1));
+
+    if POINTER_WIDTH == 32 {
+        assert_eq!(u64_to_size(u64::from(u32::MAX) + 1), None);
+        assert_eq!(i64_to_ptrdiff(i64::MIN), None);
+    } else {
+        assert_eq!(u64_to_size(u64::MAX), Some(usize::MAX));
+    }
+}
@@ -0,0 +1,47 @@
+use std::ptr;
+
+use ffi_toolkit::{try_mut_slice_from_raw, try_slice_from_raw};
+
+#[test]
+fn shared_slices() {
+    let sectors = [1u64, 2, 3];
+    assert_eq!(
+        unsafe { try_slice_from_raw(sectors.as_ptr(), 3) },
+        Some(&sectors[..])
+    );
+    assert_eq!(
+        unsafe { try_slice_from_raw(sectors.as_ptr(), 0) },
+        Some(&[][..])
+    );
+    // Null is only an empty slice
+    assert_eq!(
+        unsafe { try_slice_from_raw::<u64>(ptr::null(), 0) },
+        Some(&[][..])
+    );
+    assert_eq!(unsafe { try_slice_from_raw::<u64>(ptr::null(), 1) }, None);
+}
+
+#[test]
+fn invalid_pointers_and_lengths() {
+    let sectors = [1u64, 2];
+    let unaligned = unsafe { (sectors.as_ptr() as *const u8).add(1) } as *const u64;
+    assert_eq!(unsafe { try_slice_from_raw(unaligned, 1) }, None);
+    assert_eq!(unsafe { try_slice_from_raw(unaligned, 0) }, None);
+    // The size of the elements overflows `isize`
+    assert_eq!(
+        unsafe { try_slice_from_raw(sectors.as_ptr(), usize::MAX / 4) },
+        None
+    );
+}
+
+#[test]
+fn mutable_slices() {
+    let mut buffer = [0u8; 4];
+    let slice = unsafe { try_mut_slice_from_raw(buffer.as_mut_ptr(), buffer.len()) }.unwrap();
+    slice[1] = 7;
+    assert_eq!(buffer, [0, 7, 0, 0]);
+    assert!(unsafe { try_mut_slice_from_raw::<u8>(ptr::null_mut(), 0) }
+        .unwrap()
+        .is_empty());
+    assert!(unsafe { try_mut_slice_from_raw::<u8>(ptr::null_mut(), 4) }.is_none());
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::testing::Snapshot;
+use ffi_toolkit::{
+    code_and_message_impl, error_response, free_c_str, free_raw_ptr, CodeAndMessage,
+    FCPResponseStatus,
+};
+
+#[repr(C)]
+#[derive(DropStructMacro, Debug)]
+pub struct SealResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub sector_size: u64,
+}
+
+impl Default for SealResponse {
+    fn default() -> Self {
+        SealResponse {
+            status_code: FCPResponseStatus::FCPNoError,
+            error_msg: ptr::null(),
+            sector_size: 0,
+        }
+    }
+}
+
+code_and_message_impl!(SealResponse);
+
+#[test]
+fn error_response_snapshot() {
+    let response: *mut SealResponse =
+        error_response(FCPResponseStatus::FCPCallerError, "invalid sector size");
+    let snapshot = unsafe { Snapshot::new(&*response).c_str("error_msg", (*response).error_msg) };
+    assert_eq!(
+        snapshot.to_string(),
+        "SealResponse {
+    status_code: FCPCallerError,
+    error_msg: <ptr 1>,
+    sector_size: 0,
+}
+error_msg: \"invalid sector size\"
+"
+    );
+    unsafe { free_raw_ptr(response) };
+}
+
+#[test]
+fn pointers_are_numbered_by_first_appearance() {
+    let (a, b) = (1u8, 2u8);
+    let pointers: (*const u8, *const u8, *const u8, *const u8) = (&a, &b, &a, ptr::null());
+    assert_eq!(
+        Snapshot::new(&pointers).to_string(),
+        "(\n    <ptr 1>,\n    <ptr 2>,\n    <ptr 1>,\n    null,\n)\n"
+    );
+}
+
+#[test]
+fn snapshots_are_stable_across_allocations() {
+    let render = || {
+        let response: *mut SealResponse =
+            error_response(FCPResponseStatus::FCPReceiverError, "disk full");
+        let snapshot =
+            unsafe { Snapshot::new(&*response).c_str("error_msg", (*response).error_msg) };
+        unsafe { free_raw_ptr(response) };
+        snapshot
+    };
+    assert_eq!(render(), render());
+}
+
+#[test]
+fn null_c_str() {
+    let response = SealResponse::default();
+    let snapshot = unsafe { Snapshot::new(&response).c_str("error_msg", response.error_msg) };
+    assert!(snapshot.to_string().ends_with("error_msg: null\n"));
+}
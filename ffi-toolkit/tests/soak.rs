@@ -0,0 +1,54 @@
+#![cfg(feature = "testing")]
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::time::Duration;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::testing::Soak;
+use ffi_toolkit::{free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct HandleResponse {
+    pub path: *const libc::c_char,
+}
+
+unsafe extern "C" fn create_handle() -> *mut HandleResponse {
+    raw_ptr(HandleResponse {
+        path: rust_str_to_c_str("/tmp/sector"),
+    })
+}
+
+unsafe extern "C" fn destroy_handle(ptr: *mut HandleResponse) {
+    free_raw_ptr(ptr)
+}
+
+#[test]
+fn balanced_cycle() {
+    let report = Soak::new()
+        .threads(4)
+        .duration(Duration::from_millis(200))
+        .run(|| unsafe { destroy_handle(create_handle()) });
+    assert!(report.iterations >= 4);
+}
+
+// A registry of handles the host is expected to release, not backed by toolkit allocations so
+// the test doesn't interfere with `balanced_cycle()`
+static OPEN_HANDLES: AtomicIsize = AtomicIsize::new(0);
+
+#[test]
+fn detects_drift() {
+    let report = Soak::new()
+        .threads(2)
+        .duration(Duration::from_millis(50))
+        .probe("open handles", || OPEN_HANDLES.load(Ordering::SeqCst))
+        .run_report(|| {
+            // Every cycle opens a handle and never releases it
+            OPEN_HANDLES.fetch_add(1, Ordering::SeqCst);
+        });
+    assert_eq!(
+        report.drift,
+        vec![("open handles", report.iterations as isize)]
+    );
+    assert!(report.to_string().contains("`open handles` drifted by"));
+}
@@ -0,0 +1,75 @@
+use ffi_toolkit::{
+    FCPResponseStatus, FCP_BUSY_ERROR, FCP_CALLER_ERROR, FCP_NO_ERROR, FCP_RECEIVER_ERROR,
+    FCP_UNCLASSIFIED_ERROR,
+};
+
+#[test]
+fn constants_match_discriminants() {
+    assert_eq!(FCPResponseStatus::FCPNoError as i32, FCP_NO_ERROR);
+    assert_eq!(
+        FCPResponseStatus::FCPUnclassifiedError as i32,
+        FCP_UNCLASSIFIED_ERROR
+    );
+    assert_eq!(FCPResponseStatus::FCPCallerError as i32, FCP_CALLER_ERROR);
+    assert_eq!(
+        FCPResponseStatus::FCPReceiverError as i32,
+        FCP_RECEIVER_ERROR
+    );
+    assert_eq!(FCPResponseStatus::FCPBusyError as i32, FCP_BUSY_ERROR);
+
+    for (variant, _, value) in FCPResponseStatus::VARIANTS {
+        assert_eq!(*variant as i32, *value);
+    }
+}
+
+#[test]
+fn header_matches_discriminants() {
+    let header = FCPResponseStatus::c_header();
+    for (variant, constant, value) in FCPResponseStatus::VARIANTS {
+        assert!(header.contains(&format!("#define {} {}\n", constant, value)));
+        assert!(header.contains(&format!("  {:?} = {},\n", variant, value)));
+    }
+    assert!(header.contains("typedef enum FCPResponseStatus {\n"));
+    assert!(header.ends_with("} FCPResponseStatus;\n"));
+}
+
+#[test]
+fn classification() {
+    assert!(FCPResponseStatus::FCPNoError.is_ok());
+    assert!(FCPResponseStatus::FCPCallerError.is_error());
+    assert!(FCPResponseStatus::FCPCallerError.is_caller_error());
+    assert!(FCPResponseStatus::FCPReceiverError.is_receiver_error());
+    assert!(FCPResponseStatus::FCPBusyError.is_retryable());
+    for (variant, _, _) in FCPResponseStatus::VARIANTS {
+        if *variant != FCPResponseStatus::FCPBusyError {
+            assert!(!variant.is_retryable());
+        }
+    }
+}
+
+#[test]
+fn std_errors() {
+    use std::io::ErrorKind;
+
+    assert_eq!(
+        FCPResponseStatus::from(ErrorKind::InvalidInput),
+        FCPResponseStatus::FCPCallerError
+    );
+    assert_eq!(
+        FCPResponseStatus::from(ErrorKind::TimedOut),
+        FCPResponseStatus::FCPBusyError
+    );
+    assert_eq!(
+        FCPResponseStatus::from(ErrorKind::NotFound),
+        FCPResponseStatus::FCPReceiverError
+    );
+    let utf8 = String::from_utf8(vec![0xff]).unwrap_err();
+    assert_eq!(
+        FCPResponseStatus::from(utf8.utf8_error()),
+        FCPResponseStatus::FCPCallerError
+    );
+    assert_eq!(
+        FCPResponseStatus::from(utf8),
+        FCPResponseStatus::FCPCallerError
+    );
+}
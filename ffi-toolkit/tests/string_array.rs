@@ -0,0 +1,80 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::{fil_free_string_array, fil_string_array_get, free_c_str, FfiStringArray};
+
+#[test]
+fn round_trip() {
+    let array = FfiStringArray::new(vec!["a", "bc", ""]);
+    assert_eq!(array.len(), 3);
+    assert_eq!(array.to_vec(), vec!["a", "bc", ""]);
+    let second = unsafe { CStr::from_ptr(*array.ptr.add(1)) };
+    assert_eq!(second.to_str().unwrap(), "bc");
+}
+
+#[test]
+fn empty() {
+    let array = FfiStringArray::default();
+    assert!(array.is_empty());
+    assert!(!array.ptr.is_null());
+    assert!(array.to_vec().is_empty());
+}
+
+#[test]
+fn interior_nul_is_truncated() {
+    let array = FfiStringArray::new(vec![String::from("before\0after")]);
+    assert_eq!(array.to_vec(), vec!["before"]);
+}
+
+#[test]
+fn from_iter() {
+    let array: FfiStringArray = (1..=3).map(|n| format!("bafy{}", n)).collect();
+    assert_eq!(array.to_vec(), vec!["bafy1", "bafy2", "bafy3"]);
+}
+
+#[test]
+fn indexed_access() {
+    let array = FfiStringArray::new(vec!["/tmp/a", "/tmp/b"]);
+    unsafe {
+        let first = fil_string_array_get(&array, 0);
+        assert_eq!(CStr::from_ptr(first).to_str().unwrap(), "/tmp/a");
+        assert!(fil_string_array_get(&array, 2).is_null());
+        assert!(fil_string_array_get(ptr::null(), 0).is_null());
+    }
+    fil_free_string_array(array);
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct ListPathsResponse {
+    pub paths_ptr: *const *const libc::c_char,
+    pub paths_len: libc::size_t,
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn drop_struct_frees_raw_string_arrays() {
+    let ((), report) = ffi_toolkit::testing::track_ffi_memory_report(|| {
+        let paths: Vec<*const libc::c_char> = ["/tmp/a", "/tmp/b"]
+            .iter()
+            .map(|path| ffi_toolkit::rust_str_to_c_str(*path) as *const libc::c_char)
+            .collect();
+        let (paths_ptr, paths_len) = ffi_toolkit::vec_into_raw_parts_exact(paths);
+        let response = ListPathsResponse {
+            paths_len,
+            paths_ptr,
+        };
+        drop(response);
+    });
+    assert!(report.leaks.is_empty());
+    assert!(report.double_frees.is_empty());
+}
+
+#[test]
+fn drop_struct_ignores_null_string_arrays() {
+    drop(ListPathsResponse {
+        paths_ptr: ptr::null(),
+        paths_len: 0,
+    });
+}
@@ -0,0 +1,61 @@
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    fil_free_string, free_raw_ptr, raw_ptr, FCPResponseStatus, FfiString, StringRef,
+};
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct GetValueResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub value: FfiString,
+}
+
+#[test]
+fn interior_nul_bytes_are_kept() {
+    let s = FfiString::from("key\0value");
+    assert_eq!(s.len(), 9);
+    assert_eq!(s.as_str(), "key\0value");
+    let borrowed = s.as_string_ref();
+    assert_eq!(unsafe { borrowed.as_str() }, Ok("key\0value"));
+    assert_eq!(s.into_string(), "key\0value");
+}
+
+#[test]
+fn string_refs_from_c() {
+    let bytes = b"bafk\0\xff";
+    let s = StringRef {
+        ptr: bytes.as_ptr(),
+        len: bytes.len(),
+    };
+    assert!(unsafe { s.as_str() }.is_err());
+    assert_eq!(unsafe { s.to_string_lossy() }, "bafk\0\u{fffd}");
+    assert_eq!(unsafe { s.as_bytes() }, bytes);
+
+    let empty = StringRef {
+        ptr: std::ptr::null(),
+        len: 0,
+    };
+    assert_eq!(unsafe { empty.as_str() }, Ok(""));
+    assert_eq!(unsafe { StringRef::default().as_str() }, Ok(""));
+}
+
+#[test]
+fn response_fields() {
+    let response = raw_ptr(GetValueResponse {
+        value: FfiString::from("v\0lue".to_string()),
+        ..Default::default()
+    });
+    unsafe {
+        assert_eq!((*response).value.as_str(), "v\0lue");
+        // Frees the string along with the response
+        free_raw_ptr(response);
+    }
+    assert!(GetValueResponse::default().value.is_empty());
+}
+
+#[test]
+fn freed_by_c() {
+    fil_free_string(FfiString::from("proof"));
+    fil_free_string(FfiString::default());
+}
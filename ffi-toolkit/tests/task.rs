@@ -0,0 +1,352 @@
+use std::ffi::CStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::{
+    catch_panic_response_with_timeout, fil_task_poll, fil_task_release, fil_task_timing,
+    fil_task_wait, free_raw_ptr, is_live, lifecycle, raw_ptr, set_task_threads, spawn_ffi_task,
+    spawn_ffi_task_with_callback, task_take_response, task_timing, worker_pool_stats,
+    worker_queue_depth, FCPResponseStatus, FfiTaskStatus, FfiTaskTiming, HandleError, SendUserData,
+};
+
+// `shutdown()` stops the pool
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct ProveResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    pub proofs: u64,
+}
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct OtherResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+fn serial() -> std::sync::MutexGuard<'static, ()> {
+    SERIAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn prove(proofs: u64) -> *mut ProveResponse {
+    raw_ptr(ProveResponse {
+        proofs,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn spawn_wait_take() {
+    let _serial = serial();
+    let (release, blocked) = mpsc::channel::<()>();
+    let task = spawn_ffi_task(move || {
+        blocked.recv().unwrap();
+        prove(3)
+    });
+
+    assert_eq!(fil_task_poll(task), FfiTaskStatus::Running);
+    assert_eq!(fil_task_wait(task, 10), FfiTaskStatus::Running);
+    assert_eq!(task_take_response::<ProveResponse>(task), Ok(None));
+
+    release.send(()).unwrap();
+    assert_eq!(fil_task_wait(task, 60_000), FfiTaskStatus::Finished);
+    assert_eq!(fil_task_poll(task), FfiTaskStatus::Finished);
+    assert!(matches!(
+        task_take_response::<OtherResponse>(task),
+        Err(HandleError::WrongType { .. })
+    ));
+
+    let response = task_take_response::<ProveResponse>(task).unwrap().unwrap();
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).proofs, 3);
+        free_raw_ptr(response);
+    }
+    assert!(!is_live(task));
+    assert_eq!(fil_task_poll(task), FfiTaskStatus::InvalidHandle);
+    assert_eq!(
+        task_take_response::<ProveResponse>(task),
+        Err(HandleError::Stale)
+    );
+}
+
+#[test]
+fn panics_become_error_responses() {
+    let _serial = serial();
+    let task = spawn_ffi_task(|| -> *mut ProveResponse { panic!("out of memory") });
+    assert_eq!(fil_task_wait(task, 60_000), FfiTaskStatus::Finished);
+    let response = task_take_response::<ProveResponse>(task).unwrap().unwrap();
+    unsafe {
+        assert_eq!(
+            (*response).status_code,
+            FCPResponseStatus::FCPUnclassifiedError
+        );
+        let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+        assert_eq!(message, "Rust panic: out of memory");
+        free_raw_ptr(response);
+    }
+}
+
+#[test]
+fn tasks_run_concurrently() {
+    let _serial = serial();
+    let tasks: Vec<_> = (0..8).map(|i| spawn_ffi_task(move || prove(i))).collect();
+    let waiters: Vec<_> = tasks
+        .iter()
+        .map(|&task| thread::spawn(move || fil_task_wait(task, 60_000)))
+        .collect();
+    for waiter in waiters {
+        assert_eq!(waiter.join().unwrap(), FfiTaskStatus::Finished);
+    }
+    for (i, task) in tasks.into_iter().enumerate() {
+        let response = task_take_response::<ProveResponse>(task).unwrap().unwrap();
+        unsafe {
+            assert_eq!((*response).proofs, i as u64);
+            free_raw_ptr(response);
+        }
+    }
+}
+
+#[test]
+fn released_tasks_still_run() {
+    let _serial = serial();
+    let (done, finished) = mpsc::channel();
+    let task = spawn_ffi_task(move || {
+        let response = prove(1);
+        done.send(()).unwrap();
+        response
+    });
+    fil_task_release(task);
+    assert_eq!(fil_task_poll(task), FfiTaskStatus::InvalidHandle);
+    finished.recv().unwrap();
+    fil_task_release(task);
+}
+
+#[test]
+fn pool_load_and_task_timing() {
+    let _serial = serial();
+    // Starts the pool, unless an earlier test did
+    let warm_up = spawn_ffi_task(|| prove(0));
+    assert_eq!(fil_task_wait(warm_up, 60_000), FfiTaskStatus::Finished);
+    fil_task_release(warm_up);
+    let threads = worker_pool_stats().threads;
+    assert!(threads > 0);
+
+    // Every thread is kept busy, so that one more task has to wait
+    let (started, running) = mpsc::channel();
+    let (release, blocked) = mpsc::channel::<()>();
+    let blocked = Arc::new(Mutex::new(blocked));
+    let blockers: Vec<_> = (0..threads)
+        .map(|_| {
+            let started = started.clone();
+            let blocked = Arc::clone(&blocked);
+            spawn_ffi_task(move || {
+                started.send(()).unwrap();
+                blocked.lock().unwrap().recv().unwrap();
+                prove(1)
+            })
+        })
+        .collect();
+    for _ in 0..threads {
+        running.recv().unwrap();
+    }
+    let waiting = spawn_ffi_task(|| prove(2));
+    assert_eq!(worker_queue_depth(), 1);
+    let stats = worker_pool_stats();
+    assert_eq!((stats.queued, stats.running), (1, threads));
+
+    let mut timing = FfiTaskTiming::default();
+    assert_eq!(
+        unsafe { fil_task_timing(waiting, &mut timing) },
+        FfiTaskStatus::Running
+    );
+    assert_eq!(timing.run_us, 0);
+    thread::sleep(Duration::from_millis(5));
+
+    for _ in 0..threads {
+        release.send(()).unwrap();
+    }
+    for task in blockers {
+        assert_eq!(fil_task_wait(task, 60_000), FfiTaskStatus::Finished);
+        fil_task_release(task);
+    }
+    assert_eq!(fil_task_wait(waiting, 60_000), FfiTaskStatus::Finished);
+    let timing = task_timing(waiting).unwrap();
+    assert!(timing.queued_us >= 5_000);
+    // The run time of a finished task doesn't grow anymore
+    assert_eq!(task_timing(waiting).unwrap().run_us, timing.run_us);
+    fil_task_release(waiting);
+    assert!(task_timing(waiting).is_err());
+}
+
+#[test]
+fn shutdown_cancels_queued_tasks() {
+    let _serial = serial();
+    // Stops the pool, so that it is started with a single thread
+    lifecycle::shutdown();
+    assert!(set_task_threads(1));
+
+    lifecycle::init();
+    let (release, blocked) = mpsc::channel::<()>();
+    let (started, running_started) = mpsc::channel::<()>();
+    let running = spawn_ffi_task(move || {
+        started.send(()).unwrap();
+        blocked.recv().unwrap();
+        prove(1)
+    });
+    let queued = spawn_ffi_task(|| prove(2));
+    assert!(!set_task_threads(1));
+    // Otherwise the shutdown may cancel it before it starts
+    running_started.recv().unwrap();
+
+    let shutdown = thread::spawn(lifecycle::shutdown);
+    while lifecycle::is_initialized() {
+        thread::yield_now();
+    }
+    release.send(()).unwrap();
+    shutdown.join().unwrap();
+
+    // The handles were released on shutdown
+    assert_eq!(fil_task_poll(running), FfiTaskStatus::InvalidHandle);
+    assert_eq!(fil_task_poll(queued), FfiTaskStatus::InvalidHandle);
+    assert!(set_task_threads(0));
+}
+
+extern "C" fn done(user_data: *mut libc::c_void, response: *mut ProveResponse) {
+    let responses = unsafe { &*(user_data as *const Mutex<Sender<usize>>) };
+    responses.lock().unwrap().send(response as usize).unwrap();
+}
+
+fn callback_channel() -> (Box<Mutex<Sender<usize>>>, Receiver<usize>) {
+    let (sender, receiver) = mpsc::channel();
+    (Box::new(Mutex::new(sender)), receiver)
+}
+
+// The status, proofs and error message of the next response, which is freed
+fn next_response(responses: &Receiver<usize>) -> (FCPResponseStatus, u64, String) {
+    let response = responses.recv().unwrap() as *mut ProveResponse;
+    unsafe {
+        let message = if (*response).error_msg.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr((*response).error_msg)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let next = ((*response).status_code, (*response).proofs, message);
+        free_raw_ptr(response);
+        next
+    }
+}
+
+fn user_data(sender: &Mutex<Sender<usize>>) -> SendUserData {
+    unsafe { SendUserData::new(sender as *const _ as *mut libc::c_void) }
+}
+
+#[test]
+fn completion_callbacks() {
+    let _serial = serial();
+    let (sender, responses) = callback_channel();
+    spawn_ffi_task_with_callback(|| prove(7), done, user_data(&sender));
+    spawn_ffi_task_with_callback(
+        || -> *mut ProveResponse { panic!("out of memory") },
+        done,
+        user_data(&sender),
+    );
+
+    let mut received: Vec<_> = (0..2).map(|_| next_response(&responses)).collect();
+    received.sort_by_key(|(_, proofs, _)| *proofs);
+    assert_eq!(
+        received[0],
+        (
+            FCPResponseStatus::FCPUnclassifiedError,
+            0,
+            "Rust panic: out of memory".to_string()
+        )
+    );
+    assert_eq!(
+        received[1],
+        (FCPResponseStatus::FCPNoError, 7, String::new())
+    );
+    // Exactly once
+    assert!(responses
+        .recv_timeout(std::time::Duration::from_millis(50))
+        .is_err());
+}
+
+#[test]
+fn shutdown_calls_back_cancelled_tasks() {
+    let _serial = serial();
+    lifecycle::shutdown();
+    assert!(set_task_threads(1));
+
+    lifecycle::init();
+    let (sender, responses) = callback_channel();
+    let (release, blocked) = mpsc::channel::<()>();
+    let (started, running_started) = mpsc::channel::<()>();
+    spawn_ffi_task_with_callback(
+        move || {
+            started.send(()).unwrap();
+            blocked.recv().unwrap();
+            prove(1)
+        },
+        done,
+        user_data(&sender),
+    );
+    spawn_ffi_task_with_callback(|| prove(2), done, user_data(&sender));
+    running_started.recv().unwrap();
+
+    let shutdown = thread::spawn(lifecycle::shutdown);
+    while lifecycle::is_initialized() {
+        thread::yield_now();
+    }
+    release.send(()).unwrap();
+    shutdown.join().unwrap();
+
+    assert_eq!(next_response(&responses).1, 1);
+    assert_eq!(
+        next_response(&responses),
+        (
+            FCPResponseStatus::FCPUnclassifiedError,
+            0,
+            "the task was cancelled by shutdown".to_string()
+        )
+    );
+    assert!(set_task_threads(0));
+}
+
+#[test]
+fn timed_out_calls_cancel_the_task() {
+    let _serial = serial();
+    let response: *mut ProveResponse =
+        catch_panic_response_with_timeout(Duration::from_secs(60), |_| prove(4));
+    unsafe {
+        assert_eq!((*response).proofs, 4);
+        free_raw_ptr(response);
+    }
+
+    let (cancelled, saw_cancel) = mpsc::channel();
+    let response: *mut ProveResponse =
+        catch_panic_response_with_timeout(Duration::from_millis(10), move |token| {
+            while !token.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            cancelled.send(()).unwrap();
+            prove(5)
+        });
+    unsafe {
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+        let message = CStr::from_ptr((*response).error_msg).to_str().unwrap();
+        assert_eq!(message, "the call timed out after 10ms");
+        free_raw_ptr(response);
+    }
+    saw_cancel.recv().unwrap();
+}
@@ -0,0 +1,80 @@
+use std::ffi::CStr;
+use std::fs;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Mutex;
+
+use ffi_toolkit::lifecycle;
+use ffi_toolkit::{
+    fil_create_temp_dir, fil_destroy_temp_dir, fil_destroy_temp_dir_response, FCPResponseStatus,
+    TempDir,
+};
+
+// `shutdown()` removes the directories of all tests
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// creates a directory with a file in it, returns the handle and the path
+unsafe fn create(prefix: &[u8]) -> (*mut TempDir, PathBuf) {
+    let response = fil_create_temp_dir(prefix.as_ptr() as *const libc::c_char);
+    assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+    let path = PathBuf::from(CStr::from_ptr((*response).path).to_str().unwrap());
+    let handle = (*response).handle;
+    fil_destroy_temp_dir_response(response);
+    fs::write(path.join("cache"), b"partial").unwrap();
+    (handle, path)
+}
+
+#[test]
+fn destroy_removes_directory() {
+    let _serial = SERIAL.lock().unwrap();
+    let (handle, path) = unsafe { create(b"fil-test\0") };
+    assert!(path.is_dir());
+    assert!(path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("fil-test-"));
+    unsafe { fil_destroy_temp_dir(handle) };
+    assert!(!path.exists());
+}
+
+#[test]
+fn unique_directories() {
+    let _serial = SERIAL.lock().unwrap();
+    let first = TempDir::create("fil-unique").unwrap();
+    let second = TempDir::create("fil-unique").unwrap();
+    assert_ne!(first.path(), second.path());
+}
+
+#[test]
+fn shutdown_removes_remaining_directories() {
+    let _serial = SERIAL.lock().unwrap();
+    let (handle, path) = unsafe { create(b"fil-shutdown\0") };
+    let (null_prefix_handle, null_prefix_path) = unsafe {
+        let response = fil_create_temp_dir(ptr::null());
+        let path = PathBuf::from(CStr::from_ptr((*response).path).to_str().unwrap());
+        let handle = (*response).handle;
+        fil_destroy_temp_dir_response(response);
+        (handle, path)
+    };
+    assert!(null_prefix_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("fil-"));
+
+    lifecycle::shutdown();
+    assert!(!path.exists());
+    assert!(!null_prefix_path.exists());
+
+    // Destroying the handles afterwards is fine
+    unsafe {
+        fil_destroy_temp_dir(handle);
+        fil_destroy_temp_dir(null_prefix_handle);
+    }
+
+    // The cleanup is registered again for the next shutdown
+    let (_handle, path) = unsafe { create(b"fil-shutdown\0") };
+    lifecycle::shutdown();
+    assert!(!path.exists());
+}
@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use ffi_toolkit::{
+    elapsed_since_ns, fil_instant_now, fil_monotonic_now_ns, monotonic_now_ns, FfiDuration,
+    FfiInstant, FfiTimestamp,
+};
+
+#[test]
+fn duration_constructors() {
+    assert_eq!(
+        FfiDuration::from_millis(1_500),
+        FfiDuration::new(1, 500_000_000).unwrap()
+    );
+    assert_eq!(
+        FfiDuration::new(1, 2_000_000_001).unwrap(),
+        FfiDuration { secs: 3, nanos: 1 }
+    );
+    assert_eq!(FfiDuration::new(u64::MAX, 1_000_000_000), None);
+    assert_eq!(
+        FfiDuration::new(u64::MAX, 999_999_999).unwrap().secs,
+        u64::MAX
+    );
+    assert_eq!(FfiDuration::from_secs(2).to_millis(), Some(2_000));
+    assert_eq!(FfiDuration::from_secs(u64::MAX).to_millis(), None);
+}
+
+#[test]
+fn duration_round_trip() {
+    let durations = [Duration::ZERO, Duration::new(5, 123), Duration::MAX];
+    for &duration in durations.iter() {
+        assert_eq!(FfiDuration::from(duration).to_duration(), Some(duration));
+    }
+    // Written by C without normalizing
+    let invalid = FfiDuration {
+        secs: 0,
+        nanos: 1_000_000_000,
+    };
+    assert_eq!(invalid.to_duration(), None);
+}
+
+#[test]
+fn timestamp_round_trip() {
+    let times = [
+        UNIX_EPOCH,
+        UNIX_EPOCH + Duration::new(1_600_000_000, 42),
+        UNIX_EPOCH - Duration::from_nanos(1),
+        UNIX_EPOCH - Duration::new(86_400, 5),
+    ];
+    for &time in times.iter() {
+        let timestamp = FfiTimestamp::from_system_time(time).unwrap();
+        assert!(timestamp.nanos < 1_000_000_000);
+        assert_eq!(timestamp.to_system_time(), Some(time));
+    }
+}
+
+#[test]
+fn timestamp_before_epoch() {
+    let timestamp = FfiTimestamp::from_system_time(UNIX_EPOCH - Duration::from_nanos(1)).unwrap();
+    assert_eq!(timestamp, FfiTimestamp::new(-1, 999_999_999).unwrap());
+    assert!(timestamp < FfiTimestamp::UNIX_EPOCH);
+}
+
+#[test]
+fn invalid_timestamps() {
+    assert_eq!(FfiTimestamp::new(0, 1_000_000_000), None);
+    let invalid = FfiTimestamp {
+        secs: 0,
+        nanos: u32::MAX,
+    };
+    assert_eq!(invalid.to_system_time(), None);
+    assert!(FfiTimestamp::now() > FfiTimestamp::from_unix_secs(1_600_000_000));
+}
+
+#[test]
+fn monotonic_clock() {
+    let start = fil_monotonic_now_ns();
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(monotonic_now_ns() > start);
+    assert!(elapsed_since_ns(start) >= Duration::from_millis(5));
+    assert_eq!(elapsed_since_ns(u64::MAX), Duration::ZERO);
+}
+
+#[test]
+#[cfg(unix)]
+fn monotonic_clock_matches_host() {
+    let mut host = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut host) };
+    let host_ns = host.tv_sec as u64 * 1_000_000_000 + host.tv_nsec as u64;
+    let elapsed = elapsed_since_ns(host_ns);
+    assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+}
+
+#[test]
+fn duration_units_and_arithmetic() {
+    assert_eq!(
+        FfiDuration::from_micros(1_500_001),
+        FfiDuration {
+            secs: 1,
+            nanos: 500_001_000
+        }
+    );
+    assert_eq!(
+        FfiDuration::from_nanos(1_000_000_002).to_nanos(),
+        Some(1_000_000_002)
+    );
+    assert_eq!(FfiDuration::from_secs(u64::MAX).to_nanos(), None);
+
+    let second = FfiDuration::from_secs(1);
+    assert_eq!(
+        second.saturating_add(FfiDuration::from_millis(500)),
+        FfiDuration::from_millis(1_500)
+    );
+    assert_eq!(
+        second.saturating_sub(FfiDuration::from_secs(2)),
+        FfiDuration::ZERO
+    );
+    assert_eq!(second.saturating_mul(3), FfiDuration::from_secs(3));
+    let max = FfiDuration::from(Duration::MAX);
+    assert_eq!(max.saturating_add(second), max);
+    assert_eq!(
+        FfiDuration {
+            secs: 1,
+            nanos: 1_500_000_000
+        }
+        .to_duration_saturating(),
+        Duration::from_millis(2_500)
+    );
+}
+
+#[test]
+fn instants() {
+    let start = fil_instant_now();
+    let later = start.saturating_add(FfiDuration::from_millis(5));
+    assert_eq!(
+        later.saturating_duration_since(start),
+        FfiDuration::from_millis(5)
+    );
+    assert_eq!(start.saturating_duration_since(later), FfiDuration::ZERO);
+    assert_eq!(
+        start
+            .saturating_sub(FfiDuration::from(Duration::MAX))
+            .monotonic_ns,
+        0
+    );
+    assert!(later.elapsed() <= start.elapsed());
+
+    let instant = Instant::now();
+    let converted = FfiInstant::from_instant(instant);
+    let back = converted.to_instant().unwrap();
+    let skew = if back > instant {
+        back - instant
+    } else {
+        instant - back
+    };
+    assert!(skew < Duration::from_millis(100));
+}
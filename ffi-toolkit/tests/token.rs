@@ -0,0 +1,21 @@
+use std::collections::HashSet;
+
+use ffi_toolkit::{random_token_u128, random_token_u64};
+
+#[test]
+fn tokens_are_unique_and_non_zero() {
+    let tokens: HashSet<u64> = (0..1000).map(|_| random_token_u64()).collect();
+    assert_eq!(tokens.len(), 1000);
+    assert!(!tokens.contains(&0));
+
+    let wide: HashSet<u128> = (0..1000).map(|_| random_token_u128().to_u128()).collect();
+    assert_eq!(wide.len(), 1000);
+    assert!(!wide.contains(&0));
+}
+
+#[test]
+fn tokens_use_all_bits() {
+    let combined = (0..64).fold(0u64, |bits, _| bits | random_token_u64());
+    // All 64 bits not being set at least once within 64 tokens is astronomically unlikely
+    assert_eq!(combined, u64::MAX);
+}
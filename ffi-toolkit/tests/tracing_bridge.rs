@@ -0,0 +1,139 @@
+#![cfg(feature = "tracing")]
+
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use ffi_toolkit::{FfiLogLevel, FfiTracingCallbacks, FfiTracingLayer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::Registry;
+
+type Calls = Mutex<Vec<String>>;
+
+fn calls<'a>(user_data: *mut libc::c_void) -> &'a Calls {
+    unsafe { &*(user_data as *const Calls) }
+}
+
+fn string(ptr: *const libc::c_char) -> String {
+    unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+}
+
+extern "C" fn new_span(
+    span_id: u64,
+    parent_id: u64,
+    name: *const libc::c_char,
+    fields: *const libc::c_char,
+    user_data: *mut libc::c_void,
+) {
+    calls(user_data).lock().unwrap().push(format!(
+        "new {} parent {} {} [{}]",
+        span_id,
+        parent_id,
+        string(name),
+        string(fields)
+    ));
+}
+
+extern "C" fn enter(span_id: u64, user_data: *mut libc::c_void) {
+    calls(user_data)
+        .lock()
+        .unwrap()
+        .push(format!("enter {}", span_id));
+}
+
+extern "C" fn exit(span_id: u64, user_data: *mut libc::c_void) {
+    calls(user_data)
+        .lock()
+        .unwrap()
+        .push(format!("exit {}", span_id));
+}
+
+extern "C" fn close(span_id: u64, user_data: *mut libc::c_void) {
+    calls(user_data)
+        .lock()
+        .unwrap()
+        .push(format!("close {}", span_id));
+}
+
+extern "C" fn event(
+    span_id: u64,
+    level: FfiLogLevel,
+    target: *const libc::c_char,
+    message: *const libc::c_char,
+    fields: *const libc::c_char,
+    user_data: *mut libc::c_void,
+) {
+    calls(user_data).lock().unwrap().push(format!(
+        "event in {} {:?} {}: {} [{}]",
+        span_id,
+        level,
+        string(target),
+        string(message),
+        string(fields)
+    ));
+}
+
+fn callbacks(calls: &Calls) -> FfiTracingCallbacks {
+    FfiTracingCallbacks {
+        new_span: Some(new_span),
+        enter: Some(enter),
+        exit: Some(exit),
+        close: Some(close),
+        event: Some(event),
+        user_data: calls as *const Calls as *mut libc::c_void,
+    }
+}
+
+#[test]
+fn spans_and_events_reach_the_host() {
+    let calls = Mutex::new(Vec::new());
+    let subscriber = Registry::default().with(FfiTracingLayer::new(callbacks(&calls)));
+    let ids = tracing::subscriber::with_default(subscriber, || {
+        let seal = tracing::info_span!("seal", sector_id = 7u64);
+        let seal_id = seal.id().unwrap().into_u64();
+        let _seal = seal.enter();
+        let replicate = tracing::debug_span!("replicate", layers = 11);
+        let replicate_id = replicate.id().unwrap().into_u64();
+        replicate.in_scope(|| {
+            tracing::warn!(target: "proofs", layer = 3, "slow layer");
+        });
+        drop(replicate);
+        (seal_id, replicate_id)
+    });
+    let (seal, replicate) = ids;
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            format!("new {} parent 0 seal [sector_id=7]", seal),
+            format!("enter {}", seal),
+            format!("new {} parent {} replicate [layers=11]", replicate, seal),
+            format!("enter {}", replicate),
+            format!("event in {} Warn proofs: slow layer [layer=3]", replicate),
+            format!("exit {}", replicate),
+            format!("close {}", replicate),
+            format!("exit {}", seal),
+            format!("close {}", seal),
+        ]
+    );
+}
+
+#[test]
+fn callbacks_are_optional() {
+    let calls = Mutex::new(Vec::new());
+    let callbacks = FfiTracingCallbacks {
+        new_span: None,
+        enter: None,
+        exit: None,
+        close: None,
+        ..callbacks(&calls)
+    };
+    let subscriber = Registry::default().with(FfiTracingLayer::new(callbacks));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("seal").in_scope(|| tracing::error!("failed"));
+        tracing::info!("outside");
+    });
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].ends_with("Error tracing_bridge: failed []"));
+    assert_eq!(calls[1], "event in 0 Info tracing_bridge: outside []");
+}
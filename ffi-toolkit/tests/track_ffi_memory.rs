@@ -0,0 +1,58 @@
+#![cfg(feature = "testing")]
+
+use std::ptr;
+
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::testing::track_ffi_memory_report;
+use ffi_toolkit::{free_c_str, free_raw_ptr, raw_ptr, rust_str_to_c_str, track_ffi_memory};
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct MessageResponse {
+    pub message: *const libc::c_char,
+}
+
+#[test]
+fn clean_body_passes() {
+    let length = track_ffi_memory! {
+        let response = raw_ptr(MessageResponse {
+            message: rust_str_to_c_str("hello"),
+        });
+        unsafe { free_raw_ptr(response) };
+        5
+    };
+    assert_eq!(length, 5);
+}
+
+#[test]
+fn reports_leaks_with_type_names() {
+    let (response, report) = track_ffi_memory_report(|| {
+        raw_ptr(MessageResponse {
+            message: rust_str_to_c_str("leaked"),
+        })
+    });
+    assert_eq!(report.leaks.len(), 2);
+    assert_eq!(report.leaks["c_char"], 1);
+    assert_eq!(report.leaks["track_ffi_memory::MessageResponse"], 1);
+    unsafe { free_raw_ptr(response) };
+}
+
+#[test]
+fn reports_double_frees_instead_of_crashing() {
+    let (_, report) = track_ffi_memory_report(|| unsafe {
+        let message = rust_str_to_c_str("freed twice");
+        free_c_str(message);
+        free_c_str(message);
+    });
+    assert!(report.leaks.is_empty());
+    assert_eq!(report.double_frees, vec!["c_char"]);
+}
+
+#[test]
+#[should_panic(expected = "leaked 1 allocation(s) of `c_char`")]
+fn macro_fails_on_leak() {
+    track_ffi_memory! {
+        let message = rust_str_to_c_str("leaked");
+        assert_ne!(message, ptr::null_mut());
+    }
+}
@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::FfiDebugId;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct SealResponse {
+    pub error_msg: *const libc::c_char,
+    pub debug_id: FfiDebugId,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[derive(DropStructMacro)]
+pub enum Response {
+    Ok,
+    Err,
+}
+
+fn main() {}
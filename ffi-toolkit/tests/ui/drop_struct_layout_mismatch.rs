@@ -0,0 +1,14 @@
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::free_c_str;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+#[ffi_drop(layout(size = 16, align = 8))]
+pub struct SealResponse {
+    pub error_msg: *const libc::c_char,
+    // Was moved in front of `error_msg`
+    #[ffi_drop(offset = 0)]
+    pub proof_len: libc::size_t,
+}
+
+fn main() {}
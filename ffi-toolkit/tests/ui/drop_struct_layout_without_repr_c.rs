@@ -0,0 +1,9 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[derive(DropStructMacro)]
+#[ffi_drop(layout(size = 8, align = 8))]
+pub struct SealResponse {
+    pub error_msg: *const libc::c_char,
+}
+
+fn main() {}
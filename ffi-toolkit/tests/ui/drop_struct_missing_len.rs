@@ -0,0 +1,10 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    pub proof_ptr: *const u8,
+    pub proof_length: libc::size_t,
+}
+
+fn main() {}
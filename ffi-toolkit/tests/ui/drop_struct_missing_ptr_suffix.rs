@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    pub error_msg: *const libc::c_char,
+    pub proof: *const u8,
+    pub proof_len: libc::size_t,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+pub struct SectorResult {
+    pub sector_id: u64,
+}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    #[ffi_drop(nested)]
+    pub result: SectorResult,
+}
+
+fn main() {}
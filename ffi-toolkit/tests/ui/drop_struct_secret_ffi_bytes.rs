@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::DropStructMacro;
+use ffi_toolkit::FfiBytes;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    #[ffi_drop(secret)]
+    pub key: FfiBytes,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    pub proofs_ptr: *const u8,
+    pub proofs_len: libc::size_t,
+    pub proofs_cap: libc::size_t,
+}
+
+fn main() {}
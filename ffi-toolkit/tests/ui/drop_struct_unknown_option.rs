@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    #[ffi_drop(private)]
+    pub key_ptr: *const u8,
+    pub key_len: libc::size_t,
+}
+
+fn main() {}
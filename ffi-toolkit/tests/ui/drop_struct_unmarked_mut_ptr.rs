@@ -0,0 +1,10 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    pub error_msg: *const libc::c_char,
+    pub context: *mut libc::c_void,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    pub comm_r: *const [u8; 32],
+}
+
+fn main() {}
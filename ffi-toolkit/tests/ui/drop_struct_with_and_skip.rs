@@ -0,0 +1,12 @@
+use drop_struct_macro_derive::DropStructMacro;
+
+fn close_fd(_fd: libc::c_int) {}
+
+#[repr(C)]
+#[derive(DropStructMacro)]
+pub struct Response {
+    #[ffi_drop(skip, with = "close_fd")]
+    pub sector_fd: libc::c_int,
+}
+
+fn main() {}
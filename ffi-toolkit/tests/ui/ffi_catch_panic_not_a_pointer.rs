@@ -0,0 +1,8 @@
+use drop_struct_macro_derive::ffi_catch_panic;
+
+#[ffi_catch_panic]
+pub extern "C" fn fil_sector_size() -> u64 {
+    2048
+}
+
+fn main() {}
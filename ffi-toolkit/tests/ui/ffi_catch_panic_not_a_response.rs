@@ -0,0 +1,16 @@
+use drop_struct_macro_derive::ffi_catch_panic;
+use ffi_toolkit::{code_and_message_impl, CodeAndMessage, FCPResponseStatus};
+
+pub struct Response {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+}
+
+code_and_message_impl!(Response);
+
+#[ffi_catch_panic]
+pub extern "C" fn fil_sector_size() -> *mut Response {
+    std::ptr::null_mut()
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use drop_struct_macro_derive::FFIErrorCode;
+
+#[derive(Debug, FFIErrorCode)]
+struct SealError {
+    sector_size: u64,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use drop_struct_macro_derive::FFIErrorCode;
+
+#[derive(Debug, FFIErrorCode)]
+enum SealError {
+    #[ffi_error(code = "FCPCalerError")]
+    InvalidSectorSize,
+}
+
+impl std::fmt::Display for SealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid sector size")
+    }
+}
+
+fn main() {}
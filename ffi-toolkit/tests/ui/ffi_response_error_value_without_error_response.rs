@@ -0,0 +1,13 @@
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::FCPResponseStatus;
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct VerifyResponse {
+    pub status_code: FCPResponseStatus,
+    pub error_msg: *const libc::c_char,
+    #[ffi_drop(error_value = "true")]
+    pub is_valid: bool,
+}
+
+fn main() {}
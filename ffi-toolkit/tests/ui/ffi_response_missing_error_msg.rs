@@ -0,0 +1,11 @@
+use drop_struct_macro_derive::FFIResponse;
+use ffi_toolkit::FCPResponseStatus;
+
+#[repr(C)]
+#[derive(FFIResponse)]
+pub struct Response {
+    pub status_code: FCPResponseStatus,
+    pub sector_id: u64,
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use std::mem;
+
+use ffi_toolkit::{ffi_vtable, FfiVTableHeader};
+
+unsafe extern "C" fn double(value: u64) -> u64 {
+    value * 2
+}
+
+extern "C" fn is_even(value: u64) -> bool {
+    value.is_multiple_of(2)
+}
+
+ffi_vtable! {
+    /// The functions exported by this test "plugin"
+    pub struct TestVTable: version 3, export test_plugin_vtable {
+        double: unsafe extern "C" fn(u64) -> u64 = double,
+        is_even: extern "C" fn(u64) -> bool = is_even,
+    }
+}
+
+#[test]
+fn vtable_exposes_functions() {
+    let vtable = unsafe { &*test_plugin_vtable() };
+    assert_eq!(vtable.header.version, 3);
+    assert_eq!(vtable.header.size, mem::size_of::<TestVTable>());
+    assert_eq!(unsafe { (vtable.double)(21) }, 42);
+    assert!((vtable.is_even)(42));
+}
+
+#[test]
+fn header_is_readable_without_concrete_type() {
+    let header = unsafe { &*(test_plugin_vtable() as *const FfiVTableHeader) };
+    assert_eq!(header.version, 3);
+}
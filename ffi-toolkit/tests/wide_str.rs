@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use ffi_toolkit::{
+    free_w_str, rust_string_to_wstr, try_wstr_to_rust_string, wstr_to_pathbuf, wstr_to_rust_string,
+};
+
+#[test]
+fn round_trip() {
+    let wide = rust_string_to_wstr("C:\\proofs\\sector-1 ✓ 𝄞");
+    unsafe {
+        assert_eq!(wstr_to_rust_string(wide), "C:\\proofs\\sector-1 ✓ 𝄞");
+        assert_eq!(
+            try_wstr_to_rust_string(wide).unwrap(),
+            "C:\\proofs\\sector-1 ✓ 𝄞"
+        );
+        assert_eq!(
+            wstr_to_pathbuf(wide),
+            PathBuf::from("C:\\proofs\\sector-1 ✓ 𝄞")
+        );
+        free_w_str(wide);
+    }
+}
+
+#[test]
+fn null_and_empty() {
+    unsafe {
+        assert_eq!(wstr_to_rust_string(std::ptr::null()), "");
+        assert_eq!(wstr_to_pathbuf(std::ptr::null()), PathBuf::new());
+        free_w_str(std::ptr::null_mut());
+    }
+    let empty = rust_string_to_wstr("");
+    unsafe {
+        assert_eq!(*empty, 0);
+        assert_eq!(wstr_to_rust_string(empty), "");
+        free_w_str(empty);
+    }
+}
+
+#[test]
+fn unpaired_surrogates() {
+    let wide = [u16::from(b'k'), 0xd800, u16::from(b'y'), 0];
+    unsafe {
+        assert_eq!(wstr_to_rust_string(wide.as_ptr()), "k\u{fffd}y");
+        assert!(try_wstr_to_rust_string(wide.as_ptr()).is_err());
+    }
+}
+
+#[test]
+#[should_panic(expected = "nul character")]
+fn interior_nul_panics() {
+    rust_string_to_wstr("key\0value");
+}
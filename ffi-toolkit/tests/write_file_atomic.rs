@@ -0,0 +1,59 @@
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::ptr;
+
+use ffi_toolkit::{
+    fil_destroy_write_file_response, fil_write_file_atomic, write_file_atomic, FCPResponseStatus,
+    FfiIoErrorKind, TempDir,
+};
+
+#[test]
+fn replaces_contents() {
+    let dir = TempDir::create("fil-atomic").unwrap();
+    let path = dir.path().join("state.json");
+    write_file_atomic(&path, b"{\"epoch\": 1}").unwrap();
+    write_file_atomic(&path, b"{\"epoch\": 2}").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"{\"epoch\": 2}");
+    // No temporary files are left behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn exported_write() {
+    let dir = TempDir::create("fil-atomic").unwrap();
+    let path = dir.path().join("config");
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    unsafe {
+        let response = fil_write_file_atomic(c_path.as_ptr(), b"abc".as_ptr(), 3);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        assert_eq!((*response).error_kind, FfiIoErrorKind::NoError);
+        fil_destroy_write_file_response(response);
+
+        // An empty file
+        let response = fil_write_file_atomic(c_path.as_ptr(), ptr::null(), 0);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPNoError);
+        fil_destroy_write_file_response(response);
+    }
+    assert!(fs::read(&path).unwrap().is_empty());
+}
+
+#[test]
+fn typed_errors() {
+    let dir = TempDir::create("fil-atomic").unwrap();
+    let missing = CString::new(dir.path().join("missing/config").to_str().unwrap()).unwrap();
+    unsafe {
+        let response = fil_write_file_atomic(missing.as_ptr(), b"abc".as_ptr(), 3);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPReceiverError);
+        assert_eq!((*response).error_kind, FfiIoErrorKind::NotFound);
+        assert!(CStr::from_ptr((*response).error_msg)
+            .to_str()
+            .unwrap()
+            .starts_with("failed to write "));
+        fil_destroy_write_file_response(response);
+
+        let response = fil_write_file_atomic(ptr::null(), b"abc".as_ptr(), 3);
+        assert_eq!((*response).status_code, FCPResponseStatus::FCPCallerError);
+        fil_destroy_write_file_response(response);
+    }
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}